@@ -3,6 +3,8 @@ set -euo pipefail
 
 BASE_URL="${INLINE_RELEASE_BASE_URL:-https://public-assets.inline.chat/cli}"
 MANIFEST_URL="${INLINE_RELEASE_MANIFEST_URL:-${BASE_URL%/}/manifest.json}"
+# Minisign public key; when set, the manifest and archive must be signed with it.
+PUBLIC_KEY="${INLINE_RELEASE_PUBLIC_KEY:-}"
 
 RED='\033[0;31m'
 GREEN='\033[0;32m'
@@ -46,6 +48,9 @@ check_prereqs() {
   if ! command_exists curl && ! command_exists wget; then
     error "need 'curl' or 'wget' (command not found)"
   fi
+  if [ -n "$PUBLIC_KEY" ] && ! command_exists minisign; then
+    error "need 'minisign' to check release signatures (INLINE_RELEASE_PUBLIC_KEY is set)"
+  fi
 }
 
 downloader() {
@@ -70,6 +75,18 @@ download_file() {
   mv "$temp_file" "$output_file"
 }
 
+verify_signature() {
+  local file="$1"
+  local url="$2"
+  if [ -z "$PUBLIC_KEY" ]; then
+    return 0
+  fi
+  if ! downloader "$url.minisig" "$file.minisig"; then
+    error "No signature published at $url.minisig"
+  fi
+  minisign -Vq -m "$file" -x "$file.minisig" -P "$PUBLIC_KEY" || error "Signature check failed for $url"
+}
+
 can_write_dir() {
   local dir="$1"
   if [ -d "$dir" ]; then
@@ -171,6 +188,7 @@ TMPDIR_CLEANUP="$(mktemp -d)"
 log "Fetching manifest..."
 manifest_file="$TMPDIR_CLEANUP/manifest.json"
 download_file "$MANIFEST_URL" "$manifest_file"
+verify_signature "$manifest_file" "$MANIFEST_URL"
 manifest="$(cat "$manifest_file")"
 
 target="$(detect_target)"
@@ -199,6 +217,7 @@ download_file "$url" "$archive"
 
 log "Verifying checksum..."
 echo "$sha256  $archive" | $SHA256_CMD -c -
+verify_signature "$archive" "$url"
 
 tar -xzf "$archive" -C "$TMPDIR_CLEANUP"
 
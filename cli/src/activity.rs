@@ -0,0 +1,132 @@
+//! `chats list --activity`: a 7-day ASCII sparkline of message volume per
+//! chat, so the threads that are alive stand out at a glance.
+//!
+//! There is no count RPC, so each chat's last week of history is paged in
+//! (usually one `getChatHistory` call). Chats whose last message is known to
+//! be older than the window are skipped without a call.
+
+use std::collections::HashMap;
+
+use futures_util::stream::{self, StreamExt};
+use inline_protocol::proto;
+use inline_sdk::RealtimeSession;
+use serde::Serialize;
+
+use crate::history::fetch_history_window;
+use crate::peer::input_chat_peer;
+
+pub(crate) const ACTIVITY_DAYS: usize = 7;
+const DAY_SECS: i64 = 24 * 60 * 60;
+/// Chats whose history is fetched at once.
+const ACTIVITY_PARALLEL: usize = 4;
+/// Quietest to busiest; `_` is a day without messages.
+const LEVELS: &[u8] = b"_.:-=+*#";
+
+/// Messages per day for one chat, oldest day first.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ChatActivity {
+    pub(crate) chat_id: i64,
+    pub(crate) days: [usize; ACTIVITY_DAYS],
+    pub(crate) total: usize,
+}
+
+/// Buckets message dates into the 24-hour days ending at `now`.
+pub(crate) fn daily_counts(
+    dates: impl IntoIterator<Item = i64>,
+    now: i64,
+) -> [usize; ACTIVITY_DAYS] {
+    let mut days = [0; ACTIVITY_DAYS];
+    for date in dates {
+        let age = now - date;
+        if (0..ACTIVITY_DAYS as i64 * DAY_SECS).contains(&age) {
+            days[ACTIVITY_DAYS - 1 - (age / DAY_SECS) as usize] += 1;
+        }
+    }
+    days
+}
+
+/// One char per day, scaled against `max` (the busiest day across the
+/// listed chats) so rows compare with each other. Any message shows above `_`.
+pub(crate) fn sparkline(days: &[usize], max: usize) -> String {
+    let top = LEVELS.len() - 1;
+    days.iter()
+        .map(|&count| {
+            let level = if count == 0 || max == 0 {
+                0
+            } else {
+                (count * top).div_ceil(max).clamp(1, top)
+            };
+            LEVELS[level] as char
+        })
+        .collect()
+}
+
+/// Counts the last week of messages in each of `chats`. Chats whose history
+/// can't be fetched are left out rather than failing the list.
+pub(crate) async fn fetch_chat_activity(
+    realtime: &RealtimeSession,
+    chats: &[&proto::Chat],
+    last_message_dates: &HashMap<i64, i64>,
+    now: i64,
+) -> HashMap<i64, ChatActivity> {
+    let since = now - ACTIVITY_DAYS as i64 * DAY_SECS;
+    stream::iter(chats)
+        .map(|chat| async move {
+            let quiet = last_message_dates
+                .get(&chat.id)
+                .is_some_and(|date| *date < since);
+            let days = if quiet {
+                [0; ACTIVITY_DAYS]
+            } else {
+                match fetch_history_window(
+                    realtime,
+                    &input_chat_peer(chat.id),
+                    None,
+                    None,
+                    Some(since),
+                    None,
+                )
+                .await
+                {
+                    Ok(messages) => daily_counts(messages.iter().map(|message| message.date), now),
+                    Err(err) => {
+                        log::debug!("could not fetch activity for chat {}: {err}", chat.id);
+                        return None;
+                    }
+                }
+            };
+            Some(ChatActivity {
+                chat_id: chat.id,
+                days,
+                total: days.iter().sum(),
+            })
+        })
+        .buffer_unordered(ACTIVITY_PARALLEL)
+        .filter_map(|activity| async move { activity })
+        .map(|activity| (activity.chat_id, activity))
+        .collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_are_bucketed_and_scaled_against_the_busiest() {
+        let now = 10 * DAY_SECS;
+        let dates = [
+            now,
+            now - 60,
+            now - DAY_SECS - 1,
+            now - 6 * DAY_SECS,
+            now - 8 * DAY_SECS,
+        ];
+        let days = daily_counts(dates, now);
+        assert_eq!(days, [1, 0, 0, 0, 0, 1, 2]);
+        assert_eq!(sparkline(&days, 2), "=____=#");
+        assert_eq!(sparkline(&days, 40), ".____..");
+        assert_eq!(sparkline(&[0; ACTIVITY_DAYS], 0), "_______");
+    }
+}
@@ -0,0 +1,219 @@
+//! Keyword alerts for `inline alerts`.
+//!
+//! Alerts live in the local state file. `inline alerts run` watches new
+//! messages in every chat and fires each alert whose keyword a message
+//! contains: a desktop notification, an `--exec` hook, or both. An alert can
+//! be muted for a while, and a cooldown keeps a burst of matches from firing
+//! it more than once.
+
+use std::collections::HashMap;
+
+use inline_protocol::proto;
+use serde::{Deserialize, Serialize};
+
+use crate::output::{format_relative_date, style_heading};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alert {
+    /// Local number, stable until the alert is removed.
+    pub id: u64,
+    /// Matched case-insensitively anywhere in the message text.
+    pub keyword: String,
+    /// Only messages in this chat; all chats when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chat_id: Option<i64>,
+    pub notify: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exec: Option<String>,
+    /// Seconds to stay quiet after firing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cooldown_secs: Option<u64>,
+    /// Unix seconds until which `inline alerts mute` silenced the alert.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub muted_until: Option<i64>,
+    pub created_at: i64,
+}
+
+impl Alert {
+    /// Whether `message` should fire the alert. Your own messages never do, so
+    /// a hook that posts back to the chat doesn't trigger itself.
+    pub(crate) fn matches(&self, message: &proto::Message) -> bool {
+        if message.out {
+            return false;
+        }
+        if self
+            .chat_id
+            .is_some_and(|chat_id| chat_id != message.chat_id)
+        {
+            return false;
+        }
+        message
+            .message
+            .as_deref()
+            .unwrap_or_default()
+            .to_lowercase()
+            .contains(&self.keyword.to_lowercase())
+    }
+
+    pub(crate) fn is_muted(&self, now: i64) -> bool {
+        self.muted_until.is_some_and(|until| until > now)
+    }
+
+    fn actions(&self) -> String {
+        let mut actions = Vec::new();
+        if self.notify {
+            actions.push("notify");
+        }
+        if self.exec.is_some() {
+            actions.push("exec");
+        }
+        if actions.is_empty() {
+            "print".to_string()
+        } else {
+            actions.join(", ")
+        }
+    }
+}
+
+pub(crate) fn next_alert_id(alerts: &[Alert]) -> u64 {
+    alerts.iter().map(|alert| alert.id).max().unwrap_or(0) + 1
+}
+
+/// When each alert last fired in a running `inline alerts run`, for cooldowns.
+#[derive(Default)]
+pub(crate) struct Cooldowns {
+    fired_at: HashMap<u64, i64>,
+}
+
+impl Cooldowns {
+    /// Records a firing at `now` unless the alert is still cooling down from
+    /// the last one; returns whether it may fire.
+    pub(crate) fn try_fire(&mut self, alert: &Alert, now: i64) -> bool {
+        let cooling = match (alert.cooldown_secs, self.fired_at.get(&alert.id)) {
+            (Some(secs), Some(&last)) => now - last < secs as i64,
+            _ => false,
+        };
+        if !cooling {
+            self.fired_at.insert(alert.id, now);
+        }
+        !cooling
+    }
+}
+
+/// `90` → `90s`, `600` → `10m`: the largest unit that divides evenly.
+pub(crate) fn short_duration(secs: u64) -> String {
+    [(86_400, "d"), (3_600, "h"), (60, "m")]
+        .into_iter()
+        .find(|(unit, _)| secs >= *unit && secs.is_multiple_of(*unit))
+        .map(|(unit, suffix)| format!("{}{suffix}", secs / unit))
+        .unwrap_or_else(|| format!("{secs}s"))
+}
+
+pub(crate) fn print_alert_list(alerts: &[Alert], now: i64) {
+    if alerts.is_empty() {
+        println!("No alerts. Add one with `inline alerts add --keyword TEXT --notify`.");
+        return;
+    }
+    let keyword_width = alerts
+        .iter()
+        .map(|alert| alert.keyword.chars().count() + 2)
+        .max()
+        .unwrap_or_default()
+        .max(7);
+    println!(
+        "{}",
+        style_heading(&format!(
+            "{:>4}  {:<keyword_width$}  {:<10}  {:<12}  {:<8}  muted",
+            "#", "keyword", "chat", "actions", "cooldown"
+        ))
+    );
+    for alert in alerts {
+        let chat = alert
+            .chat_id
+            .map(|chat_id| chat_id.to_string())
+            .unwrap_or_else(|| "all".to_string());
+        let cooldown = alert
+            .cooldown_secs
+            .map(short_duration)
+            .unwrap_or_else(|| "-".to_string());
+        let muted = match alert.muted_until {
+            Some(until) if until > now => format!("until {}", format_relative_date(until, now)),
+            _ => "-".to_string(),
+        };
+        println!(
+            "{:>4}  {:<keyword_width$}  {:<10}  {:<12}  {:<8}  {}",
+            alert.id,
+            format!("\"{}\"", alert.keyword),
+            chat,
+            alert.actions(),
+            cooldown,
+            muted
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(chat_id: Option<i64>, cooldown_secs: Option<u64>) -> Alert {
+        Alert {
+            id: 1,
+            keyword: "Prod Down".to_string(),
+            chat_id,
+            notify: true,
+            exec: None,
+            cooldown_secs,
+            muted_until: None,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn alerts_match_keyword_and_chat_but_not_own_messages() {
+        let message = |chat_id: i64, text: &str, out: bool| proto::Message {
+            id: 1,
+            chat_id,
+            message: Some(text.to_string()),
+            out,
+            ..Default::default()
+        };
+
+        assert!(alert(None, None).matches(&message(7, "is PROD DOWN again?", false)));
+        assert!(alert(Some(7), None).matches(&message(7, "prod down", false)));
+        assert!(!alert(Some(7), None).matches(&message(8, "prod down", false)));
+        assert!(!alert(None, None).matches(&message(7, "prod is fine", false)));
+        assert!(!alert(None, None).matches(&message(7, "prod down", true)));
+    }
+
+    #[test]
+    fn cooldowns_and_mutes_hold_an_alert_back() {
+        let mut cooldowns = Cooldowns::default();
+        let cooling = alert(None, Some(600));
+        assert!(cooldowns.try_fire(&cooling, 1_000));
+        assert!(!cooldowns.try_fire(&cooling, 1_599));
+        assert!(cooldowns.try_fire(&cooling, 1_600));
+
+        let every_time = alert(None, None);
+        let mut cooldowns = Cooldowns::default();
+        assert!(cooldowns.try_fire(&every_time, 1_000));
+        assert!(cooldowns.try_fire(&every_time, 1_000));
+
+        let muted = Alert {
+            muted_until: Some(2_000),
+            ..alert(None, None)
+        };
+        assert!(muted.is_muted(1_999));
+        assert!(!muted.is_muted(2_000));
+        assert_eq!(next_alert_id(&[muted]), 2);
+    }
+
+    #[test]
+    fn durations_use_the_largest_even_unit() {
+        assert_eq!(short_duration(90), "90s");
+        assert_eq!(short_duration(600), "10m");
+        assert_eq!(short_duration(7_200), "2h");
+        assert_eq!(short_duration(86_400), "1d");
+    }
+}
@@ -0,0 +1,118 @@
+//! Response cache for read-style REST calls (`users search`, `api post getX`).
+//!
+//! The SDK decides what is cacheable and when an entry is fresh; entries live
+//! in the local state file so repeated invocations in a script reuse them.
+//! `--no-cache` leaves the cache off for one invocation.
+
+use std::time::Duration;
+
+use inline_sdk::api::{CachedResponse, ResponseCache};
+use serde::{Deserialize, Serialize};
+
+use crate::state::{LocalDb, LocalState};
+
+/// How long a response is reused without asking the server.
+pub(crate) const API_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Stale entries stay this long so ETag revalidation can still use them.
+const API_CACHE_MAX_AGE_SECS: i64 = 24 * 60 * 60;
+
+/// Entries kept in the state file, newest first.
+const API_CACHE_KEPT: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiCacheEntry {
+    /// Digest of the method, body, and token; never the token itself.
+    pub key: String,
+    #[serde(flatten)]
+    pub response: CachedResponse,
+}
+
+pub(crate) struct LocalApiCache {
+    local_db: LocalDb,
+}
+
+impl LocalApiCache {
+    pub(crate) fn new(local_db: LocalDb) -> Self {
+        Self { local_db }
+    }
+}
+
+impl ResponseCache for LocalApiCache {
+    fn get(&self, key: &str) -> Option<CachedResponse> {
+        let state = self.local_db.load().ok()?;
+        state
+            .api_cache
+            .into_iter()
+            .find(|entry| entry.key == key)
+            .map(|entry| entry.response)
+    }
+
+    fn put(&self, key: &str, response: CachedResponse) {
+        // A cache that can't be written only costs a refetch next time.
+        let Ok(mut state) = self.local_db.load() else {
+            return;
+        };
+        insert(&mut state, key, response);
+        if let Err(err) = self.local_db.save(&state) {
+            log::debug!("could not save API response cache: {err}");
+        }
+    }
+}
+
+/// Replaces the entry for `key` and drops entries too old to revalidate.
+fn insert(state: &mut LocalState, key: &str, response: CachedResponse) {
+    let oldest = response.stored_at - API_CACHE_MAX_AGE_SECS;
+    state
+        .api_cache
+        .retain(|entry| entry.key != key && entry.response.stored_at > oldest);
+    state.api_cache.push(ApiCacheEntry {
+        key: key.to_string(),
+        response,
+    });
+    state
+        .api_cache
+        .sort_by_key(|entry| std::cmp::Reverse(entry.response.stored_at));
+    state.api_cache.truncate(API_CACHE_KEPT);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(stored_at: i64) -> CachedResponse {
+        CachedResponse {
+            body: r#"{"ok":true,"result":{}}"#.to_string(),
+            etag: None,
+            last_modified: None,
+            stored_at,
+        }
+    }
+
+    #[test]
+    fn insert_replaces_the_key_and_prunes_old_and_extra_entries() {
+        let mut state = LocalState::default();
+        insert(&mut state, "old", response(0));
+        insert(&mut state, "a", response(100_000));
+        insert(&mut state, "a", response(100_010));
+        assert_eq!(
+            state
+                .api_cache
+                .iter()
+                .map(|entry| (entry.key.as_str(), entry.response.stored_at))
+                .collect::<Vec<_>>(),
+            [("a", 100_010)]
+        );
+
+        for index in 0..API_CACHE_KEPT + 5 {
+            insert(
+                &mut state,
+                &index.to_string(),
+                response(200_000 + index as i64),
+            );
+        }
+        assert_eq!(state.api_cache.len(), API_CACHE_KEPT);
+        assert_eq!(state.api_cache[0].key, (API_CACHE_KEPT + 4).to_string());
+    }
+}
@@ -0,0 +1,287 @@
+//! Incremental chat archives for `inline archive`.
+//!
+//! Each chat is kept as `<dir>/<chat_id>.jsonl`, one message per line in the
+//! same shape as `inline backup restore` writes. Runs append only messages
+//! whose id isn't in the file yet, so overlapping or repeated runs are safe.
+
+use std::collections::BTreeSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use inline_protocol::proto;
+use serde_json::Value;
+
+use crate::watch::shell_quote;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub(crate) enum ArchiveInterval {
+    Hour,
+    Day,
+    Week,
+}
+
+impl ArchiveInterval {
+    fn seconds(self) -> u64 {
+        match self {
+            Self::Hour => 60 * 60,
+            Self::Day => 24 * 60 * 60,
+            Self::Week => 7 * 24 * 60 * 60,
+        }
+    }
+
+    /// Daily and weekly runs happen at 03:00 local time.
+    fn cron_schedule(self) -> &'static str {
+        match self {
+            Self::Hour => "0 * * * *",
+            Self::Day => "0 3 * * *",
+            Self::Week => "0 3 * * 0",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub(crate) enum ScheduleFormat {
+    Launchd,
+    Cron,
+}
+
+impl ScheduleFormat {
+    pub(crate) fn native() -> Self {
+        if cfg!(target_os = "macos") {
+            Self::Launchd
+        } else {
+            Self::Cron
+        }
+    }
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Launchd => "launchd",
+            Self::Cron => "cron",
+        }
+    }
+}
+
+pub(crate) fn archive_path(dir: &Path, chat_id: i64) -> PathBuf {
+    dir.join(format!("{chat_id}.jsonl"))
+}
+
+pub(crate) fn log_path(dir: &Path) -> PathBuf {
+    dir.join("archive.log")
+}
+
+/// Message ids already in an archive file; empty when it doesn't exist yet.
+/// Lines that don't parse (a run killed mid-write) are skipped.
+pub(crate) fn archived_ids(path: &Path) -> io::Result<BTreeSet<i64>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(BTreeSet::new()),
+        Err(error) => return Err(error),
+    };
+    let mut ids = BTreeSet::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if let Some(id) = serde_json::from_str::<Value>(&line)
+            .ok()
+            .and_then(|message| message["id"].as_i64())
+        {
+            ids.insert(id);
+        }
+    }
+    Ok(ids)
+}
+
+/// Appends the messages not in `ids`, oldest first. Returns how many were written.
+pub(crate) fn append_new_messages(
+    path: &Path,
+    messages: &[proto::Message],
+    ids: &BTreeSet<i64>,
+) -> io::Result<usize> {
+    let mut fresh: Vec<_> = messages
+        .iter()
+        .filter(|message| !ids.contains(&message.id))
+        .collect();
+    if fresh.is_empty() {
+        return Ok(0);
+    }
+    fresh.sort_by_key(|message| message.id);
+    fresh.dedup_by_key(|message| message.id);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut lines = Vec::new();
+    if ends_mid_line(path)? {
+        lines.push(b'\n');
+    }
+    for message in &fresh {
+        serde_json::to_writer(&mut lines, message)?;
+        lines.push(b'\n');
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(&lines)?;
+    Ok(fresh.len())
+}
+
+/// True when a previous run was cut off before finishing its last line.
+fn ends_mid_line(path: &Path) -> io::Result<bool> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(error) => return Err(error),
+    };
+    if file.metadata()?.len() == 0 {
+        return Ok(false);
+    }
+    file.seek(SeekFrom::End(-1))?;
+    let mut last = [0u8; 1];
+    file.read_exact(&mut last)?;
+    Ok(last[0] != b'\n')
+}
+
+pub(crate) fn schedule_label(chat_id: i64) -> String {
+    format!("chat.inline.archive.{chat_id}")
+}
+
+pub(crate) fn render_schedule(
+    format: ScheduleFormat,
+    label: &str,
+    program: &[String],
+    every: ArchiveInterval,
+    log: &Path,
+) -> String {
+    match format {
+        ScheduleFormat::Launchd => launchd_plist(label, program, every, log),
+        ScheduleFormat::Cron => {
+            let command = program
+                .iter()
+                .map(|arg| shell_quote(arg))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let log = shell_quote(&log.display().to_string());
+            format!(
+                "{} {command} >> {log} 2>&1 # {label}\n",
+                every.cron_schedule()
+            )
+        }
+    }
+}
+
+fn launchd_plist(label: &str, program: &[String], every: ArchiveInterval, log: &Path) -> String {
+    let arguments: String = program
+        .iter()
+        .map(|arg| format!("    <string>{}</string>\n", xml_escape(arg)))
+        .collect();
+    let log = xml_escape(&log.display().to_string());
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>Label</key>
+  <string>{label}</string>
+  <key>ProgramArguments</key>
+  <array>
+{arguments}  </array>
+  <key>StartInterval</key>
+  <integer>{interval}</integer>
+  <key>RunAtLoad</key>
+  <true/>
+  <key>StandardOutPath</key>
+  <string>{log}</string>
+  <key>StandardErrorPath</key>
+  <string>{log}</string>
+</dict>
+</plist>
+"#,
+        label = xml_escape(label),
+        interval = every.seconds(),
+    )
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: i64) -> proto::Message {
+        proto::Message {
+            id,
+            message: Some(format!("message {id}")),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn appends_only_messages_missing_from_the_archive() {
+        let dir = std::env::temp_dir().join(format!("inline-archive-test-{}", std::process::id()));
+        let path = archive_path(&dir, 7);
+
+        let ids = archived_ids(&path).unwrap();
+        assert!(ids.is_empty());
+        let written = append_new_messages(&path, &[message(2), message(1)], &ids).unwrap();
+        assert_eq!(written, 2);
+
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"id\": 9, \"trunc")
+            .unwrap();
+        let ids = archived_ids(&path).unwrap();
+        assert_eq!(ids.into_iter().collect::<Vec<_>>(), [1, 2]);
+
+        let ids = archived_ids(&path).unwrap();
+        let written =
+            append_new_messages(&path, &[message(2), message(3), message(3)], &ids).unwrap();
+        let ids = archived_ids(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(written, 1);
+        assert_eq!(ids.into_iter().collect::<Vec<_>>(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn renders_launchd_and_cron_schedules() {
+        let program = vec![
+            "/usr/local/bin/inline".to_string(),
+            "archive".to_string(),
+            "run".to_string(),
+            "--dir".to_string(),
+            "/Users/sam/My Archives".to_string(),
+        ];
+        let log = Path::new("/Users/sam/My Archives/archive.log");
+
+        let plist = render_schedule(
+            ScheduleFormat::Launchd,
+            "chat.inline.archive.7",
+            &program,
+            ArchiveInterval::Day,
+            log,
+        );
+        assert!(plist.contains("<string>/Users/sam/My Archives</string>"));
+        assert!(plist.contains("<integer>86400</integer>"));
+
+        let cron = render_schedule(
+            ScheduleFormat::Cron,
+            "chat.inline.archive.7",
+            &program,
+            ArchiveInterval::Week,
+            log,
+        );
+        assert_eq!(
+            cron,
+            "0 3 * * 0 /usr/local/bin/inline archive run --dir '/Users/sam/My Archives' >> '/Users/sam/My Archives/archive.log' 2>&1 # chat.inline.archive.7\n"
+        );
+    }
+}
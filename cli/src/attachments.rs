@@ -2,10 +2,16 @@ use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::{fs, io};
 
+use crate::checksum::sha256_file;
 use crate::errors::CliError;
 use crate::output::format_bytes;
+use crate::photos::{PhotoOptions, photo_preview, prepare_photo};
+use crate::video::{probe_video, transcode_video, video_thumbnail};
 use inline_protocol::proto;
-use inline_sdk::api::{UploadFileInput, UploadFileResult, UploadFileType, UploadVideoMetadata};
+use inline_sdk::api::{
+    UploadFileInput, UploadFileResult, UploadFileType, UploadPhotoMetadata, UploadThumbnail,
+    UploadVideoMetadata,
+};
 
 pub(crate) const MAX_ATTACHMENT_BYTES: u64 = 200 * 1024 * 1024;
 
@@ -13,16 +19,22 @@ pub(crate) const MAX_ATTACHMENT_BYTES: u64 = 200 * 1024 * 1024;
 pub(crate) struct PreparedAttachment {
     upload_path: PathBuf,
     pub(crate) display_name: String,
-    file_name: String,
+    pub(crate) file_name: String,
     mime_type: Option<String>,
     file_type: UploadFileType,
     video_metadata: Option<UploadVideoMetadata>,
-    #[allow(dead_code)]
+    photo_metadata: Option<UploadPhotoMetadata>,
+    thumbnail: Option<UploadThumbnail>,
     pub(crate) size_bytes: u64,
     cleanup_path: Option<PathBuf>,
 }
 
 impl PreparedAttachment {
+    /// Digest of the bytes that will be uploaded (the zip for folders).
+    pub(crate) fn sha256(&self) -> io::Result<String> {
+        sha256_file(&self.upload_path)
+    }
+
     pub(crate) fn to_upload_input(&self) -> UploadFileInput {
         let mut input = UploadFileInput::new(
             self.upload_path.clone(),
@@ -35,8 +47,43 @@ impl PreparedAttachment {
         if let Some(metadata) = self.video_metadata {
             input = input.with_video_metadata(metadata);
         }
+        if let Some(metadata) = self.photo_metadata {
+            input = input.with_photo_metadata(metadata);
+        }
+        if let Some(thumbnail) = self.thumbnail.clone() {
+            input = input.with_thumbnail(thumbnail);
+        }
         input
     }
+
+    /// Adds photo dimensions and a thumbnail (a poster frame for videos) so
+    /// clients can show a preview right away. Skipped when the image tools or
+    /// ffmpeg aren't installed.
+    fn add_preview(&mut self, data_dir: &Path) {
+        match self.file_type {
+            UploadFileType::Photo => {
+                let Some(preview) = photo_preview(&self.upload_path, data_dir) else {
+                    return;
+                };
+                if let (Ok(width), Ok(height)) =
+                    (i32::try_from(preview.width), i32::try_from(preview.height))
+                    && width > 0
+                    && height > 0
+                {
+                    self.photo_metadata = Some(UploadPhotoMetadata::new(width, height));
+                }
+                self.thumbnail = preview.thumbnail.map(UploadThumbnail::jpeg);
+            }
+            UploadFileType::Video => {
+                if let Some(metadata) = self.video_metadata {
+                    self.thumbnail =
+                        video_thumbnail(&self.upload_path, metadata.duration, data_dir)
+                            .map(UploadThumbnail::jpeg);
+                }
+            }
+            _ => {}
+        }
+    }
 }
 
 impl Drop for PreparedAttachment {
@@ -47,15 +94,40 @@ impl Drop for PreparedAttachment {
     }
 }
 
+/// What goes into the zip when a folder is attached.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ZipOptions {
+    /// Glob patterns to leave out (`*.log`, `node_modules/`, `/dist`).
+    pub(crate) exclude: Vec<String>,
+    /// File name for the uploaded zip instead of `<folder>.zip`.
+    pub(crate) name: Option<String>,
+    /// Skip files ignored by `.gitignore` files inside the folder, and `.git/`.
+    pub(crate) respect_gitignore: bool,
+}
+
+/// How `--attach` paths are turned into uploads.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AttachmentOptions {
+    /// Upload photos and videos as documents, byte-for-byte.
+    pub(crate) force_file: bool,
+    pub(crate) zip: ZipOptions,
+    pub(crate) photos: PhotoOptions,
+    /// Re-encode videos the apps can't play, or that are over the size limit.
+    pub(crate) transcode: bool,
+}
+
 pub(crate) fn prepare_attachments(
     paths: &[PathBuf],
     data_dir: &Path,
-    force_file: bool,
+    options: &AttachmentOptions,
     quiet: bool,
 ) -> Result<Vec<PreparedAttachment>, Box<dyn std::error::Error>> {
     if paths.is_empty() {
         return Ok(Vec::new());
     }
+    if options.zip.name.is_some() && paths.iter().filter(|path| path.is_dir()).count() > 1 {
+        return Err(CliError::invalid_args("--zip-name only works with a single folder").into());
+    }
 
     let mut prepared = Vec::with_capacity(paths.len());
     for path in paths {
@@ -63,12 +135,28 @@ pub(crate) fn prepare_attachments(
             CliError::invalid_args(format!("Attachment not found: {}", path.display()))
         })?;
         if metadata.is_dir() {
-            prepared.push(prepare_directory_attachment(path, data_dir, quiet)?);
+            prepared.push(prepare_directory_attachment(
+                path,
+                data_dir,
+                &options.zip,
+                quiet,
+            )?);
+        } else if metadata.is_file() && options.force_file {
+            prepared.push(prepare_file_attachment(path, metadata.len(), true, quiet)?);
+        } else if metadata.is_file() && is_video_path(path) {
+            prepared.push(prepare_video_attachment(
+                path,
+                metadata.len(),
+                data_dir,
+                options.transcode,
+                quiet,
+            )?);
         } else if metadata.is_file() {
-            prepared.push(prepare_file_attachment(
+            prepared.push(prepare_photo_attachment(
                 path,
                 metadata.len(),
-                force_file,
+                data_dir,
+                &options.photos,
                 quiet,
             )?);
         } else {
@@ -80,9 +168,141 @@ pub(crate) fn prepare_attachments(
         }
     }
 
+    for attachment in &mut prepared {
+        attachment.add_preview(data_dir);
+    }
+    Ok(prepared)
+}
+
+/// Prepares a temporary captured image (clipboard or screenshot). The file is
+/// removed once the attachment is dropped.
+pub(crate) fn prepare_captured_attachment(
+    path: PathBuf,
+    display_name: &str,
+    force_file: bool,
+    quiet: bool,
+) -> Result<PreparedAttachment, Box<dyn std::error::Error>> {
+    let size = fs::metadata(&path)?.len();
+    let mut prepared = prepare_file_attachment(&path, size, force_file, quiet)?;
+    prepared.display_name = display_name.to_string();
+    prepared.file_name = display_name.to_string();
+    if let Some(data_dir) = path.parent() {
+        prepared.add_preview(data_dir);
+    }
+    prepared.cleanup_path = Some(path);
+    Ok(prepared)
+}
+
+/// Uploads a converted copy when the file is a photo that needs HEIC
+/// conversion or downscaling, and the file itself otherwise. Conversion
+/// failures fall back to the original with a warning.
+fn prepare_photo_attachment(
+    path: &Path,
+    size: u64,
+    data_dir: &Path,
+    photo_options: &PhotoOptions,
+    quiet: bool,
+) -> Result<PreparedAttachment, Box<dyn std::error::Error>> {
+    let converted = match prepare_photo(path, data_dir, photo_options) {
+        Ok(Some(converted)) => converted,
+        Ok(None) => return prepare_file_attachment(path, size, false, quiet),
+        Err(reason) => {
+            if !quiet {
+                log::warn!(
+                    "could not convert {} ({}). Uploading the original.",
+                    path.display(),
+                    reason
+                );
+            }
+            return prepare_file_attachment(path, size, false, quiet);
+        }
+    };
+    if !quiet {
+        eprintln!("{}: {}.", path.display(), converted.description);
+    }
+    prepare_converted_attachment(path, converted.path, converted.file_name, quiet)
+}
+
+/// Transcodes videos the apps can't play (or that are over the limit) when
+/// `transcode` is set, and suggests it otherwise.
+fn prepare_video_attachment(
+    path: &Path,
+    size: u64,
+    data_dir: &Path,
+    transcode: bool,
+    quiet: bool,
+) -> Result<PreparedAttachment, Box<dyn std::error::Error>> {
+    let probe = probe_video(path);
+    let reason = if size > MAX_ATTACHMENT_BYTES {
+        Some(format!("{} (limit 200MB)", format_bytes(size as i64)))
+    } else {
+        probe.as_ref().and_then(|probe| probe.unplayable_reason())
+    };
+    let Some(reason) = reason else {
+        return prepare_file_attachment(path, size, false, quiet);
+    };
+    match probe {
+        Some(probe) if transcode => {
+            if !quiet {
+                eprintln!(
+                    "{} is {}; transcoding to an H.264/AAC mp4.",
+                    path.display(),
+                    reason
+                );
+            }
+            let converted = transcode_video(path, &probe, data_dir, MAX_ATTACHMENT_BYTES, quiet)
+                .map_err(|reason| CliError::transcode_failed(path, reason))?;
+            let stem = path
+                .file_stem()
+                .and_then(|value| value.to_str())
+                .unwrap_or("video");
+            prepare_converted_attachment(path, converted, format!("{stem}.mp4"), quiet)
+        }
+        None if transcode => {
+            Err(CliError::transcode_failed(path, "ffprobe could not read the video").into())
+        }
+        _ => {
+            if !quiet {
+                eprintln!(
+                    "Hint: {} is {}; --transcode re-encodes it to an mp4 every app can play.",
+                    path.display(),
+                    reason
+                );
+            }
+            prepare_file_attachment(path, size, false, quiet)
+        }
+    }
+}
+
+/// Prepares a converted copy of `original` that is deleted after upload.
+fn prepare_converted_attachment(
+    original: &Path,
+    converted: PathBuf,
+    file_name: String,
+    quiet: bool,
+) -> Result<PreparedAttachment, Box<dyn std::error::Error>> {
+    let result = fs::metadata(&converted)
+        .map_err(Into::into)
+        .and_then(|metadata| prepare_file_attachment(&converted, metadata.len(), false, quiet));
+    let mut prepared = match result {
+        Ok(prepared) => prepared,
+        Err(err) => {
+            let _ = fs::remove_file(&converted);
+            return Err(err);
+        }
+    };
+    prepared.display_name = original.display().to_string();
+    prepared.file_name = file_name;
+    prepared.cleanup_path = Some(converted);
     Ok(prepared)
 }
 
+fn is_video_path(path: &Path) -> bool {
+    mime_guess::from_path(path)
+        .first()
+        .is_some_and(|mime| mime.type_() == mime_guess::mime::VIDEO)
+}
+
 pub(crate) fn input_media_from_upload(
     upload: &UploadFileResult,
 ) -> Result<proto::InputMedia, Box<dyn std::error::Error>> {
@@ -113,12 +333,20 @@ pub(crate) fn input_media_from_upload(
 fn prepare_directory_attachment(
     path: &Path,
     data_dir: &Path,
+    zip_options: &ZipOptions,
     quiet: bool,
 ) -> Result<PreparedAttachment, Box<dyn std::error::Error>> {
     if !quiet {
         eprintln!("Zipping folder {}...", path.display());
     }
-    let (zip_path, zip_name) = zip_directory(path, data_dir)?;
+    let (zip_path, zip_name, skipped) = zip_directory(path, data_dir, zip_options)?;
+    if !quiet && skipped > 0 {
+        eprintln!(
+            "Left out {} excluded {}.",
+            skipped,
+            if skipped == 1 { "entry" } else { "entries" }
+        );
+    }
     let size = fs::metadata(&zip_path)?.len();
     ensure_attachment_size(&zip_name, size, quiet)?;
 
@@ -129,6 +357,8 @@ fn prepare_directory_attachment(
         mime_type: Some("application/zip".to_string()),
         file_type: UploadFileType::Document,
         video_metadata: None,
+        photo_metadata: None,
+        thumbnail: None,
         size_bytes: size,
         cleanup_path: Some(zip_path),
     })
@@ -165,11 +395,11 @@ fn prepare_file_attachment(
 
     let mut video_metadata = None;
     if matches!(file_type, UploadFileType::Video) {
-        if let Some(metadata) = probe_video_metadata(path) {
+        if let Some(metadata) = probe_video(path).and_then(|probe| probe.metadata()) {
             video_metadata = Some(metadata);
         } else if !quiet {
-            eprintln!(
-                "Warning: could not read video metadata for {}. Uploading as document.",
+            log::warn!(
+                "could not read video metadata for {}. Uploading as document.",
                 display_name
             );
             file_type = UploadFileType::Document;
@@ -183,6 +413,8 @@ fn prepare_file_attachment(
         mime_type,
         file_type,
         video_metadata,
+        photo_metadata: None,
+        thumbnail: None,
         size_bytes: size,
         cleanup_path: None,
     })
@@ -203,19 +435,35 @@ fn ensure_attachment_size(
     Ok(())
 }
 
+/// Zips `dir` into the data dir. Returns the zip path, the name to upload it
+/// as, and how many files or folders were excluded.
 fn zip_directory(
     dir: &Path,
     data_dir: &Path,
-) -> Result<(PathBuf, String), Box<dyn std::error::Error>> {
+    zip_options: &ZipOptions,
+) -> Result<(PathBuf, String, usize), Box<dyn std::error::Error>> {
     fs::create_dir_all(data_dir)?;
     let folder_name = dir
         .file_name()
         .and_then(|value| value.to_str())
         .filter(|value| !value.trim().is_empty())
         .unwrap_or("folder");
-    let zip_name = format!("{}.zip", folder_name);
+    let zip_name = match zip_options.name.as_deref() {
+        Some(name) => zip_file_name(name)?,
+        None => format!("{}.zip", folder_name),
+    };
     let zip_path = data_dir.join(format!("{}-{}.zip", folder_name, current_epoch_seconds()));
 
+    let mut rules = zip_options
+        .exclude
+        .iter()
+        .filter_map(|pattern| IgnoreRule::parse(pattern, ""))
+        .collect::<Vec<_>>();
+    if zip_options.respect_gitignore {
+        rules.extend(IgnoreRule::parse(".git/", ""));
+    }
+    let exclude_count = rules.len();
+
     let file = fs::File::create(&zip_path)?;
     let mut zip = zip::ZipWriter::new(file);
     let options = zip::write::FileOptions::default()
@@ -223,22 +471,45 @@ fn zip_directory(
         .unix_permissions(0o644);
 
     let mut has_entries = false;
-    for entry in walkdir::WalkDir::new(dir)
-        .into_iter()
-        .filter_map(Result::ok)
-    {
-        if entry.path() == dir {
+    let mut skipped = 0;
+    let mut walker = walkdir::WalkDir::new(dir).sort_by_file_name().into_iter();
+    while let Some(entry) = walker.next() {
+        let Ok(entry) = entry else { continue };
+        if entry.file_type().is_symlink() {
             continue;
         }
-        if entry.file_type().is_symlink() {
+        let is_dir = entry.file_type().is_dir();
+        let relative = entry.path().strip_prefix(dir)?;
+        let name = relative.to_string_lossy().replace('\\', "/");
+
+        if entry.path() != dir {
+            // --zip-exclude always wins; .gitignore rules can re-include with `!`.
+            let excluded = is_ignored(&rules[..exclude_count], &name, is_dir)
+                || is_ignored(&rules[exclude_count..], &name, is_dir);
+            if excluded {
+                skipped += 1;
+                if is_dir {
+                    walker.skip_current_dir();
+                }
+                continue;
+            }
+        }
+        if is_dir
+            && zip_options.respect_gitignore
+            && let Ok(contents) = fs::read_to_string(entry.path().join(".gitignore"))
+        {
+            rules.extend(
+                contents
+                    .lines()
+                    .filter_map(|line| IgnoreRule::parse(line, &name)),
+            );
+        }
+        if entry.path() == dir {
             continue;
         }
 
-        let relative = entry.path().strip_prefix(dir)?;
-        let mut name = relative.to_string_lossy().replace('\\', "/");
-        if entry.file_type().is_dir() {
-            name.push('/');
-            zip.add_directory(name, options)?;
+        if is_dir {
+            zip.add_directory(format!("{name}/"), options)?;
             continue;
         }
 
@@ -253,50 +524,172 @@ fn zip_directory(
     zip.finish()?;
 
     if !has_entries {
-        return Err(CliError::invalid_args("Folder has no files to upload.").into());
+        let _ = fs::remove_file(&zip_path);
+        let message = if skipped > 0 {
+            "Folder has no files to upload after exclusions."
+        } else {
+            "Folder has no files to upload."
+        };
+        return Err(CliError::invalid_args(message).into());
     }
 
-    Ok((zip_path, zip_name))
+    Ok((zip_path, zip_name, skipped))
 }
 
-fn probe_video_metadata(path: &Path) -> Option<UploadVideoMetadata> {
-    let output = std::process::Command::new("ffprobe")
-        .arg("-v")
-        .arg("error")
-        .arg("-select_streams")
-        .arg("v:0")
-        .arg("-show_entries")
-        .arg("stream=width,height,duration")
-        .arg("-of")
-        .arg("json")
-        .arg(path)
-        .output();
-
-    let output = match output {
-        Ok(output) if output.status.success() => output,
-        _ => return None,
-    };
+fn zip_file_name(name: &str) -> Result<String, CliError> {
+    let name = name.trim();
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return Err(CliError::invalid_args(
+            "--zip-name must be a file name like build.zip",
+        ));
+    }
+    if name.to_ascii_lowercase().ends_with(".zip") {
+        Ok(name.to_string())
+    } else {
+        Ok(format!("{name}.zip"))
+    }
+}
 
-    let parsed: FfprobeOutput = serde_json::from_slice(&output.stdout).ok()?;
-    let stream = parsed.streams.into_iter().next()?;
-    let width = stream.width?;
-    let height = stream.height?;
-    let duration = stream
-        .duration
-        .and_then(|value| value.parse::<f64>().ok())?;
-    if width <= 0 || height <= 0 {
-        return None;
-    }
-    let duration = duration.ceil() as i32;
-    if duration <= 0 {
-        return None;
-    }
-
-    Some(UploadVideoMetadata {
-        width,
-        height,
-        duration,
-    })
+/// One line of a `.gitignore` (or a `--zip-exclude` pattern), with gitignore
+/// semantics: a trailing `/` only matches folders, a `/` anywhere else anchors
+/// the pattern to `base`, and `!` re-includes.
+#[derive(Debug)]
+struct IgnoreRule {
+    base: String,
+    pattern: String,
+    anchored: bool,
+    dir_only: bool,
+    negated: bool,
+}
+
+impl IgnoreRule {
+    fn parse(line: &str, base: &str) -> Option<Self> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let pattern = line.trim_start_matches('/').to_string();
+        if pattern.is_empty() {
+            return None;
+        }
+        Some(Self {
+            base: base.to_string(),
+            pattern,
+            anchored,
+            dir_only,
+            negated,
+        })
+    }
+
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let path = if self.base.is_empty() {
+            path
+        } else {
+            match path
+                .strip_prefix(self.base.as_str())
+                .and_then(|rest| rest.strip_prefix('/'))
+            {
+                Some(rest) => rest,
+                None => return false,
+            }
+        };
+        if self.anchored {
+            glob_match(&self.pattern, path)
+        } else {
+            let file_name = path.rsplit('/').next().unwrap_or(path);
+            glob_match(&self.pattern, file_name)
+        }
+    }
+}
+
+/// The last matching rule decides, as in git. Folders that are excluded are
+/// never walked, so their contents don't need rules of their own.
+fn is_ignored(rules: &[IgnoreRule], path: &str, is_dir: bool) -> bool {
+    rules
+        .iter()
+        .rev()
+        .find(|rule| rule.matches(path, is_dir))
+        .is_some_and(|rule| !rule.negated)
+}
+
+/// `*` and `?` stop at `/`; `**` crosses folders; `[abc]` is a character class.
+fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let path = path.chars().collect::<Vec<_>>();
+    glob_match_from(&pattern, &path)
+}
+
+fn glob_match_from(pattern: &[char], path: &[char]) -> bool {
+    match pattern.first() {
+        None => path.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let mut rest = &pattern[2..];
+            // "**/" also matches zero folders.
+            if rest.first() == Some(&'/') {
+                if glob_match_from(&rest[1..], path) {
+                    return true;
+                }
+                rest = &pattern[2..];
+            }
+            (0..=path.len()).any(|skip| glob_match_from(rest, &path[skip..]))
+        }
+        Some('*') => {
+            let rest = &pattern[1..];
+            for skip in 0..=path.len() {
+                if glob_match_from(rest, &path[skip..]) {
+                    return true;
+                }
+                if path.get(skip) == Some(&'/') {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => {
+            matches!(path.first(), Some(ch) if *ch != '/')
+                && glob_match_from(&pattern[1..], &path[1..])
+        }
+        Some('[') => {
+            let Some(close) = pattern.iter().skip(2).position(|ch| *ch == ']') else {
+                return path.first() == Some(&'[') && glob_match_from(&pattern[1..], &path[1..]);
+            };
+            let class = &pattern[1..close + 2];
+            let Some(ch) = path.first() else {
+                return false;
+            };
+            let (negated, class) = match class.first() {
+                Some('!' | '^') => (true, &class[1..]),
+                _ => (false, class),
+            };
+            let mut found = false;
+            let mut index = 0;
+            while index < class.len() {
+                if class.get(index + 1) == Some(&'-') && index + 2 < class.len() {
+                    found |= (class[index]..=class[index + 2]).contains(ch);
+                    index += 3;
+                } else {
+                    found |= class[index] == *ch;
+                    index += 1;
+                }
+            }
+            found != negated && *ch != '/' && glob_match_from(&pattern[close + 3..], &path[1..])
+        }
+        Some(literal) => {
+            path.first() == Some(literal) && glob_match_from(&pattern[1..], &path[1..])
+        }
+    }
 }
 
 fn current_epoch_seconds() -> u64 {
@@ -306,18 +699,6 @@ fn current_epoch_seconds() -> u64 {
         .as_secs()
 }
 
-#[derive(serde::Deserialize)]
-struct FfprobeOutput {
-    streams: Vec<FfprobeStream>,
-}
-
-#[derive(serde::Deserialize)]
-struct FfprobeStream {
-    width: Option<i32>,
-    height: Option<i32>,
-    duration: Option<String>,
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -368,6 +749,109 @@ mod tests {
         assert_eq!(document.to_upload_input().file_name, "image.jpg");
     }
 
+    #[test]
+    fn upload_input_carries_photo_previews() {
+        let mut photo = prepare_file_attachment(Path::new("image.jpg"), 100, false, true).unwrap();
+        photo.photo_metadata = Some(UploadPhotoMetadata::new(4032, 3024));
+        photo.thumbnail = Some(UploadThumbnail::jpeg(vec![0xff, 0xd8]));
+
+        let input = photo.to_upload_input();
+        assert_eq!(
+            input.photo_metadata,
+            Some(UploadPhotoMetadata::new(4032, 3024))
+        );
+        assert_eq!(input.thumbnail.unwrap().mime_type, "image/jpeg");
+
+        let mut document =
+            prepare_file_attachment(Path::new("notes.txt"), 100, false, true).unwrap();
+        document.add_preview(Path::new("/nonexistent"));
+        assert!(document.to_upload_input().thumbnail.is_none());
+    }
+
+    #[test]
+    fn captured_attachments_upload_as_named_photos_and_clean_up() {
+        let dir = std::env::temp_dir().join(format!("inline-capture-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("clipboard-1.png");
+        fs::write(&path, b"png").unwrap();
+
+        let prepared =
+            prepare_captured_attachment(path.clone(), "clipboard.png", false, true).unwrap();
+        assert!(matches!(prepared.file_type, UploadFileType::Photo));
+        assert_eq!(prepared.to_upload_input().file_name, "clipboard.png");
+        drop(prepared);
+        assert!(!path.exists());
+        let _ = fs::remove_dir(&dir);
+    }
+
+    #[test]
+    fn zip_globs_follow_gitignore_rules() {
+        let rules = [
+            "*.log",
+            "node_modules/",
+            "/dist",
+            "docs/**/*.tmp",
+            "!keep.log",
+        ]
+        .iter()
+        .filter_map(|pattern| IgnoreRule::parse(pattern, ""))
+        .collect::<Vec<_>>();
+
+        assert!(is_ignored(&rules, "server/debug.log", false));
+        assert!(!is_ignored(&rules, "server/keep.log", false));
+        assert!(is_ignored(&rules, "web/node_modules", true));
+        assert!(!is_ignored(&rules, "web/node_modules", false));
+        assert!(is_ignored(&rules, "dist", true));
+        assert!(!is_ignored(&rules, "web/dist", true));
+        assert!(is_ignored(&rules, "docs/a.tmp", false));
+        assert!(is_ignored(&rules, "docs/api/v1/a.tmp", false));
+        assert!(!is_ignored(&rules, "src/a.tmp", false));
+
+        let nested = IgnoreRule::parse("build", "web").unwrap();
+        assert!(nested.matches("web/build", true));
+        assert!(nested.matches("web/app/build", true));
+        assert!(!nested.matches("build", true));
+
+        assert!(glob_match("img-[0-9].png", "img-4.png"));
+        assert!(!glob_match("img-[!0-9].png", "img-4.png"));
+        assert!(!glob_match("src/*.rs", "src/bin/main.rs"));
+    }
+
+    #[test]
+    fn folder_zips_honor_exclusions_gitignore_and_custom_name() {
+        let dir = std::env::temp_dir().join(format!("inline-zip-{}", std::process::id()));
+        let project = dir.join("project");
+        fs::create_dir_all(project.join("target/debug")).unwrap();
+        fs::create_dir_all(project.join("src")).unwrap();
+        fs::write(project.join(".gitignore"), "target/\n").unwrap();
+        fs::write(project.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(project.join("server.log"), "noise").unwrap();
+        fs::write(project.join("target/debug/app"), "binary").unwrap();
+
+        let options = ZipOptions {
+            exclude: vec!["*.log".to_string()],
+            name: Some("app-src".to_string()),
+            respect_gitignore: true,
+        };
+        let (zip_path, zip_name, skipped) =
+            zip_directory(&project, &dir.join("data"), &options).unwrap();
+        assert_eq!(zip_name, "app-src.zip");
+        assert_eq!(skipped, 2);
+
+        let archive = zip::ZipArchive::new(fs::File::open(&zip_path).unwrap()).unwrap();
+        let mut names = archive.file_names().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, [".gitignore", "src/", "src/main.rs"]);
+
+        let options = ZipOptions {
+            exclude: vec!["*".to_string()],
+            ..ZipOptions::default()
+        };
+        let err = zip_directory(&project, &dir.join("data"), &options).unwrap_err();
+        assert!(err.to_string().contains("after exclusions"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn oversized_attachment_errors_are_structured() {
         let err = ensure_attachment_size("big.bin", MAX_ATTACHMENT_BYTES + 1, true).unwrap_err();
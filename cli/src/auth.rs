@@ -22,9 +22,23 @@ struct SecretsFile {
     token: Option<String>,
     api_base_url: Option<String>,
     updated_at: Option<i64>,
+    /// Older files only have `updated_at`, which `store_token` also set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token_saved_at: Option<i64>,
     device_id: Option<String>,
 }
 
+/// Where the active token came from, for `auth me`, `auth refresh` and `doctor`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenStatus {
+    pub source: &'static str,
+    /// Unix seconds; unknown for `INLINE_TOKEN`.
+    pub saved_at: Option<i64>,
+    /// Tokens carry no expiry today; they stay valid until logout or revocation.
+    pub expires_at: Option<i64>,
+}
+
 pub struct AuthStore {
     path: PathBuf,
     api_base_url: String,
@@ -54,6 +68,37 @@ impl AuthStore {
         Ok(secrets.token.filter(|token| !token.trim().is_empty()))
     }
 
+    pub fn token_status(&self) -> Result<Option<TokenStatus>, AuthError> {
+        if load_env_token().is_some() {
+            return Ok(Some(TokenStatus {
+                source: "INLINE_TOKEN",
+                saved_at: None,
+                expires_at: None,
+            }));
+        }
+        let Some(secrets) = self.read_secrets_for_current_api()? else {
+            return Ok(None);
+        };
+        if secrets
+            .token
+            .as_deref()
+            .is_none_or(|token| token.trim().is_empty())
+        {
+            return Ok(None);
+        }
+        Ok(Some(TokenStatus {
+            source: "secrets_file",
+            saved_at: secrets.token_saved_at.or(secrets.updated_at),
+            expires_at: None,
+        }))
+    }
+
+    /// Reads the secrets file even when `INLINE_TOKEN` is set, so `doctor --fix`
+    /// can tell an unreadable file from one that's just unused.
+    pub fn check_secrets(&self) -> Result<(), AuthError> {
+        self.read_secrets().map(|_| ())
+    }
+
     pub fn device_id(&self) -> Result<String, AuthError> {
         if let Ok(device_id) = env::var("INLINE_DEVICE_ID") {
             let device_id = device_id.trim().to_string();
@@ -85,6 +130,7 @@ impl AuthStore {
         secrets.token = Some(token.to_string());
         secrets.api_base_url = Some(self.api_base_url.clone());
         secrets.updated_at = Some(current_epoch_seconds() as i64);
+        secrets.token_saved_at = secrets.updated_at;
         self.write_secrets(&secrets)
     }
 
@@ -109,6 +155,7 @@ impl AuthStore {
         }
 
         secrets.token = None;
+        secrets.token_saved_at = None;
         secrets.api_base_url = Some(self.api_base_url.clone());
         secrets.updated_at = Some(current_epoch_seconds() as i64);
         self.write_secrets(&secrets)
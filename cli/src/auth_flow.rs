@@ -1,11 +1,14 @@
 use dialoguer::{Input, Select};
 use serde::Serialize;
 
-use crate::auth::AuthStore;
+use crate::auth::{AuthStore, TokenStatus};
 use crate::errors::CliError;
 use crate::identity as client_info;
+use crate::output::format_relative_date;
 use crate::state::LocalDb;
-use crate::{AuthLoginArgs, fetch_me, is_interactive_terminal, user_display_name};
+use crate::{
+    AuthLoginArgs, current_epoch_seconds, fetch_me, is_interactive_terminal, user_display_name,
+};
 use inline_protocol::proto;
 use inline_sdk::api::{ApiClient, ApiError};
 
@@ -16,6 +19,28 @@ pub(crate) struct AuthLogoutOutput {
     pub(crate) effective_token_present: bool,
     pub(crate) effective_token_source: Option<String>,
     pub(crate) warning: Option<String>,
+    /// Other sessions signed out by `--all-devices`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) sessions_revoked: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AuthMeOutput {
+    #[serde(flatten)]
+    pub(crate) user: proto::User,
+    pub(crate) token: Option<TokenStatus>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AuthRefreshOutput {
+    pub(crate) valid: bool,
+    /// Always false: the server has no refresh endpoint, so a rejected token
+    /// is replaced by logging in again instead.
+    pub(crate) refreshed: bool,
+    pub(crate) user: proto::User,
+    pub(crate) token: Option<TokenStatus>,
 }
 
 #[derive(Clone)]
@@ -99,9 +124,9 @@ pub(crate) async fn handle_login(
             match result {
                 Ok(result) => {
                     auth_store.store_token(&result.token)?;
-                    let mut realtime =
+                    let realtime =
                         client_info::connect_realtime(realtime_url, &result.token).await?;
-                    match fetch_me(&mut realtime).await {
+                    match fetch_me(&realtime).await {
                         Ok(me) => {
                             local_db.set_current_user(me.clone())?;
                             let name = user_display_name(&me);
@@ -199,6 +224,7 @@ pub(crate) fn build_auth_logout_output(env_token_present: bool) -> AuthLogoutOut
                 "INLINE_TOKEN is still set; future commands will remain authenticated from the environment."
                     .to_string(),
             ),
+            sessions_revoked: None,
         }
     } else {
         AuthLogoutOutput {
@@ -206,11 +232,15 @@ pub(crate) fn build_auth_logout_output(env_token_present: bool) -> AuthLogoutOut
             effective_token_present: false,
             effective_token_source: None,
             warning: None,
+            sessions_revoked: None,
         }
     }
 }
 
 pub(crate) fn print_auth_logout(output: &AuthLogoutOutput) {
+    if let Some(revoked) = output.sessions_revoked {
+        println!("Signed out {revoked} other session(s) and this one.");
+    }
     if let Some(warning) = output.warning.as_deref() {
         println!("Cleared saved token.");
         println!("Warning: {warning}");
@@ -219,6 +249,37 @@ pub(crate) fn print_auth_logout(output: &AuthLogoutOutput) {
     }
 }
 
+pub(crate) fn print_auth_refresh(output: &AuthRefreshOutput) {
+    println!(
+        "Token for {} (id {}) is still valid; nothing to refresh.",
+        user_display_name(&output.user),
+        output.user.id
+    );
+    if let Some(token) = &output.token {
+        println!(
+            "token: {}",
+            describe_token_status(token, current_epoch_seconds() as i64)
+        );
+    }
+}
+
+/// One-line summary of where the token came from and how long it lasts.
+pub(crate) fn describe_token_status(token: &TokenStatus, now: i64) -> String {
+    let origin = match token.saved_at {
+        Some(saved_at) => format!(
+            "saved {} ({})",
+            format_relative_date(saved_at, now),
+            token.source
+        ),
+        None => format!("from {}", token.source),
+    };
+    let expiry = match token.expires_at {
+        Some(expires_at) => format!("expires {}", format_relative_date(expires_at, now)),
+        None => "no expiry reported; valid until logout or revocation".to_string(),
+    };
+    format!("{origin}, {expiry}")
+}
+
 pub(crate) fn print_auth_user(user: &proto::User) {
     let name = user_display_name(user);
     println!("Logged in as {} (id {}).", name, user.id);
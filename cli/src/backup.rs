@@ -0,0 +1,312 @@
+//! `inline backup` archives.
+//!
+//! An archive is a zstd- (or, with `--compress gzip`, gzip-) compressed tar with `manifest.json`, the chat list as
+//! returned by `getChats` (`chats.json`, including users and spaces), a media
+//! manifest (`media.json`), and one JSON-lines file of messages per chat under
+//! `messages/`. `restore` writes the same layout to a folder.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, Read, Write};
+use std::path::Path;
+
+use inline_protocol::proto;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::compression::{Compression, encoder, open_decompressed};
+
+pub(crate) const BACKUP_FORMAT_VERSION: u32 = 1;
+const MANIFEST_PATH: &str = "manifest.json";
+const CHATS_PATH: &str = "chats.json";
+const MEDIA_PATH: &str = "media.json";
+const MESSAGES_DIR: &str = "messages";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BackupManifest {
+    pub(crate) version: u32,
+    pub(crate) created_at: i64,
+    /// When the previous backup this one continues from was taken. Unset for
+    /// full backups.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) incremental_since: Option<i64>,
+    pub(crate) chats: Vec<BackupChat>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BackupChat {
+    pub(crate) chat_id: i64,
+    pub(crate) title: String,
+    /// Messages up to this id were in an earlier backup.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) after_message_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) last_message_id: Option<i64>,
+    pub(crate) messages: usize,
+    pub(crate) media: usize,
+    /// False when history was cut off by the page limit.
+    pub(crate) complete: bool,
+}
+
+pub(crate) struct Backup {
+    pub(crate) manifest: BackupManifest,
+    pub(crate) chats: proto::GetChatsResult,
+    /// Oldest first, keyed by chat id.
+    pub(crate) messages: BTreeMap<i64, Vec<proto::Message>>,
+    /// One object per media item, with `chatId` and `messageId`.
+    pub(crate) media: Vec<Value>,
+}
+
+impl Backup {
+    pub(crate) fn message_count(&self) -> usize {
+        self.messages.values().map(Vec::len).sum()
+    }
+
+    /// Files of the backup layout, shared by archives and restored folders.
+    fn entries(&self) -> Result<Vec<(String, Vec<u8>)>, serde_json::Error> {
+        let mut entries = vec![
+            (
+                MANIFEST_PATH.to_string(),
+                serde_json::to_vec_pretty(&self.manifest)?,
+            ),
+            (CHATS_PATH.to_string(), serde_json::to_vec(&self.chats)?),
+            (
+                MEDIA_PATH.to_string(),
+                serde_json::to_vec_pretty(&self.media)?,
+            ),
+        ];
+        for (chat_id, messages) in &self.messages {
+            let mut lines = Vec::new();
+            for message in messages {
+                serde_json::to_writer(&mut lines, message)?;
+                lines.push(b'\n');
+            }
+            entries.push((format!("{MESSAGES_DIR}/{chat_id}.jsonl"), lines));
+        }
+        Ok(entries)
+    }
+}
+
+pub(crate) fn write_archive(
+    path: &Path,
+    backup: &Backup,
+    compression: Compression,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let temp_path = path.with_extension("partial");
+    let encoder = encoder(File::create(&temp_path)?, compression)?;
+    let mut builder = tar::Builder::new(encoder);
+    for (name, bytes) in backup.entries()? {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(bytes.len() as u64);
+        header.set_mode(0o600);
+        header.set_mtime(backup.manifest.created_at.max(0) as u64);
+        header.set_cksum();
+        builder.append_data(&mut header, &name, bytes.as_slice())?;
+    }
+    builder.into_inner()?.finish()?.flush()?;
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+pub(crate) fn read_archive(path: &Path) -> Result<Backup, Box<dyn std::error::Error>> {
+    let mut archive = tar::Archive::new(open_decompressed(path)?);
+    let mut manifest = None;
+    let mut chats = None;
+    let mut media = Vec::new();
+    let mut messages = BTreeMap::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes)?;
+        match name.as_str() {
+            MANIFEST_PATH => manifest = Some(serde_json::from_slice::<BackupManifest>(&bytes)?),
+            CHATS_PATH => chats = Some(serde_json::from_slice(&bytes)?),
+            MEDIA_PATH => media = serde_json::from_slice(&bytes)?,
+            _ => {
+                let Some(chat_id) = name
+                    .strip_prefix(&format!("{MESSAGES_DIR}/"))
+                    .and_then(|name| name.strip_suffix(".jsonl"))
+                    .and_then(|chat_id| chat_id.parse::<i64>().ok())
+                else {
+                    continue;
+                };
+                let mut chat_messages = Vec::new();
+                for line in bytes.lines() {
+                    let line = line?;
+                    if !line.trim().is_empty() {
+                        chat_messages.push(serde_json::from_str(&line)?);
+                    }
+                }
+                messages.insert(chat_id, chat_messages);
+            }
+        }
+    }
+    let manifest = manifest.ok_or("not an inline backup: manifest.json is missing")?;
+    if manifest.version > BACKUP_FORMAT_VERSION {
+        return Err(format!(
+            "backup format {} is newer than this inline supports ({BACKUP_FORMAT_VERSION}); update inline",
+            manifest.version
+        )
+        .into());
+    }
+    Ok(Backup {
+        manifest,
+        chats: chats.unwrap_or_default(),
+        messages,
+        media,
+    })
+}
+
+/// Folds a chain of backups (full plus incrementals, any order) into one.
+/// The newest chat list wins; messages and media are merged by id.
+pub(crate) fn merge_backups(mut backups: Vec<Backup>) -> Option<Backup> {
+    backups.sort_by_key(|backup| backup.manifest.created_at);
+    let mut merged = backups.pop()?;
+    let mut chats = merged
+        .manifest
+        .chats
+        .drain(..)
+        .map(|chat| (chat.chat_id, chat))
+        .collect::<BTreeMap<_, _>>();
+    for older in backups.into_iter().rev() {
+        for (chat_id, messages) in older.messages {
+            merged.messages.entry(chat_id).or_default().extend(messages);
+        }
+        merged.media.extend(older.media);
+        for chat in older.manifest.chats {
+            chats.entry(chat.chat_id).or_insert(chat);
+        }
+        merged.manifest.incremental_since = older.manifest.incremental_since;
+    }
+    for (chat_id, messages) in &mut merged.messages {
+        messages.sort_by_key(|message| message.id);
+        messages.dedup_by_key(|message| message.id);
+        if let Some(chat) = chats.get_mut(chat_id) {
+            chat.after_message_id = None;
+            chat.messages = messages.len();
+            chat.last_message_id = messages.last().map(|message| message.id);
+        }
+    }
+    let media_key = |media: &Value| {
+        (
+            media["chatId"].as_i64(),
+            media["messageId"].as_i64(),
+            media["mediaId"].as_i64(),
+        )
+    };
+    merged.media.sort_by_key(media_key);
+    merged.media.dedup_by_key(|media| media_key(media));
+    for chat in chats.values_mut() {
+        chat.media = merged
+            .media
+            .iter()
+            .filter(|media| media["chatId"].as_i64() == Some(chat.chat_id))
+            .count();
+    }
+    merged.manifest.chats = chats.into_values().collect();
+    Some(merged)
+}
+
+/// Writes the backup layout as plain files under `dir`.
+pub(crate) fn write_folder(dir: &Path, backup: &Backup) -> Result<(), Box<dyn std::error::Error>> {
+    fs::create_dir_all(dir.join(MESSAGES_DIR))?;
+    for (name, bytes) in backup.entries()? {
+        fs::write(dir.join(&name), bytes)?;
+    }
+    Ok(())
+}
+
+/// Media manifest rows for `messages`, tagged with where they came from.
+pub(crate) fn media_manifest(chat_id: i64, messages: &[proto::Message]) -> io::Result<Vec<Value>> {
+    let mut rows = Vec::new();
+    for message in messages {
+        for media in crate::message_export::export_media(message) {
+            let mut row = serde_json::to_value(media)?;
+            if let Value::Object(fields) = &mut row {
+                fields.insert("chatId".to_string(), chat_id.into());
+                fields.insert("messageId".to_string(), message.id.into());
+            }
+            rows.push(row);
+        }
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: i64) -> proto::Message {
+        proto::Message {
+            id,
+            chat_id: 7,
+            message: Some(format!("message {id}")),
+            ..Default::default()
+        }
+    }
+
+    fn backup(created_at: i64, ids: &[i64], incremental_since: Option<i64>) -> Backup {
+        Backup {
+            manifest: BackupManifest {
+                version: BACKUP_FORMAT_VERSION,
+                created_at,
+                incremental_since,
+                chats: vec![BackupChat {
+                    chat_id: 7,
+                    title: "Design".to_string(),
+                    after_message_id: incremental_since.map(|_| ids[0] - 1),
+                    last_message_id: ids.last().copied(),
+                    messages: ids.len(),
+                    media: 0,
+                    complete: true,
+                }],
+            },
+            chats: proto::GetChatsResult::default(),
+            messages: BTreeMap::from([(7, ids.iter().copied().map(message).collect())]),
+            media: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn archive_round_trips() {
+        let dir = std::env::temp_dir().join(format!("inline-backup-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("backup.tar.zst");
+        let gzip_path = dir.join("backup.tar.gz");
+
+        write_archive(&path, &backup(100, &[1, 2, 3], None), Compression::Zstd).unwrap();
+        write_archive(&gzip_path, &backup(100, &[1, 2], None), Compression::Gzip).unwrap();
+        let read = read_archive(&path).unwrap();
+        let gzip_read = read_archive(&gzip_path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(read.manifest.created_at, 100);
+        assert_eq!(read.message_count(), 3);
+        assert_eq!(read.messages[&7][2].message.as_deref(), Some("message 3"));
+        assert_eq!(gzip_read.message_count(), 2);
+    }
+
+    #[test]
+    fn merge_folds_incrementals_into_the_full_backup() {
+        let merged = merge_backups(vec![
+            backup(200, &[3, 4], Some(100)),
+            backup(100, &[1, 2, 3], None),
+        ])
+        .unwrap();
+
+        assert_eq!(merged.manifest.created_at, 200);
+        assert_eq!(merged.manifest.incremental_since, None);
+        let ids = merged.messages[&7]
+            .iter()
+            .map(|message| message.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+        let chat = &merged.manifest.chats[0];
+        assert_eq!(chat.messages, 4);
+        assert_eq!(chat.after_message_id, None);
+        assert_eq!(chat.last_message_id, Some(4));
+    }
+}
@@ -0,0 +1,170 @@
+//! Unread totals for `inline badge`.
+//!
+//! Counts follow the app's badge: archived chats are left out, and a chat
+//! marked unread with no unread messages counts as one.
+
+use std::collections::BTreeMap;
+
+use inline_protocol::proto;
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Badge {
+    pub(crate) total: i64,
+    pub(crate) unread_chats: usize,
+    /// Only with `--by-space`; spaces with nothing unread are left out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) spaces: Option<Vec<SpaceBadge>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SpaceBadge {
+    /// Empty for DMs and home threads, listed as "Home".
+    pub(crate) space_id: Option<i64>,
+    pub(crate) name: String,
+    pub(crate) unread: i64,
+}
+
+pub(crate) fn count_unread(payload: &proto::GetChatsResult, by_space: bool) -> Badge {
+    let mut total = 0;
+    let mut unread_chats = 0;
+    let mut per_space: BTreeMap<Option<i64>, i64> = BTreeMap::new();
+    for dialog in &payload.dialogs {
+        if dialog.archived == Some(true) {
+            continue;
+        }
+        let count = i64::from(dialog.unread_count.unwrap_or(0).max(0));
+        let count = if count == 0 && dialog.unread_mark == Some(true) {
+            1
+        } else {
+            count
+        };
+        if count == 0 {
+            continue;
+        }
+        total += count;
+        unread_chats += 1;
+        *per_space
+            .entry(dialog_space_id(payload, dialog))
+            .or_default() += count;
+    }
+
+    let spaces = by_space.then(|| {
+        let mut spaces = per_space
+            .into_iter()
+            .map(|(space_id, unread)| SpaceBadge {
+                space_id,
+                name: space_name(payload, space_id),
+                unread,
+            })
+            .collect::<Vec<_>>();
+        spaces.sort_by(|left, right| {
+            right
+                .unread
+                .cmp(&left.unread)
+                .then_with(|| left.name.cmp(&right.name))
+        });
+        spaces
+    });
+    Badge {
+        total,
+        unread_chats,
+        spaces,
+    }
+}
+
+/// One line for a status bar: `5`, or `5 (Acme 3, Home 2)` by space.
+pub(crate) fn format_badge(badge: &Badge) -> String {
+    match badge.spaces.as_deref() {
+        Some(spaces) if !spaces.is_empty() => {
+            let parts = spaces
+                .iter()
+                .map(|space| format!("{} {}", space.name, space.unread))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{} ({parts})", badge.total)
+        }
+        _ => badge.total.to_string(),
+    }
+}
+
+fn dialog_space_id(payload: &proto::GetChatsResult, dialog: &proto::Dialog) -> Option<i64> {
+    dialog.space_id.or_else(|| {
+        let chat_id = dialog
+            .chat_id
+            .or_else(|| match &dialog.peer.as_ref()?.r#type {
+                Some(proto::peer::Type::Chat(chat)) => Some(chat.chat_id),
+                _ => None,
+            })?;
+        payload
+            .chats
+            .iter()
+            .find(|chat| chat.id == chat_id)
+            .and_then(|chat| chat.space_id)
+    })
+}
+
+fn space_name(payload: &proto::GetChatsResult, space_id: Option<i64>) -> String {
+    let Some(space_id) = space_id else {
+        return "Home".to_string();
+    };
+    payload
+        .spaces
+        .iter()
+        .find(|space| space.id == space_id)
+        .map(|space| space.name.clone())
+        .unwrap_or_else(|| format!("space {space_id}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dialog(chat_id: i64, unread: i32) -> proto::Dialog {
+        proto::Dialog {
+            chat_id: Some(chat_id),
+            unread_count: Some(unread),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn totals_skip_archived_chats_and_group_by_space() {
+        let payload = proto::GetChatsResult {
+            chats: vec![proto::Chat {
+                id: 7,
+                space_id: Some(31),
+                ..Default::default()
+            }],
+            spaces: vec![proto::Space {
+                id: 31,
+                name: "Acme".to_string(),
+                ..Default::default()
+            }],
+            dialogs: vec![
+                dialog(7, 3),
+                dialog(8, 2),
+                proto::Dialog {
+                    archived: Some(true),
+                    ..dialog(9, 40)
+                },
+                proto::Dialog {
+                    unread_mark: Some(true),
+                    ..dialog(10, 0)
+                },
+                dialog(11, 0),
+            ],
+            ..Default::default()
+        };
+
+        let badge = count_unread(&payload, false);
+        assert_eq!((badge.total, badge.unread_chats), (6, 3));
+        assert_eq!(format_badge(&badge), "6");
+
+        let badge = count_unread(&payload, true);
+        assert_eq!(format_badge(&badge), "6 (Acme 3, Home 3)");
+        assert_eq!(badge.spaces.unwrap()[0].space_id, Some(31));
+    }
+}
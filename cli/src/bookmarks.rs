@@ -0,0 +1,148 @@
+//! Local message bookmarks for `inline bookmarks`.
+//!
+//! Bookmarks live in the local state file together with a copy of the message,
+//! so the text stays readable after the message is edited or deleted on the
+//! server.
+
+use serde::{Deserialize, Serialize};
+
+use crate::deep_link::inline_url;
+use crate::digest::truncate_preview;
+use crate::output::{format_relative_date, style_heading};
+use inline_protocol::proto;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bookmark {
+    /// Local number, stable until the bookmark is removed.
+    pub id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chat_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<i64>,
+    pub message_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Unix seconds when the bookmark was added or last updated.
+    pub saved_at: i64,
+    /// The message as it was when bookmarked.
+    pub message: proto::Message,
+}
+
+impl Bookmark {
+    pub(crate) fn matches_peer(&self, chat_id: Option<i64>, user_id: Option<i64>) -> bool {
+        self.chat_id == chat_id && self.user_id == user_id
+    }
+
+    pub(crate) fn peer_label(&self) -> String {
+        match (self.chat_id, self.user_id) {
+            (Some(chat_id), _) => format!("chat {chat_id}"),
+            (None, Some(user_id)) => format!("user {user_id}"),
+            (None, None) => "-".to_string(),
+        }
+    }
+
+    pub(crate) fn url(&self) -> Option<String> {
+        match (self.chat_id, self.user_id) {
+            (Some(chat_id), _) => inline_url("chat", chat_id, Some(self.message_id)),
+            (None, Some(user_id)) => inline_url("user", user_id, Some(self.message_id)),
+            (None, None) => None,
+        }
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        self.message.message.as_deref().unwrap_or_default()
+    }
+}
+
+pub(crate) fn next_bookmark_id(bookmarks: &[Bookmark]) -> u64 {
+    bookmarks
+        .iter()
+        .map(|bookmark| bookmark.id)
+        .max()
+        .unwrap_or(0)
+        + 1
+}
+
+pub(crate) fn print_bookmark_list(bookmarks: &[Bookmark], now: i64) {
+    if bookmarks.is_empty() {
+        println!("No bookmarks. Add one with `inline bookmarks add --chat-id ID --message-id ID`.");
+        return;
+    }
+    let peer_width = bookmarks
+        .iter()
+        .map(|bookmark| bookmark.peer_label().len())
+        .max()
+        .unwrap_or_default()
+        .max(4);
+    println!(
+        "{}",
+        style_heading(&format!(
+            "{:>4}  {:<peer_width$}  {:>8}  {:<8}  note / message",
+            "#", "peer", "message", "saved"
+        ))
+    );
+    for bookmark in bookmarks {
+        let summary = match bookmark.note.as_deref() {
+            Some(note) => format!("{note} — {}", truncate_preview(bookmark.text())),
+            None => truncate_preview(bookmark.text()),
+        };
+        println!(
+            "{:>4}  {:<peer_width$}  {:>8}  {:<8}  {}",
+            bookmark.id,
+            bookmark.peer_label(),
+            bookmark.message_id,
+            format_relative_date(bookmark.saved_at, now),
+            summary
+        );
+    }
+}
+
+pub(crate) fn print_bookmark(bookmark: &Bookmark, now: i64) {
+    println!(
+        "Bookmark {} · {} message {}",
+        bookmark.id,
+        bookmark.peer_label(),
+        bookmark.message_id
+    );
+    if let Some(note) = &bookmark.note {
+        println!("note: {note}");
+    }
+    println!(
+        "sent: {} by user {}",
+        format_relative_date(bookmark.message.date, now),
+        bookmark.message.from_id
+    );
+    println!("saved: {}", format_relative_date(bookmark.saved_at, now));
+    println!();
+    println!("{}", bookmark.text());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bookmarks_number_from_the_highest_id_and_link_to_their_message() {
+        let bookmark = |id: u64, chat_id: Option<i64>, user_id: Option<i64>| Bookmark {
+            id,
+            chat_id,
+            user_id,
+            message_id: 456,
+            note: None,
+            saved_at: 0,
+            message: proto::Message::default(),
+        };
+        let bookmarks = [bookmark(1, Some(123), None), bookmark(4, None, Some(42))];
+
+        assert_eq!(next_bookmark_id(&[]), 1);
+        assert_eq!(next_bookmark_id(&bookmarks), 5);
+        assert_eq!(
+            bookmarks[0].url().as_deref(),
+            Some("inline://chat/123?message=456")
+        );
+        assert_eq!(bookmarks[1].peer_label(), "user 42");
+        assert!(bookmarks[1].matches_peer(None, Some(42)));
+        assert!(!bookmarks[1].matches_peer(Some(42), None));
+    }
+}
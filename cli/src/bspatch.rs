@@ -0,0 +1,205 @@
+//! Applies bsdiff (`BSDIFF40`) patches for delta updates.
+//!
+//! A patch is a 32-byte header followed by three bzip2 streams: control
+//! triples, bytes added to the old file, and bytes inserted verbatim.
+
+use std::io::{self, Read};
+
+use bzip2::read::BzDecoder;
+
+const MAGIC: &[u8; 8] = b"BSDIFF40";
+const HEADER_LEN: usize = 32;
+/// Largest output a patch may declare; the header is read before anything is
+/// checksummed, so it can't size the allocation on its own.
+pub(crate) const MAX_NEW_LEN: usize = 512 * 1024 * 1024;
+
+/// Applies `patch` to `old`, refusing patches whose header declares an output
+/// larger than `max_new_len` (capped at [`MAX_NEW_LEN`]).
+pub(crate) fn apply_patch(old: &[u8], patch: &[u8], max_new_len: usize) -> io::Result<Vec<u8>> {
+    if patch.len() < HEADER_LEN || &patch[..8] != MAGIC {
+        return Err(invalid("not a BSDIFF40 patch"));
+    }
+    let ctrl_len = header_len(&patch[8..16])?;
+    let diff_len = header_len(&patch[16..24])?;
+    let new_len = header_len(&patch[24..32])?;
+    if new_len > max_new_len.min(MAX_NEW_LEN) {
+        return Err(invalid("new file is larger than expected"));
+    }
+    let ctrl_end = HEADER_LEN
+        .checked_add(ctrl_len)
+        .filter(|end| *end <= patch.len())
+        .ok_or_else(|| invalid("truncated control block"))?;
+    let diff_end = ctrl_end
+        .checked_add(diff_len)
+        .filter(|end| *end <= patch.len())
+        .ok_or_else(|| invalid("truncated diff block"))?;
+
+    let mut ctrl = BzDecoder::new(&patch[HEADER_LEN..ctrl_end]);
+    let mut diff = BzDecoder::new(&patch[ctrl_end..diff_end]);
+    let mut extra = BzDecoder::new(&patch[diff_end..]);
+
+    let mut new = vec![0u8; new_len];
+    let mut new_pos = 0usize;
+    let mut old_pos = 0i64;
+    while new_pos < new_len {
+        let add = control_len(read_offset(&mut ctrl)?)?;
+        let copy = control_len(read_offset(&mut ctrl)?)?;
+        let seek = read_offset(&mut ctrl)?;
+
+        let add_end = new_pos
+            .checked_add(add)
+            .filter(|end| *end <= new_len)
+            .ok_or_else(|| invalid("diff runs past the new file"))?;
+        diff.read_exact(&mut new[new_pos..add_end])?;
+        for (offset, byte) in new[new_pos..add_end].iter_mut().enumerate() {
+            let source = advance(old_pos, offset as i64)?;
+            if source >= 0
+                && let Some(old_byte) = old.get(source as usize)
+            {
+                *byte = byte.wrapping_add(*old_byte);
+            }
+        }
+        new_pos = add_end;
+        old_pos = advance(old_pos, add as i64)?;
+
+        let copy_end = new_pos
+            .checked_add(copy)
+            .filter(|end| *end <= new_len)
+            .ok_or_else(|| invalid("extra runs past the new file"))?;
+        extra.read_exact(&mut new[new_pos..copy_end])?;
+        new_pos = copy_end;
+        old_pos = advance(old_pos, seek)?;
+    }
+    Ok(new)
+}
+
+fn advance(old_pos: i64, by: i64) -> io::Result<i64> {
+    old_pos
+        .checked_add(by)
+        .ok_or_else(|| invalid("old file offset overflows"))
+}
+
+/// bsdiff integers are 8-byte little-endian sign-magnitude.
+fn decode_offset(bytes: [u8; 8]) -> i64 {
+    let magnitude = (u64::from_le_bytes(bytes) & !(1 << 63)) as i64;
+    if bytes[7] & 0x80 != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+fn read_offset(reader: &mut impl Read) -> io::Result<i64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(decode_offset(bytes))
+}
+
+fn header_len(bytes: &[u8]) -> io::Result<usize> {
+    let value = decode_offset(bytes.try_into().expect("8-byte header field"));
+    usize::try_from(value).map_err(|_| invalid("negative length in header"))
+}
+
+fn control_len(value: i64) -> io::Result<usize> {
+    usize::try_from(value).map_err(|_| invalid("negative length in control block"))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("bad patch: {message}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bzip2::Compression;
+    use bzip2::write::BzEncoder;
+    use std::io::Write;
+
+    fn encode_offset(value: i64) -> [u8; 8] {
+        let mut bytes = value.unsigned_abs().to_le_bytes();
+        if value < 0 {
+            bytes[7] |= 0x80;
+        }
+        bytes
+    }
+
+    fn compress(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = BzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn patch(triples: &[(i64, i64, i64)], diff: &[u8], extra: &[u8], new_len: i64) -> Vec<u8> {
+        let ctrl = compress(
+            &triples
+                .iter()
+                .flat_map(|(add, copy, seek)| {
+                    [
+                        encode_offset(*add),
+                        encode_offset(*copy),
+                        encode_offset(*seek),
+                    ]
+                    .concat()
+                })
+                .collect::<Vec<_>>(),
+        );
+        let diff = compress(diff);
+        let mut patch = MAGIC.to_vec();
+        patch.extend(encode_offset(ctrl.len() as i64));
+        patch.extend(encode_offset(diff.len() as i64));
+        patch.extend(encode_offset(new_len));
+        patch.extend(ctrl);
+        patch.extend(diff);
+        patch.extend(compress(extra));
+        patch
+    }
+
+    #[test]
+    fn applies_diff_extra_and_backward_seeks() {
+        let old = b"hello world";
+        // "hello" unchanged, then " there", then re-read "hello" with a
+        // capital H by seeking back to the start of the old file.
+        let patch = patch(
+            &[(5, 6, -5), (5, 0, 0)],
+            &[0, 0, 0, 0, 0, b'H'.wrapping_sub(b'h'), 0, 0, 0, 0],
+            b" there",
+            16,
+        );
+
+        assert_eq!(
+            apply_patch(old, &patch, MAX_NEW_LEN).unwrap(),
+            b"hello thereHello"
+        );
+    }
+
+    #[test]
+    fn rejects_foreign_and_truncated_patches() {
+        assert!(apply_patch(b"", b"BSDIFF39", MAX_NEW_LEN).is_err());
+        let mut truncated = patch(&[(1, 0, 0)], &[0], b"", 1);
+        truncated.truncate(40);
+        assert!(apply_patch(b"a", &truncated, MAX_NEW_LEN).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_headers() {
+        let huge = patch(&[(1, 0, 0)], &[0], b"", i64::MAX);
+        let error = apply_patch(b"a", &huge, MAX_NEW_LEN).unwrap_err();
+        assert!(
+            error.to_string().contains("larger than expected"),
+            "{error}"
+        );
+
+        let oversized = patch(&[(2, 0, 0)], &[0, 0], b"", 2);
+        assert!(apply_patch(b"ab", &oversized, 1).is_err());
+
+        let negative = patch(&[(1, 0, 0)], &[0], b"", -1);
+        assert!(apply_patch(b"a", &negative, MAX_NEW_LEN).is_err());
+    }
+
+    #[test]
+    fn rejects_seeks_that_overflow() {
+        let patch = patch(&[(0, 0, i64::MAX), (1, 0, 0), (1, 0, 0)], &[0, 0], b"", 2);
+        let error = apply_patch(b"a", &patch, MAX_NEW_LEN).unwrap_err();
+        assert!(error.to_string().contains("overflows"), "{error}");
+    }
+}
@@ -0,0 +1,138 @@
+//! Methods the configured server was found not to implement.
+//!
+//! The realtime protocol has no capability list to negotiate: `ConnectionOpen`
+//! carries nothing. Instead the session notes each method the server rejects
+//! as unknown (or answers with the wrong result type). Only the ones it
+//! answered with an explicit "not implemented" (status 501) are kept in local
+//! state, so later commands fail with "server does not support X" before
+//! sending anything; the rest are guesses and only last for the invocation.
+//! Entries belong to one realtime URL and CLI version, expire after a day in
+//! case the server has been upgraded since, and `inline server
+//! forget-unsupported` drops them sooner.
+
+use inline_protocol::proto;
+use serde::{Deserialize, Serialize};
+
+use crate::state::LocalState;
+
+/// How long a learned entry is trusted before the method is tried again.
+pub(crate) const MAX_AGE_SECS: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerCapabilities {
+    pub realtime_url: String,
+    pub cli_version: String,
+    /// Unix seconds when a method was last found missing.
+    pub checked_at: i64,
+    /// Protocol method names, such as `GET_CHAT_HISTORY`.
+    pub unsupported_methods: Vec<String>,
+}
+
+/// The methods to fail fast on when connecting to `realtime_url`.
+pub(crate) fn known_unsupported(
+    state: &LocalState,
+    realtime_url: &str,
+    cli_version: &str,
+    now: i64,
+) -> Vec<proto::Method> {
+    state
+        .server_capabilities
+        .as_ref()
+        .filter(|entry| is_current(entry, realtime_url, cli_version, now))
+        .map(|entry| {
+            entry
+                .unsupported_methods
+                .iter()
+                .filter_map(|name| proto::Method::from_str_name(name))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Records what a session learned. Returns whether `state` changed and needs
+/// saving; methods already on record leave it untouched.
+pub(crate) fn remember(
+    state: &mut LocalState,
+    realtime_url: &str,
+    cli_version: &str,
+    methods: &[proto::Method],
+    now: i64,
+) -> bool {
+    let known = known_unsupported(state, realtime_url, cli_version, now);
+    if methods.iter().all(|method| known.contains(method)) {
+        return false;
+    }
+    let mut unsupported_methods = known
+        .iter()
+        .chain(methods)
+        .map(|method| method.as_str_name().to_string())
+        .collect::<Vec<_>>();
+    unsupported_methods.sort();
+    unsupported_methods.dedup();
+    state.server_capabilities = Some(ServerCapabilities {
+        realtime_url: realtime_url.to_string(),
+        cli_version: cli_version.to_string(),
+        checked_at: now,
+        unsupported_methods,
+    });
+    true
+}
+
+/// Drops everything learned, returning what was on record.
+pub(crate) fn forget(state: &mut LocalState) -> Option<ServerCapabilities> {
+    state.server_capabilities.take()
+}
+
+fn is_current(entry: &ServerCapabilities, realtime_url: &str, cli_version: &str, now: i64) -> bool {
+    entry.realtime_url == realtime_url
+        && entry.cli_version == cli_version
+        && now - entry.checked_at < MAX_AGE_SECS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "wss://chat.internal/realtime";
+
+    #[test]
+    fn learned_methods_apply_to_one_server_and_version_for_a_day() {
+        let mut state = LocalState::default();
+        assert!(remember(
+            &mut state,
+            URL,
+            "1.4.0",
+            &[proto::Method::GetChatHistory],
+            1_000
+        ));
+        assert!(!remember(
+            &mut state,
+            URL,
+            "1.4.0",
+            &[proto::Method::GetChatHistory],
+            1_100
+        ));
+        assert!(remember(
+            &mut state,
+            URL,
+            "1.4.0",
+            &[proto::Method::GetChatHistory, proto::Method::GetMe],
+            1_200
+        ));
+        assert_eq!(
+            known_unsupported(&state, URL, "1.4.0", 2_000),
+            vec![proto::Method::GetChatHistory, proto::Method::GetMe]
+        );
+
+        assert!(
+            known_unsupported(&state, "wss://api.inline.chat/realtime", "1.4.0", 2_000).is_empty()
+        );
+        assert!(known_unsupported(&state, URL, "1.5.0", 2_000).is_empty());
+        assert!(known_unsupported(&state, URL, "1.4.0", 1_200 + MAX_AGE_SECS).is_empty());
+
+        assert!(forget(&mut state).is_some());
+        assert!(known_unsupported(&state, URL, "1.4.0", 2_000).is_empty());
+        assert!(forget(&mut state).is_none());
+    }
+}
@@ -0,0 +1,126 @@
+//! Image capture for `messages send --paste` and `--screenshot`.
+//!
+//! macOS only. The clipboard is read through AppKit via `osascript` (the same
+//! `«class PNGf»` pasteboard type `pngpaste` uses) and screenshots go through the
+//! system `screencapture` tool, so no extra binaries need to be installed.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::errors::CliError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CaptureSource {
+    Clipboard,
+    Screenshot,
+}
+
+impl CaptureSource {
+    pub(crate) fn flag(self) -> &'static str {
+        match self {
+            Self::Clipboard => "--paste",
+            Self::Screenshot => "--screenshot",
+        }
+    }
+
+    pub(crate) fn file_name(self) -> &'static str {
+        match self {
+            Self::Clipboard => "clipboard.png",
+            Self::Screenshot => "screenshot.png",
+        }
+    }
+}
+
+/// AppleScript that writes the clipboard's PNG data to the path given as the
+/// first argument. Fails when the clipboard holds no image.
+const CLIPBOARD_PNG_SCRIPT: [&str; 6] = [
+    "on run argv",
+    "set imageData to the clipboard as «class PNGf»",
+    "set fileRef to open for access (POSIX file (item 1 of argv)) with write permission",
+    "write imageData to fileRef",
+    "close access fileRef",
+    "end run",
+];
+
+/// Captures an image into `data_dir` and returns its path. The caller owns the
+/// file and should remove it after upload.
+pub(crate) fn capture_image(
+    source: CaptureSource,
+    data_dir: &Path,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if !cfg!(target_os = "macos") {
+        return Err(CliError::capture_failed(source.flag(), "only supported on macOS").into());
+    }
+
+    fs::create_dir_all(data_dir)?;
+    let path = capture_path(source, data_dir, current_epoch_millis());
+    let result = match source {
+        CaptureSource::Clipboard => {
+            let mut command = std::process::Command::new("osascript");
+            for line in CLIPBOARD_PNG_SCRIPT {
+                command.arg("-e").arg(line);
+            }
+            command.arg(&path).output()
+        }
+        // -i: interactive selection (space toggles window mode), -x: no shutter sound.
+        CaptureSource::Screenshot => std::process::Command::new("screencapture")
+            .arg("-i")
+            .arg("-x")
+            .arg(&path)
+            .output(),
+    };
+
+    let output = result
+        .map_err(|err| CliError::capture_failed(source.flag(), format!("could not run: {err}")))?;
+    let captured = fs::metadata(&path)
+        .map(|metadata| metadata.len() > 0)
+        .unwrap_or(false);
+    if output.status.success() && captured {
+        return Ok(path);
+    }
+
+    let _ = fs::remove_file(&path);
+    let reason = match source {
+        CaptureSource::Clipboard => "the clipboard does not contain an image",
+        CaptureSource::Screenshot => "the screenshot was cancelled",
+    };
+    Err(CliError::capture_failed(source.flag(), reason).into())
+}
+
+fn capture_path(source: CaptureSource, data_dir: &Path, epoch_millis: u128) -> PathBuf {
+    let stem = source
+        .file_name()
+        .strip_suffix(".png")
+        .unwrap_or(source.file_name());
+    data_dir.join(format!("{stem}-{epoch_millis}.png"))
+}
+
+fn current_epoch_millis() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_paths_are_unique_pngs_in_data_dir() {
+        let path = capture_path(CaptureSource::Screenshot, Path::new("/tmp/inline"), 42);
+        assert_eq!(path, PathBuf::from("/tmp/inline/screenshot-42.png"));
+        assert_eq!(CaptureSource::Clipboard.file_name(), "clipboard.png");
+        assert_eq!(CaptureSource::Clipboard.flag(), "--paste");
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn capture_is_rejected_off_macos() {
+        let err = capture_image(CaptureSource::Clipboard, Path::new("/nonexistent")).unwrap_err();
+        let cli_err = err.downcast_ref::<CliError>().unwrap();
+        assert_eq!(cli_err.code, "capture_failed");
+        assert!(cli_err.message.contains("--paste"));
+    }
+}
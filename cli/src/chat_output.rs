@@ -2,11 +2,13 @@ use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
 
 use crate::message_output::message_summary;
+use crate::notifications::{dialog_mode_label, dialog_override_mode};
 use crate::output::{
     ChatListItem, ChatListOutput, PeerSummary, SpaceSummary, space_summary, user_display_name,
 };
 use crate::peer::{MessageKey, PeerKey, peer_key_from_peer};
 use inline_protocol::proto;
+use serde::Serialize;
 
 pub(crate) fn apply_chat_list_limits(
     mut payload: proto::GetChatsResult,
@@ -92,6 +94,157 @@ pub(crate) fn apply_chat_list_limits(
     payload
 }
 
+/// A chat's notification mode, for `chats list --json`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ChatNotifications {
+    pub(crate) chat_id: i64,
+    /// `all`, `mentions`, `none`, or `default` (follows the global mode).
+    pub(crate) mode: &'static str,
+    pub(crate) muted: bool,
+}
+
+/// Notification modes for every chat in `payload`, from its dialogs.
+pub(crate) fn chat_notifications(payload: &proto::GetChatsResult) -> Vec<ChatNotifications> {
+    payload
+        .chats
+        .iter()
+        .map(|chat| {
+            let mode = dialog_for_chat(payload, chat).and_then(dialog_override_mode);
+            ChatNotifications {
+                chat_id: chat.id,
+                mode: mode.map_or("default", dialog_mode_label),
+                muted: mode == Some(proto::dialog_notification_settings::Mode::None),
+            }
+        })
+        .collect()
+}
+
+/// The dialog for `chat`, matched by chat id or, for DMs, by peer.
+fn dialog_for_chat<'a>(
+    payload: &'a proto::GetChatsResult,
+    chat: &proto::Chat,
+) -> Option<&'a proto::Dialog> {
+    let peer = chat.peer_id.as_ref().and_then(peer_key_from_peer);
+    payload.dialogs.iter().find(|dialog| {
+        dialog.chat_id == Some(chat.id)
+            || (peer.is_some() && dialog.peer.as_ref().and_then(peer_key_from_peer) == peer)
+    })
+}
+
+/// One chat of a space, for `spaces chats`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SpaceChat {
+    pub(crate) chat_id: i64,
+    pub(crate) title: String,
+    pub(crate) emoji: Option<String>,
+    /// Everyone in the space can see and join it.
+    pub(crate) public: bool,
+    /// In your chat list; public threads you haven't joined are `false`.
+    pub(crate) joined: bool,
+    pub(crate) unread_count: i32,
+    pub(crate) last_message_date: Option<i64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SpaceChatsOutput {
+    pub(crate) space_id: i64,
+    pub(crate) space_name: Option<String>,
+    pub(crate) chats: Vec<SpaceChat>,
+}
+
+/// The chats of `space_id` in `payload`, newest activity first. A public
+/// thread counts as joined once it has an open dialog.
+pub(crate) fn space_chats(payload: &proto::GetChatsResult, space_id: i64) -> SpaceChatsOutput {
+    let last_message_dates = payload
+        .messages
+        .iter()
+        .map(|message| ((message.chat_id, message.id), message.date))
+        .collect::<HashMap<_, _>>();
+    let mut chats = payload
+        .chats
+        .iter()
+        .filter(|chat| chat.space_id == Some(space_id))
+        .map(|chat| {
+            let dialog = dialog_for_chat(payload, chat);
+            let public = chat.is_public.unwrap_or(false);
+            let open = dialog.is_some_and(|dialog| dialog.open != Some(false));
+            SpaceChat {
+                chat_id: chat.id,
+                title: chat.title.clone(),
+                emoji: chat.emoji.clone().filter(|emoji| !emoji.is_empty()),
+                public,
+                joined: !public || open,
+                unread_count: dialog.and_then(|dialog| dialog.unread_count).unwrap_or(0),
+                last_message_date: chat
+                    .last_msg_id
+                    .and_then(|id| last_message_dates.get(&(chat.id, id)).copied()),
+            }
+        })
+        .collect::<Vec<_>>();
+    chats.sort_by_key(|chat| (Reverse(chat.last_message_date), chat.chat_id));
+    SpaceChatsOutput {
+        space_id,
+        space_name: payload
+            .spaces
+            .iter()
+            .find(|space| space.id == space_id)
+            .map(|space| space.name.clone()),
+        chats,
+    }
+}
+
+/// Where you've read up to in one chat, for `chats read-state`. Every field
+/// is always present so monitoring scripts can rely on the shape.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ChatReadState {
+    pub(crate) chat_id: i64,
+    pub(crate) peer: Option<PeerSummary>,
+    pub(crate) title: String,
+    /// Newest message id you've read; `null` before the first read.
+    pub(crate) read_max_id: Option<i64>,
+    pub(crate) last_message_id: Option<i64>,
+    pub(crate) unread_count: i32,
+    /// Set by `chats mark-unread`, independent of `unreadCount`.
+    pub(crate) unread_mark: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ReadStateOutput {
+    pub(crate) dialogs: Vec<ChatReadState>,
+}
+
+/// Read state for every chat in `payload`, in list order.
+pub(crate) fn chat_read_states(payload: &proto::GetChatsResult) -> Vec<ChatReadState> {
+    let users_by_id = payload
+        .users
+        .iter()
+        .map(|user| (user.id, user.clone()))
+        .collect::<HashMap<_, _>>();
+    payload
+        .chats
+        .iter()
+        .map(|chat| {
+            let dialog = dialog_for_chat(payload, chat);
+            ChatReadState {
+                chat_id: chat.id,
+                peer: chat.peer_id.as_ref().and_then(peer_summary_from_peer),
+                title: chat_display_name(chat, &users_by_id),
+                read_max_id: dialog.and_then(|dialog| dialog.read_max_id),
+                last_message_id: chat.last_msg_id,
+                unread_count: dialog.and_then(|dialog| dialog.unread_count).unwrap_or(0),
+                unread_mark: dialog
+                    .and_then(|dialog| dialog.unread_mark)
+                    .unwrap_or(false),
+            }
+        })
+        .collect()
+}
+
 pub(crate) fn apply_chat_list_filter(
     mut payload: proto::GetChatsResult,
     filter: Option<&str>,
@@ -304,6 +457,7 @@ pub(crate) fn build_chat_list(
             .as_ref()
             .map(|summary| summary.relative_date.clone());
 
+        let notification_mode = draft.dialog.as_ref().and_then(dialog_override_mode);
         items.push(ChatListItem {
             chat: draft.chat,
             dialog: draft.dialog,
@@ -312,6 +466,9 @@ pub(crate) fn build_chat_list(
             space: draft.space,
             space_name: draft.space_name,
             unread_count: draft.unread_count,
+            notifications: notification_mode.map_or("default", dialog_mode_label),
+            muted: notification_mode == Some(proto::dialog_notification_settings::Mode::None),
+            activity: None,
             last_message: last_message_summary,
             last_message_line,
             last_message_relative_date,
@@ -0,0 +1,150 @@
+//! `chats create --title-template`, for scripts that open a thread per
+//! release, incident, or day.
+//!
+//! `{name}` placeholders are filled from `--var name=value` and the built-ins
+//! `date` (today, UTC, `YYYY-MM-DD`) and `user` (your username, or your name
+//! without one). `{{` and `}}` are literal braces. A placeholder with no value
+//! is an error rather than being left in the title.
+
+use chrono::NaiveDate;
+
+/// Names filled in without `--var`; a `--var` with the same name wins.
+pub(crate) const BUILT_INS: &[&str] = &["date", "user"];
+
+/// Parses one `--var NAME=VALUE`.
+pub(crate) fn parse_var(value: &str) -> Result<(String, String), String> {
+    let (name, value) = value
+        .split_once('=')
+        .ok_or_else(|| "expected NAME=VALUE".to_string())?;
+    let name = name.trim();
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-'))
+    {
+        return Err(format!(
+            "invalid name {name:?} (use letters, digits, `_`, or `-`)"
+        ));
+    }
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// The placeholder names `template` uses, in order of first use.
+pub(crate) fn placeholders(template: &str) -> Result<Vec<&str>, String> {
+    let mut names = Vec::new();
+    walk(template, |part| {
+        if let Part::Placeholder(name) = part
+            && !names.contains(&name)
+        {
+            names.push(name);
+        }
+    })?;
+    Ok(names)
+}
+
+/// Fills in `template`. `lookup` gets each placeholder name; `None` fails
+/// with that name.
+pub(crate) fn render<'v>(
+    template: &str,
+    lookup: impl Fn(&str) -> Option<&'v str>,
+) -> Result<String, String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut missing = Vec::new();
+    walk(template, |part| match part {
+        Part::Text(text) => rendered.push_str(text),
+        Part::Placeholder(name) => match lookup(name) {
+            Some(value) => rendered.push_str(value),
+            None => {
+                if !missing.contains(&name) {
+                    missing.push(name);
+                }
+            }
+        },
+    })?;
+    if !missing.is_empty() {
+        return Err(format!(
+            "no value for {} (pass --var NAME=VALUE; built-ins are {})",
+            missing
+                .iter()
+                .map(|name| format!("{{{name}}}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            BUILT_INS.join(", ")
+        ));
+    }
+    Ok(rendered)
+}
+
+pub(crate) fn date_value(today: NaiveDate) -> String {
+    today.format("%Y-%m-%d").to_string()
+}
+
+enum Part<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+fn walk<'a>(template: &'a str, mut visit: impl FnMut(Part<'a>)) -> Result<(), String> {
+    let mut rest = template;
+    while let Some(at) = rest.find(['{', '}']) {
+        visit(Part::Text(&rest[..at]));
+        let tail = &rest[at..];
+        if let Some(after) = tail.strip_prefix("{{").or_else(|| tail.strip_prefix("}}")) {
+            visit(Part::Text(&tail[..1]));
+            rest = after;
+            continue;
+        }
+        if tail.starts_with('}') {
+            return Err("unmatched `}` (write `}}` for a literal brace)".to_string());
+        }
+        let end = tail
+            .find('}')
+            .ok_or_else(|| "unclosed `{` (write `{{` for a literal brace)".to_string())?;
+        let name = tail[1..end].trim();
+        if name.is_empty() {
+            return Err("empty placeholder `{}`".to_string());
+        }
+        visit(Part::Placeholder(name));
+        rest = &tail[end + 1..];
+    }
+    visit(Part::Text(rest));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_fill_vars_and_reject_missing_ones() {
+        let vars = [("version", "1.2.3"), ("date", "2026-10-15")];
+        let lookup = |name: &str| {
+            vars.iter()
+                .find(|(known, _)| *known == name)
+                .map(|(_, value)| *value)
+        };
+        assert_eq!(
+            render("Release {version} – {date} {{draft}}", lookup).unwrap(),
+            "Release 1.2.3 – 2026-10-15 {draft}"
+        );
+        assert_eq!(
+            placeholders("{ version } {date} {version}").unwrap(),
+            ["version", "date"]
+        );
+        let err = render("Release {verison} by {user}", lookup).unwrap_err();
+        assert!(err.starts_with("no value for {verison}, {user}"), "{err}");
+        assert!(render("Release {version", lookup).is_err());
+        assert!(render("Release }", lookup).is_err());
+
+        assert_eq!(
+            parse_var("version=1.2=rc").unwrap(),
+            ("version".to_string(), "1.2=rc".to_string())
+        );
+        assert!(parse_var("version").is_err());
+        assert!(parse_var("bad name=x").is_err());
+        assert_eq!(
+            date_value(NaiveDate::from_ymd_opt(2026, 1, 5).unwrap()),
+            "2026-01-05"
+        );
+    }
+}
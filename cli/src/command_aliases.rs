@@ -0,0 +1,354 @@
+//! Git-style command aliases from `[alias]`, and `inline-NAME` executables on
+//! PATH as external subcommands.
+//!
+//! Both are only tried when the first word isn't a built-in command, so
+//! neither can shadow one. `standup = "messages send --to standup"` expands
+//! in place and is parsed like typed arguments; a value starting with `!`
+//! runs through `sh -c` with the remaining arguments as `$1`, `$2`, ....
+
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command as Process;
+
+use clap::Command;
+
+use crate::errors::CliError;
+
+/// Expansion chains longer than this are treated as a loop.
+const MAX_EXPANSIONS: usize = 16;
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Resolved {
+    /// The command line with the alias expanded, to be parsed again.
+    Argv(Vec<OsString>),
+    /// A shell alias or external subcommand to run in the CLI's place.
+    Run(External),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct External {
+    pub(crate) program: PathBuf,
+    pub(crate) args: Vec<OsString>,
+    /// Global flags given before the name, passed on as their env vars so
+    /// nested `inline` calls keep them.
+    pub(crate) env: Vec<(&'static str, OsString)>,
+}
+
+impl External {
+    /// Runs to completion with the terminal attached; returns the exit code.
+    pub(crate) fn run(&self) -> io::Result<i32> {
+        let mut process = Process::new(&self.program);
+        process.args(&self.args).envs(self.env.iter().cloned());
+        if let Ok(exe) = env::current_exe() {
+            process.env("INLINE_CLI", exe);
+        }
+        let status = process.status()?;
+        Ok(status.code().unwrap_or(1))
+    }
+}
+
+/// Expands `argv` when its first word is an alias or an `inline-NAME`
+/// program on `path`. `None` leaves the parse error as it was.
+pub(crate) fn resolve(
+    command: &Command,
+    argv: &[OsString],
+    aliases: &BTreeMap<String, String>,
+    path: Option<&OsStr>,
+) -> Result<Option<Resolved>, CliError> {
+    let mut argv = argv.to_vec();
+    let mut expanded = 0;
+    while let Some(at) = first_positional(command, &argv) {
+        let Some(name) = argv[at].to_str().map(str::to_string) else {
+            break;
+        };
+        if name == "help" || command.find_subcommand(&name).is_some() {
+            break;
+        }
+        let rest = argv[at + 1..].to_vec();
+        match aliases.get(&name) {
+            Some(_) if expanded == MAX_EXPANSIONS => {
+                return Err(CliError::invalid_command_alias(
+                    &name,
+                    "aliases expand into each other in a loop",
+                ));
+            }
+            Some(value) if value.starts_with('!') => {
+                let mut args = vec![
+                    OsString::from("-c"),
+                    OsString::from(format!("{} \"$@\"", &value[1..])),
+                    OsString::from(&name),
+                ];
+                args.extend(rest);
+                return Ok(Some(Resolved::Run(External {
+                    program: PathBuf::from("sh"),
+                    args,
+                    env: leading_env(&argv[1..at]),
+                })));
+            }
+            Some(value) => {
+                let words = split_words(value)
+                    .ok_or_else(|| CliError::invalid_command_alias(&name, "unbalanced quotes"))?;
+                if words.is_empty() {
+                    return Err(CliError::invalid_command_alias(&name, "empty alias"));
+                }
+                argv.splice(at..=at, words.into_iter().map(OsString::from));
+                expanded += 1;
+            }
+            None => {
+                if let Some(program) = find_external(&name, path) {
+                    return Ok(Some(Resolved::Run(External {
+                        program,
+                        args: rest,
+                        env: leading_env(&argv[1..at]),
+                    })));
+                }
+                break;
+            }
+        }
+    }
+    Ok((expanded > 0).then_some(Resolved::Argv(argv)))
+}
+
+/// The value of a global `--NAME VALUE`/`--NAME=VALUE` given before the
+/// first word, for loading the right profile before anything is parsed.
+pub(crate) fn leading_option(command: &Command, argv: &[OsString], long: &str) -> Option<String> {
+    let end = first_positional(command, argv).unwrap_or(argv.len());
+    let flag = format!("--{long}");
+    let mut tokens = argv.get(1..end)?.iter().filter_map(|token| token.to_str());
+    while let Some(token) = tokens.next() {
+        if token == flag {
+            return tokens.next().map(str::to_string);
+        }
+        if let Some(value) = token
+            .strip_prefix(&flag)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// Index of the first token that isn't a global flag or a flag's value.
+fn first_positional(command: &Command, argv: &[OsString]) -> Option<usize> {
+    let mut index = 1;
+    while index < argv.len() {
+        let token = argv[index].to_str()?;
+        if token == "--" {
+            return None;
+        }
+        let takes_value = if let Some(long) = token.strip_prefix("--") {
+            !long.contains('=')
+                && command
+                    .get_arguments()
+                    .find(|arg| arg.get_long() == Some(long))
+                    .is_some_and(|arg| arg.get_action().takes_values())
+        } else if let Some(short) = token.strip_prefix('-').filter(|short| !short.is_empty()) {
+            short.chars().count() == 1
+                && command
+                    .get_arguments()
+                    .find(|arg| arg.get_short() == short.chars().next())
+                    .is_some_and(|arg| arg.get_action().takes_values())
+        } else {
+            return Some(index);
+        };
+        index += if takes_value { 2 } else { 1 };
+    }
+    None
+}
+
+fn leading_env(leading: &[OsString]) -> Vec<(&'static str, OsString)> {
+    let mut env = Vec::new();
+    let mut tokens = leading.iter();
+    while let Some(token) = tokens.next() {
+        match token.to_str() {
+            Some("--json") => env.push(("INLINE_OUTPUT", OsString::from("json"))),
+            Some("--read-only") => env.push(("INLINE_READ_ONLY", OsString::from("1"))),
+            Some("--profile") => {
+                if let Some(profile) = tokens.next() {
+                    env.push(("INLINE_PROFILE", profile.clone()));
+                }
+            }
+            Some(token) => {
+                if let Some(profile) = token.strip_prefix("--profile=") {
+                    env.push(("INLINE_PROFILE", OsString::from(profile)));
+                }
+            }
+            None => {}
+        }
+    }
+    env
+}
+
+fn find_external(name: &str, path: Option<&OsStr>) -> Option<PathBuf> {
+    if name.is_empty() || name.starts_with('-') || name.contains(std::path::MAIN_SEPARATOR) {
+        return None;
+    }
+    let file_name = format!("inline-{name}");
+    env::split_paths(path?)
+        .map(|dir| dir.join(&file_name))
+        .find(|candidate| is_executable(candidate))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Splits like a shell would: whitespace separates words, and single quotes,
+/// double quotes, and backslashes group them. `None` for unbalanced quotes.
+fn split_words(value: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        match (quote, ch) {
+            (Some(open), ch) if ch == open => quote = None,
+            (Some('"') | None, '\\') => {
+                current.push(chars.next()?);
+                in_word = true;
+            }
+            (Some(_), ch) => current.push(ch),
+            (None, '\'' | '"') => {
+                quote = Some(ch);
+                in_word = true;
+            }
+            (None, ch) if ch.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, ch) => {
+                current.push(ch);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return None;
+    }
+    if in_word {
+        words.push(current);
+    }
+    Some(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cli;
+    use clap::CommandFactory;
+
+    fn argv(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    fn aliases(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn aliases_expand_in_place_after_global_flags() {
+        let aliases = aliases(&[
+            ("standup", "messages send --to standup --text 'Shipped it'"),
+            ("su", "standup"),
+            ("chats", "messages list"),
+            ("loop", "loop"),
+            ("hi", "!echo hi"),
+        ]);
+        let command = Cli::command();
+
+        assert_eq!(
+            resolve(
+                &command,
+                &argv(&["inline", "--profile", "work", "su", "--silent"]),
+                &aliases,
+                None
+            )
+            .unwrap(),
+            Some(Resolved::Argv(argv(&[
+                "inline",
+                "--profile",
+                "work",
+                "messages",
+                "send",
+                "--to",
+                "standup",
+                "--text",
+                "Shipped it",
+                "--silent",
+            ])))
+        );
+        // Built-ins win over an alias with the same name.
+        assert_eq!(
+            resolve(
+                &command,
+                &argv(&["inline", "chats", "list"]),
+                &aliases,
+                None
+            )
+            .unwrap(),
+            None
+        );
+        assert_eq!(
+            resolve(&command, &argv(&["inline", "loop"]), &aliases, None)
+                .unwrap_err()
+                .code,
+            "invalid_config"
+        );
+
+        let Some(Resolved::Run(shell)) = resolve(
+            &command,
+            &argv(&["inline", "--json", "hi", "there"]),
+            &aliases,
+            None,
+        )
+        .unwrap() else {
+            panic!("expected a shell alias");
+        };
+        assert_eq!(shell.program, PathBuf::from("sh"));
+        assert_eq!(shell.args, argv(&["-c", "echo hi \"$@\"", "hi", "there"]));
+        assert_eq!(shell.env, vec![("INLINE_OUTPUT", OsString::from("json"))]);
+    }
+
+    #[test]
+    fn words_split_on_quotes_and_escapes() {
+        assert_eq!(
+            split_words(r#"send --text "two words" it\'s '' x"#).unwrap(),
+            ["send", "--text", "two words", "it's", "", "x"]
+        );
+        assert_eq!(split_words("unbalanced 'quote"), None);
+        assert_eq!(
+            leading_option(
+                &Cli::command(),
+                &argv(&[
+                    "inline",
+                    "-v",
+                    "--profile=work",
+                    "standup",
+                    "--profile",
+                    "x"
+                ]),
+                "profile"
+            )
+            .as_deref(),
+            Some("work")
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use inline_protocol::proto;
+use serde::Serialize;
+
+use super::watch::next_updates;
+use super::{CommandContext, Execute};
+use crate::alerts::{Alert, Cooldowns, next_alert_id, print_alert_list, short_duration};
+use crate::chat_output::chat_display_name;
+use crate::dates::parse_duration;
+use crate::errors::CliError;
+use crate::message_output::message_summary;
+use crate::notify_done;
+use crate::output::{self, JsonFormat};
+use crate::validation::validate_optional_positive_id_arg;
+use crate::watch::{hook_command, message_hook_vars, new_messages_in_updates};
+use crate::{
+    AlertsAddArgs, AlertsCommand, AlertsIdArgs, AlertsMuteArgs, AlertsRunArgs,
+    current_epoch_seconds,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AlertListOutput<'a> {
+    alerts: &'a [Alert],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AlertOutput<'a> {
+    alert: &'a Alert,
+}
+
+/// One line of `alerts run --json`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AlertFiredEvent<'a> {
+    alert_id: u64,
+    keyword: &'a str,
+    message: &'a proto::Message,
+}
+
+impl Execute for AlertsCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            AlertsCommand::Add(args) => add(ctx, args),
+            AlertsCommand::List => {
+                let state = ctx.local_db.load()?;
+                if ctx.json {
+                    return ctx.print_json(&AlertListOutput {
+                        alerts: &state.alerts,
+                    });
+                }
+                print_alert_list(&state.alerts, current_epoch_seconds() as i64);
+                Ok(())
+            }
+            AlertsCommand::Remove(args) => remove(ctx, args),
+            AlertsCommand::Mute(args) => mute(ctx, args),
+            AlertsCommand::Unmute(args) => unmute(ctx, args),
+            AlertsCommand::Run(args) => run(ctx, args).await,
+        }
+    }
+}
+
+fn add(ctx: &CommandContext, args: AlertsAddArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let keyword = args.keyword.trim().to_string();
+    if keyword.is_empty() {
+        return Err(CliError::invalid_args("--keyword is empty").into());
+    }
+    let chat_id = validate_optional_positive_id_arg("--chat-id", args.chat_id)?;
+    let exec = args
+        .exec
+        .map(|exec| exec.trim().to_string())
+        .filter(|exec| !exec.is_empty());
+    let cooldown_secs = args
+        .cooldown
+        .as_deref()
+        .map(|cooldown| {
+            parse_duration(cooldown)
+                .map(|duration| duration.as_secs())
+                .map_err(|err| CliError::invalid_args(format!("--cooldown: {err}")))
+        })
+        .transpose()?;
+
+    let mut state = ctx.local_db.load()?;
+    let alert = Alert {
+        id: next_alert_id(&state.alerts),
+        keyword,
+        chat_id,
+        notify: args.notify,
+        exec,
+        cooldown_secs,
+        muted_until: None,
+        created_at: current_epoch_seconds() as i64,
+    };
+    state.alerts.push(alert.clone());
+    ctx.local_db.save(&state)?;
+
+    if ctx.json {
+        return ctx.print_json(&AlertOutput { alert: &alert });
+    }
+    println!("Added alert {} for \"{}\".", alert.id, alert.keyword);
+    if !alert.notify && alert.exec.is_none() {
+        println!("It only prints matches; add --notify or --exec to be told another way.");
+    }
+    println!("Run `inline alerts run` to start watching.");
+    Ok(())
+}
+
+fn remove(ctx: &CommandContext, args: AlertsIdArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = ctx.local_db.load()?;
+    find_alert(&mut state.alerts, args.id)?;
+    state.alerts.retain(|alert| alert.id != args.id);
+    ctx.local_db.save(&state)?;
+    if ctx.json {
+        return ctx.print_json(&serde_json::json!({ "removed": args.id }));
+    }
+    println!("Removed alert {}.", args.id);
+    Ok(())
+}
+
+fn mute(ctx: &CommandContext, args: AlertsMuteArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let duration = parse_duration(&args.duration)
+        .map_err(|err| CliError::invalid_args(format!("--for: {err}")))?;
+    let mut state = ctx.local_db.load()?;
+    let alert = find_alert(&mut state.alerts, args.id)?;
+    alert.muted_until = Some(current_epoch_seconds() as i64 + duration.as_secs() as i64);
+    let alert = alert.clone();
+    ctx.local_db.save(&state)?;
+    if ctx.json {
+        return ctx.print_json(&AlertOutput { alert: &alert });
+    }
+    println!(
+        "Muted alert {} for {}.",
+        alert.id,
+        short_duration(duration.as_secs())
+    );
+    Ok(())
+}
+
+fn unmute(ctx: &CommandContext, args: AlertsIdArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = ctx.local_db.load()?;
+    let alert = find_alert(&mut state.alerts, args.id)?;
+    alert.muted_until = None;
+    let alert = alert.clone();
+    ctx.local_db.save(&state)?;
+    if ctx.json {
+        return ctx.print_json(&AlertOutput { alert: &alert });
+    }
+    println!("Unmuted alert {}.", alert.id);
+    Ok(())
+}
+
+fn find_alert(alerts: &mut [Alert], id: u64) -> Result<&mut Alert, CliError> {
+    alerts
+        .iter_mut()
+        .find(|alert| alert.id == id)
+        .ok_or_else(|| CliError::invalid_args(format!("No alert {id} (see `inline alerts list`)")))
+}
+
+async fn run(ctx: &CommandContext, args: AlertsRunArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if args.count == Some(0) {
+        return Err(CliError::invalid_args("--count must be greater than 0").into());
+    }
+    if ctx.local_db.load()?.alerts.is_empty() {
+        return Err(CliError::invalid_args(
+            "No alerts to watch for; add one with `inline alerts add --keyword TEXT --notify`",
+        )
+        .into());
+    }
+    // Names for printed lines and notifications; chats or users that appear
+    // later fall back to ids.
+    let users_by_id = ctx.users_by_id().await?;
+    let chats_by_id = ctx.chats_by_id().await?;
+    let current_user_id = ctx.local_db.load()?.current_user.map(|user| user.id);
+    let realtime = ctx.realtime().await?;
+    let mut events = realtime.subscribe();
+    if !ctx.json {
+        eprintln!("Watching all chats for alerts. Press Ctrl-C to stop.");
+    }
+
+    let mut cooldowns = Cooldowns::default();
+    let mut fired = 0;
+    loop {
+        let updates = next_updates(&mut events).await?;
+        let mut messages = new_messages_in_updates(&updates).peekable();
+        if messages.peek().is_none() {
+            continue;
+        }
+        // Re-read so alerts changed since the last batch apply.
+        let alerts = ctx.local_db.load()?.alerts;
+        for message in messages {
+            let now = current_epoch_seconds() as i64;
+            for alert in alerts.iter().filter(|alert| {
+                alert.matches(message) && !alert.is_muted(now) && cooldowns.try_fire(alert, now)
+            }) {
+                let chat_name = chats_by_id
+                    .get(&message.chat_id)
+                    .map(|chat| chat_display_name(chat, users_by_id))
+                    .unwrap_or_else(|| format!("chat {}", message.chat_id));
+                let summary = message_summary(message, users_by_id, current_user_id, now, None);
+                if ctx.json {
+                    output::print_json(
+                        &AlertFiredEvent {
+                            alert_id: alert.id,
+                            keyword: &alert.keyword,
+                            message,
+                        },
+                        JsonFormat::Compact,
+                    )?;
+                } else {
+                    println!(
+                        "[alert {} \"{}\"] [{chat_name}] {}: {}",
+                        alert.id, alert.keyword, summary.sender_name, summary.preview
+                    );
+                }
+                if alert.notify {
+                    notify_done::notify(&format!(
+                        "\"{}\" in {chat_name}: {}",
+                        alert.keyword, summary.preview
+                    ));
+                }
+                if let Some(template) = alert.exec.as_deref() {
+                    let mut vars = vec![
+                        ("alert_id", alert.id.to_string()),
+                        ("keyword", alert.keyword.clone()),
+                    ];
+                    vars.extend(message_hook_vars(message));
+                    let status = hook_command(template, &vars, ctx.config.read_only)
+                        .status()
+                        .await?;
+                    if !status.success() {
+                        log::warn!(
+                            "hook for alert {} on message {} exited with {status}.",
+                            alert.id,
+                            message.id
+                        );
+                    }
+                }
+                fired += 1;
+                if args.count.is_some_and(|count| fired >= count) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::fs;
+use std::io::{self, Read};
+
+use serde_json::{Map, Value};
+
+use super::{CommandContext, Execute};
+use crate::errors::CliError;
+use crate::watch::shell_quote;
+use crate::{ApiCommand, ApiPostArgs};
+
+impl Execute for ApiCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            ApiCommand::Post(args) => post(ctx, args).await,
+        }
+    }
+}
+
+async fn post(ctx: &CommandContext, args: ApiPostArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let url = ctx.api.method_url(&args.method)?;
+    let body = read_body(args.data.as_deref())?;
+    if args.curl {
+        println!("{}", curl_command(&url, &body));
+        return Ok(());
+    }
+
+    let token = ctx.token()?;
+    let result = ctx.api.post_raw(&token, &args.method, body).await?;
+    // Arbitrary results have no human rendering; print JSON either way.
+    ctx.print_json(&result)?;
+    Ok(())
+}
+
+/// Parses `--data`: inline JSON, `@FILE`, or `@-` for stdin. Must be an object.
+fn read_body(data: Option<&str>) -> Result<Map<String, Value>, Box<dyn std::error::Error>> {
+    let raw = match data {
+        None => return Ok(Map::new()),
+        Some("@-") => {
+            let mut buffer = String::new();
+            io::stdin().read_to_string(&mut buffer)?;
+            buffer
+        }
+        Some(data) => match data.strip_prefix('@') {
+            Some(path) => fs::read_to_string(path)?,
+            None => data.to_string(),
+        },
+    };
+    match serde_json::from_str(&raw) {
+        Ok(Value::Object(body)) => Ok(body),
+        Ok(_) => Err(CliError::invalid_args("--data must be a JSON object").into()),
+        Err(error) => Err(CliError::invalid_args(format!("--data is not JSON: {error}")).into()),
+    }
+}
+
+/// The request as a curl command. The token stays an `$INLINE_TOKEN`
+/// reference so the printed command is safe to share or paste into scripts.
+fn curl_command(url: &str, body: &Map<String, Value>) -> String {
+    let body = serde_json::to_string(body).unwrap_or_else(|_| "{}".to_string());
+    format!(
+        "curl -sS -X POST {} -H \"Authorization: Bearer $INLINE_TOKEN\" -H 'Content-Type: application/json' --data {}",
+        shell_quote(url),
+        shell_quote(&body)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn body_must_be_a_json_object() {
+        assert!(read_body(None).unwrap().is_empty());
+        assert_eq!(
+            read_body(Some(r#"{"peerThreadId":123}"#)).unwrap()["peerThreadId"],
+            123
+        );
+        assert!(read_body(Some("[1]")).is_err());
+        assert!(read_body(Some("{nope")).is_err());
+    }
+
+    #[test]
+    fn curl_command_quotes_body_and_keeps_token_out() {
+        let body = read_body(Some(r#"{"text":"it's"}"#)).unwrap();
+        assert_eq!(
+            curl_command("https://api.inline.chat/v1/sendMessage", &body),
+            r#"curl -sS -X POST https://api.inline.chat/v1/sendMessage -H "Authorization: Bearer $INLINE_TOKEN" -H 'Content-Type: application/json' --data '{"text":"it'\''s"}'"#
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use serde::Serialize;
+
+use super::{CommandContext, Execute};
+use crate::archive::{
+    ScheduleFormat, append_new_messages, archive_path, archived_ids, log_path, render_schedule,
+    schedule_label,
+};
+use crate::history::fetch_history_after;
+use crate::peer::input_chat_peer;
+use crate::validation::validate_positive_id_arg;
+use crate::{ArchiveCommand, ArchiveRunArgs, ArchiveScheduleArgs};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArchiveRunOutput {
+    chat_id: i64,
+    path: String,
+    fetched: usize,
+    appended: usize,
+    archived: usize,
+    /// False when the chat had more new history than one run fetches.
+    complete: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArchiveScheduleOutput {
+    format: &'static str,
+    label: String,
+    command: Vec<String>,
+    contents: String,
+}
+
+impl Execute for ArchiveCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            ArchiveCommand::Run(args) => run_archive(ctx, args).await,
+            ArchiveCommand::Schedule(args) => schedule_archive(ctx, args),
+        }
+    }
+}
+
+async fn run_archive(
+    ctx: &CommandContext,
+    args: ArchiveRunArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let chat_id = validate_positive_id_arg("--chat-id", args.chat_id)?;
+    let path = archive_path(&args.dir, chat_id);
+    let ids = archived_ids(&path)?;
+
+    let realtime = ctx.realtime().await?;
+    let (messages, complete) =
+        fetch_history_after(&realtime, &input_chat_peer(chat_id), ids.last().copied()).await?;
+    let appended = append_new_messages(&path, &messages, &ids)?;
+
+    let output = ArchiveRunOutput {
+        chat_id,
+        path: path.display().to_string(),
+        fetched: messages.len(),
+        appended,
+        archived: ids.len() + appended,
+        complete,
+    };
+    if ctx.json {
+        return ctx.print_json(&output);
+    }
+    if !complete {
+        log::warn!(
+            "chat {chat_id} has more history than one run fetches; older messages were left out."
+        );
+    }
+    println!(
+        "Archived {} new message(s) from chat {} to {} ({} total).",
+        output.appended, output.chat_id, output.path, output.archived
+    );
+    Ok(())
+}
+
+fn schedule_archive(
+    ctx: &CommandContext,
+    args: ArchiveScheduleArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let chat_id = validate_positive_id_arg("--chat-id", args.chat_id)?;
+    // The job runs from launchd/cron with a different working directory.
+    let dir = std::path::absolute(&args.dir)?;
+    let format = args.format.unwrap_or_else(ScheduleFormat::native);
+    let label = schedule_label(chat_id);
+    let command = vec![
+        std::env::current_exe()?.display().to_string(),
+        "archive".to_string(),
+        "run".to_string(),
+        "--chat-id".to_string(),
+        chat_id.to_string(),
+        "--dir".to_string(),
+        dir.display().to_string(),
+    ];
+    let contents = render_schedule(format, &label, &command, args.every, &log_path(&dir));
+
+    if ctx.json {
+        return ctx.print_json(&ArchiveScheduleOutput {
+            format: format.as_str(),
+            label,
+            command,
+            contents,
+        });
+    }
+    print!("{contents}");
+    Ok(())
+}
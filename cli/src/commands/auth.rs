@@ -0,0 +1,152 @@
+use inline_protocol::proto;
+
+use super::{CommandContext, Execute};
+use crate::auth_flow::{
+    AuthLogoutOutput, AuthMeOutput, AuthRefreshOutput, build_auth_logout_output,
+    describe_token_status, handle_login, print_auth_logout, print_auth_refresh, print_auth_user,
+};
+use crate::errors::{CliError, is_auth_expired};
+use crate::{
+    AuthCommand, AuthLoginArgs, auth, confirm_action, current_epoch_seconds, fetch_me,
+    is_interactive_terminal,
+};
+
+impl Execute for AuthCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            AuthCommand::Login(args) => {
+                handle_login(
+                    args,
+                    &ctx.api,
+                    &ctx.auth_store,
+                    &ctx.config.realtime_url,
+                    &ctx.local_db,
+                    ctx.json,
+                )
+                .await?;
+            }
+            AuthCommand::Me => {
+                let realtime = ctx.realtime().await?;
+                let me = fetch_me(&realtime).await?;
+                ctx.local_db.set_current_user(me.clone())?;
+                let token = ctx.auth_store.token_status()?;
+                if ctx.json {
+                    ctx.print_json(&AuthMeOutput { user: me, token })?;
+                } else {
+                    print_auth_user(&me);
+                    if let Some(token) = &token {
+                        println!(
+                            "token: {}",
+                            describe_token_status(token, current_epoch_seconds() as i64)
+                        );
+                    }
+                }
+            }
+            AuthCommand::Refresh => refresh(ctx).await?,
+            AuthCommand::Logout(args) => {
+                let sessions_revoked = if args.all_devices {
+                    let Some(revoked) = logout_all_devices(ctx, args.yes).await? else {
+                        println!("Cancelled.");
+                        return Ok(());
+                    };
+                    Some(revoked)
+                } else {
+                    None
+                };
+                let env_token_present = auth::env_token_present();
+                ctx.auth_store.clear_token()?;
+                ctx.local_db.clear_current_user()?;
+                let output = AuthLogoutOutput {
+                    sessions_revoked,
+                    ..build_auth_logout_output(env_token_present)
+                };
+                if ctx.json {
+                    ctx.print_json(&output)?;
+                } else {
+                    print_auth_logout(&output);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Signs out every other session on the account, then this one on the server.
+/// Returns how many other sessions were revoked, or `None` if cancelled.
+/// Stops at the first failure so the token is still there to run it again.
+async fn logout_all_devices(
+    ctx: &CommandContext,
+    assume_yes: bool,
+) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+    if ctx.json && !assume_yes {
+        return Err(CliError::confirmation_required().into());
+    }
+    let token = ctx.token()?;
+    let realtime = ctx.realtime().await?;
+    let sessions = realtime.call(proto::GetSessionsInput {}).await?.sessions;
+    let others = sessions
+        .iter()
+        .filter(|session| session.active && !session.current)
+        .collect::<Vec<_>>();
+    let prompt = format!(
+        "Sign out {} other session(s) and this one? Every device will have to log in again.",
+        others.len()
+    );
+    if !confirm_action(&prompt, assume_yes)? {
+        return Ok(None);
+    }
+    for session in &others {
+        realtime
+            .call(proto::RevokeSessionInput {
+                session_id: session.id,
+            })
+            .await?;
+    }
+    ctx.api.logout(&token).await?;
+    Ok(Some(others.len()))
+}
+
+/// Checks the current token against the server. A rejected saved token is
+/// replaced through the login flow when a terminal is available; otherwise the
+/// `auth_expired` error (exit code 3) is returned for scripts to act on.
+async fn refresh(ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+    let checked = match ctx.realtime().await {
+        Ok(realtime) => fetch_me(&realtime).await,
+        Err(error) => Err(error),
+    };
+    let me = match checked {
+        Ok(me) => me,
+        Err(error)
+            if is_auth_expired(error.as_ref())
+                && !ctx.json
+                && !auth::env_token_present()
+                && is_interactive_terminal() =>
+        {
+            eprintln!("The saved token was rejected ({error}). Log in again to replace it.");
+            return handle_login(
+                AuthLoginArgs::default(),
+                &ctx.api,
+                &ctx.auth_store,
+                &ctx.config.realtime_url,
+                &ctx.local_db,
+                ctx.json,
+            )
+            .await;
+        }
+        Err(error) => return Err(error),
+    };
+
+    ctx.local_db.set_current_user(me.clone())?;
+    let output = AuthRefreshOutput {
+        valid: true,
+        refreshed: false,
+        user: me,
+        token: ctx.auth_store.token_status()?,
+    };
+    if ctx.json {
+        ctx.print_json(&output)
+    } else {
+        print_auth_refresh(&output);
+        Ok(())
+    }
+}
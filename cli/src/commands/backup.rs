@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use super::{CommandContext, Execute};
+use crate::backup::{
+    BACKUP_FORMAT_VERSION, Backup, BackupChat, BackupManifest, media_manifest, merge_backups,
+    read_archive, write_archive, write_folder,
+};
+use crate::chat_output::chat_display_name;
+use crate::compression::Compression;
+use crate::errors::CliError;
+use crate::history::fetch_history_after;
+use crate::message_output::build_message_list_from_messages;
+use crate::output::{self, format_relative_date};
+use crate::peer::input_chat_peer;
+use crate::validation::validate_positive_id_arg;
+use crate::{
+    BackupCommand, BackupCreateArgs, BackupInspectArgs, BackupRestoreArgs, current_epoch_seconds,
+    input_peer_from_proto_peer,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupOutput {
+    path: String,
+    created_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    incremental_since: Option<i64>,
+    chats: usize,
+    messages: usize,
+    media: usize,
+    /// Chats whose history was cut off by the page limit.
+    incomplete_chat_ids: Vec<i64>,
+}
+
+impl Execute for BackupCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            BackupCommand::Create(args) => create_backup(ctx, args).await,
+            BackupCommand::Inspect(args) => inspect_backup(ctx, args),
+            BackupCommand::Restore(args) => restore_backup(ctx, args),
+        }
+    }
+}
+
+async fn create_backup(
+    ctx: &CommandContext,
+    args: BackupCreateArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = ctx.local_db.load()?;
+    let (high_water, incremental_since) = if args.full {
+        (Default::default(), None)
+    } else {
+        (state.backup_high_water.clone(), state.last_backup_at)
+    };
+    let realtime = ctx.realtime().await?;
+    let chats = ctx.chats().await?;
+    let users_by_id = ctx.users_by_id().await?;
+
+    let mut manifest = BackupManifest {
+        version: BACKUP_FORMAT_VERSION,
+        created_at: current_epoch_seconds() as i64,
+        incremental_since,
+        chats: Vec::new(),
+    };
+    let mut messages = std::collections::BTreeMap::new();
+    let mut media = Vec::new();
+    for chat in &chats.chats {
+        let after_id = high_water.get(&chat.id).copied();
+        let peer = chat
+            .peer_id
+            .as_ref()
+            .and_then(input_peer_from_proto_peer)
+            .unwrap_or_else(|| input_chat_peer(chat.id));
+        let (chat_messages, complete) = match (after_id, chat.last_msg_id) {
+            (Some(after_id), Some(last_id)) if last_id <= after_id => (Vec::new(), true),
+            _ => fetch_history_after(&realtime, &peer, after_id).await?,
+        };
+        let title = chat_display_name(chat, users_by_id);
+        if !complete && !ctx.json {
+            log::warn!(
+                "{title} has more history than one backup fetches; older messages were left out."
+            );
+        }
+        let chat_media = media_manifest(chat.id, &chat_messages)?;
+        manifest.chats.push(BackupChat {
+            chat_id: chat.id,
+            title,
+            after_message_id: after_id,
+            last_message_id: chat_messages.last().map(|message| message.id).or(after_id),
+            messages: chat_messages.len(),
+            media: chat_media.len(),
+            complete,
+        });
+        media.extend(chat_media);
+        if !chat_messages.is_empty() {
+            messages.insert(chat.id, chat_messages);
+        }
+    }
+    let backup = Backup {
+        manifest,
+        chats: chats.clone(),
+        messages,
+        media,
+    };
+    let compression = args
+        .compress
+        .or_else(|| Compression::from_path(&args.output))
+        .unwrap_or(Compression::Zstd);
+    write_archive(&args.output, &backup, compression)?;
+
+    if args.full {
+        state.backup_high_water.clear();
+    }
+    for chat in &backup.manifest.chats {
+        if let Some(last_id) = chat.last_message_id {
+            state.backup_high_water.insert(chat.chat_id, last_id);
+        }
+    }
+    state.last_backup_at = Some(backup.manifest.created_at);
+    ctx.local_db.save(&state)?;
+
+    let output = BackupOutput {
+        path: args.output.display().to_string(),
+        created_at: backup.manifest.created_at,
+        incremental_since: backup.manifest.incremental_since,
+        chats: backup.manifest.chats.len(),
+        messages: backup.message_count(),
+        media: backup.media.len(),
+        incomplete_chat_ids: backup
+            .manifest
+            .chats
+            .iter()
+            .filter(|chat| !chat.complete)
+            .map(|chat| chat.chat_id)
+            .collect(),
+    };
+    if ctx.json {
+        return ctx.print_json(&output);
+    }
+    let scope = match output.incremental_since {
+        Some(since) => format!(
+            "new since the backup {}",
+            format_relative_date(since, output.created_at)
+        ),
+        None => "full history".to_string(),
+    };
+    println!(
+        "Backed up {} message(s) and {} media item(s) from {} chat(s) ({scope}) to {}.",
+        output.messages, output.media, output.chats, output.path
+    );
+    Ok(())
+}
+
+fn inspect_backup(
+    ctx: &CommandContext,
+    args: BackupInspectArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let backup = read_archive(&args.archive)
+        .map_err(|err| CliError::invalid_args(format!("{}: {err}", args.archive.display())))?;
+    let Some(chat_id) = args.chat_id else {
+        if ctx.json {
+            return ctx.print_json(&backup.manifest);
+        }
+        let now = current_epoch_seconds() as i64;
+        println!(
+            "{} backup from {} ({} message(s), {} media item(s))",
+            if backup.manifest.incremental_since.is_some() {
+                "Incremental"
+            } else {
+                "Full"
+            },
+            format_relative_date(backup.manifest.created_at, now),
+            backup.message_count(),
+            backup.media.len()
+        );
+        for chat in backup
+            .manifest
+            .chats
+            .iter()
+            .filter(|chat| chat.messages > 0)
+        {
+            println!(
+                "  {} (chat {}): {} message(s), {} media",
+                chat.title, chat.chat_id, chat.messages, chat.media
+            );
+        }
+        return Ok(());
+    };
+
+    let chat_id = validate_positive_id_arg("--chat-id", chat_id)?;
+    let Some(messages) = backup.messages.get(&chat_id) else {
+        return Err(CliError::invalid_args(format!(
+            "No messages for chat {chat_id} in {}",
+            args.archive.display()
+        ))
+        .into());
+    };
+    // Newest first, like `messages list`.
+    let mut messages = messages.iter().rev().cloned().collect::<Vec<_>>();
+    if let Some(limit) = args.limit {
+        messages.truncate(limit);
+    }
+    let users_by_id = backup
+        .chats
+        .users
+        .iter()
+        .map(|user| (user.id, user.clone()))
+        .collect::<HashMap<_, _>>();
+    let peer_name = backup
+        .manifest
+        .chats
+        .iter()
+        .find(|chat| chat.chat_id == chat_id)
+        .map(|chat| chat.title.clone());
+    let current_user_id = ctx.local_db.load()?.current_user.map(|user| user.id);
+    let output = build_message_list_from_messages(
+        &messages,
+        &users_by_id,
+        current_user_id,
+        None,
+        peer_name,
+        None,
+    );
+    output::print_messages(&output, ctx.json, ctx.json_format)?;
+    Ok(())
+}
+
+fn restore_backup(
+    ctx: &CommandContext,
+    args: BackupRestoreArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut backups = Vec::with_capacity(args.archives.len());
+    for path in &args.archives {
+        backups.push(
+            read_archive(path)
+                .map_err(|err| CliError::invalid_args(format!("{}: {err}", path.display())))?,
+        );
+    }
+    if backups
+        .iter()
+        .all(|backup| backup.manifest.incremental_since.is_some())
+    {
+        log::warn!("every archive is incremental; history from before the oldest one is missing.");
+    }
+    let merged = merge_backups(backups).ok_or("no archives to restore")?;
+    write_folder(&args.output, &merged)?;
+    if ctx.json {
+        return ctx.print_json(&merged.manifest);
+    }
+    println!(
+        "Restored {} message(s) from {} chat(s) in {} archive(s) to {}.",
+        merged.message_count(),
+        merged.messages.len(),
+        args.archives.len(),
+        args.output.display()
+    );
+    Ok(())
+}
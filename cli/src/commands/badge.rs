@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use inline_protocol::proto;
+
+use super::watch::next_updates;
+use super::{CommandContext, Execute};
+use crate::BadgeArgs;
+use crate::badge::{Badge, count_unread, format_badge};
+use crate::output::{self, JsonFormat};
+
+/// Updates come in bursts (a message, then its read receipt), so the count is
+/// refreshed once things go quiet for this long.
+const SETTLE: Duration = Duration::from_millis(500);
+
+impl Execute for BadgeArgs {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.watch {
+            let badge = count_unread(ctx.chats().await?, self.by_space);
+            if ctx.json {
+                return ctx.print_json(&badge);
+            }
+            println!("{}", format_badge(&badge));
+            return Ok(());
+        }
+
+        let realtime = ctx.realtime().await?;
+        let mut events = realtime.subscribe();
+        let mut last: Option<Badge> = None;
+        loop {
+            // Re-read the dialogs rather than applying each update: reads on
+            // other devices, archiving, and mark-unread all move the count.
+            let payload = realtime.call(proto::GetChatsInput {}).await?;
+            let badge = count_unread(&payload, self.by_space);
+            if last.as_ref() != Some(&badge) {
+                if ctx.json {
+                    // One object per line, like `inline watch`.
+                    output::print_json(&badge, JsonFormat::Compact)?;
+                } else {
+                    println!("{}", format_badge(&badge));
+                }
+                last = Some(badge);
+            }
+            next_updates(&mut events).await?;
+            while let Ok(updates) = tokio::time::timeout(SETTLE, next_updates(&mut events)).await {
+                updates?;
+            }
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use inline_protocol::proto;
+use serde::Serialize;
+
+use super::{CommandContext, Execute};
+use crate::bookmarks::{Bookmark, next_bookmark_id, print_bookmark, print_bookmark_list};
+use crate::deep_link::open_url;
+use crate::errors::CliError;
+use crate::validation::validate_message_id_arg;
+use crate::{
+    BookmarksAddArgs, BookmarksCommand, BookmarksOpenArgs, BookmarksRemoveArgs,
+    current_epoch_seconds, fetch_message_by_id,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BookmarkAddOutput<'a> {
+    updated: bool,
+    bookmark: &'a Bookmark,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BookmarkListOutput<'a> {
+    bookmarks: &'a [Bookmark],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BookmarkOpenOutput<'a> {
+    bookmark: &'a Bookmark,
+    url: Option<String>,
+    opened: bool,
+}
+
+impl Execute for BookmarksCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            BookmarksCommand::Add(args) => add(ctx, args).await,
+            BookmarksCommand::List => {
+                let state = ctx.local_db.load()?;
+                if ctx.json {
+                    return ctx.print_json(&BookmarkListOutput {
+                        bookmarks: &state.bookmarks,
+                    });
+                }
+                print_bookmark_list(&state.bookmarks, current_epoch_seconds() as i64);
+                Ok(())
+            }
+            BookmarksCommand::Open(args) => open(ctx, args),
+            BookmarksCommand::Remove(args) => remove(ctx, args),
+        }
+    }
+}
+
+async fn add(
+    ctx: &CommandContext,
+    args: BookmarksAddArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let message_id = validate_message_id_arg("--message-id", args.message_id)?;
+    let peer = ctx
+        .resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+        .await?;
+    let (chat_id, user_id) = match &peer.r#type {
+        Some(proto::input_peer::Type::Chat(chat)) => (Some(chat.chat_id), None),
+        Some(proto::input_peer::Type::User(user)) => (None, Some(user.user_id)),
+        _ => return Err(CliError::missing_peer().into()),
+    };
+    let note = args
+        .note
+        .map(|note| note.trim().to_string())
+        .filter(|note| !note.is_empty());
+
+    let realtime = ctx.realtime().await?;
+    let message = fetch_message_by_id(&realtime, &peer, message_id).await?;
+
+    let mut state = ctx.local_db.load()?;
+    let saved_at = current_epoch_seconds() as i64;
+    let existing = state.bookmarks.iter().position(|bookmark| {
+        bookmark.message_id == message_id && bookmark.matches_peer(chat_id, user_id)
+    });
+    let index = match existing {
+        Some(index) => {
+            let bookmark = &mut state.bookmarks[index];
+            bookmark.message = message;
+            bookmark.saved_at = saved_at;
+            if note.is_some() {
+                bookmark.note = note;
+            }
+            index
+        }
+        None => {
+            let id = next_bookmark_id(&state.bookmarks);
+            state.bookmarks.push(Bookmark {
+                id,
+                chat_id,
+                user_id,
+                message_id,
+                note,
+                saved_at,
+                message,
+            });
+            state.bookmarks.len() - 1
+        }
+    };
+    ctx.local_db.save(&state)?;
+
+    let bookmark = &state.bookmarks[index];
+    if ctx.json {
+        return ctx.print_json(&BookmarkAddOutput {
+            updated: existing.is_some(),
+            bookmark,
+        });
+    }
+    if existing.is_some() {
+        println!("Updated bookmark {}.", bookmark.id);
+    } else {
+        println!(
+            "Bookmarked {} message {} as {}.",
+            bookmark.peer_label(),
+            bookmark.message_id,
+            bookmark.id
+        );
+    }
+    Ok(())
+}
+
+fn open(ctx: &CommandContext, args: BookmarksOpenArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let state = ctx.local_db.load()?;
+    let bookmark = find_bookmark(&state.bookmarks, args.id)?;
+    let url = bookmark.url();
+    let opened = !args.print && url.is_some();
+
+    if !ctx.json {
+        print_bookmark(bookmark, current_epoch_seconds() as i64);
+        if let Some(url) = url.as_deref().filter(|_| !opened) {
+            println!();
+            println!("{url}");
+        }
+    }
+    if let Some(url) = url.as_deref().filter(|_| opened) {
+        open_url(url)?;
+    }
+    if ctx.json {
+        ctx.print_json(&BookmarkOpenOutput {
+            bookmark,
+            url,
+            opened,
+        })?;
+    }
+    Ok(())
+}
+
+fn remove(
+    ctx: &CommandContext,
+    args: BookmarksRemoveArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = ctx.local_db.load()?;
+    find_bookmark(&state.bookmarks, args.id)?;
+    state.bookmarks.retain(|bookmark| bookmark.id != args.id);
+    ctx.local_db.save(&state)?;
+    if ctx.json {
+        return ctx.print_json(&serde_json::json!({ "removed": args.id }));
+    }
+    println!("Removed bookmark {}.", args.id);
+    Ok(())
+}
+
+fn find_bookmark(bookmarks: &[Bookmark], id: u64) -> Result<&Bookmark, CliError> {
+    bookmarks
+        .iter()
+        .find(|bookmark| bookmark.id == id)
+        .ok_or_else(|| {
+            CliError::invalid_args(format!("No bookmark {id} (see `inline bookmarks list`)"))
+        })
+}
@@ -0,0 +1,96 @@
+use inline_protocol::proto;
+
+use super::{CommandContext, Execute};
+use crate::errors::CliError;
+use crate::output::{UserListOutput, user_display_name, user_summary};
+use crate::validation::{
+    validate_optional_positive_id_arg, validate_positive_id_arg, validate_table_only_list_flags,
+};
+use crate::{BotsCommand, filter_bots_payload, filter_users_output, output};
+
+impl Execute for BotsCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            BotsCommand::List(args) => {
+                validate_table_only_list_flags(ctx.json, args.ids, args.id)?;
+                let realtime = ctx.realtime().await?;
+                let mut payload = realtime.call(proto::ListBotsInput {}).await?;
+                if ctx.json {
+                    filter_bots_payload(&mut payload, args.filter.as_deref());
+                    ctx.print_json(&payload)?;
+                } else {
+                    let mut output = UserListOutput {
+                        users: payload.bots.iter().map(user_summary).collect(),
+                    };
+                    filter_users_output(&mut output, args.filter.as_deref());
+                    if args.ids {
+                        for user in &output.users {
+                            println!("{}", user.user.id);
+                        }
+                    } else if args.id {
+                        if output.users.len() != 1 {
+                            return Err(CliError::invalid_args(format!(
+                                "Expected exactly 1 match for --id, got {}",
+                                output.users.len()
+                            ))
+                            .into());
+                        }
+                        if let Some(user) = output.users.first() {
+                            println!("{}", user.user.id);
+                        }
+                    } else {
+                        output::print_users(&output, false, ctx.json_format)?;
+                    }
+                }
+            }
+            BotsCommand::Create(args) => {
+                let add_to_space =
+                    validate_optional_positive_id_arg("--add-to-space", args.add_to_space)?;
+                let name = args.name.trim();
+                if name.is_empty() {
+                    return Err(CliError::invalid_args("Bot name cannot be empty").into());
+                }
+                let username = args.username.trim().trim_start_matches('@');
+                if username.is_empty() {
+                    return Err(CliError::invalid_args("Bot username cannot be empty").into());
+                }
+
+                let realtime = ctx.realtime().await?;
+                let input = proto::CreateBotInput {
+                    name: name.to_string(),
+                    username: username.to_string(),
+                    add_to_space,
+                };
+                let payload = realtime.call(input).await?;
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else if args.quiet {
+                    let bot = payload.bot.as_ref().ok_or_else(|| {
+                        CliError::unexpected_api_response("createBot", "missing bot")
+                    })?;
+                    println!("{}", bot.id);
+                } else if let Some(bot) = payload.bot.as_ref() {
+                    println!("Created bot {} (id {}).", user_display_name(bot), bot.id);
+                    println!(
+                        "To reveal token: inline bots reveal-token --bot-user-id {}",
+                        bot.id
+                    );
+                } else {
+                    println!("Created bot.");
+                }
+            }
+            BotsCommand::RevealToken(args) => {
+                let bot_user_id = validate_positive_id_arg("--bot-user-id", args.bot_user_id)?;
+                let realtime = ctx.realtime().await?;
+                let input = proto::RevealBotTokenInput { bot_user_id };
+                let payload = realtime.call(input).await?;
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else {
+                    println!("{}", payload.token);
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,1058 @@
+use std::collections::HashMap;
+
+use inline_protocol::proto;
+use inline_sdk::RealtimeSession;
+use inline_sdk::api::{CreatePrivateChatResult, ReadMessagesInput};
+use serde::Serialize;
+
+use super::users::search_users;
+use super::{CommandContext, Execute};
+use crate::activity::{ChatActivity, fetch_chat_activity};
+use crate::chat_output::{
+    ChatNotifications, ReadStateOutput, apply_chat_list_filter, apply_chat_list_limits,
+    build_chat_list, chat_notifications, chat_read_states,
+};
+use crate::chat_template;
+use crate::deep_link::inline_url;
+use crate::errors::CliError;
+use crate::output::{
+    build_chat_participants_output, print_chat_details, user_display_name, user_presence,
+};
+use crate::peer::{
+    api_peer_from_input, find_user_by_username, input_chat_peer, input_peer_from_args,
+};
+use crate::roster::{RosterEntry, diff_roster, parse_roster};
+use crate::validation::{
+    validate_optional_message_id_arg, validate_optional_positive_id_arg, validate_positive_id_arg,
+    validate_positive_ids_arg, validate_table_only_list_flags,
+};
+use crate::{
+    ChatsCommand, ChatsCreateArgs, ChatsCreateFromMessageArgs, ChatsSyncParticipantsArgs,
+    confirm_action, current_epoch_seconds, fetch_me, fetch_message_by_id, new_message_ids, output,
+    peer_label_from_input, send_message,
+};
+
+/// `chats create --if-not-exists` when the title is already taken.
+/// `chats list --json`: the `getChats` payload plus each chat's notification
+/// mode, so scripts can skip chats people have muted.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChatListJson {
+    #[serde(flatten)]
+    payload: proto::GetChatsResult,
+    notifications: Vec<ChatNotifications>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    activity: Option<Vec<ChatActivity>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExistingChatOutput<'a> {
+    chat: &'a proto::Chat,
+    existing: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ParticipantSyncOutput {
+    chat_id: i64,
+    applied: bool,
+    add: Vec<ParticipantChange>,
+    remove: Vec<ParticipantChange>,
+    unchanged: usize,
+    /// Participants missing from the roster that are never removed (you).
+    kept: Vec<ParticipantChange>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ParticipantChange {
+    user_id: i64,
+    name: String,
+    /// `pending` until applied, then `done` or `failed`.
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateFromMessageOutput {
+    chat: proto::Chat,
+    source_chat_id: i64,
+    source_message_id: i64,
+    first_message_id: Option<i64>,
+    invited_user_ids: Vec<i64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateDmOutput<'a> {
+    chat_id: Option<i64>,
+    user_id: i64,
+    #[serde(flatten)]
+    payload: &'a CreatePrivateChatResult,
+}
+
+impl Execute for ChatsCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            ChatsCommand::List(args) => {
+                output::set_table_width(args.width);
+                validate_table_only_list_flags(ctx.json, args.ids, args.id)?;
+                let payload = ctx.chats().await?.clone();
+
+                if ctx.json {
+                    let mut payload = apply_chat_list_filter(payload, args.filter.as_deref());
+                    if args.limit.is_some() || args.offset.is_some() {
+                        payload = apply_chat_list_limits(payload, args.limit, args.offset);
+                    }
+                    let activity = if args.activity {
+                        let last_message_dates = payload
+                            .messages
+                            .iter()
+                            .map(|message| ((message.chat_id, message.id), message.date))
+                            .collect::<HashMap<_, _>>();
+                        let last_message_dates = payload
+                            .chats
+                            .iter()
+                            .filter_map(|chat| {
+                                let date = last_message_dates.get(&(chat.id, chat.last_msg_id?))?;
+                                Some((chat.id, *date))
+                            })
+                            .collect();
+                        let chats = payload.chats.iter().collect::<Vec<_>>();
+                        let mut activity = fetch_chat_activity(
+                            &ctx.realtime().await?,
+                            &chats,
+                            &last_message_dates,
+                            current_epoch_seconds() as i64,
+                        )
+                        .await;
+                        Some(
+                            payload
+                                .chats
+                                .iter()
+                                .filter_map(|chat| activity.remove(&chat.id))
+                                .collect(),
+                        )
+                    } else {
+                        None
+                    };
+                    ctx.print_json(&ChatListJson {
+                        notifications: chat_notifications(&payload),
+                        activity,
+                        payload,
+                    })?;
+                } else {
+                    let current_user = ctx.local_db.load()?.current_user;
+                    let mut output = build_chat_list(
+                        payload,
+                        current_user.as_ref(),
+                        args.limit,
+                        args.offset,
+                        args.filter.as_deref(),
+                    )?;
+                    if args.activity {
+                        let chats = output
+                            .items
+                            .iter()
+                            .map(|item| &item.chat)
+                            .collect::<Vec<_>>();
+                        let last_message_dates = output
+                            .items
+                            .iter()
+                            .filter_map(|item| {
+                                Some((item.chat.id, item.last_message.as_ref()?.message.date))
+                            })
+                            .collect();
+                        let mut activity = fetch_chat_activity(
+                            &ctx.realtime().await?,
+                            &chats,
+                            &last_message_dates,
+                            current_epoch_seconds() as i64,
+                        )
+                        .await;
+                        for item in &mut output.items {
+                            item.activity = activity.remove(&item.chat.id).map(|chat| chat.days);
+                        }
+                    }
+                    if args.ids {
+                        for item in &output.items {
+                            println!("{}", item.chat.id);
+                        }
+                    } else if args.id {
+                        if output.items.len() != 1 {
+                            return Err(CliError::invalid_args(format!(
+                                "Expected exactly 1 match for --id, got {}",
+                                output.items.len()
+                            ))
+                            .into());
+                        }
+                        if let Some(item) = output.items.first() {
+                            println!("{}", item.chat.id);
+                        }
+                    } else {
+                        output::print_chat_list(&output, false, ctx.json_format)?;
+                    }
+                }
+            }
+            ChatsCommand::Get(args) => {
+                let peer = ctx
+                    .resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+                    .await?;
+                let realtime = ctx.realtime().await?;
+                let input = proto::GetChatInput {
+                    peer_id: Some(peer),
+                };
+                let payload = realtime.call(input).await?;
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else if let Some(chat) = payload.chat.as_ref() {
+                    print_chat_details(chat, payload.dialog.as_ref());
+                } else {
+                    println!("Chat not found.");
+                }
+            }
+            ChatsCommand::Participants(args) => {
+                let chat_id = validate_positive_id_arg("--chat-id", args.chat_id)?;
+                let realtime = ctx.realtime().await?;
+                let input = proto::GetChatParticipantsInput { chat_id };
+                let mut payload = realtime.call(input).await?;
+                if args.online_only {
+                    keep_online_participants(&mut payload)?;
+                }
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else {
+                    let output =
+                        build_chat_participants_output(payload, current_epoch_seconds() as i64);
+                    output::print_chat_participants(&output, false, ctx.json_format)?;
+                }
+            }
+            ChatsCommand::AddParticipant(args) => {
+                let chat_id = validate_positive_id_arg("--chat-id", args.chat_id)?;
+                let user_id = validate_positive_id_arg("--user-id", args.user_id)?;
+                let realtime = ctx.realtime().await?;
+                let input = proto::AddChatParticipantInput {
+                    chat_id,
+                    user_id: Some(user_id),
+                    group_id: None,
+                };
+                let payload = realtime.call(input).await?;
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else {
+                    println!("Added user {} to chat {}.", user_id, chat_id);
+                }
+            }
+            ChatsCommand::RemoveParticipant(args) => {
+                let chat_id = validate_positive_id_arg("--chat-id", args.chat_id)?;
+                let user_id = validate_positive_id_arg("--user-id", args.user_id)?;
+                let realtime = ctx.realtime().await?;
+                let input = proto::RemoveChatParticipantInput {
+                    chat_id,
+                    user_id: Some(user_id),
+                    group_id: None,
+                };
+                let payload = realtime.call(input).await?;
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else {
+                    println!("Removed user {} from chat {}.", user_id, chat_id);
+                }
+            }
+            ChatsCommand::SyncParticipants(args) => sync_participants(ctx, args).await?,
+            ChatsCommand::Create(args) => create_chat(ctx, args).await?,
+            ChatsCommand::CreateFromMessage(args) => create_from_message(ctx, args).await?,
+            ChatsCommand::CreateDm(args) => {
+                let user_id = match (args.user_id, &args.query, &args.username, &args.email) {
+                    (Some(user_id), ..) => validate_positive_id_arg("--user-id", user_id)?,
+                    (None, Some(query), ..) => resolve_dm_query(ctx, query).await?,
+                    (None, None, Some(username), _) => resolve_dm_username(ctx, username).await?,
+                    (None, None, None, Some(email)) => resolve_dm_email(ctx, email).await?,
+                    (None, None, None, None) => {
+                        return Err(CliError::invalid_args(
+                            "Provide --user-id, --username, --email, or --query",
+                        )
+                        .into());
+                    }
+                };
+                let token = ctx.token()?;
+                let payload = ctx.api.create_private_chat(&token, user_id).await?;
+                let chat_id = payload.chat.get("id").and_then(|value| value.as_i64());
+                if ctx.json {
+                    ctx.print_json(&CreateDmOutput {
+                        chat_id,
+                        user_id,
+                        payload: &payload,
+                    })?;
+                } else {
+                    if args.quiet {
+                        let chat_id = chat_id.ok_or_else(|| {
+                            CliError::unexpected_api_response(
+                                "createPrivateChat",
+                                "missing chat.id",
+                            )
+                        })?;
+                        println!("{chat_id}");
+                    } else if let Some(chat_id) = chat_id {
+                        println!("Created DM chat {} with user {}.", chat_id, user_id);
+                    } else {
+                        println!("Created DM with user {}.", user_id);
+                    }
+                }
+            }
+            ChatsCommand::UpdateVisibility(args) => {
+                let chat_id = validate_positive_id_arg("--chat-id", args.chat_id)?;
+                if args.public == args.private {
+                    return Err(CliError::invalid_args("Provide --public or --private").into());
+                }
+                if args.public && !args.participants.is_empty() {
+                    return Err(CliError::invalid_args(
+                        "Public chats cannot include explicit participants",
+                    )
+                    .into());
+                }
+                if args.private && args.participants.is_empty() {
+                    return Err(CliError::invalid_args(
+                        "Private chats require at least one participant.",
+                    )
+                    .into());
+                }
+                validate_positive_ids_arg("--participant", &args.participants)?;
+
+                let realtime = ctx.realtime().await?;
+                let participants = args
+                    .participants
+                    .iter()
+                    .map(|user_id| proto::InputChatParticipant {
+                        user_id: Some(*user_id),
+                        group_id: None,
+                    })
+                    .collect();
+                let input = proto::UpdateChatVisibilityInput {
+                    chat_id,
+                    is_public: args.public,
+                    participants,
+                };
+                let payload = realtime.call(input).await?;
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else {
+                    let label = if args.public { "public" } else { "private" };
+                    if let Some(chat) = payload.chat.as_ref() {
+                        println!("Updated chat {} to {}.", chat.id, label);
+                    } else {
+                        println!("Updated chat {} to {}.", chat_id, label);
+                    }
+                }
+            }
+            ChatsCommand::Rename(args) => {
+                let chat_id = validate_positive_id_arg("--chat-id", args.chat_id)?;
+                let title = args.title.trim();
+                if title.is_empty() {
+                    return Err(CliError::invalid_args("Chat/thread title cannot be empty").into());
+                }
+                let emoji = args.emoji.and_then(|value| {
+                    let trimmed = value.trim();
+                    if trimmed.is_empty() {
+                        None
+                    } else {
+                        Some(trimmed.to_string())
+                    }
+                });
+
+                let realtime = ctx.realtime().await?;
+                let input = proto::UpdateChatInfoInput {
+                    chat_id,
+                    title: Some(title.to_string()),
+                    emoji,
+                };
+                let payload = realtime.call(input).await?;
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else if let Some(chat) = payload.chat.as_ref() {
+                    println!("Renamed chat {} to \"{}\".", chat.id, chat.title);
+                } else {
+                    println!("Renamed chat {}.", chat_id);
+                }
+            }
+            ChatsCommand::Join(args) => {
+                let chat_id = validate_positive_id_arg("--chat-id", args.chat_id)?;
+                let realtime = ctx.realtime().await?;
+                let input = proto::ShowInChatListInput {
+                    peer_id: Some(input_chat_peer(chat_id)),
+                };
+                let payload = realtime.call(input).await?;
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else {
+                    let title = payload
+                        .chat
+                        .as_ref()
+                        .map(|chat| chat.title.clone())
+                        .filter(|title| !title.is_empty())
+                        .unwrap_or_else(|| format!("chat {chat_id}"));
+                    println!("Joined {title}.");
+                }
+            }
+            ChatsCommand::MarkUnread(args) => {
+                let peer = ctx
+                    .resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+                    .await?;
+                let realtime = ctx.realtime().await?;
+                let input = proto::MarkAsUnreadInput {
+                    peer_id: Some(peer),
+                };
+                let payload = realtime.call(input).await?;
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else {
+                    println!("Marked as unread (updates: {}).", payload.updates.len());
+                }
+            }
+            ChatsCommand::MarkRead(args) => {
+                let max_id = validate_optional_message_id_arg("--max-id", args.max_id)?;
+                let peer = ctx
+                    .resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+                    .await?;
+                let label = peer_label_from_input(&peer);
+                let token = ctx.token()?;
+                let mut input = ReadMessagesInput::new(
+                    api_peer_from_input(&peer).ok_or_else(CliError::missing_peer)?,
+                );
+                if let Some(max_id) = max_id {
+                    input = input.with_max_id(max_id);
+                }
+                let payload = ctx.api.read_messages(&token, input).await?;
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else if let Some(max_id) = max_id {
+                    println!("Marked {label} as read (max id {max_id}).");
+                } else {
+                    println!("Marked {label} as read.");
+                }
+            }
+            ChatsCommand::ReadState(args) => {
+                let peer = if args.all {
+                    None
+                } else {
+                    Some(
+                        ctx.resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+                            .await?,
+                    )
+                };
+                let mut dialogs = chat_read_states(ctx.chats().await?);
+                if let Some(peer) = peer {
+                    dialogs.retain(|state| match &peer.r#type {
+                        Some(proto::input_peer::Type::Chat(chat)) => state.chat_id == chat.chat_id,
+                        Some(proto::input_peer::Type::User(user)) => {
+                            state.peer.as_ref().is_some_and(|peer| {
+                                peer.peer_type == "user" && peer.id == user.user_id
+                            })
+                        }
+                        _ => false,
+                    });
+                    if dialogs.is_empty() {
+                        return Err(CliError::not_found_in_chat_list(&peer_label_from_input(
+                            &peer,
+                        ))
+                        .into());
+                    }
+                }
+                let output = ReadStateOutput { dialogs };
+                if ctx.json {
+                    ctx.print_json(&output)?;
+                } else {
+                    output::print_read_states(&output);
+                }
+            }
+            ChatsCommand::Delete(args) => {
+                let chat_id = validate_positive_id_arg("--chat-id", args.chat_id)?;
+                let prompt = format!("Delete chat {}? This cannot be undone.", chat_id);
+                if ctx.json && !args.yes {
+                    return Err(CliError::confirmation_required().into());
+                }
+                // Fail on missing credentials before prompting.
+                ctx.token()?;
+                if !confirm_action(&prompt, args.yes)? {
+                    println!("Cancelled.");
+                    return Ok(());
+                }
+                let realtime = ctx.realtime().await?;
+                let peer = input_peer_from_args(Some(chat_id), None)?;
+                let input = proto::DeleteChatInput {
+                    peer_id: Some(peer),
+                };
+                let payload = realtime.call(input).await?;
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else {
+                    println!("Deleted chat {}.", chat_id);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Picks the DM target for `create-dm --query`: the only result, or the one
+/// whose username matches the query exactly.
+async fn resolve_dm_query(
+    ctx: &CommandContext,
+    query: &str,
+) -> Result<i64, Box<dyn std::error::Error>> {
+    let users = search_users(ctx, query, 10).await?;
+    let wanted = query.trim().trim_start_matches('@');
+    let exact = users.iter().find(|user| {
+        user.username
+            .as_deref()
+            .is_some_and(|username| username.eq_ignore_ascii_case(wanted))
+    });
+    match (users.as_slice(), exact) {
+        (_, Some(user)) | ([user], None) => Ok(user.id),
+        ([], None) => {
+            Err(CliError::invalid_args(format!("No users match \"{}\"", query.trim())).into())
+        }
+        (candidates, None) => Err(ambiguous_user(query.trim(), candidates).into()),
+    }
+}
+
+/// Exact username: users in the chat list first, then a server-side search.
+async fn resolve_dm_username(
+    ctx: &CommandContext,
+    username: &str,
+) -> Result<i64, Box<dyn std::error::Error>> {
+    let wanted = username.trim().trim_start_matches('@');
+    if wanted.is_empty() {
+        return Err(CliError::invalid_args("--username must not be empty").into());
+    }
+    if let Some(user) = find_user_by_username(&ctx.chats().await?.users, wanted) {
+        return Ok(user.id);
+    }
+    let users = search_users(ctx, wanted, 10).await?;
+    if let Some(user) = find_user_by_username(&users, wanted) {
+        return Ok(user.id);
+    }
+    match users.as_slice() {
+        [] => Err(CliError::not_found_user(&format!("@{wanted}")).into()),
+        candidates => Err(ambiguous_user(&format!("@{wanted}"), candidates).into()),
+    }
+}
+
+/// Exact email. Search results often leave emails out, so a single search
+/// hit without one is taken as the match; one with a different email is not.
+async fn resolve_dm_email(
+    ctx: &CommandContext,
+    email: &str,
+) -> Result<i64, Box<dyn std::error::Error>> {
+    let wanted = email.trim();
+    if !wanted.contains('@') || wanted.starts_with('@') {
+        return Err(CliError::invalid_args(format!("Invalid --email: {email}")).into());
+    }
+    let has_email = |user: &&proto::User| {
+        user.email
+            .as_deref()
+            .is_some_and(|candidate| candidate.trim().eq_ignore_ascii_case(wanted))
+    };
+    if let Some(user) = ctx.chats().await?.users.iter().find(has_email) {
+        return Ok(user.id);
+    }
+    let users = search_users(ctx, wanted, 10).await?;
+    if let Some(user) = users.iter().find(has_email) {
+        return Ok(user.id);
+    }
+    match users.as_slice() {
+        [user] if user.email.is_none() => Ok(user.id),
+        [] | [_] => Err(CliError::not_found_user(wanted).into()),
+        candidates => Err(ambiguous_user(wanted, candidates).into()),
+    }
+}
+
+fn ambiguous_user(target: &str, candidates: &[proto::User]) -> CliError {
+    let candidates = candidates
+        .iter()
+        .map(|user| {
+            let label = match user.username.as_deref() {
+                Some(username) => format!("{} @{username}", user_display_name(user)),
+                None => user_display_name(user),
+            };
+            (label, user.id)
+        })
+        .collect::<Vec<_>>();
+    CliError::ambiguous_user(target, &candidates)
+}
+
+async fn sync_participants(
+    ctx: &CommandContext,
+    args: ChatsSyncParticipantsArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let chat_id = validate_positive_id_arg("--chat-id", args.chat_id)?;
+    let contents = std::fs::read_to_string(&args.file).map_err(|err| {
+        CliError::invalid_args(format!("Couldn't read {}: {err}", args.file.display()))
+    })?;
+    let roster = parse_roster(&contents)
+        .map_err(|err| CliError::invalid_args(format!("{}: {err}", args.file.display())))?;
+
+    let realtime = ctx.realtime().await?;
+    let participants = realtime
+        .call(proto::GetChatParticipantsInput { chat_id })
+        .await?;
+    let me = fetch_me(&realtime).await?;
+    let mut users = participants.users.clone();
+    users.extend(ctx.users_by_id().await?.values().cloned());
+    users.push(me.clone());
+
+    let mut desired = Vec::with_capacity(roster.len());
+    let mut unknown = Vec::new();
+    for entry in &roster {
+        match entry {
+            RosterEntry::UserId(user_id) => desired.push(*user_id),
+            RosterEntry::Username(username) => match find_user_by_username(&users, username) {
+                Some(user) => desired.push(user.id),
+                None => unknown.push(format!("@{username}")),
+            },
+        }
+    }
+    if !unknown.is_empty() {
+        return Err(CliError::invalid_args(format!(
+            "Unknown username(s) {}; use user ids for people you don't share a chat with",
+            unknown.join(", ")
+        ))
+        .into());
+    }
+    let current = participants
+        .participants
+        .iter()
+        .map(|participant| participant.user_id)
+        .collect::<Vec<_>>();
+    let diff = diff_roster(&current, &desired, &[me.id]);
+
+    let names = users
+        .iter()
+        .map(|user| (user.id, user_display_name(user)))
+        .collect::<HashMap<_, _>>();
+    let change = |user_id: i64| ParticipantChange {
+        user_id,
+        name: names
+            .get(&user_id)
+            .cloned()
+            .unwrap_or_else(|| format!("user {user_id}")),
+        status: "pending",
+        error: None,
+    };
+    let mut output = ParticipantSyncOutput {
+        chat_id,
+        applied: false,
+        add: diff.add.iter().copied().map(change).collect(),
+        remove: diff.remove.iter().copied().map(change).collect(),
+        unchanged: diff.unchanged.len(),
+        kept: diff.kept.iter().copied().map(change).collect(),
+    };
+
+    if !ctx.json {
+        for user in &output.add {
+            println!("+ {} ({})", user.name, user.user_id);
+        }
+        for user in &output.remove {
+            println!("- {} ({})", user.name, user.user_id);
+        }
+        for user in &output.kept {
+            println!(
+                "  {} ({}) is not on the roster but stays in the chat",
+                user.name, user.user_id
+            );
+        }
+        println!(
+            "{} to add, {} to remove, {} unchanged.",
+            output.add.len(),
+            output.remove.len(),
+            output.unchanged
+        );
+    }
+    let has_changes = !output.add.is_empty() || !output.remove.is_empty();
+    if !args.apply || !has_changes {
+        if ctx.json {
+            ctx.print_json(&output)?;
+        } else if has_changes {
+            println!("Run again with --apply to make these changes.");
+        }
+        return Ok(());
+    }
+
+    if ctx.json && !args.yes {
+        return Err(CliError::confirmation_required().into());
+    }
+    let prompt = format!(
+        "Add {} and remove {} participant(s) in chat {chat_id}?",
+        output.add.len(),
+        output.remove.len()
+    );
+    if !confirm_action(&prompt, args.yes)? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+    for user in &mut output.add {
+        let result = realtime
+            .call(proto::AddChatParticipantInput {
+                chat_id,
+                user_id: Some(user.user_id),
+                group_id: None,
+            })
+            .await;
+        record_participant_change(user, result.map(|_| ()), ctx.json, "add");
+    }
+    for user in &mut output.remove {
+        let result = realtime
+            .call(proto::RemoveChatParticipantInput {
+                chat_id,
+                user_id: Some(user.user_id),
+                group_id: None,
+            })
+            .await;
+        record_participant_change(user, result.map(|_| ()), ctx.json, "remove");
+    }
+    output.applied = true;
+    if ctx.json {
+        return ctx.print_json(&output);
+    }
+    let failed = output
+        .add
+        .iter()
+        .chain(&output.remove)
+        .filter(|user| user.status == "failed")
+        .count();
+    if failed == 0 {
+        println!("Chat {chat_id} now matches the roster.");
+    } else {
+        println!("Done with {failed} failure(s).");
+    }
+    Ok(())
+}
+
+/// Drops participants who aren't online. Fails when the server sends no
+/// presence at all, rather than reporting that nobody is around.
+fn keep_online_participants(
+    payload: &mut proto::GetChatParticipantsResult,
+) -> Result<(), CliError> {
+    let presence = payload
+        .users
+        .iter()
+        .filter_map(|user| user_presence(user).map(|presence| (user.id, presence)))
+        .collect::<HashMap<_, _>>();
+    if presence.is_empty() && !payload.participants.is_empty() {
+        return Err(CliError::presence_unavailable());
+    }
+    payload
+        .participants
+        .retain(|participant| presence.get(&participant.user_id) == Some(&"online"));
+    Ok(())
+}
+
+async fn create_chat(
+    ctx: &CommandContext,
+    args: ChatsCreateArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let space_id = validate_optional_positive_id_arg("--space-id", args.space_id)?;
+    if args.public && !args.participants.is_empty() {
+        return Err(
+            CliError::invalid_args("Public chats cannot include explicit participants").into(),
+        );
+    }
+    if space_id.is_none() {
+        if args.public {
+            return Err(
+                CliError::invalid_args("Public home threads are not supported yet.").into(),
+            );
+        }
+        if args.participants.is_empty() {
+            return Err(CliError::invalid_args(
+                "Provide at least one --participant for a home thread.",
+            )
+            .into());
+        }
+    }
+    validate_positive_ids_arg("--participant", &args.participants)?;
+    let realtime = ctx.realtime().await?;
+    let (title, emoji) = match &args.title_template {
+        Some(template) => {
+            render_chat_templates(&realtime, template, args.emoji.as_deref(), &args.vars).await?
+        }
+        None => (args.title.clone().unwrap_or_default(), args.emoji.clone()),
+    };
+    let title = title.trim();
+    if title.is_empty() {
+        return Err(CliError::invalid_args("Chat title cannot be empty").into());
+    }
+
+    if args.if_not_exists
+        && let Some(chat) = ctx
+            .chats()
+            .await?
+            .chats
+            .iter()
+            .find(|chat| chat.space_id == space_id && chat.title.trim() == title)
+    {
+        if ctx.json {
+            ctx.print_json(&ExistingChatOutput {
+                chat,
+                existing: true,
+            })?;
+        } else if args.quiet {
+            println!("{}", chat.id);
+        } else {
+            println!("Chat {} already exists.", chat.id);
+        }
+        return Ok(());
+    }
+
+    let participants = args
+        .participants
+        .iter()
+        .map(|user_id| proto::InputChatParticipant {
+            user_id: Some(*user_id),
+            group_id: None,
+        })
+        .collect();
+    let description = args.description.and_then(|value| {
+        let trimmed = value.trim();
+        if trimmed.is_empty() {
+            None
+        } else {
+            Some(trimmed.to_string())
+        }
+    });
+    let emoji = emoji.and_then(|value| {
+        let trimmed = value.trim();
+        if trimmed.is_empty() {
+            None
+        } else {
+            Some(trimmed.to_string())
+        }
+    });
+    let input = proto::CreateChatInput {
+        title: Some(title.to_string()),
+        space_id,
+        description,
+        emoji,
+        is_public: args.public,
+        participants,
+        reserved_chat_id: None,
+    };
+    let payload = realtime.call(input).await?;
+    if ctx.json {
+        ctx.print_json(&payload)?;
+    } else if let Some(chat) = payload.chat.as_ref() {
+        if args.quiet {
+            println!("{}", chat.id);
+        } else {
+            println!("Created chat {}.", chat.id);
+        }
+    } else if args.quiet {
+        return Err(CliError::unexpected_api_response("createChat", "missing chat").into());
+    } else {
+        println!("Created chat.");
+    }
+    Ok(())
+}
+
+/// Renders `--title-template`, and `--emoji` the same way. `{user}` costs a
+/// getMe, so it's only fetched when used and not given with `--var`.
+async fn render_chat_templates(
+    realtime: &RealtimeSession,
+    template: &str,
+    emoji: Option<&str>,
+    vars: &[(String, String)],
+) -> Result<(String, Option<String>), Box<dyn std::error::Error>> {
+    let invalid = |flag: &str, err: String| CliError::invalid_args(format!("{flag}: {err}"));
+    let mut used =
+        chat_template::placeholders(template).map_err(|err| invalid("--title-template", err))?;
+    if let Some(emoji) = emoji {
+        used.extend(chat_template::placeholders(emoji).map_err(|err| invalid("--emoji", err))?);
+    }
+    let mut values = vars.to_vec();
+    let given = |name: &str| values.iter().any(|(known, _)| known == name);
+    let needs_date = used.contains(&"date") && !given("date");
+    let needs_user = used.contains(&"user") && !given("user");
+    if needs_date {
+        values.push((
+            "date".to_string(),
+            chat_template::date_value(chrono::Utc::now().date_naive()),
+        ));
+    }
+    if needs_user {
+        let me = fetch_me(realtime).await?;
+        let user = me
+            .username
+            .clone()
+            .filter(|username| !username.is_empty())
+            .unwrap_or_else(|| user_display_name(&me));
+        values.push(("user".to_string(), user));
+    }
+    let lookup = |name: &str| {
+        values
+            .iter()
+            .find(|(known, _)| known == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let title =
+        chat_template::render(template, lookup).map_err(|err| invalid("--title-template", err))?;
+    let emoji = emoji
+        .map(|emoji| chat_template::render(emoji, lookup).map_err(|err| invalid("--emoji", err)))
+        .transpose()?;
+    Ok((title, emoji))
+}
+
+async fn create_from_message(
+    ctx: &CommandContext,
+    args: ChatsCreateFromMessageArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let chat_id = validate_positive_id_arg("--chat-id", args.chat_id)?;
+    let message_id = validate_positive_id_arg("--message-id", args.message_id)?;
+    let title = args.title.trim();
+    if title.is_empty() {
+        return Err(CliError::invalid_args("Chat title cannot be empty").into());
+    }
+    let emoji = args
+        .emoji
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+
+    let realtime = ctx.realtime().await?;
+    let source_peer = input_peer_from_args(Some(chat_id), None)?;
+    let source = realtime
+        .call(proto::GetChatInput {
+            peer_id: Some(source_peer.clone()),
+        })
+        .await?
+        .chat
+        .ok_or_else(|| CliError::unexpected_api_response("getChat", "missing chat"))?;
+    if source.space_id.is_none() && !args.invite_participants {
+        return Err(CliError::invalid_args(
+            "Chat is not in a space; pass --invite-participants to start a home thread with the same people.",
+        )
+        .into());
+    }
+    let message = fetch_message_by_id(&realtime, &source_peer, message_id).await?;
+
+    let mut users = ctx.users_by_id().await?.clone();
+    let mut invited_user_ids = Vec::new();
+    if args.invite_participants {
+        let participants = realtime
+            .call(proto::GetChatParticipantsInput { chat_id })
+            .await?;
+        let me = fetch_me(&realtime).await?;
+        invited_user_ids = participants
+            .participants
+            .iter()
+            .map(|participant| participant.user_id)
+            .filter(|user_id| *user_id != me.id)
+            .collect();
+        for user in participants.users {
+            users.entry(user.id).or_insert(user);
+        }
+    }
+    let sender = users
+        .get(&message.from_id)
+        .map(user_display_name)
+        .unwrap_or_else(|| format!("user {}", message.from_id));
+
+    let created = realtime
+        .call(proto::CreateChatInput {
+            title: Some(title.to_string()),
+            space_id: source.space_id,
+            description: None,
+            emoji,
+            is_public: false,
+            participants: invited_user_ids
+                .iter()
+                .map(|user_id| proto::InputChatParticipant {
+                    user_id: Some(*user_id),
+                    group_id: None,
+                })
+                .collect(),
+            reserved_chat_id: None,
+        })
+        .await?
+        .chat
+        .ok_or_else(|| CliError::unexpected_api_response("createChat", "missing chat"))?;
+
+    let quote = quote_message(
+        message.message.as_deref().unwrap_or_default(),
+        &sender,
+        &source.title,
+        inline_url("chat", chat_id, Some(message_id)).as_deref(),
+    );
+    let sent = send_message(
+        &realtime,
+        &input_peer_from_args(Some(created.id), None)?,
+        Some(quote),
+        None,
+        true,
+        None,
+        None,
+        Some(true),
+    )
+    .await?;
+    let first_message_id = new_message_ids(&sent.updates).first().copied();
+
+    if ctx.json {
+        return ctx.print_json(&CreateFromMessageOutput {
+            chat: created,
+            source_chat_id: chat_id,
+            source_message_id: message_id,
+            first_message_id,
+            invited_user_ids,
+        });
+    }
+    if args.quiet {
+        println!("{}", created.id);
+    } else if invited_user_ids.is_empty() {
+        println!("Created chat {} from message {message_id}.", created.id);
+    } else {
+        println!(
+            "Created chat {} from message {message_id} with {} participant(s).",
+            created.id,
+            invited_user_ids.len()
+        );
+    }
+    Ok(())
+}
+
+/// First message of a thread started from another message: the original as a
+/// Markdown quote, then who said it and a link back.
+fn quote_message(text: &str, sender: &str, chat_title: &str, url: Option<&str>) -> String {
+    let mut quote = if text.trim().is_empty() {
+        "> (no text)".to_string()
+    } else {
+        text.trim()
+            .lines()
+            .map(|line| format!("> {line}").trim_end().to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    quote.push_str(&format!("\n\n— {sender} in {chat_title}"));
+    if let Some(url) = url {
+        quote.push_str(&format!(" · [original message]({url})"));
+    }
+    quote
+}
+
+fn record_participant_change<E: std::fmt::Display>(
+    user: &mut ParticipantChange,
+    result: Result<(), E>,
+    json: bool,
+    action: &str,
+) {
+    match result {
+        Ok(()) => user.status = "done",
+        Err(err) => {
+            if !json {
+                eprintln!("Failed to {action} {} ({}): {err}", user.name, user.user_id);
+            }
+            user.status = "failed";
+            user.error = Some(err.to_string());
+        }
+    }
+}
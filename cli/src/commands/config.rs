@@ -0,0 +1,214 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::{CommandContext, Execute};
+use crate::errors::CliError;
+use crate::peer::{PeerTarget, validate_alias_name};
+use crate::{AliasCommand, ConfigCommand, config, config_file};
+
+impl Execute for ConfigCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            ConfigCommand::Get(args) => {
+                let value = match ctx.config.setting(&args.key) {
+                    Some(setting) => serde_json::to_value(setting)?,
+                    None => match ctx.config.file.get(&args.key) {
+                        Some(value) => serde_json::json!({
+                            "key": args.key,
+                            "value": value,
+                            "source": "file",
+                        }),
+                        None => {
+                            return Err(CliError::config_key_not_set(&args.key).into());
+                        }
+                    },
+                };
+                if ctx.json {
+                    ctx.print_json(&value)?;
+                } else if let Some(text) = value["value"].as_str() {
+                    println!("{text}");
+                } else if !value["value"].is_null() {
+                    println!("{}", value["value"]);
+                }
+            }
+            ConfigCommand::Set(args) => {
+                if args.key == "profile" && !args.value.is_empty() {
+                    let profiles = ctx.config.file.table_names("profiles");
+                    if !profiles.contains(&args.value) {
+                        return Err(config_file::ConfigFileError::UnknownProfile(args.value).into());
+                    }
+                }
+                if let Some(spec) = config::config_setting_spec(&args.key) {
+                    config::validate_setting_value(spec, &args.value)?;
+                }
+                let mut file = ctx.config.file.clone();
+                let value = if config::config_setting_spec(&args.key).is_some() {
+                    config_file::ConfigValue::String(args.value)
+                } else {
+                    config_file::ConfigValue::from_input(&args.value)
+                };
+                file.set(&args.key, value.clone())?;
+                file.save(&ctx.config.config_path)?;
+                if ctx.json {
+                    let payload = serde_json::json!({ "key": args.key, "value": value });
+                    ctx.print_json(&payload)?;
+                } else {
+                    println!("Set {} in {}.", args.key, ctx.config.config_path.display());
+                }
+            }
+            ConfigCommand::Unset(args) => {
+                let mut file = ctx.config.file.clone();
+                let removed = file.remove(&args.key);
+                if removed.is_none() {
+                    return Err(CliError::config_key_not_set(&args.key).into());
+                }
+                file.save(&ctx.config.config_path)?;
+                if ctx.json {
+                    let payload = serde_json::json!({ "key": args.key, "removed": removed });
+                    ctx.print_json(&payload)?;
+                } else {
+                    println!(
+                        "Removed {} from {}.",
+                        args.key,
+                        ctx.config.config_path.display()
+                    );
+                }
+            }
+            ConfigCommand::List => {
+                let payload = ConfigListOutput {
+                    config_path: ctx.config.config_path.display().to_string(),
+                    profile: ctx.config.profile.clone(),
+                    settings: ctx.config.settings.clone(),
+                    aliases: ctx.config.aliases.clone(),
+                    file: ctx
+                        .config
+                        .file
+                        .entries()
+                        .map(|(key, value)| (key.clone(), value.clone()))
+                        .collect(),
+                };
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else {
+                    println!("Config file: {}", payload.config_path);
+                    println!();
+                    for setting in &payload.settings {
+                        println!(
+                            "{} = {} ({})",
+                            setting.key,
+                            setting.value.as_deref().unwrap_or("-"),
+                            setting.source
+                        );
+                    }
+                    let extra = payload
+                        .file
+                        .iter()
+                        .filter(|(key, _)| ctx.config.setting(key).is_none())
+                        .collect::<Vec<_>>();
+                    if !extra.is_empty() {
+                        println!();
+                        for (key, value) in extra {
+                            println!("{key} = {value}");
+                        }
+                    }
+                }
+            }
+            ConfigCommand::Path => {
+                if ctx.json {
+                    let payload = serde_json::json!({
+                        "configPath": ctx.config.config_path.display().to_string(),
+                        "exists": ctx.config.config_path.exists(),
+                    });
+                    ctx.print_json(&payload)?;
+                } else {
+                    println!("{}", ctx.config.config_path.display());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Execute for AliasCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            AliasCommand::Add(args) => {
+                let name = validate_alias_name(&args.name)?.to_string();
+                let peer = args.peer.trim().to_string();
+                if PeerTarget::parse(&peer)?.is_none() {
+                    return Err(CliError::invalid_args(format!(
+                        "Invalid alias target `{peer}`: expected chat:ID, user:ID, or @username."
+                    ))
+                    .into());
+                }
+                let mut file = ctx.config.file.clone();
+                file.set(
+                    &format!("aliases.{name}"),
+                    config_file::ConfigValue::String(peer.clone()),
+                )?;
+                file.save(&ctx.config.config_path)?;
+                if ctx.json {
+                    ctx.print_json(&AliasOutput { name, peer })?;
+                } else {
+                    println!("Alias {name} -> {peer}.");
+                }
+            }
+            AliasCommand::List => {
+                let aliases = ctx
+                    .config
+                    .aliases
+                    .iter()
+                    .map(|(name, peer)| AliasOutput {
+                        name: name.clone(),
+                        peer: peer.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                if ctx.json {
+                    ctx.print_json(&aliases)?;
+                } else if aliases.is_empty() {
+                    println!("No aliases. Add one with `inline alias add NAME chat:ID`.");
+                } else {
+                    let width = aliases
+                        .iter()
+                        .map(|alias| alias.name.len())
+                        .max()
+                        .unwrap_or(0);
+                    for alias in &aliases {
+                        println!("{:width$}  {}", alias.name, alias.peer);
+                    }
+                }
+            }
+            AliasCommand::Remove(args) => {
+                let name = validate_alias_name(&args.name)?;
+                let mut file = ctx.config.file.clone();
+                if file.remove(&format!("aliases.{name}")).is_none() {
+                    return Err(CliError::unknown_peer_alias(name).into());
+                }
+                file.save(&ctx.config.config_path)?;
+                if ctx.json {
+                    ctx.print_json(&serde_json::json!({ "removed": name }))?;
+                } else {
+                    println!("Removed alias {name}.");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AliasOutput {
+    name: String,
+    peer: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfigListOutput {
+    config_path: String,
+    profile: Option<String>,
+    settings: Vec<config::ConfigSetting>,
+    aliases: BTreeMap<String, String>,
+    file: BTreeMap<String, config_file::ConfigValue>,
+}
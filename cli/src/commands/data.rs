@@ -0,0 +1,146 @@
+//! Per-invocation cache of server state.
+//!
+//! A single command often needs the same data more than once: `--to @sam`
+//! resolves the user from `getChats`, and rendering a message list needs the
+//! same payload again for sender and chat names. `DataContext` keeps one
+//! realtime session and the first `getChats` result for the rest of the
+//! invocation. Commands that change chats don't refresh it, so read chat state
+//! before mutating if you need both.
+
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::future::Future;
+
+use inline_protocol::proto;
+use inline_sdk::RealtimeSession;
+
+use crate::identity::realtime_client_builder;
+
+#[derive(Default)]
+pub(crate) struct DataContext {
+    realtime: tokio::sync::OnceCell<RealtimeSession>,
+    chats: tokio::sync::OnceCell<proto::GetChatsResult>,
+    users_by_id: OnceCell<HashMap<i64, proto::User>>,
+    chats_by_id: OnceCell<HashMap<i64, proto::Chat>>,
+}
+
+impl DataContext {
+    /// Returns the shared session, connecting on first use. `token` and
+    /// `unsupported` (methods already known to be missing on the server) are
+    /// only called when a connection is needed. The connect future is boxed so
+    /// the many call sites don't each carry it inline (debug builds overflow
+    /// the main thread's stack otherwise).
+    pub(crate) async fn realtime(
+        &self,
+        url: &str,
+        token: impl FnOnce() -> Result<String, Box<dyn std::error::Error>>,
+        unsupported: impl FnOnce() -> Vec<proto::Method>,
+    ) -> Result<RealtimeSession, Box<dyn std::error::Error>> {
+        let session = self
+            .realtime
+            .get_or_try_init(|| async {
+                let token = token()?;
+                let connect = realtime_client_builder(url, &token)
+                    .unsupported_methods(unsupported())
+                    .connect_session();
+                Ok::<_, Box<dyn std::error::Error>>(Box::pin(connect).await?)
+            })
+            .await?;
+        Ok(session.clone())
+    }
+
+    /// Methods the server explicitly reported as not implemented, or nothing
+    /// if the invocation never connected.
+    pub(crate) fn confirmed_unsupported_methods(&self) -> Vec<proto::Method> {
+        self.realtime
+            .get()
+            .map(RealtimeSession::confirmed_unsupported_methods)
+            .unwrap_or_default()
+    }
+
+    /// Returns the memoized `getChats` payload. `realtime` is only awaited when
+    /// the payload hasn't been fetched yet.
+    pub(crate) async fn chats(
+        &self,
+        realtime: impl Future<Output = Result<RealtimeSession, Box<dyn std::error::Error>>>,
+    ) -> Result<&proto::GetChatsResult, Box<dyn std::error::Error>> {
+        self.chats
+            .get_or_try_init(|| async {
+                Ok(Box::pin(realtime)
+                    .await?
+                    .call(proto::GetChatsInput {})
+                    .await?)
+            })
+            .await
+    }
+
+    pub(crate) fn users_by_id(&self, chats: &proto::GetChatsResult) -> &HashMap<i64, proto::User> {
+        self.users_by_id.get_or_init(|| {
+            chats
+                .users
+                .iter()
+                .map(|user| (user.id, user.clone()))
+                .collect()
+        })
+    }
+
+    pub(crate) fn chats_by_id(&self, chats: &proto::GetChatsResult) -> &HashMap<i64, proto::Chat> {
+        self.chats_by_id.get_or_init(|| {
+            chats
+                .chats
+                .iter()
+                .map(|chat| (chat.id, chat.clone()))
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[tokio::test]
+    async fn cached_chats_skip_the_network_and_are_indexed() {
+        let data = DataContext::default();
+        data.chats
+            .set(proto::GetChatsResult {
+                chats: vec![proto::Chat {
+                    id: 7,
+                    ..Default::default()
+                }],
+                users: vec![proto::User {
+                    id: 42,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            })
+            .unwrap();
+
+        let connects = Cell::new(0);
+        let chats = data
+            .chats(async {
+                connects.set(connects.get() + 1);
+                Err("offline".into())
+            })
+            .await
+            .unwrap();
+        assert_eq!(connects.get(), 0);
+        assert!(data.users_by_id(chats).contains_key(&42));
+        assert!(data.chats_by_id(chats).contains_key(&7));
+    }
+
+    #[tokio::test]
+    async fn realtime_reports_missing_token_before_connecting() {
+        let data = DataContext::default();
+        let err = data
+            .realtime(
+                "ws://127.0.0.1:9/realtime",
+                || Err("no token".into()),
+                Vec::new,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "no token");
+    }
+}
@@ -0,0 +1,125 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader};
+
+use chrono::Utc;
+use inline_sdk::RealtimeError;
+use serde::Serialize;
+
+use super::{CommandContext, Execute};
+use crate::dates::parse_duration;
+use crate::errors::CliError;
+use crate::event_log::{EventBody, RecordedEvent, append_event, describe_event, read_events};
+use crate::output::{self, JsonFormat};
+use crate::{DebugCommand, DebugRecordArgs, DebugReplayArgs};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DebugRecordOutput {
+    output: String,
+    events: usize,
+}
+
+impl Execute for DebugCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            DebugCommand::Record(args) => record(ctx, args).await,
+            DebugCommand::Replay(args) => replay(ctx, args),
+        }
+    }
+}
+
+async fn record(
+    ctx: &CommandContext,
+    args: DebugRecordArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let duration = args
+        .duration
+        .as_deref()
+        .map(parse_duration)
+        .transpose()
+        .map_err(|error| CliError::invalid_args(format!("--duration: {error}")))?;
+    if args.count == Some(0) {
+        return Err(CliError::invalid_args("--count must be greater than 0").into());
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&args.output)?;
+
+    let realtime = ctx.realtime().await?;
+    let mut events = realtime.subscribe();
+    if !ctx.json {
+        eprintln!(
+            "Recording realtime events to {}. Press Ctrl-C to stop.",
+            args.output.display()
+        );
+    }
+
+    let deadline = duration.map(|duration| tokio::time::Instant::now() + duration);
+    let mut recorded = 0;
+    while args.count.is_none_or(|count| recorded < count) {
+        let received = match deadline {
+            Some(deadline) => match tokio::time::timeout_at(deadline, events.recv()).await {
+                Ok(received) => received,
+                Err(_) => break,
+            },
+            None => events.recv().await,
+        };
+        let body = match received {
+            Ok(event) => match EventBody::from_realtime(&event) {
+                Some(body) => body,
+                None => continue,
+            },
+            Err(RealtimeError::EventLagged { skipped }) => EventBody::Lagged { skipped },
+            Err(error) => return Err(error.into()),
+        };
+        let event = RecordedEvent {
+            at_ms: Utc::now().timestamp_millis(),
+            body,
+        };
+        append_event(&mut file, &event)?;
+        recorded += 1;
+    }
+
+    let output = DebugRecordOutput {
+        output: args.output.display().to_string(),
+        events: recorded,
+    };
+    if ctx.json {
+        return ctx.print_json(&output);
+    }
+    println!("Recorded {} event(s) to {}.", output.events, output.output);
+    Ok(())
+}
+
+fn replay(ctx: &CommandContext, args: DebugReplayArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let events = if args.file.as_os_str() == "-" {
+        read_events(io::stdin().lock())
+    } else {
+        read_events(BufReader::new(File::open(&args.file)?))
+    }
+    .map_err(|error| {
+        CliError::invalid_args(format!(
+            "{} is not a debug recording: {error}",
+            args.file.display()
+        ))
+    })?;
+
+    let Some(start_ms) = events.first().map(|event| event.at_ms) else {
+        if !ctx.json {
+            println!("No events recorded.");
+        }
+        return Ok(());
+    };
+    for event in &events {
+        if ctx.json {
+            // One object per line, like the recording and `watch --json`.
+            output::print_json(event, JsonFormat::Compact)?;
+        } else {
+            for line in describe_event(event, start_ms) {
+                println!("{line}");
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use chrono::Utc;
+
+use super::{CommandContext, Execute};
+use crate::chat_output::build_chat_list;
+use crate::digest::{DIGEST_HISTORY_LIMIT, build_digest, digest_candidate_chats, render_digest};
+use crate::errors::CliError;
+use crate::history::fetch_history_window;
+use crate::peer::input_peer_from_args;
+use crate::validation::{parse_time_filters, validate_optional_positive_id_arg};
+use crate::{
+    DigestArgs, current_epoch_seconds, fetch_me, input_peer_from_proto_peer, send_message,
+};
+
+impl Execute for DigestArgs {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        let (since_ts, _) = parse_time_filters(Some(&self.since), None, Utc::now())?;
+        let since_ts = since_ts.unwrap_or_default();
+        let space_id = validate_optional_positive_id_arg("--space-id", self.space_id)?;
+        let post_to = validate_optional_positive_id_arg("--post-to", self.post_to)?;
+        if self.max_chats == 0 {
+            return Err(CliError::invalid_args("--max-chats must be greater than 0").into());
+        }
+        let realtime = ctx.realtime().await?;
+
+        let me = fetch_me(&realtime).await?;
+        let users_by_id = ctx.users_by_id().await?;
+        let chat_list = build_chat_list(ctx.chats().await?.clone(), Some(&me), None, None, None)?;
+        let candidates =
+            digest_candidate_chats(chat_list.items, since_ts, space_id, self.max_chats);
+
+        let mut chats = Vec::with_capacity(candidates.len());
+        for item in candidates {
+            let Some(peer) = item
+                .chat
+                .peer_id
+                .as_ref()
+                .and_then(input_peer_from_proto_peer)
+            else {
+                continue;
+            };
+            let messages = fetch_history_window(
+                &realtime,
+                &peer,
+                None,
+                Some(DIGEST_HISTORY_LIMIT),
+                Some(since_ts),
+                None,
+            )
+            .await?;
+            chats.push((item, messages));
+        }
+
+        let now = current_epoch_seconds() as i64;
+        let mut digest = build_digest(since_ts, space_id, chats, users_by_id, me.id, now);
+        if let Some(chat_id) = post_to {
+            let peer = input_peer_from_args(Some(chat_id), None)?;
+            send_message(
+                &realtime,
+                &peer,
+                Some(render_digest(&digest)),
+                None,
+                true,
+                None,
+                None,
+                None,
+            )
+            .await?;
+            digest.posted_to_chat_id = Some(chat_id);
+        }
+
+        if ctx.json {
+            ctx.print_json(&digest)?;
+        } else {
+            print!("{}", render_digest(&digest));
+            if let Some(chat_id) = digest.posted_to_chat_id {
+                println!("\nPosted digest to chat {chat_id}.");
+            }
+        }
+        Ok(())
+    }
+}
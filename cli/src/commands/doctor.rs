@@ -0,0 +1,121 @@
+use serde::Serialize;
+
+use super::{CommandContext, Execute};
+use crate::auth::env_token_present;
+use crate::auth_flow::handle_login;
+use crate::doctor::{build_doctor_output, print_doctor, probe_connectivity};
+use crate::doctor_fix::{DoctorFix, DoctorFixResult, apply_fix, plan_fixes};
+use crate::errors::CliError;
+use crate::{
+    AuthLoginArgs, DoctorArgs, confirm_action, current_epoch_seconds, is_interactive_terminal,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DoctorFixOutput {
+    fixes: Vec<DoctorFixResult>,
+    /// The token file was reset and `inline auth login` still needs to run.
+    login_required: bool,
+}
+
+impl Execute for DoctorArgs {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        if self.fix {
+            return fix(ctx, self.yes).await;
+        }
+        let mut output = build_doctor_output(&ctx.config, &ctx.auth_store, &ctx.local_db);
+        if !self.offline {
+            if !ctx.json {
+                eprintln!("Checking connectivity...");
+            }
+            let token = ctx.auth_store.load_token().ok().flatten();
+            output =
+                output.with_connectivity(probe_connectivity(&ctx.config, token.as_deref()).await);
+        }
+        if ctx.json {
+            ctx.print_json(&output)
+        } else {
+            print_doctor(&output);
+            Ok(())
+        }
+    }
+}
+
+async fn fix(ctx: &CommandContext, yes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let now = current_epoch_seconds() as i64;
+    let fixes = plan_fixes(&ctx.config, &ctx.auth_store, &ctx.local_db, now);
+    if ctx.json && !yes && !fixes.is_empty() {
+        return Err(CliError::confirmation_required().into());
+    }
+    if !ctx.json {
+        if fixes.is_empty() {
+            println!("Nothing to fix.");
+            return Ok(());
+        }
+        println!("Found {} problem(s):", fixes.len());
+        for fix in &fixes {
+            println!("  - {}", fix.problem());
+            println!("    fix: {}", fix.action());
+        }
+        println!();
+    }
+
+    let mut results = Vec::new();
+    let mut secrets_reset = false;
+    for fix in &fixes {
+        if !confirm_action(&format!("{}?", fix.action()), yes)? {
+            if !ctx.json {
+                println!("Skipped: {}", fix.action());
+            }
+            results.push(fix.result("skipped", None, None));
+            continue;
+        }
+        match apply_fix(fix, &ctx.local_db, now) {
+            Ok(backup) => {
+                secrets_reset |= matches!(fix, DoctorFix::ResetSecrets { .. });
+                if !ctx.json {
+                    match &backup {
+                        Some(backup) => {
+                            println!("Fixed: {} (backup: {})", fix.action(), backup.display())
+                        }
+                        None => println!("Fixed: {}", fix.action()),
+                    }
+                }
+                results.push(fix.result("fixed", backup.as_deref(), None));
+            }
+            Err(error) => {
+                if !ctx.json {
+                    println!("Failed: {}: {error}", fix.action());
+                }
+                results.push(fix.result("failed", None, Some(error)));
+            }
+        }
+    }
+
+    // INLINE_TOKEN still works without the file, so there's nothing to log in to.
+    let mut login_required = secrets_reset && !env_token_present();
+    if login_required && !ctx.json {
+        if is_interactive_terminal() && confirm_action("Log in again now?", yes)? {
+            handle_login(
+                AuthLoginArgs::default(),
+                &ctx.api,
+                &ctx.auth_store,
+                &ctx.config.realtime_url,
+                &ctx.local_db,
+                false,
+            )
+            .await?;
+            login_required = false;
+        } else {
+            println!("Run `inline auth login` to log in again.");
+        }
+    }
+
+    if ctx.json {
+        return ctx.print_json(&DoctorFixOutput {
+            fixes: results,
+            login_required,
+        });
+    }
+    Ok(())
+}
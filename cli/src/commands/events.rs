@@ -0,0 +1,103 @@
+use std::fs;
+
+use chrono::{Duration, Utc};
+use serde::Serialize;
+
+use super::messages::export_peer_from_input_peer;
+use super::{CommandContext, Execute};
+use crate::dates::parse_duration;
+use crate::errors::CliError;
+use crate::events::{ChatEvent, EventSource, extract_event, render_ics};
+use crate::history::fetch_history_window;
+use crate::output::user_display_name;
+use crate::validation::parse_time_filters;
+use crate::{EventsCommand, EventsExtractArgs, current_epoch_seconds};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EventsExtractOutput {
+    path: Option<String>,
+    messages: usize,
+    events: Vec<ChatEvent>,
+}
+
+impl Execute for EventsCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            EventsCommand::Extract(args) => extract(ctx, args).await,
+        }
+    }
+}
+
+async fn extract(
+    ctx: &CommandContext,
+    args: EventsExtractArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if args.limit <= 0 {
+        return Err(CliError::invalid_args("--limit must be greater than 0").into());
+    }
+    let duration = parse_duration(&args.duration)
+        .map_err(|err| CliError::invalid_args(format!("--duration: {err}")))?;
+    let duration = Duration::from_std(duration)
+        .map_err(|_| CliError::invalid_args("--duration is too long"))?;
+    let (since_ts, until_ts) =
+        parse_time_filters(Some(&args.since), args.until.as_deref(), Utc::now())?;
+    let peer = ctx
+        .resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+        .await?;
+    let realtime = ctx.realtime().await?;
+    let mut messages =
+        fetch_history_window(&realtime, &peer, None, Some(args.limit), since_ts, until_ts).await?;
+    messages.sort_by_key(|message| (message.date, message.id));
+
+    let users_by_id = ctx.users_by_id().await?;
+    let chats_by_id = ctx.chats_by_id().await?;
+    let export_peer = export_peer_from_input_peer(&peer, users_by_id, chats_by_id);
+    let chat_name = export_peer.name.as_deref().unwrap_or("Inline");
+    let events = messages
+        .iter()
+        .filter_map(|message| {
+            let sender_name = users_by_id
+                .get(&message.from_id)
+                .map(user_display_name)
+                .unwrap_or_else(|| format!("user {}", message.from_id));
+            extract_event(
+                &EventSource {
+                    peer_type: &export_peer.peer_type,
+                    peer_id: export_peer.id,
+                    chat_name,
+                    message_id: message.id,
+                    sent_at: message.date,
+                    sender_name: &sender_name,
+                    text: message.message.as_deref()?,
+                },
+                duration,
+            )
+        })
+        .collect::<Vec<_>>();
+    let ics = render_ics(&events, current_epoch_seconds() as i64);
+
+    if let Some(path) = &args.output {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, ics.as_bytes())?;
+    }
+    if ctx.json {
+        return ctx.print_json(&EventsExtractOutput {
+            path: args.output.as_ref().map(|path| path.display().to_string()),
+            messages: messages.len(),
+            events,
+        });
+    }
+    match &args.output {
+        Some(path) => println!(
+            "Found {} event(s) in {} message(s); wrote {}.",
+            events.len(),
+            messages.len(),
+            path.display()
+        ),
+        None => print!("{ics}"),
+    }
+    Ok(())
+}
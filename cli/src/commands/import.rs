@@ -0,0 +1,407 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use inline_protocol::proto;
+use inline_sdk::RealtimeSession;
+use rand::{RngCore, rngs::OsRng};
+use serde::Serialize;
+
+use super::{CommandContext, Execute};
+use crate::attachments::{AttachmentOptions, input_media_from_upload, prepare_attachments};
+use crate::compression::read_to_string_decompressed;
+use crate::errors::CliError;
+use crate::import::{
+    ImportMapping, ImportThread, ThreadMapping, import_message_text, parse_user_map,
+    sent_message_id, slack, telegram,
+};
+use crate::output::user_display_name;
+use crate::peer::input_chat_peer;
+use crate::photos::PhotoOptions;
+use crate::validation::validate_positive_id_arg;
+use crate::{ImportCommand, current_epoch_seconds};
+
+/// How often progress is printed and the mapping file is checkpointed.
+const IMPORT_CHECKPOINT_EVERY: usize = 50;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportOutput {
+    source: &'static str,
+    mapping_path: String,
+    dry_run: bool,
+    threads: Vec<ImportThreadOutput>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportThreadOutput {
+    source_id: String,
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chat_id: Option<i64>,
+    messages: usize,
+    imported: usize,
+    skipped: usize,
+}
+
+impl Execute for ImportCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            ImportCommand::Slack(args) => {
+                let space_id = validate_positive_id_arg("--space-id", args.space_id)?;
+                let user_map = load_user_map(args.map.as_deref())?;
+                let files = slack::read_archive(&args.archive).map_err(|err| {
+                    CliError::invalid_args(format!("{}: {err}", args.archive.display()))
+                })?;
+                let threads = slack::parse_archive(&files, &user_map, &args.channel)
+                    .map_err(CliError::invalid_args)?;
+                let mapping_path = args
+                    .mapping
+                    .unwrap_or_else(|| default_mapping_path(&args.archive));
+                let mapping = ImportMapping::load(&mapping_path, "slack")?;
+                if !args.dry_run
+                    && !ctx.confirm_send_count(pending_messages(&threads, &mapping), args.force)?
+                {
+                    println!("Cancelled.");
+                    return Ok(());
+                }
+                run_import(
+                    ctx,
+                    "slack",
+                    threads,
+                    mapping,
+                    &mapping_path,
+                    Some(space_id),
+                    args.dry_run,
+                )
+                .await
+            }
+            ImportCommand::Telegram(args) => {
+                let chat_id = validate_positive_id_arg("--chat-id", args.chat_id)?;
+                let user_map = load_user_map(args.map.as_deref())?;
+                let contents = read_to_string_decompressed(&args.file).map_err(|err| {
+                    CliError::invalid_args(format!("{}: {err}", args.file.display()))
+                })?;
+                let base_dir = args.file.parent().unwrap_or_else(|| Path::new("."));
+                let thread = telegram::parse_export(
+                    &contents,
+                    base_dir,
+                    &user_map,
+                    args.source_chat.as_deref(),
+                )
+                .map_err(|err| CliError::invalid_args(format!("{}: {err}", args.file.display())))?;
+                let mapping_path = args
+                    .mapping
+                    .unwrap_or_else(|| default_mapping_path(&args.file));
+                let mut mapping = ImportMapping::load(&mapping_path, "telegram")?;
+                let thread_mapping = mapping
+                    .threads
+                    .entry(thread.source_id.clone())
+                    .or_insert_with(|| ThreadMapping {
+                        title: thread.title.clone(),
+                        chat_id,
+                        messages: Default::default(),
+                    });
+                if thread_mapping.chat_id != chat_id {
+                    return Err(CliError::invalid_args(format!(
+                        "{} already imports {} into chat {}; pass --chat-id {} or a new --mapping file",
+                        mapping_path.display(),
+                        thread.title,
+                        thread_mapping.chat_id,
+                        thread_mapping.chat_id
+                    ))
+                    .into());
+                }
+                let threads = vec![thread];
+                if !args.dry_run
+                    && !ctx.confirm_send_count(pending_messages(&threads, &mapping), args.force)?
+                {
+                    println!("Cancelled.");
+                    return Ok(());
+                }
+                run_import(
+                    ctx,
+                    "telegram",
+                    threads,
+                    mapping,
+                    &mapping_path,
+                    None,
+                    args.dry_run,
+                )
+                .await
+            }
+        }
+    }
+}
+
+/// Messages a run would send: everything not already in `mapping`.
+fn pending_messages(threads: &[ImportThread], mapping: &ImportMapping) -> usize {
+    threads
+        .iter()
+        .map(|thread| {
+            let existing = mapping.threads.get(&thread.source_id);
+            thread
+                .messages
+                .iter()
+                .filter(|message| {
+                    existing
+                        .is_none_or(|existing| !existing.messages.contains_key(&message.source_id))
+                })
+                .count()
+        })
+        .sum()
+}
+
+fn load_user_map(path: Option<&Path>) -> Result<HashMap<String, i64>, Box<dyn std::error::Error>> {
+    let Some(path) = path else {
+        return Ok(HashMap::new());
+    };
+    let contents = fs::read_to_string(path)?;
+    Ok(parse_user_map(&contents)
+        .map_err(|err| CliError::invalid_args(format!("{}: {err}", path.display())))?)
+}
+
+fn default_mapping_path(source: &Path) -> PathBuf {
+    let mut path = source.as_os_str().to_owned();
+    path.push(".inline-map.json");
+    PathBuf::from(path)
+}
+
+/// Replays `threads` in order. Threads already in `mapping` reuse their chat;
+/// others are created in `space_id`. Messages already in `mapping` are skipped.
+/// Attachments are uploaded again and sent with the message text as caption.
+async fn run_import(
+    ctx: &CommandContext,
+    source: &'static str,
+    threads: Vec<ImportThread>,
+    mut mapping: ImportMapping,
+    mapping_path: &Path,
+    space_id: Option<i64>,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut output = ImportOutput {
+        source,
+        mapping_path: mapping_path.display().to_string(),
+        dry_run,
+        threads: Vec::new(),
+    };
+    if dry_run {
+        for thread in &threads {
+            let existing = mapping.threads.get(&thread.source_id);
+            let skipped = existing.map_or(0, |existing| {
+                thread
+                    .messages
+                    .iter()
+                    .filter(|message| existing.messages.contains_key(&message.source_id))
+                    .count()
+            });
+            output.threads.push(ImportThreadOutput {
+                source_id: thread.source_id.clone(),
+                title: thread.title.clone(),
+                chat_id: existing.map(|existing| existing.chat_id),
+                messages: thread.messages.len(),
+                imported: 0,
+                skipped,
+            });
+        }
+        return print_import_output(ctx, &output);
+    }
+
+    let token = ctx.token()?;
+    let realtime = ctx.realtime().await?;
+    let users_by_id = ctx.users_by_id().await?;
+    for thread in threads {
+        if !mapping.threads.contains_key(&thread.source_id) {
+            let Some(space_id) = space_id else {
+                return Err(CliError::invalid_args(format!(
+                    "No chat to import {} into",
+                    thread.title
+                ))
+                .into());
+            };
+            let input = proto::CreateChatInput {
+                title: Some(thread.title.clone()),
+                space_id: Some(space_id),
+                description: None,
+                emoji: None,
+                is_public: thread.is_public,
+                participants: thread
+                    .participant_ids
+                    .iter()
+                    .map(|user_id| proto::InputChatParticipant {
+                        user_id: Some(*user_id),
+                        group_id: None,
+                    })
+                    .collect(),
+                reserved_chat_id: None,
+            };
+            let payload = realtime.call(input).await?;
+            let chat = payload
+                .chat
+                .ok_or_else(|| format!("createChat returned no chat for {}", thread.title))?;
+            mapping.threads.insert(
+                thread.source_id.clone(),
+                ThreadMapping {
+                    title: thread.title.clone(),
+                    chat_id: chat.id,
+                    messages: Default::default(),
+                },
+            );
+            mapping.save(mapping_path)?;
+        }
+
+        // Checked out while sending and put back at every checkpoint.
+        let mut thread_mapping = mapping
+            .threads
+            .remove(&thread.source_id)
+            .expect("thread mapping was just ensured");
+        let peer = input_chat_peer(thread_mapping.chat_id);
+        let total = thread.messages.len();
+        let mut imported = 0;
+        let mut skipped = 0;
+        for message in &thread.messages {
+            if thread_mapping.messages.contains_key(&message.source_id) {
+                skipped += 1;
+                continue;
+            }
+            let author = message
+                .author_user_id
+                .and_then(|user_id| users_by_id.get(&user_id))
+                .map(user_display_name)
+                .unwrap_or_else(|| message.author.clone());
+            let reply_to_msg_id = message
+                .reply_to
+                .as_ref()
+                .and_then(|source_id| thread_mapping.messages.get(source_id))
+                .copied()
+                .filter(|message_id| *message_id > 0);
+            let random_id = OsRng.next_u64() as i64;
+            let input = proto::SendMessageInput {
+                peer_id: Some(peer.clone()),
+                message: Some(import_message_text(message, &author)),
+                reply_to_msg_id,
+                random_id: Some(random_id),
+                temporary_send_date: Some(current_epoch_seconds() as i64),
+                parse_markdown: Some(true),
+                send_mode: Some(proto::MessageSendMode::ModeSilent as i32),
+                ..Default::default()
+            };
+            let sent =
+                send_import_message(ctx, &realtime, &token, input, message.attachment.as_deref())
+                    .await;
+            let result = match sent {
+                Ok(result) => result,
+                Err(err) => {
+                    mapping
+                        .threads
+                        .insert(thread.source_id.clone(), thread_mapping);
+                    mapping.save(mapping_path)?;
+                    return Err(err);
+                }
+            };
+            // 0 marks "sent, id unknown" so a rerun still skips it.
+            thread_mapping.messages.insert(
+                message.source_id.clone(),
+                sent_message_id(&result, random_id).unwrap_or(0),
+            );
+            imported += 1;
+            if imported % IMPORT_CHECKPOINT_EVERY == 0 {
+                mapping
+                    .threads
+                    .insert(thread.source_id.clone(), thread_mapping.clone());
+                mapping.save(mapping_path)?;
+                if !ctx.json {
+                    eprintln!("{}: {}/{total} messages", thread.title, imported + skipped);
+                }
+            }
+        }
+        let chat_id = thread_mapping.chat_id;
+        mapping
+            .threads
+            .insert(thread.source_id.clone(), thread_mapping);
+        mapping.save(mapping_path)?;
+        output.threads.push(ImportThreadOutput {
+            source_id: thread.source_id.clone(),
+            title: thread.title.clone(),
+            chat_id: Some(chat_id),
+            messages: total,
+            imported,
+            skipped,
+        });
+    }
+    print_import_output(ctx, &output)
+}
+
+async fn send_import_message(
+    ctx: &CommandContext,
+    realtime: &RealtimeSession,
+    token: &str,
+    mut input: proto::SendMessageInput,
+    attachment: Option<&Path>,
+) -> Result<proto::SendMessageResult, Box<dyn std::error::Error>> {
+    if let Some(path) = attachment {
+        let prepared = prepare_attachments(
+            &[path.to_path_buf()],
+            &ctx.config.data_dir,
+            // Imported files keep their exported bytes.
+            &AttachmentOptions {
+                photos: PhotoOptions {
+                    original: true,
+                    ..PhotoOptions::default()
+                },
+                ..AttachmentOptions::default()
+            },
+            true,
+        )?;
+        for attachment in &prepared {
+            let upload = ctx
+                .api
+                .upload_file(token, attachment.to_upload_input())
+                .await?;
+            input.media = Some(input_media_from_upload(&upload)?);
+        }
+    }
+    Ok(realtime.call(input).await?)
+}
+
+fn print_import_output(
+    ctx: &CommandContext,
+    output: &ImportOutput,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (imported, skipped) = output
+        .threads
+        .iter()
+        .fold((0, 0), |(imported, skipped), thread| {
+            (imported + thread.imported, skipped + thread.skipped)
+        });
+    ctx.report_completion(if output.dry_run {
+        format!("dry run, {} thread(s)", output.threads.len())
+    } else {
+        format!("{imported} message(s) imported, {skipped} skipped")
+    });
+    if ctx.json {
+        return ctx.print_json(output);
+    }
+    for thread in &output.threads {
+        let chat = thread
+            .chat_id
+            .map(|chat_id| format!(" (chat {chat_id})"))
+            .unwrap_or_default();
+        if output.dry_run {
+            let pending = thread.messages - thread.skipped;
+            println!("{}{chat}: {pending} message(s) to import", thread.title);
+        } else {
+            println!(
+                "{}{chat}: imported {}, skipped {} already imported",
+                thread.title, thread.imported, thread.skipped
+            );
+        }
+    }
+    if output.dry_run {
+        println!("Dry run: nothing was sent.");
+    } else {
+        println!("Mapping saved to {}.", output.mapping_path);
+    }
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use chrono::Utc;
+
+use super::{CommandContext, Execute};
+use crate::chat_output::build_chat_list;
+use crate::digest::digest_candidate_chats;
+use crate::errors::CliError;
+use crate::history::fetch_history_window;
+use crate::mentions::{MentionsOutput, find_mentions};
+use crate::output;
+use crate::validation::{parse_time_filters, validate_optional_positive_id_arg};
+use crate::{MentionsArgs, current_epoch_seconds, fetch_me, input_peer_from_proto_peer};
+
+impl Execute for MentionsArgs {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        let (since_ts, _) = parse_time_filters(Some(&self.since), None, Utc::now())?;
+        let since_ts = since_ts.unwrap_or_default();
+        let space_id = validate_optional_positive_id_arg("--space-id", self.space_id)?;
+        if self.max_chats == 0 {
+            return Err(CliError::invalid_args("--max-chats must be greater than 0").into());
+        }
+        let realtime = ctx.realtime().await?;
+
+        let me = fetch_me(&realtime).await?;
+        let users_by_id = ctx.users_by_id().await?;
+        let chat_list = build_chat_list(ctx.chats().await?.clone(), Some(&me), None, None, None)?;
+        let candidates =
+            digest_candidate_chats(chat_list.items, since_ts, space_id, self.max_chats);
+
+        let mut chats = Vec::with_capacity(candidates.len());
+        for item in candidates {
+            let Some(peer) = item
+                .chat
+                .peer_id
+                .as_ref()
+                .and_then(input_peer_from_proto_peer)
+            else {
+                continue;
+            };
+            // Page back to the cutoff so replies later in the window are seen too.
+            let messages =
+                fetch_history_window(&realtime, &peer, None, None, Some(since_ts), None).await?;
+            chats.push((item, messages));
+        }
+
+        let chats_scanned = chats.len();
+        let now = current_epoch_seconds() as i64;
+        let output = MentionsOutput {
+            since: since_ts,
+            unreplied_only: self.unreplied,
+            chats_scanned,
+            mentions: find_mentions(chats, users_by_id, me.id, since_ts, self.unreplied, now),
+        };
+        if ctx.json {
+            ctx.print_json(&output)?;
+        } else {
+            output::print_mentions(&output);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,3127 @@
+use chrono::Utc;
+use futures_util::stream::{self, StreamExt};
+use inline_protocol::proto;
+use inline_sdk::api::{ApiClient, UploadFileResult};
+use inline_sdk::{RealtimeEventReceiver, RealtimeSession};
+use rand::{RngCore, rngs::OsRng};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{env, fs};
+use tokio::time::Instant;
+
+use super::watch::next_updates;
+use super::{CommandContext, Execute};
+use crate::attachments::{
+    AttachmentOptions, MAX_ATTACHMENT_BYTES, PreparedAttachment, ZipOptions,
+    input_media_from_upload, prepare_attachments, prepare_captured_attachment,
+};
+use crate::capture::{CaptureSource, capture_image};
+use crate::chat_output::chat_display_name;
+use crate::compression::{
+    Compression, compressed_path, strip_compression_extension, write_compressed,
+};
+use crate::dates::parse_duration;
+use crate::downloads::{
+    download_message_media, resolve_batch_download_path, resolve_download_path,
+};
+use crate::editor;
+use crate::errors::CliError;
+use crate::front_matter;
+use crate::history::{
+    HitContext, fetch_history_window, fetch_hit_contexts, search_media_window,
+    search_messages_window,
+};
+use crate::idempotency;
+use crate::message_export::{
+    ExportPeer, MessageExportBuildInput, MessageExportFormat, apply_media_local_paths,
+    build_message_export_bundle, forward_source_key, infer_export_format, render_export,
+};
+use crate::message_output::{
+    build_media_list, build_message_list, build_message_list_from_messages, message_summary,
+};
+use crate::message_selectors::parse_message_id_selectors;
+use crate::output::{PeerSummary, print_message_detail, user_display_name};
+use crate::pending_sends::{self, PendingSend};
+use crate::photos::PhotoOptions;
+use crate::preview::{ImageProtocol, detect_image_protocol, quick_look, render_photo};
+use crate::state::LocalDb;
+use crate::validation::{
+    normalize_search_queries, normalize_translation_language, parse_time_filters,
+    validate_attachment_inputs, validate_context_size, validate_message_id_arg,
+    validate_message_ids_arg, validate_message_limit, validate_optional_message_id_arg,
+    validate_output_dir_path_arg, validate_output_file_path_arg, validate_parallel,
+    validate_positive_id_arg,
+};
+use crate::{
+    MessagesCommand, MessagesExportArgs, MessagesForwardArgs, MessagesListArgs, MessagesSendArgs,
+    confirm_action, current_epoch_seconds, fetch_message_by_id, fetch_messages_by_ids,
+    filter_messages_by_time, input_peer_from_proto_peer, new_message_ids, output,
+    peer_label_from_input, send_message, send_message_with_random_id,
+};
+
+const DEFAULT_MEDIA_LIMIT: i32 = 50;
+
+/// `messages send` JSON: the usual send result plus the message it created.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SendMessageOutput {
+    #[serde(flatten)]
+    result: proto::SendMessageResult,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<proto::Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delivered: Option<bool>,
+}
+
+/// `messages delete` JSON: the raw result plus which ids the server reported
+/// removing. Deletes always remove messages for everyone in the chat.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteMessagesOutput {
+    #[serde(flatten)]
+    result: proto::DeleteMessagesResult,
+    for_everyone: bool,
+    /// Absent when the server's reply doesn't list the deleted ids.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deleted_message_ids: Option<Vec<i64>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    not_deleted_message_ids: Vec<i64>,
+}
+
+/// `messages send --edit` JSON when the editor was saved empty and nothing was
+/// sent.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NotSentOutput {
+    sent: bool,
+    reason: &'static str,
+}
+
+/// `messages send --idempotency-key` JSON when the key was already used for
+/// the chat and nothing was sent.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AlreadySentOutput<'a> {
+    already_sent: bool,
+    idempotency_key: &'a str,
+    message_ids: Vec<i64>,
+}
+
+/// `messages send --attach` JSON: the usual send result plus what was uploaded.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SendAttachmentsOutput {
+    #[serde(flatten)]
+    result: proto::SendMessageResult,
+    message_ids: Vec<i64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    messages: Vec<proto::Message>,
+    attachments: Vec<SentAttachmentOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delivered: Option<bool>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SentAttachmentOutput {
+    path: String,
+    file_name: String,
+    size_bytes: u64,
+    /// Digest of the uploaded bytes, for checking against a build artifact.
+    sha256: String,
+    file_unique_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_id: Option<i64>,
+}
+
+impl Execute for MessagesCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            MessagesCommand::List(args) => {
+                output::set_table_width(args.width);
+                let limit = validate_message_limit(args.limit)?;
+                let offset_id = validate_optional_message_id_arg("--offset-id", args.offset_id)?;
+                let (since_ts, until_ts) =
+                    parse_time_filters(args.since.as_deref(), args.until.as_deref(), Utc::now())?;
+                let auto_translate = args
+                    .translate
+                    .as_deref()
+                    .is_some_and(|language| language.trim() == "auto");
+                let translation_language = if auto_translate {
+                    Some(
+                        ctx.config
+                            .language
+                            .clone()
+                            .ok_or_else(CliError::translate_language_not_set)?,
+                    )
+                } else {
+                    args.translate
+                        .as_deref()
+                        .map(normalize_translation_language)
+                        .transpose()?
+                };
+                let peer = ctx
+                    .resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+                    .await?;
+                let peer_summary = peer_summary_from_input(&peer);
+                let realtime = ctx.realtime().await?;
+
+                let mut payload = proto::GetChatHistoryResult {
+                    messages: fetch_history_window(
+                        &realtime, &peer, offset_id, limit, since_ts, until_ts,
+                    )
+                    .await?,
+                };
+                filter_messages_by_list_options(&mut payload.messages, &args);
+
+                let message_ids = collect_message_ids(&payload.messages);
+                let mut translations_by_id = match translation_language.as_deref() {
+                    Some(language) => {
+                        fetch_message_translations(&realtime, &peer, &message_ids, language).await?
+                    }
+                    None => HashMap::new(),
+                };
+                if auto_translate {
+                    drop_unchanged_translations(&mut translations_by_id, &payload.messages);
+                }
+
+                if ctx.json {
+                    if translation_language.is_some() {
+                        let output = TranslatedChatHistoryOutput {
+                            payload,
+                            translations: translations_in_message_order(
+                                &message_ids,
+                                &translations_by_id,
+                            ),
+                        };
+                        ctx.print_json(&output)?;
+                    } else {
+                        ctx.print_json(&payload)?;
+                    }
+                } else {
+                    let users_by_id = ctx.users_by_id().await?;
+                    let chats_by_id = ctx.chats_by_id().await?;
+                    let current_user_id = ctx.local_db.load()?.current_user.map(|user| user.id);
+                    let output = build_message_list(
+                        payload,
+                        users_by_id,
+                        current_user_id,
+                        peer_summary,
+                        peer_name_from_input(&peer, users_by_id, chats_by_id),
+                        Some(&translations_by_id),
+                    );
+                    output::print_messages(&output, false, ctx.json_format)?;
+                }
+            }
+            MessagesCommand::Media(args) => {
+                let limit = validate_message_limit(args.limit)?.unwrap_or(DEFAULT_MEDIA_LIMIT);
+                let offset_id = validate_optional_message_id_arg("--offset-id", args.offset_id)?;
+                let (since_ts, until_ts) =
+                    parse_time_filters(args.since.as_deref(), args.until.as_deref(), Utc::now())?;
+                let peer = ctx
+                    .resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+                    .await?;
+                let realtime = ctx.realtime().await?;
+                let messages = search_media_window(
+                    &realtime,
+                    &peer,
+                    args.kind.search_filter(),
+                    offset_id,
+                    limit,
+                    since_ts,
+                    until_ts,
+                )
+                .await?;
+                // A full page means there may be more; point at the oldest one.
+                let next_offset_id = (messages.len() >= limit as usize)
+                    .then(|| messages.iter().map(|message| message.id).min())
+                    .flatten();
+                let users_by_id = ctx.users_by_id().await?;
+                let chats_by_id = ctx.chats_by_id().await?;
+                let current_user_id = ctx.local_db.load()?.current_user.map(|user| user.id);
+                let output = build_media_list(
+                    &messages,
+                    args.kind,
+                    users_by_id,
+                    current_user_id,
+                    peer_summary_from_input(&peer),
+                    peer_name_from_input(&peer, users_by_id, chats_by_id),
+                    next_offset_id,
+                );
+                if ctx.json {
+                    ctx.print_json(&output)?;
+                } else {
+                    output::print_media_list(&output);
+                }
+            }
+            MessagesCommand::Search(args) => {
+                output::set_table_width(args.width);
+                let limit = validate_message_limit(args.limit)?;
+                let context = validate_context_size(args.context)?;
+                let (since_ts, until_ts) =
+                    parse_time_filters(args.since.as_deref(), args.until.as_deref(), Utc::now())?;
+                let translation_language = args
+                    .translate
+                    .as_deref()
+                    .map(normalize_translation_language)
+                    .transpose()?;
+                let peer = ctx
+                    .resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+                    .await?;
+                let queries = normalize_search_queries(&args.query)?;
+                let peer_summary = peer_summary_from_input(&peer);
+                let realtime = ctx.realtime().await?;
+
+                let payload = proto::SearchMessagesResult {
+                    messages: search_messages_window(
+                        &realtime, &peer, queries, limit, since_ts, until_ts,
+                    )
+                    .await?,
+                };
+                let contexts = match context {
+                    Some(context) => Some(
+                        fetch_hit_contexts(&realtime, &peer, &payload.messages, context).await?,
+                    ),
+                    None => None,
+                };
+
+                if ctx.json {
+                    let translations = match translation_language.as_deref() {
+                        Some(language) => {
+                            let message_ids = collect_message_ids(&payload.messages);
+                            let translations_by_id = fetch_message_translations(
+                                &realtime,
+                                &peer,
+                                &message_ids,
+                                language,
+                            )
+                            .await?;
+                            Some(translations_in_message_order(
+                                &message_ids,
+                                &translations_by_id,
+                            ))
+                        }
+                        None => None,
+                    };
+                    match (contexts, translations) {
+                        (Some(context), translations) => {
+                            ctx.print_json(&SearchMessagesContextOutput {
+                                payload,
+                                translations,
+                                context,
+                            })?;
+                        }
+                        (None, Some(translations)) => {
+                            ctx.print_json(&TranslatedSearchMessagesOutput {
+                                payload,
+                                translations,
+                            })?;
+                        }
+                        (None, None) => ctx.print_json(&payload)?,
+                    }
+                } else {
+                    let translations_by_id = if let Some(language) = translation_language.as_deref()
+                    {
+                        let message_ids = collect_message_ids(&payload.messages);
+                        fetch_message_translations(&realtime, &peer, &message_ids, language).await?
+                    } else {
+                        HashMap::new()
+                    };
+                    let users_by_id = ctx.users_by_id().await?;
+                    let chats_by_id = ctx.chats_by_id().await?;
+                    let current_user_id = ctx.local_db.load()?.current_user.map(|user| user.id);
+                    let peer_name = peer_name_from_input(&peer, users_by_id, chats_by_id);
+                    if let Some(contexts) = contexts {
+                        let groups: Vec<_> = contexts
+                            .into_iter()
+                            .map(|group| {
+                                let output = build_message_list_from_messages(
+                                    &group.messages,
+                                    users_by_id,
+                                    current_user_id,
+                                    peer_summary.clone(),
+                                    peer_name.clone(),
+                                    Some(&translations_by_id),
+                                );
+                                (group.hit_ids, output)
+                            })
+                            .collect();
+                        output::print_message_context_groups(&groups);
+                    } else {
+                        let output = build_message_list_from_messages(
+                            &payload.messages,
+                            users_by_id,
+                            current_user_id,
+                            peer_summary,
+                            peer_name,
+                            Some(&translations_by_id),
+                        );
+                        output::print_messages(&output, false, ctx.json_format)?;
+                    }
+                }
+            }
+            MessagesCommand::Get(args) => {
+                let message_ids = parse_message_id_selectors("--message-id", &args.message_ids)?;
+                let peer = ctx
+                    .resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+                    .await?;
+                let peer_label = peer_label_from_input(&peer);
+                let translation_language = args
+                    .translate
+                    .as_deref()
+                    .map(normalize_translation_language)
+                    .transpose()?;
+                let realtime = ctx.realtime().await?;
+                let (messages, missing_message_ids) =
+                    fetch_messages_by_ids(&realtime, &peer, &message_ids).await?;
+                if message_ids.len() == 1 {
+                    let message = messages.into_iter().next().ok_or_else(|| {
+                        CliError::invalid_args("Message not found for that peer.")
+                    })?;
+                    if ctx.json {
+                        if let Some(language) = translation_language.as_deref() {
+                            let message_ids = [message.id];
+                            let translations_by_id = fetch_message_translations(
+                                &realtime,
+                                &peer,
+                                &message_ids,
+                                language,
+                            )
+                            .await?;
+                            let output = TranslatedMessageOutput {
+                                message,
+                                translations: translations_in_message_order(
+                                    &message_ids,
+                                    &translations_by_id,
+                                ),
+                            };
+                            ctx.print_json(&output)?;
+                        } else {
+                            ctx.print_json(&message)?;
+                        }
+                    } else {
+                        let translations_by_id = if let Some(language) =
+                            translation_language.as_deref()
+                        {
+                            fetch_message_translations(&realtime, &peer, &[message.id], language)
+                                .await?
+                        } else {
+                            HashMap::new()
+                        };
+                        let users_by_id = ctx.users_by_id().await?;
+                        let current_user_id = ctx.local_db.load()?.current_user.map(|user| user.id);
+                        let summary = message_summary(
+                            &message,
+                            users_by_id,
+                            current_user_id,
+                            current_epoch_seconds() as i64,
+                            Some(&translations_by_id),
+                        );
+                        print_message_detail(&summary, &peer_label);
+                    }
+                } else if ctx.json {
+                    let translations = if let Some(language) = translation_language.as_deref() {
+                        let found_ids = collect_message_ids(&messages);
+                        let translations_by_id =
+                            fetch_message_translations(&realtime, &peer, &found_ids, language)
+                                .await?;
+                        translations_in_message_order(&found_ids, &translations_by_id)
+                    } else {
+                        Vec::new()
+                    };
+                    let output = MessagesGetBatchOutput {
+                        messages,
+                        missing_message_ids,
+                        translations,
+                    };
+                    ctx.print_json(&output)?;
+                } else {
+                    let translations_by_id = if let Some(language) = translation_language.as_deref()
+                    {
+                        let found_ids = collect_message_ids(&messages);
+                        fetch_message_translations(&realtime, &peer, &found_ids, language).await?
+                    } else {
+                        HashMap::new()
+                    };
+                    let users_by_id = ctx.users_by_id().await?;
+                    let chats_by_id = ctx.chats_by_id().await?;
+                    let current_user_id = ctx.local_db.load()?.current_user.map(|user| user.id);
+                    let output = build_message_list_from_messages(
+                        &messages,
+                        users_by_id,
+                        current_user_id,
+                        peer_summary_from_input(&peer),
+                        peer_name_from_input(&peer, users_by_id, chats_by_id),
+                        Some(&translations_by_id),
+                    );
+                    output::print_messages(&output, false, ctx.json_format)?;
+                    if !missing_message_ids.is_empty() {
+                        log::warn!(
+                            "{} message id(s) were not found: {}",
+                            missing_message_ids.len(),
+                            missing_message_ids
+                                .iter()
+                                .map(ToString::to_string)
+                                .collect::<Vec<_>>()
+                                .join(",")
+                        );
+                    }
+                }
+            }
+            MessagesCommand::Send(mut args) => {
+                apply_message_file(&mut args)?;
+                if args.edit {
+                    let initial = args.text.take().unwrap_or_default();
+                    let help = send_editor_help(&args);
+                    args.text = editor::compose(&initial, &help, &ctx.config.data_dir)?;
+                    let has_media = !args.attachments.is_empty() || args.paste || args.screenshot;
+                    if args.text.is_none() && !has_media {
+                        if ctx.json {
+                            ctx.print_json(&NotSentOutput {
+                                sent: false,
+                                reason: "empty_message",
+                            })?;
+                        } else {
+                            eprintln!("Empty message; not sent.");
+                        }
+                        return Ok(());
+                    }
+                }
+                if args.to.len() > 1 {
+                    return send_to_many(ctx, args).await;
+                }
+                let reply_to = validate_optional_message_id_arg("--reply-to", args.reply_to)?;
+                let peer = ctx
+                    .resolve_peer(
+                        args.to.first().map(String::as_str),
+                        args.chat_id,
+                        args.user_id,
+                    )
+                    .await?;
+                let wait_timeout = parse_wait_timeout(&args)?;
+                let key = args.idempotency_key.as_deref();
+                if let Some(key) = key
+                    && let Some(message_ids) = already_sent(ctx, key, &peer)?
+                {
+                    if ctx.json {
+                        ctx.print_json(&AlreadySentOutput {
+                            already_sent: true,
+                            idempotency_key: key,
+                            message_ids,
+                        })?;
+                    } else if args.quiet {
+                        for id in message_ids {
+                            println!("{id}");
+                        }
+                    } else {
+                        println!(
+                            "Already sent to {} with this idempotency key; not sending again.",
+                            peer_label_from_input(&peer)
+                        );
+                    }
+                    return Ok(());
+                }
+                let content = prepare_send_content(ctx, &args)?;
+                if !ctx.confirm_send_count(content.attachments.len(), args.force)? {
+                    println!("Cancelled.");
+                    return Ok(());
+                }
+                let realtime = ctx.realtime().await?;
+                let mut events = wait_timeout.map(|_| realtime.subscribe());
+                if content.attachments.is_empty() {
+                    let text = content
+                        .caption
+                        .ok_or_else(|| {
+                            CliError::invalid_args(
+                                "Missing required argument: provide --text/--message/--msg, --stdin, --from-file, or --attach",
+                            )
+                        })?;
+                    let payload = send_message_with_random_id(
+                        &realtime,
+                        &peer,
+                        Some(text),
+                        None,
+                        true,
+                        reply_to,
+                        content.mention_entities,
+                        content.has_link,
+                        key.map(|key| idempotency::random_id(key, &peer, 0)),
+                        send_mode(&args),
+                    )
+                    .await?;
+                    let mut message_id = new_message_ids(&payload.updates).first().copied();
+                    let delivered = match (events.as_mut(), wait_timeout) {
+                        (Some(events), Some(timeout)) => {
+                            let ids = wait_for_delivery(
+                                &realtime,
+                                events,
+                                &peer,
+                                &payload.updates,
+                                timeout,
+                            )
+                            .await?;
+                            message_id = message_id.or(ids.first().copied());
+                            Some(true)
+                        }
+                        _ => None,
+                    };
+                    record_sent(ctx, key, &peer, message_id.into_iter().collect())?;
+                    ctx.remember_sent(&peer);
+                    if ctx.json {
+                        let message = match message_id {
+                            Some(id) => sent_messages(&realtime, &peer, &payload.updates, &[id])
+                                .await?
+                                .pop(),
+                            None => None,
+                        };
+                        ctx.print_json(&SendMessageOutput {
+                            result: payload,
+                            message_id,
+                            message,
+                            delivered,
+                        })?;
+                    } else if args.quiet {
+                        print_new_message_ids("sendMessage", &payload.updates)?;
+                    } else if let Some(id) = message_id {
+                        if delivered.is_some() {
+                            println!("Message sent and delivered (id {id}).");
+                        } else {
+                            println!("Message sent (id {id}).");
+                        }
+                    } else {
+                        println!("Message sent (updates: {}).", payload.updates.len());
+                    }
+                } else {
+                    let peer_summary = peer_summary_from_input(&peer);
+                    let mut output = send_messages_with_attachments(
+                        &ctx.api,
+                        &realtime,
+                        &content.token,
+                        &peer,
+                        content.caption,
+                        reply_to,
+                        content.mention_entities,
+                        content.has_link,
+                        send_mode(&args),
+                        &content.attachments,
+                        peer_summary,
+                        ctx.json || args.quiet,
+                        key,
+                        &ctx.local_db,
+                        &mut vec![None; content.attachments.len()],
+                    )
+                    .await?;
+                    if let (Some(events), Some(timeout)) = (events.as_mut(), wait_timeout) {
+                        wait_for_delivery(
+                            &realtime,
+                            events,
+                            &peer,
+                            &output.result.updates,
+                            timeout,
+                        )
+                        .await?;
+                        output.delivered = Some(true);
+                        if !ctx.json && !args.quiet {
+                            println!("Delivered.");
+                        }
+                    }
+                    record_sent(ctx, key, &peer, output.message_ids.clone())?;
+                    ctx.remember_sent(&peer);
+                    if ctx.json {
+                        output.messages = sent_messages(
+                            &realtime,
+                            &peer,
+                            &output.result.updates,
+                            &output.message_ids,
+                        )
+                        .await?;
+                        ctx.print_json(&output)?;
+                    } else if args.quiet {
+                        print_new_message_ids("sendMessage", &output.result.updates)?;
+                    }
+                }
+            }
+            MessagesCommand::Nudge(args) => {
+                let peer = ctx
+                    .resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+                    .await?;
+                let realtime = ctx.realtime().await?;
+                let media = proto::InputMedia {
+                    media: Some(proto::input_media::Media::Nudge(proto::InputMediaNudge {})),
+                };
+                let payload =
+                    send_message(&realtime, &peer, None, Some(media), false, None, None, None)
+                        .await?;
+                ctx.remember_sent(&peer);
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else {
+                    println!("Nudged {}.", peer_label_from_input(&peer));
+                }
+            }
+            MessagesCommand::Forward(args) => {
+                let MessagesForwardArgs {
+                    from_chat_id,
+                    from_user_id,
+                    message_ids,
+                    to_chat_id,
+                    to_user_id,
+                    no_header,
+                    quiet,
+                    force,
+                } = args;
+
+                if message_ids.is_empty() {
+                    return Err(CliError::missing_message_ids().into());
+                }
+                validate_message_ids_arg("--message-id", &message_ids)?;
+                // Each forwarded message is a new message in the destination.
+                if !ctx.confirm_send_count(message_ids.len(), force)? {
+                    println!("Cancelled.");
+                    return Ok(());
+                }
+
+                let from_peer = match (from_chat_id, from_user_id) {
+                    (Some(_), Some(_)) => {
+                        return Err(CliError::invalid_args(
+                            "Provide only one of --from-chat-id or --from-user-id",
+                        )
+                        .into());
+                    }
+                    (Some(chat_id), None) => {
+                        let chat_id = validate_positive_id_arg("--from-chat-id", chat_id)?;
+                        proto::InputPeer {
+                            r#type: Some(proto::input_peer::Type::Chat(proto::InputPeerChat {
+                                chat_id,
+                            })),
+                        }
+                    }
+                    (None, Some(user_id)) => {
+                        let user_id = validate_positive_id_arg("--from-user-id", user_id)?;
+                        proto::InputPeer {
+                            r#type: Some(proto::input_peer::Type::User(proto::InputPeerUser {
+                                user_id,
+                            })),
+                        }
+                    }
+                    (None, None) => return Err(CliError::missing_forward_source().into()),
+                };
+
+                let to_peer = match (to_chat_id, to_user_id) {
+                    (Some(_), Some(_)) => {
+                        return Err(CliError::invalid_args(
+                            "Provide only one of --to-chat-id or --to-user-id",
+                        )
+                        .into());
+                    }
+                    (Some(chat_id), None) => {
+                        let chat_id = validate_positive_id_arg("--to-chat-id", chat_id)?;
+                        proto::InputPeer {
+                            r#type: Some(proto::input_peer::Type::Chat(proto::InputPeerChat {
+                                chat_id,
+                            })),
+                        }
+                    }
+                    (None, Some(user_id)) => {
+                        let user_id = validate_positive_id_arg("--to-user-id", user_id)?;
+                        proto::InputPeer {
+                            r#type: Some(proto::input_peer::Type::User(proto::InputPeerUser {
+                                user_id,
+                            })),
+                        }
+                    }
+                    (None, None) => return Err(CliError::missing_forward_destination().into()),
+                };
+
+                let from_label = peer_label_from_input(&from_peer);
+                let to_label = peer_label_from_input(&to_peer);
+                let message_count = message_ids.len();
+                let share_forward_header = if no_header { Some(false) } else { None };
+
+                let realtime = ctx.realtime().await?;
+                let input = proto::ForwardMessagesInput {
+                    from_peer_id: Some(from_peer),
+                    message_ids,
+                    to_peer_id: Some(to_peer.clone()),
+                    share_forward_header,
+                };
+                let payload = realtime.call(input).await?;
+                ctx.remember_sent(&to_peer);
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else if quiet {
+                    print_new_message_ids("forwardMessages", &payload.updates)?;
+                } else {
+                    println!(
+                        "Forwarded {} message(s) from {} to {} (updates: {}).",
+                        message_count,
+                        from_label,
+                        to_label,
+                        payload.updates.len()
+                    );
+                }
+            }
+            MessagesCommand::Export(args) => {
+                export_messages(ctx, args, MessageExportFormat::Json).await?;
+            }
+            MessagesCommand::Transcript(args) => {
+                export_messages(ctx, args.into(), MessageExportFormat::Markdown).await?;
+            }
+            MessagesCommand::Download(args) => {
+                if args.message_ids.is_empty() && args.from_msg_id.is_none() {
+                    return Err(CliError::missing_message_ids().into());
+                }
+                if args.limit.is_some() && args.from_msg_id.is_none() {
+                    return Err(CliError::invalid_args(
+                        "--limit requires --from-msg-id for downloads",
+                    )
+                    .into());
+                }
+                let message_ids = if args.message_ids.is_empty() {
+                    Vec::new()
+                } else {
+                    parse_message_id_selectors("--message-id", &args.message_ids)?
+                };
+                let from_msg_id =
+                    validate_optional_message_id_arg("--from-msg-id", args.from_msg_id)?;
+                let limit = validate_message_limit(args.limit)?;
+                let parallel = validate_parallel(args.parallel)?;
+                let history_window_download = from_msg_id.is_some();
+                let batch_download = history_window_download || message_ids.len() > 1;
+                if batch_download && args.output.is_some() {
+                    return Err(CliError::invalid_args(
+                        "--output can only be used with one --message-id; use --dir for batch or history-window downloads",
+                    )
+                    .into());
+                }
+                if batch_download && args.dir.is_none() {
+                    return Err(CliError::invalid_args(
+                        "Batch and history-window downloads require --dir so every file has a destination directory",
+                    )
+                    .into());
+                }
+                let peer = ctx
+                    .resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+                    .await?;
+                if let Some(output) = args.output.as_ref() {
+                    validate_output_file_path_arg("--output", output)?;
+                }
+                if let Some(dir) = args.dir.as_ref() {
+                    validate_output_dir_path_arg("--dir", dir)?;
+                }
+                let realtime = ctx.realtime().await?;
+                let (messages, missing_message_ids) = if let Some(from_msg_id) = from_msg_id {
+                    (
+                        fetch_history_window(
+                            &realtime,
+                            &peer,
+                            Some(from_msg_id),
+                            limit,
+                            None,
+                            None,
+                        )
+                        .await?,
+                        Vec::new(),
+                    )
+                } else {
+                    fetch_messages_by_ids(&realtime, &peer, &message_ids).await?
+                };
+                if !history_window_download && message_ids.len() == 1 {
+                    let message = messages.into_iter().next().ok_or_else(|| {
+                        CliError::invalid_args("Message not found for that peer.")
+                    })?;
+                    let output_path = resolve_download_path(&message, args.output, args.dir)?;
+                    let bytes = download_message_media(&message, &output_path).await?;
+                    if ctx.json {
+                        let output = DownloadOutput {
+                            path: output_path.display().to_string(),
+                            bytes,
+                        };
+                        ctx.print_json(&output)?;
+                    } else if args.quiet {
+                        println!("{}", output_path.display());
+                    } else {
+                        println!("Downloaded to {}", output_path.display());
+                    }
+                } else {
+                    let Some(dir) = args.dir else {
+                        unreachable!("batch download directory is validated before auth");
+                    };
+                    let summary = download_messages_media(&messages, &dir, parallel).await?;
+
+                    let output = DownloadBatchOutput {
+                        files: summary.files,
+                        skipped_message_ids: summary.skipped_message_ids,
+                        missing_message_ids,
+                        errors: summary.errors,
+                    };
+                    if ctx.json {
+                        ctx.print_json(&output)?;
+                    } else if args.quiet {
+                        for file in &output.files {
+                            println!("{}", file.path);
+                        }
+                        print_download_errors(&output.errors);
+                    } else {
+                        print_download_batch_summary(&output, &dir);
+                    }
+                    ctx.report_completion(format!(
+                        "{} file(s) downloaded, {} failed",
+                        output.files.len(),
+                        output.errors.len()
+                    ));
+                }
+            }
+            MessagesCommand::View(args) => {
+                if ctx.json {
+                    return Err(CliError::invalid_args(
+                        "messages view draws in the terminal and has no --json output; use `inline messages download --json` instead",
+                    )
+                    .into());
+                }
+                let message_id = validate_message_id_arg("--message-id", args.message_id)?;
+                let protocol = ImageProtocol::parse(&args.protocol)?;
+                let peer = ctx
+                    .resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+                    .await?;
+                let realtime = ctx.realtime().await?;
+                let (messages, _) = fetch_messages_by_ids(&realtime, &peer, &[message_id]).await?;
+                let message = messages
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| CliError::invalid_args("Message not found for that peer."))?;
+                let preview_dir = ctx.config.data_dir.join("previews");
+                fs::create_dir_all(&preview_dir)?;
+                let path = resolve_batch_download_path(&message, &preview_dir)?;
+                download_message_media(&message, &path).await?;
+                let is_photo = matches!(
+                    message
+                        .media
+                        .as_ref()
+                        .and_then(|media| media.media.as_ref()),
+                    Some(proto::message_media::Media::Photo(_))
+                );
+                let result = if is_photo {
+                    let protocol =
+                        protocol.unwrap_or_else(|| detect_image_protocol(|key| env::var(key).ok()));
+                    render_photo(&path, protocol, args.width).map(|_| ())
+                } else {
+                    eprintln!("Opening {} in Quick Look...", path.display());
+                    quick_look(&path)
+                };
+                let _ = fs::remove_file(&path);
+                result?;
+            }
+            MessagesCommand::Delete(args) => {
+                if args.message_ids.is_empty() {
+                    return Err(CliError::missing_message_ids().into());
+                }
+                validate_message_ids_arg("--message-id", &args.message_ids)?;
+                let peer = ctx
+                    .resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+                    .await?;
+                let message_count = args.message_ids.len();
+                let prompt = format!(
+                    "Delete {} message(s) from {} for everyone?",
+                    message_count,
+                    peer_label_from_input(&peer)
+                );
+                if ctx.json && !args.yes {
+                    return Err(CliError::confirmation_required().into());
+                }
+                // Fail on missing credentials before prompting.
+                ctx.token()?;
+                if !confirm_action(&prompt, args.yes)? {
+                    println!("Cancelled.");
+                    return Ok(());
+                }
+                let realtime = ctx.realtime().await?;
+                let input = proto::DeleteMessagesInput {
+                    message_ids: args.message_ids.clone(),
+                    peer_id: Some(peer),
+                };
+                let payload = realtime.call(input).await?;
+                let deleted = deleted_message_ids(&payload.updates);
+                let mut not_deleted = Vec::new();
+                if let Some(deleted) = &deleted {
+                    for id in &args.message_ids {
+                        if !deleted.contains(id) && !not_deleted.contains(id) {
+                            not_deleted.push(*id);
+                        }
+                    }
+                }
+                if ctx.json {
+                    ctx.print_json(&DeleteMessagesOutput {
+                        result: payload,
+                        for_everyone: true,
+                        deleted_message_ids: deleted,
+                        not_deleted_message_ids: not_deleted,
+                    })?;
+                } else {
+                    match deleted {
+                        Some(deleted) => {
+                            println!(
+                                "Deleted {} message(s) for everyone: {}.",
+                                deleted.len(),
+                                join_ids(&deleted)
+                            );
+                            if !not_deleted.is_empty() {
+                                println!(
+                                    "Not deleted (already gone or not yours to delete): {}.",
+                                    join_ids(&not_deleted)
+                                );
+                            }
+                        }
+                        None => println!("Deleted {message_count} message(s) for everyone."),
+                    }
+                }
+            }
+            MessagesCommand::Edit(args) => {
+                let message_id = validate_message_id_arg("--message-id", args.message_id)?;
+                let peer = ctx
+                    .resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+                    .await?;
+                let mut existing = None;
+                let text = if args.edit {
+                    let realtime = ctx.realtime().await?;
+                    let message = fetch_message_by_id(&realtime, &peer, message_id).await?;
+                    let current = message.message.clone().unwrap_or_default();
+                    let initial = args.text.clone().unwrap_or_else(|| current.clone());
+                    let help = [
+                        format!(
+                            "Editing message {message_id}. Save and close the editor to update it."
+                        ),
+                        "Markdown is supported. An empty or unchanged message cancels.".to_string(),
+                    ];
+                    existing = Some(message);
+                    match editor::compose(&initial, &help, &ctx.config.data_dir)? {
+                        Some(text) if text != current.trim() => text,
+                        _ => {
+                            println!("No changes; message not edited.");
+                            return Ok(());
+                        }
+                    }
+                } else {
+                    resolve_message_caption(args.text, args.stdin)?
+                        .ok_or_else(CliError::missing_text_or_stdin)?
+                };
+                let mention_entities = parse_mention_entities(&args.mentions)?;
+                let realtime = ctx.realtime().await?;
+                // Without explicit entities, carry the existing ones over so a text-only
+                // edit does not strip mentions or formatting. Markdown edits let the
+                // server derive entities from the new text instead.
+                let entities = match mention_entities {
+                    Some(entities) => Some(entities),
+                    None if args.parse_markdown => None,
+                    None => {
+                        let existing = match existing {
+                            Some(message) => message,
+                            None => fetch_message_by_id(&realtime, &peer, message_id).await?,
+                        };
+                        preserved_entities_for_edit(
+                            existing.entities.as_ref(),
+                            existing.message.as_deref().unwrap_or_default(),
+                            &text,
+                        )
+                    }
+                };
+                let input = proto::EditMessageInput {
+                    message_id,
+                    peer_id: Some(peer),
+                    text,
+                    entities,
+                    parse_markdown: args.parse_markdown.then_some(true),
+                    actions: None,
+                };
+                let payload = realtime.call(input).await?;
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else {
+                    println!("Message edited (updates: {}).", payload.updates.len());
+                }
+            }
+            MessagesCommand::AddReaction(args) => {
+                let message_id = validate_message_id_arg("--message-id", args.message_id)?;
+                let peer = ctx
+                    .resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+                    .await?;
+                let emoji = args.emoji.trim().to_string();
+                if emoji.is_empty() {
+                    return Err(CliError::invalid_args("Emoji cannot be empty").into());
+                }
+                let realtime = ctx.realtime().await?;
+                let input = proto::AddReactionInput {
+                    emoji,
+                    message_id,
+                    peer_id: Some(peer),
+                };
+                let payload = realtime.call(input).await?;
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else {
+                    println!("Reaction added (updates: {}).", payload.updates.len());
+                }
+            }
+            MessagesCommand::DeleteReaction(args) => {
+                let message_id = validate_message_id_arg("--message-id", args.message_id)?;
+                let peer = ctx
+                    .resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+                    .await?;
+                let emoji = args.emoji.trim().to_string();
+                if emoji.is_empty() {
+                    return Err(CliError::invalid_args("Emoji cannot be empty").into());
+                }
+                let realtime = ctx.realtime().await?;
+                let input = proto::DeleteReactionInput {
+                    emoji,
+                    peer_id: Some(peer),
+                    message_id,
+                };
+                let payload = realtime.call(input).await?;
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else {
+                    println!("Reaction deleted (updates: {}).", payload.updates.len());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn export_messages(
+    ctx: &CommandContext,
+    args: MessagesExportArgs,
+    default_format: MessageExportFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let limit = validate_message_limit(args.limit)?;
+    let offset_id = validate_optional_message_id_arg("--offset-id", args.offset_id)?;
+    let from_msg_id = validate_optional_message_id_arg("--from-msg-id", args.from_msg_id)?;
+    let history_offset_id = from_msg_id.or(offset_id);
+    let (since_ts, until_ts) =
+        parse_time_filters(args.since.as_deref(), args.until.as_deref(), Utc::now())?;
+    let translation_language = args
+        .translate
+        .as_deref()
+        .map(normalize_translation_language)
+        .transpose()?;
+    let peer = ctx
+        .resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+        .await?;
+    let requested_output_path = args.output;
+    let output_bundle_dir = requested_output_path
+        .as_ref()
+        .filter(|path| is_export_output_bundle_dir(path, args.download_media))
+        .cloned();
+    let format_inference_path = if output_bundle_dir.is_some() {
+        None
+    } else {
+        requested_output_path
+            .as_deref()
+            .map(strip_compression_extension)
+    };
+    let format = infer_export_format(
+        args.format,
+        format_inference_path.as_deref(),
+        default_format,
+    );
+    let compression = args.compress.or_else(|| {
+        requested_output_path
+            .as_deref()
+            .filter(|_| output_bundle_dir.is_none())
+            .and_then(Compression::from_path)
+    });
+    let output_path =
+        resolve_export_output_path(requested_output_path, output_bundle_dir.as_deref(), format)
+            .map(|path| match compression {
+                Some(compression) => compressed_path(&path, compression),
+                None => path,
+            });
+    if let Some(output_path) = output_path.as_ref() {
+        validate_output_file_path_arg("--output", output_path)?;
+    }
+    let media_download = resolve_export_media_download(
+        args.download_media,
+        args.media_dir,
+        args.parallel,
+        output_path.as_deref(),
+        output_bundle_dir.as_deref(),
+    )?;
+    if let Some((media_dir, _)) = media_download.as_ref() {
+        validate_output_dir_path_arg("--media-dir", media_dir)?;
+    }
+    let realtime = ctx.realtime().await?;
+
+    let messages = if args.message_ids.is_empty() {
+        fetch_history_window(
+            &realtime,
+            &peer,
+            history_offset_id,
+            limit,
+            since_ts,
+            until_ts,
+        )
+        .await?
+    } else {
+        let message_ids = parse_message_id_selectors("--message-id", &args.message_ids)?;
+        let (mut messages, missing_message_ids) =
+            fetch_messages_by_ids(&realtime, &peer, &message_ids).await?;
+        if !missing_message_ids.is_empty() {
+            log::warn!(
+                "{} message id(s) were not found: {}",
+                missing_message_ids.len(),
+                missing_message_ids
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            );
+        }
+        filter_messages_by_time(&mut messages, since_ts, until_ts);
+        messages
+    };
+    let translations = if let Some(language) = translation_language.as_deref() {
+        let message_ids = collect_message_ids(&messages);
+        let translations_by_id =
+            fetch_message_translations(&realtime, &peer, &message_ids, language).await?;
+        translations_in_message_order(&message_ids, &translations_by_id)
+    } else {
+        Vec::new()
+    };
+
+    let users_by_id = ctx.users_by_id().await?;
+    let chats_by_id = ctx.chats_by_id().await?;
+    let spaces_by_id = ctx
+        .chats()
+        .await?
+        .spaces
+        .iter()
+        .map(|space| (space.id, space.clone()))
+        .collect::<HashMap<_, _>>();
+    let mut warnings = Vec::new();
+    let mut related_messages_by_id = messages
+        .iter()
+        .cloned()
+        .map(|message| (message.id, message))
+        .collect::<HashMap<_, _>>();
+    let missing_reply_ids = collect_missing_reply_ids(&messages, &related_messages_by_id);
+    if !missing_reply_ids.is_empty() {
+        let (reply_messages, missing_message_ids) =
+            fetch_messages_by_ids(&realtime, &peer, &missing_reply_ids).await?;
+        for message in reply_messages {
+            related_messages_by_id.insert(message.id, message);
+        }
+        if !missing_message_ids.is_empty() {
+            warnings.push(format!(
+                "Could not resolve {} reply target(s): {}",
+                missing_message_ids.len(),
+                missing_message_ids
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ));
+        }
+    }
+
+    let mut forward_messages_by_key = HashMap::new();
+    for (source_peer, message_ids) in collect_forward_sources(&messages) {
+        let Some(input_peer) = input_peer_from_proto_peer(&source_peer) else {
+            warnings.push("Could not resolve a forwarded source peer.".to_string());
+            continue;
+        };
+        let (forward_messages, missing_message_ids) =
+            fetch_messages_by_ids(&realtime, &input_peer, &message_ids).await?;
+        for message in forward_messages {
+            if let Some(key) = forward_source_key(&source_peer, message.id) {
+                forward_messages_by_key.insert(key, message);
+            }
+        }
+        if !missing_message_ids.is_empty() {
+            warnings.push(format!(
+                "Could not resolve {} forwarded source message(s): {}",
+                missing_message_ids.len(),
+                missing_message_ids
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ));
+        }
+    }
+
+    let export_peer = export_peer_from_input_peer(&peer, users_by_id, chats_by_id);
+    let message_count = messages.len();
+    let media_download_summary = if let Some((media_dir, parallel)) = media_download.as_ref() {
+        download_messages_media(&messages, media_dir, *parallel).await?
+    } else {
+        MediaDownloadSummary::default()
+    };
+    for error in &media_download_summary.errors {
+        warnings.push(format!(
+            "Could not download media for message {}: {}",
+            error.message_id, error.error
+        ));
+    }
+    let media_paths_by_message_id = media_download_summary
+        .files
+        .iter()
+        .map(|file| (file.message_id, file.path.clone()))
+        .collect::<HashMap<_, _>>();
+    let mut bundle = build_message_export_bundle(MessageExportBuildInput {
+        peer: export_peer,
+        messages,
+        users_by_id,
+        chats_by_id,
+        spaces_by_id: &spaces_by_id,
+        related_messages_by_id: &related_messages_by_id,
+        forward_messages_by_key: &forward_messages_by_key,
+        translations,
+        warnings,
+    });
+    apply_media_local_paths(&mut bundle, &media_paths_by_message_id);
+    let payload_text = render_export(&bundle, format, ctx.json_format)?;
+    let bytes = payload_text.len();
+    let media_file_count = media_download_summary.files.len();
+    if let Some(output_path) = output_path {
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let compressed_bytes = match compression {
+            Some(compression) => Some(write_compressed(
+                &output_path,
+                payload_text.as_bytes(),
+                compression,
+            )?),
+            None => {
+                fs::write(&output_path, payload_text.as_bytes())?;
+                None
+            }
+        };
+        if ctx.json {
+            let output = ExportOutput {
+                path: output_path.display().to_string(),
+                format: format.as_str().to_string(),
+                messages: message_count,
+                bytes,
+                compression: compression.map(Compression::as_str),
+                compressed_bytes,
+                media_files: media_download_summary.files.clone(),
+                skipped_message_ids: media_download_summary.skipped_message_ids.clone(),
+                media_errors: media_download_summary.errors.clone(),
+            };
+            ctx.print_json(&output)?;
+        } else if let Some((media_dir, _)) = media_download.as_ref() {
+            print_export_media_summary(
+                message_count,
+                format,
+                &output_path,
+                media_dir,
+                &media_download_summary,
+            );
+        } else {
+            println!(
+                "Exported {} message(s) as {}{} to {}.",
+                message_count,
+                format.as_str(),
+                compression
+                    .map(|compression| format!(" ({})", compression.as_str()))
+                    .unwrap_or_default(),
+                output_path.display()
+            );
+        }
+    } else {
+        print!("{payload_text}");
+        if let Some((media_dir, _)) = media_download.as_ref() {
+            eprintln!(
+                "Downloaded {} media file(s) to {}.{}{}",
+                media_file_count,
+                media_dir.display(),
+                skipped_suffix(media_download_summary.skipped_message_ids.len()),
+                failed_suffix(media_download_summary.errors.len())
+            );
+            print_download_errors(&media_download_summary.errors);
+        }
+    }
+    let mut summary = format!("{message_count} message(s)");
+    if media_download.is_some() {
+        summary.push_str(&format!(", {media_file_count} media file(s)"));
+        if !media_download_summary.errors.is_empty() {
+            summary.push_str(&format!(", {} failed", media_download_summary.errors.len()));
+        }
+    }
+    ctx.report_completion(summary);
+    Ok(())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportOutput {
+    path: String,
+    format: String,
+    messages: usize,
+    /// Size of the export before compression.
+    bytes: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compression: Option<&'static str>,
+    /// Size on disk when compressed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compressed_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    media_files: Vec<DownloadedFileOutput>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped_message_ids: Vec<i64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    media_errors: Vec<DownloadErrorOutput>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadOutput {
+    path: String,
+    bytes: u64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadedFileOutput {
+    message_id: i64,
+    path: String,
+    bytes: u64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadErrorOutput {
+    message_id: i64,
+    error: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadBatchOutput {
+    files: Vec<DownloadedFileOutput>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped_message_ids: Vec<i64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    missing_message_ids: Vec<i64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<DownloadErrorOutput>,
+}
+
+#[derive(Default)]
+struct MediaDownloadSummary {
+    files: Vec<DownloadedFileOutput>,
+    skipped_message_ids: Vec<i64>,
+    errors: Vec<DownloadErrorOutput>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TranslatedChatHistoryOutput {
+    #[serde(flatten)]
+    payload: proto::GetChatHistoryResult,
+    translations: Vec<proto::MessageTranslation>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TranslatedSearchMessagesOutput {
+    #[serde(flatten)]
+    payload: proto::SearchMessagesResult,
+    translations: Vec<proto::MessageTranslation>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchMessagesContextOutput {
+    #[serde(flatten)]
+    payload: proto::SearchMessagesResult,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    translations: Option<Vec<proto::MessageTranslation>>,
+    /// One entry per hit, or per run of hits whose windows overlap.
+    context: Vec<HitContext>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TranslatedMessageOutput {
+    #[serde(flatten)]
+    message: proto::Message,
+    translations: Vec<proto::MessageTranslation>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MessagesGetBatchOutput {
+    messages: Vec<proto::Message>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    missing_message_ids: Vec<i64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    translations: Vec<proto::MessageTranslation>,
+}
+
+fn require_stdin_pipe(stdin_is_terminal: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if stdin_is_terminal {
+        Err(CliError::stdin_not_piped().into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Makes sure the preview URL is part of the text, since previews are generated
+/// from links in the message body.
+fn append_link_preview_url(caption: Option<String>, url: &str) -> Result<String, CliError> {
+    let url = url.trim();
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        return Err(CliError::invalid_args(
+            "--link-preview-url must be an http(s) URL",
+        ));
+    }
+    Ok(match caption {
+        Some(text) if text.contains(url) => text,
+        Some(text) => format!("{text}\n{url}"),
+        None => url.to_string(),
+    })
+}
+
+pub(super) fn resolve_message_caption(
+    text: Option<String>,
+    stdin: bool,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    if stdin {
+        require_stdin_pipe(std::io::stdin().is_terminal())?;
+        use std::io::Read;
+        let mut buffer = String::new();
+        std::io::stdin().read_to_string(&mut buffer)?;
+        let trimmed = buffer.trim();
+        if trimmed.is_empty() {
+            return Err(CliError::invalid_args("stdin was empty").into());
+        }
+        return Ok(Some(trimmed.to_string()));
+    }
+
+    if let Some(text) = text {
+        let trimmed = text.trim();
+        if trimmed.is_empty() {
+            return Err(CliError::invalid_args("message text is empty").into());
+        }
+        return Ok(Some(trimmed.to_string()));
+    }
+
+    Ok(None)
+}
+
+/// `--from-file`: the body becomes `--text`, and front matter fills in the
+/// destination, `reply_to`, and mentions. Flags on the command line win; a
+/// destination in the file is ignored when one was passed.
+fn apply_message_file(args: &mut MessagesSendArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(path) = args.from_file.clone() else {
+        return Ok(());
+    };
+    let contents = if path.as_os_str() == "-" {
+        require_stdin_pipe(std::io::stdin().is_terminal())?;
+        use std::io::Read;
+        let mut buffer = String::new();
+        std::io::stdin().read_to_string(&mut buffer)?;
+        buffer
+    } else {
+        fs::read_to_string(&path).map_err(|err| {
+            CliError::invalid_args(format!("could not read {}: {err}", path.display()))
+        })?
+    };
+    let document = front_matter::split(&contents)
+        .map_err(|err| CliError::invalid_args(format!("{}: {err}", path.display())))?;
+    let body = document.body.trim();
+    let invalid = |key: &str, message: String| {
+        CliError::invalid_args(format!("{}: `{key}` {message}", path.display()))
+    };
+    let parse_id = |key: &str, value: front_matter::Value| match value {
+        front_matter::Value::Scalar(value) => value
+            .parse::<i64>()
+            .map_err(|_| invalid(key, format!("must be a number, got `{value}`"))),
+        front_matter::Value::List(_) => Err(invalid(key, "must be a single id".to_string())),
+    };
+
+    let mut to = Vec::new();
+    let mut chat_id = None;
+    let mut user_id = None;
+    for (key, value) in document.fields {
+        match key.as_str() {
+            "to" => to = value.into_list(),
+            "chat" => chat_id = Some(parse_id(&key, value)?),
+            "user" => user_id = Some(parse_id(&key, value)?),
+            "reply_to" => {
+                let reply_to = parse_id(&key, value)?;
+                args.reply_to.get_or_insert(reply_to);
+            }
+            "mentions" => {
+                for mention in value.into_list() {
+                    let mention =
+                        locate_mention(&mention, body).map_err(|message| invalid(&key, message))?;
+                    args.mentions.push(mention);
+                }
+            }
+            _ => {
+                return Err(invalid(
+                    &key,
+                    "is not a known field (use to, chat, user, reply_to, or mentions)".to_string(),
+                )
+                .into());
+            }
+        }
+    }
+    let destinations = usize::from(!to.is_empty())
+        + usize::from(chat_id.is_some())
+        + usize::from(user_id.is_some());
+    if destinations > 1 {
+        return Err(CliError::invalid_args(format!(
+            "{}: set only one of `to`, `chat`, or `user`",
+            path.display()
+        ))
+        .into());
+    }
+    if args.to.is_empty() && args.chat_id.is_none() && args.user_id.is_none() {
+        args.to = to;
+        args.chat_id = chat_id;
+        args.user_id = user_id;
+    }
+    if !body.is_empty() {
+        args.text = Some(body.to_string());
+    }
+    Ok(())
+}
+
+/// The commented header `messages send --edit` puts above the text.
+fn send_editor_help(args: &MessagesSendArgs) -> Vec<String> {
+    let mut help = vec![
+        "Write the message below the line, then save and close the editor to send it.".to_string(),
+        "Markdown is supported. An empty message cancels.".to_string(),
+    ];
+    let destination = if !args.to.is_empty() {
+        Some(args.to.join(", "))
+    } else if let Some(chat_id) = args.chat_id {
+        Some(format!("chat:{chat_id}"))
+    } else {
+        args.user_id.map(|user_id| format!("user:{user_id}"))
+    };
+    if let Some(destination) = destination {
+        help.push(format!("To: {destination}"));
+    }
+    if let Some(reply_to) = args.reply_to {
+        help.push(format!("Replying to message {reply_to}"));
+    }
+    help
+}
+
+/// A front-matter mention: `USER_ID:OFFSET:LENGTH` as with `--mention`, or
+/// `USER_ID:TEXT` for the first occurrence of TEXT in the body.
+fn locate_mention(mention: &str, body: &str) -> Result<String, String> {
+    let Some((user_id, target)) = mention.split_once(':') else {
+        return Err(format!(
+            "entry `{mention}` must be USER_ID:TEXT or USER_ID:OFFSET:LENGTH"
+        ));
+    };
+    let is_range = target.split_once(':').is_some_and(|(offset, length)| {
+        offset.trim().parse::<i64>().is_ok() && length.trim().parse::<i64>().is_ok()
+    });
+    if is_range {
+        return Ok(mention.to_string());
+    }
+    let target = target.trim();
+    let start = body
+        .find(target)
+        .filter(|_| !target.is_empty())
+        .ok_or_else(|| format!("entry `{mention}`: `{target}` isn't in the message"))?;
+    let offset = body[..start].encode_utf16().count();
+    let length = target.encode_utf16().count();
+    Ok(format!("{}:{offset}:{length}", user_id.trim()))
+}
+
+/// What `messages send` sends, validated and with attachments ready to upload.
+struct SendContent {
+    token: String,
+    caption: Option<String>,
+    has_link: Option<bool>,
+    mention_entities: Option<proto::MessageEntities>,
+    attachments: Vec<PreparedAttachment>,
+}
+
+fn prepare_send_content(
+    ctx: &CommandContext,
+    args: &MessagesSendArgs,
+) -> Result<SendContent, Box<dyn std::error::Error>> {
+    let mut caption = resolve_message_caption(args.text.clone(), args.stdin)?;
+    let has_link = if args.no_link_preview {
+        Some(false)
+    } else if let Some(url) = args.link_preview_url.as_deref() {
+        caption = Some(append_link_preview_url(caption, url)?);
+        Some(true)
+    } else {
+        None
+    };
+    let mention_entities = parse_mention_entities(&args.mentions)?;
+    if mention_entities.is_some() && caption.is_none() {
+        return Err(CliError::mentions_require_text().into());
+    }
+    let captures = [
+        (args.paste, CaptureSource::Clipboard),
+        (args.screenshot, CaptureSource::Screenshot),
+    ]
+    .into_iter()
+    .filter_map(|(enabled, source)| enabled.then_some(source))
+    .collect::<Vec<_>>();
+    if args.attachments.is_empty() && captures.is_empty() && caption.is_none() {
+        return Err(CliError::invalid_args(
+            "Missing required argument: provide --text/--message/--msg, --stdin, --from-file, --attach, --paste, or --screenshot",
+        )
+        .into());
+    }
+    // With --transcode, oversized videos are shrunk first; the limit is
+    // checked again on the file that is actually uploaded.
+    let max_input_bytes = if args.transcode {
+        u64::MAX
+    } else {
+        MAX_ATTACHMENT_BYTES
+    };
+    validate_attachment_inputs(&args.attachments, max_input_bytes)?;
+    let token = ctx.token()?;
+    let mut attachments = prepare_attachments(
+        &args.attachments,
+        &ctx.config.data_dir,
+        &AttachmentOptions {
+            force_file: args.force_file,
+            zip: ZipOptions {
+                exclude: args.zip_exclude.clone(),
+                name: args.zip_name.clone(),
+                respect_gitignore: args.respect_gitignore,
+            },
+            photos: PhotoOptions {
+                max_dimension: args.max_dimension,
+                original: args.original,
+            },
+            transcode: args.transcode,
+        },
+        ctx.json || args.quiet,
+    )?;
+    for source in captures {
+        if !ctx.json && source == CaptureSource::Screenshot {
+            eprintln!("Select an area to capture (Esc to cancel)...");
+        }
+        let path = capture_image(source, &ctx.config.data_dir)?;
+        attachments.push(prepare_captured_attachment(
+            path,
+            source.file_name(),
+            args.force_file,
+            ctx.json || args.quiet,
+        )?);
+    }
+    Ok(SendContent {
+        token,
+        caption,
+        has_link,
+        mention_entities,
+        attachments,
+    })
+}
+
+/// Interval between read-backs while waiting for a sent message to show up.
+const DELIVERY_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+fn parse_wait_timeout(args: &MessagesSendArgs) -> Result<Option<Duration>, CliError> {
+    if !args.wait_delivered {
+        return Ok(None);
+    }
+    parse_duration(&args.wait_timeout)
+        .map(Some)
+        .map_err(|err| CliError::invalid_args(format!("--wait-timeout: {err}")))
+}
+
+/// Returns once every message a send created can be read back from the
+/// server. The ids come from the send result, or from the next pushed update
+/// for an outgoing message in `peer` when the result carried none.
+async fn wait_for_delivery(
+    realtime: &RealtimeSession,
+    events: &mut RealtimeEventReceiver,
+    peer: &proto::InputPeer,
+    updates: &[proto::Update],
+    timeout: Duration,
+) -> Result<Vec<i64>, Box<dyn std::error::Error>> {
+    let deadline = Instant::now() + timeout;
+    let mut message_ids = new_message_ids(updates);
+    while message_ids.is_empty() {
+        let pushed = tokio::time::timeout_at(deadline, next_updates(events))
+            .await
+            .map_err(|_| {
+                CliError::delivery_unconfirmed(format!(
+                    "no message id arrived within {}s",
+                    timeout.as_secs()
+                ))
+            })??;
+        message_ids = pushed
+            .iter()
+            .filter_map(|update| match update.update.as_ref() {
+                Some(proto::update::Update::UpdateMessageId(update)) => Some(update.message_id),
+                Some(proto::update::Update::NewMessage(update)) => update
+                    .message
+                    .as_ref()
+                    .filter(|message| message.out && message_in_peer(message, peer))
+                    .map(|message| message.id),
+                _ => None,
+            })
+            .collect();
+    }
+    loop {
+        let (_, missing) = fetch_messages_by_ids(realtime, peer, &message_ids).await?;
+        if missing.is_empty() {
+            return Ok(message_ids);
+        }
+        if Instant::now() + DELIVERY_POLL_INTERVAL > deadline {
+            return Err(CliError::delivery_unconfirmed(format!(
+                "message(s) {} not readable after {}s",
+                missing
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                timeout.as_secs()
+            ))
+            .into());
+        }
+        tokio::time::sleep(DELIVERY_POLL_INTERVAL).await;
+    }
+}
+
+/// Ids in the `deleteMessages` updates of a reply, or `None` when it has none.
+fn deleted_message_ids(updates: &[proto::Update]) -> Option<Vec<i64>> {
+    let mut deleted = None::<Vec<i64>>;
+    for update in updates {
+        if let Some(proto::update::Update::DeleteMessages(update)) = &update.update {
+            let ids = deleted.get_or_insert_with(Vec::new);
+            for id in &update.message_ids {
+                if !ids.contains(id) {
+                    ids.push(*id);
+                }
+            }
+        }
+    }
+    deleted
+}
+
+fn join_ids(ids: &[i64]) -> String {
+    ids.iter()
+        .map(i64::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn message_in_peer(message: &proto::Message, peer: &proto::InputPeer) -> bool {
+    match &peer.r#type {
+        Some(proto::input_peer::Type::Chat(chat)) => message.chat_id == chat.chat_id,
+        Some(proto::input_peer::Type::User(user)) => matches!(
+            message.peer_id.as_ref().and_then(|peer| peer.r#type.as_ref()),
+            Some(proto::peer::Type::User(peer)) if peer.user_id == user.user_id
+        ),
+        _ => false,
+    }
+}
+
+/// Destinations above this many need `--yes` or a confirmation prompt.
+const SEND_CONFIRM_OVER: usize = 5;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SendDestinationResult {
+    to: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    peer: Option<PeerSummary>,
+    /// `sent`, `failed`, or `already_sent` (skipped for `--idempotency-key`).
+    status: &'static str,
+    message_ids: Vec<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SendToManyOutput {
+    sent: usize,
+    already_sent: usize,
+    failed: usize,
+    results: Vec<SendDestinationResult>,
+}
+
+/// `proto::MessageSendMode` for the send flags; `None` sends normally.
+fn send_mode(args: &MessagesSendArgs) -> Option<i32> {
+    args.silent
+        .then_some(proto::MessageSendMode::ModeSilent as i32)
+}
+
+/// `messages send` with several `--to`: one connection, the same content to
+/// each destination, and a result per destination instead of stopping at the
+/// first failure.
+async fn send_to_many(
+    ctx: &CommandContext,
+    args: MessagesSendArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if args.reply_to.is_some() {
+        return Err(
+            CliError::invalid_args("--reply-to can't be combined with more than one --to").into(),
+        );
+    }
+    let wait_timeout = parse_wait_timeout(&args)?;
+    // Deduplicated by peer, so an alias and the chat it names only get one send.
+    let mut targets: Vec<(String, proto::InputPeer)> = Vec::with_capacity(args.to.len());
+    for to in &args.to {
+        let peer = ctx.resolve_peer(Some(to), None, None).await?;
+        if !targets.iter().any(|(_, known)| *known == peer) {
+            targets.push((to.clone(), peer));
+        }
+    }
+    let needs_confirmation = targets.len() > SEND_CONFIRM_OVER;
+    if needs_confirmation && ctx.json && !args.yes {
+        return Err(CliError::confirmation_required().into());
+    }
+    let content = prepare_send_content(ctx, &args)?;
+    // Every attachment goes out as its own message, to every destination.
+    let count = targets.len() * content.attachments.len().max(1);
+    if !ctx.confirm_send_count(count, args.force)? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+    // Already asked about above, with the message count.
+    let needs_confirmation = needs_confirmation && (args.force || !ctx.exceeds_send_limit(count));
+    if needs_confirmation
+        && !confirm_action(
+            &format!("Send this message to {} chats?", targets.len()),
+            args.yes,
+        )?
+    {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let realtime = ctx.realtime().await?;
+    let mut events = wait_timeout.map(|_| realtime.subscribe());
+    let mut results = Vec::with_capacity(targets.len());
+    let key = args.idempotency_key.as_deref();
+    // Filled by the first destination, so each file is only uploaded once.
+    let mut uploads = vec![None; content.attachments.len()];
+    for (to, peer) in targets {
+        if let Some(key) = key
+            && let Some(message_ids) = already_sent(ctx, key, &peer)?
+        {
+            results.push(SendDestinationResult {
+                to,
+                peer: peer_summary_from_input(&peer),
+                status: "already_sent",
+                message_ids,
+                error: None,
+            });
+            continue;
+        }
+        let sent = if content.attachments.is_empty() {
+            send_message_with_random_id(
+                &realtime,
+                &peer,
+                content.caption.clone(),
+                None,
+                true,
+                None,
+                content.mention_entities.clone(),
+                content.has_link,
+                key.map(|key| idempotency::random_id(key, &peer, 0)),
+                send_mode(&args),
+            )
+            .await
+            .map(|payload| payload.updates)
+        } else {
+            send_messages_with_attachments(
+                &ctx.api,
+                &realtime,
+                &content.token,
+                &peer,
+                content.caption.clone(),
+                None,
+                content.mention_entities.clone(),
+                content.has_link,
+                send_mode(&args),
+                &content.attachments,
+                None,
+                true,
+                key,
+                &ctx.local_db,
+                &mut uploads,
+            )
+            .await
+            .map(|output| output.result.updates)
+        };
+        let sent = match (sent, events.as_mut(), wait_timeout) {
+            (Ok(updates), Some(events), Some(timeout)) => {
+                wait_for_delivery(&realtime, events, &peer, &updates, timeout).await
+            }
+            (sent, _, _) => sent.map(|updates| new_message_ids(&updates)),
+        };
+        let (status, message_ids, error) = match sent {
+            Ok(message_ids) => {
+                record_sent(ctx, key, &peer, message_ids.clone())?;
+                ctx.remember_sent(&peer);
+                ("sent", message_ids, None)
+            }
+            Err(error) => (
+                "failed",
+                Vec::new(),
+                Some(error.to_string().trim().to_string()),
+            ),
+        };
+        results.push(SendDestinationResult {
+            to,
+            peer: peer_summary_from_input(&peer),
+            status,
+            message_ids,
+            error,
+        });
+    }
+    let count = |status: &str| {
+        results
+            .iter()
+            .filter(|result| result.status == status)
+            .count()
+    };
+    let (sent, already_sent, failed) = (count("sent"), count("already_sent"), count("failed"));
+    let total = results.len();
+
+    if ctx.json {
+        ctx.print_json(&SendToManyOutput {
+            sent,
+            already_sent,
+            failed,
+            results,
+        })?;
+    } else if args.quiet {
+        for id in results.iter().flat_map(|result| &result.message_ids) {
+            println!("{id}");
+        }
+    } else {
+        print_send_results(&results);
+    }
+    if failed > 0 {
+        return Err(CliError::broadcast_failed(failed, total).into());
+    }
+    Ok(())
+}
+
+/// Message ids of an earlier confirmed send with `key` to `peer`.
+fn already_sent(
+    ctx: &CommandContext,
+    key: &str,
+    peer: &proto::InputPeer,
+) -> Result<Option<Vec<i64>>, Box<dyn std::error::Error>> {
+    let state = ctx.local_db.load()?;
+    Ok(idempotency::find(&state, key, peer).map(|send| send.message_ids.clone()))
+}
+
+/// Remembers a confirmed send right away, so a run that fails later still
+/// skips it next time.
+fn record_sent(
+    ctx: &CommandContext,
+    key: Option<&str>,
+    peer: &proto::InputPeer,
+    message_ids: Vec<i64>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(key) = key else {
+        return Ok(());
+    };
+    let mut state = ctx.local_db.load()?;
+    idempotency::record(
+        &mut state,
+        key,
+        peer,
+        message_ids,
+        current_epoch_seconds() as i64,
+    );
+    ctx.local_db.save(&state)?;
+    Ok(())
+}
+
+fn print_send_results(results: &[SendDestinationResult]) {
+    let to_width = results
+        .iter()
+        .map(|result| result.to.chars().count())
+        .max()
+        .unwrap_or_default()
+        .max(2);
+    let status_width = results
+        .iter()
+        .map(|result| result.status.len())
+        .max()
+        .unwrap_or_default()
+        .max("status".len());
+    println!(
+        "{}",
+        output::style_heading(&format!(
+            "{:<to_width$}  {:<status_width$}  message id / error",
+            "to", "status"
+        ))
+    );
+    for result in results {
+        let detail = match &result.error {
+            Some(error) => error.lines().next().unwrap_or_default().to_string(),
+            None => result
+                .message_ids
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        println!(
+            "{:<to_width$}  {:<status_width$}  {detail}",
+            result.to, result.status
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn send_messages_with_attachments(
+    api: &ApiClient,
+    realtime: &RealtimeSession,
+    token: &str,
+    peer: &proto::InputPeer,
+    caption: Option<String>,
+    reply_to_msg_id: Option<i64>,
+    mention_entities: Option<proto::MessageEntities>,
+    has_link: Option<bool>,
+    send_mode: Option<i32>,
+    attachments: &[PreparedAttachment],
+    peer_summary: Option<PeerSummary>,
+    json: bool,
+    idempotency_key: Option<&str>,
+    local_db: &LocalDb,
+    // Earlier uploads of `attachments` by index; reused instead of uploading
+    // again and filled in by this call.
+    uploads: &mut [Option<UploadFileResult>],
+) -> Result<SendAttachmentsOutput, Box<dyn std::error::Error>> {
+    let total = attachments.len();
+    let mut updates = Vec::new();
+    let mut message_ids = Vec::with_capacity(total);
+    let mut sent = Vec::with_capacity(total);
+    for (idx, attachment) in attachments.iter().enumerate() {
+        let sha256 = attachment.sha256()?;
+        let upload = match &uploads[idx] {
+            Some(upload) => upload.clone(),
+            None => {
+                if !json {
+                    println!(
+                        "Uploading ({}/{}) {}...",
+                        idx + 1,
+                        total,
+                        attachment.display_name
+                    );
+                }
+                let upload = api.upload_file(token, attachment.to_upload_input()).await?;
+                uploads[idx] = Some(upload.clone());
+                upload
+            }
+        };
+
+        let media = input_media_from_upload(&upload)?;
+        let random_id = idempotency_key
+            .map(|key| idempotency::random_id(key, peer, idx))
+            .unwrap_or_else(|| OsRng.next_u64() as i64);
+        let send = match send_message_with_random_id(
+            realtime,
+            peer,
+            caption.clone(),
+            Some(media.clone()),
+            caption.is_some(),
+            reply_to_msg_id,
+            mention_entities.clone(),
+            has_link,
+            Some(random_id),
+            send_mode,
+        )
+        .await
+        {
+            Ok(send) => send,
+            Err(err) => {
+                // The upload is done; keep it so `inline resend pending` can
+                // finish the send without uploading again.
+                let failed_at = current_epoch_seconds() as i64;
+                let entry = PendingSend {
+                    id: 0,
+                    peer: peer.clone(),
+                    media,
+                    caption: caption.clone(),
+                    parse_markdown: caption.is_some(),
+                    reply_to_msg_id,
+                    entities: mention_entities.clone(),
+                    has_link,
+                    send_mode,
+                    random_id,
+                    idempotency_key: idempotency_key.map(str::to_string),
+                    file_name: attachment.file_name.clone(),
+                    size_bytes: attachment.size_bytes,
+                    failed_at,
+                    attempts: 1,
+                    last_error: err.to_string(),
+                };
+                let mut state = local_db.load()?;
+                let id = pending_sends::record(&mut state, entry, failed_at);
+                local_db.save(&state)?;
+                if !json {
+                    eprintln!(
+                        "{} uploaded but wasn't sent; run `inline resend pending --id {id}` to retry without uploading again.",
+                        attachment.display_name
+                    );
+                }
+                return Err(err);
+            }
+        };
+        let message_id = new_message_ids(&send.updates).first().copied();
+        if !json {
+            match message_id {
+                Some(id) => println!(
+                    "Sent {} (id {}, sha256: {}).",
+                    attachment.display_name, id, sha256
+                ),
+                None => println!(
+                    "Sent {} (updates: {}, sha256: {}).",
+                    attachment.display_name,
+                    send.updates.len(),
+                    sha256
+                ),
+            }
+        }
+        message_ids.extend(message_id);
+        updates.extend(send.updates);
+        sent.push(SentAttachmentOutput {
+            path: attachment.display_name.clone(),
+            file_name: attachment.file_name.clone(),
+            size_bytes: attachment.size_bytes,
+            sha256,
+            file_unique_id: upload.file_unique_id,
+            message_id,
+        });
+    }
+
+    let _ = (peer_summary, caption);
+    Ok(SendAttachmentsOutput {
+        result: proto::SendMessageResult { updates },
+        message_ids,
+        messages: Vec::new(),
+        attachments: sent,
+        delivered: None,
+    })
+}
+
+/// The messages a send created, taken from its updates when the server
+/// included them and fetched otherwise.
+async fn sent_messages(
+    realtime: &RealtimeSession,
+    peer: &proto::InputPeer,
+    updates: &[proto::Update],
+    message_ids: &[i64],
+) -> Result<Vec<proto::Message>, Box<dyn std::error::Error>> {
+    let mut included: HashMap<i64, proto::Message> = updates
+        .iter()
+        .filter_map(|update| match update.update.as_ref() {
+            Some(proto::update::Update::NewMessage(update)) => update.message.clone(),
+            _ => None,
+        })
+        .map(|message| (message.id, message))
+        .collect();
+    let missing: Vec<i64> = message_ids
+        .iter()
+        .copied()
+        .filter(|id| !included.contains_key(id))
+        .collect();
+    if !missing.is_empty() {
+        let (fetched, _) = fetch_messages_by_ids(realtime, peer, &missing).await?;
+        included.extend(fetched.into_iter().map(|message| (message.id, message)));
+    }
+    Ok(message_ids
+        .iter()
+        .filter_map(|id| included.remove(id))
+        .collect())
+}
+
+/// Prints one message id per line for `--quiet`.
+pub(super) fn print_new_message_ids(
+    method: &str,
+    updates: &[proto::Update],
+) -> Result<(), CliError> {
+    let ids = new_message_ids(updates);
+    if ids.is_empty() {
+        return Err(CliError::unexpected_api_response(
+            method,
+            "missing new message id",
+        ));
+    }
+    for id in ids {
+        println!("{id}");
+    }
+    Ok(())
+}
+
+fn print_download_batch_summary(output: &DownloadBatchOutput, dir: &Path) {
+    println!(
+        "Downloaded {} file(s) to {}.{}{}{}",
+        output.files.len(),
+        dir.display(),
+        skipped_suffix(output.skipped_message_ids.len()),
+        missing_suffix(output.missing_message_ids.len()),
+        failed_suffix(output.errors.len())
+    );
+    if !output.missing_message_ids.is_empty() {
+        log::warn!(
+            "{} message id(s) were not found: {}",
+            output.missing_message_ids.len(),
+            output
+                .missing_message_ids
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+    }
+    print_download_errors(&output.errors);
+}
+
+fn print_export_media_summary(
+    message_count: usize,
+    format: MessageExportFormat,
+    output_path: &Path,
+    media_dir: &Path,
+    media_download_summary: &MediaDownloadSummary,
+) {
+    println!(
+        "Exported {} message(s) as {} to {}. Downloaded {} media file(s) to {}.{}{}",
+        message_count,
+        format.as_str(),
+        output_path.display(),
+        media_download_summary.files.len(),
+        media_dir.display(),
+        skipped_suffix(media_download_summary.skipped_message_ids.len()),
+        failed_suffix(media_download_summary.errors.len())
+    );
+    print_download_errors(&media_download_summary.errors);
+}
+
+fn skipped_suffix(count: usize) -> String {
+    if count == 0 {
+        String::new()
+    } else {
+        format!(" Skipped {count} message(s) without media.")
+    }
+}
+
+fn missing_suffix(count: usize) -> String {
+    if count == 0 {
+        String::new()
+    } else {
+        format!(" Missing {count} message(s).")
+    }
+}
+
+fn failed_suffix(count: usize) -> String {
+    if count == 0 {
+        String::new()
+    } else {
+        format!(" Failed {count} media download(s).")
+    }
+}
+
+fn print_download_errors(errors: &[DownloadErrorOutput]) {
+    for error in errors.iter().take(5) {
+        log::warn!("message {} failed: {}", error.message_id, error.error);
+    }
+    if errors.len() > 5 {
+        log::warn!("{} additional media download(s) failed.", errors.len() - 5);
+    }
+}
+
+fn collect_missing_reply_ids(
+    messages: &[proto::Message],
+    related_messages_by_id: &HashMap<i64, proto::Message>,
+) -> Vec<i64> {
+    let mut ids = Vec::new();
+    for message in messages {
+        let Some(reply_to_msg_id) = message.reply_to_msg_id else {
+            continue;
+        };
+        if related_messages_by_id.contains_key(&reply_to_msg_id) || ids.contains(&reply_to_msg_id) {
+            continue;
+        }
+        ids.push(reply_to_msg_id);
+    }
+    ids
+}
+
+fn collect_forward_sources(messages: &[proto::Message]) -> Vec<(proto::Peer, Vec<i64>)> {
+    let mut indexes = HashMap::<String, usize>::new();
+    let mut groups = Vec::<(proto::Peer, Vec<i64>)>::new();
+    for message in messages {
+        let Some(forward) = message.fwd_from.as_ref() else {
+            continue;
+        };
+        let Some(peer) = forward.from_peer_id.as_ref() else {
+            continue;
+        };
+        let Some(peer_key) = forward_peer_group_key(peer) else {
+            continue;
+        };
+        let index = if let Some(index) = indexes.get(&peer_key) {
+            *index
+        } else {
+            let index = groups.len();
+            groups.push((peer.clone(), Vec::new()));
+            indexes.insert(peer_key, index);
+            index
+        };
+        if forward.from_message_id > 0 && !groups[index].1.contains(&forward.from_message_id) {
+            groups[index].1.push(forward.from_message_id);
+        }
+    }
+    groups
+}
+
+fn forward_peer_group_key(peer: &proto::Peer) -> Option<String> {
+    match &peer.r#type {
+        Some(proto::peer::Type::Chat(chat)) => Some(format!("chat:{}", chat.chat_id)),
+        Some(proto::peer::Type::User(user)) => Some(format!("user:{}", user.user_id)),
+        None => None,
+    }
+}
+
+pub(super) fn export_peer_from_input_peer(
+    peer: &proto::InputPeer,
+    users_by_id: &HashMap<i64, proto::User>,
+    chats_by_id: &HashMap<i64, proto::Chat>,
+) -> ExportPeer {
+    match &peer.r#type {
+        Some(proto::input_peer::Type::Chat(chat)) => ExportPeer {
+            peer_type: "chat".to_string(),
+            id: chat.chat_id,
+            name: chats_by_id
+                .get(&chat.chat_id)
+                .map(|chat| chat_display_name(chat, users_by_id)),
+        },
+        Some(proto::input_peer::Type::User(user)) => ExportPeer {
+            peer_type: "user".to_string(),
+            id: user.user_id,
+            name: users_by_id.get(&user.user_id).map(user_display_name),
+        },
+        Some(proto::input_peer::Type::Self_(_)) => ExportPeer {
+            peer_type: "self".to_string(),
+            id: 0,
+            name: Some("You".to_string()),
+        },
+        None => ExportPeer {
+            peer_type: "unknown".to_string(),
+            id: 0,
+            name: None,
+        },
+    }
+}
+
+fn filter_messages_by_list_options(messages: &mut Vec<proto::Message>, args: &MessagesListArgs) {
+    if !args.has_media && !args.empty_text && !args.forwarded {
+        return;
+    }
+
+    messages.retain(|message| {
+        (!args.has_media || message_has_any_media(message))
+            && (!args.empty_text || message_has_empty_text(message))
+            && (!args.forwarded || message.fwd_from.is_some())
+    });
+}
+
+fn message_has_any_media(message: &proto::Message) -> bool {
+    message
+        .media
+        .as_ref()
+        .and_then(|media| media.media.as_ref())
+        .is_some()
+}
+
+fn message_has_empty_text(message: &proto::Message) -> bool {
+    message
+        .message
+        .as_deref()
+        .is_none_or(|text| text.trim().is_empty())
+}
+
+fn parse_mention_entities(
+    raw_mentions: &[String],
+) -> Result<Option<proto::MessageEntities>, Box<dyn std::error::Error>> {
+    if raw_mentions.is_empty() {
+        return Ok(None);
+    }
+
+    let mut entities = Vec::with_capacity(raw_mentions.len());
+    for raw in raw_mentions {
+        let parts: Vec<&str> = raw.split(':').collect();
+        if parts.len() != 3 {
+            return Err(CliError::invalid_args(format!(
+                "Invalid mention '{raw}'. Use USER_ID:OFFSET:LENGTH (offset/length are UTF-16 units)."
+            ))
+            .into());
+        }
+        let user_id: i64 = parts[0]
+            .trim()
+            .parse()
+            .map_err(|_| CliError::invalid_args(format!("Invalid mention user id in '{raw}'")))?;
+        let offset: i64 = parts[1]
+            .trim()
+            .parse()
+            .map_err(|_| CliError::invalid_args(format!("Invalid mention offset in '{raw}'")))?;
+        let length: i64 = parts[2]
+            .trim()
+            .parse()
+            .map_err(|_| CliError::invalid_args(format!("Invalid mention length in '{raw}'")))?;
+
+        if user_id <= 0 {
+            return Err(CliError::invalid_args(format!(
+                "Mention user id must be positive in '{raw}'"
+            ))
+            .into());
+        }
+        if offset < 0 {
+            return Err(
+                CliError::invalid_args(format!("Mention offset must be >= 0 in '{raw}'")).into(),
+            );
+        }
+        if length <= 0 {
+            return Err(
+                CliError::invalid_args(format!("Mention length must be > 0 in '{raw}'")).into(),
+            );
+        }
+
+        entities.push(proto::MessageEntity {
+            r#type: proto::message_entity::Type::Mention as i32,
+            offset,
+            length,
+            entity: Some(proto::message_entity::Entity::Mention(
+                proto::message_entity::MessageEntityMention { user_id },
+            )),
+        });
+    }
+
+    Ok(Some(proto::MessageEntities { entities }))
+}
+
+/// Entities of the old text whose covered span reads the same in the new text.
+/// Offsets and lengths are in UTF-16 code units.
+fn preserved_entities_for_edit(
+    existing: Option<&proto::MessageEntities>,
+    old_text: &str,
+    text: &str,
+) -> Option<proto::MessageEntities> {
+    let old_text = old_text.encode_utf16().collect::<Vec<_>>();
+    let text = text.encode_utf16().collect::<Vec<_>>();
+    let span = |units: &[u16], entity: &proto::MessageEntity| {
+        let start = usize::try_from(entity.offset).ok()?;
+        let end = start.checked_add(usize::try_from(entity.length).ok()?)?;
+        units.get(start..end).map(<[u16]>::to_vec)
+    };
+    let entities = existing?
+        .entities
+        .iter()
+        .filter(|entity| {
+            span(&text, entity).is_some_and(|new| span(&old_text, entity) == Some(new))
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    if entities.is_empty() {
+        None
+    } else {
+        Some(proto::MessageEntities { entities })
+    }
+}
+
+fn collect_message_ids(messages: &[proto::Message]) -> Vec<i64> {
+    messages.iter().map(|message| message.id).collect()
+}
+
+/// Drops translations that came back the same as the message, which the
+/// server does for text already in the target language. The RPC reports no
+/// source language, so this is how `--translate auto` skips those messages.
+fn drop_unchanged_translations(
+    translations_by_id: &mut HashMap<i64, proto::MessageTranslation>,
+    messages: &[proto::Message],
+) {
+    for message in messages {
+        let original = message.message.as_deref().unwrap_or_default().trim();
+        if translations_by_id
+            .get(&message.id)
+            .is_some_and(|translation| translation.translation.trim() == original)
+        {
+            translations_by_id.remove(&message.id);
+        }
+    }
+}
+
+fn translations_in_message_order(
+    message_ids: &[i64],
+    translations_by_id: &HashMap<i64, proto::MessageTranslation>,
+) -> Vec<proto::MessageTranslation> {
+    message_ids
+        .iter()
+        .filter_map(|message_id| translations_by_id.get(message_id).cloned())
+        .collect()
+}
+
+fn is_export_output_bundle_dir(path: &Path, download_media: bool) -> bool {
+    path.is_dir() || (download_media && path.extension().is_none())
+}
+
+fn resolve_export_output_path(
+    output_path: Option<PathBuf>,
+    output_bundle_dir: Option<&Path>,
+    format: MessageExportFormat,
+) -> Option<PathBuf> {
+    match (output_path, output_bundle_dir) {
+        (Some(_), Some(bundle_dir)) => {
+            Some(bundle_dir.join(format!("transcript.{}", format.extension())))
+        }
+        (Some(path), None) => Some(path),
+        (None, _) => None,
+    }
+}
+
+fn resolve_export_media_download(
+    download_media: bool,
+    media_dir: Option<PathBuf>,
+    parallel: Option<usize>,
+    output_path: Option<&Path>,
+    output_bundle_dir: Option<&Path>,
+) -> Result<Option<(PathBuf, usize)>, Box<dyn std::error::Error>> {
+    if !download_media {
+        if media_dir.is_some() {
+            return Err(CliError::invalid_args("--media-dir requires --download-media").into());
+        }
+        if parallel.is_some() {
+            return Err(CliError::invalid_args(
+                "--parallel requires --download-media for export/transcript",
+            )
+            .into());
+        }
+        return Ok(None);
+    }
+
+    let parallel = validate_parallel(parallel.unwrap_or(8))?;
+    let media_dir = media_dir.unwrap_or_else(|| {
+        output_bundle_dir
+            .map(|dir| dir.join("media"))
+            .unwrap_or_else(|| default_export_media_dir(output_path))
+    });
+    Ok(Some((media_dir, parallel)))
+}
+
+fn default_export_media_dir(output_path: Option<&Path>) -> PathBuf {
+    let Some(output_path) = output_path else {
+        return PathBuf::from("inline-media");
+    };
+    let stem = strip_compression_extension(output_path);
+    let stem = stem
+        .file_stem()
+        .and_then(|value| value.to_str())
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or("inline");
+    let dir_name = format!("{stem}-media");
+    output_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .map(|parent| parent.join(&dir_name))
+        .unwrap_or_else(|| PathBuf::from(dir_name))
+}
+
+async fn download_messages_media(
+    messages: &[proto::Message],
+    dir: &Path,
+    parallel: usize,
+) -> Result<MediaDownloadSummary, Box<dyn std::error::Error>> {
+    fs::create_dir_all(dir)?;
+    let skipped_message_ids = messages
+        .iter()
+        .filter(|message| !message_has_downloadable_media(message))
+        .map(|message| message.id)
+        .collect::<Vec<_>>();
+    let downloadable_messages = messages
+        .iter()
+        .filter(|message| message_has_downloadable_media(message))
+        .cloned()
+        .collect::<Vec<_>>();
+    let requested_order = messages
+        .iter()
+        .enumerate()
+        .map(|(index, message)| (message.id, index))
+        .collect::<HashMap<_, _>>();
+
+    let results = stream::iter(downloadable_messages)
+        .map(|message| {
+            let dir = dir.to_path_buf();
+            async move {
+                let message_id = message.id;
+                let output_path = match resolve_batch_download_path(&message, &dir) {
+                    Ok(path) => path,
+                    Err(error) => {
+                        return Err(DownloadErrorOutput {
+                            message_id,
+                            error: error.to_string(),
+                        });
+                    }
+                };
+                match download_message_media(&message, &output_path).await {
+                    Ok(bytes) => Ok(DownloadedFileOutput {
+                        message_id,
+                        path: output_path.display().to_string(),
+                        bytes,
+                    }),
+                    Err(error) => Err(DownloadErrorOutput {
+                        message_id,
+                        error: error.to_string(),
+                    }),
+                }
+            }
+        })
+        .buffer_unordered(parallel)
+        .collect::<Vec<_>>()
+        .await;
+
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    for result in results {
+        match result {
+            Ok(file) => files.push(file),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    files.sort_by_key(|file| {
+        requested_order
+            .get(&file.message_id)
+            .copied()
+            .unwrap_or(usize::MAX)
+    });
+    errors.sort_by_key(|error| {
+        requested_order
+            .get(&error.message_id)
+            .copied()
+            .unwrap_or(usize::MAX)
+    });
+    Ok(MediaDownloadSummary {
+        files,
+        skipped_message_ids,
+        errors,
+    })
+}
+
+fn message_has_downloadable_media(message: &proto::Message) -> bool {
+    matches!(
+        message
+            .media
+            .as_ref()
+            .and_then(|media| media.media.as_ref()),
+        Some(proto::message_media::Media::Document(_))
+            | Some(proto::message_media::Media::Video(_))
+            | Some(proto::message_media::Media::Photo(_))
+            | Some(proto::message_media::Media::Voice(_))
+    )
+}
+
+async fn fetch_message_translations(
+    realtime: &RealtimeSession,
+    peer: &proto::InputPeer,
+    message_ids: &[i64],
+    language: &str,
+) -> Result<HashMap<i64, proto::MessageTranslation>, Box<dyn std::error::Error>> {
+    if message_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let input = proto::TranslateMessagesInput {
+        peer_id: Some(peer.clone()),
+        message_ids: message_ids.to_vec(),
+        language: language.to_string(),
+    };
+
+    let payload = realtime.call(input).await?;
+
+    Ok(payload
+        .translations
+        .into_iter()
+        .map(|translation| (translation.message_id, translation))
+        .collect())
+}
+
+pub(super) fn peer_summary_from_input(peer: &proto::InputPeer) -> Option<PeerSummary> {
+    match &peer.r#type {
+        Some(proto::input_peer::Type::Chat(chat)) => Some(PeerSummary {
+            peer_type: "chat".to_string(),
+            id: chat.chat_id,
+        }),
+        Some(proto::input_peer::Type::User(user)) => Some(PeerSummary {
+            peer_type: "user".to_string(),
+            id: user.user_id,
+        }),
+        Some(proto::input_peer::Type::Self_(_)) => Some(PeerSummary {
+            peer_type: "self".to_string(),
+            id: 0,
+        }),
+        None => None,
+    }
+}
+
+pub(super) fn peer_name_from_input(
+    peer: &proto::InputPeer,
+    users_by_id: &HashMap<i64, proto::User>,
+    chats_by_id: &HashMap<i64, proto::Chat>,
+) -> Option<String> {
+    match &peer.r#type {
+        Some(proto::input_peer::Type::User(user)) => users_by_id
+            .get(&user.user_id)
+            .map(user_display_name)
+            .or_else(|| Some(format!("user {}", user.user_id))),
+        Some(proto::input_peer::Type::Chat(chat)) => chats_by_id
+            .get(&chat.chat_id)
+            .map(|chat| chat_display_name(chat, users_by_id))
+            .or_else(|| Some(format!("chat {}", chat.chat_id))),
+        Some(proto::input_peer::Type::Self_(_)) => Some("You".to_string()),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deleted_ids_come_from_delete_updates() {
+        let delete = |ids: Vec<i64>| proto::Update {
+            update: Some(proto::update::Update::DeleteMessages(
+                proto::UpdateDeleteMessages {
+                    message_ids: ids,
+                    peer_id: None,
+                },
+            )),
+            ..Default::default()
+        };
+        assert_eq!(deleted_message_ids(&[]), None);
+        assert_eq!(
+            deleted_message_ids(&[delete(vec![10, 11]), delete(vec![11, 12])]),
+            Some(vec![10, 11, 12])
+        );
+    }
+
+    #[test]
+    fn empty_message_text_is_structured_invalid_args() {
+        let err = resolve_message_caption(Some("   ".to_string()), false).unwrap_err();
+        let cli_err = err.downcast_ref::<CliError>().unwrap();
+        assert_eq!(cli_err.code, "invalid_args");
+        assert_eq!(cli_err.message, "message text is empty");
+    }
+
+    #[test]
+    fn message_list_filters_are_composable() {
+        let mut messages = vec![
+            proto::Message {
+                id: 1,
+                message: Some("   ".to_string()),
+                media: Some(proto::MessageMedia {
+                    media: Some(proto::message_media::Media::Document(
+                        proto::MessageDocument {
+                            document: Some(proto::Document {
+                                id: 10,
+                                ..Default::default()
+                            }),
+                        },
+                    )),
+                }),
+                fwd_from: Some(proto::MessageFwdHeader::default()),
+                ..Default::default()
+            },
+            proto::Message {
+                id: 2,
+                message: Some("caption".to_string()),
+                media: Some(proto::MessageMedia {
+                    media: Some(proto::message_media::Media::Document(
+                        proto::MessageDocument {
+                            document: Some(proto::Document {
+                                id: 11,
+                                ..Default::default()
+                            }),
+                        },
+                    )),
+                }),
+                ..Default::default()
+            },
+            proto::Message {
+                id: 3,
+                message: None,
+                ..Default::default()
+            },
+        ];
+        let args = MessagesListArgs {
+            chat_id: Some(1),
+            user_id: None,
+            to: None,
+            limit: None,
+            offset_id: None,
+            has_media: true,
+            empty_text: true,
+            forwarded: true,
+            translate: None,
+            since: None,
+            until: None,
+            width: None,
+        };
+
+        filter_messages_by_list_options(&mut messages, &args);
+
+        assert_eq!(
+            messages
+                .iter()
+                .map(|message| message.id)
+                .collect::<Vec<_>>(),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn stdin_terminal_is_structured_stdin_not_piped() {
+        let err = require_stdin_pipe(true).unwrap_err();
+        let cli_err = err.downcast_ref::<CliError>().unwrap();
+
+        assert_eq!(cli_err.code, "stdin_not_piped");
+        assert!(cli_err.hint.as_deref().unwrap_or("").contains("--text"));
+        require_stdin_pipe(false).unwrap();
+    }
+
+    #[test]
+    fn invalid_mentions_are_structured_invalid_args() {
+        let err = parse_mention_entities(&["not-a-mention".to_string()]).unwrap_err();
+        let cli_err = err.downcast_ref::<CliError>().unwrap();
+        assert_eq!(cli_err.code, "invalid_args");
+        assert!(cli_err.message.contains("Invalid mention"));
+    }
+
+    #[test]
+    fn edit_preserves_entities_whose_text_is_unchanged() {
+        let mention = |offset, length| proto::MessageEntity {
+            r#type: proto::message_entity::Type::Mention as i32,
+            offset,
+            length,
+            entity: Some(proto::message_entity::Entity::Mention(
+                proto::message_entity::MessageEntityMention { user_id: 42 },
+            )),
+        };
+        let existing = proto::MessageEntities {
+            entities: vec![mention(0, 4), mention(14, 5)],
+        };
+        let old = "@Sam hi, ping @Alex";
+
+        let preserved = preserved_entities_for_edit(Some(&existing), old, "@Sam hi").unwrap();
+        assert_eq!(preserved.entities, vec![mention(0, 4)]);
+        let preserved =
+            preserved_entities_for_edit(Some(&existing), old, "@Sam hi, ping @Bob now").unwrap();
+        assert_eq!(preserved.entities, vec![mention(0, 4)]);
+        let preserved =
+            preserved_entities_for_edit(Some(&existing), old, "@Tom hi, ping @Alex!").unwrap();
+        assert_eq!(preserved.entities, vec![mention(14, 5)]);
+
+        assert!(preserved_entities_for_edit(Some(&existing), old, "hi").is_none());
+        assert!(preserved_entities_for_edit(Some(&existing), old, "Hi there, Sam").is_none());
+        assert!(preserved_entities_for_edit(None, old, "@Sam hi").is_none());
+    }
+
+    #[test]
+    fn translations_follow_requested_message_order() {
+        let translations_by_id: HashMap<i64, proto::MessageTranslation> = [
+            (
+                2,
+                proto::MessageTranslation {
+                    message_id: 2,
+                    language: "en".to_string(),
+                    translation: "second".to_string(),
+                    ..Default::default()
+                },
+            ),
+            (
+                1,
+                proto::MessageTranslation {
+                    message_id: 1,
+                    language: "en".to_string(),
+                    translation: "first".to_string(),
+                    ..Default::default()
+                },
+            ),
+        ]
+        .into_iter()
+        .collect();
+
+        let translations = translations_in_message_order(&[1, 3, 2], &translations_by_id);
+
+        let ids: Vec<i64> = translations
+            .iter()
+            .map(|translation| translation.message_id)
+            .collect();
+        assert_eq!(ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn translated_message_json_keeps_raw_message_fields() {
+        let output = TranslatedMessageOutput {
+            message: proto::Message {
+                id: 7,
+                message: Some("hello".to_string()),
+                ..Default::default()
+            },
+            translations: vec![proto::MessageTranslation {
+                message_id: 7,
+                language: "en".to_string(),
+                translation: "hello".to_string(),
+                ..Default::default()
+            }],
+        };
+
+        let value = serde_json::to_value(output).unwrap();
+
+        assert_eq!(value["id"], 7);
+        assert_eq!(value["message"], "hello");
+        assert_eq!(value["translations"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn translated_history_json_keeps_raw_history_fields() {
+        let output = TranslatedChatHistoryOutput {
+            payload: proto::GetChatHistoryResult {
+                messages: vec![proto::Message {
+                    id: 7,
+                    message: Some("hello".to_string()),
+                    ..Default::default()
+                }],
+            },
+            translations: vec![proto::MessageTranslation {
+                message_id: 7,
+                language: "en".to_string(),
+                translation: "hello".to_string(),
+                ..Default::default()
+            }],
+        };
+
+        let value = serde_json::to_value(output).unwrap();
+
+        assert_eq!(value["messages"].as_array().unwrap().len(), 1);
+        assert_eq!(value["messages"][0]["id"], 7);
+        assert_eq!(value["translations"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn translated_search_json_keeps_raw_search_fields() {
+        let output = TranslatedSearchMessagesOutput {
+            payload: proto::SearchMessagesResult {
+                messages: vec![proto::Message {
+                    id: 8,
+                    message: Some("hola".to_string()),
+                    ..Default::default()
+                }],
+            },
+            translations: vec![proto::MessageTranslation {
+                message_id: 8,
+                language: "en".to_string(),
+                translation: "hello".to_string(),
+                ..Default::default()
+            }],
+        };
+
+        let value = serde_json::to_value(output).unwrap();
+
+        assert_eq!(value["messages"].as_array().unwrap().len(), 1);
+        assert_eq!(value["messages"][0]["id"], 8);
+        assert_eq!(value["translations"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn link_preview_url_is_appended_once() {
+        assert_eq!(
+            append_link_preview_url(Some("read this".to_string()), "https://a.example").unwrap(),
+            "read this\nhttps://a.example"
+        );
+        assert_eq!(
+            append_link_preview_url(
+                Some("see https://a.example".to_string()),
+                "https://a.example"
+            )
+            .unwrap(),
+            "see https://a.example"
+        );
+        assert_eq!(
+            append_link_preview_url(None, "https://a.example").unwrap(),
+            "https://a.example"
+        );
+        assert!(append_link_preview_url(None, "a.example").is_err());
+    }
+
+    #[test]
+    fn export_media_download_options_validate_and_default() {
+        let (media_dir, parallel) = resolve_export_media_download(
+            true,
+            None,
+            None,
+            Some(Path::new("out/feedback.md")),
+            None,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(media_dir, PathBuf::from("out").join("feedback-media"));
+        assert_eq!(parallel, 8);
+
+        let (media_dir, parallel) =
+            resolve_export_media_download(true, Some(PathBuf::from("media")), Some(4), None, None)
+                .unwrap()
+                .unwrap();
+        assert_eq!(media_dir, PathBuf::from("media"));
+        assert_eq!(parallel, 4);
+
+        let bundle_dir = std::env::temp_dir().join(format!(
+            "inline-cli-export-bundle-test-{}-{}",
+            std::process::id(),
+            current_epoch_seconds()
+        ));
+        fs::create_dir_all(&bundle_dir).unwrap();
+        let output_path = resolve_export_output_path(
+            Some(bundle_dir.clone()),
+            Some(&bundle_dir),
+            MessageExportFormat::Markdown,
+        )
+        .unwrap();
+        assert_eq!(output_path, bundle_dir.join("transcript.md"));
+        let (media_dir, _) =
+            resolve_export_media_download(true, None, None, Some(&output_path), Some(&bundle_dir))
+                .unwrap()
+                .unwrap();
+        assert_eq!(media_dir, bundle_dir.join("media"));
+
+        let bundle_dir = PathBuf::from("feedback-bundle");
+        let output_path = resolve_export_output_path(
+            Some(bundle_dir.clone()),
+            Some(&bundle_dir),
+            MessageExportFormat::Markdown,
+        )
+        .unwrap();
+        assert_eq!(output_path, bundle_dir.join("transcript.md"));
+
+        let err =
+            resolve_export_media_download(false, Some(PathBuf::from("media")), None, None, None)
+                .unwrap_err();
+        let cli_err = err.downcast_ref::<CliError>().unwrap();
+        assert_eq!(cli_err.code, "invalid_args");
+        assert!(cli_err.message.contains("--download-media"));
+    }
+}
@@ -0,0 +1,365 @@
+//! Command execution.
+//!
+//! Each top-level subcommand lives in its own module and implements [`Execute`]
+//! against a shared [`CommandContext`], so handlers don't reach for process-wide
+//! state and can be driven from tests or other front ends.
+
+mod alerts;
+mod api;
+mod archive;
+mod auth;
+mod backup;
+mod badge;
+mod bookmarks;
+mod bots;
+mod chats;
+mod config;
+mod data;
+mod debug;
+mod digest;
+mod doctor;
+mod events;
+mod import;
+mod mentions;
+mod messages;
+mod notes;
+mod notifications;
+mod open;
+mod recent;
+mod resend;
+mod rpc;
+mod schema;
+mod server;
+mod settings;
+mod spaces;
+mod state;
+mod summarize;
+mod sync;
+mod tasks;
+mod typing;
+mod usage;
+mod users;
+mod watch;
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use inline_protocol::proto;
+use inline_sdk::RealtimeSession;
+use inline_sdk::api::ApiClient;
+use serde::Serialize;
+
+use crate::auth::AuthStore;
+use crate::capabilities;
+use crate::config::Config;
+use crate::errors::CliError;
+use crate::identity as client_info;
+use crate::output::{self, JsonFormat};
+use crate::peer::{PeerTarget, find_user_by_username, input_chat_peer, input_user_peer};
+use crate::state::LocalDb;
+use crate::{
+    AuthCommand, Command, MessagesCommand, confirm_action, current_epoch_seconds,
+    is_interactive_terminal, update,
+};
+
+pub(crate) use data::DataContext;
+
+/// Everything a command needs from the invocation: resolved config, local
+/// stores, the HTTP client, output settings, and server state shared across
+/// the command's calls.
+pub(crate) struct CommandContext {
+    pub(crate) config: Config,
+    pub(crate) auth_store: AuthStore,
+    pub(crate) local_db: LocalDb,
+    pub(crate) api: ApiClient,
+    pub(crate) json: bool,
+    pub(crate) json_format: JsonFormat,
+    pub(crate) data: DataContext,
+    /// One-line result with counts ("1204 messages"), set by long commands
+    /// for `--notify-done`.
+    pub(crate) completion: OnceLock<String>,
+}
+
+impl CommandContext {
+    pub(crate) fn token(&self) -> Result<String, Box<dyn std::error::Error>> {
+        match self.auth_store.load_token()? {
+            Some(token) => Ok(token),
+            None => Err(CliError::not_authenticated().into()),
+        }
+    }
+
+    /// Returns the invocation's realtime session, connecting on first use.
+    pub(crate) async fn realtime(&self) -> Result<RealtimeSession, Box<dyn std::error::Error>> {
+        self.data
+            .realtime(
+                &self.config.realtime_url,
+                || self.token(),
+                || self.known_unsupported_methods(),
+            )
+            .await
+    }
+
+    fn known_unsupported_methods(&self) -> Vec<proto::Method> {
+        let Ok(state) = self.local_db.load() else {
+            return Vec::new();
+        };
+        capabilities::known_unsupported(
+            &state,
+            &self.config.realtime_url,
+            client_info::client_version(),
+            chrono::Utc::now().timestamp(),
+        )
+    }
+
+    /// Saves methods the server said it doesn't implement, so the next command
+    /// fails fast instead of asking again. Guesses from error messages stay
+    /// with this invocation.
+    pub(crate) fn remember_unsupported_methods(&self) {
+        let methods = self.data.confirmed_unsupported_methods();
+        if methods.is_empty() {
+            return;
+        }
+        let Ok(mut state) = self.local_db.load() else {
+            return;
+        };
+        if capabilities::remember(
+            &mut state,
+            &self.config.realtime_url,
+            client_info::client_version(),
+            &methods,
+            chrono::Utc::now().timestamp(),
+        ) && let Err(err) = self.local_db.save(&state)
+        {
+            log::debug!("could not save server capabilities: {err}");
+        }
+    }
+
+    /// Whether sending `count` messages goes over the `send_limit` setting.
+    pub(crate) fn exceeds_send_limit(&self, count: usize) -> bool {
+        self.config.send_limit > 0 && count > self.config.send_limit
+    }
+
+    /// Checks a send of `count` messages against `send_limit`. Over it, the
+    /// estimate is printed and the user is asked; `--json` and scripts need
+    /// `--force`. Returns false when the user declines.
+    pub(crate) fn confirm_send_count(
+        &self,
+        count: usize,
+        force: bool,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        if force || !self.exceeds_send_limit(count) {
+            return Ok(true);
+        }
+        let limit = self.config.send_limit;
+        eprintln!("This will send {count} messages (send_limit is {limit}).");
+        if self.json || !is_interactive_terminal() {
+            return Err(CliError::send_limit_exceeded(count, limit).into());
+        }
+        confirm_action(&format!("Send {count} messages?"), false)
+    }
+
+    /// Moves a peer you just sent to to the front of `inline recent`.
+    pub(crate) fn remember_sent(&self, peer: &proto::InputPeer) {
+        let result = self.local_db.load().and_then(|mut state| {
+            crate::recent::touch_sent(&mut state, peer, current_epoch_seconds() as i64);
+            self.local_db.save(&state)
+        });
+        if let Err(err) = result {
+            log::debug!("could not record recent peer: {err}");
+        }
+    }
+
+    /// Records the result line shown by `--notify-done`; the first one wins.
+    pub(crate) fn report_completion(&self, summary: String) {
+        let _ = self.completion.set(summary);
+    }
+
+    /// Returns your chat list, fetched at most once per invocation.
+    pub(crate) async fn chats(&self) -> Result<&proto::GetChatsResult, Box<dyn std::error::Error>> {
+        self.data.chats(self.realtime()).await
+    }
+
+    pub(crate) async fn users_by_id(
+        &self,
+    ) -> Result<&HashMap<i64, proto::User>, Box<dyn std::error::Error>> {
+        let chats = self.chats().await?;
+        Ok(self.data.users_by_id(chats))
+    }
+
+    pub(crate) async fn chats_by_id(
+        &self,
+    ) -> Result<&HashMap<i64, proto::Chat>, Box<dyn std::error::Error>> {
+        let chats = self.chats().await?;
+        Ok(self.data.chats_by_id(chats))
+    }
+
+    /// Resolves `--to`/`--chat-id`/`--user-id`. Only `@username` targets need the
+    /// network; they are looked up among the users in your chat list. `%N`
+    /// picks from `inline recent`.
+    pub(crate) async fn resolve_peer(
+        &self,
+        to: Option<&str>,
+        chat_id: Option<i64>,
+        user_id: Option<i64>,
+    ) -> Result<proto::InputPeer, Box<dyn std::error::Error>> {
+        let recent;
+        let to = match to.map(crate::recent::parse_index).transpose()?.flatten() {
+            Some(index) => {
+                let state = self.local_db.load()?;
+                recent = crate::recent::peer_at(&state, index)?.to_string();
+                Some(recent.as_str())
+            }
+            None => to,
+        };
+        match PeerTarget::from_args(to, chat_id, user_id, &self.config.aliases)? {
+            PeerTarget::Chat(chat_id) => Ok(input_chat_peer(chat_id)),
+            PeerTarget::User(user_id) => Ok(input_user_peer(user_id)),
+            PeerTarget::Username(username) => {
+                let payload = self.chats().await?;
+                let user = find_user_by_username(&payload.users, &username)
+                    .ok_or_else(|| CliError::not_found_username(&username))?;
+                Ok(input_user_peer(user.id))
+            }
+        }
+    }
+
+    pub(crate) fn print_json<T: Serialize + ?Sized>(
+        &self,
+        value: &T,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(output::print_json(value, self.json_format)?)
+    }
+}
+
+pub(crate) trait Execute {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+impl Execute for Command {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Command::Auth { command } => command.execute(ctx).await,
+            // Top-level shortcuts share their handlers with the full subcommands.
+            Command::Login(args) => AuthCommand::Login(args).execute(ctx).await,
+            Command::Logout(args) => AuthCommand::Logout(args).execute(ctx).await,
+            Command::Me => AuthCommand::Me.execute(ctx).await,
+            Command::Search(args) => MessagesCommand::Search(args).execute(ctx).await,
+            Command::Transcript(args) => MessagesCommand::Transcript(args).execute(ctx).await,
+            Command::Update(args) => {
+                let outcome =
+                    update::run_update(&ctx.config, ctx.json, args.allow_unsigned).await?;
+                if ctx.json {
+                    ctx.print_json(&outcome)?;
+                }
+                Ok(())
+            }
+            Command::Doctor(args) => args.execute(ctx).await,
+            Command::Server { command } => command.execute(ctx).await,
+            Command::State { command } => command.execute(ctx).await,
+            Command::Config { command } => command.execute(ctx).await,
+            Command::Settings { command } => command.execute(ctx).await,
+            Command::Alias { command } => command.execute(ctx).await,
+            Command::Note(args) => args.execute(ctx).await,
+            Command::Notes { command } => command.execute(ctx).await,
+            Command::Bookmarks { command } => command.execute(ctx).await,
+            Command::Alerts { command } => command.execute(ctx).await,
+            Command::Resend { command } => command.execute(ctx).await,
+            Command::Badge(args) => args.execute(ctx).await,
+            Command::Digest(args) => args.execute(ctx).await,
+            Command::Usage(args) => args.execute(ctx).await,
+            Command::Recent(args) => args.execute(ctx).await,
+            Command::Events { command } => command.execute(ctx).await,
+            Command::Summarize(args) => args.execute(ctx).await,
+            Command::Mentions(args) => args.execute(ctx).await,
+            Command::Open(args) => args.execute(ctx).await,
+            Command::Schema { command } => command.execute(ctx).await,
+            Command::Api { command } => command.execute(ctx).await,
+            Command::Rpc { command } => command.execute(ctx).await,
+            Command::Archive { command } => command.execute(ctx).await,
+            Command::Bots { command } => command.execute(ctx).await,
+            Command::Typing { command } => command.execute(ctx).await,
+            Command::Chats { command } => command.execute(ctx).await,
+            Command::Users { command } => command.execute(ctx).await,
+            Command::Messages { command } => command.execute(ctx).await,
+            Command::Spaces { command } => command.execute(ctx).await,
+            Command::Notifications { command } => command.execute(ctx).await,
+            Command::Tasks { command } => command.execute(ctx).await,
+            Command::Import { command } => command.execute(ctx).await,
+            Command::Backup { command } => command.execute(ctx).await,
+            Command::Sync(args) => args.execute(ctx).await,
+            Command::Watch { command } => command.execute(ctx).await,
+            Command::Debug { command } => command.execute(ctx).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::config::OutputFormat;
+    use crate::config_file::ConfigFile;
+
+    fn test_context(aliases: BTreeMap<String, String>) -> CommandContext {
+        let root = PathBuf::from("/nonexistent/inline-cli-test");
+        let api_base_url = "http://127.0.0.1:9/v1".to_string();
+        let config = Config {
+            api_base_url: api_base_url.clone(),
+            realtime_url: "ws://127.0.0.1:9/realtime".to_string(),
+            data_dir: root.clone(),
+            secrets_path: root.join("secrets.json"),
+            state_path: root.join("state.json"),
+            config_path: root.join("config.toml"),
+            profile: None,
+            output_format: OutputFormat::Table,
+            json_format: None,
+            color: output::ColorMode::Never,
+            read_only: false,
+            proxy: None,
+            ca_bundle: None,
+            tls_pins: Vec::new(),
+            send_limit: 50,
+            language: None,
+            update_check: crate::update::UpdateCheck::Never,
+            aliases,
+            command_aliases: BTreeMap::new(),
+            defaults: BTreeMap::new(),
+            settings: Vec::new(),
+            file: ConfigFile::default(),
+            release_manifest_url: None,
+            release_install_url: None,
+            release_public_key: None,
+        };
+        CommandContext {
+            auth_store: AuthStore::new(config.secrets_path.clone(), api_base_url.clone()),
+            local_db: LocalDb::new(config.state_path.clone(), api_base_url.clone()),
+            api: ApiClient::try_new(api_base_url).unwrap(),
+            config,
+            json: true,
+            json_format: JsonFormat::Compact,
+            data: DataContext::default(),
+            completion: OnceLock::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn resolve_peer_handles_ids_and_aliases_without_network() {
+        let ctx = test_context(BTreeMap::from([(
+            "standup".to_string(),
+            "chat:123".to_string(),
+        )]));
+
+        assert_eq!(
+            ctx.resolve_peer(Some("standup"), None, None).await.unwrap(),
+            input_chat_peer(123)
+        );
+        assert_eq!(
+            ctx.resolve_peer(None, None, Some(42)).await.unwrap(),
+            input_user_peer(42)
+        );
+        let err = ctx.resolve_peer(None, None, None).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<CliError>().unwrap().code, "missing_peer");
+    }
+}
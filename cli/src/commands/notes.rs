@@ -0,0 +1,62 @@
+use inline_protocol::proto;
+
+use super::messages::{
+    peer_name_from_input, peer_summary_from_input, print_new_message_ids, resolve_message_caption,
+};
+use super::{CommandContext, Execute};
+use crate::history::fetch_history_window;
+use crate::message_output::build_message_list;
+use crate::peer::input_self_peer;
+use crate::validation::validate_message_limit;
+use crate::{NoteArgs, NotesCommand, output, send_message};
+
+const DEFAULT_NOTES_LIMIT: i32 = 20;
+
+impl Execute for NoteArgs {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        let text = resolve_message_caption(self.text, self.stdin)?;
+        let peer = input_self_peer();
+        let realtime = ctx.realtime().await?;
+        let payload = send_message(&realtime, &peer, text, None, true, None, None, None).await?;
+        if ctx.json {
+            ctx.print_json(&payload)?;
+        } else if self.quiet {
+            print_new_message_ids("sendMessage", &payload.updates)?;
+        } else {
+            println!("Saved to your notes.");
+        }
+        Ok(())
+    }
+}
+
+impl Execute for NotesCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            NotesCommand::List(args) => {
+                let limit = validate_message_limit(args.limit)?.unwrap_or(DEFAULT_NOTES_LIMIT);
+                let peer = input_self_peer();
+                let realtime = ctx.realtime().await?;
+                let payload = proto::GetChatHistoryResult {
+                    messages: fetch_history_window(&realtime, &peer, None, Some(limit), None, None)
+                        .await?,
+                };
+                if ctx.json {
+                    return ctx.print_json(&payload);
+                }
+                let users_by_id = ctx.users_by_id().await?;
+                let chats_by_id = ctx.chats_by_id().await?;
+                let current_user_id = ctx.local_db.load()?.current_user.map(|user| user.id);
+                let output = build_message_list(
+                    payload,
+                    users_by_id,
+                    current_user_id,
+                    peer_summary_from_input(&peer),
+                    peer_name_from_input(&peer, users_by_id, chats_by_id),
+                    None,
+                );
+                output::print_messages(&output, false, ctx.json_format)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,366 @@
+use inline_protocol::proto;
+use inline_sdk::RealtimeSession;
+use serde::Serialize;
+
+use super::{CommandContext, Execute};
+use crate::errors::CliError;
+use crate::notifications::{
+    NotificationRule, NotificationScope, dialog_mode_from_arg, dialog_mode_label,
+    effective_notification_scopes, format_notification_rules, notification_mode_from_arg,
+    notification_settings_values, parse_notification_rules, print_notification_settings,
+};
+use crate::output;
+use crate::peer::input_chat_peer;
+use crate::validation::validate_positive_id_arg;
+use crate::{
+    NotificationRulesAddArgs, NotificationRulesCommand, NotificationsCommand, NotificationsSetArgs,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NotificationsGetOutput {
+    #[serde(flatten)]
+    settings: proto::GetUserSettingsResult,
+    effective: Vec<NotificationScope>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpaceNotificationsOutput {
+    space_id: i64,
+    /// `None` when the overrides were cleared.
+    mode: Option<&'static str>,
+    chat_ids: Vec<i64>,
+    updates: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NotificationRulesOutput {
+    uses_default_rules: bool,
+    requires_mention: bool,
+    rules: Vec<NumberedRule>,
+}
+
+#[derive(Serialize)]
+struct NumberedRule {
+    number: usize,
+    #[serde(flatten)]
+    rule: NotificationRule,
+    line: String,
+}
+
+impl Execute for NotificationsCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            NotificationsCommand::Get => {
+                let realtime = ctx.realtime().await?;
+                let payload = realtime.call(proto::GetUserSettingsInput {}).await?;
+                let effective = effective_notification_scopes(
+                    payload.user_settings.as_ref(),
+                    ctx.chats().await?,
+                );
+                if ctx.json {
+                    ctx.print_json(&NotificationsGetOutput {
+                        settings: payload,
+                        effective,
+                    })?;
+                } else {
+                    print_notification_settings(payload.user_settings.as_ref(), &effective);
+                }
+            }
+            NotificationsCommand::Set(args) if args.space_id.is_some() => {
+                set_space_notifications(ctx, args).await?
+            }
+            NotificationsCommand::Set(args) => {
+                if args.mode.is_none() && !args.silent && !args.sound {
+                    return Err(CliError::invalid_args(
+                        "Provide at least one of --mode, --silent, or --sound",
+                    )
+                    .into());
+                }
+                let realtime = ctx.realtime().await?;
+                let current = fetch_user_settings(&realtime).await?;
+                let mut values = notification_settings_values(
+                    current
+                        .as_ref()
+                        .and_then(|settings| settings.notification_settings.as_ref()),
+                );
+                if let Some(mode) = args.mode {
+                    values.mode = notification_mode_from_arg(mode);
+                    values.disable_dm_notifications =
+                        values.mode == proto::notification_settings::Mode::OnlyMentions;
+                }
+                if args.silent {
+                    values.silent = true;
+                } else if args.sound {
+                    values.silent = false;
+                }
+
+                let notification_settings = proto::NotificationSettings {
+                    mode: Some(values.mode as i32),
+                    silent: Some(values.silent),
+                    disable_dm_notifications: Some(values.disable_dm_notifications),
+                    ..Default::default()
+                };
+                let user_settings = proto::UserSettings {
+                    notification_settings: Some(notification_settings),
+                };
+                let input = proto::UpdateUserSettingsInput {
+                    user_settings: Some(user_settings),
+                };
+                let payload = realtime.call(input).await?;
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else {
+                    println!(
+                        "Notification settings updated (updates: {}).",
+                        payload.updates.len()
+                    );
+                }
+            }
+            NotificationsCommand::Rules { command } => run_rules(ctx, command).await?,
+        }
+        Ok(())
+    }
+}
+
+/// The server has no space-level setting, so a space override is the same
+/// dialog override on each of the space's chats.
+async fn set_space_notifications(
+    ctx: &CommandContext,
+    args: NotificationsSetArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let space_id = validate_positive_id_arg("--space-id", args.space_id.unwrap_or_default())?;
+    let mode = match (args.mode, args.clear) {
+        (_, true) => None,
+        (Some(mode), false) => Some(dialog_mode_from_arg(mode).ok_or_else(|| {
+            CliError::invalid_args(
+                "only-mentions is a global mode; use --mode mentions for a space",
+            )
+        })?),
+        (None, false) => {
+            return Err(CliError::invalid_args("Provide --mode or --clear with --space-id").into());
+        }
+    };
+
+    let chats = ctx.chats().await?;
+    let space_name = chats
+        .spaces
+        .iter()
+        .find(|space| space.id == space_id)
+        .map(|space| space.name.clone());
+    let chat_ids: Vec<i64> = chats
+        .chats
+        .iter()
+        .filter(|chat| chat.space_id == Some(space_id))
+        .map(|chat| chat.id)
+        .collect();
+    if chat_ids.is_empty() {
+        return Err(CliError::invalid_args(format!(
+            "No chats found in space {space_id} (see `inline chats list`)"
+        ))
+        .into());
+    }
+
+    let realtime = ctx.realtime().await?;
+    let mut updates = 0;
+    for &chat_id in &chat_ids {
+        let input = proto::UpdateDialogNotificationSettingsInput {
+            peer_id: Some(input_chat_peer(chat_id)),
+            notification_settings: mode.map(|mode| proto::DialogNotificationSettings {
+                mode: Some(mode as i32),
+            }),
+        };
+        updates += realtime.call(input).await?.updates.len();
+    }
+
+    let output = SpaceNotificationsOutput {
+        space_id,
+        mode: mode.map(dialog_mode_label),
+        chat_ids,
+        updates,
+    };
+    if ctx.json {
+        return ctx.print_json(&output);
+    }
+    let space = match space_name {
+        Some(name) => format!("Space {space_id} ({name})"),
+        None => format!("Space {space_id}"),
+    };
+    match output.mode {
+        Some(mode) => println!(
+            "{space}: notifications set to {mode} for {} chat(s).",
+            output.chat_ids.len()
+        ),
+        None => println!(
+            "{space}: overrides cleared for {} chat(s); they follow the global mode.",
+            output.chat_ids.len()
+        ),
+    }
+    Ok(())
+}
+
+async fn run_rules(
+    ctx: &CommandContext,
+    command: NotificationRulesCommand,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let realtime = ctx.realtime().await?;
+    let current = fetch_user_settings(&realtime).await?;
+    let settings = current
+        .and_then(|settings| settings.notification_settings)
+        .unwrap_or_default();
+    let mut rules = parse_notification_rules(settings.zen_mode_custom_rules.as_deref());
+
+    let changed = match command {
+        NotificationRulesCommand::List => None,
+        NotificationRulesCommand::Add(args) => {
+            let rule = rule_from_args(args)?;
+            if rules.contains(&rule) {
+                return Err(CliError::invalid_args(format!(
+                    "Rule already exists: {}",
+                    rule.to_line()
+                ))
+                .into());
+            }
+            let message = format!("Added rule {}: {}", rules.len() + 1, rule.describe());
+            rules.push(rule);
+            Some(message)
+        }
+        NotificationRulesCommand::Remove(args) => {
+            if args.number == 0 || args.number > rules.len() {
+                return Err(CliError::invalid_args(format!(
+                    "No rule {}; there are {} (see `inline notifications rules list`)",
+                    args.number,
+                    rules.len()
+                ))
+                .into());
+            }
+            let rule = rules.remove(args.number - 1);
+            Some(format!("Removed rule {}: {}", args.number, rule.describe()))
+        }
+    };
+
+    if changed.is_some() {
+        let input = proto::UpdateUserSettingsInput {
+            user_settings: Some(proto::UserSettings {
+                notification_settings: Some(proto::NotificationSettings {
+                    zen_mode_custom_rules: Some(format_notification_rules(&rules)),
+                    ..Default::default()
+                }),
+            }),
+        };
+        realtime.call(input).await?;
+    }
+
+    let output = NotificationRulesOutput {
+        uses_default_rules: settings.zen_mode_uses_default_rules.unwrap_or(false),
+        requires_mention: settings.zen_mode_requires_mention.unwrap_or(false),
+        rules: rules
+            .into_iter()
+            .enumerate()
+            .map(|(index, rule)| NumberedRule {
+                number: index + 1,
+                line: rule.to_line(),
+                rule,
+            })
+            .collect(),
+    };
+    if ctx.json {
+        return ctx.print_json(&output);
+    }
+    if let Some(message) = changed {
+        println!("{message}");
+        return Ok(());
+    }
+    println!("{}", output::style_heading("Notification rules"));
+    println!(
+        "  default rules: {}",
+        if output.uses_default_rules {
+            "on"
+        } else {
+            "off"
+        }
+    );
+    println!(
+        "  requires mention: {}",
+        if output.requires_mention { "yes" } else { "no" }
+    );
+    if output.rules.is_empty() {
+        println!("  No custom rules.");
+    }
+    for rule in &output.rules {
+        println!("  {}. {}", rule.number, rule.rule.describe());
+        if rule.rule.text.is_none() {
+            println!("     {}", rule.line);
+        }
+    }
+    Ok(())
+}
+
+fn rule_from_args(
+    args: NotificationRulesAddArgs,
+) -> Result<NotificationRule, Box<dyn std::error::Error>> {
+    if let Some(text) = args.text {
+        let text = text.trim();
+        if text.is_empty() || text.contains('\n') {
+            return Err(CliError::invalid_args("--text must be a single non-empty line").into());
+        }
+        return Ok(NotificationRule {
+            text: Some(text.to_string()),
+            ..Default::default()
+        });
+    }
+    if args.chat_id.is_none() && args.from.is_none() && args.keyword.is_none() {
+        return Err(CliError::invalid_args(
+            "Provide at least one of --chat-id, --from, --keyword, or --text",
+        )
+        .into());
+    }
+    let chat_id = args
+        .chat_id
+        .map(|chat_id| validate_positive_id_arg("--chat-id", chat_id))
+        .transpose()?;
+    let from = args
+        .from
+        .map(|from| {
+            let from = from.trim().to_string();
+            let is_username =
+                from.len() > 1 && from.starts_with('@') && !from.chars().any(char::is_whitespace);
+            let is_user_id = from.parse::<i64>().is_ok_and(|user_id| user_id > 0);
+            if is_username || is_user_id {
+                Ok(from)
+            } else {
+                Err(CliError::invalid_args(
+                    "--from must be @username or a positive user id",
+                ))
+            }
+        })
+        .transpose()?;
+    let keyword = args
+        .keyword
+        .map(|keyword| {
+            let keyword = keyword.trim().to_string();
+            if keyword.is_empty() || keyword.contains(['"', '\n']) {
+                Err(CliError::invalid_args(
+                    "--keyword must be non-empty and cannot contain quotes or newlines",
+                ))
+            } else {
+                Ok(keyword)
+            }
+        })
+        .transpose()?;
+    Ok(NotificationRule {
+        chat_id,
+        from,
+        keyword,
+        text: None,
+    })
+}
+
+pub(super) async fn fetch_user_settings(
+    realtime: &RealtimeSession,
+) -> Result<Option<proto::UserSettings>, Box<dyn std::error::Error>> {
+    let payload = realtime.call(proto::GetUserSettingsInput {}).await?;
+    Ok(payload.user_settings)
+}
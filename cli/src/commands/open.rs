@@ -0,0 +1,34 @@
+use serde::Serialize;
+
+use super::{CommandContext, Execute};
+use crate::OpenArgs;
+use crate::deep_link::{inline_url_from_input, open_url};
+use crate::errors::CliError;
+use crate::validation::validate_optional_message_id_arg;
+
+impl Execute for OpenArgs {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        let message_id = validate_optional_message_id_arg("--message-id", self.message_id)?;
+        let peer = ctx
+            .resolve_peer(self.to.as_deref(), self.chat_id, self.user_id)
+            .await?;
+        let url = inline_url_from_input(&peer, message_id).ok_or_else(CliError::missing_peer)?;
+        let opened = !self.print;
+        if opened {
+            open_url(&url)?;
+        }
+        if ctx.json {
+            ctx.print_json(&OpenOutput { url, opened })?;
+        } else if !opened {
+            println!("{url}");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OpenOutput {
+    url: String,
+    opened: bool,
+}
@@ -0,0 +1,28 @@
+use serde::Serialize;
+
+use super::{CommandContext, Execute};
+use crate::recent::{RecentPeer, merge_chats, print_recent_peers};
+use crate::{RecentArgs, current_epoch_seconds};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RecentOutput<'a> {
+    peers: &'a [RecentPeer],
+}
+
+impl Execute for RecentArgs {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        let mut state = ctx.local_db.load()?;
+        if self.refresh {
+            let users_by_id = ctx.users_by_id().await?;
+            merge_chats(&mut state, ctx.chats().await?, users_by_id);
+            ctx.local_db.save(&state)?;
+        }
+        let peers = &state.recent_peers[..self.limit.min(state.recent_peers.len())];
+        if ctx.json {
+            return ctx.print_json(&RecentOutput { peers });
+        }
+        print_recent_peers(peers, current_epoch_seconds() as i64);
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+use serde::Serialize;
+
+use super::{CommandContext, Execute};
+use crate::errors::CliError;
+use crate::pending_sends::{PendingSend, print_pending_sends};
+use crate::{
+    ResendCommand, ResendPendingArgs, current_epoch_seconds, idempotency, new_message_ids,
+    send_message_with_random_id,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PendingListOutput<'a> {
+    pending: Vec<&'a PendingSend>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResendOutput {
+    sent: usize,
+    failed: usize,
+    discarded: usize,
+    results: Vec<ResendResult>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResendResult {
+    id: u32,
+    to: String,
+    file_name: String,
+    /// `sent`, `failed` (kept for another try), or `discarded`.
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl Execute for ResendCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            ResendCommand::Pending(args) => pending(ctx, args).await,
+        }
+    }
+}
+
+async fn pending(
+    ctx: &CommandContext,
+    args: ResendPendingArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = ctx.local_db.load()?;
+    if let Some(missing) = args
+        .ids
+        .iter()
+        .find(|id| !state.pending_sends.iter().any(|entry| entry.id == **id))
+    {
+        return Err(CliError::invalid_args(format!(
+            "No pending send with id {missing}; list them with `inline resend pending --dry-run`"
+        ))
+        .into());
+    }
+    let selected = |entry: &PendingSend| args.ids.is_empty() || args.ids.contains(&entry.id);
+
+    if args.dry_run {
+        let pending = state
+            .pending_sends
+            .iter()
+            .filter(|entry| selected(entry))
+            .collect::<Vec<_>>();
+        if ctx.json {
+            return ctx.print_json(&PendingListOutput { pending });
+        }
+        print_pending_sends(&pending, current_epoch_seconds() as i64);
+        return Ok(());
+    }
+
+    let mut results = Vec::new();
+    if args.discard {
+        state.pending_sends.retain(|entry| {
+            if !selected(entry) {
+                return true;
+            }
+            results.push(ResendResult {
+                id: entry.id,
+                to: entry.destination(),
+                file_name: entry.file_name.clone(),
+                status: "discarded",
+                message_id: None,
+                error: None,
+            });
+            false
+        });
+        ctx.local_db.save(&state)?;
+    } else if state.pending_sends.iter().any(selected) {
+        let count = state
+            .pending_sends
+            .iter()
+            .filter(|entry| selected(entry))
+            .count();
+        if !ctx.confirm_send_count(count, args.force)? {
+            println!("Cancelled.");
+            return Ok(());
+        }
+        let realtime = ctx.realtime().await?;
+        let mut kept = Vec::new();
+        for mut entry in std::mem::take(&mut state.pending_sends) {
+            if !selected(&entry) {
+                kept.push(entry);
+                continue;
+            }
+            let send = send_message_with_random_id(
+                &realtime,
+                &entry.peer,
+                entry.caption.clone(),
+                Some(entry.media.clone()),
+                entry.parse_markdown,
+                entry.reply_to_msg_id,
+                entry.entities.clone(),
+                entry.has_link,
+                Some(entry.random_id),
+                entry.send_mode,
+            )
+            .await;
+            match send {
+                Ok(send) => {
+                    let message_id = new_message_ids(&send.updates).first().copied();
+                    if let Some(key) = entry.idempotency_key.as_deref() {
+                        idempotency::record(
+                            &mut state,
+                            key,
+                            &entry.peer,
+                            message_id.into_iter().collect(),
+                            current_epoch_seconds() as i64,
+                        );
+                    }
+                    results.push(ResendResult {
+                        id: entry.id,
+                        to: entry.destination(),
+                        file_name: entry.file_name,
+                        status: "sent",
+                        message_id,
+                        error: None,
+                    });
+                }
+                Err(err) => {
+                    entry.attempts += 1;
+                    entry.last_error = err.to_string();
+                    results.push(ResendResult {
+                        id: entry.id,
+                        to: entry.destination(),
+                        file_name: entry.file_name.clone(),
+                        status: "failed",
+                        message_id: None,
+                        error: Some(entry.last_error.clone()),
+                    });
+                    kept.push(entry);
+                }
+            }
+        }
+        state.pending_sends = kept;
+        ctx.local_db.save(&state)?;
+    }
+
+    let count = |status| {
+        results
+            .iter()
+            .filter(|result| result.status == status)
+            .count()
+    };
+    let output = ResendOutput {
+        sent: count("sent"),
+        failed: count("failed"),
+        discarded: count("discarded"),
+        results,
+    };
+    if ctx.json {
+        ctx.print_json(&output)?;
+    } else if output.results.is_empty() {
+        println!("No pending sends.");
+    } else {
+        for result in &output.results {
+            match (result.status, result.message_id, result.error.as_deref()) {
+                ("sent", Some(id), _) => {
+                    println!("Sent {} to {} (id {id}).", result.file_name, result.to)
+                }
+                ("sent", None, _) => println!("Sent {} to {}.", result.file_name, result.to),
+                ("discarded", ..) => println!("Discarded {} ({}).", result.file_name, result.to),
+                (_, _, error) => println!(
+                    "Failed {} to {}: {}",
+                    result.file_name,
+                    result.to,
+                    error.unwrap_or_default()
+                ),
+            }
+        }
+    }
+    if output.failed > 0 {
+        return Err(CliError::resend_failed(output.failed, output.results.len()).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use std::fs;
+use std::io::{self, Read};
+
+use futures_util::future::join_all;
+use inline_protocol::proto;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{CommandContext, Execute};
+use crate::errors::{CliError, JsonCliError, json_cli_error_from_error};
+use crate::rpc_json::{RpcMethod, result_to_json};
+use crate::{RpcBatchArgs, RpcCallArgs, RpcCommand};
+
+impl Execute for RpcCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            RpcCommand::Call(args) => call(ctx, args).await,
+            RpcCommand::Batch(args) => batch(ctx, args).await,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcCallOutput {
+    method: &'static str,
+    result: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RpcBatchEntry {
+    method: String,
+    #[serde(default)]
+    input: Value,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcBatchItem {
+    index: usize,
+    method: &'static str,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<JsonCliError>,
+}
+
+async fn call(ctx: &CommandContext, args: RpcCallArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let method = RpcMethod::parse(&args.method).map_err(CliError::invalid_rpc)?;
+    let input = match args.input_json.as_deref() {
+        Some(raw) => serde_json::from_str(raw).map_err(|error| {
+            CliError::invalid_args(format!("--input-json is not JSON: {error}"))
+        })?,
+        None => Value::Null,
+    };
+    let input = method
+        .input_from_json(input)
+        .map_err(CliError::invalid_rpc)?;
+
+    let realtime = ctx.realtime().await?;
+    let result = realtime.invoke(method.method, input).await?;
+    // Results are JSON either way; there is no human rendering for arbitrary methods.
+    ctx.print_json(&RpcCallOutput {
+        method: method.name(),
+        result: result_to_json(&result)?,
+    })?;
+    Ok(())
+}
+
+async fn batch(ctx: &CommandContext, args: RpcBatchArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let raw = if args.file.as_os_str() == "-" {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer)?;
+        buffer
+    } else {
+        fs::read_to_string(&args.file)?
+    };
+    let entries: Vec<RpcBatchEntry> = serde_json::from_str(&raw).map_err(|error| {
+        CliError::invalid_args(format!(
+            "{} must be a JSON array of {{\"method\", \"input\"}} objects: {error}",
+            args.file.display()
+        ))
+    })?;
+
+    // Validate everything before sending anything, so a typo in the last
+    // entry doesn't leave the first ones applied.
+    let calls = entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            parse_entry(entry)
+                .map_err(|message| CliError::invalid_rpc(format!("call {index}: {message}")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let sends = calls.iter().map(|(_, input)| messages_sent(input)).sum();
+    if !ctx.confirm_send_count(sends, args.force)? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let realtime = ctx.realtime().await?;
+    let results = join_all(
+        calls
+            .iter()
+            .map(|(method, input)| realtime.invoke(method.method, input.clone())),
+    )
+    .await;
+
+    let mut items = Vec::with_capacity(results.len());
+    for (index, ((method, _), result)) in calls.iter().zip(results).enumerate() {
+        let item = match result {
+            Ok(result) => RpcBatchItem {
+                index,
+                method: method.name(),
+                ok: true,
+                result: Some(result_to_json(&result)?),
+                error: None,
+            },
+            Err(error) => RpcBatchItem {
+                index,
+                method: method.name(),
+                ok: false,
+                result: None,
+                error: Some(json_cli_error_from_error(&error)),
+            },
+        };
+        items.push(item);
+    }
+    ctx.print_json(&items)?;
+
+    let failed = items.iter().filter(|item| !item.ok).count();
+    if failed > 0 {
+        return Err(CliError::rpc_batch_failed(failed, items.len()).into());
+    }
+    Ok(())
+}
+
+/// How many messages a call posts, for the send_limit check.
+fn messages_sent(input: &proto::rpc_call::Input) -> usize {
+    match input {
+        proto::rpc_call::Input::SendMessage(_) => 1,
+        proto::rpc_call::Input::ForwardMessages(input) => input.message_ids.len(),
+        _ => 0,
+    }
+}
+
+fn parse_entry(entry: RpcBatchEntry) -> Result<(RpcMethod, proto::rpc_call::Input), String> {
+    let method = RpcMethod::parse(&entry.method)?;
+    let input = method.input_from_json(entry.input)?;
+    Ok((method, input))
+}
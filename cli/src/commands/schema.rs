@@ -0,0 +1,106 @@
+use serde::Serialize;
+
+use super::{CommandContext, Execute};
+use crate::SchemaCommand;
+use crate::errors::CliError;
+use crate::output_schema::{
+    OUTPUT_SCHEMA_VERSION, OUTPUT_SCHEMAS, WITHOUT_JSON_OUTPUT, output_schema,
+};
+
+impl Execute for SchemaCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            SchemaCommand::Proto => {
+                let bundle = bundled_proto_sources();
+                if ctx.json {
+                    ctx.print_json(&bundle)?;
+                } else {
+                    for file in bundle.files {
+                        println!("# {}", file.name);
+                        println!("{}", file.contents);
+                        println!();
+                    }
+                }
+            }
+            SchemaCommand::Output(args) => {
+                if args.command.is_empty() {
+                    let list = OutputSchemaList {
+                        version: OUTPUT_SCHEMA_VERSION,
+                        schemas: OUTPUT_SCHEMAS
+                            .iter()
+                            .map(|spec| OutputSchemaEntry {
+                                command: spec.command,
+                                description: spec.description,
+                            })
+                            .collect(),
+                    };
+                    if ctx.json {
+                        return ctx.print_json(&list);
+                    }
+                    println!("Output schemas (version {}):", list.version);
+                    let width = list
+                        .schemas
+                        .iter()
+                        .map(|entry| entry.command.len())
+                        .max()
+                        .unwrap_or_default();
+                    for entry in &list.schemas {
+                        println!("  {:<width$}  {}", entry.command, entry.description);
+                    }
+                    return Ok(());
+                }
+                let command = args.command.join(" ");
+                if WITHOUT_JSON_OUTPUT.contains(&command.as_str()) {
+                    return Err(CliError::invalid_args(format!(
+                        "`{command}` has no --json output, so it has no output schema"
+                    ))
+                    .into());
+                }
+                let schema = output_schema(&command).ok_or_else(|| {
+                    CliError::invalid_args(format!(
+                        "No output schema for `{command}`; list them with `inline schema output`"
+                    ))
+                })?;
+                // The schema is itself JSON, so it's printed as JSON either way.
+                ctx.print_json(&schema)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OutputSchemaList {
+    version: u32,
+    schemas: Vec<OutputSchemaEntry>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OutputSchemaEntry {
+    command: &'static str,
+    description: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProtoSchemaFile {
+    name: &'static str,
+    contents: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProtoSchemaBundle {
+    files: Vec<ProtoSchemaFile>,
+}
+
+fn bundled_proto_sources() -> ProtoSchemaBundle {
+    ProtoSchemaBundle {
+        files: vec![ProtoSchemaFile {
+            name: "core.proto",
+            contents: include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../proto/core.proto")),
+        }],
+    }
+}
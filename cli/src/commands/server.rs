@@ -0,0 +1,82 @@
+use serde::Serialize;
+
+use super::{CommandContext, Execute};
+use crate::capabilities;
+use crate::errors::CliError;
+use crate::server_check::{check_server, incompatibilities, print_server_check, server_urls};
+use crate::{ServerCheckArgs, ServerCommand};
+
+impl Execute for ServerCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            ServerCommand::Check(args) => check(ctx, args).await,
+            ServerCommand::ForgetUnsupported => forget_unsupported(ctx),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ForgetUnsupportedOutput {
+    /// Realtime URL the forgotten entry belonged to.
+    realtime_url: Option<String>,
+    forgotten: Vec<String>,
+}
+
+async fn check(
+    ctx: &CommandContext,
+    args: ServerCheckArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (api_base_url, realtime_url) = match args.url.as_deref() {
+        Some(url) => server_urls(url)?,
+        None => (
+            ctx.config.api_base_url.clone(),
+            ctx.config.realtime_url.clone(),
+        ),
+    };
+    let api_base_url = args
+        .api_url
+        .map(|url| url.trim_end_matches('/').to_string())
+        .unwrap_or(api_base_url);
+    let realtime_url = args.realtime_url.unwrap_or(realtime_url);
+    if !ctx.json {
+        eprintln!("Checking {api_base_url}...");
+    }
+
+    let output = check_server(&api_base_url, &realtime_url).await;
+    if ctx.json {
+        ctx.print_json(&output)?;
+    } else {
+        print_server_check(&output);
+    }
+    if !output.compatible {
+        return Err(CliError::server_incompatible(&incompatibilities(&output)).into());
+    }
+    Ok(())
+}
+
+fn forget_unsupported(ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = ctx.local_db.load()?;
+    let forgotten = capabilities::forget(&mut state);
+    if forgotten.is_some() {
+        ctx.local_db.save(&state)?;
+    }
+    let output = ForgetUnsupportedOutput {
+        realtime_url: forgotten.as_ref().map(|entry| entry.realtime_url.clone()),
+        forgotten: forgotten
+            .map(|entry| entry.unsupported_methods)
+            .unwrap_or_default(),
+    };
+    if ctx.json {
+        return ctx.print_json(&output);
+    }
+    match &output.realtime_url {
+        Some(url) if !output.forgotten.is_empty() => println!(
+            "Forgot {} unsupported method(s) for {url}: {}.",
+            output.forgotten.len(),
+            output.forgotten.join(", ")
+        ),
+        _ => println!("No unsupported methods were remembered."),
+    }
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use std::fs;
+use std::io::{self, Read};
+
+use inline_protocol::proto;
+use serde::Serialize;
+
+use super::notifications::fetch_user_settings;
+use super::{CommandContext, Execute};
+use crate::settings_bundle::{SettingChange, export_bundle, import_bundle, parse_bundle};
+use crate::{SettingsCommand, SettingsExportArgs, SettingsImportArgs};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SettingsImportOutput {
+    config_path: String,
+    dry_run: bool,
+    changes: Vec<SettingChange>,
+    /// Whether the file's notification settings were (or would be) applied.
+    notifications: bool,
+}
+
+impl Execute for SettingsCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            SettingsCommand::Export(args) => export(ctx, args).await,
+            SettingsCommand::Import(args) => import(ctx, args).await,
+        }
+    }
+}
+
+async fn export(
+    ctx: &CommandContext,
+    args: SettingsExportArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let notifications = if args.no_notifications {
+        None
+    } else {
+        let realtime = ctx.realtime().await?;
+        fetch_user_settings(&realtime)
+            .await?
+            .and_then(|settings| settings.notification_settings)
+    };
+    let bundle = export_bundle(&ctx.config.file, notifications);
+
+    let Some(path) = args.output else {
+        // The bundle is a file format, so stdout gets JSON even without --json.
+        return ctx.print_json(&bundle);
+    };
+    fs::write(&path, serde_json::to_string_pretty(&bundle)? + "\n")?;
+    if ctx.json {
+        return ctx.print_json(&serde_json::json!({ "path": path.display().to_string() }));
+    }
+    println!(
+        "Exported {} preference(s), {} alias(es), {} command alias(es), and {} default(s){} to {}.",
+        bundle.preferences.len(),
+        bundle.aliases.len(),
+        bundle.command_aliases.len(),
+        bundle.defaults.len(),
+        if bundle.notifications.is_some() {
+            " with notification settings"
+        } else {
+            ""
+        },
+        path.display()
+    );
+    Ok(())
+}
+
+async fn import(
+    ctx: &CommandContext,
+    args: SettingsImportArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let raw = if args.file.as_os_str() == "-" {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer)?;
+        buffer
+    } else {
+        fs::read_to_string(&args.file)?
+    };
+    let bundle = parse_bundle(&raw)?;
+    let mut file = ctx.config.file.clone();
+    let changes = import_bundle(&mut file, &bundle)?;
+    let notifications = match bundle.notifications {
+        Some(settings) if !args.no_notifications => Some(settings),
+        _ => None,
+    };
+
+    if !args.dry_run {
+        if !changes.is_empty() {
+            file.save(&ctx.config.config_path)?;
+        }
+        if let Some(settings) = notifications.clone() {
+            let realtime = ctx.realtime().await?;
+            realtime
+                .call(proto::UpdateUserSettingsInput {
+                    user_settings: Some(proto::UserSettings {
+                        notification_settings: Some(settings),
+                    }),
+                })
+                .await?;
+        }
+    }
+
+    let output = SettingsImportOutput {
+        config_path: ctx.config.config_path.display().to_string(),
+        dry_run: args.dry_run,
+        changes,
+        notifications: notifications.is_some(),
+    };
+    if ctx.json {
+        return ctx.print_json(&output);
+    }
+    let verb = if output.dry_run { "Would set" } else { "Set" };
+    for change in &output.changes {
+        match &change.from {
+            Some(from) => println!("{verb} {} = {} (was {from})", change.key, change.to),
+            None => println!("{verb} {} = {}", change.key, change.to),
+        }
+    }
+    if output.changes.is_empty() {
+        println!("The config file already matches.");
+    } else if !output.dry_run {
+        println!(
+            "Updated {} key(s) in {}.",
+            output.changes.len(),
+            output.config_path
+        );
+    }
+    if output.notifications {
+        println!(
+            "{} notification settings.",
+            if output.dry_run {
+                "Would apply"
+            } else {
+                "Applied"
+            }
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,717 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use futures_util::stream::{self, StreamExt};
+use inline_protocol::proto;
+use serde::Serialize;
+
+use super::{CommandContext, Execute};
+use crate::chat_output::{chat_display_name, space_chats};
+use crate::errors::CliError;
+use crate::history::search_messages_window;
+use crate::member_import::{BulkInviteRole, BulkInviteRow, parse_member_csv};
+use crate::message_export::csv_field;
+use crate::message_output::build_message_list_from_messages;
+use crate::output::{PeerSummary, build_space_list, build_space_members_output, user_display_name};
+use crate::peer::input_chat_peer;
+use crate::validation::{
+    normalize_search_queries, parse_time_filters, validate_message_limit, validate_parallel,
+    validate_positive_id_arg,
+};
+use crate::{
+    SpacesCommand, SpacesInviteArgs, SpacesInviteBulkArgs, SpacesRevokeInviteArgs,
+    SpacesSearchArgs, confirm_action, current_epoch_seconds, output,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BulkInviteOutput {
+    space_id: i64,
+    dry_run: bool,
+    invited: usize,
+    failed: usize,
+    skipped: usize,
+    rows: Vec<BulkInviteResult>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpaceSearchOutput {
+    space_id: i64,
+    queries: Vec<String>,
+    /// Chats of the space that were searched.
+    searched: usize,
+    total: usize,
+    /// Chats with hits, most first, then chats that failed.
+    chats: Vec<SpaceSearchChat>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpaceSearchChat {
+    chat_id: i64,
+    title: String,
+    count: usize,
+    messages: Vec<proto::Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RevokeInviteOutput {
+    space_id: i64,
+    user_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    phone: Option<String>,
+    revoked: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BulkInviteResult {
+    #[serde(flatten)]
+    row: BulkInviteRow,
+    /// `invited`, `failed`, `skipped` (invalid row), or `pending` in a dry run.
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_id: Option<i64>,
+}
+
+impl Execute for SpacesCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            SpacesCommand::List => {
+                let payload = ctx.chats().await?;
+
+                if ctx.json {
+                    ctx.print_json(payload)?;
+                } else {
+                    let output = build_space_list(payload);
+                    output::print_spaces(&output, false, ctx.json_format)?;
+                }
+            }
+            SpacesCommand::Members(args) => {
+                let space_id = validate_positive_id_arg("--space-id", args.space_id)?;
+                let realtime = ctx.realtime().await?;
+                let input = proto::GetSpaceMembersInput { space_id };
+                let payload = realtime.call(input).await?;
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else {
+                    let output = build_space_members_output(payload);
+                    output::print_space_members(&output, false, ctx.json_format)?;
+                }
+            }
+            SpacesCommand::Chats(args) => {
+                let space_id = validate_positive_id_arg("--space-id", args.space_id)?;
+                let mut output = space_chats(ctx.chats().await?, space_id);
+                if args.not_joined {
+                    output.chats.retain(|chat| !chat.joined);
+                }
+                if ctx.json {
+                    ctx.print_json(&output)?;
+                } else {
+                    output::print_space_chats(&output, current_epoch_seconds() as i64);
+                }
+            }
+            SpacesCommand::Search(args) => search(ctx, args).await?,
+            SpacesCommand::Invite(args) => {
+                let space_id = validate_positive_id_arg("--space-id", args.space_id)?;
+                let via = invite_target_from_args(&args)?;
+                let role = invite_role_from_args(args.admin, args.public_chats)?;
+                let realtime = ctx.realtime().await?;
+                let input = proto::InviteToSpaceInput {
+                    space_id,
+                    role,
+                    via: Some(via),
+                };
+                let payload = realtime.call(input).await?;
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else {
+                    let name = payload
+                        .user
+                        .as_ref()
+                        .map(user_display_name)
+                        .unwrap_or_else(|| "user".to_string());
+                    println!("Invited {} to space {}.", name, space_id);
+                }
+            }
+            SpacesCommand::InviteBulk(args) => invite_bulk(ctx, args).await?,
+            SpacesCommand::DeleteMember(args) => {
+                let space_id = validate_positive_id_arg("--space-id", args.space_id)?;
+                let user_id = validate_positive_id_arg("--user-id", args.user_id)?;
+                let prompt = format!("Remove user {} from space {}?", user_id, space_id);
+                if ctx.json && !args.yes {
+                    return Err(CliError::confirmation_required().into());
+                }
+                // Fail on missing credentials before prompting.
+                ctx.token()?;
+                if !confirm_action(&prompt, args.yes)? {
+                    println!("Cancelled.");
+                    return Ok(());
+                }
+                let realtime = ctx.realtime().await?;
+                let input = proto::DeleteMemberInput { space_id, user_id };
+                let payload = realtime.call(input).await?;
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else {
+                    println!("Member removed (updates: {}).", payload.updates.len());
+                }
+            }
+            SpacesCommand::RevokeInvite(args) => revoke_invite(ctx, args).await?,
+            SpacesCommand::UpdateMemberAccess(args) => {
+                let space_id = validate_positive_id_arg("--space-id", args.space_id)?;
+                let user_id = validate_positive_id_arg("--user-id", args.user_id)?;
+                let role = require_member_access_role(args.admin, args.member, args.public_chats)?;
+                let realtime = ctx.realtime().await?;
+                let input = proto::UpdateMemberAccessInput {
+                    space_id,
+                    user_id,
+                    role: Some(role),
+                };
+                let payload = realtime.call(input).await?;
+                if ctx.json {
+                    ctx.print_json(&payload)?;
+                } else {
+                    println!(
+                        "Updated member access (updates: {}).",
+                        payload.updates.len()
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn invite_bulk(
+    ctx: &CommandContext,
+    args: SpacesInviteBulkArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let space_id = validate_positive_id_arg("--space-id", args.space_id)?;
+    let contents = std::fs::read_to_string(&args.csv).map_err(|err| {
+        CliError::invalid_args(format!("Couldn't read {}: {err}", args.csv.display()))
+    })?;
+    let rows = parse_member_csv(&contents)
+        .map_err(|err| CliError::invalid_args(format!("{}: {err}", args.csv.display())))?;
+    let invalid = rows.iter().filter(|row| row.error.is_some()).count();
+    let valid = rows.len() - invalid;
+
+    if !ctx.json {
+        print_bulk_invite_preview(&rows);
+        println!("{valid} to invite, {invalid} with problems, space {space_id}.",);
+    }
+    if args.dry_run {
+        let output = bulk_invite_output(space_id, true, rows, Vec::new());
+        return if ctx.json {
+            ctx.print_json(&output)
+        } else {
+            Ok(())
+        };
+    }
+    if invalid > 0 && !args.skip_invalid {
+        return Err(CliError::invalid_args(format!(
+            "{invalid} row(s) have problems; fix them or pass --skip-invalid to invite the rest"
+        ))
+        .into());
+    }
+    if valid == 0 {
+        return Err(CliError::invalid_args("No valid rows to invite").into());
+    }
+    if ctx.json && !args.yes {
+        return Err(CliError::confirmation_required().into());
+    }
+    // Fail on missing credentials before prompting.
+    ctx.token()?;
+    if !confirm_action(
+        &format!("Invite {valid} people to space {space_id}?"),
+        args.yes,
+    )? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let realtime = ctx.realtime().await?;
+    let mut outcomes = Vec::with_capacity(rows.len());
+    let mut sent = 0usize;
+    for row in &rows {
+        if row.error.is_some() {
+            outcomes.push(Err(None));
+            continue;
+        }
+        if sent > 0 && args.delay_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(args.delay_ms)).await;
+        }
+        sent += 1;
+        let input = proto::InviteToSpaceInput {
+            space_id,
+            role: bulk_invite_role(row.role),
+            via: Some(proto::invite_to_space_input::Via::Email(row.email.clone())),
+        };
+        let outcome = realtime
+            .call(input)
+            .await
+            .map(|payload| payload.user.map(|user| user.id))
+            .map_err(|err| Some(err.to_string()));
+        if !ctx.json {
+            match &outcome {
+                Ok(_) => println!("Invited {} ({}).", row.email, row.role.label()),
+                Err(Some(err)) => eprintln!("Failed to invite {}: {err}", row.email),
+                Err(None) => {}
+            }
+        }
+        outcomes.push(outcome);
+    }
+
+    let output = bulk_invite_output(space_id, false, rows, outcomes);
+    if let Some(path) = &args.report {
+        std::fs::write(path, bulk_invite_report_csv(&output.rows))?;
+    }
+    if ctx.json {
+        return ctx.print_json(&output);
+    }
+    println!(
+        "Invited {}, failed {}, skipped {}.",
+        output.invited, output.failed, output.skipped
+    );
+    if let Some(path) = &args.report {
+        println!("Report written to {}.", path.display());
+    }
+    Ok(())
+}
+
+/// `outcomes` lines up with `rows`; `Err(None)` marks a skipped row. Empty
+/// for dry runs.
+fn bulk_invite_output(
+    space_id: i64,
+    dry_run: bool,
+    rows: Vec<BulkInviteRow>,
+    outcomes: Vec<Result<Option<i64>, Option<String>>>,
+) -> BulkInviteOutput {
+    let mut outcomes = outcomes.into_iter();
+    let rows = rows
+        .into_iter()
+        .map(|mut row| {
+            let (status, user_id) = match outcomes.next() {
+                _ if row.error.is_some() => ("skipped", None),
+                None => ("pending", None),
+                Some(Ok(user_id)) => ("invited", user_id),
+                Some(Err(error)) => {
+                    row.error = error;
+                    ("failed", None)
+                }
+            };
+            BulkInviteResult {
+                row,
+                status,
+                user_id,
+            }
+        })
+        .collect::<Vec<_>>();
+    let count = |status: &str| rows.iter().filter(|row| row.status == status).count();
+    BulkInviteOutput {
+        space_id,
+        dry_run,
+        invited: count("invited"),
+        failed: count("failed"),
+        skipped: count("skipped"),
+        rows,
+    }
+}
+
+fn print_bulk_invite_preview(rows: &[BulkInviteRow]) {
+    let email_width = rows
+        .iter()
+        .map(|row| row.email.chars().count())
+        .max()
+        .unwrap_or_default()
+        .clamp(5, 40);
+    println!(
+        "{}",
+        output::style_heading(&format!(
+            "{:>5}  {:<email_width$}  {:<12}  status",
+            "line", "email", "role"
+        ))
+    );
+    for row in rows {
+        println!(
+            "{:>5}  {:<email_width$}  {:<12}  {}",
+            row.line,
+            row.email,
+            row.role.label(),
+            row.error.as_deref().unwrap_or("ok")
+        );
+    }
+}
+
+fn bulk_invite_report_csv(rows: &[BulkInviteResult]) -> String {
+    let mut report = String::from("line,email,role,status,user_id,error\n");
+    for result in rows {
+        report.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            result.row.line,
+            csv_field(&result.row.email),
+            result.row.role.label(),
+            result.status,
+            result
+                .user_id
+                .map(|user_id| user_id.to_string())
+                .unwrap_or_default(),
+            csv_field(result.row.error.as_deref().unwrap_or_default())
+        ));
+    }
+    report
+}
+
+fn bulk_invite_role(role: BulkInviteRole) -> Option<proto::SpaceMemberRole> {
+    match role {
+        // Same as `spaces invite` without flags: the server's default role.
+        BulkInviteRole::Member => None,
+        BulkInviteRole::PublicChats => Some(space_member_role_member(true)),
+        BulkInviteRole::Admin => Some(space_member_role_admin()),
+    }
+}
+
+async fn search(
+    ctx: &CommandContext,
+    args: SpacesSearchArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let space_id = validate_positive_id_arg("--space-id", args.space_id)?;
+    let queries = normalize_search_queries(&args.query)?;
+    let limit = validate_message_limit(args.limit)?;
+    let parallel = validate_parallel(args.parallel)?;
+    let (since_ts, until_ts) =
+        parse_time_filters(args.since.as_deref(), args.until.as_deref(), Utc::now())?;
+
+    let users_by_id = ctx.users_by_id().await?;
+    let chats = ctx
+        .chats()
+        .await?
+        .chats
+        .iter()
+        .filter(|chat| chat.space_id == Some(space_id))
+        .collect::<Vec<_>>();
+    if chats.is_empty() {
+        return Err(CliError::invalid_args(format!(
+            "No chats from space {space_id} in your chat list"
+        ))
+        .into());
+    }
+    let searched = chats.len();
+    let realtime = ctx.realtime().await?;
+    let mut outcomes = stream::iter(chats)
+        .map(|chat| {
+            let realtime = &realtime;
+            let queries = queries.clone();
+            async move {
+                let peer = input_chat_peer(chat.id);
+                let outcome =
+                    search_messages_window(realtime, &peer, queries, limit, since_ts, until_ts)
+                        .await;
+                (chat, outcome)
+            }
+        })
+        .buffer_unordered(parallel)
+        .collect::<Vec<_>>()
+        .await;
+    // One chat's failure is reported with the results; only a search that
+    // failed everywhere fails the command, with that error.
+    if outcomes.iter().all(|(_, outcome)| outcome.is_err())
+        && let Some((_, Err(err))) = outcomes.pop()
+    {
+        return Err(err);
+    }
+    let mut results = outcomes
+        .into_iter()
+        .map(|(chat, outcome)| {
+            let (messages, error) = match outcome {
+                Ok(messages) => (messages, None),
+                Err(err) => (Vec::new(), Some(err.to_string())),
+            };
+            SpaceSearchChat {
+                chat_id: chat.id,
+                title: chat_display_name(chat, users_by_id),
+                count: messages.len(),
+                messages,
+                error,
+            }
+        })
+        .filter(|chat| chat.count > 0 || chat.error.is_some())
+        .collect::<Vec<_>>();
+    results.sort_by(|a, b| {
+        a.error
+            .is_some()
+            .cmp(&b.error.is_some())
+            .then(b.count.cmp(&a.count))
+            .then_with(|| a.title.cmp(&b.title))
+    });
+    let failed = results.iter().filter(|chat| chat.error.is_some()).count();
+    let output = SpaceSearchOutput {
+        space_id,
+        queries,
+        searched,
+        total: results.iter().map(|chat| chat.count).sum(),
+        chats: results,
+    };
+
+    if ctx.json {
+        ctx.print_json(&output)?;
+        return Ok(());
+    }
+    let current_user_id = ctx.local_db.load()?.current_user.map(|user| user.id);
+    for chat in output.chats.iter().filter(|chat| chat.count > 0) {
+        let list = build_message_list_from_messages(
+            &chat.messages,
+            users_by_id,
+            current_user_id,
+            Some(PeerSummary {
+                peer_type: "chat".to_string(),
+                id: chat.chat_id,
+            }),
+            Some(format!("{} ({})", chat.title, chat.count)),
+            None,
+        );
+        output::print_messages(&list, false, ctx.json_format)?;
+        println!();
+    }
+    for chat in output.chats.iter().filter(|chat| chat.error.is_some()) {
+        eprintln!(
+            "Couldn't search {} (chat {}): {}",
+            chat.title,
+            chat.chat_id,
+            chat.error.as_deref().unwrap_or_default()
+        );
+    }
+    let with_hits = output.chats.len() - failed;
+    println!(
+        "{} result(s) in {with_hits} of {} chat(s).",
+        output.total, output.searched
+    );
+    Ok(())
+}
+
+async fn revoke_invite(
+    ctx: &CommandContext,
+    args: SpacesRevokeInviteArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let space_id = validate_positive_id_arg("--space-id", args.space_id)?;
+    let email = args
+        .email
+        .as_deref()
+        .map(str::trim)
+        .filter(|email| !email.is_empty());
+    let phone = args
+        .phone
+        .as_deref()
+        .map(str::trim)
+        .filter(|phone| !phone.is_empty());
+    let target = match (email, phone) {
+        (Some(email), None) => email,
+        (None, Some(phone)) => phone,
+        _ => return Err(CliError::invalid_args("Provide one of --email or --phone").into()),
+    };
+    if ctx.json && !args.yes {
+        return Err(CliError::confirmation_required().into());
+    }
+
+    let realtime = ctx.realtime().await?;
+    let members = realtime
+        .call(proto::GetSpaceMembersInput { space_id })
+        .await?;
+    let invitee = members
+        .users
+        .iter()
+        .filter(|user| {
+            members
+                .members
+                .iter()
+                .any(|member| member.user_id == user.id)
+        })
+        .find(|user| invite_matches(user, email, phone))
+        .ok_or_else(|| CliError::no_pending_invite(space_id, target, None))?;
+    if invitee.pending_setup != Some(true) {
+        return Err(CliError::no_pending_invite(space_id, target, Some(invitee.id)).into());
+    }
+    let user_id = invitee.id;
+
+    let prompt = format!("Revoke the invite for {target} to space {space_id}?");
+    if !confirm_action(&prompt, args.yes)? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+    realtime
+        .call(proto::DeleteMemberInput { space_id, user_id })
+        .await?;
+    if ctx.json {
+        ctx.print_json(&RevokeInviteOutput {
+            space_id,
+            user_id,
+            email: email.map(str::to_string),
+            phone: phone.map(str::to_string),
+            revoked: true,
+        })?;
+    } else {
+        println!("Revoked the invite for {target} to space {space_id}.");
+    }
+    Ok(())
+}
+
+/// Emails match case-insensitively and phone numbers by their digits, so
+/// `+1 (555) 123-4567` finds `+15551234567`.
+fn invite_matches(user: &proto::User, email: Option<&str>, phone: Option<&str>) -> bool {
+    fn digits(value: &str) -> String {
+        value.chars().filter(char::is_ascii_digit).collect()
+    }
+    match (email, phone) {
+        (Some(email), _) => user
+            .email
+            .as_deref()
+            .is_some_and(|known| known.eq_ignore_ascii_case(email)),
+        (None, Some(phone)) => user
+            .phone_number
+            .as_deref()
+            .is_some_and(|known| !digits(phone).is_empty() && digits(known) == digits(phone)),
+        (None, None) => false,
+    }
+}
+
+fn invite_target_from_args(
+    args: &SpacesInviteArgs,
+) -> Result<proto::invite_to_space_input::Via, Box<dyn std::error::Error>> {
+    let mut target = None;
+    if let Some(user_id) = args.user_id {
+        let user_id = validate_positive_id_arg("--user-id", user_id)?;
+        target = Some(proto::invite_to_space_input::Via::UserId(user_id));
+    }
+    if let Some(email) = args.email.as_ref() {
+        if target.is_some() {
+            return Err(CliError::invalid_args(
+                "Provide only one of --user-id, --email, or --phone",
+            )
+            .into());
+        }
+        let trimmed = email.trim();
+        if trimmed.is_empty() {
+            return Err(CliError::invalid_args("Email cannot be empty").into());
+        }
+        target = Some(proto::invite_to_space_input::Via::Email(
+            trimmed.to_string(),
+        ));
+    }
+    if let Some(phone) = args.phone.as_ref() {
+        if target.is_some() {
+            return Err(CliError::invalid_args(
+                "Provide only one of --user-id, --email, or --phone",
+            )
+            .into());
+        }
+        let trimmed = phone.trim();
+        if trimmed.is_empty() {
+            return Err(CliError::invalid_args("Phone number cannot be empty").into());
+        }
+        target = Some(proto::invite_to_space_input::Via::PhoneNumber(
+            trimmed.to_string(),
+        ));
+    }
+    target.ok_or_else(|| CliError::invalid_args("Provide --user-id, --email, or --phone").into())
+}
+
+fn invite_role_from_args(
+    admin: bool,
+    public_chats: bool,
+) -> Result<Option<proto::SpaceMemberRole>, Box<dyn std::error::Error>> {
+    if admin && public_chats {
+        return Err(CliError::invalid_args("Provide only one of --admin or --public-chats").into());
+    }
+    if admin {
+        return Ok(Some(space_member_role_admin()));
+    }
+    if public_chats {
+        return Ok(Some(space_member_role_member(true)));
+    }
+    Ok(None)
+}
+
+fn require_member_access_role(
+    admin: bool,
+    member: bool,
+    public_chats: bool,
+) -> Result<proto::SpaceMemberRole, Box<dyn std::error::Error>> {
+    if admin && (member || public_chats) {
+        return Err(CliError::invalid_args(
+            "Provide only one of --admin or --member/--public-chats",
+        )
+        .into());
+    }
+    if admin {
+        return Ok(space_member_role_admin());
+    }
+    if !member && !public_chats {
+        return Err(
+            CliError::invalid_args("Provide --admin or --member (or --public-chats)").into(),
+        );
+    }
+    Ok(space_member_role_member(public_chats))
+}
+
+fn space_member_role_member(can_access_public_chats: bool) -> proto::SpaceMemberRole {
+    proto::SpaceMemberRole {
+        role: Some(proto::space_member_role::Role::Member(
+            proto::SpaceMemberOptions {
+                can_access_public_chats,
+            },
+        )),
+    }
+}
+
+fn space_member_role_admin() -> proto::SpaceMemberRole {
+    proto::SpaceMemberRole {
+        role: Some(proto::space_member_role::Role::Admin(
+            proto::SpaceAdminOptions {},
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invite_user_id_is_structured_invalid_args() {
+        let args = SpacesInviteArgs {
+            space_id: 1,
+            user_id: Some(0),
+            email: None,
+            phone: None,
+            admin: false,
+            public_chats: false,
+        };
+        let err = invite_target_from_args(&args).unwrap_err();
+        let cli_err = err.downcast_ref::<CliError>().unwrap();
+
+        assert_eq!(cli_err.code, "invalid_args");
+        assert!(cli_err.message.contains("--user-id"));
+    }
+
+    #[test]
+    fn invites_match_email_case_and_phone_digits() {
+        let user = proto::User {
+            email: Some("Sam@Example.com".to_string()),
+            phone_number: Some("+15551234567".to_string()),
+            ..Default::default()
+        };
+        assert!(invite_matches(&user, Some("sam@example.com"), None));
+        assert!(invite_matches(&user, None, Some("+1 (555) 123-4567")));
+        assert!(!invite_matches(&user, None, Some("+15550000000")));
+        assert!(!invite_matches(
+            &proto::User::default(),
+            Some("sam@example.com"),
+            None
+        ));
+    }
+}
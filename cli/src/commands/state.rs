@@ -0,0 +1,70 @@
+use serde::Serialize;
+
+use super::{CommandContext, Execute};
+use crate::output;
+use crate::state::StateMigrationStatus;
+use crate::{StateCommand, StateMigrateArgs};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StateMigrateOutput {
+    #[serde(flatten)]
+    status: StateMigrationStatus,
+    dry_run: bool,
+    applied: bool,
+}
+
+impl Execute for StateCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            StateCommand::Migrate(args) => migrate(ctx, args),
+        }
+    }
+}
+
+fn migrate(ctx: &CommandContext, args: StateMigrateArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let status = if args.dry_run {
+        ctx.local_db.migration_status()?
+    } else {
+        ctx.local_db.migrate()?
+    };
+    let applied = !args.dry_run && status.exists && !status.pending.is_empty();
+    if ctx.json {
+        return ctx.print_json(&StateMigrateOutput {
+            status,
+            dry_run: args.dry_run,
+            applied,
+        });
+    }
+
+    if !status.exists {
+        println!(
+            "No state file at {} yet; it will be created at version {}.",
+            status.path, status.to_version
+        );
+        return Ok(());
+    }
+    if status.pending.is_empty() {
+        println!(
+            "State file {} is up to date (version {}).",
+            status.path, status.to_version
+        );
+        return Ok(());
+    }
+    println!(
+        "State file {} is at version {}; this CLI uses version {}.",
+        status.path, status.from_version, status.to_version
+    );
+    println!();
+    println!("{}", output::style_heading("Pending migrations"));
+    for migration in &status.pending {
+        println!("  {:>3}  {}", migration.version, migration.description);
+    }
+    println!();
+    if applied {
+        println!("Migrated to version {}.", status.to_version);
+    } else {
+        println!("Dry run: nothing was changed. Run `inline state migrate` to apply.");
+    }
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::env;
+
+use chrono::Utc;
+use inline_protocol::proto;
+use serde::Serialize;
+
+use super::messages::export_peer_from_input_peer;
+use super::{CommandContext, Execute};
+use crate::errors::CliError;
+use crate::history::fetch_history_window;
+use crate::message_export::{
+    MessageExportBuildInput, MessageExportFormat, build_message_export_bundle, render_export,
+};
+use crate::peer::input_peer_from_args;
+use crate::summarize::{SummarizeRequest, post_to_summarizer_endpoint, run_summarizer_command};
+use crate::validation::{parse_time_filters, validate_optional_positive_id_arg};
+use crate::{SummarizeArgs, send_message};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SummarizeOutput {
+    peer_type: String,
+    peer_id: i64,
+    name: Option<String>,
+    since: Option<i64>,
+    until: Option<i64>,
+    messages: usize,
+    summary: Option<String>,
+    posted_to_chat_id: Option<i64>,
+}
+
+enum Summarizer {
+    Command(String),
+    Endpoint(String),
+}
+
+impl Execute for SummarizeArgs {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        let post_to = validate_optional_positive_id_arg("--post-to", self.post_to)?;
+        if self.limit <= 0 {
+            return Err(CliError::invalid_args("--limit must be greater than 0").into());
+        }
+        let summarizer = match (self.command, self.endpoint) {
+            (Some(command), _) => Summarizer::Command(command),
+            (None, Some(url)) => Summarizer::Endpoint(url),
+            (None, None) => {
+                return Err(CliError::invalid_args(
+                    "Pass --command or --endpoint (or set defaults.summarize.command in the config)",
+                )
+                .into());
+            }
+        };
+        let bearer_token = match self.token_env.as_deref() {
+            Some(name) => Some(env::var(name).map_err(|_| {
+                CliError::invalid_args(format!("--token-env {name} is not set in the environment"))
+            })?),
+            None => None,
+        };
+        let (since_ts, until_ts) =
+            parse_time_filters(Some(&self.since), self.until.as_deref(), Utc::now())?;
+        let peer = ctx
+            .resolve_peer(self.to.as_deref(), self.chat_id, self.user_id)
+            .await?;
+        let realtime = ctx.realtime().await?;
+        let messages =
+            fetch_history_window(&realtime, &peer, None, Some(self.limit), since_ts, until_ts)
+                .await?;
+
+        let users_by_id = ctx.users_by_id().await?;
+        let chats_by_id = ctx.chats_by_id().await?;
+        let export_peer = export_peer_from_input_peer(&peer, users_by_id, chats_by_id);
+        let mut output = SummarizeOutput {
+            peer_type: export_peer.peer_type.clone(),
+            peer_id: export_peer.id,
+            name: export_peer.name.clone(),
+            since: since_ts,
+            until: until_ts,
+            messages: messages.len(),
+            summary: None,
+            posted_to_chat_id: None,
+        };
+        if messages.is_empty() {
+            if ctx.json {
+                return ctx.print_json(&output);
+            }
+            println!("No messages to summarize since {}.", self.since);
+            return Ok(());
+        }
+
+        let spaces_by_id = ctx
+            .chats()
+            .await?
+            .spaces
+            .iter()
+            .map(|space| (space.id, space.clone()))
+            .collect::<HashMap<_, _>>();
+        let related_messages_by_id = messages
+            .iter()
+            .cloned()
+            .map(|message| (message.id, message))
+            .collect::<HashMap<i64, proto::Message>>();
+        let bundle = build_message_export_bundle(MessageExportBuildInput {
+            peer: export_peer,
+            messages,
+            users_by_id,
+            chats_by_id,
+            spaces_by_id: &spaces_by_id,
+            related_messages_by_id: &related_messages_by_id,
+            forward_messages_by_key: &HashMap::new(),
+            translations: Vec::new(),
+            warnings: Vec::new(),
+        });
+        let transcript = render_export(&bundle, MessageExportFormat::Markdown, ctx.json_format)?;
+
+        let request = SummarizeRequest {
+            peer_type: &output.peer_type,
+            peer_id: output.peer_id,
+            name: output.name.as_deref(),
+            since: since_ts,
+            until: until_ts,
+            messages: output.messages,
+            transcript: &transcript,
+        };
+        let summary = match &summarizer {
+            Summarizer::Command(command) => {
+                run_summarizer_command(command, &request, ctx.config.read_only).await?
+            }
+            Summarizer::Endpoint(url) => {
+                post_to_summarizer_endpoint(url, bearer_token.as_deref(), &request).await?
+            }
+        };
+        let summary = summary.trim().to_string();
+        if summary.is_empty() {
+            return Err(CliError::summarizer_failed("The summarizer returned no text").into());
+        }
+
+        if let Some(chat_id) = post_to {
+            let target = input_peer_from_args(Some(chat_id), None)?;
+            send_message(
+                &realtime,
+                &target,
+                Some(summary.clone()),
+                None,
+                true,
+                None,
+                None,
+                None,
+            )
+            .await?;
+            output.posted_to_chat_id = Some(chat_id);
+        }
+        output.summary = Some(summary);
+
+        if ctx.json {
+            return ctx.print_json(&output);
+        }
+        println!("{}", output.summary.as_deref().unwrap_or_default());
+        if let Some(chat_id) = output.posted_to_chat_id {
+            println!("\nPosted summary to chat {chat_id}.");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use std::time::Duration;
+
+use inline_client::{
+    AuthCredential, AuthToken, ClientBackend, ClientStore, ConnectRequest, InlineId, SdkBackend,
+    SqliteStore,
+};
+
+use super::{CommandContext, Execute};
+use crate::errors::CliError;
+use crate::identity::{self, client_identity};
+use crate::output::format_relative_date;
+use crate::sync::{SyncChatSummary, SyncSummary, client_store_path};
+use crate::{SyncArgs, current_epoch_seconds, fetch_me};
+
+impl Execute for SyncArgs {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        let store_path = client_store_path(&ctx.config.data_dir);
+        // Until the cache can be sealed like the state file, don't write
+        // message history in plaintext next to encrypted state.
+        if let Some(key_source) = ctx.local_db.encryption_key_source() {
+            return Err(CliError::sync_cache_unencrypted(&store_path, key_source).into());
+        }
+        let store = SqliteStore::open(store_path)?;
+        let mut backend = SdkBackend::builder()
+            .api_base_url(ctx.config.api_base_url.clone())
+            .realtime_url(ctx.config.realtime_url.clone())
+            .identity(client_identity())
+            .store(store.clone());
+        if let Some(proxy) = identity::proxy() {
+            backend = backend.proxy(proxy.clone());
+        }
+        if let Some(tls) = identity::tls() {
+            backend = backend.tls(tls.clone());
+        }
+        let backend = backend.build()?;
+
+        // The namespace ties the cache to one account; switching accounts
+        // starts a fresh cache instead of mixing histories.
+        let user_id = match ctx.local_db.load()?.current_user {
+            Some(user) => user.id,
+            None => fetch_me(&ctx.realtime().await?).await?.id,
+        };
+        let token = AuthToken::try_new(ctx.token()?)?;
+        backend
+            .connect(
+                ConnectRequest::new(AuthCredential::AccessToken { token })
+                    .with_account_namespace(user_id.to_string()),
+            )
+            .await?;
+
+        let mut summary = SyncSummary::default();
+        summary.first_sync = store.sync_state().await?.last_sync_date == 0;
+        let mut deliveries = backend.catch_up().await?;
+        // Chat and space buckets arrive as hints after discovery; keep
+        // applying them until the connection has been quiet for a while.
+        let idle = Duration::from_millis(self.idle_ms);
+        loop {
+            for delivery in deliveries {
+                summary.record(&delivery.event);
+                if let Some(delivery_id) = delivery.delivery_id {
+                    backend.acknowledge_event_delivery(delivery_id).await?;
+                }
+            }
+            match tokio::time::timeout(idle, backend.receive_event_deliveries()).await {
+                Ok(next) => deliveries = next?,
+                Err(_) => break,
+            }
+        }
+
+        summary.last_sync_date = store.sync_state().await?.last_sync_date;
+        for (chat_id, messages) in summary.active_chat_ids() {
+            let title = match store.dialog(InlineId::new(chat_id)).await? {
+                Some(dialog) => match (dialog.title, dialog.peer_user_id) {
+                    (Some(title), _) => Some(title),
+                    (None, Some(user_id)) => store
+                        .user(user_id)
+                        .await?
+                        .and_then(|user| user.display_name.or(user.username)),
+                    (None, None) => None,
+                },
+                None => None,
+            };
+            summary.chats.push(SyncChatSummary {
+                chat_id,
+                title,
+                messages,
+            });
+        }
+
+        if ctx.json {
+            return ctx.print_json(&summary);
+        }
+        if summary.first_sync {
+            println!(
+                "Started the local cache from a snapshot; later syncs pull only what changed."
+            );
+        }
+        if summary.is_empty() {
+            println!("Already up to date.");
+        } else {
+            println!(
+                "Synced {} new or edited message(s) in {} chat(s), {} deleted.",
+                summary.messages,
+                summary.chats.len(),
+                summary.deleted_messages
+            );
+            for chat in &summary.chats {
+                let title = chat
+                    .title
+                    .clone()
+                    .unwrap_or_else(|| format!("chat {}", chat.chat_id));
+                println!("  {title}: {} message(s)", chat.messages);
+            }
+            let other = [
+                (summary.chats_updated, "chat(s) updated"),
+                (summary.chats_deleted, "chat(s) deleted"),
+                (summary.users_updated, "user(s) updated"),
+                (summary.spaces_updated, "space(s) updated"),
+                (summary.reactions, "reaction change(s)"),
+                (summary.read_states, "read state change(s)"),
+            ]
+            .into_iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, label)| format!("{count} {label}"))
+            .collect::<Vec<_>>();
+            if !other.is_empty() {
+                println!("  Also: {}.", other.join(", "));
+            }
+        }
+        if summary.last_sync_date > 0 {
+            println!(
+                "Cache is current as of {}.",
+                format_relative_date(summary.last_sync_date, current_epoch_seconds() as i64)
+            );
+        }
+        Ok(())
+    }
+}
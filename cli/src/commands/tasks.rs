@@ -0,0 +1,125 @@
+use serde::Serialize;
+
+use super::{CommandContext, Execute};
+use crate::config_file::ConfigFile;
+use crate::peer::input_peer_from_args;
+use crate::tasks::{
+    BUILTIN_TASK_PROVIDERS, LinearTaskProvider, NotionTaskProvider, TaskProvider, TaskSource,
+    TaskSourceMessage, resolve_task_provider, webhook_task_providers,
+};
+use crate::validation::{
+    validate_message_id_arg, validate_optional_positive_id_arg, validate_positive_id_arg,
+};
+use crate::{TasksCommand, fetch_me, fetch_message_by_id, tasks};
+
+impl Execute for TasksCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            TasksCommand::CreateLinear(args) => {
+                create_task(
+                    ctx,
+                    &LinearTaskProvider,
+                    args.chat_id,
+                    args.message_id,
+                    args.space_id,
+                )
+                .await?;
+            }
+            TasksCommand::CreateNotion(args) => {
+                let space_id = validate_positive_id_arg("--space-id", args.space_id)?;
+                create_task(
+                    ctx,
+                    &NotionTaskProvider,
+                    args.chat_id,
+                    args.message_id,
+                    Some(space_id),
+                )
+                .await?;
+            }
+            TasksCommand::Create(args) => {
+                let config_file = ConfigFile::load(&ctx.config.config_path)?;
+                let provider = resolve_task_provider(&args.provider, &config_file)?;
+                create_task(ctx, &provider, args.chat_id, args.message_id, args.space_id).await?;
+            }
+            TasksCommand::Providers => {
+                let config_file = ConfigFile::load(&ctx.config.config_path)?;
+                let output = TaskProvidersOutput {
+                    builtin: BUILTIN_TASK_PROVIDERS.to_vec(),
+                    webhooks: webhook_task_providers(&config_file)?,
+                    config_path: ctx.config.config_path.display().to_string(),
+                };
+                if ctx.json {
+                    ctx.print_json(&output)?;
+                } else {
+                    for name in &output.builtin {
+                        println!("{name}\tbuilt-in");
+                    }
+                    for webhook in &output.webhooks {
+                        println!("{}\twebhook\t{}", webhook.name, webhook.url);
+                    }
+                    if output.webhooks.is_empty() {
+                        println!("No webhook providers configured in {}.", output.config_path);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn create_task(
+    ctx: &CommandContext,
+    provider: &impl TaskProvider,
+    chat_id: i64,
+    message_id: i64,
+    space_id: Option<i64>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let chat_id = validate_positive_id_arg("--chat-id", chat_id)?;
+    let message_id = validate_message_id_arg("--message-id", message_id)?;
+    let space_id = validate_optional_positive_id_arg("--space-id", space_id)?;
+    let token = ctx.token()?;
+
+    let message = if provider.requires_message() {
+        let realtime = ctx.realtime().await?;
+        let me = fetch_me(&realtime).await?;
+        let peer = input_peer_from_args(Some(chat_id), None)?;
+        let message = fetch_message_by_id(&realtime, &peer, message_id).await?;
+        Some(TaskSourceMessage {
+            from_id: me.id,
+            text: message.message.unwrap_or_default(),
+            date: message.date,
+        })
+    } else {
+        None
+    };
+    let source = TaskSource {
+        chat_id,
+        message_id,
+        space_id,
+        message,
+    };
+
+    let created = provider
+        .create_from_message(&ctx.api, &token, &source)
+        .await?;
+    if ctx.json {
+        ctx.print_json(&created.payload)?;
+    } else if let Some(url) = created.url {
+        let title_display = created
+            .title
+            .map(|title| format!(" \"{title}\""))
+            .unwrap_or_default();
+        println!("Created {}{}: {}", provider.label(), title_display, url);
+    } else {
+        println!("{} created.", provider.label());
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskProvidersOutput {
+    builtin: Vec<&'static str>,
+    webhooks: Vec<tasks::WebhookTaskProvider>,
+    config_path: String,
+}
@@ -0,0 +1,32 @@
+use inline_protocol::proto;
+
+use super::{CommandContext, Execute};
+use crate::{TypingCommand, peer_label_from_input};
+
+impl Execute for TypingCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        let (label, args, action) = match self {
+            TypingCommand::Start(args) => (
+                "started",
+                args,
+                Some(proto::update_compose_action::ComposeAction::Typing as i32),
+            ),
+            TypingCommand::Stop(args) => ("stopped", args, None),
+        };
+        let peer = ctx
+            .resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+            .await?;
+        let realtime = ctx.realtime().await?;
+        let input = proto::SendComposeActionInput {
+            peer_id: Some(peer.clone()),
+            action,
+        };
+        let payload = realtime.call(input).await?;
+        if ctx.json {
+            ctx.print_json(&payload)?;
+        } else {
+            println!("Typing {label} for {}.", peer_label_from_input(&peer));
+        }
+        Ok(())
+    }
+}
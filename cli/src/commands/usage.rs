@@ -0,0 +1,80 @@
+use futures_util::stream::{self, StreamExt};
+
+use super::{CommandContext, Execute};
+use crate::UsageArgs;
+use crate::chat_output::chat_display_name;
+use crate::errors::CliError;
+use crate::history::fetch_history_after;
+use crate::output;
+use crate::peer::input_chat_peer;
+use crate::usage::{ChatUsage, build_usage_output, tally_chat};
+use crate::validation::{validate_parallel, validate_positive_id_arg};
+
+impl Execute for UsageArgs {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        let space_id = validate_positive_id_arg("--space-id", self.space_id)?;
+        let parallel = validate_parallel(self.parallel)?;
+        if self.limit == Some(0) {
+            return Err(CliError::invalid_args("--limit must be at least 1").into());
+        }
+
+        let users_by_id = ctx.users_by_id().await?;
+        let chats = ctx
+            .chats()
+            .await?
+            .chats
+            .iter()
+            .filter(|chat| chat.space_id == Some(space_id))
+            .collect::<Vec<_>>();
+        if chats.is_empty() {
+            return Err(CliError::invalid_args(format!(
+                "No chats from space {space_id} in your chat list"
+            ))
+            .into());
+        }
+        let realtime = ctx.realtime().await?;
+        let mut outcomes = stream::iter(chats)
+            .map(|chat| {
+                let realtime = &realtime;
+                async move {
+                    let outcome = fetch_history_after(realtime, &input_chat_peer(chat.id), None)
+                        .await
+                        .map(|(messages, complete)| {
+                            tally_chat(
+                                chat.id,
+                                chat_display_name(chat, users_by_id),
+                                &messages,
+                                complete,
+                            )
+                        });
+                    (chat, outcome)
+                }
+            })
+            .buffer_unordered(parallel)
+            .collect::<Vec<_>>()
+            .await;
+        // Like `spaces search`: a chat that failed is listed with its error,
+        // and only a scan that failed everywhere fails the command.
+        if outcomes.iter().all(|(_, outcome)| outcome.is_err())
+            && let Some((_, Err(err))) = outcomes.pop()
+        {
+            return Err(err);
+        }
+        let chats = outcomes
+            .into_iter()
+            .map(|(chat, outcome)| {
+                outcome.unwrap_or_else(|err| ChatUsage {
+                    error: Some(err.to_string()),
+                    ..tally_chat(chat.id, chat_display_name(chat, users_by_id), &[], true)
+                })
+            })
+            .collect();
+        let output = build_usage_output(space_id, chats, self.limit);
+
+        if ctx.json {
+            return ctx.print_json(&output);
+        }
+        output::print_usage(&output);
+        Ok(())
+    }
+}
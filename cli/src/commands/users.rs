@@ -0,0 +1,121 @@
+use inline_protocol::proto;
+use serde_json::Value;
+
+use super::{CommandContext, Execute};
+use crate::errors::CliError;
+use crate::output::{UserListOutput, build_user_list, user_summary};
+use crate::validation::{validate_positive_id_arg, validate_table_only_list_flags};
+use crate::{UsersCommand, filter_users_output, filter_users_payload, output};
+
+impl Execute for UsersCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            UsersCommand::List(args) => {
+                validate_table_only_list_flags(ctx.json, args.ids, args.id)?;
+                let mut payload = ctx.chats().await?.clone();
+
+                if ctx.json {
+                    filter_users_payload(&mut payload, args.filter.as_deref());
+                    ctx.print_json(&payload)?;
+                } else {
+                    let mut output = build_user_list(&payload);
+                    filter_users_output(&mut output, args.filter.as_deref());
+                    if args.ids {
+                        for user in &output.users {
+                            println!("{}", user.user.id);
+                        }
+                    } else if args.id {
+                        if output.users.len() != 1 {
+                            return Err(CliError::invalid_args(format!(
+                                "Expected exactly 1 match for --id, got {}",
+                                output.users.len()
+                            ))
+                            .into());
+                        }
+                        if let Some(user) = output.users.first() {
+                            println!("{}", user.user.id);
+                        }
+                    } else {
+                        output::print_users(&output, false, ctx.json_format)?;
+                    }
+                }
+            }
+            UsersCommand::Search(args) => {
+                let users = search_users(ctx, &args.query, args.limit).await?;
+                if ctx.json {
+                    ctx.print_json(&users)?;
+                } else if users.is_empty() {
+                    println!("No users match \"{}\".", args.query.trim());
+                } else {
+                    let output = UserListOutput {
+                        users: users.iter().map(user_summary).collect(),
+                    };
+                    output::print_users(&output, false, ctx.json_format)?;
+                }
+            }
+            UsersCommand::Get(args) => {
+                let user_id = validate_positive_id_arg("--id", args.id)?;
+                let payload = ctx.chats().await?;
+
+                if ctx.json {
+                    if let Some(user) = payload.users.iter().find(|user| user.id == user_id) {
+                        ctx.print_json(user)?;
+                    } else {
+                        return Err(CliError::not_found_user_id(user_id).into());
+                    }
+                } else {
+                    let output = build_user_list(payload);
+                    if let Some(user) = output
+                        .users
+                        .into_iter()
+                        .find(|user| user.user.id == user_id)
+                    {
+                        output::print_users(
+                            &UserListOutput { users: vec![user] },
+                            false,
+                            ctx.json_format,
+                        )?;
+                    } else {
+                        return Err(CliError::not_found_user_id(user_id).into());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Searches every user on the server, not just people you share chats with.
+pub(super) async fn search_users(
+    ctx: &CommandContext,
+    query: &str,
+    limit: u32,
+) -> Result<Vec<proto::User>, Box<dyn std::error::Error>> {
+    if query.trim().is_empty() {
+        return Err(CliError::invalid_args("--query must not be empty").into());
+    }
+    let token = ctx.token()?;
+    let result = ctx.api.search_contacts(&token, query, Some(limit)).await?;
+    Ok(result.users.iter().filter_map(user_from_api).collect())
+}
+
+/// Converts a user object from the HTTP API (camelCase JSON) to the protocol type.
+fn user_from_api(value: &Value) -> Option<proto::User> {
+    let text = |key: &str| {
+        value
+            .get(key)
+            .and_then(Value::as_str)
+            .filter(|text| !text.is_empty())
+            .map(str::to_string)
+    };
+    Some(proto::User {
+        id: value.get("id").and_then(Value::as_i64)?,
+        first_name: text("firstName"),
+        last_name: text("lastName"),
+        username: text("username"),
+        email: text("email"),
+        phone_number: text("phoneNumber"),
+        bot: value.get("bot").and_then(Value::as_bool),
+        ..Default::default()
+    })
+}
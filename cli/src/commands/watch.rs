@@ -0,0 +1,235 @@
+use std::process::Stdio;
+
+use inline_protocol::proto;
+use inline_sdk::{RealtimeError, RealtimeEvent, RealtimeEventReceiver};
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+
+use super::{CommandContext, Execute};
+use crate::chat_output::chat_display_name;
+use crate::current_epoch_seconds;
+use crate::errors::CliError;
+use crate::message_output::message_summary;
+use crate::output::{self, JsonFormat};
+use crate::peer::input_chat_peer;
+use crate::validation::validate_optional_positive_id_arg;
+use crate::watch::{
+    MessageFilter, ReactionEvent, ReactionFilter, hook_command, message_hook_vars,
+    new_messages_in_updates, reactions_in_updates,
+};
+use crate::{WatchCommand, WatchMessagesArgs, WatchReactionsArgs};
+
+impl Execute for WatchCommand {
+    async fn execute(self, ctx: &CommandContext) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            WatchCommand::Reactions(args) => watch_reactions(ctx, args).await,
+            WatchCommand::Messages(args) => watch_messages(ctx, args).await,
+        }
+    }
+}
+
+/// What to do with each matching event.
+struct Hook {
+    exec: Option<String>,
+    stdin_json: bool,
+    read_only: bool,
+}
+
+impl Hook {
+    async fn run<T: Serialize>(
+        &self,
+        vars: &[(&'static str, String)],
+        payload: &T,
+        label: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(template) = self.exec.as_deref() else {
+            return Ok(());
+        };
+        let mut command = hook_command(template, vars, self.read_only);
+        let status = if self.stdin_json {
+            let mut child = command.stdin(Stdio::piped()).spawn()?;
+            if let Some(mut stdin) = child.stdin.take() {
+                let mut json = serde_json::to_vec(payload)?;
+                json.push(b'\n');
+                // A hook that ignores stdin may exit before reading it.
+                let _ = stdin.write_all(&json).await;
+            }
+            child.wait().await?
+        } else {
+            command.status().await?
+        };
+        if !status.success() {
+            log::warn!("hook for {label} exited with {status}.");
+        }
+        Ok(())
+    }
+}
+
+fn validate_count(count: Option<usize>) -> Result<(), CliError> {
+    if count == Some(0) {
+        return Err(CliError::invalid_args("--count must be greater than 0"));
+    }
+    Ok(())
+}
+
+/// Waits for the next batch of pushed updates. Falling behind is reported and
+/// skipped rather than ending the watch.
+pub(super) async fn next_updates(
+    events: &mut RealtimeEventReceiver,
+) -> Result<Vec<proto::Update>, Box<dyn std::error::Error>> {
+    loop {
+        match events.recv().await {
+            Ok(RealtimeEvent::Updates(updates)) => return Ok(updates),
+            Ok(_) => {}
+            Err(RealtimeError::EventLagged { skipped }) => {
+                log::warn!("fell behind and skipped {skipped} event(s).");
+            }
+            Err(error) => return Err(error.into()),
+        }
+    }
+}
+
+async fn watch_reactions(
+    ctx: &CommandContext,
+    args: WatchReactionsArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let filter = ReactionFilter {
+        chat_id: validate_optional_positive_id_arg("--chat-id", args.chat_id)?,
+        emoji: args.emoji,
+    };
+    validate_count(args.count)?;
+    let hook = Hook {
+        exec: args.exec,
+        stdin_json: args.stdin_json,
+        read_only: ctx.config.read_only,
+    };
+    let realtime = ctx.realtime().await?;
+    let mut events = realtime.subscribe();
+    if !ctx.json {
+        eprintln!("Watching reactions. Press Ctrl-C to stop.");
+    }
+
+    let mut matched = 0;
+    loop {
+        let updates = next_updates(&mut events).await?;
+        for reaction in reactions_in_updates(&updates).filter(|reaction| filter.matches(reaction)) {
+            let event = ReactionEvent::from(reaction);
+            if ctx.json {
+                // One object per line so the stream can be piped into jq.
+                output::print_json(&event, JsonFormat::Compact)?;
+            } else if hook.exec.is_none() {
+                println!(
+                    "{} on message {} in chat {} by user {}",
+                    event.emoji, event.message_id, event.chat_id, event.user_id
+                );
+            }
+            let label = format!("message {}", event.message_id);
+            hook.run(&event.hook_vars(), &event, &label).await?;
+            matched += 1;
+            if args.count.is_some_and(|count| matched >= count) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+async fn watch_messages(
+    ctx: &CommandContext,
+    args: WatchMessagesArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    validate_count(args.count)?;
+    let peer = match args.chat.as_deref() {
+        Some(title) => Some(input_chat_peer(find_chat_by_title(ctx, title).await?)),
+        None if args.to.is_none() && args.chat_id.is_none() && args.user_id.is_none() => None,
+        None => Some(
+            ctx.resolve_peer(args.to.as_deref(), args.chat_id, args.user_id)
+                .await?,
+        ),
+    };
+    let filter = MessageFilter {
+        peer,
+        needles: args
+            .matches
+            .iter()
+            .map(|needle| needle.trim().to_lowercase())
+            .filter(|needle| !needle.is_empty())
+            .collect(),
+        include_own: args.include_own,
+    };
+    let hook = Hook {
+        exec: args.exec,
+        stdin_json: args.stdin_json,
+        read_only: ctx.config.read_only,
+    };
+    // Names for human output; chats or users that appear later fall back to ids.
+    let (users_by_id, chats_by_id) = if ctx.json || hook.exec.is_some() {
+        (None, None)
+    } else {
+        (
+            Some(ctx.users_by_id().await?),
+            Some(ctx.chats_by_id().await?),
+        )
+    };
+    let current_user_id = ctx.local_db.load()?.current_user.map(|user| user.id);
+    let realtime = ctx.realtime().await?;
+    let mut events = realtime.subscribe();
+    if !ctx.json {
+        eprintln!("Watching messages. Press Ctrl-C to stop.");
+    }
+
+    let mut matched = 0;
+    loop {
+        let updates = next_updates(&mut events).await?;
+        for message in new_messages_in_updates(&updates).filter(|message| filter.matches(message)) {
+            if ctx.json {
+                output::print_json(message, JsonFormat::Compact)?;
+            } else if let (Some(users_by_id), Some(chats_by_id)) = (users_by_id, chats_by_id) {
+                let summary = message_summary(
+                    message,
+                    users_by_id,
+                    current_user_id,
+                    current_epoch_seconds() as i64,
+                    None,
+                );
+                let chat_name = chats_by_id
+                    .get(&message.chat_id)
+                    .map(|chat| chat_display_name(chat, users_by_id))
+                    .unwrap_or_else(|| format!("chat {}", message.chat_id));
+                println!("[{chat_name}] {}: {}", summary.sender_name, summary.preview);
+            }
+            let label = format!("message {}", message.id);
+            hook.run(&message_hook_vars(message), message, &label)
+                .await?;
+            matched += 1;
+            if args.count.is_some_and(|count| matched >= count) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+async fn find_chat_by_title(
+    ctx: &CommandContext,
+    title: &str,
+) -> Result<i64, Box<dyn std::error::Error>> {
+    let wanted = title.trim().to_lowercase();
+    let chats = ctx.chats().await?;
+    let matches = chats
+        .chats
+        .iter()
+        .filter(|chat| chat.title.trim().to_lowercase() == wanted)
+        .map(|chat| chat.id)
+        .collect::<Vec<_>>();
+    match matches.as_slice() {
+        [chat_id] => Ok(*chat_id),
+        [] => Err(CliError::not_found_chat_title(title).into()),
+        ids => Err(CliError::invalid_args(format!(
+            "Several chats are titled \"{title}\" ({}); use --chat-id instead",
+            ids.iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+        .into()),
+    }
+}
@@ -0,0 +1,196 @@
+//! gzip/zstd for `messages export --compress` and backup archives.
+//!
+//! Writers stream through the encoder instead of compressing in memory first.
+//! Readers go by the file's magic bytes rather than its name, so a renamed
+//! `.json.gz` still reads back.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// The level `inline backup` has always used.
+const ZSTD_LEVEL: i32 = 9;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub(crate) enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            Self::Gzip => "gz",
+            Self::Zstd => "zst",
+        }
+    }
+
+    /// The compression a file name asks for: `.gz`, `.zst`, or `.zstd`.
+    pub(crate) fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "gz" | "gzip" => Some(Self::Gzip),
+            "zst" | "zstd" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    fn sniff(header: &[u8]) -> Option<Self> {
+        if header.starts_with(&GZIP_MAGIC) {
+            Some(Self::Gzip)
+        } else if header.starts_with(&ZSTD_MAGIC) {
+            Some(Self::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+/// `path` with the compression suffix added, unless it already ends in one.
+pub(crate) fn compressed_path(path: &Path, compression: Compression) -> PathBuf {
+    if Compression::from_path(path) == Some(compression) {
+        return path.to_path_buf();
+    }
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(compression.extension());
+    PathBuf::from(name)
+}
+
+/// `path` without a trailing `.gz`/`.zst`, for reading the inner extension.
+pub(crate) fn strip_compression_extension(path: &Path) -> PathBuf {
+    match Compression::from_path(path) {
+        Some(_) => path.with_extension(""),
+        None => path.to_path_buf(),
+    }
+}
+
+/// Wraps `writer` in an encoder; call `finish` on the result to flush the
+/// trailer.
+pub(crate) fn encoder<W: Write>(writer: W, compression: Compression) -> io::Result<Encoder<W>> {
+    Ok(match compression {
+        Compression::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+            writer,
+            flate2::Compression::default(),
+        )),
+        Compression::Zstd => Encoder::Zstd(zstd::stream::write::Encoder::new(writer, ZSTD_LEVEL)?),
+    })
+}
+
+pub(crate) enum Encoder<W: Write> {
+    Gzip(flate2::write::GzEncoder<W>),
+    Zstd(zstd::stream::write::Encoder<'static, W>),
+}
+
+impl<W: Write> Encoder<W> {
+    pub(crate) fn finish(self) -> io::Result<W> {
+        match self {
+            Self::Gzip(encoder) => encoder.finish(),
+            Self::Zstd(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Gzip(encoder) => encoder.write(buf),
+            Self::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Gzip(encoder) => encoder.flush(),
+            Self::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Writes `contents` to `path` compressed. Returns the size on disk.
+pub(crate) fn write_compressed(
+    path: &Path,
+    contents: &[u8],
+    compression: Compression,
+) -> io::Result<u64> {
+    let mut encoder = encoder(BufWriter::new(File::create(path)?), compression)?;
+    encoder.write_all(contents)?;
+    encoder.finish()?.flush()?;
+    Ok(path.metadata()?.len())
+}
+
+/// Opens `path` for reading, decompressing gzip or zstd when the file starts
+/// with their magic bytes. Anything else is read as is.
+pub(crate) fn open_decompressed(path: &Path) -> io::Result<Box<dyn Read>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let header = io::BufRead::fill_buf(&mut reader)?;
+    Ok(match Compression::sniff(header) {
+        Some(Compression::Gzip) => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+        Some(Compression::Zstd) => Box::new(zstd::stream::read::Decoder::with_buffer(reader)?),
+        None => Box::new(reader),
+    })
+}
+
+pub(crate) fn read_to_string_decompressed(path: &Path) -> io::Result<String> {
+    let mut contents = String::new();
+    open_decompressed(path)?.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compressed_files_read_back_by_content() {
+        let dir = std::env::temp_dir().join(format!("inline-compression-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let text = r#"{"messages": []}"#.repeat(50);
+        for compression in [Compression::Gzip, Compression::Zstd] {
+            let path = compressed_path(&dir.join("export.json"), compression);
+            let size = write_compressed(&path, text.as_bytes(), compression).unwrap();
+            assert!(size < text.len() as u64);
+            // Renamed without its suffix, it still decompresses.
+            let renamed = dir.join(format!("renamed-{}", compression.as_str()));
+            std::fs::rename(&path, &renamed).unwrap();
+            assert_eq!(read_to_string_decompressed(&renamed).unwrap(), text);
+        }
+        let plain = dir.join("plain.json");
+        std::fs::write(&plain, &text).unwrap();
+        let result = read_to_string_decompressed(&plain);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(result.unwrap(), text);
+    }
+
+    #[test]
+    fn suffixes_are_added_once_and_stripped_for_format_inference() {
+        assert_eq!(
+            compressed_path(Path::new("out/chat.json"), Compression::Gzip),
+            PathBuf::from("out/chat.json.gz")
+        );
+        assert_eq!(
+            compressed_path(Path::new("chat.json.zst"), Compression::Zstd),
+            PathBuf::from("chat.json.zst")
+        );
+        assert_eq!(
+            strip_compression_extension(Path::new("out/chat.jsonl.gz")),
+            PathBuf::from("out/chat.jsonl")
+        );
+        assert_eq!(
+            strip_compression_extension(Path::new("chat.md")),
+            PathBuf::from("chat.md")
+        );
+    }
+}
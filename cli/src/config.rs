@@ -1,6 +1,122 @@
+use std::collections::BTreeMap;
 use std::env;
 use std::path::PathBuf;
 
+use serde::Serialize;
+
+use crate::config_file::{ConfigFile, ConfigFileError, ConfigValue};
+use crate::output::{ColorMode, JsonFormat};
+use crate::update::UpdateCheck;
+
+/// Settings that can be read from the config file, in `inline config list` order.
+///
+/// Precedence for each setting is flags > env > config file (profile section, then
+/// top level) > defaults.
+pub const CONFIG_SETTINGS: [ConfigSettingSpec; 13] = [
+    ConfigSettingSpec {
+        key: "api_base_url",
+        env: Some("INLINE_API_BASE_URL"),
+        allowed: &[],
+    },
+    ConfigSettingSpec {
+        key: "realtime_url",
+        env: Some("INLINE_REALTIME_URL"),
+        allowed: &[],
+    },
+    ConfigSettingSpec {
+        key: "output",
+        env: Some("INLINE_OUTPUT"),
+        allowed: &["table", "json"],
+    },
+    ConfigSettingSpec {
+        key: "json_format",
+        env: None,
+        allowed: &["pretty", "compact"],
+    },
+    ConfigSettingSpec {
+        key: "color",
+        env: None,
+        allowed: &["auto", "always", "never"],
+    },
+    ConfigSettingSpec {
+        key: "profile",
+        env: Some("INLINE_PROFILE"),
+        allowed: &[],
+    },
+    ConfigSettingSpec {
+        key: "read_only",
+        env: Some("INLINE_READ_ONLY"),
+        allowed: &["true", "false", "1", "0"],
+    },
+    ConfigSettingSpec {
+        key: "proxy",
+        env: Some("INLINE_PROXY"),
+        allowed: &[],
+    },
+    ConfigSettingSpec {
+        key: "ca_bundle",
+        env: Some("INLINE_CA_BUNDLE"),
+        allowed: &[],
+    },
+    ConfigSettingSpec {
+        key: "tls_pins",
+        env: Some("INLINE_TLS_PINS"),
+        allowed: &[],
+    },
+    ConfigSettingSpec {
+        key: "send_limit",
+        env: Some("INLINE_SEND_LIMIT"),
+        allowed: &[],
+    },
+    ConfigSettingSpec {
+        key: "language",
+        env: Some("INLINE_LANGUAGE"),
+        allowed: &[],
+    },
+    ConfigSettingSpec {
+        key: "update.check",
+        env: Some("INLINE_UPDATE_CHECK"),
+        allowed: &["never", "daily", "weekly"],
+    },
+];
+
+pub struct ConfigSettingSpec {
+    pub key: &'static str,
+    pub env: Option<&'static str>,
+    pub allowed: &'static [&'static str],
+}
+
+pub fn config_setting_spec(key: &str) -> Option<&'static ConfigSettingSpec> {
+    CONFIG_SETTINGS.iter().find(|spec| spec.key == key)
+}
+
+pub fn validate_setting_value(
+    spec: &ConfigSettingSpec,
+    value: &str,
+) -> Result<(), ConfigFileError> {
+    if spec.allowed.is_empty() || spec.allowed.contains(&value) {
+        return Ok(());
+    }
+    Err(ConfigFileError::InvalidValue {
+        key: spec.key.to_string(),
+        message: format!("expected one of {}, got `{value}`", spec.allowed.join(", ")),
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSetting {
+    pub key: String,
+    pub value: Option<String>,
+    pub source: String,
+}
+
 #[derive(Clone, Debug)]
 pub struct Config {
     pub api_base_url: String,
@@ -8,31 +124,120 @@ pub struct Config {
     pub data_dir: PathBuf,
     pub secrets_path: PathBuf,
     pub state_path: PathBuf,
+    pub config_path: PathBuf,
+    pub profile: Option<String>,
+    pub output_format: OutputFormat,
+    pub json_format: Option<JsonFormat>,
+    pub color: ColorMode,
+    /// Refuse commands that change anything on the server (`--read-only`).
+    pub read_only: bool,
+    /// Proxy URL, or `none` to ignore `HTTPS_PROXY`/`ALL_PROXY`.
+    pub proxy: Option<String>,
+    /// PEM file of extra root CAs for a self-hosted server.
+    pub ca_bundle: Option<PathBuf>,
+    /// SHA-256 certificate fingerprints the server must present (comma-separated).
+    pub tls_pins: Vec<String>,
+    /// Messages one invocation may send before it asks for confirmation or
+    /// `--force`; 0 turns the check off.
+    pub send_limit: usize,
+    /// Language code messages are read in, for `--translate auto`.
+    pub language: Option<String>,
+    /// How often to look for a newer release in the background.
+    pub update_check: UpdateCheck,
+    pub aliases: BTreeMap<String, String>,
+    /// `[alias]` command aliases, e.g. `standup = "messages send --to standup"`.
+    pub command_aliases: BTreeMap<String, String>,
+    /// `[defaults]` flag values, e.g. `messages.list.limit`.
+    pub defaults: BTreeMap<String, ConfigValue>,
+    pub settings: Vec<ConfigSetting>,
+    pub file: ConfigFile,
     pub release_manifest_url: Option<String>,
     pub release_install_url: Option<String>,
+    /// Minisign public key that release manifests and archives must be signed
+    /// with; built in at compile time, or `INLINE_RELEASE_PUBLIC_KEY`.
+    pub release_public_key: Option<String>,
 }
 
 impl Config {
-    pub fn load() -> Self {
+    pub fn load(profile_flag: Option<&str>) -> Result<Self, ConfigFileError> {
         let debug = cfg!(debug_assertions);
-        let api_base_url = env::var("INLINE_API_BASE_URL").unwrap_or_else(|_| {
-            if debug {
-                "http://localhost:8000/v1".to_string()
-            } else {
-                "https://api.inline.chat/v1".to_string()
-            }
-        });
+        let config_path = env::var("INLINE_CONFIG_PATH")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| default_config_path());
+        let file = ConfigFile::load(&config_path)?;
+        let mut resolver = SettingResolver::new(&file, profile_flag)?;
+
+        let default_api_base_url = if debug {
+            "http://localhost:8000/v1"
+        } else {
+            "https://api.inline.chat/v1"
+        };
+        let api_base_url = resolver.resolve_or("api_base_url", default_api_base_url)?;
         let api_base_url = api_base_url.trim_end_matches('/').to_string();
 
-        let realtime_url = env::var("INLINE_REALTIME_URL").unwrap_or_else(|_| {
-            if debug {
-                "ws://localhost:8000/realtime".to_string()
-            } else {
-                "wss://api.inline.chat/realtime".to_string()
-            }
-        });
+        let default_realtime_url = if debug {
+            "ws://localhost:8000/realtime"
+        } else {
+            "wss://api.inline.chat/realtime"
+        };
+        let realtime_url = resolver.resolve_or("realtime_url", default_realtime_url)?;
         let realtime_url = realtime_url.trim_end_matches('/').to_string();
 
+        let output_format = match resolver.resolve_or("output", "table")?.as_str() {
+            "json" => OutputFormat::Json,
+            _ => OutputFormat::Table,
+        };
+        // Unset means "whatever the flags say" (pretty unless --compact).
+        let json_format = match resolver.resolve("json_format")?.as_deref() {
+            Some("pretty") => Some(JsonFormat::Pretty),
+            Some("compact") => Some(JsonFormat::Compact),
+            _ => None,
+        };
+        let color = match resolver.resolve_or("color", "auto")?.as_str() {
+            "always" => ColorMode::Always,
+            "never" => ColorMode::Never,
+            _ => ColorMode::Auto,
+        };
+        let read_only = matches!(
+            resolver.resolve_or("read_only", "false")?.as_str(),
+            "true" | "1"
+        );
+        let proxy = resolver.resolve("proxy")?;
+        let ca_bundle = resolver
+            .resolve("ca_bundle")?
+            .filter(|path| !path.trim().is_empty())
+            .map(PathBuf::from);
+        let tls_pins = resolver
+            .resolve("tls_pins")?
+            .map(|pins| {
+                pins.split(',')
+                    .map(str::trim)
+                    .filter(|pin| !pin.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let send_limit = resolver
+            .resolve_or("send_limit", DEFAULT_SEND_LIMIT)?
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| ConfigFileError::InvalidValue {
+                key: "send_limit".to_string(),
+                message: "expected a number of messages (0 turns the check off)".to_string(),
+            })?;
+        let language = resolver
+            .resolve("language")?
+            .map(|language| language.trim().to_string())
+            .filter(|language| !language.is_empty());
+        let update_check = match resolver.resolve_or("update.check", "daily")?.as_str() {
+            "never" => UpdateCheck::Never,
+            "weekly" => UpdateCheck::Weekly,
+            _ => UpdateCheck::Daily,
+        };
+        let aliases = resolver.aliases();
+        let command_aliases = resolver.command_aliases();
+        let defaults = resolver.defaults();
+
         let data_dir = env::var("INLINE_DATA_DIR")
             .map(PathBuf::from)
             .unwrap_or_else(|_| default_data_dir(debug));
@@ -63,20 +268,168 @@ impl Config {
                 .as_ref()
                 .map(|base| format!("{base}/install.sh"))
         });
+        let release_public_key = env::var("INLINE_RELEASE_PUBLIC_KEY")
+            .ok()
+            .or_else(|| EMBEDDED_RELEASE_PUBLIC_KEY.map(str::to_string))
+            .filter(|key| !key.trim().is_empty());
 
-        Self {
+        let profile = resolver.profile.clone();
+        let settings = resolver.settings;
+        Ok(Self {
             api_base_url,
             realtime_url,
             data_dir,
             secrets_path,
             state_path,
+            config_path,
+            profile,
+            output_format,
+            json_format,
+            color,
+            read_only,
+            proxy,
+            ca_bundle,
+            tls_pins,
+            send_limit,
+            language,
+            update_check,
+            aliases,
+            command_aliases,
+            defaults,
+            settings,
+            file,
             release_manifest_url,
             release_install_url,
+            release_public_key,
+        })
+    }
+
+    pub fn setting(&self, key: &str) -> Option<&ConfigSetting> {
+        self.settings.iter().find(|setting| setting.key == key)
+    }
+}
+
+/// Resolves settings across env, the active profile section, and the top level of
+/// the config file, recording where each value came from.
+struct SettingResolver<'a> {
+    file: &'a ConfigFile,
+    profile: Option<String>,
+    settings: Vec<ConfigSetting>,
+}
+
+impl<'a> SettingResolver<'a> {
+    fn new(file: &'a ConfigFile, profile_flag: Option<&str>) -> Result<Self, ConfigFileError> {
+        let (profile, source) = if let Some(profile) = profile_flag {
+            (Some(profile.to_string()), "flag")
+        } else if let Ok(profile) = env::var("INLINE_PROFILE") {
+            (Some(profile), "env:INLINE_PROFILE")
+        } else if let Some(profile) = file.get_str("profile") {
+            (Some(profile.to_string()), "file")
+        } else {
+            (None, "default")
+        };
+        let profile = profile.filter(|profile| !profile.trim().is_empty());
+        if let Some(profile) = profile.as_deref()
+            && !file
+                .table_names("profiles")
+                .iter()
+                .any(|name| name == profile)
+        {
+            return Err(ConfigFileError::UnknownProfile(profile.to_string()));
         }
+
+        Ok(Self {
+            file,
+            settings: vec![ConfigSetting {
+                key: "profile".to_string(),
+                value: profile.clone(),
+                source: source.to_string(),
+            }],
+            profile,
+        })
+    }
+
+    fn resolve(&mut self, key: &str) -> Result<Option<String>, ConfigFileError> {
+        let spec = config_setting_spec(key);
+        let from_env = spec.and_then(|spec| spec.env).and_then(|name| {
+            env::var(name)
+                .ok()
+                .map(|value| (value, format!("env:{name}")))
+        });
+        let from_profile = self.profile.as_deref().and_then(|profile| {
+            self.file
+                .get(&format!("profiles.{profile}.{key}"))
+                .map(|value| (value.to_string(), format!("profile:{profile}")))
+        });
+        let from_file = || {
+            self.file
+                .get(key)
+                .map(|value| (value.to_string(), "file".to_string()))
+        };
+        let resolved = from_env.or(from_profile).or_else(from_file);
+        let (value, source) = match resolved {
+            Some((value, source)) => (Some(value), source),
+            None => (None, "default".to_string()),
+        };
+        if let (Some(spec), Some(value)) = (spec, value.as_deref()) {
+            validate_setting_value(spec, value)?;
+        }
+        self.settings.push(ConfigSetting {
+            key: key.to_string(),
+            value: value.clone(),
+            source,
+        });
+        Ok(value)
+    }
+
+    fn resolve_or(&mut self, key: &str, default: &str) -> Result<String, ConfigFileError> {
+        let value = self.resolve(key)?;
+        if value.is_none()
+            && let Some(setting) = self.settings.last_mut()
+        {
+            setting.value = Some(default.to_string());
+        }
+        Ok(value.unwrap_or_else(|| default.to_string()))
+    }
+
+    /// Top-level `[aliases]` merged with the active profile's aliases.
+    fn aliases(&self) -> BTreeMap<String, String> {
+        let mut aliases = self.file.string_table("aliases");
+        if let Some(profile) = self.profile.as_deref() {
+            aliases.extend(
+                self.file
+                    .string_table(&format!("profiles.{profile}.aliases")),
+            );
+        }
+        aliases
+    }
+
+    /// Top-level `[alias]` merged with the active profile's `[profiles.NAME.alias]`.
+    fn command_aliases(&self) -> BTreeMap<String, String> {
+        let mut aliases = self.file.string_table("alias");
+        if let Some(profile) = self.profile.as_deref() {
+            aliases.extend(self.file.string_table(&format!("profiles.{profile}.alias")));
+        }
+        aliases
+    }
+
+    /// Profile defaults override top-level ones key by key.
+    fn defaults(&self) -> BTreeMap<String, ConfigValue> {
+        let mut defaults = self.file.values_under("defaults");
+        if let Some(profile) = self.profile.as_deref() {
+            defaults.extend(
+                self.file
+                    .values_under(&format!("profiles.{profile}.defaults")),
+            );
+        }
+        defaults
     }
 }
 
 const DEFAULT_RELEASE_BASE_URL: &str = "https://public-assets.inline.chat/cli";
+const DEFAULT_SEND_LIMIT: &str = "50";
+/// Set by the release build; dev builds have none and need `--allow-unsigned`.
+const EMBEDDED_RELEASE_PUBLIC_KEY: Option<&str> = option_env!("INLINE_RELEASE_PUBLIC_KEY");
 
 fn default_data_dir(debug: bool) -> PathBuf {
     let base = env::var("HOME")
@@ -85,3 +438,75 @@ fn default_data_dir(debug: bool) -> PathBuf {
     let dir_name = if debug { "inline-dev" } else { "inline" };
     base.join(".local").join("share").join(dir_name)
 }
+
+fn default_config_path() -> PathBuf {
+    let base = env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| env::var("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_else(|_| PathBuf::from("."));
+    base.join("inline").join("config.toml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_section_overrides_top_level_values() {
+        let file = ConfigFile::parse(
+            r#"
+color = "never"
+json_format = "pretty"
+
+[aliases]
+standup = "chat:123"
+
+[profiles.work]
+json_format = "compact"
+
+[profiles.work.aliases]
+sam = "user:42"
+"#,
+        )
+        .unwrap();
+
+        let mut resolver = SettingResolver::new(&file, Some("work")).unwrap();
+        assert_eq!(
+            resolver.resolve("json_format").unwrap().as_deref(),
+            Some("compact")
+        );
+        assert_eq!(resolver.resolve("color").unwrap().as_deref(), Some("never"));
+        let aliases = resolver.aliases();
+        assert_eq!(aliases.get("standup").map(String::as_str), Some("chat:123"));
+        assert_eq!(aliases.get("sam").map(String::as_str), Some("user:42"));
+
+        let sources = resolver
+            .settings
+            .iter()
+            .map(|setting| (setting.key.as_str(), setting.source.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sources,
+            vec![
+                ("profile", "flag"),
+                ("json_format", "profile:work"),
+                ("color", "file"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_profiles_and_invalid_values() {
+        let file = ConfigFile::parse("color = \"purple\"\n").unwrap();
+        assert!(matches!(
+            SettingResolver::new(&file, Some("missing")),
+            Err(ConfigFileError::UnknownProfile(_))
+        ));
+
+        let mut resolver = SettingResolver::new(&file, None).unwrap();
+        assert!(matches!(
+            resolver.resolve("color"),
+            Err(ConfigFileError::InvalidValue { .. })
+        ));
+    }
+}
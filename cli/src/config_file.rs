@@ -0,0 +1,550 @@
+//! Reader and writer for the CLI config file (`~/.config/inline/config.toml`).
+//!
+//! Supports the TOML subset the CLI needs: `[section.sub]` tables, `# comments`,
+//! and `key = value` pairs where values are basic strings, integers, or booleans.
+//! Keys are flattened to dotted paths (`tasks.webhooks.todoist.url`).
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ConfigFileError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("config parse error on line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[error("invalid config value for {key}: {message}")]
+    InvalidValue { key: String, message: String },
+    #[error("unknown config profile `{0}` (add a [profiles.{0}] section to the config file)")]
+    UnknownProfile(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ConfigValue {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+}
+
+impl ConfigValue {
+    /// Interprets a value typed on the command line (`inline config set`).
+    pub fn from_input(value: &str) -> Self {
+        match value {
+            "true" => Self::Boolean(true),
+            "false" => Self::Boolean(false),
+            _ => value
+                .parse()
+                .map(Self::Integer)
+                .unwrap_or_else(|_| Self::String(value.to_string())),
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn to_toml(&self) -> String {
+        match self {
+            Self::String(value) => {
+                let escaped = value
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n")
+                    .replace('\t', "\\t");
+                format!("\"{escaped}\"")
+            }
+            Self::Integer(value) => value.to_string(),
+            Self::Boolean(value) => value.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::String(value) => write!(f, "{value}"),
+            Self::Integer(value) => write!(f, "{value}"),
+            Self::Boolean(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ConfigFile {
+    values: BTreeMap<String, ConfigValue>,
+    /// Text the values were parsed from; `render` edits it in place.
+    source: String,
+}
+
+/// Two files are equal when they hold the same values, whatever their layout.
+impl PartialEq for ConfigFile {
+    fn eq(&self, other: &Self) -> bool {
+        self.values == other.values
+    }
+}
+
+impl Eq for ConfigFile {}
+
+impl ConfigFile {
+    /// Loads the config file, treating a missing file as empty.
+    pub fn load(path: &Path) -> Result<Self, ConfigFileError> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(ConfigFileError::Io(err)),
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, ConfigFileError> {
+        let mut values = BTreeMap::new();
+        let mut section = String::new();
+        for (index, raw_line) in contents.lines().enumerate() {
+            let line_number = index + 1;
+            let line = strip_comment(raw_line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header
+                    .strip_suffix(']')
+                    .ok_or_else(|| parse_error(line_number, "unterminated table header"))?;
+                section = parse_key_path(header)
+                    .ok_or_else(|| parse_error(line_number, "invalid table name"))?;
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| parse_error(line_number, "expected key = value"))?;
+            let key = parse_key_path(key).ok_or_else(|| parse_error(line_number, "invalid key"))?;
+            let value = parse_value(value.trim())
+                .ok_or_else(|| parse_error(line_number, "unsupported value"))?;
+            let full_key = if section.is_empty() {
+                key
+            } else {
+                format!("{section}.{key}")
+            };
+            values.insert(full_key, value);
+        }
+        Ok(Self {
+            values,
+            source: contents.to_string(),
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&ConfigValue> {
+        self.values.get(key)
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(ConfigValue::as_str)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &ConfigValue)> {
+        self.values.iter()
+    }
+
+    pub fn set(&mut self, key: &str, value: ConfigValue) -> Result<(), ConfigFileError> {
+        let key = parse_key_path(key).ok_or_else(|| ConfigFileError::InvalidValue {
+            key: key.to_string(),
+            message: "keys use letters, digits, '_' or '-', separated by '.'".to_string(),
+        })?;
+        self.values.insert(key, value);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<ConfigValue> {
+        self.values.remove(key)
+    }
+
+    /// String values stored directly under `prefix` (e.g. `[aliases]`).
+    pub fn string_table(&self, prefix: &str) -> BTreeMap<String, String> {
+        let prefix = format!("{prefix}.");
+        self.values
+            .iter()
+            .filter_map(|(key, value)| {
+                let name = key.strip_prefix(&prefix)?;
+                (!name.contains('.'))
+                    .then(|| Some((name.to_string(), value.as_str()?.to_string())))?
+            })
+            .collect()
+    }
+
+    /// Every value nested under `prefix`, keyed by the rest of its dotted path.
+    pub fn values_under(&self, prefix: &str) -> BTreeMap<String, ConfigValue> {
+        let prefix = format!("{prefix}.");
+        self.values
+            .iter()
+            .filter_map(|(key, value)| {
+                Some((key.strip_prefix(&prefix)?.to_string(), value.clone()))
+            })
+            .collect()
+    }
+
+    /// Renders the file as TOML by editing the text it was parsed from, so
+    /// comments, blank lines, and key order survive. Changed keys are rewritten
+    /// in place, removed keys are dropped, and new keys are added after the
+    /// last key of their table, or in a new table at the end.
+    pub fn render(&self) -> String {
+        let mut lines = Vec::new();
+        // Index after the last line of each table, where new keys go. The
+        // top-level table has no header, so new keys there default to the top.
+        let mut table_ends = vec![(String::new(), 0)];
+        let mut present = BTreeSet::new();
+        let mut section = String::new();
+        for raw_line in self.source.lines() {
+            let line = strip_comment(raw_line).trim();
+            if let Some(header) = line.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+                section = parse_key_path(header).unwrap_or_default();
+                lines.push(raw_line.to_string());
+                table_ends.push((section.clone(), lines.len()));
+                continue;
+            }
+            let Some((key, old_value)) = line.split_once('=') else {
+                lines.push(raw_line.to_string());
+                continue;
+            };
+            let key = parse_key_path(key).unwrap_or_default();
+            let full_key = if section.is_empty() {
+                key
+            } else {
+                format!("{section}.{key}")
+            };
+            let Some(value) = self.values.get(&full_key) else {
+                continue;
+            };
+            if parse_value(old_value.trim()).as_ref() == Some(value) {
+                lines.push(raw_line.to_string());
+            } else {
+                let (key_text, _) = raw_line.split_once('=').unwrap_or_default();
+                let comment = &raw_line[strip_comment(raw_line).len()..];
+                let mut rewritten = format!("{} = {}", key_text.trim_end(), value.to_toml());
+                if !comment.is_empty() {
+                    rewritten.push(' ');
+                    rewritten.push_str(comment);
+                }
+                lines.push(rewritten);
+            }
+            present.insert(full_key);
+            if let Some(end) = table_ends
+                .iter_mut()
+                .rev()
+                .find(|(name, _)| *name == section)
+            {
+                end.1 = lines.len();
+            }
+        }
+        if table_ends.len() == 1 && table_ends[0].1 == 0 {
+            // No tables and no keys: new keys follow the existing comments.
+            table_ends[0].1 = lines.len();
+        }
+
+        let mut insertions = Vec::new();
+        let mut new_tables: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for (key, value) in &self.values {
+            if present.contains(key) {
+                continue;
+            }
+            // The most specific table the key belongs to; dotted keys never
+            // go top-level, matching how a fresh file would lay them out.
+            let table = table_ends
+                .iter()
+                .filter(|(name, _)| match key.strip_prefix(name.as_str()) {
+                    Some(rest) if !name.is_empty() => rest.starts_with('.'),
+                    _ => name.is_empty() && !key.contains('.'),
+                })
+                .max_by_key(|(name, _)| name.len());
+            match table {
+                Some((name, end)) => {
+                    let name = key.strip_prefix(&format!("{name}.")).unwrap_or(key);
+                    insertions.push((*end, format!("{name} = {}", value.to_toml())));
+                }
+                None => {
+                    let (table, name) = key.rsplit_once('.').unwrap_or(("", key));
+                    new_tables
+                        .entry(table)
+                        .or_default()
+                        .push(format!("{name} = {}", value.to_toml()));
+                }
+            }
+        }
+        // Inserting from the back keeps earlier indices valid and leaves keys
+        // bound for the same spot in order.
+        insertions.sort_by_key(|(end, _)| *end);
+        for (end, line) in insertions.into_iter().rev() {
+            lines.insert(end, line);
+        }
+        for (table, entries) in new_tables {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(format!("[{table}]"));
+            lines.extend(entries);
+        }
+
+        let mut output = lines.join("\n");
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        output
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ConfigFileError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.render())?;
+        set_file_permissions(path, 0o600)?;
+        Ok(())
+    }
+
+    /// Names of the direct child tables under `prefix` (e.g. webhook names under
+    /// `tasks.webhooks`).
+    pub fn table_names(&self, prefix: &str) -> Vec<String> {
+        let prefix = format!("{prefix}.");
+        let mut names = self
+            .values
+            .keys()
+            .filter_map(|key| key.strip_prefix(&prefix))
+            .filter_map(|rest| rest.split_once('.').map(|(name, _)| name.to_string()))
+            .collect::<Vec<_>>();
+        names.dedup();
+        names
+    }
+}
+
+fn parse_error(line: usize, message: &str) -> ConfigFileError {
+    ConfigFileError::Parse {
+        line,
+        message: message.to_string(),
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, ch) in line.char_indices() {
+        match ch {
+            '\\' if in_string => escaped = !escaped,
+            '"' if !escaped => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => escaped = false,
+        }
+    }
+    line
+}
+
+fn parse_key_path(value: &str) -> Option<String> {
+    let parts = value
+        .split('.')
+        .map(|part| {
+            let part = part.trim();
+            let part = part
+                .strip_prefix('"')
+                .and_then(|part| part.strip_suffix('"'))
+                .unwrap_or(part);
+            let valid = !part.is_empty()
+                && part
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-');
+            valid.then(|| part.to_string())
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(parts.join("."))
+}
+
+fn parse_value(value: &str) -> Option<ConfigValue> {
+    if let Some(inner) = value.strip_prefix('"') {
+        return parse_basic_string(inner).map(ConfigValue::String);
+    }
+    match value {
+        "true" => return Some(ConfigValue::Boolean(true)),
+        "false" => return Some(ConfigValue::Boolean(false)),
+        _ => {}
+    }
+    value
+        .replace('_', "")
+        .parse()
+        .ok()
+        .map(ConfigValue::Integer)
+}
+
+fn parse_basic_string(inner: &str) -> Option<String> {
+    let mut output = String::new();
+    let mut chars = inner.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' => return chars.as_str().trim().is_empty().then_some(output),
+            '\\' => match chars.next()? {
+                'n' => output.push('\n'),
+                't' => output.push('\t'),
+                '"' => output.push('"'),
+                '\\' => output.push('\\'),
+                _ => return None,
+            },
+            ch => output.push(ch),
+        }
+    }
+    None
+}
+
+#[cfg(unix)]
+fn set_file_permissions(path: &Path, mode: u32) -> Result<(), io::Error> {
+    use std::os::unix::fs::PermissionsExt;
+    let perm = fs::Permissions::from_mode(mode);
+    fs::set_permissions(path, perm)
+}
+
+#[cfg(not(unix))]
+fn set_file_permissions(_path: &Path, _mode: u32) -> Result<(), io::Error> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tables_comments_and_scalar_values() {
+        let file = ConfigFile::parse(
+            r#"
+# top-level comment
+api_base_url = "https://inline.example.com/v1" # trailing comment
+
+[tasks.webhooks.todoist]
+url = "https://hooks.example.com/inline#frag"
+label = "Todoist \"task\""
+timeout_secs = 10
+enabled = true
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            file.get_str("api_base_url"),
+            Some("https://inline.example.com/v1")
+        );
+        assert_eq!(
+            file.get_str("tasks.webhooks.todoist.url"),
+            Some("https://hooks.example.com/inline#frag")
+        );
+        assert_eq!(
+            file.get_str("tasks.webhooks.todoist.label"),
+            Some("Todoist \"task\"")
+        );
+        assert_eq!(
+            file.get("tasks.webhooks.todoist.timeout_secs"),
+            Some(&ConfigValue::Integer(10))
+        );
+        assert_eq!(
+            file.get("tasks.webhooks.todoist.enabled"),
+            Some(&ConfigValue::Boolean(true))
+        );
+        assert_eq!(file.table_names("tasks.webhooks"), vec!["todoist"]);
+    }
+
+    #[test]
+    fn render_round_trips_through_parse() {
+        let mut file =
+            ConfigFile::parse("output = \"json\"\n[aliases]\nstandup = \"chat:123\"\n").unwrap();
+        file.set(
+            "profiles.work.api_base_url",
+            ConfigValue::from_input("https://a.example/v1"),
+        )
+        .unwrap();
+        file.set("profiles.work.retries", ConfigValue::from_input("3"))
+            .unwrap();
+        file.set(
+            "aliases.quote",
+            ConfigValue::String("say \"hi\"".to_string()),
+        )
+        .unwrap();
+        assert!(file.set("bad key", ConfigValue::from_input("x")).is_err());
+
+        let rendered = file.render();
+        assert!(
+            rendered.starts_with("output = \"json\"\n[aliases]\nstandup = \"chat:123\"\nquote = ")
+        );
+        assert_eq!(ConfigFile::parse(&rendered).unwrap(), file);
+        assert_eq!(file.string_table("aliases").len(), 2);
+        assert_eq!(file.string_table("profiles").len(), 0);
+
+        assert!(file.remove("aliases.quote").is_some());
+        assert!(file.remove("aliases.quote").is_none());
+    }
+
+    #[test]
+    fn render_keeps_comments_and_order_and_only_touches_changed_keys() {
+        let source = r#"# Inline CLI config
+output = "json" # scripts want JSON
+api_base_url = "https://inline.example/v1"
+
+# Shortcuts
+[aliases]
+standup = "chat:1"
+ops = "chat:2"
+
+[profiles.work]
+color = "never"
+"#;
+        let mut file = ConfigFile::parse(source).unwrap();
+        assert_eq!(file.render(), source);
+
+        file.set("output", ConfigValue::from_input("table"))
+            .unwrap();
+        file.remove("aliases.standup");
+        file.set("aliases.deploys", ConfigValue::from_input("chat:3"))
+            .unwrap();
+        file.set("send_limit", ConfigValue::from_input("20"))
+            .unwrap();
+        file.set("profiles.work.read_only", ConfigValue::from_input("true"))
+            .unwrap();
+        file.set("update.check", ConfigValue::from_input("weekly"))
+            .unwrap();
+
+        let rendered = file.render();
+        assert_eq!(
+            rendered,
+            r#"# Inline CLI config
+output = "table" # scripts want JSON
+api_base_url = "https://inline.example/v1"
+send_limit = 20
+
+# Shortcuts
+[aliases]
+ops = "chat:2"
+deploys = "chat:3"
+
+[profiles.work]
+color = "never"
+read_only = true
+
+[update]
+check = "weekly"
+"#
+        );
+        assert_eq!(ConfigFile::parse(&rendered).unwrap(), file);
+    }
+
+    #[test]
+    fn reports_line_numbers_for_invalid_lines() {
+        let err = ConfigFile::parse("ok = 1\n\n[broken\n").unwrap_err();
+        match err {
+            ConfigFileError::Parse { line, .. } => assert_eq!(line, 3),
+            other => panic!("unexpected error: {other}"),
+        }
+        assert!(ConfigFile::parse("key = [1, 2]").is_err());
+        assert!(ConfigFile::parse("key = \"unterminated").is_err());
+    }
+}
@@ -14,6 +14,35 @@ static RELATIVE_AGO_RE: LazyLock<Regex> =
 static RELATIVE_FUTURE_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^(\d+)(mo|w|d|h|m)$").expect("valid future regex"));
 
+/// Matches: "30s", "10m", "2h", "1d" (lengths of time)
+static DURATION_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d+)\s*(s|m|h|d)$").expect("valid duration regex"));
+
+/// Parse a length of time such as "30s", "10m", "2h", or "1d".
+pub fn parse_duration(input: &str) -> Result<std::time::Duration, String> {
+    let raw = input.trim();
+    let lower = raw.to_lowercase();
+    let caps = DURATION_RE
+        .captures(&lower)
+        .ok_or_else(|| format!("invalid duration {raw:?} (use e.g. 30s, 10m, 2h, 1d)"))?;
+    let value: u64 = caps[1]
+        .parse()
+        .map_err(|_| format!("invalid number in {raw:?}"))?;
+    if value < 1 {
+        return Err(format!("invalid duration {raw:?}"));
+    }
+    let unit = match &caps[2] {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        _ => 24 * 60 * 60,
+    };
+    value
+        .checked_mul(unit)
+        .map(std::time::Duration::from_secs)
+        .ok_or_else(|| format!("duration {raw:?} is too long"))
+}
+
 /// Parse human-friendly time expressions into Unix timestamps.
 ///
 /// # Supported formats
@@ -253,6 +282,20 @@ mod tests {
         assert!(parse_relative_time("0h ago", now).is_err());
     }
 
+    #[test]
+    fn test_durations() {
+        assert_eq!(
+            parse_duration("10m").unwrap(),
+            std::time::Duration::from_secs(600)
+        );
+        assert_eq!(
+            parse_duration(" 2H ").unwrap(),
+            std::time::Duration::from_secs(7200)
+        );
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("10 minutes").is_err());
+    }
+
     #[test]
     fn test_case_insensitive() {
         let now = test_now();
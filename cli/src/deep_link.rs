@@ -0,0 +1,72 @@
+//! `inline://` URLs handled by the Inline desktop and mobile apps.
+
+use std::process::Command;
+
+use crate::errors::CliError;
+use inline_protocol::proto;
+
+/// App URL for a chat or DM (`inline://chat/123`, `inline://user/42`), optionally
+/// scrolled to a message.
+pub(crate) fn inline_url(peer_type: &str, id: i64, message_id: Option<i64>) -> Option<String> {
+    let path = match peer_type {
+        "chat" => "chat",
+        "user" => "user",
+        _ => return None,
+    };
+    let mut url = format!("inline://{path}/{id}");
+    if let Some(message_id) = message_id {
+        url.push_str(&format!("?message={message_id}"));
+    }
+    Some(url)
+}
+
+pub(crate) fn inline_url_from_input(
+    peer: &proto::InputPeer,
+    message_id: Option<i64>,
+) -> Option<String> {
+    match &peer.r#type {
+        Some(proto::input_peer::Type::Chat(chat)) => inline_url("chat", chat.chat_id, message_id),
+        Some(proto::input_peer::Type::User(user)) => inline_url("user", user.user_id, message_id),
+        _ => None,
+    }
+}
+
+/// Hands a URL to the OS so the registered app opens it.
+pub(crate) fn open_url(url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(target_os = "windows") {
+        "explorer"
+    } else {
+        "xdg-open"
+    };
+    let status = Command::new(opener).arg(url).status().map_err(|err| {
+        CliError::invalid_args(format!("Could not run `{opener}` to open {url}: {err}"))
+    })?;
+    if !status.success() {
+        return Err(CliError::invalid_args(format!(
+            "`{opener}` could not open {url}. Is the Inline app installed?"
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peer::{input_chat_peer, input_user_peer};
+
+    #[test]
+    fn builds_chat_and_user_urls_with_optional_message() {
+        assert_eq!(
+            inline_url_from_input(&input_chat_peer(123), Some(456)).as_deref(),
+            Some("inline://chat/123?message=456")
+        );
+        assert_eq!(
+            inline_url_from_input(&input_user_peer(42), None).as_deref(),
+            Some("inline://user/42")
+        );
+        assert_eq!(inline_url("space", 1, None), None);
+    }
+}
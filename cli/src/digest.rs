@@ -0,0 +1,340 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::message_output::message_summary;
+use crate::output::{ChatListItem, PeerSummary};
+use inline_protocol::proto;
+
+pub(crate) const DIGEST_HISTORY_LIMIT: i32 = 100;
+const DIGEST_TOP_THREADS: usize = 5;
+const DIGEST_MENTION_PREVIEW_CHARS: usize = 80;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DigestOutput {
+    pub(crate) since: i64,
+    pub(crate) space_id: Option<i64>,
+    pub(crate) chats_with_activity: usize,
+    pub(crate) total_messages: usize,
+    pub(crate) total_unread: i32,
+    pub(crate) total_mentions: usize,
+    pub(crate) chats: Vec<DigestChat>,
+    pub(crate) mentions: Vec<DigestMention>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) posted_to_chat_id: Option<i64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DigestChat {
+    pub(crate) chat_id: i64,
+    pub(crate) peer: PeerSummary,
+    pub(crate) display_name: String,
+    pub(crate) space_name: Option<String>,
+    pub(crate) unread_count: i32,
+    pub(crate) message_count: usize,
+    pub(crate) mention_count: usize,
+    pub(crate) last_message_line: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DigestMention {
+    pub(crate) chat_id: i64,
+    pub(crate) chat_name: String,
+    pub(crate) message_id: i64,
+    pub(crate) date: i64,
+    pub(crate) sender_name: String,
+    pub(crate) preview: String,
+}
+
+/// Chats worth fetching history for: anything unread or active since the cutoff,
+/// optionally scoped to one space. Keeps the chat list order (most recent first).
+pub(crate) fn digest_candidate_chats(
+    items: Vec<ChatListItem>,
+    since_ts: i64,
+    space_id: Option<i64>,
+    max_chats: usize,
+) -> Vec<ChatListItem> {
+    items
+        .into_iter()
+        .filter(|item| space_id.is_none_or(|space_id| item.chat.space_id == Some(space_id)))
+        .filter(|item| {
+            item.unread_count.unwrap_or(0) > 0
+                || item
+                    .last_message
+                    .as_ref()
+                    .is_some_and(|summary| summary.message.date >= since_ts)
+        })
+        .take(max_chats)
+        .collect()
+}
+
+pub(crate) fn build_digest(
+    since_ts: i64,
+    space_id: Option<i64>,
+    chats: Vec<(ChatListItem, Vec<proto::Message>)>,
+    users_by_id: &HashMap<i64, proto::User>,
+    current_user_id: i64,
+    now: i64,
+) -> DigestOutput {
+    let mut digest_chats = Vec::new();
+    let mut mentions = Vec::new();
+    for (item, messages) in chats {
+        let messages = messages
+            .into_iter()
+            .filter(|message| message.date >= since_ts)
+            .collect::<Vec<_>>();
+        let unread_count = item.unread_count.unwrap_or(0);
+        if messages.is_empty() && unread_count == 0 {
+            continue;
+        }
+
+        let mut mention_count = 0;
+        for message in &messages {
+            if message.from_id == current_user_id || !mentions_user(message, current_user_id) {
+                continue;
+            }
+            mention_count += 1;
+            let summary = message_summary(message, users_by_id, Some(current_user_id), now, None);
+            mentions.push(DigestMention {
+                chat_id: item.chat.id,
+                chat_name: item.display_name.clone(),
+                message_id: message.id,
+                date: message.date,
+                sender_name: summary.sender_name,
+                preview: truncate_preview(&summary.preview),
+            });
+        }
+
+        digest_chats.push(DigestChat {
+            chat_id: item.chat.id,
+            peer: item.peer,
+            display_name: item.display_name,
+            space_name: item.space_name,
+            unread_count,
+            message_count: messages.len(),
+            mention_count,
+            last_message_line: item.last_message_line,
+        });
+    }
+
+    digest_chats.sort_by_key(|chat| {
+        (
+            Reverse(chat.mention_count),
+            Reverse(chat.message_count),
+            Reverse(chat.unread_count),
+        )
+    });
+    mentions.sort_by_key(|mention| Reverse(mention.date));
+
+    DigestOutput {
+        since: since_ts,
+        space_id,
+        chats_with_activity: digest_chats.len(),
+        total_messages: digest_chats.iter().map(|chat| chat.message_count).sum(),
+        total_unread: digest_chats.iter().map(|chat| chat.unread_count).sum(),
+        total_mentions: mentions.len(),
+        chats: digest_chats,
+        mentions,
+        posted_to_chat_id: None,
+    }
+}
+
+/// Markdown report used for both terminal output and `--post-to`.
+pub(crate) fn render_digest(digest: &DigestOutput) -> String {
+    let mut output = String::new();
+    output.push_str("**Inline digest** since ");
+    output.push_str(&format_digest_date(digest.since));
+    output.push_str("\n\n");
+    output.push_str(&format!(
+        "{} message(s) across {} chat(s), {} unread, {} mention(s) of you.\n",
+        digest.total_messages,
+        digest.chats_with_activity,
+        digest.total_unread,
+        digest.total_mentions
+    ));
+
+    if !digest.chats.is_empty() {
+        output.push_str("\n**Top threads**\n");
+        for chat in digest.chats.iter().take(DIGEST_TOP_THREADS) {
+            output.push_str("- ");
+            output.push_str(&chat.display_name);
+            if let Some(space_name) = chat.space_name.as_deref() {
+                output.push_str(" (");
+                output.push_str(space_name);
+                output.push(')');
+            }
+            output.push_str(&format!(": {} new", chat.message_count));
+            if chat.unread_count > 0 {
+                output.push_str(&format!(", {} unread", chat.unread_count));
+            }
+            if chat.mention_count > 0 {
+                output.push_str(&format!(", {} mention(s)", chat.mention_count));
+            }
+            output.push('\n');
+        }
+    }
+
+    if !digest.mentions.is_empty() {
+        output.push_str("\n**Mentions of you**\n");
+        for mention in &digest.mentions {
+            output.push_str(&format!(
+                "- {} in {}: {}\n",
+                mention.sender_name, mention.chat_name, mention.preview
+            ));
+        }
+    }
+
+    output
+}
+
+pub(crate) fn mentions_user(message: &proto::Message, user_id: i64) -> bool {
+    message.entities.as_ref().is_some_and(|entities| {
+        entities.entities.iter().any(|entity| {
+            matches!(
+                &entity.entity,
+                Some(proto::message_entity::Entity::Mention(mention)) if mention.user_id == user_id
+            )
+        })
+    })
+}
+
+pub(crate) fn truncate_preview(value: &str) -> String {
+    if value.chars().count() <= DIGEST_MENTION_PREVIEW_CHARS {
+        return value.to_string();
+    }
+    let mut preview = value
+        .chars()
+        .take(DIGEST_MENTION_PREVIEW_CHARS - 3)
+        .collect::<String>();
+    preview.push_str("...");
+    preview
+}
+
+fn format_digest_date(timestamp: i64) -> String {
+    DateTime::<Utc>::from_timestamp(timestamp, 0)
+        .map(|date| date.format("%b %-d, %H:%M UTC").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chat_item(chat_id: i64, space_id: Option<i64>, unread: i32, last_date: i64) -> ChatListItem {
+        let last_message = proto::Message {
+            id: 1,
+            date: last_date,
+            ..Default::default()
+        };
+        ChatListItem {
+            chat: proto::Chat {
+                id: chat_id,
+                space_id,
+                ..Default::default()
+            },
+            dialog: None,
+            peer: PeerSummary {
+                peer_type: "chat".to_string(),
+                id: chat_id,
+            },
+            display_name: format!("Chat {chat_id}"),
+            space: None,
+            space_name: None,
+            unread_count: Some(unread),
+            notifications: "default",
+            muted: false,
+            activity: None,
+            last_message: Some(message_summary(
+                &last_message,
+                &HashMap::new(),
+                None,
+                0,
+                None,
+            )),
+            last_message_line: None,
+            last_message_relative_date: None,
+        }
+    }
+
+    fn mention_message(id: i64, from_id: i64, user_id: i64, date: i64) -> proto::Message {
+        proto::Message {
+            id,
+            from_id,
+            date,
+            message: Some("@me ping".to_string()),
+            entities: Some(proto::MessageEntities {
+                entities: vec![proto::MessageEntity {
+                    r#type: proto::message_entity::Type::Mention as i32,
+                    offset: 0,
+                    length: 3,
+                    entity: Some(proto::message_entity::Entity::Mention(
+                        proto::message_entity::MessageEntityMention { user_id },
+                    )),
+                }],
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn candidates_skip_quiet_chats_and_other_spaces() {
+        let items = vec![
+            chat_item(1, Some(31), 0, 200),
+            chat_item(2, Some(31), 0, 50),
+            chat_item(3, Some(31), 4, 50),
+            chat_item(4, Some(7), 0, 200),
+        ];
+
+        let ids = digest_candidate_chats(items, 100, Some(31), 10)
+            .iter()
+            .map(|item| item.chat.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![1, 3]);
+    }
+
+    #[test]
+    fn digest_counts_messages_and_mentions_since_cutoff() {
+        let chats = vec![
+            (
+                chat_item(1, None, 0, 200),
+                vec![
+                    proto::Message {
+                        id: 1,
+                        date: 50,
+                        ..Default::default()
+                    },
+                    proto::Message {
+                        id: 2,
+                        date: 150,
+                        ..Default::default()
+                    },
+                ],
+            ),
+            (
+                chat_item(2, None, 2, 300),
+                vec![
+                    mention_message(5, 9, 42, 300),
+                    mention_message(6, 42, 42, 310),
+                ],
+            ),
+        ];
+
+        let digest = build_digest(100, None, chats, &HashMap::new(), 42, 400);
+        assert_eq!(digest.chats_with_activity, 2);
+        assert_eq!(digest.total_messages, 3);
+        assert_eq!(digest.total_unread, 2);
+        assert_eq!(digest.total_mentions, 1);
+        assert_eq!(digest.chats[0].chat_id, 2);
+        assert_eq!(digest.mentions[0].message_id, 5);
+
+        let text = render_digest(&digest);
+        assert!(text.contains("3 message(s) across 2 chat(s), 2 unread, 1 mention(s) of you."));
+        assert!(text.contains("- Chat 2: 2 new, 2 unread, 1 mention(s)"));
+        assert!(text.contains("**Mentions of you**"));
+    }
+}
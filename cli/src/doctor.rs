@@ -1,13 +1,23 @@
 use serde::Serialize;
 use std::env;
+use std::time::{Duration, Instant};
 
 use crate::auth::AuthStore;
 use crate::config::Config;
 use crate::identity as client_info;
 use crate::output;
-use crate::state::LocalDb;
-use crate::user_display_name;
+use crate::output::format_relative_date;
+use crate::state::{LocalDb, StateEncryptionStatus};
+use crate::sync::client_store_path;
+use crate::{current_epoch_seconds, user_display_name};
 use inline_protocol::proto;
+use inline_sdk::RealtimeError;
+
+/// Per-probe limit, so a black-holed host can't hang `inline doctor`.
+pub(crate) const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Clock differences beyond this break TLS validation and token checks.
+const MAX_CLOCK_SKEW_SECONDS: i64 = 60;
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -17,6 +27,9 @@ pub(crate) struct DoctorOutput {
     config: DoctorConfig,
     paths: DoctorPaths,
     auth: DoctorAuth,
+    encryption: DoctorEncryption,
+    /// Network probes; empty with `--offline`.
+    connectivity: Option<DoctorConnectivity>,
 }
 
 #[derive(Serialize)]
@@ -44,8 +57,13 @@ struct DoctorClient {
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct DoctorConfig {
+    profile: Option<String>,
     api_base_url: String,
     realtime_url: String,
+    /// Effective proxy with the password redacted.
+    proxy: Option<String>,
+    ca_bundle: Option<String>,
+    tls_pins: Vec<String>,
     release_manifest_url: Option<String>,
     release_install_url: Option<String>,
 }
@@ -59,6 +77,17 @@ struct DoctorPaths {
     secrets_exists: bool,
     state_path: String,
     state_exists: bool,
+    config_path: String,
+    config_exists: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DoctorEncryption {
+    state: StateEncryptionStatus,
+    /// The `inline sync` cache is SQLite and is not encrypted.
+    sync_cache_path: String,
+    sync_cache_exists: bool,
 }
 
 #[derive(Serialize)]
@@ -67,10 +96,59 @@ struct DoctorAuth {
     token_present: bool,
     token_source: Option<String>,
     token_error: Option<String>,
+    /// Unix seconds the saved token was written; unknown for `INLINE_TOKEN`.
+    token_saved_at: Option<i64>,
+    /// The server does not report token expiry yet, so this is always empty.
+    token_expires_at: Option<i64>,
     current_user: Option<proto::User>,
     state_error: Option<String>,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DoctorConnectivity {
+    /// Proxy the probes went through, with the password redacted.
+    proxy: Option<String>,
+    dns: DoctorProbe,
+    api: DoctorProbe,
+    realtime: DoctorProbe,
+    /// Server clock minus local clock, from the API's `Date` header.
+    clock_skew_seconds: Option<i64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DoctorProbe {
+    pub(crate) target: String,
+    pub(crate) ok: bool,
+    pub(crate) latency_ms: Option<u64>,
+    pub(crate) detail: String,
+    /// dns, connect, tls, timeout, proxy, http, or protocol when the probe failed.
+    pub(crate) error_kind: Option<&'static str>,
+}
+
+impl DoctorProbe {
+    fn ok(target: &str, started: Instant, detail: String) -> Self {
+        Self {
+            target: target.to_string(),
+            ok: true,
+            latency_ms: Some(started.elapsed().as_millis() as u64),
+            detail,
+            error_kind: None,
+        }
+    }
+
+    fn failed(target: &str, kind: &'static str, detail: String) -> Self {
+        Self {
+            target: target.to_string(),
+            ok: false,
+            latency_ms: None,
+            detail,
+            error_kind: Some(kind),
+        }
+    }
+}
+
 pub(crate) fn build_doctor_output(
     config: &Config,
     auth_store: &AuthStore,
@@ -105,6 +183,8 @@ pub(crate) fn build_doctor_output(
         }
     }
 
+    let token_status = auth_store.token_status().ok().flatten();
+
     let (current_user, state_error) = match local_db.load() {
         Ok(state) => (state.current_user, None),
         Err(err) => (None, Some(err.to_string())),
@@ -120,8 +200,17 @@ pub(crate) fn build_doctor_output(
         },
         client: build_doctor_client(),
         config: DoctorConfig {
+            profile: config.profile.clone(),
             api_base_url: config.api_base_url.clone(),
             realtime_url: config.realtime_url.clone(),
+            proxy: client_info::proxy().map(|proxy| proxy.redacted_url()),
+            ca_bundle: config
+                .ca_bundle
+                .as_ref()
+                .map(|path| path.display().to_string()),
+            tls_pins: client_info::tls()
+                .map(|tls| tls.pinned_sha256())
+                .unwrap_or_default(),
             release_manifest_url: config.release_manifest_url.clone(),
             release_install_url: config.release_install_url.clone(),
         },
@@ -132,17 +221,204 @@ pub(crate) fn build_doctor_output(
             secrets_exists: config.secrets_path.exists(),
             state_path: config.state_path.display().to_string(),
             state_exists: config.state_path.exists(),
+            config_path: config.config_path.display().to_string(),
+            config_exists: config.config_path.exists(),
         },
         auth: DoctorAuth {
             token_present,
             token_source,
             token_error,
+            token_saved_at: token_status.as_ref().and_then(|token| token.saved_at),
+            token_expires_at: token_status.as_ref().and_then(|token| token.expires_at),
             current_user,
             state_error,
         },
+        encryption: DoctorEncryption {
+            state: local_db.encryption_status(),
+            sync_cache_path: client_store_path(&config.data_dir).display().to_string(),
+            sync_cache_exists: client_store_path(&config.data_dir).exists(),
+        },
+        connectivity: None,
+    }
+}
+
+impl DoctorOutput {
+    pub(crate) fn with_connectivity(mut self, connectivity: DoctorConnectivity) -> Self {
+        self.connectivity = Some(connectivity);
+        self
+    }
+}
+
+/// Resolves the API host, sends a HEAD to the API base URL, and opens (then
+/// closes) a realtime websocket, all through the configured proxy and TLS
+/// settings. Without a token the websocket is still opened; the server
+/// rejecting the session counts as reachable.
+pub(crate) async fn probe_connectivity(config: &Config, token: Option<&str>) -> DoctorConnectivity {
+    let proxy = client_info::proxy();
+    let api_host = reqwest::Url::parse(&config.api_base_url)
+        .ok()
+        .and_then(|url| Some((url.host_str()?.to_string(), url.port_or_known_default()?)));
+    let via_proxy = match (&api_host, proxy) {
+        (Some((host, _)), Some(proxy)) => !proxy.bypasses(host),
+        _ => false,
+    };
+
+    let dns = match &api_host {
+        Some((host, port)) => probe_dns(host, *port, via_proxy).await,
+        None => DoctorProbe::failed(&config.api_base_url, "dns", "invalid API URL".to_string()),
+    };
+    let (api, clock_skew_seconds) = probe_api(&config.api_base_url).await;
+    let realtime = probe_realtime(&config.realtime_url, token).await;
+
+    DoctorConnectivity {
+        proxy: proxy.map(|proxy| proxy.redacted_url()),
+        dns,
+        api,
+        realtime,
+        clock_skew_seconds,
+    }
+}
+
+async fn probe_dns(host: &str, port: u16, via_proxy: bool) -> DoctorProbe {
+    let started = Instant::now();
+    match tokio::time::timeout(PROBE_TIMEOUT, tokio::net::lookup_host((host, port))).await {
+        Ok(Ok(addresses)) => {
+            let addresses = addresses
+                .map(|address| address.ip().to_string())
+                .collect::<Vec<_>>();
+            let mut detail = addresses.join(", ");
+            if via_proxy {
+                detail.push_str(" (requests go through the proxy)");
+            }
+            DoctorProbe::ok(host, started, detail)
+        }
+        Ok(Err(err)) if via_proxy => DoctorProbe::ok(
+            host,
+            started,
+            format!("not resolvable here ({err}); the proxy resolves it"),
+        ),
+        Ok(Err(err)) => DoctorProbe::failed(host, "dns", err.to_string()),
+        Err(_) => DoctorProbe::failed(host, "timeout", "DNS lookup timed out".to_string()),
+    }
+}
+
+pub(crate) async fn probe_api(url: &str) -> (DoctorProbe, Option<i64>) {
+    let client = match client_info::http_client_builder()
+        .map_err(|err| err.to_string())
+        .and_then(|builder| {
+            builder
+                .timeout(PROBE_TIMEOUT)
+                .build()
+                .map_err(|err| err.to_string())
+        }) {
+        Ok(client) => client,
+        Err(err) => return (DoctorProbe::failed(url, "connect", err), None),
+    };
+    let started = Instant::now();
+    match client.head(url).send().await {
+        Ok(response) => {
+            let skew = response
+                .headers()
+                .get(reqwest::header::DATE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| clock_skew(value, current_epoch_seconds() as i64));
+            let mut detail = format!("HTTP {}", response.status().as_u16());
+            if url.starts_with("https://") {
+                detail.push_str(", TLS ok");
+            }
+            (DoctorProbe::ok(url, started, detail), skew)
+        }
+        Err(err) => {
+            let text = error_chain(&err);
+            let kind = if err.is_timeout() {
+                "timeout"
+            } else {
+                classify_connection_error(&text)
+            };
+            (DoctorProbe::failed(url, kind, text), None)
+        }
+    }
+}
+
+pub(crate) async fn probe_realtime(url: &str, token: Option<&str>) -> DoctorProbe {
+    let started = Instant::now();
+    let result = client_info::realtime_client_builder(url, token.unwrap_or_default())
+        .connect_timeout(PROBE_TIMEOUT)
+        .without_heartbeat()
+        .connect()
+        .await;
+    match result {
+        Ok(client) => {
+            let probe = DoctorProbe::ok(url, started, "connected, session open".to_string());
+            let _ = client.close().await;
+            probe
+        }
+        Err(RealtimeError::ConnectionError { reason_name, .. }) => {
+            let detail = if token.is_some() {
+                format!("connected, but the server rejected the session ({reason_name})")
+            } else {
+                "connected (not logged in)".to_string()
+            };
+            DoctorProbe::ok(url, started, detail)
+        }
+        Err(RealtimeError::Timeout { operation, .. }) => DoctorProbe::failed(
+            url,
+            "timeout",
+            format!("{operation} timed out after {}s", PROBE_TIMEOUT.as_secs()),
+        ),
+        Err(RealtimeError::Proxy(err)) => DoctorProbe::failed(url, "proxy", err.to_string()),
+        Err(RealtimeError::Protocol(err)) => DoctorProbe::failed(
+            url,
+            "protocol",
+            format!("the server's reply isn't this CLI's realtime protocol ({err})"),
+        ),
+        Err(err) => {
+            let text = error_chain(&err);
+            DoctorProbe::failed(url, classify_connection_error(&text), text)
+        }
     }
 }
 
+/// The error and its sources, since the useful part ("certificate expired",
+/// "connection refused") is usually a few levels down.
+pub(crate) fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut text = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        let cause_text = cause.to_string();
+        if !text.contains(&cause_text) {
+            text.push_str(": ");
+            text.push_str(&cause_text);
+        }
+        source = cause.source();
+    }
+    text
+}
+
+pub(crate) fn classify_connection_error(text: &str) -> &'static str {
+    let text = text.to_ascii_lowercase();
+    if ["certificate", "tls", "handshake", "pin"]
+        .iter()
+        .any(|needle| text.contains(needle))
+    {
+        "tls"
+    } else if text.contains("dns") || text.contains("failed to lookup") {
+        "dns"
+    } else if text.contains("proxy") {
+        "proxy"
+    } else if text.contains("http error") || text.contains("status") {
+        "http"
+    } else {
+        "connect"
+    }
+}
+
+/// Seconds the server clock is ahead of ours, from an HTTP `Date` header.
+fn clock_skew(date_header: &str, now: i64) -> Option<i64> {
+    let server = chrono::DateTime::parse_from_rfc2822(date_header).ok()?;
+    Some(server.timestamp() - now)
+}
+
 pub(crate) fn print_doctor(output: &DoctorOutput) {
     print_section("System");
     println!("  version: {}", output.system.version);
@@ -175,8 +451,20 @@ pub(crate) fn print_doctor(output: &DoctorOutput) {
     );
 
     print_section_after_break("Config");
+    println!(
+        "  profile: {}",
+        output.config.profile.as_deref().unwrap_or("-")
+    );
     println!("  api base url: {}", output.config.api_base_url);
     println!("  realtime url: {}", output.config.realtime_url);
+    println!("  proxy: {}", output.config.proxy.as_deref().unwrap_or("-"));
+    println!(
+        "  ca bundle: {}",
+        output.config.ca_bundle.as_deref().unwrap_or("-")
+    );
+    if !output.config.tls_pins.is_empty() {
+        println!("  tls pins: {}", output.config.tls_pins.join(", "));
+    }
     println!(
         "  release manifest url: {}",
         output.config.release_manifest_url.as_deref().unwrap_or("-")
@@ -214,6 +502,15 @@ pub(crate) fn print_doctor(output: &DoctorOutput) {
             "missing"
         }
     );
+    println!(
+        "  config file: {} ({})",
+        output.paths.config_path,
+        if output.paths.config_exists {
+            "exists"
+        } else {
+            "missing"
+        }
+    );
 
     print_section_after_break("Auth");
     if output.auth.token_present {
@@ -222,6 +519,19 @@ pub(crate) fn print_doctor(output: &DoctorOutput) {
         } else {
             println!("  token: present");
         }
+        if let Some(saved_at) = output.auth.token_saved_at {
+            println!(
+                "  token saved: {}",
+                format_relative_date(saved_at, current_epoch_seconds() as i64)
+            );
+        }
+        match output.auth.token_expires_at {
+            Some(expires_at) => println!(
+                "  token expires: {}",
+                format_relative_date(expires_at, current_epoch_seconds() as i64)
+            ),
+            None => println!("  token expires: not reported (check with `inline auth refresh`)"),
+        }
     } else {
         println!("  token: absent");
     }
@@ -242,6 +552,65 @@ pub(crate) fn print_doctor(output: &DoctorOutput) {
     if let Some(error) = &output.auth.state_error {
         println!("  state error: {}", error);
     }
+
+    print_section_after_break("Encryption");
+    let state = &output.encryption.state;
+    match state.key_source {
+        Some(source) => println!("  state file: {} (key: {source})", state.file),
+        None => println!("  state file: {}", state.file),
+    }
+    if let Some(error) = &state.key_error {
+        println!("  key error: {error}");
+    }
+    if output.encryption.sync_cache_exists {
+        println!(
+            "  sync cache: not encrypted ({}; delete it to remove cached messages)",
+            output.encryption.sync_cache_path
+        );
+    } else {
+        println!("  sync cache: none");
+    }
+
+    if let Some(connectivity) = &output.connectivity {
+        print_connectivity(connectivity);
+    }
+}
+
+fn print_connectivity(connectivity: &DoctorConnectivity) {
+    print_section_after_break("Connectivity");
+    println!(
+        "  proxy: {}",
+        connectivity.proxy.as_deref().unwrap_or("none")
+    );
+    for (label, probe) in [
+        ("dns", &connectivity.dns),
+        ("api", &connectivity.api),
+        ("realtime", &connectivity.realtime),
+    ] {
+        let latency = probe
+            .latency_ms
+            .map(|ms| format!(" ({ms} ms)"))
+            .unwrap_or_default();
+        if probe.ok {
+            println!("  {label}: ok {} — {}{latency}", probe.target, probe.detail);
+        } else {
+            println!(
+                "  {label}: FAILED {} — {} error: {}",
+                probe.target,
+                probe.error_kind.unwrap_or("unknown"),
+                probe.detail
+            );
+        }
+    }
+    match connectivity.clock_skew_seconds {
+        Some(skew) if skew.abs() > MAX_CLOCK_SKEW_SECONDS => println!(
+            "  clock: off by {}s ({} the server); fix the system clock, TLS and sign-in can fail",
+            skew.abs(),
+            if skew > 0 { "behind" } else { "ahead of" }
+        ),
+        Some(skew) => println!("  clock: in sync ({skew:+}s)"),
+        None => println!("  clock: unknown (no Date header from the API)"),
+    }
 }
 
 fn print_section(title: &str) {
@@ -269,6 +638,28 @@ fn build_doctor_client() -> DoctorClient {
 mod tests {
     use super::*;
 
+    #[test]
+    fn connectivity_errors_are_classified_and_clock_skew_read_from_date() {
+        assert_eq!(
+            clock_skew("Thu, 15 Oct 2026 12:01:30 GMT", 1_792_065_600),
+            Some(90)
+        );
+        assert_eq!(clock_skew("yesterday", 0), None);
+
+        assert_eq!(
+            classify_connection_error("invalid peer certificate: Expired"),
+            "tls"
+        );
+        assert_eq!(
+            classify_connection_error("error sending request: dns error: failed to lookup address"),
+            "dns"
+        );
+        assert_eq!(
+            classify_connection_error("tcp connect error: Connection refused (os error 111)"),
+            "connect"
+        );
+    }
+
     #[test]
     fn doctor_client_diagnostics_match_client_metadata() {
         let output = build_doctor_client();
@@ -0,0 +1,242 @@
+//! Repairs for `inline doctor --fix`.
+//!
+//! Only local files are touched. Anything that gets removed is renamed to a
+//! `.corrupt-<unix seconds>` sibling first, so a wrong call can be undone by
+//! renaming it back.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::auth::AuthStore;
+use crate::config::Config;
+use crate::state::{LocalDb, LocalState, StateError};
+
+/// Update-check timestamps further ahead of the local clock than this never
+/// expire, so no check runs again until they're cleared.
+const FUTURE_SLACK_SECONDS: i64 = 5 * 60;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DoctorFix {
+    CreateDataDir { path: PathBuf },
+    ResetState { path: PathBuf, error: String },
+    ClearUpdateCheck { reason: String },
+    ResetSecrets { path: PathBuf, error: String },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DoctorFixResult {
+    pub(crate) kind: &'static str,
+    pub(crate) problem: String,
+    pub(crate) action: String,
+    /// fixed, skipped, or failed.
+    pub(crate) status: &'static str,
+    pub(crate) backup_path: Option<String>,
+    pub(crate) error: Option<String>,
+}
+
+impl DoctorFix {
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Self::CreateDataDir { .. } => "create_data_dir",
+            Self::ResetState { .. } => "reset_state",
+            Self::ClearUpdateCheck { .. } => "clear_update_check",
+            Self::ResetSecrets { .. } => "reset_secrets",
+        }
+    }
+
+    pub(crate) fn problem(&self) -> String {
+        match self {
+            Self::CreateDataDir { path } => format!("Data dir {} is missing", path.display()),
+            Self::ResetState { path, error } => {
+                format!("State file {} is unreadable: {error}", path.display())
+            }
+            Self::ClearUpdateCheck { reason } => format!("Update-check state is stale: {reason}"),
+            Self::ResetSecrets { path, error } => {
+                format!("Token file {} is unreadable: {error}", path.display())
+            }
+        }
+    }
+
+    pub(crate) fn action(&self) -> String {
+        match self {
+            Self::CreateDataDir { path } => format!("Create {}", path.display()),
+            Self::ResetState { path, .. } => {
+                format!("Back up and remove {}", path.display())
+            }
+            Self::ClearUpdateCheck { .. } => "Clear the saved update-check state".to_string(),
+            Self::ResetSecrets { path, .. } => {
+                format!("Back up and remove {}, then log in again", path.display())
+            }
+        }
+    }
+
+    pub(crate) fn result(
+        &self,
+        status: &'static str,
+        backup_path: Option<&Path>,
+        error: Option<String>,
+    ) -> DoctorFixResult {
+        DoctorFixResult {
+            kind: self.kind(),
+            problem: self.problem(),
+            action: self.action(),
+            status,
+            backup_path: backup_path.map(|path| path.display().to_string()),
+            error,
+        }
+    }
+}
+
+/// Problems `--fix` knows how to repair, in the order they should be applied.
+pub(crate) fn plan_fixes(
+    config: &Config,
+    auth_store: &AuthStore,
+    local_db: &LocalDb,
+    now: i64,
+) -> Vec<DoctorFix> {
+    let mut fixes = Vec::new();
+    if !config.data_dir.exists() {
+        fixes.push(DoctorFix::CreateDataDir {
+            path: config.data_dir.clone(),
+        });
+    }
+    match local_db.load() {
+        // Io errors (permissions) and a missing key aren't fixed by deleting.
+        Err(err @ (StateError::Json(_) | StateError::Undecryptable)) => {
+            fixes.push(DoctorFix::ResetState {
+                path: config.state_path.clone(),
+                error: err.to_string(),
+            });
+        }
+        Err(_) => {}
+        Ok(state) => {
+            if let Some(reason) =
+                stale_update_check(&state, config.release_manifest_url.as_deref(), now)
+            {
+                fixes.push(DoctorFix::ClearUpdateCheck { reason });
+            }
+        }
+    }
+    if let Err(err) = auth_store.check_secrets() {
+        fixes.push(DoctorFix::ResetSecrets {
+            path: config.secrets_path.clone(),
+            error: err.to_string(),
+        });
+    }
+    fixes
+}
+
+fn stale_update_check(state: &LocalState, manifest_url: Option<&str>, now: i64) -> Option<String> {
+    let in_future = [state.last_update_attempt_at, state.last_update_check_at]
+        .into_iter()
+        .flatten()
+        .any(|at| at > now + FUTURE_SLACK_SECONDS);
+    if in_future {
+        return Some("last check is dated in the future".to_string());
+    }
+    match (state.release_manifest_url.as_deref(), manifest_url) {
+        (Some(saved), Some(current)) if saved != current => {
+            Some(format!("saved for {saved}, now using {current}"))
+        }
+        (Some(saved), None) => Some(format!("saved for {saved}, update checks are now off")),
+        _ => None,
+    }
+}
+
+/// Applies one fix. Returns where the old file was moved, if anywhere.
+pub(crate) fn apply_fix(
+    fix: &DoctorFix,
+    local_db: &LocalDb,
+    now: i64,
+) -> Result<Option<PathBuf>, String> {
+    match fix {
+        DoctorFix::CreateDataDir { path } => {
+            create_private_dir(path).map_err(|err| err.to_string())?;
+            Ok(None)
+        }
+        DoctorFix::ResetState { path, .. } | DoctorFix::ResetSecrets { path, .. } => {
+            move_aside(path, now).map_err(|err| err.to_string())
+        }
+        DoctorFix::ClearUpdateCheck { .. } => {
+            let mut state = local_db.load().map_err(|err| err.to_string())?;
+            state.release_manifest_url = None;
+            state.last_update_attempt_at = None;
+            state.last_update_check_at = None;
+            state.last_update_notified_version = None;
+            state.last_seen_release_version = None;
+            state.update_check_failures = 0;
+            local_db.save(&state).map_err(|err| err.to_string())?;
+            Ok(None)
+        }
+    }
+}
+
+/// Renames `path` to `<path>.corrupt-<now>`. A file that's already gone needs
+/// no backup.
+fn move_aside(path: &Path, now: i64) -> Result<Option<PathBuf>, io::Error> {
+    let backup = backup_path(path, now);
+    match fs::rename(path, &backup) {
+        Ok(()) => Ok(Some(backup)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+fn backup_path(path: &Path, now: i64) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".corrupt-{now}"));
+    path.with_file_name(name)
+}
+
+fn create_private_dir(path: &Path) -> Result<(), io::Error> {
+    fs::create_dir_all(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_check_state_is_stale_when_dated_ahead_or_for_another_manifest() {
+        let now = 1_792_065_600;
+        let manifest = "https://public-assets.inline.chat/cli/manifest.json";
+        let mut state = LocalState {
+            release_manifest_url: Some(manifest.to_string()),
+            last_update_attempt_at: Some(now - 60),
+            ..LocalState::default()
+        };
+        assert_eq!(stale_update_check(&state, Some(manifest), now), None);
+
+        state.last_update_attempt_at = Some(now + 86_400);
+        assert_eq!(
+            stale_update_check(&state, Some(manifest), now).as_deref(),
+            Some("last check is dated in the future")
+        );
+
+        state.last_update_attempt_at = Some(now);
+        assert!(
+            stale_update_check(&state, Some("https://example.com/manifest.json"), now)
+                .is_some_and(|reason| reason.starts_with("saved for "))
+        );
+        assert!(stale_update_check(&state, None, now).is_some());
+        assert_eq!(stale_update_check(&LocalState::default(), None, now), None);
+    }
+
+    #[test]
+    fn backups_sit_next_to_the_original() {
+        assert_eq!(
+            backup_path(Path::new("/data/state.json"), 42),
+            PathBuf::from("/data/state.json.corrupt-42")
+        );
+    }
+}
@@ -92,7 +92,7 @@ pub(crate) async fn download_message_media(
         std::fs::create_dir_all(parent)?;
     }
 
-    let client = client_info::http_client_builder().build()?;
+    let client = client_info::http_client_builder()?.build()?;
     let response = client.get(url).send().await?;
     if !response.status().is_success() {
         let status = response.status().as_u16();
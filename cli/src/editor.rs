@@ -0,0 +1,110 @@
+//! `--edit` on `messages send` and `messages edit`: open `$VISUAL` or
+//! `$EDITOR` on the text, the way `git commit` does, and send what was saved.
+//!
+//! The commented help header ends at a scissors line and everything above it
+//! is dropped, so markdown headings (`# Title`) in the message survive. A
+//! message left empty cancels.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::errors::CliError;
+
+const SCISSORS: &str = "# ------------------------ >8 ------------------------";
+
+/// File the text is edited in, under the data directory; removed afterwards.
+const FILE_NAME: &str = "INLINE_MESSAGE.md";
+
+/// `$VISUAL`, then `$EDITOR`, then `vi`. The value may carry arguments
+/// (`code --wait`); it runs through `sh`.
+fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Opens the editor on `initial` below a header made of `help` lines, and
+/// returns the saved text, trimmed, or `None` if it was left empty.
+pub(crate) fn compose(
+    initial: &str,
+    help: &[String],
+    data_dir: &Path,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    fs::create_dir_all(data_dir)?;
+    let path = data_dir.join(FILE_NAME);
+    fs::write(&path, template(initial, help))?;
+
+    let editor = editor_command();
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$@\""))
+        .arg(&editor)
+        .arg(&path)
+        .status();
+    let saved = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+    let status =
+        status.map_err(|err| CliError::editor_failed(&editor, format!("could not run: {err}")))?;
+    if !status.success() {
+        return Err(CliError::editor_failed(&editor, format!("exited with {status}")).into());
+    }
+    let text = strip_help(&saved?);
+    Ok((!text.is_empty()).then_some(text))
+}
+
+fn template(initial: &str, help: &[String]) -> String {
+    let mut contents = String::new();
+    for line in help {
+        contents.push_str("# ");
+        contents.push_str(line);
+        contents.push('\n');
+    }
+    contents.push_str(SCISSORS);
+    contents.push('\n');
+    contents.push_str(initial);
+    if !initial.is_empty() && !initial.ends_with('\n') {
+        contents.push('\n');
+    }
+    contents
+}
+
+/// The text below the scissors line, or all of it if the line was deleted.
+fn strip_help(contents: &str) -> String {
+    let body = contents
+        .split_inclusive('\n')
+        .position(|line| line.trim_end() == SCISSORS)
+        .map(|index| {
+            contents
+                .split_inclusive('\n')
+                .skip(index + 1)
+                .collect::<String>()
+        })
+        .unwrap_or_else(|| contents.to_string());
+    body.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn help_header_is_dropped_but_headings_are_kept() {
+        let help = vec![
+            "Save and close to send.".to_string(),
+            "To: chat 7".to_string(),
+        ];
+        let contents = template("# Release notes\n\n- faster sync", &help);
+        assert_eq!(
+            contents,
+            format!(
+                "# Save and close to send.\n# To: chat 7\n{SCISSORS}\n# Release notes\n\n- faster sync\n"
+            )
+        );
+        assert_eq!(strip_help(&contents), "# Release notes\n\n- faster sync");
+        assert_eq!(strip_help(&template("", &help)), "");
+        assert_eq!(strip_help("  kept whole\n"), "kept whole");
+    }
+}
@@ -0,0 +1,179 @@
+//! Recorded realtime traffic for `inline debug record` and `inline debug replay`.
+//!
+//! A recording is NDJSON: one pushed event per line with the time it arrived,
+//! in the same serde shape as `--json` output, so a recording can be trimmed
+//! by hand or checked in as a test fixture.
+
+use std::io::{self, BufRead, Write};
+
+use inline_protocol::proto;
+use inline_sdk::RealtimeEvent;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::digest::truncate_preview;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RecordedEvent {
+    /// Unix time in milliseconds when the event arrived.
+    pub(crate) at_ms: i64,
+    #[serde(flatten)]
+    pub(crate) body: EventBody,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub(crate) enum EventBody {
+    Updates {
+        updates: Vec<proto::Update>,
+    },
+    Ack {
+        msg_id: u64,
+    },
+    Pong {
+        nonce: u64,
+    },
+    /// The recorder fell behind and the client dropped this many events.
+    Lagged {
+        skipped: u64,
+    },
+}
+
+impl EventBody {
+    pub(crate) fn from_realtime(event: &RealtimeEvent) -> Option<Self> {
+        match event {
+            RealtimeEvent::Updates(updates) => Some(Self::Updates {
+                updates: updates.clone(),
+            }),
+            RealtimeEvent::Ack { msg_id } => Some(Self::Ack { msg_id: *msg_id }),
+            RealtimeEvent::Pong { nonce } => Some(Self::Pong { nonce: *nonce }),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn append_event(writer: &mut impl Write, event: &RecordedEvent) -> io::Result<()> {
+    let mut line = serde_json::to_vec(event)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    // Flushed per event so an interrupted recording keeps everything so far.
+    writer.flush()
+}
+
+/// Reads a recording, skipping blank lines. Errors name the offending line.
+pub(crate) fn read_events(reader: impl BufRead) -> Result<Vec<RecordedEvent>, String> {
+    let mut events = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|error| error.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event =
+            serde_json::from_str(&line).map_err(|error| format!("line {}: {error}", index + 1))?;
+        events.push(event);
+    }
+    Ok(events)
+}
+
+/// Human-readable lines for one event, timed relative to the recording start.
+pub(crate) fn describe_event(event: &RecordedEvent, start_ms: i64) -> Vec<String> {
+    let offset = format!("+{:.3}s", (event.at_ms - start_ms) as f64 / 1000.0);
+    match &event.body {
+        EventBody::Updates { updates } if updates.is_empty() => {
+            vec![format!("{offset} updates (empty)")]
+        }
+        EventBody::Updates { updates } => updates
+            .iter()
+            .map(|update| format!("{offset} {}", describe_update(update)))
+            .collect(),
+        EventBody::Ack { msg_id } => vec![format!("{offset} ack {msg_id}")],
+        EventBody::Pong { nonce } => vec![format!("{offset} pong {nonce}")],
+        EventBody::Lagged { skipped } => {
+            vec![format!("{offset} lagged, {skipped} event(s) dropped")]
+        }
+    }
+}
+
+fn describe_update(update: &proto::Update) -> String {
+    match update.update.as_ref() {
+        Some(proto::update::Update::NewMessage(proto::UpdateNewMessage {
+            message: Some(message),
+            ..
+        })) => format!(
+            "NewMessage chat {} message {}: {}",
+            message.chat_id,
+            message.id,
+            truncate_preview(message.message.as_deref().unwrap_or_default())
+        ),
+        Some(proto::update::Update::UpdateReaction(proto::UpdateReaction {
+            reaction: Some(reaction),
+            ..
+        })) => format!(
+            "UpdateReaction chat {} message {}: {}",
+            reaction.chat_id, reaction.message_id, reaction.emoji
+        ),
+        Some(inner) => match serde_json::to_value(inner) {
+            Ok(Value::Object(map)) if map.len() == 1 => {
+                map.keys().next().cloned().unwrap_or_default()
+            }
+            _ => "update".to_string(),
+        },
+        None => "update (empty)".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_and_describes_recorded_events() {
+        let events = [
+            RecordedEvent {
+                at_ms: 1_000,
+                body: EventBody::Updates {
+                    updates: vec![proto::Update {
+                        update: Some(proto::update::Update::NewMessage(proto::UpdateNewMessage {
+                            message: Some(proto::Message {
+                                id: 12,
+                                chat_id: 7,
+                                message: Some("deploy done".to_string()),
+                                ..Default::default()
+                            }),
+                        })),
+                        ..Default::default()
+                    }],
+                },
+            },
+            RecordedEvent {
+                at_ms: 3_500,
+                body: EventBody::Lagged { skipped: 4 },
+            },
+        ];
+        let mut recording = Vec::new();
+        for event in &events {
+            append_event(&mut recording, event).unwrap();
+        }
+        recording.extend_from_slice(b"\n");
+
+        let read = read_events(recording.as_slice()).unwrap();
+        assert_eq!(read, events);
+        assert_eq!(
+            describe_event(&read[0], 1_000),
+            ["+0.000s NewMessage chat 7 message 12: deploy done"]
+        );
+        assert_eq!(
+            describe_event(&read[1], 1_000),
+            ["+2.500s lagged, 4 event(s) dropped"]
+        );
+
+        let error =
+            read_events(&b"{\"atMs\":1,\"kind\":\"ack\",\"msgId\":3}\nnot json\n"[..]).unwrap_err();
+        assert!(error.starts_with("line 2:"), "{error}");
+    }
+}
@@ -0,0 +1,267 @@
+//! Calendar events found in chat messages, for `inline events extract`.
+//!
+//! Day words are resolved with [`parse_relative_time`] against the message's own
+//! send time, so "friday" in a message from last week means that week's Friday.
+//! Like the rest of the CLI's date handling, times are read as UTC.
+
+use std::sync::LazyLock;
+
+use chrono::{DateTime, Duration, NaiveTime, TimeZone, Timelike, Utc};
+use regex::Regex;
+use serde::Serialize;
+
+use crate::dates::parse_relative_time;
+use crate::deep_link::inline_url;
+use crate::digest::truncate_preview;
+
+/// "today", "tomorrow", weekday names with an optional next/this, or
+/// YYYY-MM-DD. Abbreviations are left out: "sat" and "sun" are words too.
+static DAY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(today|tomorrow|(?:(?:next|this)\s+)?(?:mon|tues|wednes|thurs|fri|satur|sun)day|\d{4}-\d{2}-\d{2})\b",
+    )
+    .expect("valid day regex")
+});
+
+/// "3pm", "10:30 am", "at 14:00". A bare "14:00" needs the "at" so version
+/// numbers and scores don't turn into meetings.
+static TIME_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(?:(\d{1,2})(?::(\d{2}))?\s*(am|pm)\b|at\s+(\d{1,2}):(\d{2})\b)")
+        .expect("valid time regex")
+});
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ChatEvent {
+    pub(crate) message_id: i64,
+    /// Unix seconds; midnight UTC for all-day events.
+    pub(crate) start: i64,
+    pub(crate) end: i64,
+    pub(crate) all_day: bool,
+    /// The words in the message the date was read from.
+    pub(crate) matched: String,
+    pub(crate) summary: String,
+    pub(crate) description: String,
+    pub(crate) url: Option<String>,
+}
+
+pub(crate) struct EventSource<'a> {
+    pub(crate) peer_type: &'a str,
+    pub(crate) peer_id: i64,
+    pub(crate) chat_name: &'a str,
+    pub(crate) message_id: i64,
+    pub(crate) sent_at: i64,
+    pub(crate) sender_name: &'a str,
+    pub(crate) text: &'a str,
+}
+
+/// The first date/time expression in a message, as an event. Messages with
+/// only a time are taken to mean the next such time after they were sent.
+pub(crate) fn extract_event(source: &EventSource<'_>, duration: Duration) -> Option<ChatEvent> {
+    let sent_at = Utc.timestamp_opt(source.sent_at, 0).single()?;
+    let day = DAY_RE.find(source.text).and_then(|found| {
+        let day = parse_relative_time(found.as_str(), sent_at).ok()?;
+        Some((day, found.as_str()))
+    });
+    let time = TIME_RE
+        .captures(source.text)
+        .and_then(|caps| Some((parse_time_captures(&caps)?, caps.get(0)?.as_str())));
+
+    let (start, end, all_day, matched) = match (day, time) {
+        (Some((day, day_text)), Some((time, time_text))) => {
+            let start = day + i64::from(time.num_seconds_from_midnight());
+            let end = start + duration.num_seconds();
+            (start, end, false, format!("{day_text} {time_text}"))
+        }
+        // "today" on its own is how people say when something happened.
+        (Some((_, day_text)), None) if day_text.eq_ignore_ascii_case("today") => return None,
+        (Some((day, day_text)), None) => (day, day + 86_400, true, day_text.to_string()),
+        (None, Some((time, time_text))) => {
+            let mut start = sent_at.date_naive().and_time(time).and_utc().timestamp();
+            if start <= source.sent_at {
+                start += 86_400;
+            }
+            let end = start + duration.num_seconds();
+            (start, end, false, time_text.to_string())
+        }
+        (None, None) => return None,
+    };
+
+    let first_line = source.text.lines().next().unwrap_or_default().trim();
+    Some(ChatEvent {
+        message_id: source.message_id,
+        start,
+        end,
+        all_day,
+        matched,
+        summary: truncate_preview(first_line),
+        description: format!(
+            "{}\n\n— {} in {}",
+            source.text.trim(),
+            source.sender_name,
+            source.chat_name
+        ),
+        url: inline_url(source.peer_type, source.peer_id, Some(source.message_id)),
+    })
+}
+
+fn parse_time_captures(caps: &regex::Captures<'_>) -> Option<NaiveTime> {
+    let number = |index: usize| caps.get(index).and_then(|m| m.as_str().parse::<u32>().ok());
+    if let Some(meridiem) = caps.get(3) {
+        let hour = number(1).filter(|hour| (1..=12).contains(hour))?;
+        let minute = number(2).unwrap_or(0);
+        let hour = match (meridiem.as_str().to_ascii_lowercase().as_str(), hour) {
+            ("am", 12) => 0,
+            ("am", hour) => hour,
+            ("pm", 12) => 12,
+            (_, hour) => hour + 12,
+        };
+        return NaiveTime::from_hms_opt(hour, minute, 0);
+    }
+    NaiveTime::from_hms_opt(number(4)?, number(5)?, 0)
+}
+
+/// An iCalendar (RFC 5545) file with one VEVENT per event.
+pub(crate) fn render_ics(events: &[ChatEvent], now: i64) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Inline//inline-cli//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    for event in events {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!(
+            "UID:{}-{}@inline.chat",
+            event.message_id,
+            event.url.as_deref().map(uid_suffix).unwrap_or_default()
+        ));
+        lines.push(format!("DTSTAMP:{}", ics_date_time(now)));
+        if event.all_day {
+            lines.push(format!("DTSTART;VALUE=DATE:{}", ics_date(event.start)));
+            lines.push(format!("DTEND;VALUE=DATE:{}", ics_date(event.end)));
+        } else {
+            lines.push(format!("DTSTART:{}", ics_date_time(event.start)));
+            lines.push(format!("DTEND:{}", ics_date_time(event.end)));
+        }
+        lines.push(format!("SUMMARY:{}", ics_text(&event.summary)));
+        let mut description = event.description.clone();
+        if let Some(url) = &event.url {
+            description.push_str(&format!("\n{url}"));
+            lines.push(format!("URL:{url}"));
+        }
+        lines.push(format!("DESCRIPTION:{}", ics_text(&description)));
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut output = String::new();
+    for line in lines {
+        output.push_str(&fold_ics_line(&line));
+        output.push_str("\r\n");
+    }
+    output
+}
+
+fn uid_suffix(url: &str) -> String {
+    url.trim_start_matches("inline://")
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .replace('/', "-")
+}
+
+fn ics_date(timestamp: i64) -> String {
+    DateTime::<Utc>::from_timestamp(timestamp, 0)
+        .unwrap_or_default()
+        .format("%Y%m%d")
+        .to_string()
+}
+
+fn ics_date_time(timestamp: i64) -> String {
+    DateTime::<Utc>::from_timestamp(timestamp, 0)
+        .unwrap_or_default()
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+fn ics_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Splits content lines longer than 75 octets, continuing with a space,
+/// without cutting through a UTF-8 character.
+fn fold_ics_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut width = 0;
+    for ch in line.chars() {
+        if width + ch.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(ch);
+        width += ch.len_utf8();
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(text: &str) -> EventSource<'_> {
+        EventSource {
+            peer_type: "chat",
+            peer_id: 7,
+            chat_name: "Design",
+            message_id: 456,
+            // Wednesday, January 28, 2026, 15:04:05 UTC
+            sent_at: 1_769_612_645,
+            sender_name: "Sam",
+            text,
+        }
+    }
+
+    #[test]
+    fn days_and_times_resolve_against_the_message_date() {
+        let hour = Duration::hours(1);
+        let event = extract_event(&source("Sync next friday at 3pm?"), hour).unwrap();
+        assert_eq!(ics_date_time(event.start), "20260130T150000Z");
+        assert_eq!(ics_date_time(event.end), "20260130T160000Z");
+        assert_eq!(event.matched, "next friday 3pm");
+        assert_eq!(event.url.as_deref(), Some("inline://chat/7?message=456"));
+
+        let event = extract_event(&source("Launch is 2026-02-10"), hour).unwrap();
+        assert!(event.all_day);
+        assert_eq!(ics_date(event.start), "20260210");
+        assert_eq!(ics_date(event.end), "20260211");
+
+        // Earlier than the message was sent, so the next day.
+        let event = extract_event(&source("call at 09:30"), hour).unwrap();
+        assert_eq!(ics_date_time(event.start), "20260129T093000Z");
+
+        assert!(extract_event(&source("bumped to v1.2, score 3:1"), hour).is_none());
+        assert!(extract_event(&source("fixed it today, sat on it all week"), hour).is_none());
+    }
+
+    #[test]
+    fn ics_escapes_text_and_folds_long_lines() {
+        let event = extract_event(
+            &source("Retro tomorrow; bring notes, please"),
+            Duration::hours(1),
+        )
+        .unwrap();
+        let ics = render_ics(&[event], 0);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.contains("UID:456-chat-7@inline.chat\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20260129\r\n"));
+        assert!(ics.contains("SUMMARY:Retro tomorrow\\; bring notes\\, please\r\n"));
+        assert!(ics.lines().all(|line| line.len() <= 75));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    }
+}
@@ -0,0 +1,287 @@
+//! Per-command flag defaults from the `[defaults]` config section.
+//!
+//! Keys are a command path followed by a flag name, e.g.
+//! `messages.list.limit = 50`, or just a flag (`json = true`) for every
+//! command. A default applies when its path is a prefix of the invoked
+//! command, the flag wasn't given on the command line or through its env var,
+//! and it doesn't conflict with a flag that was; the most specific path wins. Defaults are spliced into argv right
+//! after the subcommand, so clap validates them like typed flags.
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+
+use crate::config_file::ConfigValue;
+use crate::errors::CliError;
+
+/// Returns `argv` with applicable defaults inserted, or `None` when nothing applies.
+pub(crate) fn apply_flag_defaults(
+    command: &Command,
+    argv: &[OsString],
+    defaults: &BTreeMap<String, ConfigValue>,
+) -> Result<Option<Vec<OsString>>, CliError> {
+    if defaults.is_empty() {
+        return Ok(None);
+    }
+    let mut command = command.clone();
+    command.build();
+    let Ok(matches) = command.clone().try_get_matches_from(argv) else {
+        return Ok(None);
+    };
+
+    let mut path = Vec::new();
+    let mut leaf = &command;
+    let mut leaf_matches = &matches;
+    while let Some((name, sub_matches)) = leaf_matches.subcommand() {
+        let Some(sub) = leaf.find_subcommand(name) else {
+            break;
+        };
+        path.push(sub);
+        leaf = sub;
+        leaf_matches = sub_matches;
+    }
+    let names: Vec<&str> = path.iter().map(|sub| sub.get_name()).collect();
+
+    let mut applicable: Vec<(&[&str], &str, &ConfigValue)> = defaults
+        .iter()
+        .filter_map(|(key, value)| {
+            let segments: Vec<&str> = key.split('.').collect();
+            let (flag, scope) = segments.split_last()?;
+            let depth = scope.len();
+            (names.len() >= depth && names[..depth] == scope[..])
+                .then(|| (&names[..depth], *flag, value))
+        })
+        .collect();
+    // Most specific first, so `messages.list.limit` beats `messages.limit`.
+    applicable.sort_by_key(|(scope, _, _)| std::cmp::Reverse(scope.len()));
+
+    let explicit: Vec<&Arg> = leaf
+        .get_arguments()
+        .filter(|arg| is_explicit(leaf_matches, arg.get_id().as_str()))
+        .collect();
+    let mut inserted = Vec::new();
+    let mut seen = Vec::new();
+    for (scope, flag, value) in applicable {
+        let long = flag.replace('_', "-");
+        let Some(arg) = leaf
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(long.as_str()))
+        else {
+            if scope.len() == names.len() {
+                return Err(invalid_default(scope, flag, "no such flag"));
+            }
+            continue;
+        };
+        let id = arg.get_id().as_str();
+        if seen.contains(&id)
+            || is_explicit(leaf_matches, id)
+            || conflicts_with_explicit(leaf, arg, &explicit)
+        {
+            continue;
+        }
+        seen.push(id);
+        match (arg.get_action(), value) {
+            (ArgAction::SetTrue, ConfigValue::Boolean(true)) => {
+                inserted.push(OsString::from(format!("--{long}")));
+            }
+            (ArgAction::SetTrue, ConfigValue::Boolean(false)) => {}
+            (ArgAction::SetTrue, _) => {
+                return Err(invalid_default(scope, flag, "expected true or false"));
+            }
+            (ArgAction::Set | ArgAction::Append, value) => {
+                inserted.push(OsString::from(format!("--{long}")));
+                inserted.push(OsString::from(value.to_string()));
+            }
+            _ => return Err(invalid_default(scope, flag, "flag can't have a default")),
+        }
+    }
+    if inserted.is_empty() {
+        return Ok(None);
+    }
+
+    let at = subcommand_end(argv, &path);
+    let mut expanded = argv[..at].to_vec();
+    expanded.extend(inserted);
+    expanded.extend_from_slice(&argv[at..]);
+    Ok(Some(expanded))
+}
+
+fn is_explicit(matches: &ArgMatches, id: &str) -> bool {
+    matches!(
+        matches.value_source(id),
+        Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+    )
+}
+
+/// Whether `arg` can't be combined with a flag the user gave, either directly
+/// (`conflicts_with`, in either direction) or through a one-of `ArgGroup`.
+fn conflicts_with_explicit(command: &Command, arg: &Arg, explicit: &[&Arg]) -> bool {
+    let id = arg.get_id();
+    let conflicts = command.get_arg_conflicts_with(arg);
+    explicit.iter().any(|given| {
+        conflicts
+            .iter()
+            .any(|other| other.get_id() == given.get_id())
+            || command
+                .get_arg_conflicts_with(given)
+                .iter()
+                .any(|other| other.get_id() == id)
+            || command.get_groups().any(|group| {
+                !group.clone().is_multiple()
+                    && group.get_args().any(|member| member == id)
+                    && group.get_args().any(|member| member == given.get_id())
+            })
+    })
+}
+
+/// Index just past the last subcommand token, so inserted flags can't be
+/// taken as positional values.
+fn subcommand_end(argv: &[OsString], path: &[&Command]) -> usize {
+    let mut at = 1;
+    for sub in path {
+        let Some(offset) = argv[at..].iter().position(|token| {
+            token.to_str().is_some_and(|token| {
+                token == sub.get_name() || sub.get_all_aliases().any(|alias| alias == token)
+            })
+        }) else {
+            return argv.len();
+        };
+        at += offset + 1;
+    }
+    at.min(argv.len())
+}
+
+fn invalid_default(scope: &[&str], flag: &str, reason: &str) -> CliError {
+    let key = scope
+        .iter()
+        .copied()
+        .chain([flag])
+        .collect::<Vec<_>>()
+        .join(".");
+    let command = if scope.is_empty() {
+        "inline".to_string()
+    } else {
+        format!("inline {}", scope.join(" "))
+    };
+    CliError::invalid_default_flag(&key, &format!("{reason} for `{command}`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cli;
+    use clap::CommandFactory;
+
+    fn expand(args: &[&str], defaults: &[(&str, ConfigValue)]) -> Result<Vec<String>, CliError> {
+        let argv: Vec<OsString> = args.iter().map(OsString::from).collect();
+        let defaults = defaults
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect();
+        Ok(apply_flag_defaults(&Cli::command(), &argv, &defaults)?
+            .unwrap_or(argv)
+            .into_iter()
+            .map(|arg| arg.into_string().unwrap())
+            .collect())
+    }
+
+    #[test]
+    fn defaults_fill_missing_flags_beneath_explicit_ones() {
+        let defaults = [
+            ("messages.list.limit", ConfigValue::Integer(50)),
+            ("messages.limit", ConfigValue::Integer(10)),
+            ("json", ConfigValue::Boolean(true)),
+        ];
+
+        assert_eq!(
+            expand(&["inline", "messages", "list", "--chat-id", "1"], &defaults).unwrap(),
+            [
+                "inline",
+                "messages",
+                "list",
+                "--limit",
+                "50",
+                "--json",
+                "--chat-id",
+                "1"
+            ]
+        );
+        assert_eq!(
+            expand(
+                &["inline", "--json", "messages", "list", "--limit", "5"],
+                &defaults
+            )
+            .unwrap(),
+            ["inline", "--json", "messages", "list", "--limit", "5"]
+        );
+        // `messages send` has no --limit, so the broader default is skipped.
+        assert_eq!(
+            expand(&["inline", "messages", "send", "--text", "hi"], &defaults).unwrap(),
+            ["inline", "messages", "send", "--json", "--text", "hi"]
+        );
+        // Inserted after the subcommand, not after a positional.
+        assert_eq!(
+            expand(&["inline", "note", "hello"], &defaults).unwrap(),
+            ["inline", "note", "--json", "hello"]
+        );
+    }
+
+    #[test]
+    fn defaults_that_conflict_with_explicit_flags_are_skipped() {
+        let defaults = [
+            ("messages.list.chat_id", ConfigValue::Integer(5)),
+            ("messages.list.limit", ConfigValue::Integer(50)),
+        ];
+
+        assert_eq!(
+            expand(&["inline", "messages", "list", "--user-id", "3"], &defaults).unwrap(),
+            [
+                "inline",
+                "messages",
+                "list",
+                "--limit",
+                "50",
+                "--user-id",
+                "3"
+            ]
+        );
+        assert_eq!(
+            expand(&["inline", "messages", "list", "--to", "@sam"], &defaults).unwrap(),
+            [
+                "inline", "messages", "list", "--limit", "50", "--to", "@sam"
+            ]
+        );
+        assert_eq!(
+            expand(&["inline", "messages", "list"], &defaults).unwrap(),
+            [
+                "inline",
+                "messages",
+                "list",
+                "--chat-id",
+                "5",
+                "--limit",
+                "50"
+            ]
+        );
+        let argv = expand(&["inline", "messages", "list", "--to", "@sam"], &defaults).unwrap();
+        assert!(Cli::command().try_get_matches_from(argv).is_ok());
+    }
+
+    #[test]
+    fn unknown_or_mistyped_defaults_are_errors_for_their_command() {
+        let defaults = [("chats.list.sort", ConfigValue::String("activity".into()))];
+        let error = expand(&["inline", "chats", "list"], &defaults).unwrap_err();
+        assert_eq!(error.code, "invalid_config");
+        assert!(
+            error.message.contains("chats.list.sort"),
+            "{}",
+            error.message
+        );
+        assert!(expand(&["inline", "users", "list"], &defaults).is_ok());
+
+        let defaults = [("json", ConfigValue::String("yes".into()))];
+        assert!(expand(&["inline", "me"], &defaults).is_err());
+    }
+}
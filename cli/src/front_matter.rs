@@ -0,0 +1,287 @@
+//! Front matter for `messages send --from-file`: an optional block between
+//! `---` lines at the top of the file, followed by the message body.
+//!
+//! Only the YAML that metadata needs is understood: `key: value` lines with
+//! plain, `'single'`, or `"double"` quoted scalars, `[a, b]` flow lists, and
+//! `- item` block lists under an empty key. `#` starts a comment at the start
+//! of a line or after a space in a plain value. Anything else is an error
+//! rather than a guess.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Value {
+    Scalar(String),
+    List(Vec<String>),
+}
+
+impl Value {
+    /// The items of a list, or a scalar as a one-item list.
+    pub(crate) fn into_list(self) -> Vec<String> {
+        match self {
+            Value::Scalar(value) => vec![value],
+            Value::List(items) => items,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Document<'a> {
+    /// Fields in file order; keys are unique.
+    pub(crate) fields: Vec<(String, Value)>,
+    pub(crate) body: &'a str,
+}
+
+/// Splits `text` into its front matter and body. Text that doesn't start with
+/// a `---` line is all body.
+pub(crate) fn split(text: &str) -> Result<Document<'_>, String> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let Some(rest) = strip_delimiter(text, "---") else {
+        return Ok(Document {
+            fields: Vec::new(),
+            body: text,
+        });
+    };
+
+    let mut fields: Vec<(String, Value)> = Vec::new();
+    let mut remaining = rest;
+    let mut line_number = 1;
+    loop {
+        if remaining.is_empty() {
+            return Err("front matter starts with `---` but has no closing `---`".to_string());
+        }
+        let (line, next) = match remaining.split_once('\n') {
+            Some((line, next)) => (line, next),
+            None => (remaining, ""),
+        };
+        remaining = next;
+        line_number += 1;
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if matches!(line.trim_end(), "---" | "...") {
+            break;
+        }
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let error = |message: &str| format!("line {line_number}: {message}");
+
+        if let Some(item) = trimmed
+            .strip_prefix("- ")
+            .or((trimmed == "-").then_some(""))
+        {
+            let Some((_, Value::List(items))) = fields.last_mut() else {
+                return Err(error("list item without a `key:` line above it"));
+            };
+            items.push(parse_scalar(item).map_err(|message| error(&message))?);
+            continue;
+        }
+        if line.starts_with([' ', '\t']) {
+            return Err(error("unexpected indentation"));
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            return Err(error("expected `key: value`"));
+        };
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(error(&format!("invalid key {key:?}")));
+        }
+        if fields.iter().any(|(existing, _)| existing == key) {
+            return Err(error(&format!("`{key}` is set twice")));
+        }
+        let value = parse_value(value.trim()).map_err(|message| error(&message))?;
+        fields.push((key.to_string(), value));
+    }
+
+    Ok(Document {
+        fields,
+        body: remaining,
+    })
+}
+
+/// The text after a first line that is exactly `delimiter`.
+fn strip_delimiter<'a>(text: &'a str, delimiter: &str) -> Option<&'a str> {
+    let (line, rest) = match text.split_once('\n') {
+        Some((line, rest)) => (line, rest),
+        None => (text, ""),
+    };
+    (line.trim_end() == delimiter).then_some(rest)
+}
+
+fn parse_value(value: &str) -> Result<Value, String> {
+    // An empty value starts a block list; it stays empty if no items follow.
+    if value.is_empty() || value.starts_with('#') {
+        return Ok(Value::List(Vec::new()));
+    }
+    if let Some(inner) = value.strip_prefix('[') {
+        let inner = strip_comment(inner)
+            .strip_suffix(']')
+            .ok_or_else(|| "unclosed `[` list".to_string())?;
+        if inner.trim().is_empty() {
+            return Ok(Value::List(Vec::new()));
+        }
+        return split_flow_items(inner)?
+            .into_iter()
+            .map(|item| parse_scalar(item.trim()))
+            .collect::<Result<_, _>>()
+            .map(Value::List);
+    }
+    parse_scalar(value).map(Value::Scalar)
+}
+
+/// Splits `a, "b, c", d` on the commas outside quotes.
+fn split_flow_items(inner: &str) -> Result<Vec<&str>, String> {
+    let mut items = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    let mut escaped = false;
+    for (index, ch) in inner.char_indices() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if ch == '\\' => escaped = true,
+            Some(open) if ch == open => quote = None,
+            Some(_) => {}
+            None if ch == '"' || ch == '\'' => quote = Some(ch),
+            None if ch == ',' => {
+                items.push(&inner[start..index]);
+                start = index + 1;
+            }
+            None => {}
+        }
+    }
+    if quote.is_some() {
+        return Err("unclosed quote".to_string());
+    }
+    items.push(&inner[start..]);
+    Ok(items)
+}
+
+fn parse_scalar(value: &str) -> Result<String, String> {
+    if let Some(inner) = value.strip_prefix('"') {
+        let mut output = String::new();
+        let mut chars = inner.chars();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '"' => return ensure_only_comment(chars.as_str()).map(|()| output),
+                '\\' => match chars.next() {
+                    Some('n') => output.push('\n'),
+                    Some('t') => output.push('\t'),
+                    Some(ch @ ('"' | '\\')) => output.push(ch),
+                    Some(other) => return Err(format!("unknown escape `\\{other}`")),
+                    None => break,
+                },
+                ch => output.push(ch),
+            }
+        }
+        return Err("unclosed `\"`".to_string());
+    }
+    if let Some(inner) = value.strip_prefix('\'') {
+        let mut output = String::new();
+        let mut chars = inner.chars().peekable();
+        while let Some(ch) = chars.next() {
+            if ch != '\'' {
+                output.push(ch);
+            } else if chars.peek() == Some(&'\'') {
+                chars.next();
+                output.push('\'');
+            } else {
+                return ensure_only_comment(&chars.collect::<String>()).map(|()| output);
+            }
+        }
+        return Err("unclosed `'`".to_string());
+    }
+    Ok(strip_comment(value).to_string())
+}
+
+fn ensure_only_comment(rest: &str) -> Result<(), String> {
+    let rest = rest.trim();
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(())
+    } else {
+        Err(format!("unexpected `{rest}` after the closing quote"))
+    }
+}
+
+/// A plain value without a trailing ` # comment`.
+fn strip_comment(value: &str) -> &str {
+    match value.find(" #") {
+        Some(index) => value[..index].trim_end(),
+        None => value.trim_end(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_fields_from_the_body() {
+        let document = split(
+            "---\n\
+             to: \"@sam\" # the reviewer\n\
+             reply_to: 42\n\
+             mentions:\n\
+             \x20 - 7:@sam\n\
+             \x20 - '8:it''s'\n\
+             tags: [a, \"b, c\", 'd']\n\
+             ---\n\
+             Hello @sam\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            document.fields,
+            vec![
+                ("to".to_string(), Value::Scalar("@sam".to_string())),
+                ("reply_to".to_string(), Value::Scalar("42".to_string())),
+                (
+                    "mentions".to_string(),
+                    Value::List(vec!["7:@sam".to_string(), "8:it's".to_string()])
+                ),
+                (
+                    "tags".to_string(),
+                    Value::List(vec!["a".to_string(), "b, c".to_string(), "d".to_string()])
+                ),
+            ]
+        );
+        assert_eq!(document.body, "Hello @sam\n");
+    }
+
+    #[test]
+    fn text_without_front_matter_is_all_body() {
+        let document = split("Just text\n---\nmore").unwrap();
+        assert!(document.fields.is_empty());
+        assert_eq!(document.body, "Just text\n---\nmore");
+
+        let document = split("\u{feff}---\r\nchat: 7\r\n...\r\nbody").unwrap();
+        assert_eq!(
+            document.fields,
+            vec![("chat".to_string(), Value::Scalar("7".to_string()))]
+        );
+        assert_eq!(document.body, "body");
+    }
+
+    #[test]
+    fn rejects_what_it_does_not_understand() {
+        assert!(split("---\nchat: 7\n").unwrap_err().contains("closing"));
+        assert!(split("---\nchat 7\n---\n").unwrap_err().contains("line 2"));
+        assert!(
+            split("---\nchat: 7\nchat: 8\n---\n")
+                .unwrap_err()
+                .contains("set twice")
+        );
+        assert!(
+            split("---\n- 7\n---\n")
+                .unwrap_err()
+                .contains("without a `key:`")
+        );
+        assert!(
+            split("---\nto: \"@sam\n---\n")
+                .unwrap_err()
+                .contains("unclosed")
+        );
+        assert!(
+            split("---\nto: [a, b\n---\n")
+                .unwrap_err()
+                .contains("unclosed")
+        );
+    }
+}
@@ -0,0 +1,713 @@
+use std::collections::HashSet;
+
+use inline_protocol::proto;
+use inline_sdk::RealtimeSession;
+use serde::Serialize;
+
+use crate::peer_label_from_input;
+
+/// Messages requested per page when walking history towards a time boundary.
+const HISTORY_PAGE_SIZE: i32 = 100;
+/// Safety stop for very long windows (`--since "5y ago"` in a busy chat).
+const HISTORY_MAX_PAGES: usize = 200;
+
+/// Fetches chat history older than `offset_id`, restricted to `[since_ts, until_ts]`.
+///
+/// The server has no time filter, so with bounds set this pages backwards until
+/// it passes `since_ts` (or reaches the start of the chat) instead of filtering a
+/// single page. `limit` caps how many matching messages are kept. Without bounds
+/// it is a single `getChatHistory` call, as before.
+pub(crate) async fn fetch_history_window(
+    realtime: &RealtimeSession,
+    peer: &proto::InputPeer,
+    offset_id: Option<i64>,
+    limit: Option<i32>,
+    since_ts: Option<i64>,
+    until_ts: Option<i64>,
+) -> Result<Vec<proto::Message>, Box<dyn std::error::Error>> {
+    if since_ts.is_none() && until_ts.is_none() {
+        return fetch_history_page(realtime, peer, offset_id, limit).await;
+    }
+    let mut report = WalkReport::default();
+    let messages = collect_time_window(
+        async |cursor| fetch_history_page(realtime, peer, cursor, Some(HISTORY_PAGE_SIZE)).await,
+        async |after_id, limit| fetch_history_newer(realtime, peer, after_id, limit).await,
+        offset_id,
+        limit,
+        since_ts,
+        until_ts,
+        &mut report,
+    )
+    .await?;
+    report.log(peer);
+    Ok(messages)
+}
+
+/// Searches messages restricted to `[since_ts, until_ts]`, paging like
+/// [`fetch_history_window`].
+pub(crate) async fn search_messages_window(
+    realtime: &RealtimeSession,
+    peer: &proto::InputPeer,
+    queries: Vec<String>,
+    limit: Option<i32>,
+    since_ts: Option<i64>,
+    until_ts: Option<i64>,
+) -> Result<Vec<proto::Message>, Box<dyn std::error::Error>> {
+    let search_page = async |offset_id, limit| {
+        let input = proto::SearchMessagesInput {
+            peer_id: Some(peer.clone()),
+            queries: queries.clone(),
+            limit,
+            offset_id,
+            filter: None,
+        };
+        Ok::<_, Box<dyn std::error::Error>>(realtime.call(input).await?.messages)
+    };
+    if since_ts.is_none() && until_ts.is_none() {
+        return search_page(None, limit).await;
+    }
+    let mut report = WalkReport::default();
+    let messages = collect_time_window(
+        async |cursor| search_page(cursor, Some(HISTORY_PAGE_SIZE)).await,
+        no_gap_reads,
+        None,
+        limit,
+        since_ts,
+        until_ts,
+        &mut report,
+    )
+    .await?;
+    report.log(peer);
+    Ok(messages)
+}
+
+/// Lists messages matching a server-side media filter, paging older until
+/// `limit` messages are collected or the chat runs out.
+pub(crate) async fn search_media_window(
+    realtime: &RealtimeSession,
+    peer: &proto::InputPeer,
+    filter: proto::SearchMessagesFilter,
+    offset_id: Option<i64>,
+    limit: i32,
+    since_ts: Option<i64>,
+    until_ts: Option<i64>,
+) -> Result<Vec<proto::Message>, Box<dyn std::error::Error>> {
+    let mut report = WalkReport::default();
+    let messages = collect_time_window(
+        async |cursor| {
+            let input = proto::SearchMessagesInput {
+                peer_id: Some(peer.clone()),
+                queries: Vec::new(),
+                limit: Some(HISTORY_PAGE_SIZE),
+                offset_id: cursor,
+                filter: Some(filter as i32),
+            };
+            Ok(realtime.call(input).await?.messages)
+        },
+        no_gap_reads,
+        offset_id,
+        Some(limit),
+        since_ts,
+        until_ts,
+        &mut report,
+    )
+    .await?;
+    report.log(peer);
+    Ok(messages)
+}
+
+/// Fetches every message newer than `after_id` (the whole chat when `None`),
+/// oldest first. The flag is false when the walk hit the page cap before
+/// reaching `after_id` or the start of the chat.
+pub(crate) async fn fetch_history_after(
+    realtime: &RealtimeSession,
+    peer: &proto::InputPeer,
+    after_id: Option<i64>,
+) -> Result<(Vec<proto::Message>, bool), Box<dyn std::error::Error>> {
+    let mut report = WalkReport::default();
+    let outcome = collect_after_id(
+        async |cursor| fetch_history_page(realtime, peer, cursor, Some(HISTORY_PAGE_SIZE)).await,
+        async |after_id, limit| fetch_history_newer(realtime, peer, after_id, limit).await,
+        after_id,
+        &mut report,
+    )
+    .await?;
+    report.log(peer);
+    Ok(outcome)
+}
+
+/// Messages surrounding one search hit (or several hits whose windows overlap).
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HitContext {
+    pub(crate) hit_ids: Vec<i64>,
+    /// Oldest first, hits included.
+    pub(crate) messages: Vec<proto::Message>,
+}
+
+/// Fetches `context` messages before and after each hit with one
+/// `getChatHistory` around-call per hit, merging windows that overlap.
+pub(crate) async fn fetch_hit_contexts(
+    realtime: &RealtimeSession,
+    peer: &proto::InputPeer,
+    hits: &[proto::Message],
+    context: i32,
+) -> Result<Vec<HitContext>, Box<dyn std::error::Error>> {
+    let mut hit_ids: Vec<i64> = hits.iter().map(|message| message.id).collect();
+    hit_ids.sort_unstable();
+    hit_ids.dedup();
+    let mut windows = Vec::with_capacity(hit_ids.len());
+    for hit_id in hit_ids {
+        let input = proto::GetChatHistoryInput {
+            peer_id: Some(peer.clone()),
+            mode: Some(proto::GetChatHistoryMode::HistoryModeAround as i32),
+            anchor_id: Some(hit_id),
+            before_limit: Some(context),
+            after_limit: Some(context),
+            include_anchor: Some(true),
+            ..Default::default()
+        };
+        let mut messages = realtime.call(input).await?.messages;
+        // Keep the hit even if the server leaves out the anchor.
+        if let Some(hit) = hits.iter().find(|message| message.id == hit_id) {
+            messages.push(hit.clone());
+        }
+        windows.push((hit_id, messages));
+    }
+    Ok(merge_hit_windows(windows))
+}
+
+/// Joins per-hit windows (in hit order, oldest first) whose messages overlap
+/// or touch, so adjacent hits print as one group instead of repeating rows.
+fn merge_hit_windows(windows: Vec<(i64, Vec<proto::Message>)>) -> Vec<HitContext> {
+    let mut groups: Vec<HitContext> = Vec::new();
+    for (hit_id, messages) in windows {
+        let messages = sorted_oldest_first(messages);
+        let first_id = messages.first().map_or(hit_id, |message| message.id);
+        match groups.last_mut() {
+            Some(group)
+                if group
+                    .messages
+                    .last()
+                    .is_some_and(|message| message.id >= first_id) =>
+            {
+                group.hit_ids.push(hit_id);
+                group.messages.extend(messages);
+                group.messages = sorted_oldest_first(std::mem::take(&mut group.messages));
+            }
+            _ => groups.push(HitContext {
+                hit_ids: vec![hit_id],
+                messages,
+            }),
+        }
+    }
+    groups
+}
+
+async fn fetch_history_page(
+    realtime: &RealtimeSession,
+    peer: &proto::InputPeer,
+    offset_id: Option<i64>,
+    limit: Option<i32>,
+) -> Result<Vec<proto::Message>, Box<dyn std::error::Error>> {
+    let input = proto::GetChatHistoryInput {
+        peer_id: Some(peer.clone()),
+        offset_id,
+        limit,
+        ..Default::default()
+    };
+    Ok(realtime.call(input).await?.messages)
+}
+
+/// Messages with ids above `after_id`, oldest first, for re-reading a gap.
+async fn fetch_history_newer(
+    realtime: &RealtimeSession,
+    peer: &proto::InputPeer,
+    after_id: i64,
+    limit: i32,
+) -> Result<Option<Vec<proto::Message>>, Box<dyn std::error::Error>> {
+    let input = proto::GetChatHistoryInput {
+        peer_id: Some(peer.clone()),
+        mode: Some(proto::GetChatHistoryMode::HistoryModeNewer as i32),
+        after_id: Some(after_id),
+        limit: Some(limit),
+        ..Default::default()
+    };
+    Ok(Some(realtime.call(input).await?.messages))
+}
+
+/// For walks whose ids are sparse by nature (search results), where a jump
+/// between pages isn't a gap.
+async fn no_gap_reads(
+    _after_id: i64,
+    _limit: i32,
+) -> Result<Option<Vec<proto::Message>>, Box<dyn std::error::Error>> {
+    Ok(None)
+}
+
+/// What a paged walk repaired, and what it couldn't, for the log.
+#[derive(Debug, Default, PartialEq)]
+struct WalkReport {
+    /// Pages fetched again because they repeated messages or overran the cursor.
+    refetched: usize,
+    /// Messages found when re-reading an id gap between pages.
+    recovered: usize,
+    /// Id ranges between pages that couldn't be re-read.
+    gaps: Vec<(i64, i64)>,
+    /// Cursor the walk couldn't get past: the server only sent messages
+    /// already fetched.
+    stalled_at: Option<i64>,
+}
+
+impl WalkReport {
+    fn log(&self, peer: &proto::InputPeer) {
+        let label = peer_label_from_input(peer);
+        if self.refetched > 0 || self.recovered > 0 {
+            log::info!(
+                "history of {label}: fetched {} page(s) again that repeated or overran earlier pages, and recovered {} message(s) missing between pages.",
+                self.refetched,
+                self.recovered
+            );
+        }
+        if !self.gaps.is_empty() {
+            let ranges = self
+                .gaps
+                .iter()
+                .map(|(from, to)| format!("{from}-{to}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            log::warn!(
+                "history of {label} may be missing messages with ids {ranges}; they couldn't be fetched again."
+            );
+        }
+        if let Some(cursor) = self.stalled_at {
+            log::warn!(
+                "history of {label} stops short of messages older than id {cursor}: the server kept sending messages already fetched."
+            );
+        }
+    }
+}
+
+/// Fetches the page before `cursor` and keeps the messages that are older than
+/// the cursor and not seen before. Servers have been seen to repeat a page or
+/// overrun the cursor, so a page with anything else is fetched once more
+/// before the extra messages are dropped. An empty result ends the walk.
+async fn next_page(
+    fetch_page: &mut impl AsyncFnMut(
+        Option<i64>,
+    ) -> Result<Vec<proto::Message>, Box<dyn std::error::Error>>,
+    cursor: Option<i64>,
+    seen: &mut HashSet<i64>,
+    report: &mut WalkReport,
+) -> Result<Vec<proto::Message>, Box<dyn std::error::Error>> {
+    let is_new = |message: &proto::Message, seen: &HashSet<i64>| {
+        cursor.is_none_or(|cursor| message.id < cursor) && !seen.contains(&message.id)
+    };
+    let mut page = fetch_page(cursor).await?;
+    let mut page_ids = HashSet::new();
+    if !page
+        .iter()
+        .all(|message| is_new(message, seen) && page_ids.insert(message.id))
+    {
+        report.refetched += 1;
+        page = fetch_page(cursor).await?;
+    }
+    let fetched = page.len();
+    page.retain(|message| is_new(message, seen) && seen.insert(message.id));
+    if fetched > 0 && page.is_empty() {
+        report.stalled_at = cursor;
+    }
+    Ok(page)
+}
+
+/// Re-reads the ids strictly between `after_id` and `before_id`, which two
+/// consecutive pages skipped over. Ids are sequential within a chat, so
+/// nothing coming back means those messages were deleted.
+async fn fill_gap(
+    fetch_newer: &mut impl AsyncFnMut(
+        i64,
+        i32,
+    )
+        -> Result<Option<Vec<proto::Message>>, Box<dyn std::error::Error>>,
+    mut after_id: i64,
+    before_id: i64,
+    seen: &mut HashSet<i64>,
+    report: &mut WalkReport,
+) -> Vec<proto::Message> {
+    let mut recovered = Vec::new();
+    while before_id - after_id > 1 {
+        let limit = (before_id - after_id - 1).min(i64::from(HISTORY_PAGE_SIZE)) as i32;
+        let batch = match fetch_newer(after_id, limit).await {
+            Ok(Some(batch)) => batch,
+            Ok(None) => break,
+            Err(err) => {
+                log::debug!("re-reading ids {}-{}: {err}", after_id + 1, before_id - 1);
+                report.gaps.push((after_id + 1, before_id - 1));
+                break;
+            }
+        };
+        let full = batch.len() >= limit as usize;
+        let start = recovered.len();
+        recovered.extend(batch.into_iter().filter(|message| {
+            message.id > after_id && message.id < before_id && seen.insert(message.id)
+        }));
+        match recovered[start..].iter().map(|message| message.id).max() {
+            Some(newest) if full => after_id = newest,
+            _ => break,
+        }
+    }
+    report.recovered += recovered.len();
+    recovered
+}
+
+/// Walks pages of messages older than the cursor, keeping those inside the
+/// window. Stops at an empty page, once a page reaches past `since_ts`, or once
+/// `limit` messages are kept. Without `since_ts` the walk stops after one
+/// page's worth of matches so `--until` alone can't scan the whole chat.
+async fn collect_time_window(
+    mut fetch_page: impl AsyncFnMut(
+        Option<i64>,
+    ) -> Result<Vec<proto::Message>, Box<dyn std::error::Error>>,
+    mut fetch_newer: impl AsyncFnMut(
+        i64,
+        i32,
+    )
+        -> Result<Option<Vec<proto::Message>>, Box<dyn std::error::Error>>,
+    offset_id: Option<i64>,
+    limit: Option<i32>,
+    since_ts: Option<i64>,
+    until_ts: Option<i64>,
+    report: &mut WalkReport,
+) -> Result<Vec<proto::Message>, Box<dyn std::error::Error>> {
+    let keep = match (limit, since_ts) {
+        (Some(limit), _) => Some(limit.max(0) as usize),
+        (None, None) => Some(HISTORY_PAGE_SIZE as usize),
+        (None, Some(_)) => None,
+    };
+    let mut cursor = offset_id;
+    let mut kept = Vec::new();
+    let mut newest_first = None;
+    let mut seen = HashSet::new();
+    let mut previous_oldest = None;
+
+    for _ in 0..HISTORY_MAX_PAGES {
+        let mut page = next_page(&mut fetch_page, cursor, &mut seen, report).await?;
+        let Some(oldest_id) = page.iter().map(|message| message.id).min() else {
+            break;
+        };
+        if newest_first.is_none() && page.len() > 1 {
+            newest_first = Some(page[0].id > page[page.len() - 1].id);
+        }
+        if let (Some(previous_oldest), Some(newest_id)) =
+            (previous_oldest, page.iter().map(|message| message.id).max())
+        {
+            page.extend(
+                fill_gap(
+                    &mut fetch_newer,
+                    newest_id,
+                    previous_oldest,
+                    &mut seen,
+                    report,
+                )
+                .await,
+            );
+        }
+        previous_oldest = Some(oldest_id);
+        let reached_since =
+            since_ts.is_some_and(|since| page.iter().any(|message| message.date < since));
+        kept.extend(page.into_iter().filter(|message| {
+            since_ts.is_none_or(|since| message.date >= since)
+                && until_ts.is_none_or(|until| message.date <= until)
+        }));
+        if reached_since || keep.is_some_and(|keep| kept.len() >= keep) {
+            break;
+        }
+        cursor = Some(oldest_id);
+    }
+
+    // Pages arrive newest to oldest; merge them in the server's in-page order.
+    kept.sort_by_key(|message| message.id);
+    if newest_first != Some(false) {
+        kept.reverse();
+    }
+    if let Some(keep) = keep {
+        if newest_first == Some(false) {
+            kept.drain(..kept.len().saturating_sub(keep));
+        } else {
+            kept.truncate(keep);
+        }
+    }
+    Ok(kept)
+}
+
+async fn collect_after_id(
+    mut fetch_page: impl AsyncFnMut(
+        Option<i64>,
+    ) -> Result<Vec<proto::Message>, Box<dyn std::error::Error>>,
+    mut fetch_newer: impl AsyncFnMut(
+        i64,
+        i32,
+    )
+        -> Result<Option<Vec<proto::Message>>, Box<dyn std::error::Error>>,
+    after_id: Option<i64>,
+    report: &mut WalkReport,
+) -> Result<(Vec<proto::Message>, bool), Box<dyn std::error::Error>> {
+    let mut cursor = None;
+    let mut kept = Vec::new();
+    let mut seen = HashSet::new();
+    for _ in 0..HISTORY_MAX_PAGES {
+        let mut page = next_page(&mut fetch_page, cursor, &mut seen, report).await?;
+        let Some(oldest_id) = page.iter().map(|message| message.id).min() else {
+            return Ok((sorted_oldest_first(kept), report.stalled_at.is_none()));
+        };
+        if let (Some(cursor), Some(newest_id)) =
+            (cursor, page.iter().map(|message| message.id).max())
+        {
+            page.extend(fill_gap(&mut fetch_newer, newest_id, cursor, &mut seen, report).await);
+        }
+        kept.extend(
+            page.into_iter()
+                .filter(|message| after_id.is_none_or(|after_id| message.id > after_id)),
+        );
+        if after_id.is_some_and(|after_id| oldest_id <= after_id) {
+            return Ok((sorted_oldest_first(kept), true));
+        }
+        cursor = Some(oldest_id);
+    }
+    Ok((sorted_oldest_first(kept), false))
+}
+
+fn sorted_oldest_first(mut messages: Vec<proto::Message>) -> Vec<proto::Message> {
+    messages.sort_by_key(|message| message.id);
+    messages.dedup_by_key(|message| message.id);
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fake history of ids 1..=count where message `id` was sent at `id * 10`,
+    /// served newest first like `getChatHistory`.
+    fn pager(
+        count: i64,
+        page_size: usize,
+        calls: &std::cell::Cell<usize>,
+    ) -> impl AsyncFnMut(Option<i64>) -> Result<Vec<proto::Message>, Box<dyn std::error::Error>> + '_
+    {
+        async move |cursor: Option<i64>| {
+            calls.set(calls.get() + 1);
+            let before = cursor.unwrap_or(count + 1);
+            Ok((1..before)
+                .rev()
+                .take(page_size)
+                .map(|id| proto::Message {
+                    id,
+                    date: id * 10,
+                    ..Default::default()
+                })
+                .collect())
+        }
+    }
+
+    fn ids(messages: &[proto::Message]) -> Vec<i64> {
+        messages.iter().map(|message| message.id).collect()
+    }
+
+    #[test]
+    fn overlapping_hit_windows_merge_into_one_group() {
+        let window = |hit_id: i64, range: std::ops::RangeInclusive<i64>| {
+            let messages = range
+                .rev()
+                .map(|id| proto::Message {
+                    id,
+                    ..Default::default()
+                })
+                .collect();
+            (hit_id, messages)
+        };
+        let groups = merge_hit_windows(vec![
+            window(3, 1..=5),
+            window(6, 4..=8),
+            window(20, 18..=22),
+        ]);
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].hit_ids, vec![3, 6]);
+        assert_eq!(ids(&groups[0].messages), (1..=8).collect::<Vec<_>>());
+        assert_eq!(groups[1].hit_ids, vec![20]);
+        assert_eq!(ids(&groups[1].messages), (18..=22).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn since_pages_until_the_boundary() {
+        let calls = std::cell::Cell::new(0);
+        let messages = collect_time_window(
+            pager(50, 10, &calls),
+            no_gap_reads,
+            None,
+            None,
+            Some(215),
+            None,
+            &mut WalkReport::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(ids(&messages), (22..=50).rev().collect::<Vec<_>>());
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[tokio::test]
+    async fn limit_stops_paging_early() {
+        let calls = std::cell::Cell::new(0);
+        let messages = collect_time_window(
+            pager(50, 10, &calls),
+            no_gap_reads,
+            None,
+            Some(5),
+            Some(10),
+            Some(400),
+            &mut WalkReport::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(ids(&messages), vec![40, 39, 38, 37, 36]);
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn window_stops_at_the_start_of_the_chat() {
+        let calls = std::cell::Cell::new(0);
+        let messages = collect_time_window(
+            pager(25, 10, &calls),
+            no_gap_reads,
+            Some(20),
+            None,
+            Some(0),
+            None,
+            &mut WalkReport::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(ids(&messages), (1..20).rev().collect::<Vec<_>>());
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[tokio::test]
+    async fn after_id_pages_back_to_the_previous_high_water_mark() {
+        let calls = std::cell::Cell::new(0);
+        let (messages, complete) = collect_after_id(
+            pager(50, 10, &calls),
+            no_gap_reads,
+            Some(23),
+            &mut WalkReport::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(ids(&messages), (24..=50).collect::<Vec<_>>());
+        assert!(complete);
+        assert_eq!(calls.get(), 3);
+
+        let calls = std::cell::Cell::new(0);
+        let (messages, complete) = collect_after_id(
+            pager(15, 10, &calls),
+            no_gap_reads,
+            None,
+            &mut WalkReport::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(messages.len(), 15);
+        assert!(complete);
+    }
+
+    fn message(id: i64) -> proto::Message {
+        proto::Message {
+            id,
+            date: id * 10,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn repeated_and_overrunning_pages_are_fetched_again() {
+        // The first answer for each cursor below 30 repeats the page before it.
+        let calls = std::cell::Cell::new(0);
+        let mut answered = HashSet::new();
+        let quirky = async |cursor: Option<i64>| {
+            calls.set(calls.get() + 1);
+            let before = cursor.unwrap_or(31);
+            let ids = if before < 30 && answered.insert(before) {
+                (before..before + 10).rev().collect::<Vec<_>>()
+            } else {
+                (1..before).rev().take(10).collect()
+            };
+            Ok(ids.into_iter().map(message).collect())
+        };
+        let mut report = WalkReport::default();
+        let (messages, complete) = collect_after_id(quirky, no_gap_reads, None, &mut report)
+            .await
+            .unwrap();
+
+        assert_eq!(ids(&messages), (1..=30).collect::<Vec<_>>());
+        assert!(complete);
+        assert_eq!(report.refetched, 3);
+        assert_eq!(calls.get(), 7);
+    }
+
+    #[tokio::test]
+    async fn gaps_between_pages_are_read_again() {
+        // Older pages skip 16..=20; newer-than reads return them, except 18.
+        let older = async |cursor: Option<i64>| {
+            let before = cursor.unwrap_or(31);
+            Ok((1..before)
+                .rev()
+                .filter(|id| !(16..=20).contains(id))
+                .take(10)
+                .map(message)
+                .collect())
+        };
+        let newer = async |after_id: i64, limit: i32| {
+            Ok(Some(
+                (after_id + 1..)
+                    .filter(|id| *id != 18)
+                    .take(limit as usize)
+                    .map(message)
+                    .collect(),
+            ))
+        };
+        let mut report = WalkReport::default();
+        let messages = collect_time_window(older, newer, None, None, Some(0), None, &mut report)
+            .await
+            .unwrap();
+
+        let expected: Vec<i64> = (1..=30).rev().filter(|id| *id != 18).collect();
+        assert_eq!(ids(&messages), expected);
+        assert_eq!(report.recovered, 4);
+        assert!(report.gaps.is_empty());
+
+        let failing = async |_: i64, _: i32| Err("unsupported".into());
+        let mut report = WalkReport::default();
+        collect_time_window(older, failing, None, None, Some(0), None, &mut report)
+            .await
+            .unwrap();
+        assert_eq!(report.gaps, vec![(16, 20)]);
+    }
+
+    #[tokio::test]
+    async fn a_server_that_only_repeats_itself_stalls_the_walk() {
+        let stuck = async |_: Option<i64>| Ok((21..=30).rev().map(message).collect());
+        let mut report = WalkReport::default();
+        let (messages, complete) = collect_after_id(stuck, no_gap_reads, None, &mut report)
+            .await
+            .unwrap();
+
+        assert_eq!(ids(&messages), (21..=30).collect::<Vec<_>>());
+        assert!(!complete);
+        assert_eq!(report.stalled_at, Some(21));
+    }
+}
@@ -0,0 +1,112 @@
+//! `messages send --idempotency-key`, for scripts that may be re-run after a
+//! failure.
+//!
+//! A key and a destination always derive the same `random_id`, so a retry
+//! of a send whose reply was lost goes out as the same client message. Sends
+//! that were confirmed are recorded in local state, and re-running with the
+//! same key skips them. Records are dropped after 30 days.
+
+use inline_protocol::proto;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::state::LocalState;
+
+const RETENTION_SECS: i64 = 30 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdempotentSend {
+    pub key: String,
+    /// `chat:ID`, `user:ID`, or `self`.
+    pub peer: String,
+    pub message_ids: Vec<i64>,
+    /// Unix seconds when the send was confirmed.
+    pub sent_at: i64,
+}
+
+pub(crate) fn peer_key(peer: &proto::InputPeer) -> String {
+    match &peer.r#type {
+        Some(proto::input_peer::Type::Chat(chat)) => format!("chat:{}", chat.chat_id),
+        Some(proto::input_peer::Type::User(user)) => format!("user:{}", user.user_id),
+        Some(proto::input_peer::Type::Self_(_)) | None => "self".to_string(),
+    }
+}
+
+/// `random_id` for message `part` (one per attachment) of a keyed send.
+pub(crate) fn random_id(key: &str, peer: &proto::InputPeer, part: usize) -> i64 {
+    let digest = Sha256::new()
+        .chain_update(key.as_bytes())
+        .chain_update([0])
+        .chain_update(peer_key(peer).as_bytes())
+        .chain_update([0])
+        .chain_update(part.to_le_bytes())
+        .finalize();
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    i64::from_le_bytes(bytes)
+}
+
+/// The earlier confirmed send with this key to this destination.
+pub(crate) fn find<'a>(
+    state: &'a LocalState,
+    key: &str,
+    peer: &proto::InputPeer,
+) -> Option<&'a IdempotentSend> {
+    let peer = peer_key(peer);
+    state
+        .idempotent_sends
+        .iter()
+        .find(|send| send.key == key && send.peer == peer)
+}
+
+pub(crate) fn record(
+    state: &mut LocalState,
+    key: &str,
+    peer: &proto::InputPeer,
+    message_ids: Vec<i64>,
+    now: i64,
+) {
+    state
+        .idempotent_sends
+        .retain(|send| now - send.sent_at < RETENTION_SECS);
+    state.idempotent_sends.push(IdempotentSend {
+        key: key.to_string(),
+        peer: peer_key(peer),
+        message_ids,
+        sent_at: now,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peer::{input_chat_peer, input_user_peer};
+
+    #[test]
+    fn keys_derive_stable_ids_and_are_remembered_per_destination() {
+        let chat = input_chat_peer(7);
+        assert_eq!(
+            random_id("deploy-42", &chat, 0),
+            random_id("deploy-42", &chat, 0)
+        );
+        assert_ne!(
+            random_id("deploy-42", &chat, 0),
+            random_id("deploy-42", &chat, 1)
+        );
+        assert_ne!(
+            random_id("deploy-42", &chat, 0),
+            random_id("deploy-42", &input_user_peer(7), 0)
+        );
+
+        let mut state = LocalState::default();
+        record(&mut state, "old", &chat, vec![1], 0);
+        record(&mut state, "deploy-42", &chat, vec![90, 91], RETENTION_SECS);
+        assert_eq!(state.idempotent_sends.len(), 1, "the old record expired");
+        assert_eq!(
+            find(&state, "deploy-42", &chat).unwrap().message_ids,
+            [90, 91]
+        );
+        assert!(find(&state, "deploy-42", &input_chat_peer(8)).is_none());
+    }
+}
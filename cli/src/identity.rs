@@ -1,9 +1,37 @@
-use inline_sdk::{AuthMetadata, ClientIdentity, RealtimeClient, RealtimeError, client_info};
+use std::io;
+use std::sync::OnceLock;
+
+use inline_sdk::{
+    AuthMetadata, ClientIdentity, ProxyConfig, RealtimeClient, RealtimeClientBuilder,
+    RealtimeError, RealtimeSession, TlsConfig, client_info,
+};
 
 pub const CLIENT_TYPE: &str = "cli";
 pub const CLIENT_TYPE_HEADER: &str = client_info::CLIENT_TYPE_HEADER;
 pub const CLIENT_VERSION_HEADER: &str = client_info::CLIENT_VERSION_HEADER;
 
+static PROXY: OnceLock<Option<ProxyConfig>> = OnceLock::new();
+
+/// Sets the proxy every HTTP and realtime connection goes through.
+pub fn set_proxy(proxy: Option<ProxyConfig>) {
+    let _ = PROXY.set(proxy);
+}
+
+pub fn proxy() -> Option<&'static ProxyConfig> {
+    PROXY.get().and_then(Option::as_ref)
+}
+
+static TLS: OnceLock<Option<TlsConfig>> = OnceLock::new();
+
+/// Sets the CA bundle and certificate pins every connection is checked against.
+pub fn set_tls(tls: Option<TlsConfig>) {
+    let _ = TLS.set(tls);
+}
+
+pub fn tls() -> Option<&'static TlsConfig> {
+    TLS.get().and_then(Option::as_ref)
+}
+
 pub fn client_identity() -> ClientIdentity {
     ClientIdentity::new(CLIENT_TYPE, env!("CARGO_PKG_VERSION"))
 }
@@ -32,17 +60,33 @@ pub fn device_name() -> Option<String> {
     client_info::device_name()
 }
 
-pub fn http_client_builder() -> reqwest::ClientBuilder {
-    client_info::http_client_builder_for(&client_identity())
+pub fn http_client_builder() -> io::Result<reqwest::ClientBuilder> {
+    let mut builder = client_info::http_client_builder_for(&client_identity());
+    if let Some(proxy) = proxy() {
+        builder = builder.proxy(proxy.reqwest_proxy()?);
+    }
+    if let Some(tls) = tls() {
+        builder = builder.use_preconfigured_tls((*tls.rustls_client_config()).clone());
+    }
+    Ok(builder)
 }
 
 pub fn current_os_version() -> Option<String> {
     client_info::current_os_version()
 }
 
-pub async fn connect_realtime(url: &str, token: &str) -> Result<RealtimeClient, RealtimeError> {
-    RealtimeClient::builder(url, token)
-        .identity(client_identity())
-        .connect()
-        .await
+/// A realtime client builder with the CLI identity, proxy, and TLS settings.
+pub fn realtime_client_builder(url: &str, token: &str) -> RealtimeClientBuilder {
+    let mut builder = RealtimeClient::builder(url, token).identity(client_identity());
+    if let Some(proxy) = proxy() {
+        builder = builder.proxy(proxy.clone());
+    }
+    if let Some(tls) = tls() {
+        builder = builder.tls(tls.clone());
+    }
+    builder
+}
+
+pub async fn connect_realtime(url: &str, token: &str) -> Result<RealtimeSession, RealtimeError> {
+    realtime_client_builder(url, token).connect_session().await
 }
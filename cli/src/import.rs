@@ -0,0 +1,227 @@
+//! Shared pipeline for `inline import`.
+//!
+//! Source parsers (see [`slack`] and [`telegram`]) turn an export into [`ImportThread`]s; the
+//! command replays them as messages and records what it sent in an
+//! [`ImportMapping`] file, which doubles as the resume point for a rerun.
+
+pub(crate) mod slack;
+pub(crate) mod telegram;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use inline_protocol::proto;
+use serde::{Deserialize, Serialize};
+
+/// One chat to create (or reuse) and fill.
+#[derive(Debug)]
+pub(crate) struct ImportThread {
+    /// Stable id in the source export, used as the mapping key.
+    pub(crate) source_id: String,
+    pub(crate) title: String,
+    pub(crate) is_public: bool,
+    /// Inline users to add to a private thread.
+    pub(crate) participant_ids: Vec<i64>,
+    /// Oldest first.
+    pub(crate) messages: Vec<ImportMessage>,
+}
+
+#[derive(Debug)]
+pub(crate) struct ImportMessage {
+    pub(crate) source_id: String,
+    /// Author name as the source knew it.
+    pub(crate) author: String,
+    /// The author's Inline account, when the user map names one.
+    pub(crate) author_user_id: Option<i64>,
+    pub(crate) date: i64,
+    pub(crate) text: String,
+    /// Source id of the message this replies to.
+    pub(crate) reply_to: Option<String>,
+    /// Local file to upload with the message.
+    pub(crate) attachment: Option<PathBuf>,
+}
+
+/// Source ids mapped to what the import created. Written after every thread so
+/// an interrupted import can be rerun without duplicating messages.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportMapping {
+    pub(crate) source: String,
+    pub(crate) threads: BTreeMap<String, ThreadMapping>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ThreadMapping {
+    pub(crate) title: String,
+    pub(crate) chat_id: i64,
+    pub(crate) messages: BTreeMap<String, i64>,
+}
+
+impl ImportMapping {
+    pub(crate) fn load(path: &Path, source: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mapping = match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str::<Self>(&contents)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(err) => return Err(err.into()),
+        };
+        if !mapping.source.is_empty() && mapping.source != source {
+            return Err(format!(
+                "{} is a {} import mapping, not {source}",
+                path.display(),
+                mapping.source
+            )
+            .into());
+        }
+        Ok(Self {
+            source: source.to_string(),
+            ..mapping
+        })
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let payload = serde_json::to_string_pretty(self)?;
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, payload)?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+}
+
+/// Parses a `source,inline_user_id` CSV. A header row and blank lines are
+/// skipped; keys are lowercased so emails and usernames match loosely.
+pub(crate) fn parse_user_map(contents: &str) -> Result<HashMap<String, i64>, String> {
+    let mut map = HashMap::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(',') else {
+            return Err(format!(
+                "line {}: expected `source,inline_user_id`",
+                index + 1
+            ));
+        };
+        let key = key.trim().trim_matches('"').to_lowercase();
+        let value = value.trim().trim_matches('"');
+        match value.parse::<i64>() {
+            Ok(user_id) if user_id > 0 => {
+                map.insert(key, user_id);
+            }
+            _ if index == 0 => continue,
+            _ => {
+                return Err(format!(
+                    "line {}: `{value}` is not a positive Inline user id",
+                    index + 1
+                ));
+            }
+        }
+    }
+    Ok(map)
+}
+
+/// Message text as sent: the original author and time on the first line, since
+/// imported messages are posted by the importing account at import time.
+pub(crate) fn import_message_text(message: &ImportMessage, author: &str) -> String {
+    let date = DateTime::<Utc>::from_timestamp(message.date, 0)
+        .map(|date| date.format("%b %-d, %Y %H:%M UTC").to_string())
+        .unwrap_or_else(|| message.date.to_string());
+    let text = message.text.trim();
+    if text.is_empty() {
+        format!("**{author}** · {date}")
+    } else {
+        format!("**{author}** · {date}\n{text}")
+    }
+}
+
+/// Finds the id the server assigned to a message sent with `random_id`.
+pub(crate) fn sent_message_id(result: &proto::SendMessageResult, random_id: i64) -> Option<i64> {
+    result
+        .updates
+        .iter()
+        .find_map(|update| match update.update.as_ref() {
+            Some(proto::update::Update::UpdateMessageId(update))
+                if update.random_id == random_id =>
+            {
+                Some(update.message_id)
+            }
+            _ => None,
+        })
+        .or_else(|| {
+            result
+                .updates
+                .iter()
+                .find_map(|update| match update.update.as_ref() {
+                    Some(proto::update::Update::NewMessage(update)) => {
+                        update.message.as_ref().map(|message| message.id)
+                    }
+                    _ => None,
+                })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_map_skips_header_and_lowercases_keys() {
+        let map = parse_user_map("slack,inline\nU024BE7LH,42\n\nAlice@Example.com, 7\n").unwrap();
+
+        assert_eq!(map.get("u024be7lh"), Some(&42));
+        assert_eq!(map.get("alice@example.com"), Some(&7));
+        assert_eq!(map.len(), 2);
+        assert!(parse_user_map("U1,42\nU2,nope").is_err());
+    }
+
+    #[test]
+    fn import_text_credits_the_original_author() {
+        let message = ImportMessage {
+            source_id: "1".to_string(),
+            author: "alice".to_string(),
+            author_user_id: None,
+            date: 1_672_923_600,
+            text: "  shipped it ".to_string(),
+            reply_to: None,
+            attachment: None,
+        };
+
+        assert_eq!(
+            import_message_text(&message, "Alice"),
+            "**Alice** · Jan 5, 2023 13:00 UTC\nshipped it"
+        );
+    }
+
+    #[test]
+    fn sent_message_id_prefers_the_matching_random_id() {
+        let result = proto::SendMessageResult {
+            updates: vec![
+                proto::Update {
+                    update: Some(proto::update::Update::UpdateMessageId(
+                        proto::UpdateMessageId {
+                            message_id: 5,
+                            random_id: 1,
+                        },
+                    )),
+                    ..Default::default()
+                },
+                proto::Update {
+                    update: Some(proto::update::Update::UpdateMessageId(
+                        proto::UpdateMessageId {
+                            message_id: 6,
+                            random_id: 2,
+                        },
+                    )),
+                    ..Default::default()
+                },
+            ],
+        };
+
+        assert_eq!(sent_message_id(&result, 2), Some(6));
+        assert_eq!(sent_message_id(&result, 3), None);
+    }
+}
@@ -0,0 +1,421 @@
+//! Slack export archives (Workspace settings → Import/Export → Export).
+//!
+//! An export is a zip (or the extracted folder) with `users.json`,
+//! `channels.json`, optionally `groups.json` for private channels, and one
+//! folder per channel holding a JSON array of messages per day.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use regex::{Captures, Regex};
+use serde::Deserialize;
+
+use super::{ImportMessage, ImportThread};
+
+/// Message subtypes that carry conversation content. Joins, topic changes,
+/// pins and the like are skipped.
+const CONTENT_SUBTYPES: &[&str] = &[
+    "bot_message",
+    "file_share",
+    "me_message",
+    "thread_broadcast",
+];
+
+#[derive(Deserialize)]
+struct SlackUser {
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    real_name: Option<String>,
+    #[serde(default)]
+    profile: SlackProfile,
+}
+
+#[derive(Default, Deserialize)]
+struct SlackProfile {
+    #[serde(default)]
+    display_name: Option<String>,
+    #[serde(default)]
+    real_name: Option<String>,
+    #[serde(default)]
+    email: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SlackChannel {
+    id: String,
+    name: String,
+    #[serde(default)]
+    members: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct SlackMessage {
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    subtype: Option<String>,
+    #[serde(default)]
+    user: Option<String>,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    bot_profile: Option<SlackBotProfile>,
+    #[serde(default)]
+    text: String,
+    ts: String,
+    #[serde(default)]
+    thread_ts: Option<String>,
+    #[serde(default)]
+    files: Vec<SlackFile>,
+}
+
+#[derive(Deserialize)]
+struct SlackBotProfile {
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SlackFile {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    title: Option<String>,
+}
+
+/// Reads every `.json` file of an export, keyed by its path relative to the
+/// export root (the folder holding `channels.json`).
+pub(crate) fn read_archive(
+    path: &Path,
+) -> Result<BTreeMap<String, Vec<u8>>, Box<dyn std::error::Error>> {
+    let mut files = BTreeMap::new();
+    if path.is_dir() {
+        for entry in walkdir::WalkDir::new(path) {
+            let entry = entry?;
+            if !entry.file_type().is_file() || !is_json(entry.path()) {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(path)?;
+            let key = relative
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.insert(key, fs::read(entry.path())?);
+        }
+    } else {
+        let mut archive = zip::ZipArchive::new(fs::File::open(path)?)?;
+        for index in 0..archive.len() {
+            let mut file = archive.by_index(index)?;
+            if !file.is_file() || !file.name().ends_with(".json") {
+                continue;
+            }
+            let name = file.name().to_string();
+            let mut bytes = Vec::with_capacity(file.size() as usize);
+            file.read_to_end(&mut bytes)?;
+            files.insert(name, bytes);
+        }
+    }
+
+    // Exports zipped from Finder nest everything under one folder.
+    let root = files
+        .keys()
+        .filter_map(|key| key.strip_suffix("channels.json"))
+        .filter(|prefix| prefix.is_empty() || prefix.ends_with('/'))
+        .min_by_key(|prefix| prefix.len())
+        .map(str::to_string)
+        .ok_or("not a Slack export: channels.json is missing")?;
+    Ok(files
+        .into_iter()
+        .filter_map(|(key, bytes)| key.strip_prefix(&root).map(|key| (key.to_string(), bytes)))
+        .collect())
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "json")
+}
+
+/// Converts an export into threads, oldest message first. `user_map` keys are
+/// Slack user ids, emails, or usernames (lowercased); `channels` limits the
+/// import to those channel names when non-empty.
+pub(crate) fn parse_archive(
+    files: &BTreeMap<String, Vec<u8>>,
+    user_map: &HashMap<String, i64>,
+    channels: &[String],
+) -> Result<Vec<ImportThread>, String> {
+    let users = match files.get("users.json") {
+        Some(bytes) => parse_json::<Vec<SlackUser>>("users.json", bytes)?,
+        None => Vec::new(),
+    };
+    let names = users
+        .iter()
+        .map(|user| (user.id.clone(), slack_user_name(user)))
+        .collect::<HashMap<_, _>>();
+    let inline_ids = users
+        .iter()
+        .filter_map(|user| {
+            let keys = [
+                Some(user.id.as_str()),
+                user.profile.email.as_deref(),
+                Some(user.name.as_str()),
+            ];
+            keys.into_iter()
+                .flatten()
+                .find_map(|key| user_map.get(&key.to_lowercase()).copied())
+                .map(|inline_id| (user.id.clone(), inline_id))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut all_channels = Vec::new();
+    for (file, is_public) in [("channels.json", true), ("groups.json", false)] {
+        if let Some(bytes) = files.get(file) {
+            for channel in parse_json::<Vec<SlackChannel>>(file, bytes)? {
+                all_channels.push((channel, is_public));
+            }
+        }
+    }
+    if let Some(missing) = channels.iter().find(|name| {
+        !all_channels.iter().any(|(channel, _)| {
+            channel
+                .name
+                .eq_ignore_ascii_case(name.trim_start_matches('#'))
+        })
+    }) {
+        return Err(format!("channel `{missing}` is not in this export"));
+    }
+
+    let mut threads = Vec::new();
+    for (channel, is_public) in all_channels {
+        if !channels.is_empty()
+            && !channels.iter().any(|name| {
+                channel
+                    .name
+                    .eq_ignore_ascii_case(name.trim_start_matches('#'))
+            })
+        {
+            continue;
+        }
+        let prefix = format!("{}/", channel.name);
+        let mut raw_messages = Vec::new();
+        for (key, bytes) in files.range(prefix.clone()..) {
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            raw_messages.extend(parse_json::<Vec<SlackMessage>>(key, bytes)?);
+        }
+        raw_messages.sort_by(|left, right| ts_order(&left.ts).total_cmp(&ts_order(&right.ts)));
+
+        let messages = raw_messages
+            .into_iter()
+            .filter(is_content_message)
+            .map(|message| {
+                let author = message
+                    .user
+                    .as_ref()
+                    .and_then(|user| names.get(user).cloned())
+                    .or_else(|| message.username.clone())
+                    .or_else(|| {
+                        message
+                            .bot_profile
+                            .as_ref()
+                            .and_then(|bot| bot.name.clone())
+                    })
+                    .unwrap_or_else(|| "Unknown".to_string());
+                let mut text = convert_mrkdwn(&message.text, &names);
+                for file in &message.files {
+                    if let Some(name) = file.name.as_deref().or(file.title.as_deref()) {
+                        text.push_str(&format!("\n📎 {name}"));
+                    }
+                }
+                let reply_to = message
+                    .thread_ts
+                    .clone()
+                    .filter(|thread_ts| *thread_ts != message.ts);
+                ImportMessage {
+                    author_user_id: message
+                        .user
+                        .as_ref()
+                        .and_then(|user| inline_ids.get(user).copied()),
+                    author,
+                    date: ts_order(&message.ts) as i64,
+                    text,
+                    reply_to,
+                    source_id: message.ts,
+                    attachment: None,
+                }
+            })
+            .collect();
+        let participant_ids = if is_public {
+            Vec::new()
+        } else {
+            channel
+                .members
+                .iter()
+                .filter_map(|member| inline_ids.get(member).copied())
+                .collect()
+        };
+        threads.push(ImportThread {
+            source_id: channel.id,
+            title: format!("#{}", channel.name),
+            is_public,
+            participant_ids,
+            messages,
+        });
+    }
+    Ok(threads)
+}
+
+fn parse_json<T: serde::de::DeserializeOwned>(file: &str, bytes: &[u8]) -> Result<T, String> {
+    serde_json::from_slice(bytes).map_err(|err| format!("{file}: {err}"))
+}
+
+fn slack_user_name(user: &SlackUser) -> String {
+    [
+        user.profile.display_name.as_deref(),
+        user.profile.real_name.as_deref(),
+        user.real_name.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .map(str::trim)
+    .find(|name| !name.is_empty())
+    .unwrap_or(&user.name)
+    .to_string()
+}
+
+fn is_content_message(message: &SlackMessage) -> bool {
+    message.kind == "message"
+        && message
+            .subtype
+            .as_deref()
+            .is_none_or(|subtype| CONTENT_SUBTYPES.contains(&subtype))
+}
+
+/// Slack timestamps are `seconds.micros` strings and double as message ids.
+fn ts_order(ts: &str) -> f64 {
+    ts.parse().unwrap_or_default()
+}
+
+/// Rewrites Slack's `<...>` markup (mentions, channel links, labelled URLs) as
+/// plain markdown and undoes its HTML escaping.
+fn convert_mrkdwn(text: &str, names: &HashMap<String, String>) -> String {
+    let markup = Regex::new(r"<([^<>]+)>").expect("valid slack markup regex");
+    let converted = markup.replace_all(text, |captures: &Captures| {
+        let inner = &captures[1];
+        let (target, label) = match inner.split_once('|') {
+            Some((target, label)) => (target, Some(label)),
+            None => (inner, None),
+        };
+        if let Some(user_id) = target.strip_prefix('@') {
+            let name = label
+                .map(str::to_string)
+                .or_else(|| names.get(user_id).cloned())
+                .unwrap_or_else(|| user_id.to_string());
+            format!("@{name}")
+        } else if let Some(channel_id) = target.strip_prefix('#') {
+            format!("#{}", label.unwrap_or(channel_id))
+        } else if let Some(special) = target.strip_prefix('!') {
+            format!(
+                "@{}",
+                label.unwrap_or(special.split('^').next().unwrap_or(special))
+            )
+        } else {
+            match label {
+                Some(label) => format!("[{label}]({target})"),
+                None => target.to_string(),
+            }
+        }
+    });
+    converted
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export() -> BTreeMap<String, Vec<u8>> {
+        let mut files = BTreeMap::new();
+        files.insert(
+            "users.json".to_string(),
+            br#"[
+                {"id": "U1", "name": "alice", "profile": {"display_name": "Alice", "email": "alice@example.com"}},
+                {"id": "U2", "name": "bob", "real_name": "Bob B", "profile": {}}
+            ]"#
+            .to_vec(),
+        );
+        files.insert(
+            "channels.json".to_string(),
+            br#"[{"id": "C1", "name": "general", "members": ["U1", "U2"]}]"#.to_vec(),
+        );
+        files.insert(
+            "groups.json".to_string(),
+            br#"[{"id": "G1", "name": "secret", "members": ["U1", "U2"]}]"#.to_vec(),
+        );
+        files.insert(
+            "general/2023-01-06.json".to_string(),
+            br#"[
+                {"type": "message", "user": "U2", "text": "thanks <@U1>!", "ts": "1672992000.000200", "thread_ts": "1672923600.000100"}
+            ]"#
+            .to_vec(),
+        );
+        files.insert(
+            "general/2023-01-05.json".to_string(),
+            br#"[
+                {"type": "message", "subtype": "channel_join", "user": "U2", "text": "<@U2> has joined", "ts": "1672900000.000001"},
+                {"type": "message", "user": "U1", "text": "see <https://inline.chat|the site> &amp; <#C1|general>", "ts": "1672923600.000100", "thread_ts": "1672923600.000100",
+                 "files": [{"name": "plan.pdf"}]}
+            ]"#
+            .to_vec(),
+        );
+        files
+    }
+
+    #[test]
+    fn parses_channels_messages_and_threads() {
+        let user_map = HashMap::from([("alice@example.com".to_string(), 42)]);
+        let threads = parse_archive(&export(), &user_map, &[]).unwrap();
+
+        assert_eq!(threads.len(), 2);
+        let general = &threads[0];
+        assert_eq!(general.title, "#general");
+        assert!(general.is_public);
+        assert!(general.participant_ids.is_empty());
+        assert_eq!(general.messages.len(), 2);
+
+        let first = &general.messages[0];
+        assert_eq!(first.author, "Alice");
+        assert_eq!(first.author_user_id, Some(42));
+        assert_eq!(first.date, 1_672_923_600);
+        assert_eq!(
+            first.text,
+            "see [the site](https://inline.chat) & #general\n📎 plan.pdf"
+        );
+        assert_eq!(first.reply_to, None);
+
+        let reply = &general.messages[1];
+        assert_eq!(reply.author, "Bob B");
+        assert_eq!(reply.text, "thanks @Alice!");
+        assert_eq!(reply.reply_to.as_deref(), Some("1672923600.000100"));
+
+        let secret = &threads[1];
+        assert!(!secret.is_public);
+        assert_eq!(secret.participant_ids, vec![42]);
+    }
+
+    #[test]
+    fn channel_filter_rejects_unknown_names() {
+        let threads = parse_archive(&export(), &HashMap::new(), &["#general".to_string()]).unwrap();
+        assert_eq!(threads.len(), 1);
+
+        let err = parse_archive(&export(), &HashMap::new(), &["random".to_string()]).unwrap_err();
+        assert!(err.contains("random"), "{err}");
+    }
+}
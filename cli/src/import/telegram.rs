@@ -0,0 +1,296 @@
+//! Telegram Desktop JSON exports (Settings → Advanced → Export Telegram data,
+//! format "Machine-readable JSON").
+//!
+//! A single-chat export is one chat object with a `messages` array; a full
+//! account export nests chats under `chats.list`. Media paths are relative to
+//! the folder holding `result.json`.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{ImportMessage, ImportThread};
+
+/// Placeholder Telegram writes instead of a path when media was not exported.
+const MISSING_FILE_PREFIX: &str = "(File ";
+
+/// Either a single chat or a full account export; untagged, so the chat
+/// shape (with its required `messages`) is tried first.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TelegramExport {
+    Chat(TelegramChat),
+    Account { chats: TelegramChatList },
+}
+
+#[derive(Deserialize)]
+struct TelegramChatList {
+    #[serde(default)]
+    list: Vec<TelegramChat>,
+}
+
+#[derive(Deserialize)]
+struct TelegramChat {
+    #[serde(default)]
+    name: Option<String>,
+    id: i64,
+    messages: Vec<TelegramMessage>,
+}
+
+#[derive(Deserialize)]
+struct TelegramMessage {
+    id: i64,
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    date_unixtime: Option<String>,
+    #[serde(default)]
+    from: Option<String>,
+    #[serde(default)]
+    from_id: Option<String>,
+    #[serde(default)]
+    forwarded_from: Option<String>,
+    #[serde(default)]
+    reply_to_message_id: Option<i64>,
+    #[serde(default)]
+    text: Value,
+    #[serde(default)]
+    photo: Option<String>,
+    #[serde(default)]
+    file: Option<String>,
+    #[serde(default)]
+    file_name: Option<String>,
+    #[serde(default)]
+    poll: Option<TelegramPoll>,
+}
+
+#[derive(Deserialize)]
+struct TelegramPoll {
+    #[serde(default)]
+    question: String,
+}
+
+/// Converts one chat of an export into a thread, oldest message first.
+/// `source_chat` picks a chat by name or id from a full account export;
+/// `user_map` keys are Telegram `from_id`s (`user123`), bare ids, or names
+/// (lowercased). Media paths resolve against `base_dir`.
+pub(crate) fn parse_export(
+    contents: &str,
+    base_dir: &Path,
+    user_map: &HashMap<String, i64>,
+    source_chat: Option<&str>,
+) -> Result<ImportThread, String> {
+    let export = serde_json::from_str::<TelegramExport>(contents)
+        .map_err(|_| "not a Telegram JSON export (expected result.json in JSON format)")?;
+    let chat = select_chat(export, source_chat)?;
+
+    let mut messages = chat
+        .messages
+        .into_iter()
+        .filter(|message| message.kind == "message")
+        .collect::<Vec<_>>();
+    messages.sort_by_key(|message| message.id);
+    let messages = messages
+        .into_iter()
+        .map(|message| {
+            let author = message
+                .from
+                .clone()
+                .filter(|name| !name.trim().is_empty())
+                .unwrap_or_else(|| "Unknown".to_string());
+            let author_user_id = [
+                message.from_id.as_deref(),
+                message
+                    .from_id
+                    .as_deref()
+                    .and_then(|from_id| from_id.strip_prefix("user")),
+                Some(author.as_str()),
+            ]
+            .into_iter()
+            .flatten()
+            .find_map(|key| user_map.get(&key.to_lowercase()).copied());
+
+            let mut text = convert_text(&message.text);
+            if let Some(poll) = &message.poll {
+                push_line(&mut text, &format!("📊 {}", poll.question));
+            }
+            if let Some(forwarded_from) = &message.forwarded_from {
+                text = format!("↪ Forwarded from {forwarded_from}\n{text}");
+            }
+            let mut attachment = None;
+            if let Some(media) = message.photo.as_deref().or(message.file.as_deref()) {
+                let path = base_dir.join(media);
+                if !media.starts_with(MISSING_FILE_PREFIX) && path.is_file() {
+                    attachment = Some(path);
+                } else {
+                    let name = message.file_name.as_deref().unwrap_or("media");
+                    push_line(
+                        &mut text,
+                        &format!("📎 {name} (not included in the export)"),
+                    );
+                }
+            }
+            ImportMessage {
+                source_id: message.id.to_string(),
+                author,
+                author_user_id,
+                date: message
+                    .date_unixtime
+                    .as_deref()
+                    .and_then(|date| date.parse().ok())
+                    .unwrap_or_default(),
+                text,
+                reply_to: message.reply_to_message_id.map(|id| id.to_string()),
+                attachment,
+            }
+        })
+        .collect();
+
+    Ok(ImportThread {
+        source_id: chat.id.to_string(),
+        title: chat
+            .name
+            .unwrap_or_else(|| format!("Telegram chat {}", chat.id)),
+        is_public: false,
+        participant_ids: Vec::new(),
+        messages,
+    })
+}
+
+fn select_chat(export: TelegramExport, source_chat: Option<&str>) -> Result<TelegramChat, String> {
+    let chats = match export {
+        TelegramExport::Chat(chat) => vec![chat],
+        TelegramExport::Account { chats } => chats.list,
+    };
+    let Some(wanted) = source_chat else {
+        return match <[TelegramChat; 1]>::try_from(chats) {
+            Ok([chat]) => Ok(chat),
+            Err(chats) => Err(format!(
+                "the export has {} chats; pick one with --source-chat",
+                chats.len()
+            )),
+        };
+    };
+    let wanted = wanted.trim();
+    let mut matches = chats
+        .into_iter()
+        .filter(|chat| {
+            chat.id.to_string() == wanted
+                || chat
+                    .name
+                    .as_deref()
+                    .is_some_and(|name| name.trim().eq_ignore_ascii_case(wanted))
+        })
+        .collect::<Vec<_>>();
+    match matches.len() {
+        1 => Ok(matches.remove(0)),
+        0 => Err(format!("chat `{wanted}` is not in this export")),
+        count => Err(format!(
+            "{count} chats are named `{wanted}`; pass the chat id to --source-chat"
+        )),
+    }
+}
+
+/// Telegram stores text as a plain string or as an array of strings and
+/// entity objects; entities are rewritten as markdown.
+fn convert_text(text: &Value) -> String {
+    match text {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts.iter().map(convert_text_part).collect(),
+        _ => String::new(),
+    }
+}
+
+fn convert_text_part(part: &Value) -> String {
+    let Some(entity) = part.as_object() else {
+        return part.as_str().unwrap_or_default().to_string();
+    };
+    let text = entity
+        .get("text")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    match entity
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+    {
+        "bold" => format!("**{text}**"),
+        "italic" => format!("_{text}_"),
+        "strikethrough" => format!("~~{text}~~"),
+        "code" => format!("`{text}`"),
+        "pre" => format!("```\n{text}\n```"),
+        "text_link" => match entity.get("href").and_then(Value::as_str) {
+            Some(href) => format!("[{text}]({href})"),
+            None => text.to_string(),
+        },
+        _ => text.to_string(),
+    }
+}
+
+fn push_line(text: &mut String, line: &str) {
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    text.push_str(line);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SINGLE_CHAT: &str = r#"{
+        "name": "Launch crew",
+        "type": "private_group",
+        "id": 4242,
+        "messages": [
+            {"id": 2, "type": "message", "date_unixtime": "1672923700", "from": "Bob", "from_id": "user222",
+             "reply_to_message_id": 1, "text": ["see ", {"type": "text_link", "text": "docs", "href": "https://inline.chat"}, " ", {"type": "bold", "text": "now"}],
+             "file": "(File not included. Change data exporting settings to download.)", "file_name": "plan.pdf"},
+            {"id": 1, "type": "message", "date_unixtime": "1672923600", "from": "Alice", "from_id": "user111", "text": "ship it?"},
+            {"id": 3, "type": "service", "action": "pin_message", "date_unixtime": "1672923800", "text": ""}
+        ]
+    }"#;
+
+    #[test]
+    fn parses_a_single_chat_export() {
+        let user_map = HashMap::from([("111".to_string(), 42)]);
+        let thread = parse_export(SINGLE_CHAT, Path::new("/nonexistent"), &user_map, None).unwrap();
+
+        assert_eq!(thread.source_id, "4242");
+        assert_eq!(thread.title, "Launch crew");
+        assert_eq!(thread.messages.len(), 2);
+
+        let first = &thread.messages[0];
+        assert_eq!(first.source_id, "1");
+        assert_eq!(first.author_user_id, Some(42));
+        assert_eq!(first.date, 1_672_923_600);
+
+        let reply = &thread.messages[1];
+        assert_eq!(reply.reply_to.as_deref(), Some("1"));
+        assert_eq!(
+            reply.text,
+            "see [docs](https://inline.chat) **now**\n📎 plan.pdf (not included in the export)"
+        );
+        assert!(reply.attachment.is_none());
+    }
+
+    #[test]
+    fn full_exports_need_a_source_chat() {
+        let full = format!(
+            r#"{{"chats": {{"list": [{SINGLE_CHAT}, {{"name": "Other", "id": 7, "messages": []}}]}}}}"#
+        );
+
+        let err = parse_export(&full, Path::new("."), &HashMap::new(), None)
+            .err()
+            .unwrap();
+        assert!(err.contains("--source-chat"), "{err}");
+
+        let thread =
+            parse_export(&full, Path::new("."), &HashMap::new(), Some("launch CREW")).unwrap();
+        assert_eq!(thread.source_id, "4242");
+        let thread = parse_export(&full, Path::new("."), &HashMap::new(), Some("7")).unwrap();
+        assert_eq!(thread.title, "Other");
+    }
+}
@@ -0,0 +1,219 @@
+//! Diagnostics through the `log` facade, which `inline-client` and
+//! `inline-sdk` already log to.
+//!
+//! Records go to stderr, and with `--log-file` also to a file as JSON lines.
+//! Command output on stdout never passes through here. Without `-v` or
+//! `--log-level`, only this CLI's own warnings and errors are shown, so
+//! stderr looks the same as before the facade existed.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::{SecondsFormat, Utc};
+use clap::ValueEnum;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub(crate) enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    fn filter(self) -> LevelFilter {
+        match self {
+            Self::Off => LevelFilter::Off,
+            Self::Error => LevelFilter::Error,
+            Self::Warn => LevelFilter::Warn,
+            Self::Info => LevelFilter::Info,
+            Self::Debug => LevelFilter::Debug,
+            Self::Trace => LevelFilter::Trace,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Filter {
+    level: LevelFilter,
+    /// Include the client and SDK crates, not just the CLI.
+    workspace: bool,
+}
+
+impl Filter {
+    /// `--log-level` wins over `-v`; each `-v` raises the level by one.
+    fn stderr(log_level: Option<LogLevel>, verbose: u8) -> Self {
+        let level = match (log_level, verbose) {
+            (Some(level), _) => level.filter(),
+            (None, 0) => LevelFilter::Warn,
+            (None, 1) => LevelFilter::Info,
+            (None, 2) => LevelFilter::Debug,
+            (None, _) => LevelFilter::Trace,
+        };
+        Self {
+            level,
+            workspace: log_level.is_some() || verbose > 0,
+        }
+    }
+
+    /// The file is for after-the-fact debugging, so it records at least
+    /// `debug` unless a level is given.
+    fn file(log_level: Option<LogLevel>, stderr: Self) -> Self {
+        Self {
+            level: log_level.map_or(stderr.level.max(LevelFilter::Debug), LogLevel::filter),
+            workspace: true,
+        }
+    }
+
+    fn allows(self, level: Level, target: &str) -> bool {
+        if level > self.level {
+            return false;
+        }
+        // Dependencies (hyper, rustls, tungstenite) are only worth their
+        // volume at trace.
+        if self.level == LevelFilter::Trace && self.workspace {
+            return true;
+        }
+        if self.workspace {
+            target.starts_with("inline")
+        } else {
+            target == "inline" || target.starts_with("inline::")
+        }
+    }
+}
+
+struct Logger {
+    stderr: Filter,
+    file: Option<(Filter, Mutex<File>)>,
+}
+
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    time: String,
+    level: &'a str,
+    target: &'a str,
+    message: String,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.stderr.allows(metadata.level(), metadata.target())
+            || self
+                .file
+                .as_ref()
+                .is_some_and(|(filter, _)| filter.allows(metadata.level(), metadata.target()))
+    }
+
+    fn log(&self, record: &Record) {
+        if self.stderr.allows(record.level(), record.target()) {
+            eprintln!("{}", format_human(record));
+        }
+        if let Some((filter, file)) = &self.file
+            && filter.allows(record.level(), record.target())
+            && let Ok(mut line) = serde_json::to_string(&JsonRecord {
+                time: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+                level: &record.level().as_str().to_ascii_lowercase(),
+                target: record.target(),
+                message: record.args().to_string(),
+            })
+            && let Ok(mut file) = file.lock()
+        {
+            line.push('\n');
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+
+    fn flush(&self) {
+        if let Some((_, file)) = &self.file
+            && let Ok(mut file) = file.lock()
+        {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// Warnings keep the `Warning: ` prefix the CLI has always printed; debug
+/// output names where it came from.
+fn format_human(record: &Record) -> String {
+    match record.level() {
+        Level::Error => format!("Error: {}", record.args()),
+        Level::Warn => format!("Warning: {}", record.args()),
+        Level::Info => record.args().to_string(),
+        Level::Debug | Level::Trace => format!(
+            "[{} {}] {}",
+            record.level().as_str().to_ascii_lowercase(),
+            record.target(),
+            record.args()
+        ),
+    }
+}
+
+/// Installs the logger. Call once, after the command line is parsed.
+pub(crate) fn init(
+    log_level: Option<LogLevel>,
+    verbose: u8,
+    log_file: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let stderr = Filter::stderr(log_level, verbose);
+    let file = match log_file {
+        Some(path) => {
+            if let Some(parent) = path.parent()
+                && !parent.as_os_str().is_empty()
+            {
+                fs::create_dir_all(parent).map_err(|err| {
+                    format!("could not create log dir {}: {err}", parent.display())
+                })?;
+            }
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|err| format!("could not open log file {}: {err}", path.display()))?;
+            Some((Filter::file(log_level, stderr), Mutex::new(file)))
+        }
+        None => None,
+    };
+    let max_level = file
+        .as_ref()
+        .map_or(stderr.level, |(filter, _)| filter.level.max(stderr.level));
+    log::set_logger(Box::leak(Box::new(Logger { stderr, file }))).map_err(|err| err.to_string())?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verbosity_widens_both_the_level_and_the_crates_shown() {
+        let quiet = Filter::stderr(None, 0);
+        assert!(quiet.allows(Level::Warn, "inline::commands::watch"));
+        assert!(!quiet.allows(Level::Warn, "inline_sdk::realtime"));
+        assert!(!quiet.allows(Level::Info, "inline::update"));
+
+        let debug = Filter::stderr(None, 2);
+        assert!(debug.allows(Level::Debug, "inline_sdk::realtime"));
+        assert!(!debug.allows(Level::Trace, "inline_sdk::realtime"));
+        assert!(!debug.allows(Level::Debug, "rustls::client"));
+        assert!(Filter::stderr(None, 3).allows(Level::Trace, "rustls::client"));
+
+        let explicit = Filter::stderr(Some(LogLevel::Error), 3);
+        assert!(!explicit.allows(Level::Warn, "inline::update"));
+        assert_eq!(
+            Filter::file(None, quiet).level,
+            LevelFilter::Debug,
+            "the file records debug even when stderr is quiet"
+        );
+        assert_eq!(
+            Filter::file(Some(LogLevel::Off), quiet).level,
+            LevelFilter::Off
+        );
+    }
+}
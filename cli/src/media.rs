@@ -1,4 +1,135 @@
+use clap::ValueEnum;
 use inline_protocol::proto;
+use serde::Serialize;
+
+/// Media kinds accepted by `messages media --type`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum MediaKindArg {
+    Photo,
+    Video,
+    #[value(alias = "document")]
+    File,
+    Link,
+}
+
+impl MediaKindArg {
+    pub(crate) fn search_filter(self) -> proto::SearchMessagesFilter {
+        match self {
+            Self::Photo => proto::SearchMessagesFilter::FilterPhotos,
+            Self::Video => proto::SearchMessagesFilter::FilterVideos,
+            Self::File => proto::SearchMessagesFilter::FilterDocuments,
+            Self::Link => proto::SearchMessagesFilter::FilterLinks,
+        }
+    }
+}
+
+/// One photo, video, file, or link found in a message.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MediaEntry {
+    pub(crate) message_id: i64,
+    pub(crate) date: i64,
+    pub(crate) from_id: i64,
+    /// File name for documents, page title for links.
+    pub(crate) name: Option<String>,
+    pub(crate) mime_type: Option<String>,
+    pub(crate) size: Option<i64>,
+    pub(crate) url: Option<String>,
+}
+
+/// Entries of `kind` in `message`. A message has at most one photo, video, or
+/// file, but may carry several links.
+pub(crate) fn media_entries(message: &proto::Message, kind: MediaKindArg) -> Vec<MediaEntry> {
+    let entry = |name: Option<String>, mime_type, size: Option<i32>, url| MediaEntry {
+        message_id: message.id,
+        date: message.date,
+        from_id: message.from_id,
+        name,
+        mime_type,
+        size: size.map(i64::from),
+        url,
+    };
+    let media = message
+        .media
+        .as_ref()
+        .and_then(|media| media.media.as_ref());
+    match (kind, media) {
+        (MediaKindArg::Photo, Some(proto::message_media::Media::Photo(photo))) => {
+            let (url, size, _, _) = photo
+                .photo
+                .as_ref()
+                .map(best_photo_size)
+                .unwrap_or_default();
+            vec![entry(None, None, size, url)]
+        }
+        (MediaKindArg::Video, Some(proto::message_media::Media::Video(video))) => video
+            .video
+            .iter()
+            .map(|video| entry(None, None, Some(video.size), video.cdn_url.clone()))
+            .collect(),
+        (MediaKindArg::File, Some(proto::message_media::Media::Document(document))) => document
+            .document
+            .iter()
+            .map(|document| {
+                entry(
+                    Some(document.file_name.clone()),
+                    Some(document.mime_type.clone()),
+                    Some(document.size),
+                    document.cdn_url.clone(),
+                )
+            })
+            .collect(),
+        (MediaKindArg::Link, _) => message_links(message)
+            .into_iter()
+            .map(|(title, url)| entry(title, None, None, Some(url)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Links in link previews and text entities, deduplicated by URL.
+fn message_links(message: &proto::Message) -> Vec<(Option<String>, String)> {
+    let mut links: Vec<(Option<String>, String)> = Vec::new();
+    let previews = message
+        .attachments
+        .iter()
+        .flat_map(|attachments| &attachments.attachments)
+        .filter_map(|attachment| match &attachment.attachment {
+            Some(proto::message_attachment::Attachment::UrlPreview(preview)) => {
+                preview.url.clone().map(|url| (preview.title.clone(), url))
+            }
+            _ => None,
+        });
+    let text = message.message.as_deref().unwrap_or_default();
+    let entities = message
+        .entities
+        .iter()
+        .flat_map(|entities| &entities.entities)
+        .filter_map(|entity| match &entity.entity {
+            Some(proto::message_entity::Entity::TextUrl(text_url)) => {
+                Some((None, text_url.url.clone()))
+            }
+            _ if entity.r#type == proto::message_entity::Type::Url as i32 => {
+                utf16_slice(text, entity.offset, entity.length).map(|url| (None, url))
+            }
+            _ => None,
+        });
+    for (title, url) in previews.chain(entities) {
+        if !url.is_empty() && !links.iter().any(|(_, existing)| *existing == url) {
+            links.push((title, url));
+        }
+    }
+    links
+}
+
+/// Entity offsets count UTF-16 code units.
+fn utf16_slice(text: &str, offset: i64, length: i64) -> Option<String> {
+    let units = text.encode_utf16().collect::<Vec<_>>();
+    let start = usize::try_from(offset).ok()?;
+    let end = start.checked_add(usize::try_from(length).ok()?)?;
+    String::from_utf16(units.get(start..end)?).ok()
+}
 
 pub(crate) fn best_photo_size(
     photo: &proto::Photo,
@@ -28,6 +159,62 @@ pub(crate) fn best_photo_size(
 mod tests {
     use super::*;
 
+    #[test]
+    fn media_entries_match_kind_and_collect_links() {
+        let document = proto::Message {
+            id: 4,
+            media: Some(proto::MessageMedia {
+                media: Some(proto::message_media::Media::Document(
+                    proto::MessageDocument {
+                        document: Some(proto::Document {
+                            file_name: "q3.pdf".to_string(),
+                            mime_type: "application/pdf".to_string(),
+                            size: 2048,
+                            cdn_url: Some("https://cdn/q3.pdf".to_string()),
+                            ..Default::default()
+                        }),
+                    },
+                )),
+            }),
+            ..Default::default()
+        };
+        let link = proto::Message {
+            id: 5,
+            message: Some("see “https://a.dev” and docs".to_string()),
+            entities: Some(proto::MessageEntities {
+                entities: vec![
+                    proto::MessageEntity {
+                        r#type: proto::message_entity::Type::Url as i32,
+                        offset: 5,
+                        length: 13,
+                        ..Default::default()
+                    },
+                    proto::MessageEntity {
+                        r#type: proto::message_entity::Type::TextUrl as i32,
+                        offset: 24,
+                        length: 4,
+                        entity: Some(proto::message_entity::Entity::TextUrl(
+                            proto::message_entity::MessageEntityTextUrl {
+                                url: "https://docs.a.dev".to_string(),
+                            },
+                        )),
+                    },
+                ],
+            }),
+            ..Default::default()
+        };
+
+        let files = media_entries(&document, MediaKindArg::File);
+        assert_eq!(files[0].name.as_deref(), Some("q3.pdf"));
+        assert_eq!(files[0].size, Some(2048));
+        assert!(media_entries(&document, MediaKindArg::Photo).is_empty());
+        let links = media_entries(&link, MediaKindArg::Link)
+            .into_iter()
+            .map(|entry| entry.url.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(links, vec!["https://a.dev", "https://docs.a.dev"]);
+    }
+
     #[test]
     fn best_photo_size_picks_largest_size_with_url() {
         let photo = proto::Photo {
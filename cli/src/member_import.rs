@@ -0,0 +1,171 @@
+//! CSV input for `inline spaces invite-bulk`.
+//!
+//! The file needs an `email` column and may have a `role` column (`member`,
+//! `public-chats`, or `admin`; blank means member). Without a header row the
+//! first column is the email and the second the role.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum BulkInviteRole {
+    Member,
+    /// Member who can also see the space's public chats.
+    PublicChats,
+    Admin,
+}
+
+impl BulkInviteRole {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" | "member" => Some(Self::Member),
+            "public-chats" | "public_chats" | "public" => Some(Self::PublicChats),
+            "admin" => Some(Self::Admin),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Member => "member",
+            Self::PublicChats => "public-chats",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+/// One data row of the file; `error` is set when it can't be invited.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BulkInviteRow {
+    /// 1-based line in the file.
+    pub(crate) line: usize,
+    pub(crate) email: String,
+    pub(crate) role: BulkInviteRole,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+}
+
+pub(crate) fn parse_member_csv(contents: &str) -> Result<Vec<BulkInviteRow>, String> {
+    let mut records = contents
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim_start_matches('\u{feff}')))
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_number, line)| (line_number, split_csv_line(line)))
+        .peekable();
+
+    let (email_column, role_column) = match records.peek() {
+        Some((_, header))
+            if header
+                .iter()
+                .any(|field| field.trim().eq_ignore_ascii_case("email")) =>
+        {
+            let column = |name: &str| {
+                header
+                    .iter()
+                    .position(|field| field.trim().eq_ignore_ascii_case(name))
+            };
+            let columns = (column("email").unwrap_or_default(), column("role"));
+            records.next();
+            columns
+        }
+        Some(_) => (0, Some(1)),
+        None => return Err("the file has no rows".to_string()),
+    };
+
+    let mut seen = HashMap::<String, usize>::new();
+    let mut rows = Vec::new();
+    for (line, fields) in records {
+        let field = |column: usize| fields.get(column).map(|value| value.trim()).unwrap_or("");
+        let email = field(email_column).to_string();
+        let role_value = role_column.map(field).unwrap_or("");
+        let role = BulkInviteRole::parse(role_value);
+        let error = if !is_plausible_email(&email) {
+            Some(format!("`{email}` is not an email address"))
+        } else if role.is_none() {
+            Some(format!(
+                "unknown role `{role_value}` (use member, public-chats, or admin)"
+            ))
+        } else {
+            seen.get(&email.to_ascii_lowercase())
+                .map(|first| format!("duplicate of line {first}"))
+        };
+        seen.entry(email.to_ascii_lowercase()).or_insert(line);
+        rows.push(BulkInviteRow {
+            line,
+            email,
+            role: role.unwrap_or(BulkInviteRole::Member),
+            error,
+        });
+    }
+    if rows.is_empty() {
+        return Err("the file has a header but no members".to_string());
+    }
+    Ok(rows)
+}
+
+fn is_plausible_email(value: &str) -> bool {
+    let Some((local, domain)) = value.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !value.chars().any(|ch| ch.is_whitespace() || ch == ',')
+}
+
+/// Splits one CSV line, honoring double-quoted fields with `""` escapes.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut current)),
+            ch => current.push(ch),
+        }
+    }
+    fields.push(current);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_header_rows_and_flags_bad_ones() {
+        let csv = "Name,Email,Role\n\"Lee, Sam\",sam@example.com,admin\nAva,ava@example.com,\nBad,not-an-email,member\nDup,SAM@example.com,member\nOdd,odd@example.com,owner\n";
+
+        let rows = parse_member_csv(csv).unwrap();
+
+        assert_eq!(rows.len(), 5);
+        assert_eq!(rows[0].email, "sam@example.com");
+        assert_eq!(rows[0].role, BulkInviteRole::Admin);
+        assert_eq!(rows[1].role, BulkInviteRole::Member);
+        assert!(rows[1].error.is_none());
+        assert!(rows[2].error.as_deref().unwrap().contains("not an email"));
+        assert_eq!(rows[3].error.as_deref(), Some("duplicate of line 2"));
+        assert!(rows[4].error.as_deref().unwrap().contains("unknown role"));
+    }
+
+    #[test]
+    fn headerless_files_use_email_then_role() {
+        let rows = parse_member_csv("a@example.com,public-chats\nb@example.com\n").unwrap();
+
+        assert_eq!(rows[0].line, 1);
+        assert_eq!(rows[0].role, BulkInviteRole::PublicChats);
+        assert_eq!(rows[1].role, BulkInviteRole::Member);
+    }
+}
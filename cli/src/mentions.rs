@@ -0,0 +1,158 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::digest::{mentions_user, truncate_preview};
+use crate::message_output::message_summary;
+use crate::output::ChatListItem;
+use inline_protocol::proto;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MentionsOutput {
+    pub(crate) since: i64,
+    pub(crate) unreplied_only: bool,
+    pub(crate) chats_scanned: usize,
+    pub(crate) mentions: Vec<Mention>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Mention {
+    pub(crate) chat_id: i64,
+    pub(crate) chat_name: String,
+    pub(crate) message_id: i64,
+    pub(crate) date: i64,
+    pub(crate) relative_date: String,
+    pub(crate) sender_id: i64,
+    pub(crate) sender_name: String,
+    pub(crate) preview: String,
+    /// Whether you sent anything in the chat after this message.
+    pub(crate) replied: bool,
+}
+
+/// Messages since `since_ts` that mention `current_user_id`, newest first.
+/// A mention counts as replied once you've posted in that chat after it.
+pub(crate) fn find_mentions(
+    chats: Vec<(ChatListItem, Vec<proto::Message>)>,
+    users_by_id: &HashMap<i64, proto::User>,
+    current_user_id: i64,
+    since_ts: i64,
+    unreplied_only: bool,
+    now: i64,
+) -> Vec<Mention> {
+    let mut mentions = Vec::new();
+    for (item, messages) in chats {
+        let last_own_message_id = messages
+            .iter()
+            .filter(|message| message.from_id == current_user_id)
+            .map(|message| message.id)
+            .max();
+        for message in &messages {
+            if message.date < since_ts
+                || message.from_id == current_user_id
+                || !mentions_user(message, current_user_id)
+            {
+                continue;
+            }
+            let replied = last_own_message_id.is_some_and(|own_id| own_id > message.id);
+            if unreplied_only && replied {
+                continue;
+            }
+            let summary = message_summary(message, users_by_id, Some(current_user_id), now, None);
+            mentions.push(Mention {
+                chat_id: item.chat.id,
+                chat_name: item.display_name.clone(),
+                message_id: message.id,
+                date: message.date,
+                relative_date: summary.relative_date,
+                sender_id: message.from_id,
+                sender_name: summary.sender_name,
+                preview: truncate_preview(&summary.preview),
+                replied,
+            });
+        }
+    }
+    mentions.sort_by_key(|mention| Reverse(mention.date));
+    mentions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::PeerSummary;
+
+    fn chat_item(chat_id: i64) -> ChatListItem {
+        ChatListItem {
+            chat: proto::Chat {
+                id: chat_id,
+                ..Default::default()
+            },
+            dialog: None,
+            peer: PeerSummary {
+                peer_type: "chat".to_string(),
+                id: chat_id,
+            },
+            display_name: format!("chat {chat_id}"),
+            space: None,
+            space_name: None,
+            unread_count: None,
+            notifications: "default",
+            muted: false,
+            activity: None,
+            last_message: None,
+            last_message_line: None,
+            last_message_relative_date: None,
+        }
+    }
+
+    fn message(id: i64, from_id: i64, mentions: Option<i64>) -> proto::Message {
+        proto::Message {
+            id,
+            from_id,
+            date: 1_000 + id,
+            message: Some(format!("message {id}")),
+            entities: mentions.map(|user_id| proto::MessageEntities {
+                entities: vec![proto::MessageEntity {
+                    r#type: proto::message_entity::Type::Mention as i32,
+                    entity: Some(proto::message_entity::Entity::Mention(
+                        proto::message_entity::MessageEntityMention { user_id },
+                    )),
+                    ..Default::default()
+                }],
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn finds_mentions_and_tracks_replies() {
+        let chats = vec![
+            (
+                chat_item(1),
+                vec![
+                    message(1, 2, Some(9)),
+                    message(2, 9, None),
+                    message(3, 2, Some(9)),
+                    message(4, 2, Some(5)),
+                ],
+            ),
+            (chat_item(2), vec![message(7, 9, Some(9))]),
+        ];
+        let users = HashMap::new();
+
+        let all = find_mentions(chats, &users, 9, 0, false, 2_000);
+        let found: Vec<_> = all
+            .iter()
+            .map(|mention| (mention.message_id, mention.replied))
+            .collect();
+        assert_eq!(found, [(3, false), (1, true)]);
+
+        let chats = vec![(
+            chat_item(1),
+            vec![message(1, 2, Some(9)), message(2, 9, None)],
+        )];
+        assert!(find_mentions(chats, &users, 9, 0, true, 2_000).is_empty());
+    }
+}
@@ -1,10 +1,13 @@
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::Path;
 
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
 use chrono::{DateTime, Utc};
 use clap::ValueEnum;
 use serde::Serialize;
 
+use crate::deep_link::inline_url;
 use crate::media::best_photo_size;
 use crate::output::{self, JsonFormat, user_display_name};
 use inline_protocol::proto;
@@ -15,6 +18,7 @@ pub(crate) enum MessageExportFormat {
     Jsonl,
     Markdown,
     Csv,
+    Mbox,
 }
 
 impl MessageExportFormat {
@@ -24,6 +28,7 @@ impl MessageExportFormat {
             Self::Jsonl => "jsonl",
             Self::Markdown => "markdown",
             Self::Csv => "csv",
+            Self::Mbox => "mbox",
         }
     }
 
@@ -33,6 +38,7 @@ impl MessageExportFormat {
             Self::Jsonl => "jsonl",
             Self::Markdown => "md",
             Self::Csv => "csv",
+            Self::Mbox => "mbox",
         }
     }
 }
@@ -131,8 +137,8 @@ pub(crate) struct MessageExportBundle {
     pub(crate) users: Vec<ExportUser>,
     pub(crate) chats: Vec<proto::Chat>,
     pub(crate) spaces: Vec<proto::Space>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub(crate) translations: Vec<proto::MessageTranslation>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) translations: BTreeMap<i64, proto::MessageTranslation>,
     #[serde(rename = "_warnings")]
     pub(crate) warnings: Vec<String>,
 }
@@ -170,6 +176,7 @@ pub(crate) fn infer_export_format(
         Some("jsonl") | Some("ndjson") => MessageExportFormat::Jsonl,
         Some("md") | Some("markdown") => MessageExportFormat::Markdown,
         Some("csv") => MessageExportFormat::Csv,
+        Some("mbox") => MessageExportFormat::Mbox,
         _ => default,
     }
 }
@@ -261,6 +268,11 @@ pub(crate) fn build_message_export_bundle(
         .filter_map(|space_id| spaces_by_id.get(space_id).cloned())
         .collect();
 
+    let translations = translations
+        .into_iter()
+        .map(|translation| (translation.message_id, translation))
+        .collect();
+
     MessageExportBundle {
         peer,
         messages: rows,
@@ -298,6 +310,7 @@ pub(crate) fn render_export(
         MessageExportFormat::Jsonl => render_jsonl(bundle),
         MessageExportFormat::Markdown => Ok(render_markdown(bundle)),
         MessageExportFormat::Csv => Ok(render_csv(bundle)),
+        MessageExportFormat::Mbox => Ok(render_mbox(bundle)),
     }
 }
 
@@ -427,7 +440,7 @@ fn export_user(user: &proto::User) -> ExportUser {
     }
 }
 
-fn export_media(message: &proto::Message) -> Vec<ExportMedia> {
+pub(crate) fn export_media(message: &proto::Message) -> Vec<ExportMedia> {
     let Some(media) = message.media.as_ref() else {
         return Vec::new();
     };
@@ -576,7 +589,7 @@ fn render_jsonl(bundle: &MessageExportBundle) -> Result<String, Box<dyn std::err
         users: &'a [ExportUser],
         chats: &'a [proto::Chat],
         spaces: &'a [proto::Space],
-        translations: &'a [proto::MessageTranslation],
+        translations: &'a BTreeMap<i64, proto::MessageTranslation>,
         #[serde(rename = "_warnings")]
         warnings: &'a [String],
     }
@@ -667,6 +680,18 @@ fn render_markdown(bundle: &MessageExportBundle) -> String {
             output.push_str("\n\n");
         }
 
+        if let Some(translation) = bundle
+            .translations
+            .get(&message.id)
+            .filter(|translation| !translation.translation.trim().is_empty())
+        {
+            output.push_str("*Translation (");
+            output.push_str(&translation.language);
+            output.push_str("):* ");
+            output.push_str(translation.translation.trim());
+            output.push_str("\n\n");
+        }
+
         for media in &message.media {
             if let Some(url) = media.local_path.as_ref().or(media.cdn_url.as_ref()) {
                 match media.kind.as_str() {
@@ -712,11 +737,7 @@ fn render_markdown(bundle: &MessageExportBundle) -> String {
 }
 
 fn inline_peer_url(peer: &ExportPeer) -> Option<String> {
-    match peer.peer_type.as_str() {
-        "chat" => Some(format!("inline://chat/{}", peer.id)),
-        "user" => Some(format!("inline://user/{}", peer.id)),
-        _ => None,
-    }
+    inline_url(&peer.peer_type, peer.id, None)
 }
 
 fn should_show_timestamp(previous_date: Option<i64>, current_date: i64) -> bool {
@@ -747,7 +768,7 @@ fn markdown_metadata_comment(message: &ExportMessage) -> String {
 
 fn render_csv(bundle: &MessageExportBundle) -> String {
     let mut output = String::new();
-    output.push_str("id,date,date_iso,from_id,sender_name,text,display_text,reply_to_msg_id,reply_to_preview,forward_from_peer_type,forward_from_peer_id,forward_from_msg_id,forward_preview,media_count,media_kinds,media_urls,local_paths\n");
+    output.push_str("id,date,date_iso,from_id,sender_name,text,display_text,reply_to_msg_id,reply_to_preview,forward_from_peer_type,forward_from_peer_id,forward_from_msg_id,forward_preview,media_count,media_kinds,media_urls,local_paths,translation\n");
     for message in &bundle.messages {
         let media_kinds = message
             .media
@@ -815,6 +836,11 @@ fn render_csv(bundle: &MessageExportBundle) -> String {
             media_kinds,
             media_urls,
             local_paths,
+            bundle
+                .translations
+                .get(&message.id)
+                .map(|translation| translation.translation.clone())
+                .unwrap_or_default(),
         ];
         output.push_str(
             &fields
@@ -852,7 +878,145 @@ fn markdown_inline_preview(value: &str) -> String {
     preview
 }
 
-fn csv_field(value: &str) -> String {
+/// One RFC 5322 message per chat message in mboxrd framing. Message-IDs are
+/// derived from the peer and message id, so replies thread through
+/// `In-Reply-To` even across separate exports of the same chat.
+fn render_mbox(bundle: &MessageExportBundle) -> String {
+    let chat_name = bundle.peer.name.as_deref().unwrap_or("Inline transcript");
+    let message_id_header = |id: i64| {
+        format!(
+            "<{id}.{}-{}@inline.chat>",
+            bundle.peer.peer_type, bundle.peer.id
+        )
+    };
+    let mut output = String::new();
+    for message in &bundle.messages {
+        let date = DateTime::<Utc>::from_timestamp(message.date, 0).unwrap_or_default();
+        output.push_str(&format!(
+            "From user-{}@inline.chat {}\n",
+            message.from_id,
+            date.format("%a %b %e %H:%M:%S %Y")
+        ));
+        let mut headers = vec![
+            ("Message-ID", message_id_header(message.id)),
+            ("Date", date.to_rfc2822()),
+            (
+                "From",
+                format!(
+                    "{} <user-{}@inline.chat>",
+                    mime_display_name(&message.sender_name),
+                    message.from_id
+                ),
+            ),
+            (
+                "To",
+                format!(
+                    "{} <{}-{}@inline.chat>",
+                    mime_display_name(chat_name),
+                    bundle.peer.peer_type,
+                    bundle.peer.id
+                ),
+            ),
+        ];
+        let subject = match &message.reply_to {
+            Some(reply) => {
+                headers.push(("In-Reply-To", message_id_header(reply.message_id)));
+                headers.push(("References", message_id_header(reply.message_id)));
+                format!("Re: {chat_name}")
+            }
+            None => chat_name.to_string(),
+        };
+        headers.push(("Subject", mime_header_word(&subject)));
+        headers.push(("MIME-Version", "1.0".to_string()));
+        headers.push(("Content-Type", "text/plain; charset=utf-8".to_string()));
+        headers.push(("Content-Transfer-Encoding", "8bit".to_string()));
+        headers.push(("X-Inline-Message-Id", message.id.to_string()));
+        headers.push(("X-Inline-From-Id", message.from_id.to_string()));
+        for (name, value) in headers {
+            output.push_str(name);
+            output.push_str(": ");
+            output.push_str(&value);
+            output.push('\n');
+        }
+        output.push('\n');
+
+        let mut body = Vec::new();
+        if let Some(forward) = &message.forwarded_from {
+            body.push(format!(
+                "Forwarded from {}:",
+                forward.sender_name.as_deref().unwrap_or("unknown sender")
+            ));
+            if let Some(text) = forward
+                .message
+                .as_ref()
+                .and_then(|source| source.display_text.as_deref())
+            {
+                body.extend(text.lines().map(|line| format!("> {line}")));
+            }
+            body.push(String::new());
+        }
+        body.push(message.display_text.trim().to_string());
+        if let Some(translation) = bundle
+            .translations
+            .get(&message.id)
+            .filter(|translation| !translation.translation.trim().is_empty())
+        {
+            body.push(String::new());
+            body.push(format!(
+                "Translation ({}): {}",
+                translation.language,
+                translation.translation.trim()
+            ));
+        }
+        for url in message
+            .media
+            .iter()
+            .filter_map(|media| media.local_path.as_ref().or(media.cdn_url.as_ref()))
+            .chain(
+                message
+                    .attachments
+                    .iter()
+                    .filter_map(|attachment| attachment.url.as_ref()),
+            )
+        {
+            body.push(url.clone());
+        }
+        for line in body.join("\n").lines() {
+            // mboxrd: quote any line that would read as a message separator.
+            if line.trim_start_matches('>').starts_with("From ") {
+                output.push('>');
+            }
+            output.push_str(line);
+            output.push('\n');
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Header text as-is when it's printable ASCII, otherwise an RFC 2047
+/// encoded word.
+fn mime_header_word(value: &str) -> String {
+    if value.chars().all(|ch| ch.is_ascii_graphic() || ch == ' ') {
+        return value.to_string();
+    }
+    format!("=?UTF-8?B?{}?=", BASE64.encode(value))
+}
+
+/// A mailbox display name, quoted when it has address punctuation in it.
+fn mime_display_name(value: &str) -> String {
+    let encoded = mime_header_word(value);
+    if encoded != value
+        || !value.contains([
+            '(', ')', '<', '>', '@', ',', ';', ':', '\\', '"', '.', '[', ']',
+        ])
+    {
+        return encoded;
+    }
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+pub(crate) fn csv_field(value: &str) -> String {
     if value.contains([',', '"', '\n', '\r']) {
         format!("\"{}\"", value.replace('"', "\"\""))
     } else {
@@ -932,6 +1096,62 @@ mod tests {
         assert!(markdown.contains("<!-- inline: MSG=7 from=1 date=0 -->"));
     }
 
+    #[test]
+    fn mbox_threads_replies_and_escapes_from_lines() {
+        let users = HashMap::from([(
+            1,
+            proto::User {
+                id: 1,
+                first_name: Some("Zoë".to_string()),
+                ..Default::default()
+            },
+        )]);
+        let empty_chats = HashMap::new();
+        let empty_spaces = HashMap::new();
+        let empty_forwards = HashMap::new();
+        let first = proto::Message {
+            id: 7,
+            from_id: 1,
+            message: Some("From now on we ship on Fridays".to_string()),
+            date: 1_700_000_000,
+            ..Default::default()
+        };
+        let reply = proto::Message {
+            id: 8,
+            from_id: 2,
+            message: Some("Agreed".to_string()),
+            date: 1_700_000_060,
+            reply_to_msg_id: Some(7),
+            ..Default::default()
+        };
+        let related = HashMap::from([(7, first.clone())]);
+        let bundle = build_message_export_bundle(MessageExportBuildInput {
+            peer: ExportPeer {
+                peer_type: "chat".to_string(),
+                id: 10,
+                name: Some("Town Hall, Q3".to_string()),
+            },
+            messages: vec![first, reply],
+            users_by_id: &users,
+            chats_by_id: &empty_chats,
+            spaces_by_id: &empty_spaces,
+            related_messages_by_id: &related,
+            forward_messages_by_key: &empty_forwards,
+            translations: Vec::new(),
+            warnings: Vec::new(),
+        });
+
+        let mbox = render_mbox(&bundle);
+        assert_eq!(mbox.matches("\nFrom user-").count() + 1, 2);
+        assert!(mbox.starts_with("From user-1@inline.chat Tue Nov 14 22:13:20 2023\n"));
+        assert!(mbox.contains("Message-ID: <7.chat-10@inline.chat>\n"));
+        assert!(mbox.contains("From: =?UTF-8?B?Wm/Dqw==?= <user-1@inline.chat>\n"));
+        assert!(mbox.contains("To: \"Town Hall, Q3\" <chat-10@inline.chat>\n"));
+        assert!(mbox.contains("\n>From now on we ship on Fridays\n"));
+        assert!(mbox.contains("In-Reply-To: <7.chat-10@inline.chat>\n"));
+        assert!(mbox.contains("Subject: Re: Town Hall, Q3\n"));
+    }
+
     #[test]
     fn markdown_keeps_replies_light_and_timestamps_sparse() {
         let ava = proto::User {
@@ -1052,4 +1272,49 @@ mod tests {
         assert!(markdown.contains("feedback-media/19700101-0000-MSG8-document-32-report.pdf"));
         assert!(!markdown.contains("https://cdn.example/report.pdf"));
     }
+
+    #[test]
+    fn translations_are_keyed_by_message_id_across_formats() {
+        let users = HashMap::new();
+        let empty_chats = HashMap::new();
+        let empty_spaces = HashMap::new();
+        let empty_messages = HashMap::new();
+        let empty_forwards = HashMap::new();
+        let bundle = build_message_export_bundle(MessageExportBuildInput {
+            peer: ExportPeer {
+                peer_type: "chat".to_string(),
+                id: 10,
+                name: Some("Town Hall".to_string()),
+            },
+            messages: vec![proto::Message {
+                id: 9,
+                from_id: 1,
+                message: Some("Hola".to_string()),
+                date: 0,
+                ..Default::default()
+            }],
+            users_by_id: &users,
+            chats_by_id: &empty_chats,
+            spaces_by_id: &empty_spaces,
+            related_messages_by_id: &empty_messages,
+            forward_messages_by_key: &empty_forwards,
+            translations: vec![proto::MessageTranslation {
+                message_id: 9,
+                language: "en".to_string(),
+                translation: "Hello".to_string(),
+                ..Default::default()
+            }],
+            warnings: Vec::new(),
+        });
+
+        let value = serde_json::to_value(&bundle).unwrap();
+        assert_eq!(value["translations"]["9"]["translation"], "Hello");
+
+        let markdown = render_markdown(&bundle);
+        assert!(markdown.contains("*Translation (en):* Hello"));
+
+        let csv = render_csv(&bundle);
+        assert!(csv.lines().next().unwrap().ends_with(",translation"));
+        assert!(csv.lines().nth(1).unwrap().ends_with(",Hello"));
+    }
 }
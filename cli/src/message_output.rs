@@ -1,9 +1,10 @@
 use std::collections::HashMap;
 
-use crate::media::best_photo_size;
+use crate::media::{MediaKindArg, best_photo_size, media_entries};
 use crate::output::{
-    AttachmentSummary, MediaSummary, MessageListOutput, MessageSummary, PeerSummary, format_bytes,
-    format_relative_date, user_summary,
+    AttachmentSummary, MediaListItem, MediaListOutput, MediaSummary, MessageListOutput,
+    MessageSummary, PeerSummary, format_bytes, format_relative_date, user_display_name,
+    user_summary,
 };
 use inline_protocol::proto;
 
@@ -53,6 +54,54 @@ pub(crate) fn build_message_list_from_messages(
     }
 }
 
+/// Media of one kind across `messages`, one row per file or link.
+pub(crate) fn build_media_list(
+    messages: &[proto::Message],
+    kind: MediaKindArg,
+    users_by_id: &HashMap<i64, proto::User>,
+    current_user_id: Option<i64>,
+    peer: Option<PeerSummary>,
+    peer_name: Option<String>,
+    next_offset_id: Option<i64>,
+) -> MediaListOutput {
+    let now = current_epoch_seconds() as i64;
+    let items = messages
+        .iter()
+        .flat_map(|message| {
+            let sender_name = message_sender_name(message, users_by_id, current_user_id);
+            let relative_date = format_relative_date(message.date, now);
+            media_entries(message, kind)
+                .into_iter()
+                .map(move |entry| MediaListItem {
+                    entry,
+                    sender_name: sender_name.clone(),
+                    relative_date: relative_date.clone(),
+                })
+        })
+        .collect();
+    MediaListOutput {
+        kind,
+        peer,
+        peer_name,
+        items,
+        next_offset_id,
+    }
+}
+
+fn message_sender_name(
+    message: &proto::Message,
+    users_by_id: &HashMap<i64, proto::User>,
+    current_user_id: Option<i64>,
+) -> String {
+    if message.out || current_user_id == Some(message.from_id) {
+        "You".to_string()
+    } else if let Some(user) = users_by_id.get(&message.from_id) {
+        user_display_name(user)
+    } else {
+        format!("user {}", message.from_id)
+    }
+}
+
 pub(crate) fn message_summary(
     message: &proto::Message,
     users_by_id: &HashMap<i64, proto::User>,
@@ -67,13 +116,7 @@ pub(crate) fn message_summary(
         .cloned();
     let preview = message_preview(message, media.as_ref(), &attachments, translation.as_ref());
     let sender = users_by_id.get(&message.from_id).map(user_summary);
-    let sender_name = if message.out || current_user_id == Some(message.from_id) {
-        "You".to_string()
-    } else if let Some(sender) = &sender {
-        sender.display_name.clone()
-    } else {
-        format!("user {}", message.from_id)
-    };
+    let sender_name = message_sender_name(message, users_by_id, current_user_id);
     let relative_date = format_relative_date(message.date, now);
     MessageSummary {
         message: message.clone(),
@@ -137,7 +180,7 @@ fn normalize_preview_text(value: &str) -> String {
         .join(" ")
 }
 
-fn message_media_summary(message: &proto::Message) -> Option<MediaSummary> {
+pub(crate) fn message_media_summary(message: &proto::Message) -> Option<MediaSummary> {
     let media = message.media.as_ref()?;
     match &media.media {
         Some(proto::message_media::Media::Document(document)) => {
@@ -284,6 +327,7 @@ fn media_preview(media: &MediaSummary) -> String {
             label
         }
         "photo" => "[photo]".to_string(),
+        "nudge" => "[nudge 👋]".to_string(),
         _ => "[media]".to_string(),
     }
 }
@@ -373,6 +417,26 @@ mod tests {
         assert_eq!(summary.sender_name, "You");
     }
 
+    #[test]
+    fn nudges_have_a_distinct_preview() {
+        let message = proto::Message {
+            id: 3,
+            from_id: 42,
+            media: Some(proto::MessageMedia {
+                media: Some(proto::message_media::Media::Nudge(proto::MessageNudge {})),
+            }),
+            ..Default::default()
+        };
+
+        let summary = message_summary(&message, &HashMap::new(), None, 0, None);
+
+        assert_eq!(summary.preview, "[nudge 👋]");
+        assert_eq!(
+            summary.media.map(|media| media.kind).as_deref(),
+            Some("nudge")
+        );
+    }
+
     #[test]
     fn preview_includes_distinct_translation_media_and_task() {
         let now = 1_700_000_000;
@@ -0,0 +1,290 @@
+//! Minisign signatures for release files: the manifest and each archive are
+//! published with a `<file>.minisig` made by the release key.
+//!
+//! Both signature algorithms minisign writes are accepted: legacy `Ed`, which
+//! signs the file itself, and the default prehashed `ED`, which signs its
+//! BLAKE2b-512 digest. The trusted comment is covered by the global signature,
+//! so it is checked too.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ring::signature::{ED25519, UnparsedPublicKey};
+use thiserror::Error;
+
+const UNTRUSTED_PREFIX: &str = "untrusted comment:";
+const TRUSTED_PREFIX: &str = "trusted comment: ";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub(crate) enum SignatureError {
+    #[error("invalid public key")]
+    InvalidKey,
+    #[error("malformed signature file")]
+    Malformed,
+    #[error("unsupported signature algorithm")]
+    UnsupportedAlgorithm,
+    #[error("signed by another key ({0})")]
+    KeyMismatch(String),
+    #[error("signature does not match")]
+    Mismatch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PublicKey {
+    key_id: [u8; 8],
+    key: [u8; 32],
+}
+
+impl PublicKey {
+    /// Parses the base64 key line, alone or as the whole `minisign.pub` file.
+    pub(crate) fn parse(value: &str) -> Result<Self, SignatureError> {
+        let line = value
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with(UNTRUSTED_PREFIX))
+            .ok_or(SignatureError::InvalidKey)?;
+        let bytes = STANDARD
+            .decode(line)
+            .map_err(|_| SignatureError::InvalidKey)?;
+        if bytes.len() != 42 || &bytes[..2] != b"Ed" {
+            return Err(SignatureError::InvalidKey);
+        }
+        Ok(Self {
+            key_id: bytes[2..10].try_into().expect("8 bytes"),
+            key: bytes[10..].try_into().expect("32 bytes"),
+        })
+    }
+
+    /// Checks `signature` (the contents of a `.minisig` file) over `data`,
+    /// returning the trusted comment.
+    pub(crate) fn verify(&self, data: &[u8], signature: &str) -> Result<String, SignatureError> {
+        let mut lines = signature.lines().map(|line| line.trim_end_matches('\r'));
+        let first = lines.next().ok_or(SignatureError::Malformed)?;
+        let signature_line = if first.starts_with(UNTRUSTED_PREFIX) {
+            lines.next().ok_or(SignatureError::Malformed)?
+        } else {
+            first
+        };
+        let trusted_comment = lines
+            .next()
+            .and_then(|line| line.strip_prefix(TRUSTED_PREFIX))
+            .ok_or(SignatureError::Malformed)?;
+        let global_signature = lines
+            .next()
+            .and_then(|line| STANDARD.decode(line.trim()).ok())
+            .filter(|bytes| bytes.len() == 64)
+            .ok_or(SignatureError::Malformed)?;
+        let bytes = STANDARD
+            .decode(signature_line.trim())
+            .map_err(|_| SignatureError::Malformed)?;
+        if bytes.len() != 74 {
+            return Err(SignatureError::Malformed);
+        }
+        let (algorithm, rest) = bytes.split_at(2);
+        let (key_id, signature) = rest.split_at(8);
+        if key_id != self.key_id {
+            return Err(SignatureError::KeyMismatch(key_id_hex(key_id)));
+        }
+
+        let key = UnparsedPublicKey::new(&ED25519, self.key);
+        let signed = match algorithm {
+            b"Ed" => key.verify(data, signature),
+            b"ED" => key.verify(&blake2b_512(data), signature),
+            _ => return Err(SignatureError::UnsupportedAlgorithm),
+        };
+        signed.map_err(|_| SignatureError::Mismatch)?;
+        let mut global = signature.to_vec();
+        global.extend_from_slice(trusted_comment.as_bytes());
+        key.verify(&global, &global_signature)
+            .map_err(|_| SignatureError::Mismatch)?;
+        Ok(trusted_comment.to_string())
+    }
+
+    pub(crate) fn key_id(&self) -> String {
+        key_id_hex(&self.key_id)
+    }
+}
+
+/// Minisign prints key ids as the little-endian number in upper-case hex.
+fn key_id_hex(key_id: &[u8]) -> String {
+    key_id
+        .iter()
+        .rev()
+        .map(|byte| format!("{byte:02X}"))
+        .collect()
+}
+
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const BLAKE2B_SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// Unkeyed BLAKE2b with a 64-byte digest (RFC 7693).
+fn blake2b_512(data: &[u8]) -> [u8; 64] {
+    let mut h = BLAKE2B_IV;
+    h[0] ^= 0x0101_0000 ^ 64;
+    let mut counter: u128 = 0;
+    let mut rest = data;
+    while rest.len() > 128 {
+        counter += 128;
+        blake2b_compress(&mut h, &rest[..128], counter, false);
+        rest = &rest[128..];
+    }
+    let mut last = [0u8; 128];
+    last[..rest.len()].copy_from_slice(rest);
+    counter += rest.len() as u128;
+    blake2b_compress(&mut h, &last, counter, true);
+
+    let mut digest = [0u8; 64];
+    for (chunk, word) in digest.chunks_exact_mut(8).zip(h) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    digest
+}
+
+fn blake2b_compress(h: &mut [u64; 8], block: &[u8], counter: u128, last: bool) {
+    let mut m = [0u64; 16];
+    for (word, chunk) in m.iter_mut().zip(block.chunks_exact(8)) {
+        *word = u64::from_le_bytes(chunk.try_into().expect("8 bytes"));
+    }
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&BLAKE2B_IV);
+    v[12] ^= counter as u64;
+    v[13] ^= (counter >> 64) as u64;
+    if last {
+        v[14] = !v[14];
+    }
+    for round in 0..12 {
+        let s = &BLAKE2B_SIGMA[round % 10];
+        mix(&mut v, [0, 4, 8, 12], m[s[0]], m[s[1]]);
+        mix(&mut v, [1, 5, 9, 13], m[s[2]], m[s[3]]);
+        mix(&mut v, [2, 6, 10, 14], m[s[4]], m[s[5]]);
+        mix(&mut v, [3, 7, 11, 15], m[s[6]], m[s[7]]);
+        mix(&mut v, [0, 5, 10, 15], m[s[8]], m[s[9]]);
+        mix(&mut v, [1, 6, 11, 12], m[s[10]], m[s[11]]);
+        mix(&mut v, [2, 7, 8, 13], m[s[12]], m[s[13]]);
+        mix(&mut v, [3, 4, 9, 14], m[s[14]], m[s[15]]);
+    }
+    for (i, word) in h.iter_mut().enumerate() {
+        *word ^= v[i] ^ v[i + 8];
+    }
+}
+
+fn mix(v: &mut [u64; 16], [a, b, c, d]: [usize; 4], x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    use super::*;
+    use crate::checksum::bytes_to_hex;
+
+    const KEY_ID: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+    fn keypair() -> Ed25519KeyPair {
+        Ed25519KeyPair::from_seed_unchecked(&[7; 32]).unwrap()
+    }
+
+    fn public_key_line(keypair: &Ed25519KeyPair) -> String {
+        let mut bytes = b"Ed".to_vec();
+        bytes.extend_from_slice(&KEY_ID);
+        bytes.extend_from_slice(keypair.public_key().as_ref());
+        STANDARD.encode(bytes)
+    }
+
+    fn sign(keypair: &Ed25519KeyPair, algorithm: &[u8], data: &[u8], comment: &str) -> String {
+        let signature = match algorithm {
+            b"ED" => keypair.sign(&blake2b_512(data)),
+            _ => keypair.sign(data),
+        };
+        let mut line = algorithm.to_vec();
+        line.extend_from_slice(&KEY_ID);
+        line.extend_from_slice(signature.as_ref());
+        let mut global = signature.as_ref().to_vec();
+        global.extend_from_slice(comment.as_bytes());
+        format!(
+            "untrusted comment: signature from minisign secret key\n{}\ntrusted comment: {comment}\n{}\n",
+            STANDARD.encode(line),
+            STANDARD.encode(keypair.sign(&global)),
+        )
+    }
+
+    #[test]
+    fn blake2b_matches_reference_digests() {
+        assert_eq!(
+            bytes_to_hex(&blake2b_512(b"abc")),
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+        );
+        assert_eq!(
+            bytes_to_hex(&blake2b_512(b"")),
+            "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce"
+        );
+        assert_eq!(
+            bytes_to_hex(&blake2b_512(&[b'a'; 300])),
+            "a2ff3040eda405b929c2fc2fd93e8add6ac3bb5369b679bae170ac6956863ca006285f132a868000fc3fae5bc696e5d17fe3fddfb4a342876c40451184742986"
+        );
+    }
+
+    #[test]
+    fn signatures_verify_against_the_release_key() {
+        let keypair = keypair();
+        let key = PublicKey::parse(&format!(
+            "untrusted comment: minisign public key\n{}\n",
+            public_key_line(&keypair)
+        ))
+        .unwrap();
+        assert_eq!(key.key_id(), "0807060504030201");
+        let data = br#"{"version":"0.7.0"}"#;
+
+        for algorithm in [b"ED", b"Ed"] {
+            let signature = sign(&keypair, algorithm, data, "file:manifest.json");
+            assert_eq!(key.verify(data, &signature).unwrap(), "file:manifest.json");
+            assert_eq!(
+                key.verify(br#"{"version":"6.6.6"}"#, &signature),
+                Err(SignatureError::Mismatch)
+            );
+        }
+
+        // The trusted comment is signed too.
+        let signature = sign(&keypair, b"ED", data, "file:manifest.json")
+            .replace("file:manifest.json", "file:other.json");
+        assert_eq!(key.verify(data, &signature), Err(SignatureError::Mismatch));
+
+        let other = Ed25519KeyPair::from_seed_unchecked(&[9; 32]).unwrap();
+        let forged = sign(&other, b"ED", data, "file:manifest.json");
+        assert_eq!(key.verify(data, &forged), Err(SignatureError::Mismatch));
+        assert_eq!(
+            key.verify(data, "not a signature"),
+            Err(SignatureError::Malformed)
+        );
+        assert_eq!(PublicKey::parse("RWQ="), Err(SignatureError::InvalidKey));
+    }
+}
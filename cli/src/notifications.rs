@@ -1,5 +1,9 @@
+use std::collections::{HashMap, HashSet};
+
 use clap::ValueEnum;
+use serde::Serialize;
 
+use crate::chat_output::chat_display_name;
 use crate::output;
 use inline_protocol::proto;
 
@@ -82,7 +86,126 @@ fn notification_mode_label(mode: proto::notification_settings::Mode) -> &'static
     }
 }
 
-pub(crate) fn print_notification_settings(settings: Option<&proto::UserSettings>) {
+/// Chats only have all/mentions/none; `only-mentions` is a global mode.
+pub(crate) fn dialog_mode_from_arg(
+    mode: NotificationModeArg,
+) -> Option<proto::dialog_notification_settings::Mode> {
+    match mode {
+        NotificationModeArg::All => Some(proto::dialog_notification_settings::Mode::All),
+        NotificationModeArg::None => Some(proto::dialog_notification_settings::Mode::None),
+        NotificationModeArg::Mentions => Some(proto::dialog_notification_settings::Mode::Mentions),
+        NotificationModeArg::OnlyMentions => None,
+    }
+}
+
+/// A dialog's notification override, if it has one.
+pub(crate) fn dialog_override_mode(
+    dialog: &proto::Dialog,
+) -> Option<proto::dialog_notification_settings::Mode> {
+    let mode = dialog.notification_settings.as_ref()?.mode?;
+    match proto::dialog_notification_settings::Mode::try_from(mode).ok()? {
+        proto::dialog_notification_settings::Mode::Unspecified => None,
+        mode => Some(mode),
+    }
+}
+
+pub(crate) fn dialog_mode_label(mode: proto::dialog_notification_settings::Mode) -> &'static str {
+    match mode {
+        proto::dialog_notification_settings::Mode::None => "none",
+        proto::dialog_notification_settings::Mode::Mentions => "mentions",
+        _ => "all",
+    }
+}
+
+/// One row of the effective configuration: the global mode, or an override.
+/// The server stores overrides per chat, so a space row stands for every chat
+/// in that space carrying the same override.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct NotificationScope {
+    pub(crate) scope: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) id: Option<i64>,
+    pub(crate) name: String,
+    pub(crate) mode: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) chats: Option<usize>,
+}
+
+pub(crate) fn effective_notification_scopes(
+    settings: Option<&proto::UserSettings>,
+    chats: &proto::GetChatsResult,
+) -> Vec<NotificationScope> {
+    let values = notification_settings_values(
+        settings.and_then(|value| value.notification_settings.as_ref()),
+    );
+    let mut scopes = vec![NotificationScope {
+        scope: "global",
+        id: None,
+        name: "all chats".to_string(),
+        mode: notification_mode_label(values.mode),
+        chats: None,
+    }];
+
+    let overrides: HashMap<i64, proto::dialog_notification_settings::Mode> = chats
+        .dialogs
+        .iter()
+        .filter_map(|dialog| Some((dialog.chat_id?, dialog_override_mode(dialog)?)))
+        .collect();
+
+    let mut covered = HashSet::new();
+    for space in &chats.spaces {
+        let chat_ids: Vec<i64> = chats
+            .chats
+            .iter()
+            .filter(|chat| chat.space_id == Some(space.id))
+            .map(|chat| chat.id)
+            .collect();
+        let Some(mode) = chat_ids.first().and_then(|chat_id| overrides.get(chat_id)) else {
+            continue;
+        };
+        if chat_ids
+            .iter()
+            .all(|chat_id| overrides.get(chat_id) == Some(mode))
+        {
+            covered.extend(chat_ids.iter().copied());
+            scopes.push(NotificationScope {
+                scope: "space",
+                id: Some(space.id),
+                name: space.name.clone(),
+                mode: dialog_mode_label(*mode),
+                chats: Some(chat_ids.len()),
+            });
+        }
+    }
+
+    let users_by_id: HashMap<i64, proto::User> = chats
+        .users
+        .iter()
+        .map(|user| (user.id, user.clone()))
+        .collect();
+    for chat in &chats.chats {
+        let Some(mode) = overrides.get(&chat.id) else {
+            continue;
+        };
+        if covered.contains(&chat.id) {
+            continue;
+        }
+        scopes.push(NotificationScope {
+            scope: "chat",
+            id: Some(chat.id),
+            name: chat_display_name(chat, &users_by_id),
+            mode: dialog_mode_label(*mode),
+            chats: None,
+        });
+    }
+    scopes
+}
+
+pub(crate) fn print_notification_settings(
+    settings: Option<&proto::UserSettings>,
+    scopes: &[NotificationScope],
+) {
     let values = notification_settings_values(
         settings.and_then(|value| value.notification_settings.as_ref()),
     );
@@ -97,6 +220,186 @@ pub(crate) fn print_notification_settings(settings: Option<&proto::UserSettings>
             "no"
         }
     );
+
+    println!();
+    println!("{}", output::style_heading("Effective settings"));
+    let name_width = scopes
+        .iter()
+        .map(|scope| scope.name.chars().count())
+        .max()
+        .unwrap_or_default()
+        .clamp(4, 32);
+    println!(
+        "{}",
+        output::style_heading(&format!(
+            "  {:<6}  {:>8}  {:<name_width$}  {:<13}  chats",
+            "scope", "id", "name", "mode"
+        ))
+    );
+    for scope in scopes {
+        let name: String = scope.name.chars().take(name_width).collect();
+        println!(
+            "  {:<6}  {:>8}  {:<name_width$}  {:<13}  {}",
+            scope.scope,
+            scope
+                .id
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            name,
+            scope.mode,
+            scope
+                .chats
+                .map(|chats| chats.to_string())
+                .unwrap_or_else(|| "-".to_string())
+        );
+    }
+    if scopes.len() == 1 {
+        println!("  No space or chat overrides.");
+    }
+}
+
+/// One zen-mode custom rule. The server stores rules as a single string, one
+/// per line; lines written by `rules add` use `chat:`, `from:`, and
+/// `keyword:` conditions, and anything else is kept as a plain-language rule.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct NotificationRule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) chat_id: Option<i64>,
+    /// `@username` or a user id.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) keyword: Option<String>,
+    /// Set for rules that aren't in the condition syntax.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) text: Option<String>,
+}
+
+impl NotificationRule {
+    /// The rule as it is stored in `zen_mode_custom_rules`.
+    pub(crate) fn to_line(&self) -> String {
+        if let Some(text) = &self.text {
+            return text.clone();
+        }
+        let mut parts = Vec::new();
+        if let Some(chat_id) = self.chat_id {
+            parts.push(format!("chat:{chat_id}"));
+        }
+        if let Some(from) = &self.from {
+            parts.push(format!("from:{}", quote_rule_value(from)));
+        }
+        if let Some(keyword) = &self.keyword {
+            parts.push(format!("keyword:{}", quote_rule_value(keyword)));
+        }
+        parts.join(" ")
+    }
+
+    pub(crate) fn describe(&self) -> String {
+        if let Some(text) = &self.text {
+            return text.clone();
+        }
+        let mut description = "Notify for messages".to_string();
+        if let Some(chat_id) = self.chat_id {
+            description.push_str(&format!(" in chat {chat_id}"));
+        }
+        if let Some(from) = &self.from {
+            let from = match from.parse::<i64>() {
+                Ok(user_id) => format!("user {user_id}"),
+                Err(_) => from.clone(),
+            };
+            description.push_str(&format!(" from {from}"));
+        }
+        if let Some(keyword) = &self.keyword {
+            description.push_str(&format!(" containing \"{keyword}\""));
+        }
+        description
+    }
+}
+
+pub(crate) fn parse_notification_rules(custom_rules: Option<&str>) -> Vec<NotificationRule> {
+    custom_rules
+        .unwrap_or_default()
+        .lines()
+        .map(|line| line.trim().trim_start_matches("- ").trim())
+        .filter(|line| !line.is_empty())
+        .map(parse_notification_rule)
+        .collect()
+}
+
+pub(crate) fn format_notification_rules(rules: &[NotificationRule]) -> String {
+    rules
+        .iter()
+        .map(NotificationRule::to_line)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn parse_notification_rule(line: &str) -> NotificationRule {
+    let text_rule = || NotificationRule {
+        text: Some(line.to_string()),
+        ..Default::default()
+    };
+    let Some(tokens) = split_rule_tokens(line) else {
+        return text_rule();
+    };
+    let mut rule = NotificationRule::default();
+    for token in tokens {
+        let Some((key, value)) = token.split_once(':') else {
+            return text_rule();
+        };
+        let slot = match key {
+            "chat" => match value.parse::<i64>() {
+                Ok(chat_id) if chat_id > 0 && rule.chat_id.is_none() => {
+                    rule.chat_id = Some(chat_id);
+                    continue;
+                }
+                _ => return text_rule(),
+            },
+            "from" => &mut rule.from,
+            "keyword" => &mut rule.keyword,
+            _ => return text_rule(),
+        };
+        if slot.is_some() || value.is_empty() {
+            return text_rule();
+        }
+        *slot = Some(value.to_string());
+    }
+    rule
+}
+
+/// Splits on whitespace outside double quotes and drops the quotes. `None`
+/// for unbalanced quotes.
+fn split_rule_tokens(line: &str) -> Option<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for ch in line.chars() {
+        match ch {
+            '"' => quoted = !quoted,
+            ch if ch.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            ch => current.push(ch),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    Some(tokens)
+}
+
+fn quote_rule_value(value: &str) -> String {
+    if value.chars().any(char::is_whitespace) {
+        format!("\"{value}\"")
+    } else {
+        value.to_string()
+    }
 }
 
 #[cfg(test)]
@@ -159,4 +462,84 @@ mod tests {
         assert_eq!(values.mode, proto::notification_settings::Mode::Mentions);
         assert!(!values.disable_dm_notifications);
     }
+
+    #[test]
+    fn uniform_chat_overrides_collapse_into_a_space_row() {
+        let dialog =
+            |chat_id: i64, mode: proto::dialog_notification_settings::Mode| proto::Dialog {
+                chat_id: Some(chat_id),
+                notification_settings: Some(proto::DialogNotificationSettings {
+                    mode: Some(mode as i32),
+                }),
+                ..Default::default()
+            };
+        let chat = |id: i64, space_id: i64, title: &str| proto::Chat {
+            id,
+            space_id: Some(space_id),
+            title: title.to_string(),
+            ..Default::default()
+        };
+        let chats = proto::GetChatsResult {
+            dialogs: vec![
+                dialog(1, proto::dialog_notification_settings::Mode::Mentions),
+                dialog(2, proto::dialog_notification_settings::Mode::Mentions),
+                dialog(3, proto::dialog_notification_settings::Mode::None),
+            ],
+            chats: vec![
+                chat(1, 31, "Launch"),
+                chat(2, 31, "Design"),
+                chat(3, 32, "Random"),
+                chat(4, 32, "General"),
+            ],
+            spaces: vec![
+                proto::Space {
+                    id: 31,
+                    name: "Eng".to_string(),
+                    ..Default::default()
+                },
+                proto::Space {
+                    id: 32,
+                    name: "Social".to_string(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let scopes = effective_notification_scopes(None, &chats);
+
+        let rows: Vec<_> = scopes
+            .iter()
+            .map(|scope| (scope.scope, scope.id, scope.mode, scope.chats))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("global", None, "all", None),
+                ("space", Some(31), "mentions", Some(2)),
+                ("chat", Some(3), "none", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn notification_rules_round_trip_and_keep_plain_text() {
+        let stored = "chat:7 from:@sam\n- keyword:\"ship it\"\n\nOnly ping me about outages";
+
+        let rules = parse_notification_rules(Some(stored));
+
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[0].chat_id, Some(7));
+        assert_eq!(rules[0].from.as_deref(), Some("@sam"));
+        assert_eq!(rules[1].keyword.as_deref(), Some("ship it"));
+        assert_eq!(
+            rules[1].describe(),
+            "Notify for messages containing \"ship it\""
+        );
+        assert_eq!(rules[2].text.as_deref(), Some("Only ping me about outages"));
+        assert_eq!(
+            format_notification_rules(&rules),
+            "chat:7 from:@sam\nkeyword:\"ship it\"\nOnly ping me about outages"
+        );
+    }
 }
@@ -0,0 +1,91 @@
+//! `--notify-done` for long commands (exports, batch downloads, imports), so
+//! people can switch windows and hear about it when the work is over.
+//!
+//! The terminal bell rings on stderr when it is a terminal, and on macOS a
+//! notification is posted through `osascript` with the outcome and counts.
+//! Both are best effort: a missing `osascript` never fails the command.
+//! `inline alerts run` posts its `--notify` alerts through [`notify`] too.
+
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// One-line notification text, e.g. "messages export finished: 1204 messages".
+pub(crate) fn message(command: &str, summary: Option<&str>, error: Option<&str>) -> String {
+    match (error, summary) {
+        (Some(error), _) => format!("{command} failed: {}", first_line(error)),
+        (None, Some(summary)) => format!("{command} finished: {summary}"),
+        (None, None) => format!("{command} finished"),
+    }
+}
+
+pub(crate) fn notify(message: &str) {
+    let mut stderr = io::stderr();
+    if stderr.is_terminal() {
+        let _ = stderr.write_all(b"\x07");
+        let _ = stderr.flush();
+    }
+    if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {} with title \"Inline\"",
+            applescript_string(message)
+        );
+        let status = Command::new("osascript")
+            .args(["-e", &script])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        if let Err(err) = status {
+            log::debug!("could not post notification: {err}");
+        }
+    }
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or_default()
+}
+
+fn applescript_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(ch);
+            }
+            '\n' | '\r' => quoted.push(' '),
+            _ => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_report_outcome_and_quote_for_applescript() {
+        assert_eq!(
+            message("messages export", Some("1204 messages"), None),
+            "messages export finished: 1204 messages"
+        );
+        assert_eq!(
+            message(
+                "import slack",
+                Some("ignored"),
+                Some("connection closed\ncaused by: eof")
+            ),
+            "import slack failed: connection closed"
+        );
+        assert_eq!(
+            message("messages download", None, None),
+            "messages download finished"
+        );
+        assert_eq!(
+            applescript_string("saved \"a\\b\"\ndone"),
+            r#""saved \"a\\b\" done""#
+        );
+    }
+}
@@ -1,10 +1,18 @@
+use dialoguer::console::Term;
 use serde::Serialize;
 use std::collections::HashMap;
 use std::env;
 use std::io::{self, IsTerminal};
+use std::sync::OnceLock;
 use thiserror::Error;
-use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
+use crate::activity::{ACTIVITY_DAYS, sparkline};
+use crate::chat_output::{ReadStateOutput, SpaceChatsOutput};
+use crate::media::{MediaEntry, MediaKindArg};
+use crate::mentions::MentionsOutput;
+use crate::output_schema::OUTPUT_SCHEMA_VERSION;
+use crate::text_width::{cluster_width, grapheme_indices, isolate_rtl};
+use crate::usage::{ChatUsage, UsageOutput};
 use inline_protocol::proto;
 
 #[derive(Debug, Error)]
@@ -13,12 +21,90 @@ pub enum OutputError {
     Json(#[from] serde_json::Error),
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum JsonFormat {
     Pretty,
     Compact,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+static COLOR_MODE: OnceLock<ColorMode> = OnceLock::new();
+
+/// Sets the configured color preference. `NO_COLOR` and `CLICOLOR_FORCE` still win.
+pub fn set_color_mode(mode: ColorMode) {
+    let _ = COLOR_MODE.set(mode);
+}
+
+static WRAP_TEXT: OnceLock<bool> = OnceLock::new();
+
+/// With `--wrap`, message text continues on extra rows instead of being cut
+/// at the column width.
+pub fn set_wrap_text(wrap: bool) {
+    let _ = WRAP_TEXT.set(wrap);
+}
+
+/// A pinned `--output-version` and the command path (e.g. `chats list`) its
+/// shims are looked up by.
+static OUTPUT_VERSION: OnceLock<(u32, String)> = OnceLock::new();
+
+/// Rewrites one command's JSON from layout `from` back to layout `from - 1`.
+/// When a summary struct changes shape, bump `OUTPUT_SCHEMA_VERSION` and add a
+/// shim here that undoes the change, so `--output-version` keeps old scripts
+/// working.
+struct OutputShim {
+    command: &'static str,
+    from: u32,
+    apply: fn(&mut serde_json::Value),
+}
+
+const OUTPUT_SHIMS: &[OutputShim] = &[];
+
+/// `--output-version N`: emit JSON in layout N instead of the current one.
+/// Returns the supported range when N is outside it.
+pub fn set_output_version(
+    version: u32,
+    command: String,
+) -> Result<(), std::ops::RangeInclusive<u32>> {
+    let supported = 1..=OUTPUT_SCHEMA_VERSION;
+    if !supported.contains(&version) {
+        return Err(supported);
+    }
+    let _ = OUTPUT_VERSION.set((version, command));
+    Ok(())
+}
+
+/// Applies the shims that take `value` from the current layout down to
+/// `version`, newest first.
+fn downgrade_json(
+    value: &mut serde_json::Value,
+    command: &str,
+    version: u32,
+    shims: &[OutputShim],
+) {
+    for from in (version + 1..=OUTPUT_SCHEMA_VERSION).rev() {
+        for shim in shims {
+            if shim.from == from && shim.command == command {
+                (shim.apply)(value);
+            }
+        }
+    }
+}
+
+static TABLE_WIDTH: OnceLock<u16> = OnceLock::new();
+
+/// `--width N`: lay tables out for N columns instead of the terminal's width.
+pub fn set_table_width(width: Option<u16>) {
+    if let Some(width) = width {
+        let _ = TABLE_WIDTH.set(width);
+    }
+}
+
 #[derive(Clone, Copy)]
 struct FlexibleColumn {
     header: &'static str,
@@ -44,6 +130,14 @@ pub struct ChatListItem {
     pub space: Option<SpaceSummary>,
     pub space_name: Option<String>,
     pub unread_count: Option<i32>,
+    /// The chat's notification override (`all`, `mentions`, `none`), or
+    /// `default` when it follows the global mode.
+    pub notifications: &'static str,
+    /// True when notifications for the chat are turned off (`none`).
+    pub muted: bool,
+    /// Messages per day over the last week, oldest first (`--activity`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activity: Option<[usize; ACTIVITY_DAYS]>,
     pub last_message: Option<MessageSummary>,
     pub last_message_line: Option<String>,
     pub last_message_relative_date: Option<String>,
@@ -129,6 +223,26 @@ pub struct MessageListOutput {
     pub peer_name: Option<String>,
 }
 
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MediaListOutput {
+    pub(crate) kind: MediaKindArg,
+    pub(crate) peer: Option<PeerSummary>,
+    pub(crate) peer_name: Option<String>,
+    pub(crate) items: Vec<MediaListItem>,
+    /// Pass as `--offset-id` to continue with older messages.
+    pub(crate) next_offset_id: Option<i64>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MediaListItem {
+    #[serde(flatten)]
+    pub(crate) entry: MediaEntry,
+    pub(crate) sender_name: String,
+    pub(crate) relative_date: String,
+}
+
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SpaceMemberSummary {
@@ -137,6 +251,9 @@ pub struct SpaceMemberSummary {
     pub display_name: String,
     pub role: String,
     pub can_access_public_chats: bool,
+    /// `invited` until an email or phone invitee finishes signing up, then
+    /// `active`.
+    pub status: &'static str,
 }
 
 #[derive(Clone, Serialize)]
@@ -152,6 +269,11 @@ pub struct ChatParticipantSummary {
     pub user: Option<UserSummary>,
     pub display_name: String,
     pub relative_date: String,
+    /// `online` or `offline`; `None` when the server sends no presence.
+    pub presence: Option<&'static str>,
+    /// `now` while online, the relative last-online date when the user shares
+    /// it, otherwise `-`.
+    pub last_seen: String,
 }
 
 #[derive(Clone, Serialize)]
@@ -174,6 +296,20 @@ pub fn resolve_json_format(pretty: bool, compact: bool) -> JsonFormat {
 pub fn json_string<T: Serialize + ?Sized>(
     value: &T,
     format: JsonFormat,
+) -> Result<String, OutputError> {
+    if let Some((version, command)) = OUTPUT_VERSION.get()
+        && *version < OUTPUT_SCHEMA_VERSION
+    {
+        let mut value = serde_json::to_value(value)?;
+        downgrade_json(&mut value, command, *version, OUTPUT_SHIMS);
+        return render_json(&value, format);
+    }
+    render_json(value, format)
+}
+
+fn render_json<T: Serialize + ?Sized>(
+    value: &T,
+    format: JsonFormat,
 ) -> Result<String, OutputError> {
     let payload = match format {
         JsonFormat::Pretty => serde_json::to_string_pretty(value)?,
@@ -273,7 +409,41 @@ pub fn print_chat_list(
             last_width = last_width.max(display_width(line));
         }
     }
-    let widths = flexible_widths(
+    // `--activity`: sparkline, then the week's total.
+    let busiest_day = output
+        .items
+        .iter()
+        .filter_map(|item| item.activity)
+        .flat_map(|days| days.into_iter())
+        .max();
+    let total_width = output
+        .items
+        .iter()
+        .filter_map(|item| item.activity)
+        .map(|days| days.iter().sum::<usize>().to_string().len())
+        .max()
+        .unwrap_or(0)
+        .max(3);
+    let activity_width = busiest_day.map_or(0, |_| ACTIVITY_DAYS + 1 + total_width);
+    let fixed_width = if busiest_day.is_some() {
+        fixed_table_width(&[6, 6, 8, activity_width], 7)
+    } else {
+        fixed_table_width(&[6, 6, 8], 6)
+    };
+    let activity_cell = |item: &ChatListItem| match (item.activity, busiest_day) {
+        (Some(days), Some(max)) => format!(
+            "{} {:>total_width$}  ",
+            sparkline(&days, max),
+            days.iter().sum::<usize>()
+        ),
+        (None, Some(_)) => format!("{}  ", pad_right("?", activity_width)),
+        _ => String::new(),
+    };
+    let activity_header = match busiest_day {
+        Some(_) => format!("{}  ", header_right("7d activity", activity_width)),
+        None => String::new(),
+    };
+    let mut widths = flexible_widths(
         &[
             FlexibleColumn {
                 header: "name",
@@ -294,18 +464,21 @@ pub fn print_chat_list(
                 max_width: 96,
             },
         ],
-        fixed_table_width(&[6, 6], 5),
+        fixed_width,
     );
+    fill_remaining_width(&mut widths, 2, last_width, fixed_width, terminal_columns());
     let name_width = widths[0];
     let space_width = widths[1];
     let last_width = widths[2];
 
     println!(
-        "{}  {}  {}  {}  {}",
+        "{}  {}  {}  {}  {}  {}{}",
         header_left("id", 6),
         header_right("name", name_width),
         header_right("space", space_width),
         header_left("unread", 6),
+        header_right("notify", 8),
+        activity_header,
         header_right("last message", last_width),
     );
 
@@ -314,21 +487,30 @@ pub fn print_chat_list(
         let space = item.space_name.as_deref().unwrap_or("-");
         let name_lines = wrap_display_lines(&item.display_name, name_width, 2);
         let name = name_lines.first().map(String::as_str).unwrap_or("");
+        let notify = match item.notifications {
+            "none" => "muted",
+            "default" => "-",
+            mode => mode,
+        };
         println!(
-            "{}  {}  {}  {}  {}",
+            "{}  {}  {}  {}  {}  {}{}",
             pad_left(&item.chat.id.to_string(), 6),
             pad_right(name, name_width),
             pad_right(&truncate_display(space, space_width), space_width),
             pad_left(&item.unread_count.unwrap_or(0).to_string(), 6),
+            pad_right(notify, 8),
+            activity_cell(item),
             pad_right(&truncate_display(preview, last_width), last_width),
         );
         for continuation in name_lines.iter().skip(1) {
             println!(
-                "{}  {}  {}  {}  {}",
+                "{}  {}  {}  {}  {}  {}{}",
                 pad_left("", 6),
                 pad_right(continuation, name_width),
                 pad_right("", space_width),
                 pad_left("", 6),
+                pad_right("", 8),
+                pad_right("", activity_width + if activity_width > 0 { 2 } else { 0 }),
                 pad_right("", last_width),
             );
         }
@@ -624,22 +806,23 @@ pub fn print_space_members(
                 max_width: 12,
             },
         ],
-        fixed_table_width(&[6, 6, 6], 5),
+        fixed_table_width(&[6, 6, 6, 7], 6),
     );
     let name_width = widths[0];
     let role_width = widths[1];
 
     println!(
-        "{}  {}  {}  {}  {}",
+        "{}  {}  {}  {}  {}  {}",
         header_left("user", 6),
         header_left("member", 6),
         header_right("name", name_width),
         header_right("role", role_width),
         header_right("public", 6),
+        header_right("status", 7),
     );
     for member in &output.members {
         println!(
-            "{}  {}  {}  {}  {}",
+            "{}  {}  {}  {}  {}  {}",
             pad_left(&member.member.user_id.to_string(), 6),
             pad_left(&member.member.id.to_string(), 6),
             pad_right(
@@ -655,6 +838,7 @@ pub fn print_space_members(
                 },
                 6
             ),
+            pad_right(member.status, 7),
         );
     }
     Ok(())
@@ -679,12 +863,18 @@ pub(crate) fn build_space_members_output(
                 .map(user_display_name)
                 .unwrap_or_else(|| format!("user {}", member.user_id));
             let role = member_role_label(&member);
+            let status = if user.as_ref().and_then(|user| user.pending_setup) == Some(true) {
+                "invited"
+            } else {
+                "active"
+            };
             SpaceMemberSummary {
                 member,
                 user: user.as_ref().map(user_summary),
                 display_name,
                 role,
                 can_access_public_chats,
+                status,
             }
         })
         .collect::<Vec<_>>();
@@ -713,6 +903,11 @@ pub fn print_chat_participants(
         return print_json(output, json_format);
     }
 
+    // Only servers that expose presence get the status columns.
+    let show_presence = output
+        .participants
+        .iter()
+        .any(|participant| participant.presence.is_some());
     let mut name_width = display_width("name");
     let mut joined_width = display_width("joined");
     for participant in &output.participants {
@@ -734,19 +929,31 @@ pub fn print_chat_participants(
                 max_width: 10,
             },
         ],
-        fixed_table_width(&[6], 3),
+        if show_presence {
+            fixed_table_width(&[6, 7, 9], 5)
+        } else {
+            fixed_table_width(&[6], 3)
+        },
     );
     let name_width = widths[0];
     let joined_width = widths[1];
 
-    println!(
+    let mut header = format!(
         "{}  {}  {}",
         header_left("user", 6),
         header_right("name", name_width),
         header_right("joined", joined_width),
     );
+    if show_presence {
+        header.push_str(&format!(
+            "  {}  {}",
+            header_right("status", 7),
+            header_right("last seen", 9)
+        ));
+    }
+    println!("{header}");
     for participant in &output.participants {
-        println!(
+        let mut line = format!(
             "{}  {}  {}",
             pad_left(&participant.participant.user_id.to_string(), 6),
             pad_right(
@@ -755,10 +962,37 @@ pub fn print_chat_participants(
             ),
             pad_right(&participant.relative_date, joined_width),
         );
+        if show_presence {
+            line.push_str(&format!(
+                "  {}  {}",
+                pad_right(participant.presence.unwrap_or("-"), 7),
+                pad_right(&participant.last_seen, 9)
+            ));
+        }
+        println!("{}", line.trim_end());
     }
     Ok(())
 }
 
+/// `online` or `offline` from the user's status, if the server sent one.
+pub(crate) fn user_presence(user: &proto::User) -> Option<&'static str> {
+    match user.status.as_ref()?.online() {
+        proto::user_status::Status::Online => Some("online"),
+        proto::user_status::Status::Offline => Some("offline"),
+        proto::user_status::Status::Unknown => None,
+    }
+}
+
+fn user_last_seen(user: Option<&proto::User>, now: i64) -> String {
+    if user.and_then(user_presence) == Some("online") {
+        return "now".to_string();
+    }
+    user.and_then(|user| user.status.as_ref())
+        .and_then(|status| status.last_online.as_ref())
+        .and_then(|last_online| last_online.date)
+        .map_or_else(|| "-".to_string(), |date| format_relative_date(date, now))
+}
+
 pub(crate) fn build_chat_participants_output(
     result: proto::GetChatParticipantsResult,
     now: i64,
@@ -783,6 +1017,8 @@ pub(crate) fn build_chat_participants_output(
                 user: user.as_ref().map(user_summary),
                 display_name,
                 relative_date,
+                presence: user.as_ref().and_then(user_presence),
+                last_seen: user_last_seen(user.as_ref(), now),
             }
         })
         .collect::<Vec<_>>();
@@ -812,19 +1048,52 @@ pub fn print_messages(
             println!("{}", style_heading(&format!("Messages for {}", peer_name)));
         }
     }
+    print_message_rows(&output.items, &[]);
+    Ok(())
+}
+
+/// Prints search hits with their surrounding messages, one table per group.
+/// Hit rows are marked with `>` before the id.
+pub(crate) fn print_message_context_groups(groups: &[(Vec<i64>, MessageListOutput)]) {
+    if groups.is_empty() {
+        println!("No matching messages.");
+        return;
+    }
+    for (index, (hit_ids, output)) in groups.iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        let hits = hit_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!(
+            "{}",
+            style_heading(&format!(
+                "── Match {} of {} · message {hits} ──",
+                index + 1,
+                groups.len()
+            ))
+        );
+        print_message_rows(&output.items, hit_ids);
+    }
+}
 
+fn print_message_rows(items: &[MessageSummary], marked_ids: &[i64]) {
     let mut from_width = display_width("from");
     let mut when_width = display_width("when");
-    for item in &output.items {
+    for item in items {
         from_width = from_width.max(display_width(&item.sender_name));
         when_width = when_width.max(display_width(&item.relative_date));
     }
     let when_width = when_width.min(10);
     let mut text_width = display_width("text");
-    for item in &output.items {
+    for item in items {
         text_width = text_width.max(display_width(&item.preview));
     }
-    let widths = flexible_widths(
+    let fixed_width = fixed_table_width(&[6, when_width], 4);
+    let mut widths = flexible_widths(
         &[
             FlexibleColumn {
                 header: "from",
@@ -839,8 +1108,9 @@ pub fn print_messages(
                 max_width: 96,
             },
         ],
-        fixed_table_width(&[6, when_width], 4),
+        fixed_width,
     );
+    fill_remaining_width(&mut widths, 1, text_width, fixed_width, terminal_columns());
     let from_width = widths[0];
     let text_width = widths[1];
 
@@ -851,17 +1121,388 @@ pub fn print_messages(
         header_right("from", from_width),
         header_right("text", text_width),
     );
-    for item in &output.items {
-        let text = truncate_display(&item.preview, text_width);
+    let wrap = WRAP_TEXT.get().copied().unwrap_or(false);
+    for item in items {
+        let lines = if wrap {
+            wrap_display_lines(&item.preview, text_width, usize::MAX)
+        } else {
+            vec![truncate_display(&item.preview, text_width)]
+        };
+        let id = if marked_ids.contains(&item.message.id) {
+            format!(">{}", item.message.id)
+        } else {
+            item.message.id.to_string()
+        };
         println!(
             "{}  {}  {}  {}",
-            pad_left(&item.message.id.to_string(), 6),
+            pad_left(&id, 6),
             pad_right(&item.relative_date, when_width),
             pad_right(&truncate_display(&item.sender_name, from_width), from_width),
-            pad_right(&text, text_width),
+            pad_right(lines.first().map(String::as_str).unwrap_or(""), text_width),
+        );
+        for continuation in lines.iter().skip(1) {
+            println!(
+                "{}  {}  {}  {}",
+                pad_left("", 6),
+                pad_right("", when_width),
+                pad_right("", from_width),
+                pad_right(continuation, text_width),
+            );
+        }
+    }
+}
+
+pub(crate) fn print_mentions(output: &MentionsOutput) {
+    if output.mentions.is_empty() {
+        if output.unreplied_only {
+            println!("No unreplied mentions.");
+        } else {
+            println!("No mentions.");
+        }
+        return;
+    }
+
+    let mut when_width = display_width("when");
+    let mut chat_width = display_width("chat");
+    let mut from_width = display_width("from");
+    let mut text_width = display_width("text");
+    for mention in &output.mentions {
+        when_width = when_width.max(display_width(&mention.relative_date));
+        chat_width = chat_width.max(display_width(&mention.chat_name));
+        from_width = from_width.max(display_width(&mention.sender_name));
+        text_width = text_width.max(display_width(&mention.preview));
+    }
+    let when_width = when_width.min(10);
+    let replied_width = display_width("replied");
+    let widths = flexible_widths(
+        &[
+            FlexibleColumn {
+                header: "chat",
+                content_width: chat_width,
+                min_width: 8,
+                max_width: 24,
+            },
+            FlexibleColumn {
+                header: "from",
+                content_width: from_width,
+                min_width: 8,
+                max_width: 18,
+            },
+            FlexibleColumn {
+                header: "text",
+                content_width: text_width,
+                min_width: 16,
+                max_width: 80,
+            },
+        ],
+        fixed_table_width(&[6, when_width, replied_width], 6),
+    );
+    let (chat_width, from_width, text_width) = (widths[0], widths[1], widths[2]);
+
+    println!(
+        "{}  {}  {}  {}  {}  {}",
+        header_left("id", 6),
+        header_right("when", when_width),
+        header_right("chat", chat_width),
+        header_right("from", from_width),
+        header_right("replied", replied_width),
+        header_right("text", text_width),
+    );
+    for mention in &output.mentions {
+        println!(
+            "{}  {}  {}  {}  {}  {}",
+            pad_left(&mention.message_id.to_string(), 6),
+            pad_right(&mention.relative_date, when_width),
+            pad_right(
+                &truncate_display(&mention.chat_name, chat_width),
+                chat_width
+            ),
+            pad_right(
+                &truncate_display(&mention.sender_name, from_width),
+                from_width
+            ),
+            pad_right(if mention.replied { "yes" } else { "no" }, replied_width),
+            pad_right(&truncate_display(&mention.preview, text_width), text_width),
+        );
+    }
+}
+
+pub(crate) fn print_media_list(output: &MediaListOutput) {
+    if let Some(peer_name) = &output.peer_name {
+        println!(
+            "{}",
+            style_heading(&format!(
+                "{} for {}",
+                match output.kind {
+                    MediaKindArg::Photo => "Photos",
+                    MediaKindArg::Video => "Videos",
+                    MediaKindArg::File => "Files",
+                    MediaKindArg::Link => "Links",
+                },
+                peer_name
+            ))
+        );
+    }
+    if output.items.is_empty() {
+        println!("No matching messages.");
+        return;
+    }
+
+    let size_width = 8;
+    let mut when_width = display_width("when");
+    let mut from_width = display_width("from");
+    let mut name_width = display_width("name");
+    let mut url_width = display_width("url");
+    for item in &output.items {
+        when_width = when_width.max(display_width(&item.relative_date));
+        from_width = from_width.max(display_width(&item.sender_name));
+        name_width = name_width.max(display_width(item.entry.name.as_deref().unwrap_or("-")));
+        url_width = url_width.max(display_width(item.entry.url.as_deref().unwrap_or("-")));
+    }
+    let when_width = when_width.min(10);
+    let widths = flexible_widths(
+        &[
+            FlexibleColumn {
+                header: "from",
+                content_width: from_width,
+                min_width: 8,
+                max_width: 18,
+            },
+            FlexibleColumn {
+                header: "name",
+                content_width: name_width,
+                min_width: 12,
+                max_width: 48,
+            },
+            FlexibleColumn {
+                header: "url",
+                content_width: url_width,
+                min_width: 16,
+                max_width: 120,
+            },
+        ],
+        fixed_table_width(&[6, when_width, size_width], 6),
+    );
+    let (from_width, name_width, url_width) = (widths[0], widths[1], widths[2]);
+
+    println!(
+        "{}  {}  {}  {}  {}  {}",
+        header_left("id", 6),
+        header_right("when", when_width),
+        header_right("from", from_width),
+        header_right("name", name_width),
+        header_left("size", size_width),
+        header_right("url", url_width),
+    );
+    for item in &output.items {
+        let size = item
+            .entry
+            .size
+            .map(format_bytes)
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{}  {}  {}  {}  {}  {}",
+            pad_left(&item.entry.message_id.to_string(), 6),
+            pad_right(&item.relative_date, when_width),
+            pad_right(&truncate_display(&item.sender_name, from_width), from_width),
+            pad_right(
+                &truncate_display(item.entry.name.as_deref().unwrap_or("-"), name_width),
+                name_width
+            ),
+            pad_left(&size, size_width),
+            pad_right(
+                &truncate_display(item.entry.url.as_deref().unwrap_or("-"), url_width),
+                url_width
+            ),
+        );
+    }
+    if let Some(offset_id) = output.next_offset_id {
+        println!();
+        println!("More may be older; continue with --offset-id {offset_id}.");
+    }
+}
+
+pub(crate) fn print_space_chats(output: &SpaceChatsOutput, now: i64) {
+    if output.chats.is_empty() {
+        println!("No chats found in space {}.", output.space_id);
+        return;
+    }
+    let title = |chat: &crate::chat_output::SpaceChat| match &chat.emoji {
+        Some(emoji) => format!("{emoji} {}", chat.title),
+        None => chat.title.clone(),
+    };
+    let last = |chat: &crate::chat_output::SpaceChat| {
+        chat.last_message_date
+            .map_or_else(|| "-".to_string(), |date| format_relative_date(date, now))
+    };
+    let mut title_width = display_width("chat");
+    let mut last_width = display_width("last message");
+    for chat in &output.chats {
+        title_width = title_width.max(display_width(&title(chat)));
+        last_width = last_width.max(display_width(&last(chat)));
+    }
+    let widths = flexible_widths(
+        &[FlexibleColumn {
+            header: "chat",
+            content_width: title_width,
+            min_width: 12,
+            max_width: 48,
+        }],
+        fixed_table_width(&[6, 7, 6, 6, last_width], 6),
+    );
+    let title_width = widths[0];
+
+    println!(
+        "{}  {}  {}  {}  {}  {}",
+        header_left("id", 6),
+        header_right("chat", title_width),
+        header_right("access", 7),
+        header_right("joined", 6),
+        header_left("unread", 6),
+        header_right("last message", last_width),
+    );
+    for chat in &output.chats {
+        println!(
+            "{}  {}  {}  {}  {}  {}",
+            pad_left(&chat.chat_id.to_string(), 6),
+            pad_right(&truncate_display(&title(chat), title_width), title_width),
+            pad_right(if chat.public { "public" } else { "private" }, 7),
+            pad_right(if chat.joined { "yes" } else { "no" }, 6),
+            pad_left(&chat.unread_count.to_string(), 6),
+            last(chat),
+        );
+    }
+}
+
+pub(crate) fn print_read_states(output: &ReadStateOutput) {
+    if output.dialogs.is_empty() {
+        println!("No chats found.");
+        return;
+    }
+    let optional_id = |id: Option<i64>| id.map_or_else(|| "-".to_string(), |id| id.to_string());
+    let mut title_width = display_width("chat");
+    let mut read_width = display_width("read up to");
+    let mut last_width = display_width("last");
+    for state in &output.dialogs {
+        title_width = title_width.max(display_width(&state.title));
+        read_width = read_width.max(display_width(&optional_id(state.read_max_id)));
+        last_width = last_width.max(display_width(&optional_id(state.last_message_id)));
+    }
+    let widths = flexible_widths(
+        &[FlexibleColumn {
+            header: "chat",
+            content_width: title_width,
+            min_width: 12,
+            max_width: 48,
+        }],
+        fixed_table_width(&[6, 6, read_width, last_width, 6], 6),
+    );
+    let title_width = widths[0];
+
+    println!(
+        "{}  {}  {}  {}  {}  {}",
+        header_left("id", 6),
+        header_right("chat", title_width),
+        header_left("unread", 6),
+        header_left("read up to", read_width),
+        header_left("last", last_width),
+        header_right("marked", 6),
+    );
+    for state in &output.dialogs {
+        println!(
+            "{}  {}  {}  {}  {}  {}",
+            pad_left(&state.chat_id.to_string(), 6),
+            pad_right(&truncate_display(&state.title, title_width), title_width),
+            pad_left(&state.unread_count.to_string(), 6),
+            pad_left(&optional_id(state.read_max_id), read_width),
+            pad_left(&optional_id(state.last_message_id), last_width),
+            if state.unread_mark { "yes" } else { "" },
         );
     }
-    Ok(())
+}
+
+pub(crate) fn print_usage(output: &UsageOutput) {
+    if output.chats.is_empty() {
+        println!("No chats to report.");
+        return;
+    }
+    let message_count = |chat: &ChatUsage| {
+        if chat.complete {
+            chat.messages.to_string()
+        } else {
+            format!("{}+", chat.messages)
+        }
+    };
+    let mut title_width = display_width("chat");
+    let mut messages_width = display_width("messages");
+    let mut size_width = display_width("size");
+    for chat in &output.chats {
+        title_width = title_width.max(display_width(&chat.title));
+        messages_width = messages_width.max(display_width(&message_count(chat)));
+        size_width = size_width.max(display_width(&format_bytes(chat.media_bytes)));
+    }
+    let widths = flexible_widths(
+        &[FlexibleColumn {
+            header: "chat",
+            content_width: title_width,
+            min_width: 12,
+            max_width: 48,
+        }],
+        fixed_table_width(&[6, messages_width, 6, size_width, 6], 6),
+    );
+    let title_width = widths[0];
+
+    println!(
+        "{}  {}  {}  {}  {}  {}",
+        header_left("id", 6),
+        header_right("chat", title_width),
+        header_left("messages", messages_width),
+        header_left("media", 6),
+        header_left("size", size_width),
+        header_left("share", 6),
+    );
+    for chat in &output.chats {
+        if let Some(error) = &chat.error {
+            println!(
+                "{}  {}  {error}",
+                pad_left(&chat.chat_id.to_string(), 6),
+                pad_right(&truncate_display(&chat.title, title_width), title_width),
+            );
+            continue;
+        }
+        println!(
+            "{}  {}  {}  {}  {}  {}",
+            pad_left(&chat.chat_id.to_string(), 6),
+            pad_right(&truncate_display(&chat.title, title_width), title_width),
+            pad_left(&message_count(chat), messages_width),
+            pad_left(&chat.media_messages.to_string(), 6),
+            pad_left(&format_bytes(chat.media_bytes), size_width),
+            pad_left(&format!("{:.1}%", chat.share), 6),
+        );
+    }
+    println!();
+    let biggest = output
+        .chats
+        .iter()
+        .filter(|chat| chat.media_bytes > 0)
+        .take(3)
+        .map(|chat| format!("{} ({:.0}%)", chat.title, chat.share))
+        .collect::<Vec<_>>();
+    if !biggest.is_empty() {
+        println!(
+            "{}",
+            style_heading(&format!("Biggest: {}", biggest.join(", ")))
+        );
+    }
+    println!(
+        "{} message(s) and {} of media across {} chat(s).",
+        output.messages,
+        format_bytes(output.media_bytes),
+        output.scanned
+    );
+    if output.chats.iter().any(|chat| !chat.complete) {
+        println!("Counts marked + stopped at the history page cap and are lower bounds.");
+    }
 }
 
 pub(crate) fn print_message_detail(summary: &MessageSummary, peer_label: &str) {
@@ -1036,15 +1677,51 @@ fn minimum_column_width(column: FlexibleColumn) -> usize {
         .min(column.max_width)
 }
 
+/// `--width`, then `COLUMNS`, then the size of the terminal stdout is on.
+/// Piped output without either has no width, and columns keep their
+/// preferred sizes.
 fn terminal_columns() -> Option<usize> {
+    if let Some(width) = TABLE_WIDTH.get() {
+        return Some(usize::from(*width));
+    }
     env::var("COLUMNS")
         .ok()
         .and_then(|value| value.parse::<usize>().ok())
+        .or_else(|| {
+            io::stdout()
+                .is_terminal()
+                .then(|| Term::stdout().size_checked())
+                .flatten()
+                .map(|(_, columns)| usize::from(columns))
+        })
         .filter(|columns| *columns >= 20)
 }
 
+/// Widens the column at `index` (the message text) into whatever width the
+/// other columns leave, up to its content, so wide terminals aren't wasted
+/// on a capped text column.
+fn fill_remaining_width(
+    widths: &mut [usize],
+    index: usize,
+    content_width: usize,
+    fixed_width: usize,
+    terminal_columns: Option<usize>,
+) {
+    let Some(terminal_columns) = terminal_columns else {
+        return;
+    };
+    let others = widths
+        .iter()
+        .enumerate()
+        .filter(|(column, _)| *column != index)
+        .map(|(_, width)| width)
+        .sum::<usize>();
+    let remaining = terminal_columns.saturating_sub(fixed_width + others);
+    widths[index] = widths[index].max(content_width.min(remaining));
+}
+
 fn display_width(value: &str) -> usize {
-    UnicodeWidthStr::width(value)
+    crate::text_width::display_width(value)
 }
 
 fn header_right(value: &str, width: usize) -> String {
@@ -1070,7 +1747,11 @@ fn should_use_color() -> bool {
     if env::var_os("CLICOLOR_FORCE").is_some_and(|force| force != "0") {
         return true;
     }
-    io::stdout().is_terminal()
+    match COLOR_MODE.get().copied().unwrap_or(ColorMode::Auto) {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => io::stdout().is_terminal(),
+    }
 }
 
 fn truncate_display(value: &str, max_width: usize) -> String {
@@ -1080,13 +1761,13 @@ fn truncate_display(value: &str, max_width: usize) -> String {
     let ellipsis = "...";
     let mut width = 0usize;
     let mut output = String::new();
-    for ch in value.chars() {
-        let ch_width = UnicodeWidthChar::width(ch).unwrap_or(0);
-        if width + ch_width + ellipsis.len() > max_width {
+    for (_, cluster) in grapheme_indices(value) {
+        let cluster_width = cluster_width(cluster);
+        if width + cluster_width + ellipsis.len() > max_width {
             break;
         }
-        output.push(ch);
-        width += ch_width;
+        output.push_str(cluster);
+        width += cluster_width;
     }
     output.push_str(ellipsis);
     output
@@ -1132,16 +1813,21 @@ fn take_display_line(value: &str, max_width: usize) -> (String, &str) {
     let mut end_index = 0usize;
     let mut last_space_index = None;
 
-    for (index, ch) in value.char_indices() {
-        let ch_width = UnicodeWidthChar::width(ch).unwrap_or(0);
-        if width + ch_width > max_width {
+    for (index, cluster) in grapheme_indices(value) {
+        let cluster_width = cluster_width(cluster);
+        let is_space = cluster.starts_with(char::is_whitespace);
+        if width + cluster_width > max_width {
+            // A line that fills the width exactly can break at this space.
+            if is_space {
+                last_space_index = Some(index);
+            }
             break;
         }
-        if ch.is_whitespace() {
+        if is_space {
             last_space_index = Some(index);
         }
-        width += ch_width;
-        end_index = index + ch.len_utf8();
+        width += cluster_width;
+        end_index = index + cluster.len();
     }
 
     let split_index = last_space_index
@@ -1153,7 +1839,7 @@ fn take_display_line(value: &str, max_width: usize) -> (String, &str) {
 }
 
 fn pad_right(value: &str, width: usize) -> String {
-    let mut output = value.to_string();
+    let mut output = isolate_rtl(value);
     let current = display_width(value);
     if current < width {
         output.push_str(&" ".repeat(width - current));
@@ -1164,10 +1850,10 @@ fn pad_right(value: &str, width: usize) -> String {
 fn pad_left(value: &str, width: usize) -> String {
     let current = display_width(value);
     if current >= width {
-        return value.to_string();
+        return isolate_rtl(value);
     }
     let mut output = " ".repeat(width - current);
-    output.push_str(value);
+    output.push_str(&isolate_rtl(value));
     output
 }
 
@@ -1228,12 +1914,45 @@ mod tests {
         assert!(widths[2] >= 24);
     }
 
+    #[test]
+    fn text_column_takes_the_remaining_terminal_width() {
+        let mut widths = vec![18, 96];
+        fill_remaining_width(&mut widths, 1, 300, 30, Some(200));
+        assert_eq!(widths, [18, 152]);
+
+        // Short text doesn't pad out to the edge, and a narrow or unknown
+        // width never shrinks what flexible_widths chose.
+        let mut widths = vec![18, 40];
+        fill_remaining_width(&mut widths, 1, 50, 30, Some(200));
+        assert_eq!(widths, [18, 50]);
+        fill_remaining_width(&mut widths, 1, 300, 30, Some(60));
+        assert_eq!(widths, [18, 50]);
+        fill_remaining_width(&mut widths, 1, 300, 30, None);
+        assert_eq!(widths, [18, 50]);
+    }
+
     #[test]
     fn truncate_display_preserves_display_width() {
         assert_eq!(truncate_display("hello world", 8), "hello...");
         assert_eq!(display_width(&truncate_display("hello world", 8)), 8);
     }
 
+    #[test]
+    fn truncate_and_pad_keep_clusters_whole_and_isolate_rtl() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let text = format!("hi {family}{family}{family}");
+        assert_eq!(truncate_display(&text, 8), format!("hi {family}..."));
+        assert_eq!(display_width(&truncate_display(&text, 8)), 8);
+
+        let hebrew = "\u{05E9}\u{05DC}\u{05D5}\u{05DD}";
+        assert_eq!(pad_right(hebrew, 6), format!("\u{2068}{hebrew}\u{2069}  "));
+        assert_eq!(pad_left(hebrew, 5), format!(" \u{2068}{hebrew}\u{2069}"));
+        assert_eq!(
+            wrap_display_lines(&format!("{hebrew} {hebrew} {hebrew}"), 9, usize::MAX),
+            [format!("{hebrew} {hebrew}"), hebrew.to_string()]
+        );
+    }
+
     #[test]
     fn wrap_display_lines_uses_second_line_before_truncating() {
         let lines = wrap_display_lines(
@@ -1380,11 +2099,13 @@ mod tests {
                 proto::User {
                     id: 2,
                     first_name: Some("Zoe".to_string()),
+                    pending_setup: Some(true),
                     ..Default::default()
                 },
                 proto::User {
                     id: 1,
                     first_name: Some("Ava".to_string()),
+                    pending_setup: Some(false),
                     ..Default::default()
                 },
             ],
@@ -1416,6 +2137,8 @@ mod tests {
         );
         assert!(!output.members[0].can_access_public_chats);
         assert!(output.members[1].can_access_public_chats);
+        assert_eq!(output.members[0].status, "active");
+        assert_eq!(output.members[1].status, "invited");
     }
 
     #[test]
@@ -1427,11 +2150,21 @@ mod tests {
                     proto::User {
                         id: 2,
                         first_name: Some("Zoe".to_string()),
+                        status: Some(proto::UserStatus {
+                            online: proto::user_status::Status::Offline as i32,
+                            last_online: Some(proto::LastOnline {
+                                date: Some(now - 3_600),
+                            }),
+                        }),
                         ..Default::default()
                     },
                     proto::User {
                         id: 1,
                         first_name: Some("Ava".to_string()),
+                        status: Some(proto::UserStatus {
+                            online: proto::user_status::Status::Online as i32,
+                            last_online: None,
+                        }),
                         ..Default::default()
                     },
                 ],
@@ -1463,6 +2196,10 @@ mod tests {
                 .collect::<Vec<_>>(),
             vec![("Ava", "45s ago"), ("Zoe", "2m ago")]
         );
+        assert_eq!(output.participants[0].presence, Some("online"));
+        assert_eq!(output.participants[0].last_seen, "now");
+        assert_eq!(output.participants[1].presence, Some("offline"));
+        assert_eq!(output.participants[1].last_seen, "1h ago");
     }
 
     #[test]
@@ -1502,4 +2239,39 @@ mod tests {
             "external_task title=Fix login url=https://linear.example/ISSUE-1 site=Linear app=linear status=in_progress number=ISSUE-1 assignedUserId=42"
         );
     }
+
+    #[test]
+    fn output_shims_rewrite_only_their_command_and_older_versions() {
+        fn rename_total(value: &mut serde_json::Value) {
+            if let Some(object) = value.as_object_mut()
+                && let Some(total) = object.remove("total")
+            {
+                object.insert("count".to_string(), total);
+            }
+        }
+        let shims = [OutputShim {
+            command: "chats list",
+            from: OUTPUT_SCHEMA_VERSION,
+            apply: rename_total,
+        }];
+
+        let mut value = serde_json::json!({ "total": 3 });
+        downgrade_json(&mut value, "chats list", OUTPUT_SCHEMA_VERSION, &shims);
+        assert_eq!(value, serde_json::json!({ "total": 3 }));
+
+        let older = OUTPUT_SCHEMA_VERSION - 1;
+        downgrade_json(&mut value, "users list", older, &shims);
+        assert_eq!(value, serde_json::json!({ "total": 3 }));
+        downgrade_json(&mut value, "chats list", older, &shims);
+        assert_eq!(value, serde_json::json!({ "count": 3 }));
+
+        assert_eq!(
+            set_output_version(0, "chats list".to_string()),
+            Err(1..=OUTPUT_SCHEMA_VERSION)
+        );
+        assert_eq!(
+            set_output_version(OUTPUT_SCHEMA_VERSION + 1, "chats list".to_string()),
+            Err(1..=OUTPUT_SCHEMA_VERSION)
+        );
+    }
 }
@@ -0,0 +1,2324 @@
+//! JSON Schemas for `--json` output, served by `inline schema output`.
+//!
+//! Schemas cover the CLI's own output shapes. Protobuf payloads that pass
+//! through unchanged (chats, messages, users, updates) are described as
+//! objects pointing at `inline schema proto`, with their snake_case fields
+//! left open. Bump `OUTPUT_SCHEMA_VERSION` when a field is removed, renamed,
+//! or changes type, and add a shim in `output.rs` that restores the old
+//! layout for `--output-version`; adding a field doesn't need a bump.
+
+use serde_json::{Map, Value, json};
+
+pub(crate) const OUTPUT_SCHEMA_VERSION: u32 = 1;
+const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+const SCHEMA_BASE_URL: &str = "https://inline.chat/cli/schemas";
+
+pub(crate) struct OutputSchema {
+    /// Command path, e.g. `chats list`.
+    pub(crate) command: &'static str,
+    pub(crate) description: &'static str,
+    build: fn() -> Value,
+}
+
+/// Commands that refuse `--json` and so have no output schema.
+pub(crate) const WITHOUT_JSON_OUTPUT: &[&str] = &["auth login", "login", "messages view"];
+
+pub(crate) const OUTPUT_SCHEMAS: &[OutputSchema] = &[
+    OutputSchema {
+        command: "error",
+        description: "Error envelope printed to stderr by any failing command",
+        build: error_schema,
+    },
+    OutputSchema {
+        command: "auth me",
+        description: "Your user plus where the token came from",
+        build: auth_me_schema,
+    },
+    OutputSchema {
+        command: "me",
+        description: "Your user plus where the token came from",
+        build: auth_me_schema,
+    },
+    OutputSchema {
+        command: "auth refresh",
+        description: "Whether the token is valid, with your user",
+        build: auth_refresh_schema,
+    },
+    OutputSchema {
+        command: "auth logout",
+        description: "Which saved token was cleared and which one still applies",
+        build: auth_logout_schema,
+    },
+    OutputSchema {
+        command: "logout",
+        description: "Which saved token was cleared and which one still applies",
+        build: auth_logout_schema,
+    },
+    OutputSchema {
+        command: "update",
+        description: "The installed and latest versions, and whether an update was installed",
+        build: update_schema,
+    },
+    OutputSchema {
+        command: "doctor",
+        description: "Environment, config, auth, and connectivity report, or the fixes applied with --fix",
+        build: doctor_schema,
+    },
+    OutputSchema {
+        command: "server check",
+        description: "Whether the server supports the calls this CLI makes",
+        build: server_check_schema,
+    },
+    OutputSchema {
+        command: "server forget-unsupported",
+        description: "Methods no longer remembered as unsupported",
+        build: server_forget_unsupported_schema,
+    },
+    OutputSchema {
+        command: "state migrate",
+        description: "State file migrations pending or applied",
+        build: state_migrate_schema,
+    },
+    OutputSchema {
+        command: "config get",
+        description: "One setting's value and where it came from",
+        build: config_get_schema,
+    },
+    OutputSchema {
+        command: "config set",
+        description: "The setting that was written",
+        build: config_set_schema,
+    },
+    OutputSchema {
+        command: "config unset",
+        description: "The setting that was removed",
+        build: config_unset_schema,
+    },
+    OutputSchema {
+        command: "config list",
+        description: "Effective settings, peer aliases, and the raw config file",
+        build: config_list_schema,
+    },
+    OutputSchema {
+        command: "config path",
+        description: "Where the config file lives",
+        build: config_path_schema,
+    },
+    OutputSchema {
+        command: "settings export",
+        description: "Settings bundle, or where it was written with --output",
+        build: settings_export_schema,
+    },
+    OutputSchema {
+        command: "settings import",
+        description: "Settings an import changed or would change",
+        build: settings_import_schema,
+    },
+    OutputSchema {
+        command: "alias add",
+        description: "The alias that was saved",
+        build: alias_schema,
+    },
+    OutputSchema {
+        command: "alias list",
+        description: "Peer aliases, as an array",
+        build: alias_list_schema,
+    },
+    OutputSchema {
+        command: "alias remove",
+        description: "The alias that was removed",
+        build: alias_remove_schema,
+    },
+    OutputSchema {
+        command: "chats list",
+        description: "Chats with dialogs, notification modes, and optional activity",
+        build: chats_list_schema,
+    },
+    OutputSchema {
+        command: "chats get",
+        description: "`GetChatResult` for one chat",
+        build: chats_get_schema,
+    },
+    OutputSchema {
+        command: "chats participants",
+        description: "`GetChatParticipantsResult` for one chat",
+        build: chats_participants_schema,
+    },
+    OutputSchema {
+        command: "chats add-participant",
+        description: "`AddChatParticipantResult`",
+        build: chats_add_participant_schema,
+    },
+    OutputSchema {
+        command: "chats remove-participant",
+        description: "`RemoveChatParticipantResult`",
+        build: chats_remove_participant_schema,
+    },
+    OutputSchema {
+        command: "chats sync-participants",
+        description: "Participants added, removed, and kept to match a list",
+        build: chats_sync_participants_schema,
+    },
+    OutputSchema {
+        command: "chats create",
+        description: "`CreateChatResult`, or the existing chat with --if-not-exists",
+        build: chats_create_schema,
+    },
+    OutputSchema {
+        command: "chats create-from-message",
+        description: "A thread created from a message and who was invited",
+        build: chats_create_from_message_schema,
+    },
+    OutputSchema {
+        command: "chats create-dm",
+        description: "The private chat with a user, created or opened",
+        build: chats_create_dm_schema,
+    },
+    OutputSchema {
+        command: "chats update-visibility",
+        description: "`UpdateChatVisibilityResult`",
+        build: chats_update_visibility_schema,
+    },
+    OutputSchema {
+        command: "chats rename",
+        description: "`UpdateChatInfoResult`",
+        build: chats_rename_schema,
+    },
+    OutputSchema {
+        command: "chats join",
+        description: "`ShowInChatListResult`",
+        build: chats_join_schema,
+    },
+    OutputSchema {
+        command: "chats mark-unread",
+        description: "`MarkAsUnreadResult`",
+        build: chats_mark_unread_schema,
+    },
+    OutputSchema {
+        command: "chats mark-read",
+        description: "Empty object once messages are marked read",
+        build: chats_mark_read_schema,
+    },
+    OutputSchema {
+        command: "chats read-state",
+        description: "How far you've read in one chat or every chat",
+        build: chats_read_state_schema,
+    },
+    OutputSchema {
+        command: "chats delete",
+        description: "`DeleteChatResult`",
+        build: chats_delete_schema,
+    },
+    OutputSchema {
+        command: "users list",
+        description: "`GetChatsResult` filtered to the matching users",
+        build: users_list_schema,
+    },
+    OutputSchema {
+        command: "users search",
+        description: "Matching users, as an array",
+        build: users_search_schema,
+    },
+    OutputSchema {
+        command: "users get",
+        description: "One user",
+        build: users_get_schema,
+    },
+    OutputSchema {
+        command: "messages list",
+        description: "`GetChatHistoryResult`, with translations when requested",
+        build: messages_list_schema,
+    },
+    OutputSchema {
+        command: "messages search",
+        description: "`SearchMessagesResult`, with translations and context when requested",
+        build: messages_search_schema,
+    },
+    OutputSchema {
+        command: "search",
+        description: "`SearchMessagesResult`, with translations and context when requested",
+        build: messages_search_schema,
+    },
+    OutputSchema {
+        command: "messages media",
+        description: "Photos, videos, files, or links shared in a chat",
+        build: messages_media_schema,
+    },
+    OutputSchema {
+        command: "messages get",
+        description: "One message, or a batch when several ids are given",
+        build: messages_get_schema,
+    },
+    OutputSchema {
+        command: "messages send",
+        description: "The sent message, attachments, per-destination results, or why nothing was sent",
+        build: messages_send_schema,
+    },
+    OutputSchema {
+        command: "messages nudge",
+        description: "`SendMessageResult`",
+        build: messages_nudge_schema,
+    },
+    OutputSchema {
+        command: "messages forward",
+        description: "`ForwardMessagesResult`",
+        build: messages_forward_schema,
+    },
+    OutputSchema {
+        command: "messages export",
+        description: "Export bundle on stdout, or a summary of the file written with --output",
+        build: messages_export_schema,
+    },
+    OutputSchema {
+        command: "messages transcript",
+        description: "Summary of the file written with --output; without it the transcript is Markdown",
+        build: messages_transcript_schema,
+    },
+    OutputSchema {
+        command: "transcript",
+        description: "Summary of the file written with --output; without it the transcript is Markdown",
+        build: messages_transcript_schema,
+    },
+    OutputSchema {
+        command: "messages download",
+        description: "Where one file was saved, or every file in a batch",
+        build: messages_download_schema,
+    },
+    OutputSchema {
+        command: "messages delete",
+        description: "Result of deleting messages for everyone",
+        build: messages_delete_schema,
+    },
+    OutputSchema {
+        command: "messages edit",
+        description: "`EditMessageResult`",
+        build: messages_edit_schema,
+    },
+    OutputSchema {
+        command: "messages add-reaction",
+        description: "`AddReactionResult`",
+        build: messages_add_reaction_schema,
+    },
+    OutputSchema {
+        command: "messages delete-reaction",
+        description: "`DeleteReactionResult`",
+        build: messages_delete_reaction_schema,
+    },
+    OutputSchema {
+        command: "spaces list",
+        description: "`GetChatsResult` with your spaces",
+        build: spaces_list_schema,
+    },
+    OutputSchema {
+        command: "spaces members",
+        description: "`GetSpaceMembersResult`",
+        build: spaces_members_schema,
+    },
+    OutputSchema {
+        command: "spaces chats",
+        description: "A space's chats, including public threads you haven't joined",
+        build: spaces_chats_schema,
+    },
+    OutputSchema {
+        command: "spaces search",
+        description: "Search hits across a space's chats",
+        build: spaces_search_schema,
+    },
+    OutputSchema {
+        command: "spaces invite",
+        description: "`InviteToSpaceResult`",
+        build: spaces_invite_schema,
+    },
+    OutputSchema {
+        command: "spaces invite-bulk",
+        description: "Per-row results of inviting members from a CSV",
+        build: spaces_invite_bulk_schema,
+    },
+    OutputSchema {
+        command: "spaces delete-member",
+        description: "`DeleteMemberResult`",
+        build: spaces_delete_member_schema,
+    },
+    OutputSchema {
+        command: "spaces revoke-invite",
+        description: "The pending invite that was revoked",
+        build: spaces_revoke_invite_schema,
+    },
+    OutputSchema {
+        command: "spaces update-member-access",
+        description: "`UpdateMemberAccessResult`",
+        build: spaces_update_member_access_schema,
+    },
+    OutputSchema {
+        command: "notifications get",
+        description: "Notification settings and the mode in effect per scope",
+        build: notifications_get_schema,
+    },
+    OutputSchema {
+        command: "notifications set",
+        description: "`UpdateUserSettingsResult`, or the chats changed with --space-id",
+        build: notifications_set_schema,
+    },
+    OutputSchema {
+        command: "notifications rules list",
+        description: "Custom notification rules",
+        build: notification_rules_schema,
+    },
+    OutputSchema {
+        command: "notifications rules add",
+        description: "Custom notification rules after the add",
+        build: notification_rules_schema,
+    },
+    OutputSchema {
+        command: "notifications rules remove",
+        description: "Custom notification rules after the removal",
+        build: notification_rules_schema,
+    },
+    OutputSchema {
+        command: "tasks create-linear",
+        description: "The Linear issue created from a message",
+        build: tasks_create_linear_schema,
+    },
+    OutputSchema {
+        command: "tasks create-notion",
+        description: "The Notion task created from a message",
+        build: tasks_create_notion_schema,
+    },
+    OutputSchema {
+        command: "tasks create",
+        description: "The webhook provider's response",
+        build: tasks_create_schema,
+    },
+    OutputSchema {
+        command: "tasks providers",
+        description: "Built-in and webhook task providers",
+        build: tasks_providers_schema,
+    },
+    OutputSchema {
+        command: "bots list",
+        description: "`ListBotsResult`",
+        build: bots_list_schema,
+    },
+    OutputSchema {
+        command: "bots create",
+        description: "`CreateBotResult`",
+        build: bots_create_schema,
+    },
+    OutputSchema {
+        command: "bots reveal-token",
+        description: "`RevealBotTokenResult`",
+        build: bots_reveal_token_schema,
+    },
+    OutputSchema {
+        command: "typing start",
+        description: "`SendComposeActionResult`",
+        build: typing_schema,
+    },
+    OutputSchema {
+        command: "typing stop",
+        description: "`SendComposeActionResult`",
+        build: typing_schema,
+    },
+    OutputSchema {
+        command: "note",
+        description: "`SendMessageResult` for the note",
+        build: note_schema,
+    },
+    OutputSchema {
+        command: "notes list",
+        description: "`GetChatHistoryResult` for your notes",
+        build: notes_list_schema,
+    },
+    OutputSchema {
+        command: "bookmarks add",
+        description: "The saved bookmark",
+        build: bookmarks_add_schema,
+    },
+    OutputSchema {
+        command: "bookmarks list",
+        description: "Saved bookmarks",
+        build: bookmarks_list_schema,
+    },
+    OutputSchema {
+        command: "bookmarks open",
+        description: "The bookmark and the link that was opened",
+        build: bookmarks_open_schema,
+    },
+    OutputSchema {
+        command: "bookmarks remove",
+        description: "The bookmark id that was removed",
+        build: removed_id_schema,
+    },
+    OutputSchema {
+        command: "alerts add",
+        description: "The alert that was added",
+        build: alert_schema,
+    },
+    OutputSchema {
+        command: "alerts list",
+        description: "Keyword alerts",
+        build: alerts_list_schema,
+    },
+    OutputSchema {
+        command: "alerts remove",
+        description: "The alert id that was removed",
+        build: removed_id_schema,
+    },
+    OutputSchema {
+        command: "alerts mute",
+        description: "The alert after muting",
+        build: alert_schema,
+    },
+    OutputSchema {
+        command: "alerts unmute",
+        description: "The alert after unmuting",
+        build: alert_schema,
+    },
+    OutputSchema {
+        command: "alerts run",
+        description: "One object per line for each alert that fires",
+        build: alerts_run_schema,
+    },
+    OutputSchema {
+        command: "resend pending",
+        description: "Pending sends with --dry-run, otherwise the result of retrying them",
+        build: resend_pending_schema,
+    },
+    OutputSchema {
+        command: "badge",
+        description: "Unread totals; with --watch, one object per line as they change",
+        build: badge_schema,
+    },
+    OutputSchema {
+        command: "digest",
+        description: "Activity and mentions across your chats",
+        build: digest_schema,
+    },
+    OutputSchema {
+        command: "usage",
+        description: "Per-chat message counts and media bytes for a space",
+        build: usage_schema,
+    },
+    OutputSchema {
+        command: "recent",
+        description: "Peers you last messaged or heard from",
+        build: recent_schema,
+    },
+    OutputSchema {
+        command: "summarize",
+        description: "A chat's messages summarized by the configured command",
+        build: summarize_schema,
+    },
+    OutputSchema {
+        command: "events extract",
+        description: "Calendar events found in a chat's messages",
+        build: events_extract_schema,
+    },
+    OutputSchema {
+        command: "mentions",
+        description: "Messages that mention you across your chats",
+        build: mentions_schema,
+    },
+    OutputSchema {
+        command: "open",
+        description: "The link that was opened",
+        build: open_schema,
+    },
+    OutputSchema {
+        command: "schema proto",
+        description: "The bundled .proto sources",
+        build: schema_proto_schema,
+    },
+    OutputSchema {
+        command: "schema output",
+        description: "Commands with an output schema; with a command, that schema",
+        build: schema_output_schema,
+    },
+    OutputSchema {
+        command: "api post",
+        description: "The API method's response, unchanged",
+        build: api_post_schema,
+    },
+    OutputSchema {
+        command: "rpc call",
+        description: "The RPC method and its result",
+        build: rpc_call_schema,
+    },
+    OutputSchema {
+        command: "rpc batch",
+        description: "One result or error per call, as an array",
+        build: rpc_batch_schema,
+    },
+    OutputSchema {
+        command: "import slack",
+        description: "Threads imported from a Slack export",
+        build: import_schema,
+    },
+    OutputSchema {
+        command: "import telegram",
+        description: "Threads imported from a Telegram export",
+        build: import_schema,
+    },
+    OutputSchema {
+        command: "backup create",
+        description: "What the backup archive contains",
+        build: backup_create_schema,
+    },
+    OutputSchema {
+        command: "backup inspect",
+        description: "The archive manifest, or one chat's messages with --chat-id",
+        build: backup_inspect_schema,
+    },
+    OutputSchema {
+        command: "backup restore",
+        description: "Manifest of the restored folder",
+        build: backup_manifest_schema,
+    },
+    OutputSchema {
+        command: "archive run",
+        description: "Messages appended to a chat's archive",
+        build: archive_run_schema,
+    },
+    OutputSchema {
+        command: "archive schedule",
+        description: "A scheduler entry that runs `archive run`",
+        build: archive_schedule_schema,
+    },
+    OutputSchema {
+        command: "sync",
+        description: "What a sync applied to the local cache",
+        build: sync_schema,
+    },
+    OutputSchema {
+        command: "watch reactions",
+        description: "One object per line for each new reaction",
+        build: watch_reactions_schema,
+    },
+    OutputSchema {
+        command: "watch messages",
+        description: "One `Message` per line for each new message",
+        build: watch_messages_schema,
+    },
+    OutputSchema {
+        command: "debug record",
+        description: "Where the recording went and how many events it has",
+        build: debug_record_schema,
+    },
+    OutputSchema {
+        command: "debug replay",
+        description: "One recorded event per line",
+        build: debug_event_schema,
+    },
+];
+
+/// The full schema document for `command`, or `None` if it has none.
+pub(crate) fn output_schema(command: &str) -> Option<Value> {
+    let spec = OUTPUT_SCHEMAS.iter().find(|spec| spec.command == command)?;
+    let mut schema = (spec.build)();
+    let slug = spec.command.replace(' ', "-");
+    let header = json!({
+        "$schema": SCHEMA_DIALECT,
+        "$id": format!("{SCHEMA_BASE_URL}/v{OUTPUT_SCHEMA_VERSION}/{slug}.json"),
+        "title": format!("inline {} --json", spec.command),
+        "description": spec.description,
+        "x-inline-schema-version": OUTPUT_SCHEMA_VERSION,
+    });
+    if let (Value::Object(schema), Value::Object(header)) = (&mut schema, header) {
+        schema.extend(header);
+    }
+    Some(schema)
+}
+
+fn integer() -> Value {
+    json!({ "type": "integer" })
+}
+
+fn number() -> Value {
+    json!({ "type": "number" })
+}
+
+fn string() -> Value {
+    json!({ "type": "string" })
+}
+
+fn boolean() -> Value {
+    json!({ "type": "boolean" })
+}
+
+fn string_enum(values: &[&str]) -> Value {
+    json!({ "type": "string", "enum": values })
+}
+
+fn array(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+/// A field that is always present but may be `null`.
+fn nullable(mut schema: Value) -> Value {
+    if let Some(kind) = schema.get("type").and_then(Value::as_str) {
+        schema["type"] = json!([kind, "null"]);
+    }
+    schema
+}
+
+fn proto(message: &str) -> Value {
+    json!({
+        "type": "object",
+        "description": format!("`{message}` protobuf message with snake_case fields; see `inline schema proto`"),
+        "additionalProperties": true,
+    })
+}
+
+/// An object with `required` fields always present and `optional` ones
+/// omitted when empty.
+fn object(required: &[(&str, Value)], optional: &[(&str, Value)]) -> Value {
+    let properties = required
+        .iter()
+        .chain(optional)
+        .map(|(name, schema)| (name.to_string(), schema.clone()))
+        .collect::<Map<_, _>>();
+    let required = required.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+/// A protobuf message with the CLI's own fields flattened in beside its own.
+fn extends(message: &str, required: &[(&str, Value)], optional: &[(&str, Value)]) -> Value {
+    let mut schema = object(required, optional);
+    schema["description"] = proto(message)["description"].clone();
+    schema["additionalProperties"] = json!(true);
+    schema
+}
+
+/// The result of whichever RPC method was called.
+fn rpc_result() -> Value {
+    json!({
+        "type": "object",
+        "description": "The method's `...Result` protobuf message with snake_case fields; see `inline schema proto`",
+        "additionalProperties": true,
+    })
+}
+
+/// A JSON object from the HTTP API, passed through unchanged.
+fn api_object(what: &str) -> Value {
+    json!({
+        "type": "object",
+        "description": format!("The server's {what}, passed through unchanged"),
+        "additionalProperties": true,
+    })
+}
+
+/// An object keyed by name, e.g. aliases.
+fn map(values: Value) -> Value {
+    json!({ "type": "object", "additionalProperties": values })
+}
+
+/// Output that takes one of several shapes depending on the flags.
+fn one_of(variants: Vec<Value>) -> Value {
+    json!({ "oneOf": variants })
+}
+
+fn error_schema() -> Value {
+    object(
+        &[(
+            "error",
+            object(
+                &[("code", string()), ("message", string())],
+                &[
+                    ("status", integer()),
+                    ("apiError", string()),
+                    ("apiErrorCode", integer()),
+                    ("body", string()),
+                    ("retryAfter", integer()),
+                    ("hint", string()),
+                    ("examples", array(string())),
+                ],
+            ),
+        )],
+        &[],
+    )
+}
+
+fn chats_list_schema() -> Value {
+    let notifications = object(
+        &[
+            ("chatId", integer()),
+            ("mode", string_enum(&["default", "all", "mentions", "none"])),
+            ("muted", boolean()),
+        ],
+        &[],
+    );
+    let mut days = array(integer());
+    days["minItems"] = json!(7);
+    days["maxItems"] = json!(7);
+    let activity = object(
+        &[("chatId", integer()), ("days", days), ("total", integer())],
+        &[],
+    );
+    object(
+        &[
+            ("dialogs", array(proto("Dialog"))),
+            ("chats", array(proto("Chat"))),
+            ("spaces", array(proto("Space"))),
+            ("users", array(proto("User"))),
+            ("messages", array(proto("Message"))),
+            ("notifications", array(notifications)),
+        ],
+        &[("activity", array(activity))],
+    )
+}
+
+fn chats_read_state_schema() -> Value {
+    let state = object(
+        &[
+            ("chatId", integer()),
+            ("peer", nullable(peer_summary())),
+            ("title", string()),
+            ("readMaxId", nullable(integer())),
+            ("lastMessageId", nullable(integer())),
+            ("unreadCount", integer()),
+            ("unreadMark", boolean()),
+        ],
+        &[],
+    );
+    object(&[("dialogs", array(state))], &[])
+}
+
+fn messages_delete_schema() -> Value {
+    object(
+        &[
+            ("updates", array(proto("Update"))),
+            ("forEveryone", boolean()),
+        ],
+        &[
+            ("deletedMessageIds", array(integer())),
+            ("notDeletedMessageIds", array(integer())),
+        ],
+    )
+}
+
+fn peer_summary() -> Value {
+    object(&[("peerType", string()), ("id", integer())], &[])
+}
+
+fn messages_media_schema() -> Value {
+    let item = object(
+        &[
+            ("messageId", integer()),
+            ("date", integer()),
+            ("fromId", integer()),
+            ("name", nullable(string())),
+            ("mimeType", nullable(string())),
+            ("size", nullable(integer())),
+            ("url", nullable(string())),
+            ("senderName", string()),
+            ("relativeDate", string()),
+        ],
+        &[],
+    );
+    object(
+        &[
+            ("kind", string_enum(&["photo", "video", "file", "link"])),
+            ("peer", nullable(peer_summary())),
+            ("peerName", nullable(string())),
+            ("items", array(item)),
+            ("nextOffsetId", nullable(integer())),
+        ],
+        &[],
+    )
+}
+
+fn mentions_schema() -> Value {
+    let mention = object(
+        &[
+            ("chatId", integer()),
+            ("chatName", string()),
+            ("messageId", integer()),
+            ("date", integer()),
+            ("relativeDate", string()),
+            ("senderId", integer()),
+            ("senderName", string()),
+            ("preview", string()),
+            ("replied", boolean()),
+        ],
+        &[],
+    );
+    object(
+        &[
+            ("since", integer()),
+            ("unrepliedOnly", boolean()),
+            ("chatsScanned", integer()),
+            ("mentions", array(mention)),
+        ],
+        &[],
+    )
+}
+
+fn recent_schema() -> Value {
+    let peer = object(
+        &[("peer", string()), ("at", integer())],
+        &[("title", string())],
+    );
+    object(&[("peers", array(peer))], &[])
+}
+
+fn spaces_chats_schema() -> Value {
+    let chat = object(
+        &[
+            ("chatId", integer()),
+            ("title", string()),
+            ("emoji", nullable(string())),
+            ("public", boolean()),
+            ("joined", boolean()),
+            ("unreadCount", integer()),
+            ("lastMessageDate", nullable(integer())),
+        ],
+        &[],
+    );
+    object(
+        &[
+            ("spaceId", integer()),
+            ("spaceName", nullable(string())),
+            ("chats", array(chat)),
+        ],
+        &[],
+    )
+}
+
+fn usage_schema() -> Value {
+    let chat = object(
+        &[
+            ("chatId", integer()),
+            ("title", string()),
+            ("messages", integer()),
+            ("complete", boolean()),
+            ("mediaMessages", integer()),
+            ("mediaBytes", integer()),
+            ("share", number()),
+        ],
+        &[("error", string())],
+    );
+    object(
+        &[
+            ("spaceId", integer()),
+            ("scanned", integer()),
+            ("messages", integer()),
+            ("mediaBytes", integer()),
+            ("chats", array(chat)),
+        ],
+        &[],
+    )
+}
+
+fn auth_me_schema() -> Value {
+    extends("User", &[("token", nullable(token_status()))], &[])
+}
+
+fn auth_refresh_schema() -> Value {
+    object(
+        &[
+            ("valid", boolean()),
+            ("refreshed", boolean()),
+            ("user", proto("User")),
+            ("token", nullable(token_status())),
+        ],
+        &[],
+    )
+}
+
+fn auth_logout_schema() -> Value {
+    object(
+        &[
+            ("savedTokenCleared", boolean()),
+            ("effectiveTokenPresent", boolean()),
+            ("effectiveTokenSource", nullable(string())),
+            ("warning", nullable(string())),
+        ],
+        &[("sessionsRevoked", integer())],
+    )
+}
+
+fn token_status() -> Value {
+    object(
+        &[
+            ("source", string()),
+            ("savedAt", nullable(integer())),
+            ("expiresAt", nullable(integer())),
+        ],
+        &[],
+    )
+}
+
+fn update_schema() -> Value {
+    object(
+        &[
+            ("currentVersion", string()),
+            ("latestVersion", string()),
+            ("updated", boolean()),
+            ("installPath", nullable(string())),
+        ],
+        &[],
+    )
+}
+
+fn doctor_schema() -> Value {
+    let system = object(
+        &[
+            ("version", string()),
+            ("debug", boolean()),
+            ("os", string()),
+            ("arch", string()),
+            ("executable", nullable(string())),
+        ],
+        &[],
+    );
+    let client = object(
+        &[
+            ("clientType", string()),
+            ("clientVersion", string()),
+            ("userAgent", string()),
+            ("osVersion", nullable(string())),
+            ("deviceName", nullable(string())),
+            ("clientTypeHeader", string()),
+            ("clientVersionHeader", string()),
+        ],
+        &[],
+    );
+    let config = object(
+        &[
+            ("profile", nullable(string())),
+            ("apiBaseUrl", string()),
+            ("realtimeUrl", string()),
+            ("proxy", nullable(string())),
+            ("caBundle", nullable(string())),
+            ("tlsPins", array(string())),
+            ("releaseManifestUrl", nullable(string())),
+            ("releaseInstallUrl", nullable(string())),
+        ],
+        &[],
+    );
+    let paths = object(
+        &[
+            ("dataDir", string()),
+            ("dataDirExists", boolean()),
+            ("secretsPath", string()),
+            ("secretsExists", boolean()),
+            ("statePath", string()),
+            ("stateExists", boolean()),
+            ("configPath", string()),
+            ("configExists", boolean()),
+        ],
+        &[],
+    );
+    let auth = object(
+        &[
+            ("tokenPresent", boolean()),
+            ("tokenSource", nullable(string())),
+            ("tokenError", nullable(string())),
+            ("tokenSavedAt", nullable(integer())),
+            ("tokenExpiresAt", nullable(integer())),
+            ("currentUser", nullable(proto("User"))),
+            ("stateError", nullable(string())),
+        ],
+        &[],
+    );
+    let encryption = object(
+        &[
+            (
+                "state",
+                object(
+                    &[
+                        ("file", string()),
+                        ("keySource", nullable(string())),
+                        ("keyError", nullable(string())),
+                    ],
+                    &[],
+                ),
+            ),
+            ("syncCachePath", string()),
+            ("syncCacheExists", boolean()),
+        ],
+        &[],
+    );
+    let connectivity = object(
+        &[
+            ("proxy", nullable(string())),
+            ("dns", probe()),
+            ("api", probe()),
+            ("realtime", probe()),
+            ("clockSkewSeconds", nullable(integer())),
+        ],
+        &[],
+    );
+    let report = object(
+        &[
+            ("system", system),
+            ("client", client),
+            ("config", config),
+            ("paths", paths),
+            ("auth", auth),
+            ("encryption", encryption),
+            ("connectivity", nullable(connectivity)),
+        ],
+        &[],
+    );
+    let fix = object(
+        &[
+            ("kind", string()),
+            ("problem", string()),
+            ("action", string()),
+            ("status", string()),
+            ("backupPath", nullable(string())),
+            ("error", nullable(string())),
+        ],
+        &[],
+    );
+    let fixes = object(&[("fixes", array(fix)), ("loginRequired", boolean())], &[]);
+    one_of(vec![report, fixes])
+}
+
+fn probe() -> Value {
+    object(
+        &[
+            ("target", string()),
+            ("ok", boolean()),
+            ("latencyMs", nullable(integer())),
+            ("detail", string()),
+            ("errorKind", nullable(string())),
+        ],
+        &[],
+    )
+}
+
+fn server_check_schema() -> Value {
+    let endpoint = object(
+        &[
+            ("method", string()),
+            ("usedBy", string()),
+            ("required", boolean()),
+            ("status", string()),
+            ("httpStatus", nullable(integer())),
+            ("errorKind", nullable(string())),
+            ("detail", nullable(string())),
+        ],
+        &[],
+    );
+    object(
+        &[
+            ("cliVersion", string()),
+            ("apiBaseUrl", string()),
+            ("realtimeUrl", string()),
+            ("api", probe()),
+            ("realtime", probe()),
+            ("endpoints", array(endpoint)),
+            ("compatible", boolean()),
+        ],
+        &[],
+    )
+}
+
+fn server_forget_unsupported_schema() -> Value {
+    object(
+        &[
+            ("realtimeUrl", nullable(string())),
+            ("forgotten", array(string())),
+        ],
+        &[],
+    )
+}
+
+fn state_migrate_schema() -> Value {
+    let pending = object(&[("version", integer()), ("description", string())], &[]);
+    object(
+        &[
+            ("path", string()),
+            ("exists", boolean()),
+            ("fromVersion", integer()),
+            ("toVersion", integer()),
+            ("pending", array(pending)),
+            ("dryRun", boolean()),
+            ("applied", boolean()),
+        ],
+        &[],
+    )
+}
+
+/// A config file value: a string, integer, or boolean.
+fn config_value() -> Value {
+    json!({ "type": ["string", "integer", "boolean"] })
+}
+
+fn config_get_schema() -> Value {
+    object(
+        &[
+            ("key", string()),
+            (
+                "value",
+                json!({ "type": ["string", "integer", "boolean", "null"] }),
+            ),
+            ("source", string()),
+        ],
+        &[],
+    )
+}
+
+fn config_set_schema() -> Value {
+    object(&[("key", string()), ("value", config_value())], &[])
+}
+
+fn config_unset_schema() -> Value {
+    object(&[("key", string()), ("removed", boolean())], &[])
+}
+
+fn config_list_schema() -> Value {
+    let setting = object(
+        &[
+            ("key", string()),
+            ("value", nullable(string())),
+            ("source", string()),
+        ],
+        &[],
+    );
+    object(
+        &[
+            ("configPath", string()),
+            ("profile", nullable(string())),
+            ("settings", array(setting)),
+            ("aliases", map(string())),
+            ("file", map(config_value())),
+        ],
+        &[],
+    )
+}
+
+fn config_path_schema() -> Value {
+    object(&[("configPath", string()), ("exists", boolean())], &[])
+}
+
+fn settings_export_schema() -> Value {
+    let bundle = object(
+        &[
+            ("version", integer()),
+            ("preferences", map(string())),
+            ("aliases", map(string())),
+            ("commandAliases", map(string())),
+            ("defaults", map(config_value())),
+        ],
+        &[("notifications", proto("NotificationSettings"))],
+    );
+    one_of(vec![bundle, object(&[("path", string())], &[])])
+}
+
+fn settings_import_schema() -> Value {
+    let change = object(
+        &[
+            ("key", string()),
+            (
+                "from",
+                json!({ "type": ["string", "integer", "boolean", "null"] }),
+            ),
+            ("to", config_value()),
+        ],
+        &[],
+    );
+    object(
+        &[
+            ("configPath", string()),
+            ("dryRun", boolean()),
+            ("changes", array(change)),
+            ("notifications", boolean()),
+        ],
+        &[],
+    )
+}
+
+fn alias_schema() -> Value {
+    object(&[("name", string()), ("peer", string())], &[])
+}
+
+fn alias_list_schema() -> Value {
+    array(alias_schema())
+}
+
+fn alias_remove_schema() -> Value {
+    object(&[("removed", string())], &[])
+}
+
+fn chats_get_schema() -> Value {
+    proto("GetChatResult")
+}
+
+fn chats_participants_schema() -> Value {
+    proto("GetChatParticipantsResult")
+}
+
+fn chats_add_participant_schema() -> Value {
+    proto("AddChatParticipantResult")
+}
+
+fn chats_remove_participant_schema() -> Value {
+    proto("RemoveChatParticipantResult")
+}
+
+fn chats_sync_participants_schema() -> Value {
+    let change = object(
+        &[
+            ("userId", integer()),
+            ("name", string()),
+            ("status", string()),
+        ],
+        &[("error", string())],
+    );
+    object(
+        &[
+            ("chatId", integer()),
+            ("applied", boolean()),
+            ("add", array(change.clone())),
+            ("remove", array(change.clone())),
+            ("unchanged", integer()),
+            ("kept", array(change)),
+        ],
+        &[],
+    )
+}
+
+fn chats_create_schema() -> Value {
+    let existing = object(&[("chat", proto("Chat")), ("existing", boolean())], &[]);
+    one_of(vec![proto("CreateChatResult"), existing])
+}
+
+fn chats_create_from_message_schema() -> Value {
+    object(
+        &[
+            ("chat", proto("Chat")),
+            ("sourceChatId", integer()),
+            ("sourceMessageId", integer()),
+            ("firstMessageId", nullable(integer())),
+            ("invitedUserIds", array(integer())),
+        ],
+        &[],
+    )
+}
+
+fn chats_create_dm_schema() -> Value {
+    object(
+        &[
+            ("chatId", nullable(integer())),
+            ("userId", integer()),
+            ("chat", api_object("chat")),
+            ("dialog", api_object("dialog")),
+            ("user", api_object("user")),
+        ],
+        &[],
+    )
+}
+
+fn chats_update_visibility_schema() -> Value {
+    proto("UpdateChatVisibilityResult")
+}
+
+fn chats_rename_schema() -> Value {
+    proto("UpdateChatInfoResult")
+}
+
+fn chats_join_schema() -> Value {
+    proto("ShowInChatListResult")
+}
+
+fn chats_mark_unread_schema() -> Value {
+    proto("MarkAsUnreadResult")
+}
+
+fn chats_mark_read_schema() -> Value {
+    object(&[], &[])
+}
+
+fn chats_delete_schema() -> Value {
+    proto("DeleteChatResult")
+}
+
+fn users_list_schema() -> Value {
+    proto("GetChatsResult")
+}
+
+fn users_search_schema() -> Value {
+    array(proto("User"))
+}
+
+fn users_get_schema() -> Value {
+    proto("User")
+}
+
+fn translations() -> Value {
+    array(proto("MessageTranslation"))
+}
+
+fn messages_list_schema() -> Value {
+    extends(
+        "GetChatHistoryResult",
+        &[],
+        &[("translations", translations())],
+    )
+}
+
+fn messages_search_schema() -> Value {
+    let context = object(
+        &[
+            ("hitIds", array(integer())),
+            ("messages", array(proto("Message"))),
+        ],
+        &[],
+    );
+    extends(
+        "SearchMessagesResult",
+        &[],
+        &[
+            ("translations", translations()),
+            ("context", array(context)),
+        ],
+    )
+}
+
+fn messages_get_schema() -> Value {
+    let batch = object(
+        &[("messages", array(proto("Message")))],
+        &[
+            ("missingMessageIds", array(integer())),
+            ("translations", translations()),
+        ],
+    );
+    one_of(vec![
+        extends("Message", &[], &[("translations", translations())]),
+        batch,
+    ])
+}
+
+fn messages_send_schema() -> Value {
+    let sent = extends(
+        "SendMessageResult",
+        &[],
+        &[
+            ("messageId", integer()),
+            ("message", proto("Message")),
+            ("delivered", boolean()),
+        ],
+    );
+    let attachment = object(
+        &[
+            ("path", string()),
+            ("fileName", string()),
+            ("sizeBytes", integer()),
+            ("sha256", string()),
+            ("fileUniqueId", string()),
+        ],
+        &[("messageId", integer())],
+    );
+    let attachments = extends(
+        "SendMessageResult",
+        &[
+            ("messageIds", array(integer())),
+            ("attachments", array(attachment)),
+        ],
+        &[
+            ("messages", array(proto("Message"))),
+            ("delivered", boolean()),
+        ],
+    );
+    let destination = object(
+        &[
+            ("to", string()),
+            ("status", string_enum(&["sent", "failed", "already_sent"])),
+            ("messageIds", array(integer())),
+        ],
+        &[("peer", peer_summary()), ("error", string())],
+    );
+    let many = object(
+        &[
+            ("sent", integer()),
+            ("alreadySent", integer()),
+            ("failed", integer()),
+            ("results", array(destination)),
+        ],
+        &[],
+    );
+    let already_sent = object(
+        &[
+            ("alreadySent", boolean()),
+            ("idempotencyKey", string()),
+            ("messageIds", array(integer())),
+        ],
+        &[],
+    );
+    let not_sent = object(&[("sent", boolean()), ("reason", string())], &[]);
+    one_of(vec![sent, attachments, many, already_sent, not_sent])
+}
+
+fn messages_nudge_schema() -> Value {
+    proto("SendMessageResult")
+}
+
+fn messages_forward_schema() -> Value {
+    proto("ForwardMessagesResult")
+}
+
+fn downloaded_file() -> Value {
+    object(
+        &[
+            ("messageId", integer()),
+            ("path", string()),
+            ("bytes", integer()),
+        ],
+        &[],
+    )
+}
+
+fn download_error() -> Value {
+    object(&[("messageId", integer()), ("error", string())], &[])
+}
+
+fn export_summary() -> Value {
+    object(
+        &[
+            ("path", string()),
+            ("format", string()),
+            ("messages", integer()),
+            ("bytes", integer()),
+        ],
+        &[
+            ("compression", string()),
+            ("compressedBytes", integer()),
+            ("mediaFiles", array(downloaded_file())),
+            ("skippedMessageIds", array(integer())),
+            ("mediaErrors", array(download_error())),
+        ],
+    )
+}
+
+fn messages_export_schema() -> Value {
+    let peer = object(
+        &[
+            ("peerType", string()),
+            ("id", integer()),
+            ("name", nullable(string())),
+        ],
+        &[],
+    );
+    let user = object(
+        &[
+            ("id", integer()),
+            ("firstName", nullable(string())),
+            ("lastName", nullable(string())),
+            ("username", nullable(string())),
+            ("displayName", string()),
+        ],
+        &[],
+    );
+    let media = object(
+        &[
+            ("kind", string()),
+            ("mediaId", nullable(integer())),
+            ("fileName", nullable(string())),
+            ("mimeType", nullable(string())),
+            ("size", nullable(integer())),
+            ("width", nullable(integer())),
+            ("height", nullable(integer())),
+            ("duration", nullable(integer())),
+            ("cdnUrl", nullable(string())),
+            ("localPath", nullable(string())),
+        ],
+        &[],
+    );
+    let attachment = object(
+        &[
+            ("kind", string()),
+            ("title", nullable(string())),
+            ("url", nullable(string())),
+        ],
+        &[],
+    );
+    let message_ref = object(
+        &[("messageId", integer())],
+        &[
+            ("fromId", integer()),
+            ("senderName", string()),
+            ("text", string()),
+            ("displayText", string()),
+        ],
+    );
+    let forward_ref = object(
+        &[("fromId", integer()), ("messageId", integer())],
+        &[
+            ("peer", peer.clone()),
+            ("senderName", string()),
+            ("message", message_ref.clone()),
+        ],
+    );
+    let message = object(
+        &[
+            ("id", integer()),
+            ("date", integer()),
+            ("dateIso", nullable(string())),
+            ("fromId", integer()),
+            ("senderName", string()),
+            ("text", nullable(string())),
+            ("displayText", string()),
+            ("media", array(media)),
+            ("attachments", array(attachment)),
+        ],
+        &[("replyTo", message_ref), ("forwardedFrom", forward_ref)],
+    );
+    let bundle = object(
+        &[
+            ("peer", peer),
+            ("messages", array(message)),
+            ("users", array(user)),
+            ("chats", array(proto("Chat"))),
+            ("spaces", array(proto("Space"))),
+            ("_warnings", array(string())),
+        ],
+        &[("translations", map(proto("MessageTranslation")))],
+    );
+    one_of(vec![bundle, export_summary()])
+}
+
+fn messages_transcript_schema() -> Value {
+    export_summary()
+}
+
+fn messages_download_schema() -> Value {
+    let single = object(&[("path", string()), ("bytes", integer())], &[]);
+    let batch = object(
+        &[("files", array(downloaded_file()))],
+        &[
+            ("skippedMessageIds", array(integer())),
+            ("missingMessageIds", array(integer())),
+            ("errors", array(download_error())),
+        ],
+    );
+    one_of(vec![single, batch])
+}
+
+fn messages_edit_schema() -> Value {
+    proto("EditMessageResult")
+}
+
+fn messages_add_reaction_schema() -> Value {
+    proto("AddReactionResult")
+}
+
+fn messages_delete_reaction_schema() -> Value {
+    proto("DeleteReactionResult")
+}
+
+fn spaces_list_schema() -> Value {
+    proto("GetChatsResult")
+}
+
+fn spaces_members_schema() -> Value {
+    proto("GetSpaceMembersResult")
+}
+
+fn spaces_search_schema() -> Value {
+    let chat = object(
+        &[
+            ("chatId", integer()),
+            ("title", string()),
+            ("count", integer()),
+            ("messages", array(proto("Message"))),
+        ],
+        &[("error", string())],
+    );
+    object(
+        &[
+            ("spaceId", integer()),
+            ("queries", array(string())),
+            ("searched", integer()),
+            ("total", integer()),
+            ("chats", array(chat)),
+        ],
+        &[],
+    )
+}
+
+fn spaces_invite_schema() -> Value {
+    proto("InviteToSpaceResult")
+}
+
+fn spaces_invite_bulk_schema() -> Value {
+    let row = object(
+        &[
+            ("line", integer()),
+            ("email", string()),
+            ("role", string_enum(&["member", "public-chats", "admin"])),
+            ("status", string()),
+        ],
+        &[("error", string()), ("userId", integer())],
+    );
+    object(
+        &[
+            ("spaceId", integer()),
+            ("dryRun", boolean()),
+            ("invited", integer()),
+            ("failed", integer()),
+            ("skipped", integer()),
+            ("rows", array(row)),
+        ],
+        &[],
+    )
+}
+
+fn spaces_delete_member_schema() -> Value {
+    proto("DeleteMemberResult")
+}
+
+fn spaces_revoke_invite_schema() -> Value {
+    object(
+        &[
+            ("spaceId", integer()),
+            ("userId", integer()),
+            ("revoked", boolean()),
+        ],
+        &[("email", string()), ("phone", string())],
+    )
+}
+
+fn spaces_update_member_access_schema() -> Value {
+    proto("UpdateMemberAccessResult")
+}
+
+fn notifications_get_schema() -> Value {
+    let scope = object(
+        &[("scope", string()), ("name", string()), ("mode", string())],
+        &[("id", integer()), ("chats", integer())],
+    );
+    extends("GetUserSettingsResult", &[("effective", array(scope))], &[])
+}
+
+fn notifications_set_schema() -> Value {
+    let space = object(
+        &[
+            ("spaceId", integer()),
+            ("mode", nullable(string())),
+            ("chatIds", array(integer())),
+            ("updates", integer()),
+        ],
+        &[],
+    );
+    one_of(vec![proto("UpdateUserSettingsResult"), space])
+}
+
+fn notification_rules_schema() -> Value {
+    let rule = object(
+        &[("number", integer()), ("line", string())],
+        &[
+            ("chatId", integer()),
+            ("from", string()),
+            ("keyword", string()),
+            ("text", string()),
+        ],
+    );
+    object(
+        &[
+            ("usesDefaultRules", boolean()),
+            ("requiresMention", boolean()),
+            ("rules", array(rule)),
+        ],
+        &[],
+    )
+}
+
+fn tasks_create_linear_schema() -> Value {
+    object(&[("link", nullable(string()))], &[])
+}
+
+fn tasks_create_notion_schema() -> Value {
+    object(&[("url", string()), ("taskTitle", nullable(string()))], &[])
+}
+
+fn tasks_create_schema() -> Value {
+    api_object("webhook response")
+}
+
+fn tasks_providers_schema() -> Value {
+    let webhook = object(
+        &[
+            ("name", string()),
+            ("url", string()),
+            ("label", nullable(string())),
+            ("tokenEnv", nullable(string())),
+        ],
+        &[],
+    );
+    object(
+        &[
+            ("builtin", array(string())),
+            ("webhooks", array(webhook)),
+            ("configPath", string()),
+        ],
+        &[],
+    )
+}
+
+fn bots_list_schema() -> Value {
+    proto("ListBotsResult")
+}
+
+fn bots_create_schema() -> Value {
+    proto("CreateBotResult")
+}
+
+fn bots_reveal_token_schema() -> Value {
+    proto("RevealBotTokenResult")
+}
+
+fn typing_schema() -> Value {
+    proto("SendComposeActionResult")
+}
+
+fn note_schema() -> Value {
+    proto("SendMessageResult")
+}
+
+fn notes_list_schema() -> Value {
+    proto("GetChatHistoryResult")
+}
+
+fn bookmark() -> Value {
+    object(
+        &[
+            ("id", integer()),
+            ("messageId", integer()),
+            ("savedAt", integer()),
+            ("message", proto("Message")),
+        ],
+        &[
+            ("chatId", integer()),
+            ("userId", integer()),
+            ("note", string()),
+        ],
+    )
+}
+
+fn bookmarks_add_schema() -> Value {
+    object(&[("updated", boolean()), ("bookmark", bookmark())], &[])
+}
+
+fn bookmarks_list_schema() -> Value {
+    object(&[("bookmarks", array(bookmark()))], &[])
+}
+
+fn bookmarks_open_schema() -> Value {
+    object(
+        &[
+            ("bookmark", bookmark()),
+            ("url", nullable(string())),
+            ("opened", boolean()),
+        ],
+        &[],
+    )
+}
+
+fn removed_id_schema() -> Value {
+    object(&[("removed", integer())], &[])
+}
+
+fn alert() -> Value {
+    object(
+        &[
+            ("id", integer()),
+            ("keyword", string()),
+            ("notify", boolean()),
+            ("createdAt", integer()),
+        ],
+        &[
+            ("chatId", integer()),
+            ("exec", string()),
+            ("cooldownSecs", integer()),
+            ("mutedUntil", integer()),
+        ],
+    )
+}
+
+fn alert_schema() -> Value {
+    object(&[("alert", alert())], &[])
+}
+
+fn alerts_list_schema() -> Value {
+    object(&[("alerts", array(alert()))], &[])
+}
+
+fn alerts_run_schema() -> Value {
+    object(
+        &[
+            ("alertId", integer()),
+            ("keyword", string()),
+            ("message", proto("Message")),
+        ],
+        &[],
+    )
+}
+
+fn resend_pending_schema() -> Value {
+    let pending = object(
+        &[
+            ("id", integer()),
+            ("peer", proto("InputPeer")),
+            ("media", proto("InputMedia")),
+            ("caption", nullable(string())),
+            ("parseMarkdown", boolean()),
+            ("replyToMsgId", nullable(integer())),
+            ("entities", nullable(proto("MessageEntities"))),
+            ("hasLink", nullable(boolean())),
+            ("sendMode", nullable(integer())),
+            ("randomId", integer()),
+            ("idempotencyKey", nullable(string())),
+            ("fileName", string()),
+            ("sizeBytes", integer()),
+            ("failedAt", integer()),
+            ("attempts", integer()),
+            ("lastError", string()),
+        ],
+        &[],
+    );
+    let result = object(
+        &[
+            ("id", integer()),
+            ("to", string()),
+            ("fileName", string()),
+            ("status", string()),
+        ],
+        &[("messageId", integer()), ("error", string())],
+    );
+    one_of(vec![
+        object(&[("pending", array(pending))], &[]),
+        object(
+            &[
+                ("sent", integer()),
+                ("failed", integer()),
+                ("discarded", integer()),
+                ("results", array(result)),
+            ],
+            &[],
+        ),
+    ])
+}
+
+fn badge_schema() -> Value {
+    let space = object(
+        &[
+            ("spaceId", nullable(integer())),
+            ("name", string()),
+            ("unread", integer()),
+        ],
+        &[],
+    );
+    object(
+        &[("total", integer()), ("unreadChats", integer())],
+        &[("spaces", array(space))],
+    )
+}
+
+fn digest_schema() -> Value {
+    let chat = object(
+        &[
+            ("chatId", integer()),
+            ("peer", peer_summary()),
+            ("displayName", string()),
+            ("spaceName", nullable(string())),
+            ("unreadCount", integer()),
+            ("messageCount", integer()),
+            ("mentionCount", integer()),
+            ("lastMessageLine", nullable(string())),
+        ],
+        &[],
+    );
+    let mention = object(
+        &[
+            ("chatId", integer()),
+            ("chatName", string()),
+            ("messageId", integer()),
+            ("date", integer()),
+            ("senderName", string()),
+            ("preview", string()),
+        ],
+        &[],
+    );
+    object(
+        &[
+            ("since", integer()),
+            ("spaceId", nullable(integer())),
+            ("chatsWithActivity", integer()),
+            ("totalMessages", integer()),
+            ("totalUnread", integer()),
+            ("totalMentions", integer()),
+            ("chats", array(chat)),
+            ("mentions", array(mention)),
+        ],
+        &[("postedToChatId", integer())],
+    )
+}
+
+fn summarize_schema() -> Value {
+    object(
+        &[
+            ("peerType", string()),
+            ("peerId", integer()),
+            ("name", nullable(string())),
+            ("since", nullable(integer())),
+            ("until", nullable(integer())),
+            ("messages", integer()),
+            ("summary", nullable(string())),
+            ("postedToChatId", nullable(integer())),
+        ],
+        &[],
+    )
+}
+
+fn events_extract_schema() -> Value {
+    let event = object(
+        &[
+            ("messageId", integer()),
+            ("start", integer()),
+            ("end", integer()),
+            ("allDay", boolean()),
+            ("matched", string()),
+            ("summary", string()),
+            ("description", string()),
+            ("url", nullable(string())),
+        ],
+        &[],
+    );
+    object(
+        &[
+            ("path", nullable(string())),
+            ("messages", integer()),
+            ("events", array(event)),
+        ],
+        &[],
+    )
+}
+
+fn open_schema() -> Value {
+    object(&[("url", string()), ("opened", boolean())], &[])
+}
+
+fn schema_proto_schema() -> Value {
+    let file = object(&[("name", string()), ("contents", string())], &[]);
+    object(&[("files", array(file))], &[])
+}
+
+fn schema_output_schema() -> Value {
+    let entry = object(&[("command", string()), ("description", string())], &[]);
+    let list = object(&[("version", integer()), ("schemas", array(entry))], &[]);
+    let mut schema = json!({ "type": "object", "additionalProperties": true });
+    schema["description"] = json!("A JSON Schema document, when a command is given");
+    one_of(vec![list, schema])
+}
+
+fn api_post_schema() -> Value {
+    api_object("API method's result")
+}
+
+fn rpc_call_schema() -> Value {
+    object(&[("method", string()), ("result", rpc_result())], &[])
+}
+
+fn rpc_batch_schema() -> Value {
+    let item = object(
+        &[
+            ("index", integer()),
+            ("method", string()),
+            ("ok", boolean()),
+        ],
+        &[
+            ("result", rpc_result()),
+            ("error", error_schema()["properties"]["error"].clone()),
+        ],
+    );
+    array(item)
+}
+
+fn import_schema() -> Value {
+    let thread = object(
+        &[
+            ("sourceId", string()),
+            ("title", string()),
+            ("messages", integer()),
+            ("imported", integer()),
+            ("skipped", integer()),
+        ],
+        &[("chatId", integer())],
+    );
+    object(
+        &[
+            ("source", string()),
+            ("mappingPath", string()),
+            ("dryRun", boolean()),
+            ("threads", array(thread)),
+        ],
+        &[],
+    )
+}
+
+fn backup_create_schema() -> Value {
+    object(
+        &[
+            ("path", string()),
+            ("createdAt", integer()),
+            ("chats", integer()),
+            ("messages", integer()),
+            ("media", integer()),
+            ("incompleteChatIds", array(integer())),
+        ],
+        &[("incrementalSince", integer())],
+    )
+}
+
+fn backup_manifest_schema() -> Value {
+    let chat = object(
+        &[
+            ("chatId", integer()),
+            ("title", string()),
+            ("messages", integer()),
+            ("media", integer()),
+            ("complete", boolean()),
+        ],
+        &[("afterMessageId", integer()), ("lastMessageId", integer())],
+    );
+    object(
+        &[
+            ("version", integer()),
+            ("createdAt", integer()),
+            ("chats", array(chat)),
+        ],
+        &[("incrementalSince", integer())],
+    )
+}
+
+fn backup_inspect_schema() -> Value {
+    one_of(vec![backup_manifest_schema(), message_list()])
+}
+
+/// `MessageListOutput`: messages with display names and previews resolved.
+fn message_list() -> Value {
+    let user = object(&[("displayName", string()), ("user", proto("User"))], &[]);
+    let item = object(
+        &[
+            ("message", proto("Message")),
+            ("preview", string()),
+            ("translation", nullable(proto("MessageTranslation"))),
+            ("sender", nullable(user)),
+            ("senderName", string()),
+            ("relativeDate", string()),
+            ("media", nullable(media_summary())),
+            ("attachments", array(attachment_summary())),
+        ],
+        &[],
+    );
+    object(
+        &[
+            ("items", array(item)),
+            ("peer", nullable(peer_summary())),
+            ("peerName", nullable(string())),
+        ],
+        &[],
+    )
+}
+
+fn media_summary() -> Value {
+    object(
+        &[
+            ("kind", string()),
+            ("fileName", nullable(string())),
+            ("mimeType", nullable(string())),
+            ("size", nullable(integer())),
+            ("duration", nullable(integer())),
+            ("width", nullable(integer())),
+            ("height", nullable(integer())),
+            ("url", nullable(string())),
+        ],
+        &[],
+    )
+}
+
+fn attachment_summary() -> Value {
+    object(
+        &[
+            ("kind", string()),
+            ("title", nullable(string())),
+            ("url", nullable(string())),
+            ("siteName", nullable(string())),
+            ("application", nullable(string())),
+            ("status", nullable(string())),
+            ("number", nullable(string())),
+            ("assignedUserId", nullable(integer())),
+        ],
+        &[],
+    )
+}
+
+fn archive_run_schema() -> Value {
+    object(
+        &[
+            ("chatId", integer()),
+            ("path", string()),
+            ("fetched", integer()),
+            ("appended", integer()),
+            ("archived", integer()),
+            ("complete", boolean()),
+        ],
+        &[],
+    )
+}
+
+fn archive_schedule_schema() -> Value {
+    object(
+        &[
+            ("format", string()),
+            ("label", string()),
+            ("command", array(string())),
+            ("contents", string()),
+        ],
+        &[],
+    )
+}
+
+fn sync_schema() -> Value {
+    let chat = object(
+        &[("chatId", integer()), ("messages", integer())],
+        &[("title", string())],
+    );
+    object(
+        &[
+            ("firstSync", boolean()),
+            ("lastSyncDate", integer()),
+            ("messages", integer()),
+            ("deletedMessages", integer()),
+            ("reactions", integer()),
+            ("readStates", integer()),
+            ("chatsUpdated", integer()),
+            ("chatsDeleted", integer()),
+            ("usersUpdated", integer()),
+            ("spacesUpdated", integer()),
+            ("chats", array(chat)),
+        ],
+        &[],
+    )
+}
+
+fn watch_reactions_schema() -> Value {
+    object(
+        &[
+            ("chatId", integer()),
+            ("messageId", integer()),
+            ("userId", integer()),
+            ("emoji", string()),
+            ("date", integer()),
+        ],
+        &[],
+    )
+}
+
+fn watch_messages_schema() -> Value {
+    proto("Message")
+}
+
+fn debug_record_schema() -> Value {
+    object(&[("output", string()), ("events", integer())], &[])
+}
+
+fn debug_event_schema() -> Value {
+    let event = |kind: &str, field: (&str, Value)| {
+        object(
+            &[("atMs", integer()), ("kind", string_enum(&[kind])), field],
+            &[],
+        )
+    };
+    one_of(vec![
+        event("updates", ("updates", array(proto("Update")))),
+        event("ack", ("msgId", integer())),
+        event("pong", ("nonce", integer())),
+        event("lagged", ("skipped", integer())),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cli;
+    use clap::CommandFactory;
+
+    #[test]
+    fn schemas_are_versioned_and_looked_up_by_command() {
+        let schema = output_schema("chats list").unwrap();
+        assert_eq!(
+            schema["$id"],
+            "https://inline.chat/cli/schemas/v1/chats-list.json"
+        );
+        assert_eq!(schema["x-inline-schema-version"], OUTPUT_SCHEMA_VERSION);
+        assert_eq!(
+            schema["properties"]["notifications"]["items"]["required"],
+            json!(["chatId", "mode", "muted"])
+        );
+        assert_eq!(
+            output_schema("messages media").unwrap()["properties"]["peerName"]["type"],
+            json!(["string", "null"])
+        );
+        assert!(output_schema("chats").is_none());
+        assert_eq!(output_schema("alias list").unwrap()["type"], "array");
+        assert_eq!(
+            output_schema("doctor").unwrap()["oneOf"]
+                .as_array()
+                .map(Vec::len),
+            Some(2)
+        );
+        for spec in OUTPUT_SCHEMAS {
+            let schema = output_schema(spec.command).unwrap();
+            assert!(
+                schema["type"] == "object"
+                    || schema["type"] == "array"
+                    || schema["oneOf"].is_array(),
+                "{}",
+                spec.command
+            );
+        }
+    }
+
+    #[test]
+    fn every_command_with_json_output_has_a_schema() {
+        fn leaf_commands(command: &clap::Command, path: &str, out: &mut Vec<String>) {
+            let mut subcommands = command
+                .get_subcommands()
+                .filter(|subcommand| subcommand.get_name() != "help")
+                .peekable();
+            if subcommands.peek().is_none() {
+                out.push(path.to_string());
+            }
+            for subcommand in subcommands {
+                let path = format!("{path} {}", subcommand.get_name());
+                leaf_commands(subcommand, path.trim_start(), out);
+            }
+        }
+
+        let mut leaves = Vec::new();
+        leaf_commands(&Cli::command(), "", &mut leaves);
+        assert!(leaves.len() > 100);
+        for leaf in &leaves {
+            assert_eq!(
+                output_schema(leaf).is_some(),
+                !WITHOUT_JSON_OUTPUT.contains(&leaf.as_str()),
+                "`inline {leaf} --json` needs an output schema"
+            );
+        }
+        for spec in OUTPUT_SCHEMAS.iter().filter(|spec| spec.command != "error") {
+            assert!(
+                leaves.iter().any(|leaf| leaf == spec.command),
+                "{}",
+                spec.command
+            );
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use crate::errors::CliError;
 use crate::validation::validate_positive_id_arg;
 use inline_protocol::proto;
@@ -43,23 +45,156 @@ pub(crate) fn input_peer_from_args(
     }
 }
 
-pub(crate) fn api_peer_from_args(
-    chat_id: Option<i64>,
-    user_id: Option<i64>,
-) -> Result<PeerId, Box<dyn std::error::Error>> {
-    match (chat_id, user_id) {
-        (Some(_), Some(_)) => {
-            Err(CliError::invalid_args("Provide only one of --chat-id or --user-id.").into())
+/// A peer named on the command line, before any network lookup.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum PeerTarget {
+    Chat(i64),
+    User(i64),
+    /// `@username`, resolved against the users in your chat list.
+    Username(String),
+}
+
+impl PeerTarget {
+    /// Combines `--to` (`chat:123`, `user:42`, `@username`, or a config alias) with
+    /// the legacy `--chat-id`/`--user-id` pair.
+    pub(crate) fn from_args(
+        to: Option<&str>,
+        chat_id: Option<i64>,
+        user_id: Option<i64>,
+        aliases: &BTreeMap<String, String>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let Some(to) = to else {
+            return match (chat_id, user_id) {
+                (Some(_), Some(_)) => Err(CliError::invalid_args(
+                    "Provide only one of --chat-id or --user-id.",
+                )
+                .into()),
+                (Some(chat_id), None) => {
+                    Ok(Self::Chat(validate_positive_id_arg("--chat-id", chat_id)?))
+                }
+                (None, Some(user_id)) => {
+                    Ok(Self::User(validate_positive_id_arg("--user-id", user_id)?))
+                }
+                (None, None) => Err(CliError::missing_peer().into()),
+            };
+        };
+        if chat_id.is_some() || user_id.is_some() {
+            return Err(CliError::invalid_args(
+                "Provide only one of --to, --chat-id, or --user-id.",
+            )
+            .into());
         }
-        (Some(chat_id), None) => {
-            let chat_id = validate_positive_id_arg("--chat-id", chat_id)?;
-            Ok(PeerId::thread(chat_id))
+
+        let to = to.trim();
+        if let Some(target) = Self::parse(to)? {
+            return Ok(target);
         }
-        (None, Some(user_id)) => {
-            let user_id = validate_positive_id_arg("--user-id", user_id)?;
-            Ok(PeerId::user(user_id))
+        let Some(alias_target) = aliases.get(to) else {
+            return Err(CliError::unknown_peer_alias(to).into());
+        };
+        Self::parse(alias_target)?.ok_or_else(|| {
+            CliError::invalid_args(format!(
+                "Alias `{to}` points to `{alias_target}`; expected chat:ID, user:ID, or @username."
+            ))
+            .into()
+        })
+    }
+
+    /// Parses `chat:123`, `user:42`, or `@username`. Returns `None` for anything
+    /// else so callers can fall back to alias lookup.
+    pub(crate) fn parse(value: &str) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let value = value.trim();
+        if let Some(username) = value.strip_prefix('@') {
+            let valid = !username.is_empty()
+                && username
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+            if !valid {
+                return Err(CliError::invalid_args(format!(
+                    "Invalid username `{value}`: use @ followed by letters, digits, or '_'."
+                ))
+                .into());
+            }
+            return Ok(Some(Self::Username(username.to_string())));
         }
-        (None, None) => Err(CliError::missing_peer().into()),
+
+        let Some((kind, id)) = value.split_once(':') else {
+            return Ok(None);
+        };
+        let kind = kind.trim();
+        if !matches!(kind, "chat" | "thread" | "user") {
+            return Ok(None);
+        }
+        let id = id.trim().parse::<i64>().map_err(|_| {
+            CliError::invalid_args(format!(
+                "Invalid peer `{value}`: expected chat:ID, user:ID, or @username."
+            ))
+        })?;
+        let id = validate_positive_id_arg("--to", id)?;
+        Ok(Some(if kind == "user" {
+            Self::User(id)
+        } else {
+            Self::Chat(id)
+        }))
+    }
+}
+
+/// Finds a user in a `getChats` payload by username (case-insensitive, without `@`).
+pub(crate) fn find_user_by_username<'a>(
+    users: &'a [proto::User],
+    username: &str,
+) -> Option<&'a proto::User> {
+    users.iter().find(|user| {
+        user.username
+            .as_deref()
+            .is_some_and(|candidate| candidate.eq_ignore_ascii_case(username))
+    })
+}
+
+pub(crate) fn input_chat_peer(chat_id: i64) -> proto::InputPeer {
+    proto::InputPeer {
+        r#type: Some(proto::input_peer::Type::Chat(proto::InputPeerChat {
+            chat_id,
+        })),
+    }
+}
+
+pub(crate) fn input_user_peer(user_id: i64) -> proto::InputPeer {
+    proto::InputPeer {
+        r#type: Some(proto::input_peer::Type::User(proto::InputPeerUser {
+            user_id,
+        })),
+    }
+}
+
+/// Your saved messages.
+pub(crate) fn input_self_peer() -> proto::InputPeer {
+    proto::InputPeer {
+        r#type: Some(proto::input_peer::Type::Self_(proto::InputPeerSelf {})),
+    }
+}
+
+pub(crate) fn api_peer_from_input(peer: &proto::InputPeer) -> Option<PeerId> {
+    match &peer.r#type {
+        Some(proto::input_peer::Type::Chat(chat)) => Some(PeerId::thread(chat.chat_id)),
+        Some(proto::input_peer::Type::User(user)) => Some(PeerId::user(user.user_id)),
+        _ => None,
+    }
+}
+
+pub(crate) fn validate_alias_name(name: &str) -> Result<&str, CliError> {
+    let name = name.trim();
+    let valid = !name.is_empty()
+        && !name.contains(':')
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-');
+    if valid {
+        Ok(name)
+    } else {
+        Err(CliError::invalid_args(format!(
+            "Invalid alias name `{name}`: use letters, digits, '_' or '-'."
+        )))
     }
 }
 
@@ -111,28 +246,105 @@ mod tests {
     #[test]
     fn api_peer_from_chat_and_user_ids() {
         assert_eq!(
-            api_peer_from_args(Some(123), None).unwrap(),
-            PeerId::thread(123)
+            api_peer_from_input(&input_chat_peer(123)),
+            Some(PeerId::thread(123))
         );
         assert_eq!(
-            api_peer_from_args(None, Some(456)).unwrap(),
-            PeerId::user(456)
+            api_peer_from_input(&input_user_peer(456)),
+            Some(PeerId::user(456))
         );
     }
 
     #[test]
-    fn api_peer_rejects_non_positive_ids() {
-        let err = api_peer_from_args(Some(0), None).unwrap_err();
+    fn peer_target_rejects_non_positive_ids() {
+        let aliases = BTreeMap::new();
+        let err = PeerTarget::from_args(None, Some(0), None, &aliases).unwrap_err();
         let cli_err = err.downcast_ref::<CliError>().unwrap();
         assert_eq!(cli_err.code, "invalid_args");
         assert!(cli_err.message.contains("--chat-id"));
 
-        let err = api_peer_from_args(None, Some(-1)).unwrap_err();
+        let err = PeerTarget::from_args(None, None, Some(-1), &aliases).unwrap_err();
         let cli_err = err.downcast_ref::<CliError>().unwrap();
         assert_eq!(cli_err.code, "invalid_args");
         assert!(cli_err.message.contains("--user-id"));
     }
 
+    #[test]
+    fn peer_target_accepts_specs_usernames_and_aliases() {
+        let aliases = BTreeMap::from([
+            ("standup".to_string(), "chat:123".to_string()),
+            ("sam".to_string(), "user:42".to_string()),
+            ("lead".to_string(), "@dena".to_string()),
+            ("broken".to_string(), "nowhere".to_string()),
+        ]);
+        let target = |to: &str| PeerTarget::from_args(Some(to), None, None, &aliases);
+
+        assert_eq!(target("standup").unwrap(), PeerTarget::Chat(123));
+        assert_eq!(target("sam").unwrap(), PeerTarget::User(42));
+        assert_eq!(target("user:7").unwrap(), PeerTarget::User(7));
+        assert_eq!(target("thread:8").unwrap(), PeerTarget::Chat(8));
+        assert_eq!(
+            target("@mo_salah").unwrap(),
+            PeerTarget::Username("mo_salah".to_string())
+        );
+        assert_eq!(
+            target("lead").unwrap(),
+            PeerTarget::Username("dena".to_string())
+        );
+
+        let err = target("nobody").unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<CliError>().unwrap().code,
+            "unknown_alias"
+        );
+        assert!(target("broken").is_err());
+        assert!(target("chat:abc").is_err());
+        assert!(target("chat:0").is_err());
+        assert!(target("@").is_err());
+        assert!(target("@bad name").is_err());
+        assert!(PeerTarget::from_args(Some("sam"), Some(1), None, &aliases).is_err());
+    }
+
+    #[test]
+    fn peer_target_falls_back_to_legacy_flags() {
+        let aliases = BTreeMap::new();
+        assert_eq!(
+            PeerTarget::from_args(None, Some(5), None, &aliases).unwrap(),
+            PeerTarget::Chat(5)
+        );
+        let err = PeerTarget::from_args(None, None, None, &aliases).unwrap_err();
+        assert_eq!(err.downcast_ref::<CliError>().unwrap().code, "missing_peer");
+    }
+
+    #[test]
+    fn finds_users_by_username_case_insensitively() {
+        let users = vec![
+            proto::User {
+                id: 1,
+                username: Some("Dena".to_string()),
+                ..Default::default()
+            },
+            proto::User {
+                id: 2,
+                username: None,
+                ..Default::default()
+            },
+        ];
+        assert_eq!(
+            find_user_by_username(&users, "dena").map(|user| user.id),
+            Some(1)
+        );
+        assert!(find_user_by_username(&users, "mo").is_none());
+    }
+
+    #[test]
+    fn alias_names_are_restricted() {
+        assert_eq!(validate_alias_name(" standup ").unwrap(), "standup");
+        assert!(validate_alias_name("chat:1").is_err());
+        assert!(validate_alias_name("a.b").is_err());
+        assert!(validate_alias_name("").is_err());
+    }
+
     #[test]
     fn peer_key_from_chat_peer() {
         let peer = proto::Peer {
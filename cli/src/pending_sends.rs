@@ -0,0 +1,132 @@
+//! Journal of attachments that uploaded but never went out as a message.
+//!
+//! `messages send --attach` uploads first and sends second. When the send
+//! fails (the socket dropped, the server timed out), the uploaded media id is
+//! kept here with everything needed to send it again, so `inline resend
+//! pending` can finish the job without uploading the file twice. The original
+//! `random_id` is reused, so a send that did reach the server isn't
+//! duplicated. Entries are dropped after 7 days.
+
+use inline_protocol::proto;
+use serde::{Deserialize, Serialize};
+
+use crate::idempotency::peer_key;
+use crate::output::format_relative_date;
+use crate::state::LocalState;
+
+const RETENTION_SECS: i64 = 7 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingSend {
+    pub id: u32,
+    pub peer: proto::InputPeer,
+    pub media: proto::InputMedia,
+    pub caption: Option<String>,
+    pub parse_markdown: bool,
+    pub reply_to_msg_id: Option<i64>,
+    pub entities: Option<proto::MessageEntities>,
+    pub has_link: Option<bool>,
+    /// `proto::MessageSendMode` of the original send, so a silent send stays
+    /// silent when resent.
+    #[serde(default)]
+    pub send_mode: Option<i32>,
+    pub random_id: i64,
+    /// Recorded as sent under this key once the resend succeeds.
+    pub idempotency_key: Option<String>,
+    pub file_name: String,
+    pub size_bytes: u64,
+    /// Unix seconds of the first failure.
+    pub failed_at: i64,
+    pub attempts: u32,
+    pub last_error: String,
+}
+
+impl PendingSend {
+    pub(crate) fn destination(&self) -> String {
+        peer_key(&self.peer)
+    }
+}
+
+/// Adds `entry` under the next free id, dropping expired entries first.
+pub(crate) fn record(state: &mut LocalState, mut entry: PendingSend, now: i64) -> u32 {
+    state
+        .pending_sends
+        .retain(|pending| now - pending.failed_at < RETENTION_SECS);
+    entry.id = state
+        .pending_sends
+        .iter()
+        .map(|pending| pending.id)
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let id = entry.id;
+    state.pending_sends.push(entry);
+    id
+}
+
+pub(crate) fn print_pending_sends(pending: &[&PendingSend], now: i64) {
+    if pending.is_empty() {
+        println!("No pending sends.");
+        return;
+    }
+    for entry in pending {
+        println!(
+            "{:>3}  {}  {} ({} bytes)  failed {}  {} attempt(s): {}",
+            entry.id,
+            entry.destination(),
+            entry.file_name,
+            entry.size_bytes,
+            format_relative_date(entry.failed_at, now),
+            entry.attempts,
+            entry.last_error
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peer::input_chat_peer;
+
+    fn entry(failed_at: i64) -> PendingSend {
+        PendingSend {
+            id: 0,
+            peer: input_chat_peer(7),
+            media: proto::InputMedia::default(),
+            caption: None,
+            parse_markdown: false,
+            reply_to_msg_id: None,
+            entities: None,
+            has_link: None,
+            send_mode: None,
+            random_id: 99,
+            idempotency_key: None,
+            file_name: "build.zip".to_string(),
+            size_bytes: 1 << 30,
+            failed_at,
+            attempts: 1,
+            last_error: "connection closed".to_string(),
+        }
+    }
+
+    #[test]
+    fn entries_get_fresh_ids_and_expire() {
+        let mut state = LocalState::default();
+        assert_eq!(record(&mut state, entry(0), 0), 1);
+        assert_eq!(record(&mut state, entry(10), 10), 2);
+        assert_eq!(state.pending_sends[1].destination(), "chat:7");
+
+        // The first has expired by now; ids continue from what's left.
+        let now = RETENTION_SECS + 5;
+        assert_eq!(record(&mut state, entry(now), now), 3);
+        assert_eq!(
+            state
+                .pending_sends
+                .iter()
+                .map(|pending| pending.id)
+                .collect::<Vec<_>>(),
+            [2, 3]
+        );
+    }
+}
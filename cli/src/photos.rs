@@ -0,0 +1,347 @@
+//! Photo conversion before upload: HEIC/HEIF to JPEG and `--max-dimension`
+//! downscaling for `messages send --attach`.
+//!
+//! Conversion shells out to the system `sips` tool on macOS (ImageIO) and to
+//! ImageMagick elsewhere, the same way video metadata goes through `ffprobe`.
+//! When neither is installed the original file is uploaded unchanged.
+//!
+//! The same tools read photo dimensions and render the small thumbnail sent
+//! with each photo upload.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct PhotoOptions {
+    /// Longest side in pixels; larger photos are scaled down to fit.
+    pub(crate) max_dimension: Option<u32>,
+    /// Upload photos byte-for-byte (no HEIC conversion, no downscaling).
+    pub(crate) original: bool,
+}
+
+/// Longest side of upload thumbnails, in pixels.
+pub(crate) const THUMBNAIL_SIZE: u32 = 320;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImageTool {
+    Sips,
+    Magick,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PhotoPlan {
+    to_jpeg: bool,
+    max_dimension: Option<u32>,
+}
+
+/// A converted copy of a photo in the data dir. The caller owns `path`.
+#[derive(Debug)]
+pub(crate) struct ConvertedPhoto {
+    pub(crate) path: PathBuf,
+    pub(crate) file_name: String,
+    pub(crate) description: String,
+}
+
+/// Converts or downscales `path` when `options` call for it. Returns `Ok(None)`
+/// when the photo is fine as it is, and `Err` with a reason when it needed
+/// work but no tool could do it.
+pub(crate) fn prepare_photo(
+    path: &Path,
+    data_dir: &Path,
+    options: &PhotoOptions,
+) -> Result<Option<ConvertedPhoto>, String> {
+    if options.original {
+        return Ok(None);
+    }
+    let kind = PhotoKind::from_path(path);
+    if kind == PhotoKind::Other {
+        return Ok(None);
+    }
+    let needs_tool = kind == PhotoKind::Heic || options.max_dimension.is_some();
+    if !needs_tool {
+        return Ok(None);
+    }
+    let tool = find_tool().ok_or_else(|| {
+        if cfg!(target_os = "macos") {
+            "sips is not available".to_string()
+        } else {
+            "ImageMagick (magick) is not installed".to_string()
+        }
+    })?;
+    let dimensions = read_dimensions(tool, path);
+    let Some(plan) = plan_photo(kind, options, dimensions) else {
+        return Ok(None);
+    };
+
+    let stem = path
+        .file_stem()
+        .and_then(|value| value.to_str())
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or("photo");
+    let extension = if plan.to_jpeg {
+        "jpg".to_string()
+    } else {
+        path.extension()
+            .and_then(|value| value.to_str())
+            .unwrap_or("jpg")
+            .to_ascii_lowercase()
+    };
+    fs::create_dir_all(data_dir).map_err(|err| err.to_string())?;
+    let output = data_dir.join(format!("{stem}-{}.{extension}", current_epoch_nanos()));
+
+    let mut command = photo_command(tool, path, &output, &plan);
+    let result = command
+        .output()
+        .map_err(|err| format!("could not run {}: {err}", tool.name()))?;
+    let converted = fs::metadata(&output)
+        .map(|metadata| metadata.len() > 0)
+        .unwrap_or(false);
+    if !result.status.success() || !converted {
+        let _ = fs::remove_file(&output);
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        return Err(format!("{} failed: {}", tool.name(), stderr.trim()));
+    }
+
+    Ok(Some(ConvertedPhoto {
+        path: output,
+        file_name: format!("{stem}.{extension}"),
+        description: plan.describe(),
+    }))
+}
+
+/// Dimensions and a JPEG thumbnail of a photo about to be uploaded.
+#[derive(Debug)]
+pub(crate) struct PhotoPreview {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) thumbnail: Option<Vec<u8>>,
+}
+
+/// Reads the photo's dimensions and renders a thumbnail. Returns `None` when
+/// no image tool is installed or the file can't be read.
+pub(crate) fn photo_preview(path: &Path, data_dir: &Path) -> Option<PhotoPreview> {
+    let tool = find_tool()?;
+    let (width, height) = read_dimensions(tool, path)?;
+    fs::create_dir_all(data_dir).ok()?;
+    let output = data_dir.join(format!("thumbnail-{}.jpg", current_epoch_nanos()));
+    let plan = PhotoPlan {
+        to_jpeg: true,
+        max_dimension: Some(THUMBNAIL_SIZE),
+    };
+    let rendered = photo_command(tool, path, &output, &plan)
+        .output()
+        .is_ok_and(|result| result.status.success());
+    let thumbnail = rendered
+        .then(|| fs::read(&output).ok())
+        .flatten()
+        .filter(|bytes| !bytes.is_empty());
+    let _ = fs::remove_file(&output);
+    Some(PhotoPreview {
+        width,
+        height,
+        thumbnail,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PhotoKind {
+    Heic,
+    /// JPEG and PNG, which are scaled but keep their format.
+    Scalable,
+    /// GIFs (may be animated), SVGs, and everything else are left alone.
+    Other,
+}
+
+impl PhotoKind {
+    fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|value| value.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match extension.as_str() {
+            "heic" | "heif" => Self::Heic,
+            "jpg" | "jpeg" | "png" => Self::Scalable,
+            _ => Self::Other,
+        }
+    }
+}
+
+fn plan_photo(
+    kind: PhotoKind,
+    options: &PhotoOptions,
+    dimensions: Option<(u32, u32)>,
+) -> Option<PhotoPlan> {
+    let max_dimension = options.max_dimension.filter(|max| match dimensions {
+        Some((width, height)) => width.max(height) > *max,
+        // Unknown size: let the tool decide, it won't upscale.
+        None => true,
+    });
+    let to_jpeg = kind == PhotoKind::Heic;
+    (to_jpeg || max_dimension.is_some()).then_some(PhotoPlan {
+        to_jpeg,
+        max_dimension,
+    })
+}
+
+impl PhotoPlan {
+    fn describe(&self) -> String {
+        match (self.to_jpeg, self.max_dimension) {
+            (true, Some(max)) => format!("converted to JPEG, max {max}px"),
+            (true, None) => "converted to JPEG".to_string(),
+            (false, Some(max)) => format!("scaled to max {max}px"),
+            (false, None) => "unchanged".to_string(),
+        }
+    }
+}
+
+impl ImageTool {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Sips => "sips",
+            Self::Magick => "magick",
+        }
+    }
+}
+
+fn find_tool() -> Option<ImageTool> {
+    let candidates: &[ImageTool] = if cfg!(target_os = "macos") {
+        &[ImageTool::Sips, ImageTool::Magick]
+    } else {
+        &[ImageTool::Magick]
+    };
+    candidates.iter().copied().find(|tool| {
+        let probe = match tool {
+            ImageTool::Sips => Command::new("sips").arg("--help").output(),
+            ImageTool::Magick => Command::new("magick").arg("-version").output(),
+        };
+        probe.is_ok()
+    })
+}
+
+fn photo_command(tool: ImageTool, input: &Path, output: &Path, plan: &PhotoPlan) -> Command {
+    let mut command = Command::new(tool.name());
+    match tool {
+        ImageTool::Sips => {
+            if plan.to_jpeg {
+                command.args(["-s", "format", "jpeg", "-s", "formatOptions", "90"]);
+            }
+            if let Some(max) = plan.max_dimension {
+                command.arg("-Z").arg(max.to_string());
+            }
+            command.arg(input).arg("--out").arg(output);
+        }
+        ImageTool::Magick => {
+            // -auto-orient bakes in the EXIF rotation that HEIC viewers apply.
+            command.arg(input).arg("-auto-orient");
+            if let Some(max) = plan.max_dimension {
+                command.arg("-resize").arg(format!("{max}x{max}>"));
+            }
+            if plan.to_jpeg {
+                command.args(["-quality", "90"]);
+            }
+            command.arg(output);
+        }
+    }
+    command
+}
+
+fn read_dimensions(tool: ImageTool, path: &Path) -> Option<(u32, u32)> {
+    let output = match tool {
+        ImageTool::Sips => Command::new("sips")
+            .args(["-g", "pixelWidth", "-g", "pixelHeight"])
+            .arg(path)
+            .output(),
+        ImageTool::Magick => Command::new("magick")
+            .args(["identify", "-format", "pixelWidth: %w\npixelHeight: %h\n"])
+            .arg(path)
+            .output(),
+    }
+    .ok()
+    .filter(|output| output.status.success())?;
+    parse_dimensions(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Reads `pixelWidth: N` / `pixelHeight: N` lines, as printed by `sips -g`.
+fn parse_dimensions(output: &str) -> Option<(u32, u32)> {
+    let value = |key: &str| {
+        output.lines().find_map(|line| {
+            line.trim()
+                .strip_prefix(key)?
+                .strip_prefix(':')?
+                .trim()
+                .parse::<u32>()
+                .ok()
+        })
+    };
+    Some((value("pixelWidth")?, value("pixelHeight")?))
+}
+
+fn current_epoch_nanos() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heic_converts_and_large_photos_scale_down() {
+        let defaults = PhotoOptions::default();
+        let capped = PhotoOptions {
+            max_dimension: Some(2048),
+            original: false,
+        };
+
+        let heic = PhotoKind::from_path(Path::new("IMG_0001.HEIC"));
+        assert_eq!(heic, PhotoKind::Heic);
+        assert_eq!(
+            plan_photo(heic, &defaults, Some((4032, 3024))),
+            Some(PhotoPlan {
+                to_jpeg: true,
+                max_dimension: None
+            })
+        );
+        assert_eq!(
+            plan_photo(heic, &capped, Some((4032, 3024)))
+                .unwrap()
+                .describe(),
+            "converted to JPEG, max 2048px"
+        );
+
+        let jpeg = PhotoKind::from_path(Path::new("photo.jpg"));
+        assert_eq!(plan_photo(jpeg, &defaults, Some((4032, 3024))), None);
+        assert_eq!(plan_photo(jpeg, &capped, Some((1024, 768))), None);
+        assert_eq!(
+            plan_photo(jpeg, &capped, Some((1024, 3000))).map(|plan| plan.max_dimension),
+            Some(Some(2048))
+        );
+        assert_eq!(
+            PhotoKind::from_path(Path::new("party.gif")),
+            PhotoKind::Other
+        );
+    }
+
+    #[test]
+    fn sips_dimensions_parse_and_original_skips_conversion() {
+        let output = "/tmp/IMG_0001.HEIC\n  pixelWidth: 4032\n  pixelHeight: 3024\n";
+        assert_eq!(parse_dimensions(output), Some((4032, 3024)));
+        assert_eq!(parse_dimensions("pixelWidth: 10\n"), None);
+
+        let options = PhotoOptions {
+            max_dimension: Some(100),
+            original: true,
+        };
+        let converted = prepare_photo(
+            Path::new("IMG_0001.heic"),
+            Path::new("/nonexistent"),
+            &options,
+        );
+        assert!(matches!(converted, Ok(None)));
+    }
+}
@@ -0,0 +1,312 @@
+//! Terminal previews for `inline messages view`.
+//!
+//! Photos are drawn inline with the iTerm2 or kitty graphics protocol when the
+//! terminal advertises one, otherwise as an ASCII thumbnail. Image conversion
+//! (JPEG to PNG for kitty, downscaling to BMP for ASCII) goes through macOS
+//! `sips`; other media opens in Quick Look.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+
+use crate::errors::CliError;
+
+const KITTY_CHUNK_BYTES: usize = 4096;
+const PNG_MAGIC: &[u8] = b"\x89PNG";
+const ASCII_RAMP: &[u8] = b" .:-=+*#%@";
+pub(crate) const DEFAULT_ASCII_WIDTH: u32 = 80;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ImageProtocol {
+    Iterm,
+    Kitty,
+    Ascii,
+}
+
+impl ImageProtocol {
+    pub(crate) fn parse(value: &str) -> Result<Option<Self>, CliError> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(None),
+            "iterm" | "iterm2" => Ok(Some(Self::Iterm)),
+            "kitty" => Ok(Some(Self::Kitty)),
+            "ascii" => Ok(Some(Self::Ascii)),
+            other => Err(CliError::invalid_args(format!(
+                "Invalid --protocol `{other}`: expected auto, iterm, kitty, or ascii"
+            ))),
+        }
+    }
+}
+
+/// Picks a graphics protocol from the terminal's environment variables.
+pub(crate) fn detect_image_protocol(env: impl Fn(&str) -> Option<String>) -> ImageProtocol {
+    let term_program = env("TERM_PROGRAM").unwrap_or_default();
+    if env("KITTY_WINDOW_ID").is_some()
+        || env("TERM").is_some_and(|term| term.contains("kitty"))
+        || term_program == "ghostty"
+    {
+        return ImageProtocol::Kitty;
+    }
+    if matches!(term_program.as_str(), "iTerm.app" | "WezTerm")
+        || env("LC_TERMINAL").is_some_and(|terminal| terminal == "iTerm2")
+    {
+        return ImageProtocol::Iterm;
+    }
+    ImageProtocol::Ascii
+}
+
+/// iTerm2 inline image escape (OSC 1337). Also understood by WezTerm.
+fn iterm_image_sequence(bytes: &[u8], file_name: &str) -> String {
+    format!(
+        "\x1b]1337;File=name={};size={};inline=1;preserveAspectRatio=1:{}\x07",
+        BASE64.encode(file_name),
+        bytes.len(),
+        BASE64.encode(bytes)
+    )
+}
+
+/// kitty graphics protocol transmit-and-display for PNG data, split into the
+/// 4096-byte base64 chunks the protocol requires.
+fn kitty_image_sequence(png: &[u8]) -> String {
+    let encoded = BASE64.encode(png);
+    let chunks = encoded
+        .as_bytes()
+        .chunks(KITTY_CHUNK_BYTES)
+        .collect::<Vec<_>>();
+    let mut output = String::new();
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(index + 1 < chunks.len());
+        let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+        if index == 0 {
+            output.push_str(&format!("\x1b_Gf=100,a=T,m={more};{chunk}\x1b\\"));
+        } else {
+            output.push_str(&format!("\x1b_Gm={more};{chunk}\x1b\\"));
+        }
+    }
+    output
+}
+
+/// Renders an uncompressed 24/32-bit BMP as ASCII art, `width` columns wide.
+/// Rows are sampled at half the column density since terminal cells are tall.
+fn ascii_from_bmp(bmp: &[u8], width: u32) -> Option<String> {
+    let (pixel_offset, bmp_width, bmp_height, bits) = bmp_header(bmp)?;
+    if !matches!(bits, 24 | 32) || bmp_width == 0 || bmp_height == 0 {
+        return None;
+    }
+    let bytes_per_pixel = bits as usize / 8;
+    let row_stride = (bmp_width as usize * bytes_per_pixel).div_ceil(4) * 4;
+    let columns = width.clamp(1, bmp_width);
+    let rows = (bmp_height as u64 * columns as u64 / bmp_width as u64 / 2).max(1) as u32;
+
+    let mut output = String::new();
+    for row in 0..rows {
+        let y = (row as u64 * bmp_height as u64 / rows as u64) as usize;
+        // BMP rows are stored bottom-up.
+        let row_start = pixel_offset + (bmp_height as usize - 1 - y) * row_stride;
+        for column in 0..columns {
+            let x = (column as u64 * bmp_width as u64 / columns as u64) as usize;
+            let pixel = bmp.get(row_start + x * bytes_per_pixel..)?;
+            let (b, g, r) = (pixel[0] as u32, pixel[1] as u32, pixel[2] as u32);
+            let luma = (299 * r + 587 * g + 114 * b) / 1000;
+            let index = luma as usize * (ASCII_RAMP.len() - 1) / 255;
+            output.push(ASCII_RAMP[ASCII_RAMP.len() - 1 - index] as char);
+        }
+        output.push('\n');
+    }
+    Some(output)
+}
+
+fn bmp_header(bmp: &[u8]) -> Option<(usize, u32, u32, u16)> {
+    if bmp.get(0..2)? != b"BM" {
+        return None;
+    }
+    let u32_at = |offset: usize| {
+        bmp.get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+    let pixel_offset = u32_at(10)? as usize;
+    let width = i32::from_le_bytes(u32_at(18)?.to_le_bytes());
+    let height = i32::from_le_bytes(u32_at(22)?.to_le_bytes());
+    let compression = u32_at(30)?;
+    let bits = bmp
+        .get(28..30)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))?;
+    // Top-down (negative height) and compressed bitmaps are not produced by sips.
+    if width <= 0 || height <= 0 || !matches!(compression, 0 | 3) {
+        return None;
+    }
+    Some((pixel_offset, width as u32, height as u32, bits))
+}
+
+/// Draws a downloaded photo in the terminal and returns the protocol used.
+/// kitty only accepts PNG, so JPEGs are converted first and fall back to ASCII
+/// when that is not possible.
+pub(crate) fn render_photo(
+    path: &Path,
+    protocol: ImageProtocol,
+    width: u32,
+) -> Result<ImageProtocol, Box<dyn std::error::Error>> {
+    let bytes = fs::read(path)?;
+    match protocol {
+        ImageProtocol::Iterm => {
+            let file_name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("image");
+            write_terminal(&iterm_image_sequence(&bytes, file_name))?;
+            Ok(ImageProtocol::Iterm)
+        }
+        ImageProtocol::Kitty if bytes.starts_with(PNG_MAGIC) => {
+            write_terminal(&kitty_image_sequence(&bytes))?;
+            Ok(ImageProtocol::Kitty)
+        }
+        ImageProtocol::Kitty => {
+            let png_path = path.with_extension("preview.png");
+            let converted = convert_image(path, &png_path, "png", None);
+            let result = if converted {
+                fs::read(&png_path)
+                    .map_err(Into::into)
+                    .and_then(|png| write_terminal(&kitty_image_sequence(&png)).map_err(Into::into))
+                    .map(|()| ImageProtocol::Kitty)
+            } else {
+                render_photo(path, ImageProtocol::Ascii, width)
+            };
+            let _ = fs::remove_file(&png_path);
+            result
+        }
+        ImageProtocol::Ascii => {
+            let bmp_path = path.with_extension("preview.bmp");
+            let converted = convert_image(path, &bmp_path, "bmp", Some(width.max(1)));
+            let ascii = converted
+                .then(|| fs::read(&bmp_path).ok())
+                .flatten()
+                .and_then(|bmp| ascii_from_bmp(&bmp, width));
+            let _ = fs::remove_file(&bmp_path);
+            let ascii = ascii.ok_or_else(|| {
+                CliError::invalid_args(
+                    "ASCII previews need macOS `sips`; pass --protocol iterm or kitty, or use `inline messages download`.",
+                )
+            })?;
+            write_terminal(ascii.trim_end_matches('\n'))?;
+            Ok(ImageProtocol::Ascii)
+        }
+    }
+}
+
+/// Opens a file in Quick Look and waits for the panel to close.
+pub(crate) fn quick_look(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    if !cfg!(target_os = "macos") {
+        return Err(CliError::invalid_args(
+            "Quick Look is only available on macOS; use `inline messages download` instead.",
+        )
+        .into());
+    }
+    Command::new("qlmanage")
+        .arg("-p")
+        .arg(path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+    Ok(())
+}
+
+/// Converts an image with macOS `sips`. Returns false when `sips` is missing or
+/// the conversion fails.
+fn convert_image(input: &Path, output: &Path, format: &str, max_size: Option<u32>) -> bool {
+    let mut command = Command::new("sips");
+    command.arg("-s").arg("format").arg(format);
+    if let Some(max_size) = max_size {
+        command.arg("-Z").arg(max_size.to_string());
+    }
+    command
+        .arg(input)
+        .arg("--out")
+        .arg(output)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+        && output.exists()
+}
+
+fn write_terminal(sequence: &str) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(sequence.as_bytes())?;
+    stdout.write_all(b"\n")?;
+    stdout.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env_from(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<Vec<_>>();
+        move |key| {
+            vars.iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.clone())
+        }
+    }
+
+    fn bmp_2x2() -> Vec<u8> {
+        // 2x2, 24-bit, rows padded to 8 bytes. Bottom row: black, white; top: white, black.
+        let mut bmp = vec![0u8; 54];
+        bmp[0..2].copy_from_slice(b"BM");
+        bmp[10..14].copy_from_slice(&54u32.to_le_bytes());
+        bmp[18..22].copy_from_slice(&2i32.to_le_bytes());
+        bmp[22..26].copy_from_slice(&2i32.to_le_bytes());
+        bmp[28..30].copy_from_slice(&24u16.to_le_bytes());
+        bmp.extend_from_slice(&[0, 0, 0, 255, 255, 255, 0, 0]);
+        bmp.extend_from_slice(&[255, 255, 255, 0, 0, 0, 0, 0]);
+        bmp
+    }
+
+    #[test]
+    fn detects_terminal_graphics_protocols() {
+        assert_eq!(
+            detect_image_protocol(env_from(&[("TERM_PROGRAM", "iTerm.app")])),
+            ImageProtocol::Iterm
+        );
+        assert_eq!(
+            detect_image_protocol(env_from(&[("TERM", "xterm-kitty")])),
+            ImageProtocol::Kitty
+        );
+        assert_eq!(
+            detect_image_protocol(env_from(&[("TERM", "xterm-256color")])),
+            ImageProtocol::Ascii
+        );
+        assert_eq!(
+            ImageProtocol::parse("iTerm2").unwrap(),
+            Some(ImageProtocol::Iterm)
+        );
+        assert_eq!(ImageProtocol::parse("auto").unwrap(), None);
+        assert!(ImageProtocol::parse("sixel").is_err());
+    }
+
+    #[test]
+    fn graphics_sequences_wrap_base64_payloads() {
+        let iterm = iterm_image_sequence(b"abc", "a.png");
+        assert!(iterm.starts_with("\x1b]1337;File=name=YS5wbmc=;size=3;inline=1"));
+        assert!(iterm.ends_with(":YWJj\x07"));
+
+        let kitty = kitty_image_sequence(&vec![0u8; 4000]);
+        assert_eq!(kitty.matches("\x1b_G").count(), 2);
+        assert!(kitty.starts_with("\x1b_Gf=100,a=T,m=1;"));
+        assert!(kitty.contains("\x1b_Gm=0;"));
+    }
+
+    #[test]
+    fn ascii_thumbnail_maps_luminance_bottom_up() {
+        let ascii = ascii_from_bmp(&bmp_2x2(), 2).unwrap();
+        // Two columns, one row (height halved); the top row is white then black.
+        assert_eq!(ascii, " @\n");
+        assert!(ascii_from_bmp(b"not a bmp", 2).is_none());
+    }
+}
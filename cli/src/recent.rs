@@ -0,0 +1,185 @@
+//! `inline recent`: the peers you last messaged or heard from, numbered so
+//! `--to %1` stands in for the most recent one, like `cd -` for chats.
+//!
+//! The list lives in the local state file. Sends from the CLI move their
+//! destination to the front; `inline recent --refresh` merges in each chat's
+//! latest message from the server, which covers messages you received.
+
+use std::collections::HashMap;
+
+use inline_protocol::proto;
+use serde::{Deserialize, Serialize};
+
+use crate::chat_output::chat_display_name;
+use crate::errors::CliError;
+use crate::idempotency::peer_key;
+use crate::output::{format_relative_date, style_heading};
+use crate::state::LocalState;
+
+/// Peers kept in the state file.
+const RECENT_PEERS_KEPT: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentPeer {
+    /// `chat:ID` or `user:ID`, as `--to` takes it.
+    pub peer: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Unix seconds of the last message to or from the peer.
+    pub at: i64,
+}
+
+/// Moves `peer` to its place by `at`, keeping a known title when none is given.
+pub(crate) fn touch(state: &mut LocalState, peer: String, title: Option<String>, at: i64) {
+    let title = match state
+        .recent_peers
+        .iter()
+        .position(|recent| recent.peer == peer)
+    {
+        Some(index) => {
+            let existing = state.recent_peers.remove(index);
+            if existing.at > at {
+                state.recent_peers.insert(index, existing);
+                return;
+            }
+            title.or(existing.title)
+        }
+        None => title,
+    };
+    let index = state
+        .recent_peers
+        .iter()
+        .position(|recent| recent.at <= at)
+        .unwrap_or(state.recent_peers.len());
+    state
+        .recent_peers
+        .insert(index, RecentPeer { peer, title, at });
+    state.recent_peers.truncate(RECENT_PEERS_KEPT);
+}
+
+/// Records a send to `peer`. Your saved messages aren't listed.
+pub(crate) fn touch_sent(state: &mut LocalState, peer: &proto::InputPeer, at: i64) {
+    let peer = peer_key(peer);
+    if peer != "self" {
+        touch(state, peer, None, at);
+    }
+}
+
+/// Merges the latest message of each chat in a `getChats` payload.
+pub(crate) fn merge_chats(
+    state: &mut LocalState,
+    payload: &proto::GetChatsResult,
+    users_by_id: &HashMap<i64, proto::User>,
+) {
+    let dates = payload
+        .messages
+        .iter()
+        .map(|message| ((message.chat_id, message.id), message.date))
+        .collect::<HashMap<_, _>>();
+    for chat in &payload.chats {
+        let Some(at) = chat
+            .last_msg_id
+            .and_then(|id| dates.get(&(chat.id, id)).copied())
+        else {
+            continue;
+        };
+        let peer = match chat.peer_id.as_ref().and_then(|peer| peer.r#type.as_ref()) {
+            Some(proto::peer::Type::User(user)) => format!("user:{}", user.user_id),
+            _ => format!("chat:{}", chat.id),
+        };
+        let title = chat_display_name(chat, users_by_id);
+        touch(state, peer, Some(title), at);
+    }
+}
+
+/// Parses `%N` into a 1-based index; `None` when `value` isn't one.
+pub(crate) fn parse_index(value: &str) -> Result<Option<usize>, CliError> {
+    let Some(index) = value.trim().strip_prefix('%') else {
+        return Ok(None);
+    };
+    match index.parse::<usize>() {
+        Ok(index) if index > 0 => Ok(Some(index)),
+        _ => Err(CliError::invalid_args(format!(
+            "Invalid recent peer `{value}`: expected %1, %2, ... (see `inline recent`)"
+        ))),
+    }
+}
+
+/// The `--to` value that `%index` stands for.
+pub(crate) fn peer_at(state: &LocalState, index: usize) -> Result<&str, CliError> {
+    state
+        .recent_peers
+        .get(index - 1)
+        .map(|recent| recent.peer.as_str())
+        .ok_or_else(|| {
+            CliError::invalid_args(format!(
+                "No recent peer %{index}: the list has {}. Run `inline recent --refresh` to fill it from your chats.",
+                state.recent_peers.len()
+            ))
+        })
+}
+
+pub(crate) fn print_recent_peers(peers: &[RecentPeer], now: i64) {
+    if peers.is_empty() {
+        println!("No recent peers. Send a message, or run `inline recent --refresh`.");
+        return;
+    }
+    let peer_width = peers
+        .iter()
+        .map(|recent| recent.peer.len())
+        .max()
+        .unwrap_or_default()
+        .max(4);
+    println!(
+        "{}",
+        style_heading(&format!(
+            "{:>4}  {:<peer_width$}  {:<8}  title",
+            "#", "peer", "last"
+        ))
+    );
+    for (index, recent) in peers.iter().enumerate() {
+        println!(
+            "{:>4}  {:<peer_width$}  {:<8}  {}",
+            format!("%{}", index + 1),
+            recent.peer,
+            format_relative_date(recent.at, now),
+            recent.title.as_deref().unwrap_or("-")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peer::{input_chat_peer, input_self_peer, input_user_peer};
+
+    #[test]
+    fn sends_move_peers_to_the_front_and_indices_resolve() {
+        let mut state = LocalState::default();
+        touch(
+            &mut state,
+            "chat:7".to_string(),
+            Some("Design".to_string()),
+            100,
+        );
+        touch_sent(&mut state, &input_user_peer(42), 200);
+        touch_sent(&mut state, &input_self_peer(), 300);
+        assert_eq!(peer_at(&state, 1).unwrap(), "user:42");
+
+        touch_sent(&mut state, &input_chat_peer(7), 400);
+        assert_eq!(peer_at(&state, 1).unwrap(), "chat:7");
+        assert_eq!(state.recent_peers[0].title.as_deref(), Some("Design"));
+        assert_eq!(peer_at(&state, 2).unwrap(), "user:42");
+        assert!(peer_at(&state, 3).is_err());
+
+        // An older date from a refresh doesn't push a peer back.
+        touch(&mut state, "chat:7".to_string(), None, 150);
+        assert_eq!(state.recent_peers[0].at, 400);
+
+        assert_eq!(parse_index("%2").unwrap(), Some(2));
+        assert_eq!(parse_index("chat:7").unwrap(), None);
+        assert!(parse_index("%0").is_err());
+        assert!(parse_index("%x").is_err());
+    }
+}
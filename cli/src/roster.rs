@@ -0,0 +1,97 @@
+//! Roster files for `inline chats sync-participants`: one user per line, as a
+//! user id or `@username`. Blank lines and `#` comments are ignored.
+
+use std::collections::BTreeSet;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum RosterEntry {
+    UserId(i64),
+    Username(String),
+}
+
+pub(crate) fn parse_roster(contents: &str) -> Result<Vec<RosterEntry>, String> {
+    let mut entries = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let value = line.split('#').next().unwrap_or_default().trim();
+        if value.is_empty() {
+            continue;
+        }
+        let entry = if let Some(username) = value.strip_prefix('@') {
+            if username.is_empty() || username.contains(char::is_whitespace) {
+                None
+            } else {
+                Some(RosterEntry::Username(username.to_string()))
+            }
+        } else {
+            value
+                .parse::<i64>()
+                .ok()
+                .filter(|user_id| *user_id > 0)
+                .map(RosterEntry::UserId)
+        };
+        let Some(entry) = entry else {
+            return Err(format!(
+                "line {}: `{value}` is not a user id or @username",
+                index + 1
+            ));
+        };
+        if !entries.contains(&entry) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Who to add and remove so `current` matches `desired`. Users in `protected`
+/// are never removed.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct RosterDiff {
+    pub(crate) add: Vec<i64>,
+    pub(crate) remove: Vec<i64>,
+    pub(crate) unchanged: Vec<i64>,
+    /// Participants missing from the roster that are kept anyway.
+    pub(crate) kept: Vec<i64>,
+}
+
+pub(crate) fn diff_roster(current: &[i64], desired: &[i64], protected: &[i64]) -> RosterDiff {
+    let current = current.iter().copied().collect::<BTreeSet<_>>();
+    let desired = desired.iter().copied().collect::<BTreeSet<_>>();
+    let (kept, remove) = current
+        .difference(&desired)
+        .partition(|user_id| protected.contains(user_id));
+    RosterDiff {
+        add: desired.difference(&current).copied().collect(),
+        remove,
+        unchanged: current.intersection(&desired).copied().collect(),
+        kept,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ids_and_usernames_with_comments() {
+        let roster = parse_roster("# on-call\n42   # Sam\n@ava\n\n42\n").unwrap();
+
+        assert_eq!(
+            roster,
+            vec![
+                RosterEntry::UserId(42),
+                RosterEntry::Username("ava".to_string())
+            ]
+        );
+        assert!(parse_roster("sam\n").unwrap_err().starts_with("line 1:"));
+    }
+
+    #[test]
+    fn diff_never_removes_protected_users() {
+        let diff = diff_roster(&[1, 2, 3], &[2, 4], &[1]);
+
+        assert_eq!(diff.add, vec![4]);
+        assert_eq!(diff.remove, vec![3]);
+        assert_eq!(diff.unchanged, vec![2]);
+        assert_eq!(diff.kept, vec![1]);
+    }
+}
@@ -0,0 +1,201 @@
+//! Server error names mapped to what the user can do about them.
+//!
+//! The realtime protocol sends a `Code` enum (`CHAT_ID_INVALID`), and the HTTP
+//! API an `error` string; some servers also put a name such as
+//! `NOT_PARTICIPANT` or `FLOOD_WAIT_30` in the message of a `BAD_REQUEST`.
+//! Each known name gets its own error code, an actionable message, and an exit
+//! status by category. The raw name still goes out as `apiError`.
+
+/// Exit statuses by category, after `1` (other failures) and `3` (token
+/// rejected).
+pub(crate) const NOT_FOUND_EXIT_CODE: i32 = 4;
+pub(crate) const PERMISSION_EXIT_CODE: i32 = 5;
+pub(crate) const RATE_LIMITED_EXIT_CODE: i32 = 6;
+pub(crate) const CONFLICT_EXIT_CODE: i32 = 7;
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct KnownError {
+    pub(crate) code: &'static str,
+    pub(crate) message: String,
+    pub(crate) hint: &'static str,
+    /// Seconds to wait, for `FLOOD_WAIT_N`.
+    pub(crate) retry_after: Option<u64>,
+}
+
+/// (name, code, message, hint)
+const TABLE: &[(&str, &str, &str, &str)] = &[
+    (
+        "PEER_ID_INVALID",
+        "peer_not_found",
+        "That chat or user doesn't exist or isn't visible to you",
+        "Check the id with `inline chats list` or `inline users list`.",
+    ),
+    (
+        "CHAT_ID_INVALID",
+        "chat_not_found",
+        "That chat doesn't exist or isn't visible to you",
+        "Find chat ids with `inline chats list`.",
+    ),
+    (
+        "MESSAGE_ID_INVALID",
+        "message_not_found",
+        "That message doesn't exist in this chat",
+        "List message ids with `inline messages list --chat-id ID`.",
+    ),
+    (
+        "USER_ID_INVALID",
+        "user_not_found",
+        "That user doesn't exist or isn't visible to you",
+        "Find user ids with `inline users list`.",
+    ),
+    (
+        "SPACE_ID_INVALID",
+        "space_not_found",
+        "That space doesn't exist or you aren't a member",
+        "Find space ids with `inline spaces list`.",
+    ),
+    (
+        "NOT_PARTICIPANT",
+        "not_participant",
+        "You aren't a participant of this chat",
+        "Ask a member to add you, or check the chat id with `inline chats list`.",
+    ),
+    (
+        "SPACE_ADMIN_REQUIRED",
+        "permission_denied",
+        "Only space admins can do this",
+        "Ask an admin or the owner of the space.",
+    ),
+    (
+        "SPACE_OWNER_REQUIRED",
+        "permission_denied",
+        "Only the space owner can do this",
+        "Ask the owner of the space.",
+    ),
+    (
+        "USER_ALREADY_MEMBER",
+        "already_member",
+        "That user is already a member",
+        "Nothing to do; list members with `inline spaces members --space-id ID`.",
+    ),
+    (
+        "USERNAME_TAKEN",
+        "username_taken",
+        "That username is taken",
+        "Pick another username.",
+    ),
+    (
+        "USERNAME_INVALID",
+        "invalid_input",
+        "That username isn't valid",
+        "Usernames use letters, digits, and underscores.",
+    ),
+    (
+        "FIRST_NAME_INVALID",
+        "invalid_input",
+        "That first name isn't valid",
+        "Use a non-empty first name.",
+    ),
+    (
+        "EMAIL_INVALID",
+        "invalid_input",
+        "That email address isn't valid",
+        "Check the address for typos.",
+    ),
+    (
+        "PHONE_NUMBER_INVALID",
+        "invalid_input",
+        "That phone number isn't valid",
+        "Use international format, such as +15551234567.",
+    ),
+    (
+        "RATE_LIMIT",
+        "rate_limited",
+        "The server is rate limiting requests",
+        "Wait a little and retry; slow down loops that send or fetch in bulk.",
+    ),
+    (
+        "INTERNAL_ERROR",
+        "server_error",
+        "The server failed to handle the request",
+        "Retry later; if it keeps failing, run `inline doctor` and report it.",
+    ),
+];
+
+/// Looks up `name`, or for generic names (`BAD_REQUEST`, `UNKNOWN`) an error
+/// name leading the server's `message`.
+pub(crate) fn lookup(name: &str, message: &str) -> Option<KnownError> {
+    known(name).or_else(|| {
+        if !matches!(name, "BAD_REQUEST" | "UNKNOWN" | "") {
+            return None;
+        }
+        let leading = message
+            .split(|ch: char| !(ch.is_ascii_uppercase() || ch.is_ascii_digit() || ch == '_'))
+            .next()
+            .filter(|word| word.len() > 2)?;
+        known(leading)
+    })
+}
+
+fn known(name: &str) -> Option<KnownError> {
+    if let Some(seconds) = name
+        .strip_prefix("FLOOD_WAIT_")
+        .and_then(|seconds| seconds.parse::<u64>().ok())
+    {
+        return Some(KnownError {
+            code: "rate_limited",
+            message: format!("Too many requests; retry in {seconds}s"),
+            hint: "Wait that long before retrying; slow down loops that send or fetch in bulk.",
+            retry_after: Some(seconds),
+        });
+    }
+    TABLE
+        .iter()
+        .find(|(known, ..)| *known == name)
+        .map(|&(_, code, message, hint)| KnownError {
+            code,
+            message: message.to_string(),
+            hint,
+            retry_after: None,
+        })
+}
+
+/// Exit status for an error code from [`lookup`]; `None` for other codes.
+pub(crate) fn exit_code(code: &str) -> Option<i32> {
+    match code {
+        "peer_not_found" | "chat_not_found" | "message_not_found" | "user_not_found"
+        | "space_not_found" => Some(NOT_FOUND_EXIT_CODE),
+        "not_participant" | "permission_denied" => Some(PERMISSION_EXIT_CODE),
+        "rate_limited" => Some(RATE_LIMITED_EXIT_CODE),
+        "already_member" | "username_taken" => Some(CONFLICT_EXIT_CODE),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_map_from_the_code_or_a_generic_errors_message() {
+        let known = lookup("CHAT_ID_INVALID", "").unwrap();
+        assert_eq!(known.code, "chat_not_found");
+        assert_eq!(exit_code(known.code), Some(NOT_FOUND_EXIT_CODE));
+
+        let known = lookup("BAD_REQUEST", "FLOOD_WAIT_30: slow down").unwrap();
+        assert_eq!(known.code, "rate_limited");
+        assert_eq!(known.retry_after, Some(30));
+        assert_eq!(known.message, "Too many requests; retry in 30s");
+
+        let known = lookup("UNKNOWN", "NOT_PARTICIPANT").unwrap();
+        assert_eq!(exit_code(known.code), Some(PERMISSION_EXIT_CODE));
+
+        assert_eq!(lookup("BAD_REQUEST", "chat is archived"), None);
+        // A specific code isn't second-guessed by its message.
+        assert_eq!(
+            lookup("USERNAME_TAKEN", "CHAT_ID_INVALID").unwrap().code,
+            "username_taken"
+        );
+        assert_eq!(exit_code("invalid_input"), None);
+    }
+}
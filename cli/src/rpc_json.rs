@@ -0,0 +1,143 @@
+//! JSON mapping for `inline rpc`.
+//!
+//! Inputs and results use the same serde shape as `--json` output: proto
+//! field names in snake_case, enums as numbers, and nested oneofs as
+//! `{"VariantName": {...}}`. Method names may be given as `GET_ME`, `getMe`,
+//! `GetMe`, `get_me`, or `get-me`.
+
+use inline_protocol::proto;
+use serde_json::Value;
+
+/// A method resolved from a user-supplied name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct RpcMethod {
+    pub(crate) method: proto::Method,
+    /// Name of the method's `RpcCall.input` / `RpcResult.result` variant.
+    variant: String,
+}
+
+impl RpcMethod {
+    pub(crate) fn parse(name: &str) -> Result<Self, String> {
+        let words = method_words(name.trim());
+        let screaming = words
+            .iter()
+            .map(|word| word.to_ascii_uppercase())
+            .collect::<Vec<_>>()
+            .join("_");
+        let method = proto::Method::from_str_name(&screaming)
+            .filter(|method| *method != proto::Method::Unspecified)
+            .ok_or_else(|| format!("unknown RPC method `{name}`"))?;
+        let variant = words
+            .iter()
+            .map(|word| {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            })
+            .collect();
+        Ok(Self { method, variant })
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        self.method.as_str_name()
+    }
+
+    /// Reads don't change anything on the server, so `--read-only` allows them.
+    /// Revealing a bot token is treated as a write, as in `inline bots`.
+    pub(crate) fn is_read_only(&self) -> bool {
+        let name = self.name();
+        (name.starts_with("GET_") || name.starts_with("SEARCH_") || name.starts_with("LIST_"))
+            || name == "CHECK_USERNAME"
+    }
+
+    pub(crate) fn input_from_json(&self, input: Value) -> Result<proto::rpc_call::Input, String> {
+        let input = match input {
+            Value::Null => Value::Object(Default::default()),
+            input => input,
+        };
+        let mut wrapped = serde_json::Map::new();
+        wrapped.insert(self.variant.clone(), input);
+        serde_json::from_value(Value::Object(wrapped))
+            .map_err(|error| format!("invalid input for {}: {error}", self.name()))
+    }
+}
+
+/// The result payload without its variant wrapper.
+pub(crate) fn result_to_json(result: &proto::rpc_result::Result) -> Result<Value, String> {
+    match serde_json::to_value(result).map_err(|error| error.to_string())? {
+        Value::Object(map) if map.len() == 1 => Ok(map
+            .into_iter()
+            .next()
+            .map(|(_, value)| value)
+            .unwrap_or_default()),
+        other => Ok(other),
+    }
+}
+
+fn method_words(name: &str) -> Vec<String> {
+    if name.contains(['_', '-']) {
+        return name
+            .split(['_', '-'])
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_ascii_lowercase())
+            .collect();
+    }
+    let mut words = Vec::<String>::new();
+    for ch in name.chars() {
+        match words.last_mut() {
+            Some(word) if !ch.is_ascii_uppercase() => word.push(ch),
+            _ => words.push(ch.to_ascii_lowercase().to_string()),
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_method_names_in_any_case() {
+        for name in [
+            "GET_CHAT_HISTORY",
+            "getChatHistory",
+            "GetChatHistory",
+            "get-chat-history",
+        ] {
+            let method = RpcMethod::parse(name).unwrap();
+            assert_eq!(method.method, proto::Method::GetChatHistory, "{name}");
+        }
+        assert!(RpcMethod::parse("getNothing").is_err());
+        assert!(RpcMethod::parse("UNSPECIFIED").is_err());
+        assert!(RpcMethod::parse("searchMessages").unwrap().is_read_only());
+        assert!(!RpcMethod::parse("sendMessage").unwrap().is_read_only());
+        assert!(!RpcMethod::parse("revealBotToken").unwrap().is_read_only());
+    }
+
+    #[test]
+    fn maps_input_json_to_the_method_variant() {
+        let method = RpcMethod::parse("getMe").unwrap();
+        assert!(matches!(
+            method.input_from_json(Value::Null).unwrap(),
+            proto::rpc_call::Input::GetMe(_)
+        ));
+
+        let method = RpcMethod::parse("getChat").unwrap();
+        let input = method
+            .input_from_json(json!({"peer_id": {"type": {"Chat": {"chat_id": 7}}}}))
+            .unwrap();
+        let proto::rpc_call::Input::GetChat(input) = input else {
+            panic!("expected GetChat input");
+        };
+        assert_eq!(
+            input.peer_id.and_then(|peer| peer.r#type),
+            Some(proto::input_peer::Type::Chat(proto::InputPeerChat {
+                chat_id: 7
+            }))
+        );
+        assert!(method.input_from_json(json!({"peer_id": 7})).is_err());
+    }
+}
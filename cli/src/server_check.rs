@@ -0,0 +1,329 @@
+//! Compatibility checks for `inline server check`, aimed at self-hosted
+//! servers.
+//!
+//! Endpoints are probed with an empty, unauthenticated POST. A server that
+//! has the method answers with its usual `{"ok": false, ...}` validation or
+//! auth error; a 404 means the method doesn't exist. Nothing is sent that
+//! could log anyone in, email anyone, or change data.
+
+use futures_util::future::join_all;
+use serde::Serialize;
+
+use crate::doctor::{
+    DoctorProbe, PROBE_TIMEOUT, classify_connection_error, error_chain, probe_api, probe_realtime,
+};
+use crate::errors::CliError;
+use crate::identity as client_info;
+use crate::output;
+
+/// HTTP methods this CLI calls, with the commands that need them. Optional
+/// ones back integrations a self-hosted server may leave out.
+const ENDPOINTS: &[(&str, &str, bool)] = &[
+    ("sendEmailCode", "auth login --email", true),
+    ("verifyEmailCode", "auth login --email", true),
+    ("sendSmsCode", "auth login --phone", false),
+    ("verifySmsCode", "auth login --phone", false),
+    ("logout", "auth logout", true),
+    ("uploadFile", "messages send --attach", true),
+    ("readMessages", "chats mark-read", true),
+    ("createPrivateChat", "chats create-dm", true),
+    ("searchContacts", "users search", true),
+    ("createLinearIssue", "tasks create-linear", false),
+    ("createNotionTask", "tasks create-notion", false),
+];
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ServerCheckOutput {
+    cli_version: String,
+    api_base_url: String,
+    realtime_url: String,
+    api: DoctorProbe,
+    /// Connects without a token: a protocol-level rejection still proves the
+    /// server speaks this CLI's realtime protocol.
+    realtime: DoctorProbe,
+    endpoints: Vec<EndpointCheck>,
+    /// API and realtime reachable and every required endpoint present.
+    pub(crate) compatible: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct EndpointCheck {
+    method: &'static str,
+    used_by: &'static str,
+    required: bool,
+    /// ok, missing, unexpected (not an Inline API reply), or error.
+    status: &'static str,
+    http_status: Option<u16>,
+    /// dns, connect, tls, timeout, proxy, or http when the request failed.
+    error_kind: Option<&'static str>,
+    detail: Option<String>,
+}
+
+/// API and realtime URLs for a server root such as `https://chat.internal`.
+/// A trailing `/v1` is accepted too.
+pub(crate) fn server_urls(url: &str) -> Result<(String, String), CliError> {
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|err| CliError::invalid_args(format!("invalid --url {url}: {err}")))?;
+    let realtime_scheme = match parsed.scheme() {
+        "https" => "wss",
+        "http" => "ws",
+        other => {
+            return Err(CliError::invalid_args(format!(
+                "--url must be http:// or https://, not {other}://"
+            )));
+        }
+    };
+    let root = url.trim_end_matches('/');
+    let root = root.strip_suffix("/v1").unwrap_or(root);
+    let realtime_root = root
+        .split_once("://")
+        .map_or(root, |(_, rest)| rest)
+        .to_string();
+    Ok((
+        format!("{root}/v1"),
+        format!("{realtime_scheme}://{realtime_root}/realtime"),
+    ))
+}
+
+pub(crate) async fn check_server(api_base_url: &str, realtime_url: &str) -> ServerCheckOutput {
+    let client = client_info::http_client_builder()
+        .map_err(|err| err.to_string())
+        .and_then(|builder| {
+            builder
+                .timeout(PROBE_TIMEOUT)
+                .build()
+                .map_err(|err| err.to_string())
+        });
+    let endpoints = join_all(ENDPOINTS.iter().map(|&(method, used_by, required)| {
+        let client = client.clone();
+        async move {
+            let check = EndpointCheck {
+                method,
+                used_by,
+                required,
+                status: "error",
+                http_status: None,
+                error_kind: Some("connect"),
+                detail: None,
+            };
+            match client {
+                Ok(client) => probe_endpoint(&client, api_base_url, check).await,
+                Err(err) => EndpointCheck {
+                    detail: Some(err),
+                    ..check
+                },
+            }
+        }
+    }));
+    let ((api, _), realtime, endpoints) = tokio::join!(
+        probe_api(api_base_url),
+        probe_realtime(realtime_url, None),
+        endpoints
+    );
+    let compatible = api.ok
+        && realtime.ok
+        && endpoints
+            .iter()
+            .all(|endpoint| !endpoint.required || endpoint.status == "ok");
+    ServerCheckOutput {
+        cli_version: client_info::client_version().to_string(),
+        api_base_url: api_base_url.to_string(),
+        realtime_url: realtime_url.to_string(),
+        api,
+        realtime,
+        endpoints,
+        compatible,
+    }
+}
+
+async fn probe_endpoint(
+    client: &reqwest::Client,
+    api_base_url: &str,
+    check: EndpointCheck,
+) -> EndpointCheck {
+    let response = client
+        .post(format!("{api_base_url}/{}", check.method))
+        .json(&serde_json::json!({}))
+        .send()
+        .await;
+    let response = match response {
+        Ok(response) => response,
+        Err(err) => {
+            let text = error_chain(&err);
+            let kind = if err.is_timeout() {
+                "timeout"
+            } else {
+                classify_connection_error(&text)
+            };
+            return EndpointCheck {
+                error_kind: Some(kind),
+                detail: Some(text),
+                ..check
+            };
+        }
+    };
+    let http_status = response.status().as_u16();
+    let body = response.text().await.unwrap_or_default();
+    EndpointCheck {
+        status: classify_endpoint_reply(http_status, &body),
+        http_status: Some(http_status),
+        error_kind: None,
+        ..check
+    }
+}
+
+/// Whether a reply to the empty probe shows the method exists.
+fn classify_endpoint_reply(http_status: u16, body: &str) -> &'static str {
+    if matches!(http_status, 404 | 405) {
+        return "missing";
+    }
+    let envelope = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .is_some_and(|value| value.get("ok").is_some_and(serde_json::Value::is_boolean));
+    if envelope { "ok" } else { "unexpected" }
+}
+
+pub(crate) fn print_server_check(output: &ServerCheckOutput) {
+    println!(
+        "{}",
+        output::style_heading(&format!(
+            "inline {} against {}",
+            output.cli_version, output.api_base_url
+        ))
+    );
+    println!();
+    for (label, probe) in [("API", &output.api), ("Realtime", &output.realtime)] {
+        let latency = probe
+            .latency_ms
+            .map(|ms| format!(", {ms}ms"))
+            .unwrap_or_default();
+        println!(
+            "  {label:<9} {:<6} {} ({}{latency})",
+            if probe.ok { "ok" } else { "FAIL" },
+            probe.target,
+            probe.detail
+        );
+    }
+    println!();
+
+    let method_width = output
+        .endpoints
+        .iter()
+        .map(|endpoint| endpoint.method.len())
+        .max()
+        .unwrap_or(0)
+        .max("Endpoint".len());
+    let used_by_width = output
+        .endpoints
+        .iter()
+        .map(|endpoint| endpoint.used_by.len())
+        .max()
+        .unwrap_or(0)
+        .max("Used by".len());
+    println!(
+        "  {}",
+        output::style_heading(&format!(
+            "{:<method_width$}  {:<used_by_width$}  {:<8}  Status",
+            "Endpoint", "Used by", "Required"
+        ))
+    );
+    for endpoint in &output.endpoints {
+        let mut status = endpoint.status.to_string();
+        if let Some(code) = endpoint.http_status
+            && endpoint.status != "ok"
+        {
+            status.push_str(&format!(" (HTTP {code})"));
+        }
+        if let Some(kind) = endpoint.error_kind {
+            status.push_str(&format!(" ({kind})"));
+        }
+        println!(
+            "  {:<method_width$}  {:<used_by_width$}  {:<8}  {status}",
+            endpoint.method,
+            endpoint.used_by,
+            if endpoint.required { "yes" } else { "no" },
+        );
+    }
+    println!();
+
+    let missing_optional = output
+        .endpoints
+        .iter()
+        .filter(|endpoint| !endpoint.required && endpoint.status != "ok")
+        .map(|endpoint| endpoint.used_by)
+        .collect::<Vec<_>>();
+    if output.compatible {
+        println!("Compatible with this CLI.");
+        if !missing_optional.is_empty() {
+            println!("Unavailable: {}.", missing_optional.join(", "));
+        }
+    } else {
+        println!("Not compatible with this CLI.");
+    }
+}
+
+/// The failures that make a server incompatible, for the error message.
+pub(crate) fn incompatibilities(output: &ServerCheckOutput) -> Vec<String> {
+    let mut problems = Vec::new();
+    if !output.api.ok {
+        problems.push(format!("API unreachable ({})", output.api.detail));
+    }
+    if !output.realtime.ok {
+        problems.push(format!("realtime unusable ({})", output.realtime.detail));
+    }
+    // With the API down, every endpoint fails the same way.
+    if !output.api.ok {
+        return problems;
+    }
+    problems.extend(
+        output
+            .endpoints
+            .iter()
+            .filter(|endpoint| endpoint.required && endpoint.status != "ok")
+            .map(|endpoint| format!("{} {}", endpoint.method, endpoint.status)),
+    );
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn server_root_maps_to_api_and_realtime_urls() {
+        assert_eq!(
+            server_urls("https://chat.internal/").unwrap(),
+            (
+                "https://chat.internal/v1".to_string(),
+                "wss://chat.internal/realtime".to_string()
+            )
+        );
+        assert_eq!(
+            server_urls("http://10.0.0.5:8000/v1").unwrap(),
+            (
+                "http://10.0.0.5:8000/v1".to_string(),
+                "ws://10.0.0.5:8000/realtime".to_string()
+            )
+        );
+        assert!(server_urls("ftp://chat.internal").is_err());
+    }
+
+    #[test]
+    fn only_inline_envelopes_count_as_present() {
+        assert_eq!(
+            classify_endpoint_reply(400, r#"{"ok":false,"error":"BAD_REQUEST"}"#),
+            "ok"
+        );
+        assert_eq!(
+            classify_endpoint_reply(401, r#"{"ok":false,"error":"UNAUTHORIZED"}"#),
+            "ok"
+        );
+        assert_eq!(classify_endpoint_reply(404, "Not Found"), "missing");
+        assert_eq!(
+            classify_endpoint_reply(200, "<html>welcome</html>"),
+            "unexpected"
+        );
+    }
+}
@@ -0,0 +1,252 @@
+//! The JSON file behind `inline settings export` and `inline settings import`.
+//!
+//! A bundle carries the parts of a CLI setup worth copying to another machine
+//! or sharing as a team baseline: global notification settings from the
+//! server, portable preferences from the top level of the config file, peer
+//! aliases (`[aliases]`), command aliases (`[alias]`), and flag defaults
+//! (`[defaults]`). Connection settings (server URLs, proxy, CA bundle, pins)
+//! and profiles stay behind; they describe the machine, not the user.
+
+use std::collections::BTreeMap;
+
+use inline_protocol::proto;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{config_setting_spec, validate_setting_value};
+use crate::config_file::{ConfigFile, ConfigValue};
+use crate::errors::CliError;
+use crate::peer::{PeerTarget, validate_alias_name};
+
+/// Bumped when a field is renamed or reshaped; new fields don't bump it.
+pub(crate) const SETTINGS_BUNDLE_VERSION: u32 = 1;
+
+/// Top-level config settings a bundle carries.
+pub(crate) const PORTABLE_PREFERENCES: &[&str] = &[
+    "output",
+    "json_format",
+    "color",
+    "read_only",
+    "send_limit",
+    "language",
+    "update.check",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsBundle {
+    pub version: u32,
+    /// Global notification settings; absent when exported without them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<proto::NotificationSettings>,
+    #[serde(default)]
+    pub preferences: BTreeMap<String, String>,
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+    #[serde(default)]
+    pub command_aliases: BTreeMap<String, String>,
+    #[serde(default)]
+    pub defaults: BTreeMap<String, ConfigValue>,
+}
+
+/// One config key an import sets.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingChange {
+    pub key: String,
+    /// `None` when the key wasn't set before.
+    pub from: Option<ConfigValue>,
+    pub to: ConfigValue,
+}
+
+pub(crate) fn export_bundle(
+    file: &ConfigFile,
+    notifications: Option<proto::NotificationSettings>,
+) -> SettingsBundle {
+    SettingsBundle {
+        version: SETTINGS_BUNDLE_VERSION,
+        notifications,
+        preferences: PORTABLE_PREFERENCES
+            .iter()
+            .filter_map(|key| Some((key.to_string(), file.get(key)?.to_string())))
+            .collect(),
+        aliases: file.string_table("aliases"),
+        command_aliases: file.string_table("alias"),
+        defaults: file.values_under("defaults"),
+    }
+}
+
+pub(crate) fn parse_bundle(raw: &str) -> Result<SettingsBundle, CliError> {
+    let bundle: SettingsBundle = serde_json::from_str(raw).map_err(|err| {
+        CliError::invalid_args(format!(
+            "Not a settings file from `inline settings export`: {err}"
+        ))
+    })?;
+    if bundle.version == 0 || bundle.version > SETTINGS_BUNDLE_VERSION {
+        return Err(CliError::invalid_args(format!(
+            "Settings file version {} isn't supported (this CLI reads up to {SETTINGS_BUNDLE_VERSION}); update the CLI",
+            bundle.version
+        )));
+    }
+    Ok(bundle)
+}
+
+/// Merges the bundle's config entries into `file`, keeping keys it doesn't
+/// mention. Everything is validated before anything is set, so a bad entry
+/// leaves `file` untouched. Returns the keys whose value changed.
+pub(crate) fn import_bundle(
+    file: &mut ConfigFile,
+    bundle: &SettingsBundle,
+) -> Result<Vec<SettingChange>, CliError> {
+    let mut entries = Vec::new();
+    for (key, value) in &bundle.preferences {
+        let spec = config_setting_spec(key)
+            .filter(|spec| PORTABLE_PREFERENCES.contains(&spec.key))
+            .ok_or_else(|| {
+                CliError::invalid_args(format!(
+                    "`{key}` can't be imported (portable settings: {})",
+                    PORTABLE_PREFERENCES.join(", ")
+                ))
+            })?;
+        validate_setting_value(spec, value)
+            .map_err(|err| CliError::invalid_args(err.to_string()))?;
+        entries.push((key.clone(), ConfigValue::String(value.clone())));
+    }
+    for (name, peer) in &bundle.aliases {
+        let name = validate_alias_name(name)?;
+        if !matches!(PeerTarget::parse(peer), Ok(Some(_))) {
+            return Err(CliError::invalid_args(format!(
+                "Invalid alias target `{peer}` for `{name}`: expected chat:ID, user:ID, or @username."
+            )));
+        }
+        entries.push((
+            format!("aliases.{name}"),
+            ConfigValue::String(peer.trim().to_string()),
+        ));
+    }
+    for (name, expansion) in &bundle.command_aliases {
+        entries.push((
+            format!("alias.{name}"),
+            ConfigValue::String(expansion.clone()),
+        ));
+    }
+    for (key, value) in &bundle.defaults {
+        entries.push((format!("defaults.{key}"), value.clone()));
+    }
+
+    let mut updated = file.clone();
+    let mut changes = Vec::new();
+    for (key, value) in entries {
+        let from = updated.get(&key).cloned();
+        updated
+            .set(&key, value.clone())
+            .map_err(|err| CliError::invalid_args(err.to_string()))?;
+        // `send_limit = 20` and `send_limit = "20"` are the same setting.
+        if from.as_ref().map(ToString::to_string) != Some(value.to_string()) {
+            changes.push(SettingChange {
+                key,
+                from,
+                to: value,
+            });
+        }
+    }
+    *file = updated;
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+api_base_url = "https://inline.example/v1"
+output = "json"
+send_limit = "20"
+
+[aliases]
+standup = "chat:42"
+
+[alias]
+today = "messages list --since today"
+
+[defaults]
+messages.list.limit = 50
+
+[profiles.work]
+color = "never"
+"#;
+
+    #[test]
+    fn export_keeps_portable_settings_and_leaves_machine_ones_behind() {
+        let file = ConfigFile::parse(CONFIG).unwrap();
+        let bundle = export_bundle(&file, None);
+
+        assert_eq!(bundle.version, SETTINGS_BUNDLE_VERSION);
+        assert_eq!(
+            bundle.preferences,
+            BTreeMap::from([
+                ("output".to_string(), "json".to_string()),
+                ("send_limit".to_string(), "20".to_string()),
+            ])
+        );
+        assert_eq!(bundle.aliases["standup"], "chat:42");
+        assert_eq!(
+            bundle.command_aliases["today"],
+            "messages list --since today"
+        );
+        assert_eq!(
+            bundle.defaults["messages.list.limit"],
+            ConfigValue::Integer(50)
+        );
+        let json = serde_json::to_string(&bundle).unwrap();
+        assert!(!json.contains("inline.example"));
+        assert!(!json.contains("never"));
+    }
+
+    #[test]
+    fn import_round_trips_and_reports_only_changed_keys() {
+        let bundle = parse_bundle(
+            &serde_json::to_string(&export_bundle(&ConfigFile::parse(CONFIG).unwrap(), None))
+                .unwrap(),
+        )
+        .unwrap();
+        let mut file = ConfigFile::parse("output = \"json\"\nsend_limit = \"5\"\n").unwrap();
+
+        let changes = import_bundle(&mut file, &bundle).unwrap();
+
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| change.key.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "send_limit",
+                "aliases.standup",
+                "alias.today",
+                "defaults.messages.list.limit"
+            ]
+        );
+        assert_eq!(changes[0].from, Some(ConfigValue::String("5".to_string())));
+        assert_eq!(
+            file.get_str("alias.today"),
+            Some("messages list --since today")
+        );
+        assert!(import_bundle(&mut file, &bundle).unwrap().is_empty());
+    }
+
+    #[test]
+    fn import_rejects_bad_entries_without_touching_the_file() {
+        let mut file = ConfigFile::parse("output = \"json\"\n").unwrap();
+        let before = file.clone();
+        for bundle in [
+            r#"{"version":1,"preferences":{"api_base_url":"https://evil.example"}}"#,
+            r#"{"version":1,"preferences":{"output":"xml"}}"#,
+            r#"{"version":1,"preferences":{"output":"table"},"aliases":{"bad name":"chat:1"}}"#,
+            r#"{"version":1,"aliases":{"ops":"somewhere"}}"#,
+        ] {
+            assert!(import_bundle(&mut file, &parse_bundle(bundle).unwrap()).is_err());
+            assert_eq!(file, before);
+        }
+        assert!(parse_bundle(r#"{"version":2}"#).is_err());
+        assert!(parse_bundle("[]").is_err());
+    }
+}
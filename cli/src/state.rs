@@ -1,10 +1,21 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use thiserror::Error;
 
+use crate::alerts::Alert;
+use crate::api_cache::ApiCacheEntry;
+use crate::bookmarks::Bookmark;
+use crate::capabilities::ServerCapabilities;
+use crate::idempotency::IdempotentSend;
+use crate::pending_sends::PendingSend;
+use crate::recent::RecentPeer;
+use crate::state_crypto::{KeyResolution, KeySource, StateKey, is_encrypted, resolve_state_key};
 use inline_protocol::proto;
 
 #[derive(Debug, Error)]
@@ -13,6 +24,54 @@ pub enum StateError {
     Io(#[from] io::Error),
     #[error("json error: {0}")]
     Json(#[from] serde_json::Error),
+    #[error("state file is encrypted but no key is available: {0}")]
+    Locked(String),
+    #[error("state file could not be decrypted with the current key")]
+    Undecryptable,
+    #[error(
+        "state file is schema version {found}, but this CLI only knows up to {supported}; update the CLI"
+    )]
+    TooNew { found: u32, supported: u32 },
+}
+
+/// Schema version written to the state file. Bump it and append to
+/// [`MIGRATIONS`] when a change can't be expressed with `#[serde(default)]`
+/// (renamed or reshaped fields).
+pub const STATE_VERSION: u32 = 1;
+
+/// One step in the state file's history. `apply` rewrites the JSON from
+/// `version - 1` to `version`; it runs before deserializing into [`LocalState`].
+pub struct StateMigration {
+    pub version: u32,
+    pub description: &'static str,
+    apply: fn(&mut serde_json::Map<String, Value>),
+}
+
+const MIGRATIONS: &[StateMigration] = &[StateMigration {
+    version: 1,
+    description: "Record the schema version in the state file",
+    apply: migrate_unversioned,
+}];
+
+/// Files written before versioning have no `version` and need no reshaping.
+fn migrate_unversioned(_state: &mut serde_json::Map<String, Value>) {}
+
+/// What `inline state migrate` reports.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateMigrationStatus {
+    pub path: String,
+    pub exists: bool,
+    pub from_version: u32,
+    pub to_version: u32,
+    pub pending: Vec<PendingMigration>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingMigration {
+    pub version: u32,
+    pub description: &'static str,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -22,51 +81,254 @@ pub struct LocalState {
     pub api_base_url: Option<String>,
     pub updated_at: Option<i64>,
     pub release_manifest_url: Option<String>,
-    // Used to throttle update checks even if the process exits before the check finishes.
+    /// Used to throttle update checks even if the process exits before the check finishes.
     pub last_update_attempt_at: Option<i64>,
     pub last_update_check_at: Option<i64>,
     pub last_update_notified_version: Option<String>,
     pub last_seen_release_version: Option<String>,
+    /// Update checks that failed in a row; each one doubles the wait before the next.
+    #[serde(default)]
+    pub update_check_failures: u32,
+    /// Newest message id per chat in the last `inline backup create`, so the next
+    /// backup only fetches what is newer.
+    #[serde(default)]
+    pub backup_high_water: BTreeMap<i64, i64>,
+    pub last_backup_at: Option<i64>,
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+    /// Keyword alerts for `inline alerts run`.
+    #[serde(default)]
+    pub alerts: Vec<Alert>,
+    /// Methods the realtime server turned out not to implement.
+    #[serde(default)]
+    pub server_capabilities: Option<ServerCapabilities>,
+    /// Confirmed `messages send --idempotency-key` sends.
+    #[serde(default)]
+    pub idempotent_sends: Vec<IdempotentSend>,
+    /// Uploaded attachments whose send failed, for `inline resend pending`.
+    #[serde(default)]
+    pub pending_sends: Vec<PendingSend>,
+    /// Peers for `inline recent` and `--to %N`, most recent first.
+    #[serde(default)]
+    pub recent_peers: Vec<RecentPeer>,
+    /// Read-style REST responses, reused for a minute (see `crate::api_cache`).
+    #[serde(default)]
+    pub api_cache: Vec<ApiCacheEntry>,
+    /// State saved under other API base URLs, keyed by URL, kept as-is until
+    /// the CLI talks to that server again.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub other_servers: BTreeMap<String, Value>,
+}
+
+/// What `inline doctor` reports about encryption at rest.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateEncryptionStatus {
+    /// `encrypted`, `plaintext`, or `missing`.
+    pub file: &'static str,
+    pub key_source: Option<&'static str>,
+    pub key_error: Option<String>,
 }
 
 #[derive(Clone)]
 pub struct LocalDb {
     path: PathBuf,
     api_base_url: String,
+    // Resolved on first use, so commands that never touch state skip the keychain.
+    key: Option<Arc<OnceLock<KeyResolution>>>,
 }
 
 impl LocalDb {
     pub fn new(path: PathBuf, api_base_url: String) -> Self {
-        Self { path, api_base_url }
+        Self {
+            path,
+            api_base_url,
+            key: None,
+        }
     }
 
-    pub fn load(&self) -> Result<LocalState, StateError> {
-        let contents = match fs::read_to_string(&self.path) {
-            Ok(contents) => contents,
-            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(LocalState::default()),
-            Err(err) => return Err(StateError::Io(err)),
+    /// Encrypts the state file with the key from `state_crypto`, migrating
+    /// plaintext state the first time it is read.
+    pub fn encrypted(mut self) -> Self {
+        self.key = Some(Arc::default());
+        self
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_key(mut self, key: StateKey) -> Self {
+        let cell = OnceLock::new();
+        let _ = cell.set(KeyResolution::with_key(key, KeySource::Env));
+        self.key = Some(Arc::new(cell));
+        self
+    }
+
+    fn key_resolution(&self) -> Option<&KeyResolution> {
+        self.key
+            .as_ref()
+            .map(|cell| cell.get_or_init(resolve_state_key))
+    }
+
+    fn key(&self) -> Option<&StateKey> {
+        self.key_resolution()?.key.as_ref()
+    }
+
+    /// Where the state key came from, or `None` when state is written in
+    /// plaintext.
+    pub fn encryption_key_source(&self) -> Option<&'static str> {
+        let resolution = self.key_resolution()?;
+        resolution.key.as_ref().map(|_| resolution.source.as_str())
+    }
+
+    pub fn encryption_status(&self) -> StateEncryptionStatus {
+        let file = match fs::read(&self.path) {
+            Ok(contents) if is_encrypted(&contents) => "encrypted",
+            Ok(_) => "plaintext",
+            Err(_) => "missing",
         };
-        let state: LocalState = serde_json::from_str(&contents)?;
-        if state
-            .api_base_url
-            .as_deref()
-            .is_some_and(|api_base_url| api_base_url != self.api_base_url)
-        {
+        let resolution = self.key_resolution();
+        StateEncryptionStatus {
+            file,
+            key_source: resolution.map(|resolution| resolution.source.as_str()),
+            key_error: resolution.and_then(|resolution| resolution.error.clone()),
+        }
+    }
+
+    pub fn load(&self) -> Result<LocalState, StateError> {
+        let Some(mut value) = self.read_value()? else {
             return Ok(LocalState::default());
+        };
+        let migrated = migrate_value(&mut value)?;
+        let state = self.for_this_server(serde_json::from_value(value)?)?;
+        if migrated > 0 {
+            // Best effort, like the plaintext migration: reads still work.
+            let _ = self.save(&state);
         }
         Ok(state)
     }
 
+    /// The file holds one server's state at the top level and the rest under
+    /// `otherServers`. When the top level belongs to another server, swap this
+    /// server's state in and park the other one, so the next save keeps both.
+    fn for_this_server(&self, mut state: LocalState) -> Result<LocalState, StateError> {
+        let Some(stored_url) = state
+            .api_base_url
+            .clone()
+            .filter(|api_base_url| *api_base_url != self.api_base_url)
+        else {
+            return Ok(state);
+        };
+        let mut own: LocalState = match state.other_servers.remove(&self.api_base_url) {
+            Some(value) => serde_json::from_value(value)?,
+            None => LocalState::default(),
+        };
+        own.api_base_url = Some(self.api_base_url.clone());
+        own.other_servers = std::mem::take(&mut state.other_servers);
+        own.other_servers
+            .insert(stored_url, serde_json::to_value(&state)?);
+        Ok(own)
+    }
+
+    /// Migrations the state file still needs, without changing it.
+    pub fn migration_status(&self) -> Result<StateMigrationStatus, StateError> {
+        let value = self.read_value()?;
+        let from_version = match &value {
+            Some(value) => file_version(value)?,
+            None => STATE_VERSION,
+        };
+        Ok(StateMigrationStatus {
+            path: self.path.display().to_string(),
+            exists: value.is_some(),
+            from_version,
+            to_version: STATE_VERSION,
+            pending: pending_migrations(from_version)
+                .map(|migration| PendingMigration {
+                    version: migration.version,
+                    description: migration.description,
+                })
+                .collect(),
+        })
+    }
+
+    /// Applies pending migrations and rewrites the file. `load` does the same
+    /// on the fly; this is the explicit version for `inline state migrate`.
+    pub fn migrate(&self) -> Result<StateMigrationStatus, StateError> {
+        let status = self.migration_status()?;
+        if status.pending.is_empty() {
+            return Ok(status);
+        }
+        let Some(mut value) = self.read_value()? else {
+            return Ok(status);
+        };
+        migrate_value(&mut value)?;
+        let state: LocalState = serde_json::from_value(value)?;
+        self.save(&state)?;
+        Ok(status)
+    }
+
+    /// The decrypted file as JSON, or `None` when there is no state yet.
+    fn read_value(&self) -> Result<Option<Value>, StateError> {
+        let contents = match fs::read(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(StateError::Io(err)),
+        };
+        let value = if is_encrypted(&contents) {
+            let key = self
+                .key()
+                .ok_or_else(|| StateError::Locked(self.missing_key_reason()))?;
+            let contents = key.decrypt(&contents).ok_or(StateError::Undecryptable)?;
+            serde_json::from_slice(&contents)?
+        } else {
+            let value = serde_json::from_slice(&contents)?;
+            if let Some(key) = self.key() {
+                // Best effort: a read-only data dir shouldn't break reads.
+                let _ = self.write(&key.encrypt(&contents));
+            }
+            value
+        };
+        Ok(Some(value))
+    }
+
     pub fn save(&self, state: &LocalState) -> Result<(), StateError> {
         if let Some(parent) = self.path.parent() {
             ensure_dir(parent)?;
         }
-        let payload = serde_json::to_string_pretty(state)?;
-        fs::write(&self.path, payload)?;
-        set_file_permissions(&self.path, 0o600)?;
+        let mut value = serde_json::to_value(state)?;
+        if let Value::Object(fields) = &mut value {
+            fields.insert("version".to_string(), STATE_VERSION.into());
+            // Stamp the server so another server's load parks this state
+            // instead of mistaking it for its own.
+            fields.insert("apiBaseUrl".to_string(), self.api_base_url.clone().into());
+            if let Some(Value::Object(others)) = fields.get_mut("otherServers") {
+                others.remove(&self.api_base_url);
+            }
+        }
+        let payload = serde_json::to_string_pretty(&value)?;
+        match self.key() {
+            Some(key) => self.write(&key.encrypt(payload.as_bytes()))?,
+            None => self.write(payload.as_bytes())?,
+        }
         Ok(())
     }
 
+    fn write(&self, contents: &[u8]) -> Result<(), io::Error> {
+        fs::write(&self.path, contents)?;
+        set_file_permissions(&self.path, 0o600)
+    }
+
+    fn missing_key_reason(&self) -> String {
+        match self.key_resolution() {
+            Some(KeyResolution {
+                error: Some(error), ..
+            }) => error.clone(),
+            Some(KeyResolution {
+                source: KeySource::Disabled,
+                ..
+            }) => "encryption is turned off with INLINE_STATE_KEY=none".to_string(),
+            _ => "set INLINE_STATE_KEY to the key it was written with".to_string(),
+        }
+    }
+
     pub fn set_current_user(&self, user: proto::User) -> Result<(), StateError> {
         let mut state = self.load()?;
         state.current_user = Some(user);
@@ -84,6 +346,42 @@ impl LocalDb {
     }
 }
 
+/// Runs every migration newer than the file's version, in order. Returns how
+/// many ran.
+fn migrate_value(value: &mut Value) -> Result<usize, StateError> {
+    let version = file_version(value)?;
+    let Value::Object(fields) = value else {
+        return Ok(0);
+    };
+    let mut applied = 0;
+    for migration in pending_migrations(version) {
+        (migration.apply)(fields);
+        fields.insert("version".to_string(), migration.version.into());
+        applied += 1;
+    }
+    Ok(applied)
+}
+
+fn file_version(value: &Value) -> Result<u32, StateError> {
+    let found = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .map_or(0, |version| u32::try_from(version).unwrap_or(u32::MAX));
+    if found > STATE_VERSION {
+        return Err(StateError::TooNew {
+            found,
+            supported: STATE_VERSION,
+        });
+    }
+    Ok(found)
+}
+
+fn pending_migrations(version: u32) -> impl Iterator<Item = &'static StateMigration> {
+    MIGRATIONS
+        .iter()
+        .filter(move |migration| migration.version > version)
+}
+
 fn ensure_dir(path: &Path) -> Result<(), io::Error> {
     fs::create_dir_all(path)?;
     set_dir_permissions(path, 0o700)?;
@@ -121,3 +419,105 @@ fn set_file_permissions(_path: &Path, _mode: u32) -> Result<(), io::Error> {
 fn set_dir_permissions(_path: &Path, _mode: u32) -> Result<(), io::Error> {
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plaintext_state_is_encrypted_on_first_load() {
+        let dir = std::env::temp_dir().join(format!("inline-state-test-{}", std::process::id()));
+        let path = dir.join("state.json");
+        let api_base_url = "http://127.0.0.1:9/v1".to_string();
+        let plain = LocalDb::new(path.clone(), api_base_url.clone());
+        plain
+            .set_current_user(proto::User {
+                id: 42,
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(!is_encrypted(&fs::read(&path).unwrap()));
+
+        let key = StateKey::from_hex(&"ab".repeat(32)).unwrap();
+        let encrypted = LocalDb::new(path.clone(), api_base_url.clone()).with_key(key);
+        assert_eq!(
+            encrypted.load().unwrap().current_user.map(|user| user.id),
+            Some(42)
+        );
+        assert!(is_encrypted(&fs::read(&path).unwrap()));
+        assert_eq!(encrypted.encryption_status().file, "encrypted");
+
+        let wrong_key = StateKey::from_hex(&"cd".repeat(32)).unwrap();
+        let wrong = LocalDb::new(path.clone(), api_base_url.clone()).with_key(wrong_key);
+        assert!(matches!(wrong.load(), Err(StateError::Undecryptable)));
+        let result = plain.load();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(result, Err(StateError::Locked(_))));
+    }
+
+    #[test]
+    fn switching_servers_keeps_each_servers_state() {
+        let dir = std::env::temp_dir().join(format!("inline-state-servers-{}", std::process::id()));
+        let path = dir.join("state.json");
+        let server_a = LocalDb::new(path.clone(), "https://a.example/v1".to_string());
+        let server_b = LocalDb::new(path.clone(), "https://b.example/v1".to_string());
+        let bookmark = Bookmark {
+            id: 1,
+            chat_id: Some(7),
+            user_id: None,
+            message_id: 99,
+            note: None,
+            saved_at: 0,
+            message: proto::Message::default(),
+        };
+
+        let mut state = server_a.load().unwrap();
+        state.bookmarks.push(bookmark.clone());
+        server_a.save(&state).unwrap();
+
+        let mut state = server_b.load().unwrap();
+        assert!(state.bookmarks.is_empty());
+        state.last_backup_at = Some(5);
+        server_b.save(&state).unwrap();
+
+        let state_a = server_a.load().unwrap();
+        server_a.save(&state_a).unwrap();
+        let state_b = server_b.load().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(state_a.bookmarks, vec![bookmark]);
+        assert_eq!(state_a.last_backup_at, None);
+        assert!(state_b.bookmarks.is_empty());
+        assert_eq!(state_b.last_backup_at, Some(5));
+    }
+
+    #[test]
+    fn unversioned_state_is_migrated_and_newer_files_are_refused() {
+        let dir = std::env::temp_dir().join(format!("inline-state-migrate-{}", std::process::id()));
+        let path = dir.join("state.json");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, r#"{"lastBackupAt": 7}"#).unwrap();
+        let db = LocalDb::new(path.clone(), "http://127.0.0.1:9/v1".to_string());
+
+        let status = db.migration_status().unwrap();
+        assert_eq!((status.from_version, status.to_version), (0, STATE_VERSION));
+        assert_eq!(status.pending.len(), MIGRATIONS.len());
+        // A dry run leaves the file alone.
+        assert!(!fs::read_to_string(&path).unwrap().contains("version"));
+
+        assert_eq!(db.load().unwrap().last_backup_at, Some(7));
+        let written: Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(written["version"], STATE_VERSION);
+        assert!(db.migration_status().unwrap().pending.is_empty());
+
+        fs::write(&path, r#"{"version": 999}"#).unwrap();
+        let result = db.load();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(
+            result,
+            Err(StateError::TooNew {
+                found: 999,
+                supported: STATE_VERSION
+            })
+        ));
+    }
+}
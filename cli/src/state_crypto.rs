@@ -0,0 +1,269 @@
+//! Encryption at rest for the local state file.
+//!
+//! State is sealed with ChaCha20-Poly1305 under a random 256-bit key. On macOS
+//! the key lives in the login Keychain and is created on first use. Elsewhere
+//! there is no key store, so state stays plaintext unless `INLINE_STATE_KEY`
+//! supplies one (64 hex characters). `INLINE_STATE_KEY=none` turns encryption
+//! off everywhere.
+//!
+//! Only the state file is covered. The `inline sync` cache (`client.sqlite3`)
+//! can't be sealed yet, so `inline sync` refuses to write it while a key is
+//! in use. Files the CLI writes on request (backups, archives, exports,
+//! downloads) are plaintext.
+
+use std::env;
+
+use rand::{RngCore, rngs::OsRng};
+use ring::aead::{Aad, CHACHA20_POLY1305, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+
+/// Leads every encrypted state file; plaintext state is JSON and never starts with it.
+const MAGIC: &[u8] = b"INLINE-STATE-V1\0";
+
+#[cfg(target_os = "macos")]
+const KEYCHAIN_SERVICE: &str = "chat.inline.cli";
+#[cfg(target_os = "macos")]
+const KEYCHAIN_ACCOUNT: &str = "state-key";
+
+#[derive(Clone)]
+pub(crate) struct StateKey([u8; 32]);
+
+impl StateKey {
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    fn generate() -> Self {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        Self(bytes)
+    }
+
+    pub(crate) fn from_hex(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.len() != 64 || !value.is_ascii() {
+            return None;
+        }
+        let mut bytes = [0u8; 32];
+        for (byte, pair) in bytes.iter_mut().zip(value.as_bytes().chunks(2)) {
+            *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+        }
+        Some(Self(bytes))
+    }
+
+    fn sealing_key(&self) -> LessSafeKey {
+        LessSafeKey::new(
+            UnboundKey::new(&CHACHA20_POLY1305, &self.0).expect("ChaCha20-Poly1305 takes 32 bytes"),
+        )
+    }
+
+    pub(crate) fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let mut sealed = plaintext.to_vec();
+        self.sealing_key()
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(MAGIC),
+                &mut sealed,
+            )
+            .expect("state fits in one ChaCha20-Poly1305 message");
+        let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + sealed.len());
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&sealed);
+        out
+    }
+
+    /// `None` when the file was sealed with another key or has been altered.
+    pub(crate) fn decrypt(&self, contents: &[u8]) -> Option<Vec<u8>> {
+        let rest = contents.strip_prefix(MAGIC)?;
+        if rest.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, sealed) = rest.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+        let mut sealed = sealed.to_vec();
+        let plaintext = self
+            .sealing_key()
+            .open_in_place(nonce, Aad::from(MAGIC), &mut sealed)
+            .ok()?;
+        Some(plaintext.to_vec())
+    }
+}
+
+pub(crate) fn is_encrypted(contents: &[u8]) -> bool {
+    contents.starts_with(MAGIC)
+}
+
+/// Where the state key came from, for `inline doctor`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum KeySource {
+    Env,
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    Keychain,
+    /// Turned off with `INLINE_STATE_KEY=none`.
+    Disabled,
+    /// No key store on this platform and no `INLINE_STATE_KEY`.
+    Unsupported,
+}
+
+impl KeySource {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Env => "INLINE_STATE_KEY",
+            Self::Keychain => "keychain",
+            Self::Disabled => "disabled",
+            Self::Unsupported => "unsupported",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct KeyResolution {
+    pub(crate) key: Option<StateKey>,
+    pub(crate) source: KeySource,
+    /// Why a key that should exist couldn't be read or created.
+    pub(crate) error: Option<String>,
+}
+
+impl KeyResolution {
+    pub(crate) fn with_key(key: StateKey, source: KeySource) -> Self {
+        Self {
+            key: Some(key),
+            source,
+            error: None,
+        }
+    }
+}
+
+pub(crate) fn resolve_state_key() -> KeyResolution {
+    if let Ok(value) = env::var("INLINE_STATE_KEY") {
+        if value.trim().eq_ignore_ascii_case("none") {
+            return KeyResolution {
+                key: None,
+                source: KeySource::Disabled,
+                error: None,
+            };
+        }
+        return match StateKey::from_hex(&value) {
+            Some(key) => KeyResolution::with_key(key, KeySource::Env),
+            None => KeyResolution {
+                key: None,
+                source: KeySource::Env,
+                error: Some("INLINE_STATE_KEY must be 64 hex characters or `none`".to_string()),
+            },
+        };
+    }
+    keychain_key()
+}
+
+#[cfg(target_os = "macos")]
+fn keychain_key() -> KeyResolution {
+    use std::io::Write;
+    use std::process::{Command, Output, Stdio};
+
+    use crate::checksum::bytes_to_hex;
+
+    let failed = |error: String| KeyResolution {
+        key: None,
+        source: KeySource::Keychain,
+        error: Some(error),
+    };
+    let find = || {
+        Command::new("security")
+            .args(["find-generic-password", "-s", KEYCHAIN_SERVICE])
+            .args(["-a", KEYCHAIN_ACCOUNT, "-w"])
+            .output()
+    };
+    match find() {
+        Ok(output) if output.status.success() => {
+            return match StateKey::from_hex(&String::from_utf8_lossy(&output.stdout)) {
+                Some(key) => KeyResolution::with_key(key, KeySource::Keychain),
+                None => failed(format!(
+                    "keychain item {KEYCHAIN_SERVICE}/{KEYCHAIN_ACCOUNT} is not a valid key"
+                )),
+            };
+        }
+        // 44 is errSecItemNotFound: first run, so create the key below.
+        Ok(output) if output.status.code() == Some(44) => {}
+        Ok(output) => {
+            return failed(format!(
+                "keychain lookup failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Err(error) => return failed(format!("could not run `security`: {error}")),
+    }
+
+    // `security -i` reads the command from stdin, so the key never appears
+    // in argv where other processes could list it.
+    let key = StateKey::generate();
+    let add = || -> std::io::Result<Output> {
+        let mut child = Command::new("security")
+            .arg("-i")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        writeln!(
+            stdin,
+            "add-generic-password -s {KEYCHAIN_SERVICE} -a {KEYCHAIN_ACCOUNT} -w {}",
+            bytes_to_hex(&key.0)
+        )?;
+        drop(stdin);
+        child.wait_with_output()
+    };
+    let added = match add() {
+        Ok(output) => output,
+        Err(error) => return failed(format!("could not run `security`: {error}")),
+    };
+    // Interactive mode exits 0 even when the command fails, so read it back.
+    match find() {
+        Ok(output)
+            if output.status.success()
+                && StateKey::from_hex(&String::from_utf8_lossy(&output.stdout))
+                    .is_some_and(|stored| stored.0 == key.0) =>
+        {
+            KeyResolution::with_key(key, KeySource::Keychain)
+        }
+        _ => failed(format!(
+            "could not save the state key to the keychain: {}",
+            String::from_utf8_lossy(&added.stderr).trim()
+        )),
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn keychain_key() -> KeyResolution {
+    KeyResolution {
+        key: None,
+        source: KeySource::Unsupported,
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum::bytes_to_hex;
+
+    #[test]
+    fn sealed_state_opens_only_with_its_key() {
+        let key = StateKey::generate();
+        let sealed = key.encrypt(br#"{"currentUser":null}"#);
+
+        assert!(is_encrypted(&sealed));
+        assert!(!is_encrypted(br#"{"currentUser":null}"#));
+        assert_eq!(
+            key.decrypt(&sealed).as_deref(),
+            Some(&br#"{"currentUser":null}"#[..])
+        );
+        assert!(StateKey::generate().decrypt(&sealed).is_none());
+
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(key.decrypt(&tampered).is_none());
+
+        let hex = bytes_to_hex(&key.0);
+        assert!(StateKey::from_hex(&hex).unwrap().decrypt(&sealed).is_some());
+        assert!(StateKey::from_hex("abc").is_none());
+    }
+}
@@ -0,0 +1,136 @@
+//! Pluggable summarizers for `inline summarize`.
+//!
+//! The CLI never talks to a model itself. The transcript goes either to a shell
+//! command on stdin (`llm`, `ollama run`, a local script) or as JSON to an HTTP
+//! endpoint, and whatever comes back is the summary.
+
+use std::process::Stdio;
+
+use serde::Serialize;
+use serde_json::Value;
+use tokio::io::AsyncWriteExt;
+
+use crate::errors::{CliError, HttpStatusCliError};
+use crate::identity as client_info;
+use crate::watch::hook_command;
+
+/// Body posted to `--endpoint`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SummarizeRequest<'a> {
+    pub(crate) peer_type: &'a str,
+    pub(crate) peer_id: i64,
+    pub(crate) name: Option<&'a str>,
+    pub(crate) since: Option<i64>,
+    pub(crate) until: Option<i64>,
+    pub(crate) messages: usize,
+    /// Markdown transcript, the same text `--command` receives on stdin.
+    pub(crate) transcript: &'a str,
+}
+
+impl SummarizeRequest<'_> {
+    /// Placeholders for `--command`, also exported as `INLINE_<NAME>`.
+    fn hook_vars(&self) -> Vec<(&'static str, String)> {
+        let mut vars = vec![
+            ("peer_type", self.peer_type.to_string()),
+            ("peer_id", self.peer_id.to_string()),
+            ("messages", self.messages.to_string()),
+        ];
+        if let Some(name) = self.name {
+            vars.push(("name", name.to_string()));
+        }
+        if let Some(since) = self.since {
+            vars.push(("since", since.to_string()));
+        }
+        if let Some(until) = self.until {
+            vars.push(("until", until.to_string()));
+        }
+        vars
+    }
+}
+
+/// Pipes the transcript to `template` and returns its stdout.
+pub(crate) async fn run_summarizer_command(
+    template: &str,
+    request: &SummarizeRequest<'_>,
+    read_only: bool,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut child = hook_command(template, &request.hook_vars(), read_only)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|error| {
+            CliError::invalid_args(format!("Could not run summarizer `{template}`: {error}"))
+        })?;
+    // Written from a task so a summarizer that streams output before reading all
+    // of stdin can't deadlock on a full pipe.
+    let writer = child.stdin.take().map(|mut stdin| {
+        let transcript = request.transcript.as_bytes().to_vec();
+        tokio::spawn(async move {
+            let _ = stdin.write_all(&transcript).await;
+        })
+    });
+    let output = child.wait_with_output().await?;
+    if let Some(writer) = writer {
+        let _ = writer.await;
+    }
+    if !output.status.success() {
+        return Err(CliError::summarizer_failed(format!(
+            "Summarizer `{template}` exited with {}",
+            output.status
+        ))
+        .into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Posts the request as JSON and returns the summary from the response.
+pub(crate) async fn post_to_summarizer_endpoint(
+    url: &str,
+    bearer_token: Option<&str>,
+    request: &SummarizeRequest<'_>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let client = client_info::http_client_builder()?.build()?;
+    let mut http_request = client.post(url).json(request);
+    if let Some(token) = bearer_token {
+        http_request = http_request.bearer_auth(token);
+    }
+    let response = http_request.send().await?;
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        let body = (!body.trim().is_empty()).then_some(body);
+        return Err(HttpStatusCliError::summarizer_endpoint_failed(status.as_u16(), body).into());
+    }
+    Ok(summary_from_response(&body))
+}
+
+/// Endpoints may answer with plain text or JSON carrying the text in
+/// `summary`, `text`, or `content`.
+pub(crate) fn summary_from_response(body: &str) -> String {
+    if let Ok(Value::Object(map)) = serde_json::from_str::<Value>(body) {
+        for key in ["summary", "text", "content"] {
+            if let Some(Value::String(text)) = map.get(key) {
+                return text.trim().to_string();
+            }
+        }
+    }
+    body.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoint_responses_can_be_text_or_json() {
+        assert_eq!(summary_from_response("  Shipped v2.\n"), "Shipped v2.");
+        assert_eq!(
+            summary_from_response(r#"{"summary":"Shipped v2.","model":"x"}"#),
+            "Shipped v2."
+        );
+        assert_eq!(summary_from_response(r#"{"content":" Done "}"#), "Done");
+        assert_eq!(summary_from_response(r#"{"other":1}"#), r#"{"other":1}"#);
+    }
+}
@@ -0,0 +1,133 @@
+//! Local client cache kept fresh by `inline sync`.
+//!
+//! The cache is an `inline-client` SQLite store in the data dir. It holds the
+//! update cursors, so each sync only pulls what changed since the last one.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use inline_client::ClientEvent;
+use serde::Serialize;
+
+/// How long a sync keeps listening for bucket hints after the last batch.
+pub(crate) const SYNC_IDLE_MS: u64 = 1_500;
+
+pub(crate) fn client_store_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("client.sqlite3")
+}
+
+/// What a sync applied, counted from the committed client events.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SyncSummary {
+    /// True when the cache had no sync cursor yet; it starts from now.
+    pub(crate) first_sync: bool,
+    /// Date of the newest applied update, in Unix seconds.
+    pub(crate) last_sync_date: i64,
+    pub(crate) messages: usize,
+    pub(crate) deleted_messages: usize,
+    pub(crate) reactions: usize,
+    pub(crate) read_states: usize,
+    pub(crate) chats_updated: usize,
+    pub(crate) chats_deleted: usize,
+    pub(crate) users_updated: usize,
+    pub(crate) spaces_updated: usize,
+    /// New or edited messages per chat.
+    pub(crate) chats: Vec<SyncChatSummary>,
+    #[serde(skip)]
+    messages_by_chat: BTreeMap<i64, usize>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SyncChatSummary {
+    pub(crate) chat_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) title: Option<String>,
+    pub(crate) messages: usize,
+}
+
+impl SyncSummary {
+    pub(crate) fn record(&mut self, event: &ClientEvent) {
+        match event {
+            ClientEvent::MessageStored { message } => {
+                self.messages += 1;
+                *self
+                    .messages_by_chat
+                    .entry(message.chat_id.get())
+                    .or_default() += 1;
+            }
+            ClientEvent::MessageUpserted { chat_id, .. } => {
+                self.messages += 1;
+                *self.messages_by_chat.entry(chat_id.get()).or_default() += 1;
+            }
+            ClientEvent::MessageDeleted { .. } | ClientEvent::ChatHistoryCleared { .. } => {
+                self.deleted_messages += 1;
+            }
+            ClientEvent::ReactionChanged { .. } => self.reactions += 1,
+            ClientEvent::ReadStateChanged { .. } => self.read_states += 1,
+            ClientEvent::ChatUpserted { .. } | ClientEvent::ChatParticipantsChanged { .. } => {
+                self.chats_updated += 1;
+            }
+            ClientEvent::ChatDeleted { .. } => self.chats_deleted += 1,
+            ClientEvent::UserUpserted { .. } => self.users_updated += 1,
+            ClientEvent::SpaceUpserted { .. } | ClientEvent::SpaceMemberChanged { .. } => {
+                self.spaces_updated += 1;
+            }
+            _ => {}
+        }
+    }
+
+    /// Chat ids with new messages, most active first.
+    pub(crate) fn active_chat_ids(&self) -> Vec<(i64, usize)> {
+        let mut chats = self
+            .messages_by_chat
+            .iter()
+            .map(|(chat_id, count)| (*chat_id, *count))
+            .collect::<Vec<_>>();
+        chats.sort_by(|left, right| right.1.cmp(&left.1).then(left.0.cmp(&right.0)));
+        chats
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.messages == 0
+            && self.deleted_messages == 0
+            && self.reactions == 0
+            && self.read_states == 0
+            && self.chats_updated == 0
+            && self.chats_deleted == 0
+            && self.users_updated == 0
+            && self.spaces_updated == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use inline_client::InlineId;
+
+    #[test]
+    fn summary_counts_messages_per_chat() {
+        let mut summary = SyncSummary::default();
+        for (chat_id, message_id) in [(7, 1), (8, 2), (8, 3)] {
+            summary.record(&ClientEvent::MessageUpserted {
+                chat_id: InlineId::new(chat_id),
+                message_id: InlineId::new(message_id),
+            });
+        }
+        summary.record(&ClientEvent::ChatDeleted {
+            chat_id: InlineId::new(9),
+        });
+        summary.record(&ClientEvent::Typing {
+            chat_id: InlineId::new(7),
+            user_id: InlineId::new(1),
+            is_typing: true,
+        });
+
+        assert_eq!(summary.messages, 3);
+        assert_eq!(summary.chats_deleted, 1);
+        assert_eq!(summary.active_chat_ids(), vec![(8, 2), (7, 1)]);
+        assert!(!summary.is_empty());
+        assert!(SyncSummary::default().is_empty());
+    }
+}
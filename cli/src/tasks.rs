@@ -0,0 +1,401 @@
+//! Task providers for `inline tasks`.
+//!
+//! Linear and Notion are built in and backed by the Inline API integrations.
+//! Other trackers can be wired in through webhook providers declared in the
+//! config file:
+//!
+//! ```toml
+//! [tasks.webhooks.todoist]
+//! url = "https://hooks.example.com/inline-task"
+//! label = "Todoist task"        # optional, used in human output
+//! token_env = "TODOIST_HOOK"    # optional, sent as a bearer token
+//! ```
+
+use std::env;
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::config_file::ConfigFile;
+use crate::errors::{CliError, HttpStatusCliError};
+use inline_sdk::api::{ApiClient, CreateLinearIssueInput, CreateNotionTaskInput, PeerId};
+
+pub(crate) const BUILTIN_TASK_PROVIDERS: [&str; 2] = ["linear", "notion"];
+const WEBHOOKS_CONFIG_PREFIX: &str = "tasks.webhooks";
+
+/// The message a task is created from.
+pub(crate) struct TaskSource {
+    pub(crate) chat_id: i64,
+    pub(crate) message_id: i64,
+    pub(crate) space_id: Option<i64>,
+    /// Resolved only for providers that report `requires_message`.
+    pub(crate) message: Option<TaskSourceMessage>,
+}
+
+pub(crate) struct TaskSourceMessage {
+    pub(crate) from_id: i64,
+    pub(crate) text: String,
+    pub(crate) date: i64,
+}
+
+pub(crate) struct CreatedTask {
+    pub(crate) url: Option<String>,
+    pub(crate) title: Option<String>,
+    /// Provider response, printed as-is in `--json` mode.
+    pub(crate) payload: Value,
+}
+
+pub(crate) trait TaskProvider {
+    /// Human label for the created item, e.g. "Linear issue".
+    fn label(&self) -> String;
+
+    /// Whether the source message text and sender must be fetched first.
+    fn requires_message(&self) -> bool;
+
+    async fn create_from_message(
+        &self,
+        api: &ApiClient,
+        token: &str,
+        source: &TaskSource,
+    ) -> Result<CreatedTask, Box<dyn std::error::Error>>;
+}
+
+pub(crate) struct LinearTaskProvider;
+
+impl TaskProvider for LinearTaskProvider {
+    fn label(&self) -> String {
+        "Linear issue".to_string()
+    }
+
+    fn requires_message(&self) -> bool {
+        true
+    }
+
+    async fn create_from_message(
+        &self,
+        api: &ApiClient,
+        token: &str,
+        source: &TaskSource,
+    ) -> Result<CreatedTask, Box<dyn std::error::Error>> {
+        let message = require_message_text(source)?;
+        let mut input = CreateLinearIssueInput::new(
+            message.text.clone(),
+            source.message_id,
+            source.chat_id,
+            message.from_id,
+            PeerId::thread(source.chat_id),
+        );
+        if let Some(space_id) = source.space_id {
+            input = input.with_space_id(space_id);
+        }
+
+        let result = api.create_linear_issue(token, input).await?;
+        Ok(CreatedTask {
+            url: result.link.clone(),
+            title: None,
+            payload: serde_json::to_value(&result)?,
+        })
+    }
+}
+
+pub(crate) struct NotionTaskProvider;
+
+impl TaskProvider for NotionTaskProvider {
+    fn label(&self) -> String {
+        "Notion task".to_string()
+    }
+
+    fn requires_message(&self) -> bool {
+        false
+    }
+
+    async fn create_from_message(
+        &self,
+        api: &ApiClient,
+        token: &str,
+        source: &TaskSource,
+    ) -> Result<CreatedTask, Box<dyn std::error::Error>> {
+        let space_id = source.space_id.ok_or_else(|| {
+            CliError::invalid_args("Missing required argument: --space-id for Notion tasks")
+        })?;
+        let input = CreateNotionTaskInput::new(
+            space_id,
+            source.message_id,
+            source.chat_id,
+            PeerId::thread(source.chat_id),
+        );
+
+        let result = api.create_notion_task(token, input).await?;
+        Ok(CreatedTask {
+            url: Some(result.url.clone()),
+            title: result.task_title.clone(),
+            payload: serde_json::to_value(&result)?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WebhookTaskProvider {
+    pub(crate) name: String,
+    pub(crate) url: String,
+    pub(crate) label: Option<String>,
+    pub(crate) token_env: Option<String>,
+}
+
+impl WebhookTaskProvider {
+    fn payload(&self, source: &TaskSource) -> Value {
+        let message = source.message.as_ref();
+        json!({
+            "provider": self.name,
+            "chatId": source.chat_id,
+            "messageId": source.message_id,
+            "spaceId": source.space_id,
+            "peerId": { "threadId": source.chat_id },
+            "fromId": message.map(|message| message.from_id),
+            "text": message.map(|message| message.text.as_str()),
+            "date": message.map(|message| message.date),
+        })
+    }
+}
+
+impl TaskProvider for WebhookTaskProvider {
+    fn label(&self) -> String {
+        self.label
+            .clone()
+            .unwrap_or_else(|| format!("{} task", self.name))
+    }
+
+    fn requires_message(&self) -> bool {
+        true
+    }
+
+    async fn create_from_message(
+        &self,
+        api: &ApiClient,
+        _token: &str,
+        source: &TaskSource,
+    ) -> Result<CreatedTask, Box<dyn std::error::Error>> {
+        require_message_text(source)?;
+        let mut request = api
+            .http_client()
+            .post(&self.url)
+            .json(&self.payload(source));
+        if let Some(token_env) = self.token_env.as_deref() {
+            let token = env::var(token_env).map_err(|_| {
+                CliError::invalid_args(format!(
+                    "Task webhook `{}` expects a token in ${token_env}, which is not set",
+                    self.name
+                ))
+            })?;
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            let body = (!body.trim().is_empty()).then_some(body);
+            return Err(HttpStatusCliError::task_webhook_failed(status.as_u16(), body).into());
+        }
+
+        let payload = if body.trim().is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(&body).unwrap_or(Value::String(body))
+        };
+        Ok(created_task_from_webhook_response(payload))
+    }
+}
+
+/// Provider selected by name: a built-in or a configured webhook.
+pub(crate) enum ConfiguredTaskProvider {
+    Linear(LinearTaskProvider),
+    Notion(NotionTaskProvider),
+    Webhook(WebhookTaskProvider),
+}
+
+impl TaskProvider for ConfiguredTaskProvider {
+    fn label(&self) -> String {
+        match self {
+            Self::Linear(provider) => provider.label(),
+            Self::Notion(provider) => provider.label(),
+            Self::Webhook(provider) => provider.label(),
+        }
+    }
+
+    fn requires_message(&self) -> bool {
+        match self {
+            Self::Linear(provider) => provider.requires_message(),
+            Self::Notion(provider) => provider.requires_message(),
+            Self::Webhook(provider) => provider.requires_message(),
+        }
+    }
+
+    async fn create_from_message(
+        &self,
+        api: &ApiClient,
+        token: &str,
+        source: &TaskSource,
+    ) -> Result<CreatedTask, Box<dyn std::error::Error>> {
+        match self {
+            Self::Linear(provider) => provider.create_from_message(api, token, source).await,
+            Self::Notion(provider) => provider.create_from_message(api, token, source).await,
+            Self::Webhook(provider) => provider.create_from_message(api, token, source).await,
+        }
+    }
+}
+
+pub(crate) fn resolve_task_provider(
+    name: &str,
+    config_file: &ConfigFile,
+) -> Result<ConfiguredTaskProvider, Box<dyn std::error::Error>> {
+    let name = name.trim().to_ascii_lowercase();
+    match name.as_str() {
+        "linear" => return Ok(ConfiguredTaskProvider::Linear(LinearTaskProvider)),
+        "notion" => return Ok(ConfiguredTaskProvider::Notion(NotionTaskProvider)),
+        _ => {}
+    }
+
+    webhook_task_providers(config_file)?
+        .into_iter()
+        .find(|provider| provider.name == name)
+        .map(ConfiguredTaskProvider::Webhook)
+        .ok_or_else(|| {
+            CliError::invalid_args(format!(
+                "Unknown task provider `{name}`. Use linear, notion, or a webhook from [{WEBHOOKS_CONFIG_PREFIX}.<name>] in the config file."
+            ))
+            .into()
+        })
+}
+
+pub(crate) fn webhook_task_providers(
+    config_file: &ConfigFile,
+) -> Result<Vec<WebhookTaskProvider>, CliError> {
+    config_file
+        .table_names(WEBHOOKS_CONFIG_PREFIX)
+        .into_iter()
+        .map(|name| {
+            let key = |field: &str| format!("{WEBHOOKS_CONFIG_PREFIX}.{name}.{field}");
+            let url = config_file
+                .get_str(&key("url"))
+                .filter(|url| url.starts_with("https://") || url.starts_with("http://"))
+                .ok_or_else(|| {
+                    CliError::invalid_args(format!(
+                        "Task webhook `{name}` needs an http(s) `url` in the config file"
+                    ))
+                })?;
+            Ok(WebhookTaskProvider {
+                name: name.to_ascii_lowercase(),
+                url: url.to_string(),
+                label: config_file.get_str(&key("label")).map(ToString::to_string),
+                token_env: config_file
+                    .get_str(&key("token_env"))
+                    .map(ToString::to_string),
+            })
+        })
+        .collect()
+}
+
+fn require_message_text(source: &TaskSource) -> Result<&TaskSourceMessage, CliError> {
+    source
+        .message
+        .as_ref()
+        .filter(|message| !message.text.trim().is_empty())
+        .ok_or_else(|| CliError::invalid_args("Message has no text content"))
+}
+
+fn created_task_from_webhook_response(payload: Value) -> CreatedTask {
+    let field = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| payload.get(*name).and_then(Value::as_str))
+            .map(ToString::to_string)
+    };
+    CreatedTask {
+        url: field(&["url", "link"]),
+        title: field(&["title", "taskTitle"]),
+        payload,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webhook_providers_are_read_from_config_tables() {
+        let config_file = ConfigFile::parse(
+            r#"
+[tasks.webhooks.todoist]
+url = "https://hooks.example.com/todoist"
+label = "Todoist task"
+token_env = "TODOIST_HOOK"
+
+[tasks.webhooks.jira]
+url = "https://hooks.example.com/jira"
+"#,
+        )
+        .unwrap();
+
+        let providers = webhook_task_providers(&config_file).unwrap();
+        assert_eq!(providers.len(), 2);
+        assert_eq!(providers[0].name, "jira");
+        assert_eq!(providers[0].label, None);
+        assert_eq!(providers[1].name, "todoist");
+        assert_eq!(providers[1].token_env.as_deref(), Some("TODOIST_HOOK"));
+
+        let resolved = resolve_task_provider("Todoist", &config_file).unwrap();
+        assert!(matches!(resolved, ConfiguredTaskProvider::Webhook(_)));
+        assert_eq!(resolved.label(), "Todoist task");
+        assert!(matches!(
+            resolve_task_provider("linear", &config_file).unwrap(),
+            ConfiguredTaskProvider::Linear(_)
+        ));
+        assert!(resolve_task_provider("asana", &config_file).is_err());
+    }
+
+    #[test]
+    fn webhook_provider_requires_http_url() {
+        let config_file =
+            ConfigFile::parse("[tasks.webhooks.bad]\nurl = \"ftp://example.com\"\n").unwrap();
+        assert!(webhook_task_providers(&config_file).is_err());
+    }
+
+    #[test]
+    fn webhook_payload_and_response_use_camel_case_fields() {
+        let provider = WebhookTaskProvider {
+            name: "todoist".to_string(),
+            url: "https://hooks.example.com".to_string(),
+            label: None,
+            token_env: None,
+        };
+        let source = TaskSource {
+            chat_id: 7,
+            message_id: 42,
+            space_id: Some(3),
+            message: Some(TaskSourceMessage {
+                from_id: 9,
+                text: "ship it".to_string(),
+                date: 1_700_000_000,
+            }),
+        };
+
+        let payload = provider.payload(&source);
+        assert_eq!(payload["provider"], "todoist");
+        assert_eq!(payload["messageId"], 42);
+        assert_eq!(payload["peerId"]["threadId"], 7);
+        assert_eq!(payload["text"], "ship it");
+        assert_eq!(provider.label(), "todoist task");
+
+        let created = created_task_from_webhook_response(json!({
+            "link": "https://todoist.example.com/t/1",
+            "title": "ship it"
+        }));
+        assert_eq!(
+            created.url.as_deref(),
+            Some("https://todoist.example.com/t/1")
+        );
+        assert_eq!(created.title.as_deref(), Some("ship it"));
+    }
+}
@@ -0,0 +1,153 @@
+//! Terminal widths for table cells, measured per grapheme cluster rather than
+//! per char, so emoji sequences and combining marks are never split or
+//! over-counted.
+//!
+//! Clusters here are an approximation of UAX #29 that covers what shows up in
+//! chat text: combining marks (Arabic harakat, accents), variation selectors,
+//! skin-tone modifiers, ZWJ emoji sequences, and flag pairs. A cluster is as
+//! wide as its widest char, and emoji presentation (U+FE0F) or a flag makes it
+//! two columns wide.
+
+use unicode_width::UnicodeWidthChar;
+
+const ZWJ: char = '\u{200D}';
+const EMOJI_PRESENTATION: char = '\u{FE0F}';
+/// First strong isolate / pop directional isolate.
+const FSI: char = '\u{2068}';
+const PDI: char = '\u{2069}';
+
+/// Splits `value` into grapheme clusters with their byte offsets.
+pub(crate) fn grapheme_indices(value: &str) -> Vec<(usize, &str)> {
+    let mut clusters = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<char> = None;
+    let mut regional_indicators = 0;
+    for (index, ch) in value.char_indices() {
+        let extends = previous.is_some_and(|previous| {
+            (char_width(ch) == 0 && !ch.is_control())
+                || is_emoji_modifier(ch)
+                || (previous == ZWJ && char_width(ch) == 2)
+                || (is_regional_indicator(ch) && regional_indicators % 2 == 1)
+        });
+        if !extends && index > start {
+            clusters.push((start, &value[start..index]));
+            start = index;
+            regional_indicators = 0;
+        }
+        if is_regional_indicator(ch) {
+            regional_indicators += 1;
+        }
+        previous = Some(ch);
+    }
+    if start < value.len() {
+        clusters.push((start, &value[start..]));
+    }
+    clusters
+}
+
+/// Columns `cluster` takes in a terminal.
+pub(crate) fn cluster_width(cluster: &str) -> usize {
+    let widest = cluster.chars().map(char_width).max().unwrap_or(0);
+    let emoji = cluster.contains(EMOJI_PRESENTATION)
+        || cluster
+            .chars()
+            .filter(|ch| is_regional_indicator(*ch))
+            .count()
+            == 2;
+    if emoji { widest.max(2) } else { widest }
+}
+
+pub(crate) fn display_width(value: &str) -> usize {
+    if value.is_ascii() {
+        return value
+            .bytes()
+            .filter(|byte| !byte.is_ascii_control())
+            .count();
+    }
+    grapheme_indices(value)
+        .into_iter()
+        .map(|(_, cluster)| cluster_width(cluster))
+        .sum()
+}
+
+/// Whether `value` has right-to-left letters (Hebrew, Arabic, Syriac, Thaana,
+/// N'Ko, and their presentation forms).
+pub(crate) fn has_rtl(value: &str) -> bool {
+    value.chars().any(|ch| {
+        matches!(
+            ch,
+            '\u{0590}'..='\u{08FF}'
+                | '\u{FB1D}'..='\u{FDFF}'
+                | '\u{FE70}'..='\u{FEFF}'
+                | '\u{10800}'..='\u{10FFF}'
+                | '\u{1E800}'..='\u{1EFFF}'
+        )
+    })
+}
+
+/// Wraps RTL text in a directional isolate so the terminal's bidi reordering
+/// stays inside the cell instead of pulling the next column into it.
+pub(crate) fn isolate_rtl(value: &str) -> String {
+    if has_rtl(value) {
+        format!("{FSI}{value}{PDI}")
+    } else {
+        value.to_string()
+    }
+}
+
+fn char_width(ch: char) -> usize {
+    UnicodeWidthChar::width(ch).unwrap_or(0)
+}
+
+fn is_emoji_modifier(ch: char) -> bool {
+    matches!(ch, '\u{1F3FB}'..='\u{1F3FF}')
+}
+
+fn is_regional_indicator(ch: char) -> bool {
+    matches!(ch, '\u{1F1E6}'..='\u{1F1FF}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clusters(value: &str) -> Vec<&str> {
+        grapheme_indices(value)
+            .into_iter()
+            .map(|(_, cluster)| cluster)
+            .collect()
+    }
+
+    #[test]
+    fn clusters_keep_emoji_sequences_and_marks_together() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let thumbs = "\u{1F44D}\u{1F3FD}";
+        let flags = "\u{1F1E9}\u{1F1EA}\u{1F1EB}\u{1F1F7}";
+        assert_eq!(
+            clusters(&format!("a{family}{thumbs}b")),
+            ["a", family, thumbs, "b"]
+        );
+        assert_eq!(clusters(flags).len(), 2);
+        assert_eq!(display_width(family), 2);
+        assert_eq!(display_width(thumbs), 2);
+        assert_eq!(display_width(flags), 4);
+        assert_eq!(display_width("\u{2764}\u{FE0F}"), 2);
+
+        // Arabic with harakat: each letter keeps its marks, one column each.
+        let arabic = "\u{0645}\u{064E}\u{0631}\u{0652}\u{062D}\u{064E}\u{0628}\u{064B}\u{0627}";
+        assert_eq!(clusters(arabic).len(), 5);
+        assert_eq!(display_width(arabic), 5);
+        assert_eq!(display_width("e\u{0301}t\u{00E9}"), 3);
+        assert_eq!(display_width("日本"), 4);
+    }
+
+    #[test]
+    fn rtl_text_is_isolated() {
+        assert_eq!(isolate_rtl("hello"), "hello");
+        assert_eq!(
+            isolate_rtl("\u{05E9}\u{05DC}\u{05D5}\u{05DD} 42"),
+            "\u{2068}\u{05E9}\u{05DC}\u{05D5}\u{05DD} 42\u{2069}"
+        );
+        assert_eq!(display_width(&isolate_rtl("\u{05E9}\u{05DC}")), 2);
+    }
+}
@@ -1,25 +1,39 @@
 use std::collections::HashMap;
 use std::fs::{self, File};
-use std::io::{self, Read};
+use std::io::{self, IsTerminal, Write};
+use std::net::{SocketAddr, UdpSocket};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use flate2::read::GzDecoder;
 use semver::Version;
-use serde::Deserialize;
-use sha2::{Digest, Sha256};
+use serde::{Deserialize, Serialize};
 use tar::Archive;
 use thiserror::Error;
+use tokio::io::AsyncWriteExt;
 use tokio::task::JoinHandle;
 
+use crate::bspatch::{MAX_NEW_LEN, apply_patch};
+use crate::checksum::sha256_file;
 use crate::config::Config;
 use crate::identity as client_info;
-use crate::state::{LocalDb, StateError};
-
-const UPDATE_CHECK_INTERVAL_SECS: i64 = 6 * 60 * 60;
+use crate::minisign::{PublicKey, SignatureError};
+use crate::state::{LocalDb, LocalState, StateError};
+
+const DAY_SECS: i64 = 24 * 60 * 60;
+/// Each failed check in a row doubles the wait, up to this many times.
+const UPDATE_CHECK_MAX_BACKOFF: u32 = 2;
+/// Public resolvers used only to ask the OS for a route; no packet is sent.
+const ROUTE_PROBES: [&str; 2] = ["1.1.1.1:53", "[2606:4700:4700::1111]:53"];
 const UPDATE_CHECK_TIMEOUT_SECS: u64 = 4;
 const UPDATE_CHECK_FINISH_TIMEOUT_MS: u64 = 150;
+/// Downloads give up after this many attempts; later attempts resume with
+/// `Range` when the server supports it.
+const DOWNLOAD_ATTEMPTS: u32 = 4;
+const DOWNLOAD_RETRY_BASE_MS: u64 = 1_000;
+/// A stalled download is retried after this long without data.
+const DOWNLOAD_READ_TIMEOUT_SECS: u64 = 30;
 
 #[derive(Debug, Error)]
 pub enum UpdateError {
@@ -41,6 +55,34 @@ pub enum UpdateError {
     ChecksumMismatch { expected: String, actual: String },
     #[error("missing inline binary in update bundle")]
     MissingBinary,
+    #[error("download failed with HTTP {0}")]
+    DownloadStatus(u16),
+    #[error(
+        "this build has no release signing key; pass --allow-unsigned to update without checking signatures"
+    )]
+    MissingPublicKey,
+    #[error("no signature published for {0}; pass --allow-unsigned to install it anyway")]
+    Unsigned(String),
+    #[error("signature check failed for {url}: {source}")]
+    Signature { url: String, source: SignatureError },
+}
+
+/// How often the background update check runs (`update.check`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpdateCheck {
+    Never,
+    Daily,
+    Weekly,
+}
+
+impl UpdateCheck {
+    fn interval_secs(self) -> Option<i64> {
+        match self {
+            Self::Never => None,
+            Self::Daily => Some(DAY_SECS),
+            Self::Weekly => Some(7 * DAY_SECS),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -60,11 +102,44 @@ struct UpdateTarget {
     sha256: String,
     #[allow(dead_code)]
     size: Option<u64>,
+    /// Patches from older releases to this one; used when one starts at the
+    /// running version.
+    #[serde(default)]
+    deltas: Vec<UpdateDelta>,
 }
 
-pub async fn run_update(config: &Config, json: bool) -> Result<(), UpdateError> {
+/// A bsdiff patch from the `from` release's binary to this release's binary.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateDelta {
+    from: String,
+    url: String,
+    /// Checksum of the patch file.
+    sha256: String,
+    /// Checksum of the patched binary.
+    binary_sha256: String,
+    /// Size of the patched binary; patches declaring more are rejected.
+    binary_size: Option<u64>,
+}
+
+/// What `inline update` did, printed under `--json`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateOutcome {
+    pub current_version: String,
+    pub latest_version: String,
+    pub updated: bool,
+    /// Where the new binary went; unset when nothing was installed.
+    pub install_path: Option<String>,
+}
+
+pub async fn run_update(
+    config: &Config,
+    json: bool,
+    allow_unsigned: bool,
+) -> Result<UpdateOutcome, UpdateError> {
     let mut install_url_hint = config.release_install_url.clone();
-    let result = run_update_inner(config, json, &mut install_url_hint).await;
+    let result = run_update_inner(config, json, allow_unsigned, &mut install_url_hint).await;
     if result.is_err() && !json {
         print_reinstall_instructions(install_url_hint.as_deref());
     }
@@ -74,61 +149,88 @@ pub async fn run_update(config: &Config, json: bool) -> Result<(), UpdateError>
 async fn run_update_inner(
     config: &Config,
     json: bool,
+    allow_unsigned: bool,
     install_url_hint: &mut Option<String>,
-) -> Result<(), UpdateError> {
+) -> Result<UpdateOutcome, UpdateError> {
     let manifest_url = config
         .release_manifest_url
         .clone()
         .ok_or(UpdateError::MissingManifestUrl)?;
-    let manifest = fetch_manifest(&manifest_url).await?;
+    let (manifest, manifest_payload) = fetch_manifest(&manifest_url).await?;
     if manifest.install_url.is_some() {
         *install_url_hint = manifest.install_url.clone();
     }
+    let latest = Version::parse(&manifest.version)?;
+    let current = Version::parse(env!("CARGO_PKG_VERSION"))?;
+    let mut outcome = UpdateOutcome {
+        current_version: current.to_string(),
+        latest_version: latest.to_string(),
+        updated: false,
+        install_path: None,
+    };
     let target = current_target();
     if target == "unknown" {
         if !json {
             eprintln!("Auto-update is not supported on this OS yet.");
         }
-        return Ok(());
+        return Ok(outcome);
     }
 
-    let latest = Version::parse(&manifest.version)?;
-    let current = Version::parse(env!("CARGO_PKG_VERSION"))?;
     if latest <= current {
         if !json {
             println!("inline is up to date (v{current}).");
         }
-        return Ok(());
+        return Ok(outcome);
     }
 
+    // Only a manifest that leads to an install needs to be trusted; "up to
+    // date" from a spoofed one costs nothing.
+    let release_key = if allow_unsigned {
+        if !json {
+            eprintln!("Skipping signature checks (--allow-unsigned).");
+        }
+        None
+    } else {
+        let key = release_public_key(config)?;
+        verify_signature(&key, &manifest_url, manifest_payload.as_bytes()).await?;
+        Some(key)
+    };
+
     let target_manifest = manifest
         .targets
         .get(target)
         .ok_or_else(|| UpdateError::MissingTarget(target.to_string()))?;
 
     let temp_dir = create_temp_dir()?;
-    let archive_path = temp_dir.join("inline.tar.gz");
-    download_file(&target_manifest.url, &archive_path).await?;
-    let actual_sha = sha256_file(&archive_path)?;
-    let expected_sha = target_manifest.sha256.trim().to_string();
-    if actual_sha != expected_sha {
-        return Err(UpdateError::ChecksumMismatch {
-            expected: expected_sha,
-            actual: actual_sha,
-        });
-    }
-
-    let extract_dir = temp_dir.join("extract");
-    fs::create_dir_all(&extract_dir)?;
-    extract_archive(&archive_path, &extract_dir)?;
-
-    let extracted_binary = extract_dir.join("inline");
-    if !extracted_binary.exists() {
-        return Err(UpdateError::MissingBinary);
-    }
-
     let current_exe = std::env::current_exe()?;
-    let staged_path = stage_binary(&extracted_binary, &temp_dir)?;
+    let progress = !json && io::stderr().is_terminal();
+    let delta_binary = match find_delta(target_manifest, &current) {
+        Some(delta) => match apply_delta(delta, &current_exe, &temp_dir, &latest, progress).await {
+            Ok(binary) => Some(binary),
+            Err(error) => {
+                if !json {
+                    log::warn!("delta update failed ({error}); downloading the full release.");
+                }
+                None
+            }
+        },
+        None => None,
+    };
+    let new_binary = match delta_binary {
+        Some(binary) => binary,
+        None => {
+            download_release(
+                target_manifest,
+                &temp_dir,
+                &latest,
+                release_key.as_ref(),
+                progress,
+            )
+            .await?
+        }
+    };
+
+    let staged_path = stage_binary(&new_binary, &temp_dir)?;
     let install_outcome = install_binary(&staged_path, &current_exe)?;
 
     if !json {
@@ -150,7 +252,9 @@ async fn run_update_inner(
             println!("Updated inline to v{latest}.");
         }
     }
-    Ok(())
+    outcome.updated = true;
+    outcome.install_path = Some(install_outcome.install_path.display().to_string());
+    Ok(outcome)
 }
 
 pub fn spawn_update_check(
@@ -158,6 +262,7 @@ pub fn spawn_update_check(
     local_db: &LocalDb,
     json: bool,
 ) -> Option<JoinHandle<()>> {
+    let interval = config.update_check.interval_secs()?;
     let manifest_url = config.release_manifest_url.clone()?;
     let install_url = config.release_install_url.clone();
     let local_db = local_db.clone();
@@ -171,23 +276,66 @@ pub fn spawn_update_check(
     let now = current_epoch_seconds();
     let recently_attempted = local_db.load().ok().is_some_and(|state| {
         state.release_manifest_url.as_deref() == Some(&manifest_url)
-            && state.last_update_attempt_at.is_some_and(|last_attempt| {
-                now.saturating_sub(last_attempt) < UPDATE_CHECK_INTERVAL_SECS
-            })
+            && !check_due(&state, interval, now)
     });
     if recently_attempted {
         return None;
     }
+    // Without a route there is nothing to reach; don't count it as a failure
+    // either. A proxy may be the only way out, so it's trusted to know better.
+    if config.proxy.is_none() && !has_network_route() {
+        log::debug!("skipping update check: no network route");
+        return None;
+    }
 
     Some(tokio::spawn(async move {
-        let update_result =
-            check_for_update(manifest_url, install_url, local_db, current_version, json).await;
-        if let (true, Err(error)) = (cfg!(debug_assertions), update_result) {
-            eprintln!("update check failed: {error}");
+        let update_result = check_for_update(
+            manifest_url,
+            install_url,
+            &local_db,
+            current_version,
+            interval,
+            json,
+        )
+        .await;
+        if let Err(error) = update_result {
+            log::debug!("update check failed: {error}");
+            if let Ok(mut state) = local_db.load() {
+                state.update_check_failures = state.update_check_failures.saturating_add(1);
+                let _ = local_db.save(&state);
+            }
         }
     }))
 }
 
+/// Whether the last attempt is older than `interval`, stretched by the
+/// backoff for checks that failed in a row.
+fn check_due(state: &LocalState, interval: i64, now: i64) -> bool {
+    let backoff = state.update_check_failures.min(UPDATE_CHECK_MAX_BACKOFF);
+    let wait = interval.saturating_mul(1 << backoff);
+    state
+        .last_update_attempt_at
+        .is_none_or(|last_attempt| now.saturating_sub(last_attempt) >= wait)
+}
+
+/// Whether the OS has a route to the internet. Connecting a UDP socket only
+/// looks up the route, so this is instant and sends nothing.
+fn has_network_route() -> bool {
+    ROUTE_PROBES.iter().any(|probe| {
+        let Ok(addr) = probe.parse::<SocketAddr>() else {
+            return false;
+        };
+        let bind: SocketAddr = if addr.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0u16; 8], 0).into()
+        };
+        UdpSocket::bind(bind)
+            .and_then(|socket| socket.connect(addr))
+            .is_ok()
+    })
+}
+
 pub async fn finish_update_check(handle: Option<JoinHandle<()>>) {
     if let Some(handle) = handle {
         let _ = tokio::time::timeout(
@@ -201,8 +349,9 @@ pub async fn finish_update_check(handle: Option<JoinHandle<()>>) {
 async fn check_for_update(
     manifest_url: String,
     install_url: Option<String>,
-    local_db: LocalDb,
+    local_db: &LocalDb,
     current_version: String,
+    interval: i64,
     json: bool,
 ) -> Result<(), UpdateError> {
     let target = current_target();
@@ -217,12 +366,10 @@ async fn check_for_update(
         state.last_update_attempt_at = None;
         state.last_update_notified_version = None;
         state.last_seen_release_version = None;
+        state.update_check_failures = 0;
     }
 
-    if state
-        .last_update_attempt_at
-        .is_some_and(|last_attempt| now.saturating_sub(last_attempt) < UPDATE_CHECK_INTERVAL_SECS)
-    {
+    if !check_due(&state, interval, now) {
         return Ok(());
     }
 
@@ -231,7 +378,7 @@ async fn check_for_update(
     state.last_update_attempt_at = Some(now);
     let _ = local_db.save(&state);
 
-    let client = client_info::http_client_builder()
+    let client = client_info::http_client_builder()?
         .timeout(Duration::from_secs(UPDATE_CHECK_TIMEOUT_SECS))
         .build()?;
     let response = client.get(&manifest_url).send().await?.error_for_status()?;
@@ -239,6 +386,7 @@ async fn check_for_update(
     let manifest: UpdateManifest = serde_json::from_str(&payload)?;
 
     state.last_update_check_at = Some(now);
+    state.update_check_failures = 0;
     state.last_seen_release_version = Some(manifest.version.clone());
 
     let latest = Version::parse(&manifest.version)?;
@@ -325,54 +473,236 @@ fn current_epoch_seconds() -> i64 {
         .as_secs() as i64
 }
 
-async fn fetch_manifest(url: &str) -> Result<UpdateManifest, UpdateError> {
-    let client = client_info::http_client_builder()
+/// Returns the manifest with the exact text it was parsed from, which is what
+/// its signature covers.
+async fn fetch_manifest(url: &str) -> Result<(UpdateManifest, String), UpdateError> {
+    let client = client_info::http_client_builder()?
         .timeout(Duration::from_secs(UPDATE_CHECK_TIMEOUT_SECS))
         .build()?;
     let response = client.get(url).send().await?.error_for_status()?;
     let payload = response.text().await?;
-    Ok(serde_json::from_str(&payload)?)
+    Ok((serde_json::from_str(&payload)?, payload))
 }
 
-async fn download_file(url: &str, path: &Path) -> Result<(), UpdateError> {
-    let client = client_info::http_client_builder()
-        .timeout(Duration::from_secs(60))
+fn release_public_key(config: &Config) -> Result<PublicKey, UpdateError> {
+    let key = config
+        .release_public_key
+        .as_deref()
+        .ok_or(UpdateError::MissingPublicKey)?;
+    PublicKey::parse(key).map_err(|source| UpdateError::Signature {
+        url: "release public key".to_string(),
+        source,
+    })
+}
+
+/// Checks `data` against the minisign signature published at `<url>.minisig`.
+async fn verify_signature(key: &PublicKey, url: &str, data: &[u8]) -> Result<(), UpdateError> {
+    let signature_url = format!("{url}.minisig");
+    let client = client_info::http_client_builder()?
+        .timeout(Duration::from_secs(UPDATE_CHECK_TIMEOUT_SECS))
         .build()?;
-    let response = client.get(url).send().await?.error_for_status()?;
-    let bytes = response.bytes().await?;
-    tokio::fs::write(path, &bytes).await?;
+    let response = client.get(&signature_url).send().await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(UpdateError::Unsigned(url.to_string()));
+    }
+    let signature = response.error_for_status()?.text().await?;
+    let trusted_comment =
+        key.verify(data, &signature)
+            .map_err(|source| UpdateError::Signature {
+                url: url.to_string(),
+                source,
+            })?;
+    log::debug!(
+        "verified {url} with key {}: {trusted_comment}",
+        key.key_id()
+    );
     Ok(())
 }
 
-fn create_temp_dir() -> Result<PathBuf, UpdateError> {
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default();
-    let dir = std::env::temp_dir().join(format!("inline-update-{}", timestamp.as_secs()));
-    fs::create_dir_all(&dir)?;
-    Ok(dir)
+fn find_delta<'a>(target: &'a UpdateTarget, current: &Version) -> Option<&'a UpdateDelta> {
+    target
+        .deltas
+        .iter()
+        .find(|delta| Version::parse(&delta.from).is_ok_and(|from| from == *current))
+}
+
+/// Downloads the full release archive and returns the extracted binary. With
+/// a release key, the archive's own signature must check out too.
+async fn download_release(
+    target: &UpdateTarget,
+    temp_dir: &Path,
+    latest: &Version,
+    release_key: Option<&PublicKey>,
+    progress: bool,
+) -> Result<PathBuf, UpdateError> {
+    let archive_path = temp_dir.join("inline.tar.gz");
+    let label = format!("inline v{latest}");
+    download_file(&target.url, &archive_path, &label, progress).await?;
+    verify_checksum(&archive_path, &target.sha256)?;
+    if let Some(key) = release_key {
+        verify_signature(key, &target.url, &fs::read(&archive_path)?).await?;
+    }
+
+    let extract_dir = temp_dir.join("extract");
+    fs::create_dir_all(&extract_dir)?;
+    extract_archive(&archive_path, &extract_dir)?;
+
+    let extracted_binary = extract_dir.join("inline");
+    if !extracted_binary.exists() {
+        return Err(UpdateError::MissingBinary);
+    }
+    Ok(extracted_binary)
 }
 
-fn sha256_file(path: &Path) -> Result<String, UpdateError> {
-    let mut file = File::open(path)?;
-    let mut hasher = Sha256::new();
-    let mut buffer = [0u8; 8192];
+/// Patches the running binary and returns the new one. Any failure falls back
+/// to the full download, so a locally modified binary only costs the patch.
+/// Patches aren't signed themselves; both checksums come from the signed
+/// manifest.
+async fn apply_delta(
+    delta: &UpdateDelta,
+    current_exe: &Path,
+    temp_dir: &Path,
+    latest: &Version,
+    progress: bool,
+) -> Result<PathBuf, UpdateError> {
+    let patch_path = temp_dir.join("inline.bsdiff");
+    let label = format!("inline v{} -> v{latest} patch", delta.from);
+    download_file(&delta.url, &patch_path, &label, progress).await?;
+    verify_checksum(&patch_path, &delta.sha256)?;
+
+    let old = fs::read(current_exe)?;
+    let patch = fs::read(&patch_path)?;
+    let max_len = delta.binary_size.map_or(MAX_NEW_LEN, |size| {
+        usize::try_from(size).unwrap_or(usize::MAX)
+    });
+    let new = apply_patch(&old, &patch, max_len)?;
+    let patched_path = temp_dir.join("inline.patched");
+    fs::write(&patched_path, new)?;
+    verify_checksum(&patched_path, &delta.binary_sha256)?;
+    Ok(patched_path)
+}
+
+fn verify_checksum(path: &Path, expected: &str) -> Result<(), UpdateError> {
+    let actual = sha256_file(path)?;
+    let expected = expected.trim().to_ascii_lowercase();
+    if actual != expected {
+        return Err(UpdateError::ChecksumMismatch { expected, actual });
+    }
+    Ok(())
+}
+
+/// Streams `url` to `path`, retrying with backoff and resuming partial
+/// downloads when the server honors `Range`.
+async fn download_file(
+    url: &str,
+    path: &Path,
+    label: &str,
+    progress: bool,
+) -> Result<(), UpdateError> {
+    let client = client_info::http_client_builder()?
+        .connect_timeout(Duration::from_secs(15))
+        .read_timeout(Duration::from_secs(DOWNLOAD_READ_TIMEOUT_SECS))
+        .build()?;
+    let mut attempt = 1;
     loop {
-        let read = file.read(&mut buffer)?;
-        if read == 0 {
-            break;
+        match download_attempt(&client, url, path, label, progress).await {
+            Ok(()) => return Ok(()),
+            Err(error) if attempt < DOWNLOAD_ATTEMPTS && is_retryable(&error) => {
+                let delay = download_retry_delay(attempt);
+                if progress {
+                    eprintln!(
+                        "\nDownload interrupted ({error}); retrying in {}s.",
+                        delay.as_secs()
+                    );
+                }
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+async fn download_attempt(
+    client: &reqwest::Client,
+    url: &str,
+    path: &Path,
+    label: &str,
+    progress: bool,
+) -> Result<(), UpdateError> {
+    let resume_from = tokio::fs::metadata(path)
+        .await
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+    let mut request = client.get(url);
+    if resume_from > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={resume_from}-"));
+    }
+    let mut response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(UpdateError::DownloadStatus(status.as_u16()));
+    }
+    let resumed = resume_from > 0 && status == reqwest::StatusCode::PARTIAL_CONTENT;
+    let mut file = if resumed {
+        tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .await?
+    } else {
+        tokio::fs::File::create(path).await?
+    };
+    let mut downloaded = if resumed { resume_from } else { 0 };
+    let total = response.content_length().map(|length| length + downloaded);
+
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await?;
+        downloaded += chunk.len() as u64;
+        if progress {
+            eprint!("\r{}", format_download_progress(label, downloaded, total));
+            let _ = io::stderr().flush();
         }
-        hasher.update(&buffer[..read]);
     }
-    Ok(bytes_to_hex(&hasher.finalize()))
+    file.flush().await?;
+    if progress {
+        eprintln!();
+    }
+    Ok(())
 }
 
-fn bytes_to_hex(bytes: &[u8]) -> String {
-    let mut out = String::with_capacity(bytes.len() * 2);
-    for byte in bytes {
-        let _ = std::fmt::Write::write_fmt(&mut out, format_args!("{:02x}", byte));
+fn is_retryable(error: &UpdateError) -> bool {
+    match error {
+        UpdateError::Http(error) => !error.is_builder() && !error.is_redirect(),
+        UpdateError::DownloadStatus(status) => *status == 408 || *status == 429 || *status >= 500,
+        UpdateError::Io(_) => true,
+        _ => false,
     }
-    out
+}
+
+fn download_retry_delay(attempt: u32) -> Duration {
+    Duration::from_millis(DOWNLOAD_RETRY_BASE_MS << (attempt - 1).min(5))
+}
+
+fn format_download_progress(label: &str, downloaded: u64, total: Option<u64>) -> String {
+    const MB: f64 = 1024.0 * 1024.0;
+    match total.filter(|total| *total > 0) {
+        Some(total) => format!(
+            "Downloading {label}: {:>3}% ({:.1} / {:.1} MB)",
+            (downloaded.min(total) * 100) / total,
+            downloaded as f64 / MB,
+            total as f64 / MB
+        ),
+        None => format!("Downloading {label}: {:.1} MB", downloaded as f64 / MB),
+    }
+}
+
+fn create_temp_dir() -> Result<PathBuf, UpdateError> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let dir = std::env::temp_dir().join(format!("inline-update-{}", timestamp.as_secs()));
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
 }
 
 fn extract_archive(archive_path: &Path, output_dir: &Path) -> Result<(), UpdateError> {
@@ -536,3 +866,64 @@ fn is_cross_device_link(error: &io::Error) -> bool {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_delta_from_the_running_version() {
+        let manifest: UpdateManifest = serde_json::from_str(
+            r#"{
+                "version": "0.7.0",
+                "targets": {
+                    "x86_64-unknown-linux-gnu": {
+                        "url": "https://example.com/inline.tar.gz",
+                        "sha256": "aa",
+                        "deltas": [
+                            {"from": "0.6.1", "url": "https://example.com/a.bsdiff", "sha256": "bb", "binarySha256": "cc"},
+                            {"from": "0.6.2", "url": "https://example.com/b.bsdiff", "sha256": "dd", "binarySha256": "ee"}
+                        ]
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let target = &manifest.targets["x86_64-unknown-linux-gnu"];
+
+        let delta = find_delta(target, &Version::new(0, 6, 2)).unwrap();
+        assert_eq!(delta.url, "https://example.com/b.bsdiff");
+        assert!(find_delta(target, &Version::new(0, 5, 0)).is_none());
+    }
+
+    #[test]
+    fn failed_checks_stretch_the_interval() {
+        let now = 100 * DAY_SECS;
+        let mut state = LocalState {
+            last_update_attempt_at: Some(now - DAY_SECS - 1),
+            ..Default::default()
+        };
+        assert!(check_due(&state, DAY_SECS, now));
+        assert!(!check_due(&state, 7 * DAY_SECS, now));
+
+        state.update_check_failures = 1;
+        assert!(!check_due(&state, DAY_SECS, now));
+        state.last_update_attempt_at = Some(now - 4 * DAY_SECS);
+        state.update_check_failures = 9;
+        assert!(check_due(&state, DAY_SECS, now));
+        assert!(check_due(&LocalState::default(), DAY_SECS, now));
+        assert_eq!(UpdateCheck::Never.interval_secs(), None);
+    }
+
+    #[test]
+    fn retries_back_off_and_skip_client_errors() {
+        assert_eq!(download_retry_delay(1), Duration::from_secs(1));
+        assert_eq!(download_retry_delay(3), Duration::from_secs(4));
+        assert!(is_retryable(&UpdateError::DownloadStatus(503)));
+        assert!(!is_retryable(&UpdateError::DownloadStatus(404)));
+        assert_eq!(
+            format_download_progress("inline v0.7.0", 512 * 1024, Some(2 * 1024 * 1024)),
+            "Downloading inline v0.7.0:  25% (0.5 / 2.0 MB)"
+        );
+    }
+}
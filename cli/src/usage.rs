@@ -0,0 +1,163 @@
+//! `inline usage`: per-chat message counts and attachment bytes for a space,
+//! biggest storage consumers first.
+//!
+//! Bytes come from the media summaries of every message in history (photos at
+//! their largest size, videos, files, voice notes). Link previews aren't
+//! counted. Very long chats stop at the history page cap, so their counts are
+//! lower bounds and marked `complete: false`.
+
+use std::cmp::Reverse;
+
+use inline_protocol::proto;
+use serde::Serialize;
+
+use crate::message_output::message_media_summary;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UsageOutput {
+    pub(crate) space_id: i64,
+    /// Chats scanned, including any that failed.
+    pub(crate) scanned: usize,
+    pub(crate) messages: usize,
+    pub(crate) media_bytes: i64,
+    pub(crate) chats: Vec<ChatUsage>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ChatUsage {
+    pub(crate) chat_id: i64,
+    pub(crate) title: String,
+    pub(crate) messages: usize,
+    /// False when history was cut off at the page cap.
+    pub(crate) complete: bool,
+    pub(crate) media_messages: usize,
+    pub(crate) media_bytes: i64,
+    /// Percent of the space's media bytes.
+    pub(crate) share: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+}
+
+/// Counts one chat's history.
+pub(crate) fn tally_chat(
+    chat_id: i64,
+    title: String,
+    messages: &[proto::Message],
+    complete: bool,
+) -> ChatUsage {
+    let sizes = messages
+        .iter()
+        .filter_map(|message| message_media_summary(message)?.size)
+        .map(i64::from)
+        .collect::<Vec<_>>();
+    ChatUsage {
+        chat_id,
+        title,
+        messages: messages.len(),
+        complete,
+        media_messages: sizes.len(),
+        media_bytes: sizes.iter().sum(),
+        share: 0.0,
+        error: None,
+    }
+}
+
+/// Sorts chats by media bytes (then message count), fills in each share, and
+/// keeps the first `limit`. Totals cover every chat, not just the kept ones.
+/// Chats that failed go last.
+pub(crate) fn build_usage_output(
+    space_id: i64,
+    mut chats: Vec<ChatUsage>,
+    limit: Option<usize>,
+) -> UsageOutput {
+    let scanned = chats.len();
+    let messages = chats.iter().map(|chat| chat.messages).sum();
+    let media_bytes = chats.iter().map(|chat| chat.media_bytes).sum::<i64>();
+    for chat in &mut chats {
+        if media_bytes > 0 {
+            chat.share = (chat.media_bytes as f64 * 1000.0 / media_bytes as f64).round() / 10.0;
+        }
+    }
+    chats.sort_by_key(|chat| {
+        (
+            chat.error.is_some(),
+            Reverse(chat.media_bytes),
+            Reverse(chat.messages),
+            chat.title.clone(),
+        )
+    });
+    if let Some(limit) = limit {
+        chats.truncate(limit);
+    }
+    UsageOutput {
+        space_id,
+        scanned,
+        messages,
+        media_bytes,
+        chats,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(id: i64, size: i32) -> proto::Message {
+        proto::Message {
+            id,
+            media: Some(proto::MessageMedia {
+                media: Some(proto::message_media::Media::Document(
+                    proto::MessageDocument {
+                        document: Some(proto::Document {
+                            size,
+                            ..Default::default()
+                        }),
+                    },
+                )),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn chats_are_ranked_by_media_bytes_with_shares() {
+        let text = proto::Message {
+            id: 3,
+            message: Some("hi".to_string()),
+            ..Default::default()
+        };
+        let design = tally_chat(
+            7,
+            "Design".to_string(),
+            &[document(1, 3000), document(2, 1000), text.clone()],
+            true,
+        );
+        assert_eq!(design.messages, 3);
+        assert_eq!(design.media_messages, 2);
+        assert_eq!(design.media_bytes, 4000);
+
+        let launch = tally_chat(8, "Launch".to_string(), &[document(1, 1000), text], false);
+        let failed = ChatUsage {
+            error: Some("timed out".to_string()),
+            ..tally_chat(9, "Ops".to_string(), &[], true)
+        };
+        let output = build_usage_output(31, vec![launch, failed, design], None);
+        assert_eq!(output.media_bytes, 5000);
+        assert_eq!(output.messages, 5);
+        assert_eq!(
+            output
+                .chats
+                .iter()
+                .map(|chat| (chat.chat_id, chat.share))
+                .collect::<Vec<_>>(),
+            [(7, 80.0), (8, 20.0), (9, 0.0)]
+        );
+
+        let top = build_usage_output(31, output.chats, Some(1));
+        assert_eq!(top.scanned, 3);
+        assert_eq!(top.media_bytes, 5000);
+        assert_eq!(top.chats.len(), 1);
+    }
+}
@@ -5,6 +5,8 @@ use std::path::{Path, PathBuf};
 use crate::dates::parse_relative_time;
 use crate::errors::CliError;
 
+const MAX_SEARCH_CONTEXT: i32 = 50;
+
 pub(crate) fn normalize_search_queries(
     queries: &[String],
 ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
@@ -32,6 +34,30 @@ pub(crate) fn validate_message_limit(
     }
 }
 
+/// `--parallel` for commands that fan requests out.
+pub(crate) fn validate_parallel(value: usize) -> Result<usize, Box<dyn std::error::Error>> {
+    if value == 0 {
+        return Err(CliError::invalid_args("--parallel must be greater than 0").into());
+    }
+    if value > 64 {
+        return Err(CliError::invalid_args("--parallel must be 64 or less").into());
+    }
+    Ok(value)
+}
+
+/// Around-window size for `messages search --context`.
+pub(crate) fn validate_context_size(
+    context: Option<i32>,
+) -> Result<Option<i32>, Box<dyn std::error::Error>> {
+    match context {
+        Some(value) if !(1..=MAX_SEARCH_CONTEXT).contains(&value) => Err(CliError::invalid_args(
+            format!("--context must be between 1 and {MAX_SEARCH_CONTEXT}"),
+        )
+        .into()),
+        value => Ok(value),
+    }
+}
+
 pub(crate) fn validate_table_only_list_flags(
     json: bool,
     ids: bool,
@@ -293,6 +319,15 @@ mod tests {
         assert_eq!(validate_message_limit(None).unwrap(), None);
     }
 
+    #[test]
+    fn search_context_must_be_in_range() {
+        for value in [0, 51] {
+            let err = validate_context_size(Some(value)).unwrap_err();
+            assert!(err.to_string().contains("--context"));
+        }
+        assert_eq!(validate_context_size(Some(3)).unwrap(), Some(3));
+    }
+
     #[test]
     fn table_only_list_flags_are_structured_invalid_args_in_json_mode() {
         for (ids, id) in [(true, false), (false, true), (true, true)] {
@@ -0,0 +1,354 @@
+//! Video probing and `messages send --transcode`.
+//!
+//! Both go through the ffmpeg tools on PATH: `ffprobe` reads the codecs and
+//! size, and `ffmpeg` re-encodes files the apps can't play (or that are over
+//! the upload limit) to H.264/AAC in an mp4. `ffmpeg` also grabs the poster
+//! frame sent as the thumbnail of video uploads.
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use inline_sdk::api::UploadVideoMetadata;
+
+use crate::photos::THUMBNAIL_SIZE;
+
+/// Audio bitrate for transcoded files, in kbit/s.
+const AUDIO_KBPS: u64 = 128;
+
+/// Share of the upload limit a transcode aims for, leaving room for the
+/// container and rate-control overshoot.
+const SIZE_HEADROOM: f64 = 0.9;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct VideoProbe {
+    pub(crate) video_codec: Option<String>,
+    pub(crate) audio_codec: Option<String>,
+    pub(crate) format_name: Option<String>,
+    pub(crate) width: Option<i32>,
+    pub(crate) height: Option<i32>,
+    /// Seconds.
+    pub(crate) duration: Option<f64>,
+}
+
+impl VideoProbe {
+    pub(crate) fn metadata(&self) -> Option<UploadVideoMetadata> {
+        let width = self.width.filter(|width| *width > 0)?;
+        let height = self.height.filter(|height| *height > 0)?;
+        let duration = self.duration?.ceil() as i32;
+        (duration > 0).then_some(UploadVideoMetadata {
+            width,
+            height,
+            duration,
+        })
+    }
+
+    /// Why the Inline apps can't play this file inline, if they can't. They
+    /// play H.264 in an mp4/mov container with AAC or MP3 audio (or none).
+    pub(crate) fn unplayable_reason(&self) -> Option<String> {
+        let video = self.video_codec.as_deref().unwrap_or("unknown");
+        if video != "h264" {
+            return Some(format!("{video} video"));
+        }
+        let format = self.format_name.as_deref().unwrap_or_default();
+        if !format.split(',').any(|name| name == "mp4" || name == "mov") {
+            return Some(format!(
+                "{} container",
+                format.split(',').next().unwrap_or("unknown")
+            ));
+        }
+        match self.audio_codec.as_deref() {
+            None | Some("aac" | "mp3") => None,
+            Some(audio) => Some(format!("{audio} audio")),
+        }
+    }
+}
+
+pub(crate) fn probe_video(path: &Path) -> Option<VideoProbe> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error"])
+        .args([
+            "-show_entries",
+            "stream=codec_type,codec_name,width,height,duration:format=format_name,duration",
+        ])
+        .args(["-of", "json"])
+        .arg(path)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    parse_probe(&output.stdout)
+}
+
+fn parse_probe(stdout: &[u8]) -> Option<VideoProbe> {
+    let parsed: FfprobeOutput = serde_json::from_slice(stdout).ok()?;
+    let stream_of = |kind: &str| {
+        parsed
+            .streams
+            .iter()
+            .find(|stream| stream.codec_type.as_deref() == Some(kind))
+    };
+    let video = stream_of("video")?;
+    let audio = stream_of("audio");
+    let duration = video
+        .duration
+        .as_deref()
+        .or(parsed.format.as_ref()?.duration.as_deref())
+        .and_then(|value| value.parse::<f64>().ok());
+    Some(VideoProbe {
+        video_codec: video.codec_name.clone(),
+        audio_codec: audio.and_then(|stream| stream.codec_name.clone()),
+        format_name: parsed.format.as_ref()?.format_name.clone(),
+        width: video.width,
+        height: video.height,
+        duration,
+    })
+}
+
+/// Re-encodes `path` to an H.264/AAC mp4 in `data_dir` that fits in
+/// `max_bytes`, printing progress to stderr unless `quiet`. The caller owns
+/// the returned file.
+pub(crate) fn transcode_video(
+    path: &Path,
+    probe: &VideoProbe,
+    data_dir: &Path,
+    max_bytes: u64,
+    quiet: bool,
+) -> Result<PathBuf, String> {
+    let stem = path
+        .file_stem()
+        .and_then(|value| value.to_str())
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or("video");
+    fs::create_dir_all(data_dir).map_err(|err| err.to_string())?;
+    let output = data_dir.join(format!("{stem}-{}.mp4", current_epoch_nanos()));
+
+    let mut command = Command::new("ffmpeg");
+    command
+        .args(["-hide_banner", "-loglevel", "error", "-nostats", "-y"])
+        .arg("-i")
+        .arg(path)
+        .args(transcode_args(probe.duration, max_bytes)?)
+        .args(["-progress", "pipe:1"])
+        .arg(&output)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = command
+        .spawn()
+        .map_err(|err| format!("could not run ffmpeg: {err}"))?;
+
+    if let Some(stdout) = child.stdout.take() {
+        let mut last_percent = None;
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let Some(percent) = progress_percent(&line, probe.duration) else {
+                continue;
+            };
+            if !quiet && last_percent != Some(percent) {
+                eprint!("\rTranscoding {}... {percent}%", path.display());
+                let _ = std::io::stderr().flush();
+                last_percent = Some(percent);
+            }
+        }
+        if !quiet && last_percent.is_some() {
+            eprintln!();
+        }
+    }
+
+    let result = child
+        .wait_with_output()
+        .map_err(|err| format!("ffmpeg did not finish: {err}"))?;
+    if !result.status.success() {
+        let _ = fs::remove_file(&output);
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        let reason = stderr.lines().last().unwrap_or("unknown error").trim();
+        return Err(format!("ffmpeg failed: {reason}"));
+    }
+    Ok(output)
+}
+
+/// A JPEG poster frame for the video, or `None` when ffmpeg isn't installed
+/// or can't decode it.
+pub(crate) fn video_thumbnail(path: &Path, duration: i32, data_dir: &Path) -> Option<Vec<u8>> {
+    fs::create_dir_all(data_dir).ok()?;
+    let output = data_dir.join(format!("poster-{}.jpg", current_epoch_nanos()));
+    let result = Command::new("ffmpeg")
+        .args(["-hide_banner", "-loglevel", "error", "-nostats", "-y"])
+        .arg("-ss")
+        .arg(format!("{:.2}", poster_time(duration)))
+        .arg("-i")
+        .arg(path)
+        .args(["-frames:v", "1", "-q:v", "4", "-vf"])
+        .arg(format!(
+            "scale={THUMBNAIL_SIZE}:{THUMBNAIL_SIZE}:force_original_aspect_ratio=decrease"
+        ))
+        .arg(&output)
+        .stdin(Stdio::null())
+        .output();
+    let bytes = result
+        .ok()
+        .filter(|result| result.status.success())
+        .and_then(|_| fs::read(&output).ok())
+        .filter(|bytes| !bytes.is_empty());
+    let _ = fs::remove_file(&output);
+    bytes
+}
+
+/// One second in, past fade-ins and black first frames, or the middle of
+/// clips shorter than two seconds.
+fn poster_time(duration: i32) -> f64 {
+    (f64::from(duration) / 2.0).min(1.0)
+}
+
+/// Encoder arguments. Quality-based (CRF) with a bitrate cap that keeps the
+/// result under `max_bytes` when the duration is known.
+fn transcode_args(duration: Option<f64>, max_bytes: u64) -> Result<Vec<String>, String> {
+    let mut args = [
+        "-map", "0:v:0", "-map", "0:a:0?", "-c:v", "libx264", "-preset", "medium", "-crf", "23",
+        "-pix_fmt", "yuv420p", "-c:a", "aac", "-b:a",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect::<Vec<_>>();
+    args.push(format!("{AUDIO_KBPS}k"));
+    if let Some(duration) = duration.filter(|duration| *duration > 0.0) {
+        let total_kbps = (max_bytes as f64 * 8.0 * SIZE_HEADROOM / duration / 1000.0) as u64;
+        let video_kbps = total_kbps.saturating_sub(AUDIO_KBPS);
+        if video_kbps < 100 {
+            return Err(format!(
+                "a {}s video can't fit in the upload limit at a watchable bitrate",
+                duration.round()
+            ));
+        }
+        args.extend([
+            "-maxrate".to_string(),
+            format!("{video_kbps}k"),
+            "-bufsize".to_string(),
+            format!("{}k", video_kbps * 2),
+        ]);
+    }
+    args.extend(["-movflags".to_string(), "+faststart".to_string()]);
+    Ok(args)
+}
+
+/// Percent done from an `-progress` line (`out_time_us=12345678`).
+fn progress_percent(line: &str, duration: Option<f64>) -> Option<u8> {
+    let duration = duration.filter(|duration| *duration > 0.0)?;
+    let micros = line
+        .strip_prefix("out_time_us=")
+        .or_else(|| line.strip_prefix("out_time_ms="))?
+        .trim()
+        .parse::<f64>()
+        .ok()?;
+    Some((micros / 1_000_000.0 / duration * 100.0).clamp(0.0, 100.0) as u8)
+}
+
+fn current_epoch_nanos() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+}
+
+#[derive(serde::Deserialize)]
+struct FfprobeOutput {
+    #[serde(default)]
+    streams: Vec<FfprobeStream>,
+    format: Option<FfprobeFormat>,
+}
+
+#[derive(serde::Deserialize)]
+struct FfprobeStream {
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    width: Option<i32>,
+    height: Option<i32>,
+    duration: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct FfprobeFormat {
+    format_name: Option<String>,
+    duration: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probes_flag_codecs_and_containers_the_apps_cant_play() {
+        let stdout = br#"{
+            "streams": [
+                {"codec_type": "video", "codec_name": "hevc", "width": 1920, "height": 1080},
+                {"codec_type": "audio", "codec_name": "aac"}
+            ],
+            "format": {"format_name": "mov,mp4,m4a,3gp,3g2,mj2", "duration": "12.4"}
+        }"#;
+        let probe = parse_probe(stdout).unwrap();
+        assert_eq!(probe.unplayable_reason().as_deref(), Some("hevc video"));
+        let metadata = probe.metadata().unwrap();
+        assert_eq!(
+            (metadata.width, metadata.height, metadata.duration),
+            (1920, 1080, 13)
+        );
+
+        let h264 = |format: &str, audio: Option<&str>| VideoProbe {
+            video_codec: Some("h264".to_string()),
+            audio_codec: audio.map(str::to_string),
+            format_name: Some(format.to_string()),
+            ..VideoProbe::default()
+        };
+        assert_eq!(
+            h264("mov,mp4,m4a,3gp,3g2,mj2", Some("aac")).unplayable_reason(),
+            None
+        );
+        assert_eq!(
+            h264("mov,mp4,m4a,3gp,3g2,mj2", None).unplayable_reason(),
+            None
+        );
+        assert_eq!(
+            h264("matroska,webm", Some("aac"))
+                .unplayable_reason()
+                .as_deref(),
+            Some("matroska container")
+        );
+        assert_eq!(
+            h264("mov,mp4,m4a,3gp,3g2,mj2", Some("opus"))
+                .unplayable_reason()
+                .as_deref(),
+            Some("opus audio")
+        );
+        assert!(parse_probe(br#"{"streams": [{"codec_type": "audio"}]}"#).is_none());
+    }
+
+    #[test]
+    fn transcodes_cap_bitrate_to_the_size_limit_and_report_progress() {
+        let args = transcode_args(Some(100.0), 200 * 1024 * 1024).unwrap();
+        let maxrate = args.iter().position(|arg| arg == "-maxrate").unwrap();
+        // 200MiB * 8 * 0.9 / 100s = 15099 kbit/s, minus 128 for audio.
+        assert_eq!(args[maxrate + 1], "14971k");
+        assert!(
+            transcode_args(None, 1)
+                .unwrap()
+                .iter()
+                .all(|arg| arg != "-maxrate")
+        );
+        assert!(transcode_args(Some(36_000.0), 10 * 1024 * 1024).is_err());
+
+        assert_eq!(
+            progress_percent("out_time_us=5000000", Some(10.0)),
+            Some(50)
+        );
+        assert_eq!(progress_percent("out_time_us=N/A", Some(10.0)), None);
+        assert_eq!(progress_percent("frame=12", Some(10.0)), None);
+        assert_eq!(progress_percent("out_time_us=5000000", None), None);
+    }
+
+    #[test]
+    fn poster_frames_skip_the_first_second_of_longer_clips() {
+        assert_eq!(poster_time(30), 1.0);
+        assert_eq!(poster_time(1), 0.5);
+    }
+}
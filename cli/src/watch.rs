@@ -0,0 +1,326 @@
+use inline_protocol::proto;
+use serde::Serialize;
+
+/// Which reactions `inline watch reactions` acts on.
+pub(crate) struct ReactionFilter {
+    pub(crate) chat_id: Option<i64>,
+    pub(crate) emoji: Vec<String>,
+}
+
+impl ReactionFilter {
+    pub(crate) fn matches(&self, reaction: &proto::Reaction) -> bool {
+        if self
+            .chat_id
+            .is_some_and(|chat_id| chat_id != reaction.chat_id)
+        {
+            return false;
+        }
+        self.emoji.is_empty()
+            || self
+                .emoji
+                .iter()
+                .any(|emoji| same_emoji(emoji, &reaction.emoji))
+    }
+}
+
+pub(crate) fn reactions_in_updates(
+    updates: &[proto::Update],
+) -> impl Iterator<Item = &proto::Reaction> {
+    updates
+        .iter()
+        .filter_map(|update| match update.update.as_ref() {
+            Some(proto::update::Update::UpdateReaction(update)) => update.reaction.as_ref(),
+            _ => None,
+        })
+}
+
+/// Which new messages `inline watch messages` acts on.
+pub(crate) struct MessageFilter {
+    pub(crate) peer: Option<proto::InputPeer>,
+    /// Lowercased substrings; a message matches if its text contains any.
+    pub(crate) needles: Vec<String>,
+    pub(crate) include_own: bool,
+}
+
+impl MessageFilter {
+    pub(crate) fn matches(&self, message: &proto::Message) -> bool {
+        if message.out && !self.include_own {
+            return false;
+        }
+        if let Some(peer) = &self.peer
+            && !message_in_peer(message, peer)
+        {
+            return false;
+        }
+        if self.needles.is_empty() {
+            return true;
+        }
+        let text = message
+            .message
+            .as_deref()
+            .unwrap_or_default()
+            .to_lowercase();
+        self.needles.iter().any(|needle| text.contains(needle))
+    }
+}
+
+fn message_in_peer(message: &proto::Message, peer: &proto::InputPeer) -> bool {
+    match &peer.r#type {
+        Some(proto::input_peer::Type::Chat(chat)) => message.chat_id == chat.chat_id,
+        Some(proto::input_peer::Type::User(user)) => matches!(
+            message.peer_id.as_ref().and_then(|peer| peer.r#type.as_ref()),
+            Some(proto::peer::Type::User(peer)) if peer.user_id == user.user_id
+        ),
+        Some(proto::input_peer::Type::Self_(_)) | None => true,
+    }
+}
+
+pub(crate) fn new_messages_in_updates(
+    updates: &[proto::Update],
+) -> impl Iterator<Item = &proto::Message> {
+    updates
+        .iter()
+        .filter_map(|update| match update.update.as_ref() {
+            Some(proto::update::Update::NewMessage(update)) => update.message.as_ref(),
+            _ => None,
+        })
+}
+
+/// Placeholder names and values available to `--exec` for a message.
+pub(crate) fn message_hook_vars(message: &proto::Message) -> Vec<(&'static str, String)> {
+    vec![
+        ("chat_id", message.chat_id.to_string()),
+        ("message_id", message.id.to_string()),
+        ("from_id", message.from_id.to_string()),
+        ("text", message.message.clone().unwrap_or_default()),
+        ("date", message.date.to_string()),
+    ]
+}
+
+/// Emoji typed in a terminal often lack (or add) the U+FE0F presentation
+/// selector, so `✅` and `✅\u{fe0f}` are treated as the same reaction.
+fn same_emoji(left: &str, right: &str) -> bool {
+    let strip = |value: &str| value.replace('\u{fe0f}', "");
+    strip(left) == strip(right)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ReactionEvent {
+    pub(crate) chat_id: i64,
+    pub(crate) message_id: i64,
+    pub(crate) user_id: i64,
+    pub(crate) emoji: String,
+    pub(crate) date: i64,
+}
+
+impl From<&proto::Reaction> for ReactionEvent {
+    fn from(reaction: &proto::Reaction) -> Self {
+        Self {
+            chat_id: reaction.chat_id,
+            message_id: reaction.message_id,
+            user_id: reaction.user_id,
+            emoji: reaction.emoji.clone(),
+            date: reaction.date,
+        }
+    }
+}
+
+impl ReactionEvent {
+    /// Placeholder names and values available to `--exec`.
+    pub(crate) fn hook_vars(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("chat_id", self.chat_id.to_string()),
+            ("message_id", self.message_id.to_string()),
+            ("user_id", self.user_id.to_string()),
+            ("emoji", self.emoji.clone()),
+            ("date", self.date.to_string()),
+        ]
+    }
+}
+
+/// Shell that runs `--exec` hooks.
+#[derive(Clone, Copy)]
+enum HookShell {
+    Sh,
+    Cmd,
+}
+
+/// Builds the shell command for an `--exec` hook. Every value is exported as
+/// `INLINE_<NAME>` so scripts don't have to parse arguments, and `{name}`
+/// placeholders are rendered so values are never parsed as shell syntax.
+/// In read-only mode the hook gets `INLINE_READ_ONLY=1`, so `inline` commands
+/// it runs stay read-only too.
+pub(crate) fn hook_command(
+    template: &str,
+    vars: &[(&'static str, String)],
+    read_only: bool,
+) -> tokio::process::Command {
+    let mut command = if cfg!(windows) {
+        let mut command = tokio::process::Command::new("cmd");
+        command
+            .args(["/V:ON", "/C"])
+            .arg(render_hook(template, vars, HookShell::Cmd));
+        command
+    } else {
+        let mut command = tokio::process::Command::new("sh");
+        command
+            .arg("-c")
+            .arg(render_hook(template, vars, HookShell::Sh));
+        command
+    };
+    for (name, value) in vars {
+        command.env(hook_env_name(name), value);
+    }
+    if read_only {
+        command.env("INLINE_READ_ONLY", "1");
+    }
+    command
+}
+
+fn hook_env_name(name: &str) -> String {
+    format!("INLINE_{}", name.to_ascii_uppercase())
+}
+
+/// Substitutes placeholders in one pass so values can't introduce new ones.
+/// Unknown `{...}` sequences are left untouched. `sh` gets quoted values;
+/// `cmd` has no quoting that keeps `&`, `|`, or `%` inert, so it gets
+/// delayed `!INLINE_<NAME>!` references, which it expands only after parsing
+/// the command line.
+fn render_hook(template: &str, vars: &[(&'static str, String)], shell: HookShell) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let tail = &rest[start + 1..];
+        let var = tail.find('}').and_then(|end| {
+            vars.iter()
+                .find(|(name, _)| *name == &tail[..end])
+                .map(|(name, value)| (end, name, value))
+        });
+        match var {
+            Some((end, name, value)) => {
+                let value = match shell {
+                    HookShell::Sh => shell_quote(value),
+                    HookShell::Cmd => format!("\"!{}!\"", hook_env_name(name)),
+                };
+                rendered.push_str(&value);
+                rest = &tail[end + 1..];
+            }
+            None => {
+                rendered.push('{');
+                rest = tail;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+pub(crate) fn shell_quote(value: &str) -> String {
+    let is_plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.' | '/' | ':'));
+    if is_plain {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reaction(chat_id: i64, emoji: &str) -> proto::Reaction {
+        proto::Reaction {
+            emoji: emoji.to_string(),
+            user_id: 42,
+            message_id: 9,
+            chat_id,
+            date: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn reaction_filter_checks_chat_and_emoji() {
+        let filter = ReactionFilter {
+            chat_id: Some(123),
+            emoji: vec!["✅".to_string()],
+        };
+
+        assert!(filter.matches(&reaction(123, "✅")));
+        assert!(filter.matches(&reaction(123, "✅\u{fe0f}")));
+        assert!(!filter.matches(&reaction(123, "👍")));
+        assert!(!filter.matches(&reaction(124, "✅")));
+        assert!(
+            ReactionFilter {
+                chat_id: None,
+                emoji: Vec::new(),
+            }
+            .matches(&reaction(1, "👍"))
+        );
+    }
+
+    #[test]
+    fn message_filter_checks_peer_text_and_own_messages() {
+        let message = |chat_id: i64, text: &str, out: bool| proto::Message {
+            id: 1,
+            chat_id,
+            message: Some(text.to_string()),
+            out,
+            ..Default::default()
+        };
+        let filter = MessageFilter {
+            peer: Some(proto::InputPeer {
+                r#type: Some(proto::input_peer::Type::Chat(proto::InputPeerChat {
+                    chat_id: 7,
+                })),
+            }),
+            needles: vec!["deploy failed".to_string()],
+            include_own: false,
+        };
+
+        assert!(filter.matches(&message(7, "Deploy FAILED on api", false)));
+        assert!(!filter.matches(&message(7, "deploy ok", false)));
+        assert!(!filter.matches(&message(8, "deploy failed", false)));
+        assert!(!filter.matches(&message(7, "deploy failed", true)));
+    }
+
+    #[test]
+    fn hook_placeholders_keep_shell_metacharacters_inert() {
+        let event = ReactionEvent::from(&reaction(123, "it's {date} & del x | y > %PATH%"));
+        let vars = event.hook_vars();
+        let template = "approve.sh {message_id} {emoji} {unknown}";
+
+        assert_eq!(
+            render_hook(template, &vars, HookShell::Sh),
+            r"approve.sh 9 'it'\''s {date} & del x | y > %PATH%' {unknown}"
+        );
+        assert_eq!(
+            render_hook(template, &vars, HookShell::Cmd),
+            r#"approve.sh "!INLINE_MESSAGE_ID!" "!INLINE_EMOJI!" {unknown}"#
+        );
+    }
+
+    #[test]
+    fn hooks_inherit_read_only_mode() {
+        let vars = [("chat_id", "7".to_string())];
+        let env = |read_only| {
+            hook_command("true", &vars, read_only)
+                .as_std()
+                .get_envs()
+                .map(|(name, value)| (name.to_owned(), value.map(ToOwned::to_owned)))
+                .collect::<Vec<_>>()
+        };
+        let read_only = (
+            std::ffi::OsString::from("INLINE_READ_ONLY"),
+            Some(std::ffi::OsString::from("1")),
+        );
+
+        assert!(env(true).contains(&read_only));
+        assert!(!env(false).iter().any(|(name, _)| *name == read_only.0));
+        assert!(env(false).iter().any(|(name, _)| name == "INLINE_CHAT_ID"));
+    }
+}
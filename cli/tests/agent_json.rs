@@ -37,6 +37,8 @@ fn run_inline_isolated(
         .env("INLINE_DATA_DIR", root)
         .env("INLINE_SECRETS_PATH", secrets)
         .env("INLINE_STATE_PATH", state)
+        .env("INLINE_STATE_KEY", "none")
+        .env("INLINE_CONFIG_PATH", root.join("config.toml"))
         .env("INLINE_API_BASE_URL", "http://127.0.0.1:9/v1")
         .env("INLINE_REALTIME_URL", "ws://127.0.0.1:9/realtime")
         .output()
@@ -476,4 +478,77 @@ fn destructive_json_confirmation_validates_before_auth_lookup() {
         "confirmation_required",
         "Confirmation required",
     );
+
+    let mut send_to_many = vec!["--json", "--compact", "messages", "send", "--text", "hi"];
+    for to in ["chat:1", "chat:2", "chat:3", "chat:4", "chat:5", "chat:6"] {
+        send_to_many.extend(["--to", to]);
+    }
+    assert_json_error_before_auth(
+        "send-to-many-confirmation",
+        &send_to_many,
+        "confirmation_required",
+        "Confirmation required",
+    );
+}
+
+#[test]
+fn read_only_mode_rejects_mutations_before_auth_lookup() {
+    assert_json_error_before_auth(
+        "read-only-send",
+        &[
+            "--json",
+            "--compact",
+            "--read-only",
+            "messages",
+            "send",
+            "--chat-id",
+            "1",
+            "--text",
+            "hi",
+        ],
+        "read_only",
+        "messages send",
+    );
+
+    assert_json_error_before_auth(
+        "read-only-config-set",
+        &[
+            "--json",
+            "--compact",
+            "--read-only",
+            "config",
+            "set",
+            "output",
+            "json",
+        ],
+        "read_only",
+        "config set",
+    );
+}
+
+#[test]
+fn config_file_round_trips_and_reports_invalid_values() {
+    let (root, secrets, state) = isolated_paths("config-file");
+
+    let output = run_inline_isolated(
+        &["config", "set", "output", "json"],
+        &root,
+        &secrets,
+        &state,
+    );
+    assert!(output.status.success());
+
+    // `output = "json"` makes JSON the default without --json.
+    let output = run_inline_isolated(&["config", "get", "output"], &root, &secrets, &state);
+    assert!(output.status.success());
+    let payload: Value = serde_json::from_slice(&output.stdout).expect("stdout is json");
+    assert_eq!(payload["value"], "json");
+    assert_eq!(payload["source"], "file");
+
+    std::fs::write(root.join("config.toml"), "color = \"purple\"\n").unwrap();
+    let output = run_inline_isolated(&["--json", "config", "list"], &root, &secrets, &state);
+    assert!(!output.status.success());
+    assert_eq!(stderr_json(&output)["error"]["code"], "invalid_config");
+
+    let _ = std::fs::remove_dir_all(&root);
 }
@@ -0,0 +1,333 @@
+//! Golden-output tests for the human tables in `output.rs`.
+//!
+//! Each case runs the real binary against fixture payloads and compares
+//! stdout with `tests/snapshots/<name>.txt`, so a change to a table's columns,
+//! widths, or truncation shows up as a diff that a reviewer has to accept.
+//! Run with `INLINE_UPDATE_SNAPSHOTS=1` to rewrite the files, then review them
+//! with `git diff`.
+
+mod support;
+
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use inline_protocol::proto;
+use support::MockBackend;
+
+const HOUR: i64 = 60 * 60;
+const DAY: i64 = 24 * HOUR;
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+fn assert_snapshot(name: &str, output: &std::process::Output) {
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let actual = String::from_utf8(output.stdout.clone()).expect("stdout is UTF-8");
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{name}.txt"));
+    if std::env::var_os("INLINE_UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "missing snapshot {}; run with INLINE_UPDATE_SNAPSHOTS=1 to create it:\n{actual}",
+            path.display()
+        )
+    });
+    assert!(
+        actual == expected,
+        "{name} output changed; run with INLINE_UPDATE_SNAPSHOTS=1 to accept it.\n--- expected\n{expected}\n--- actual\n{actual}"
+    );
+}
+
+fn run(backend: &MockBackend, args: &[&str]) -> std::process::Output {
+    backend.run_inline_with_env(
+        args,
+        &[
+            ("TZ", OsStr::new("UTC")),
+            ("COLUMNS", OsStr::new("100")),
+            ("NO_COLOR", OsStr::new("1")),
+        ],
+    )
+}
+
+fn user(id: i64, first_name: &str, last_name: Option<&str>, username: Option<&str>) -> proto::User {
+    proto::User {
+        id,
+        first_name: Some(first_name.to_string()),
+        last_name: last_name.map(str::to_string),
+        username: username.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+fn users() -> Vec<proto::User> {
+    vec![
+        user(1, "Mo", Some("Rahman"), Some("mo")),
+        user(4, "Ava", None, Some("ava")),
+        user(5, "Zoë", Some("Łukasiewicz"), None),
+        user(
+            6,
+            "\u{5c71}\u{7530}",
+            Some("\u{592a}\u{90ce}"),
+            Some("taro"),
+        ),
+        user(7, "\u{05e9}\u{05e8}\u{05d4}", None, None),
+        proto::User {
+            id: 8,
+            email: Some("pending@example.com".to_string()),
+            pending_setup: Some(true),
+            ..Default::default()
+        },
+    ]
+}
+
+fn chat_peer(chat_id: i64) -> proto::Peer {
+    proto::Peer {
+        r#type: Some(proto::peer::Type::Chat(proto::PeerChat { chat_id })),
+    }
+}
+
+fn user_peer(user_id: i64) -> proto::Peer {
+    proto::Peer {
+        r#type: Some(proto::peer::Type::User(proto::PeerUser { user_id })),
+    }
+}
+
+fn message(id: i64, chat_id: i64, from_id: i64, text: &str, date: i64) -> proto::Message {
+    proto::Message {
+        id,
+        chat_id,
+        from_id,
+        peer_id: Some(chat_peer(chat_id)),
+        message: Some(text.to_string()),
+        date,
+        ..Default::default()
+    }
+}
+
+fn chat(id: i64, title: &str, last_msg_id: Option<i64>) -> proto::Chat {
+    proto::Chat {
+        id,
+        title: title.to_string(),
+        space_id: Some(31),
+        peer_id: Some(chat_peer(id)),
+        last_msg_id,
+        ..Default::default()
+    }
+}
+
+fn chats_result(now: i64) -> proto::GetChatsResult {
+    let dm = proto::Chat {
+        id: 12,
+        title: String::new(),
+        space_id: None,
+        peer_id: Some(user_peer(6)),
+        last_msg_id: Some(40),
+        ..Default::default()
+    };
+    proto::GetChatsResult {
+        chats: vec![
+            chat(7, "Design", Some(10)),
+            chat(
+                8,
+                "Launch planning for the spring release and everything that comes after it",
+                Some(20),
+            ),
+            chat(
+                9,
+                "\u{1F680} Ships \u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}",
+                Some(30),
+            ),
+            chat(10, "Quiet", None),
+            dm,
+        ],
+        dialogs: vec![
+            proto::Dialog {
+                chat_id: Some(7),
+                unread_count: Some(3),
+                ..Default::default()
+            },
+            proto::Dialog {
+                chat_id: Some(9),
+                unread_count: Some(120),
+                notification_settings: Some(proto::DialogNotificationSettings {
+                    mode: Some(proto::dialog_notification_settings::Mode::None as i32),
+                }),
+                ..Default::default()
+            },
+        ],
+        messages: vec![
+            message(10, 7, 4, "Mockups are in Figma", now - 10 * 60),
+            message(
+                20,
+                8,
+                5,
+                "Here's the long version of the plan: we freeze on Monday, cut the branch Tuesday, and ship Thursday if QA is happy",
+                now - 2 * HOUR - 60,
+            ),
+            message(
+                30,
+                9,
+                7,
+                "\u{05e9}\u{05dc}\u{05d5}\u{05dd} \u{1F44B}\u{1F3FD}",
+                now - 3 * DAY - HOUR,
+            ),
+            proto::Message {
+                peer_id: Some(user_peer(6)),
+                ..message(
+                    40,
+                    12,
+                    6,
+                    "\u{4e86}\u{89e3}\u{3057}\u{307e}\u{3057}\u{305f}",
+                    now - 30,
+                )
+            },
+        ],
+        spaces: vec![proto::Space {
+            id: 31,
+            name: "Acme".to_string(),
+            ..Default::default()
+        }],
+        users: users(),
+    }
+}
+
+fn backend() -> MockBackend {
+    let now = now();
+    MockBackend::builder()
+        .rpc(move |input| match input {
+            proto::rpc_call::Input::GetChats(_) => {
+                Ok(proto::rpc_result::Result::GetChats(chats_result(now)))
+            }
+            proto::rpc_call::Input::GetChatHistory(input) => Ok(
+                proto::rpc_result::Result::GetChatHistory(proto::GetChatHistoryResult {
+                    messages: match input.offset_id {
+                        None => vec![
+                            message(3, 7, 1, "Shipped \u{2705}", now - 10 * 60 - 30),
+                            message(
+                                2,
+                                7,
+                                6,
+                                "\u{5c71}\u{7530}\u{3067}\u{3059}\u{3002}\u{3088}\u{308d}\u{3057}\u{304f}\u{304a}\u{9858}\u{3044}\u{3057}\u{307e}\u{3059}",
+                                now - 3 * HOUR - 120,
+                            ),
+                            message(
+                                1,
+                                7,
+                                4,
+                                "A long message that goes on well past the width of the text column so the table has to cut it off somewhere sensible",
+                                now - 2 * DAY - 600,
+                            ),
+                        ],
+                        Some(_) => Vec::new(),
+                    },
+                }),
+            ),
+            proto::rpc_call::Input::GetSpaceMembers(_) => Ok(
+                proto::rpc_result::Result::GetSpaceMembers(proto::GetSpaceMembersResult {
+                    members: [(1, 1, proto::member::Role::Owner), (2, 4, proto::member::Role::Admin), (3, 6, proto::member::Role::Member), (4, 8, proto::member::Role::Member)]
+                        .map(|(id, user_id, role)| proto::Member {
+                            id,
+                            space_id: 31,
+                            user_id,
+                            role: Some(role as i32),
+                            date: now - 3 * 7 * DAY - DAY,
+                            ..Default::default()
+                        })
+                        .to_vec(),
+                    users: users(),
+                }),
+            ),
+            proto::rpc_call::Input::GetChatParticipants(_) => Ok(
+                proto::rpc_result::Result::GetChatParticipants(proto::GetChatParticipantsResult {
+                    participants: [1, 5, 7]
+                        .map(|user_id| proto::ChatParticipant {
+                            user_id,
+                            date: now - 3 * 7 * DAY - DAY,
+                        })
+                        .to_vec(),
+                    users: users(),
+                    ..Default::default()
+                }),
+            ),
+            other => Err(format!("unexpected call: {other:?}")),
+        })
+        .start()
+}
+
+fn empty_backend() -> MockBackend {
+    MockBackend::builder()
+        .rpc(|input| match input {
+            proto::rpc_call::Input::GetChats(_) => Ok(proto::rpc_result::Result::GetChats(
+                proto::GetChatsResult::default(),
+            )),
+            proto::rpc_call::Input::GetChatHistory(_) => Ok(
+                proto::rpc_result::Result::GetChatHistory(proto::GetChatHistoryResult::default()),
+            ),
+            other => Err(format!("unexpected call: {other:?}")),
+        })
+        .start()
+}
+
+#[test]
+fn chats_list_table() {
+    let backend = backend();
+    assert_snapshot("chats_list", &run(&backend, &["chats", "list"]));
+    assert_snapshot(
+        "chats_list_narrow",
+        &run(&backend, &["chats", "list", "--width", "60"]),
+    );
+    assert_snapshot(
+        "chats_list_empty",
+        &run(&empty_backend(), &["chats", "list"]),
+    );
+}
+
+#[test]
+fn users_list_table() {
+    let backend = backend();
+    assert_snapshot("users_list", &run(&backend, &["users", "list"]));
+    assert_snapshot(
+        "users_list_empty",
+        &run(&empty_backend(), &["users", "list"]),
+    );
+}
+
+#[test]
+fn messages_list_table() {
+    let backend = backend();
+    assert_snapshot(
+        "messages_list",
+        &run(&backend, &["messages", "list", "--chat-id", "7"]),
+    );
+    assert_snapshot(
+        "messages_list_empty",
+        &run(&empty_backend(), &["messages", "list", "--chat-id", "7"]),
+    );
+}
+
+#[test]
+fn members_and_participants_tables() {
+    let backend = backend();
+    assert_snapshot(
+        "spaces_members",
+        &run(&backend, &["spaces", "members", "--space-id", "31"]),
+    );
+    assert_snapshot(
+        "chats_participants",
+        &run(&backend, &["chats", "participants", "--chat-id", "7"]),
+    );
+}
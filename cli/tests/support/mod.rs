@@ -0,0 +1,453 @@
+//! Mock Inline backend for end-to-end CLI tests.
+//!
+//! Serves the realtime websocket (protobuf `ClientMessage`/`ServerProtocolMessage`
+//! framing) and a minimal HTTP/1.1 API on loopback ports, records what the CLI
+//! sent, and runs the real `inline` binary against it with an isolated data dir.
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::sync::{Arc, Mutex};
+
+use futures_util::{SinkExt, StreamExt};
+use inline_protocol::proto;
+use prost::Message as _;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+pub const TEST_TOKEN: &str = "test-token";
+/// RPC handler error that is sent with status 501, as a server that doesn't
+/// implement the method would.
+pub const NOT_IMPLEMENTED: &str = "Not implemented";
+/// Fixed state key, so runs on macOS never touch the login Keychain.
+pub const TEST_STATE_KEY: &str = "5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a";
+
+type RpcHandler =
+    dyn Fn(&proto::rpc_call::Input) -> Result<proto::rpc_result::Result, String> + Send + Sync;
+
+#[derive(Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub content_type: String,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn json(body: serde_json::Value) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json".to_string(),
+            body: body.to_string().into_bytes(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordedHttpRequest {
+    pub method: String,
+    pub path: String,
+    pub body: Vec<u8>,
+}
+
+#[derive(Default)]
+pub struct MockBackendBuilder {
+    rpc: Option<Arc<RpcHandler>>,
+    routes: HashMap<String, HttpResponse>,
+    pushed_updates: Vec<proto::Update>,
+    connection_error: Option<proto::connection_error::Reason>,
+}
+
+impl MockBackendBuilder {
+    /// Answers every realtime RPC. Return `Err(message)` to send an `RpcError`
+    /// (status 501 for [`NOT_IMPLEMENTED`], 400 otherwise).
+    pub fn rpc(
+        mut self,
+        handler: impl Fn(&proto::rpc_call::Input) -> Result<proto::rpc_result::Result, String>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        self.rpc = Some(Arc::new(handler));
+        self
+    }
+
+    /// Serves `response` for requests to `path` (any method). Unknown paths get 404.
+    pub fn route(mut self, path: &str, response: HttpResponse) -> Self {
+        self.routes.insert(path.to_string(), response);
+        self
+    }
+
+    /// Pushes `updates` to every realtime connection shortly after it opens,
+    /// leaving the CLI time to subscribe first.
+    pub fn push_updates(mut self, updates: Vec<proto::Update>) -> Self {
+        self.pushed_updates = updates;
+        self
+    }
+
+    /// Answers every `ConnectionInit` with a `ConnectionError`, as the server
+    /// does for expired or revoked tokens.
+    pub fn reject_connections(mut self, reason: proto::connection_error::Reason) -> Self {
+        self.connection_error = Some(reason);
+        self
+    }
+
+    pub fn start(self) -> MockBackend {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()
+            .expect("mock backend runtime");
+        let rpc_calls = Arc::new(Mutex::new(Vec::new()));
+        let tokens = Arc::new(Mutex::new(Vec::new()));
+        let http_requests = Arc::new(Mutex::new(Vec::new()));
+
+        let (realtime_listener, http_listener) = runtime.block_on(async {
+            (
+                TcpListener::bind("127.0.0.1:0")
+                    .await
+                    .expect("bind realtime"),
+                TcpListener::bind("127.0.0.1:0").await.expect("bind http"),
+            )
+        });
+        let realtime_addr = realtime_listener.local_addr().unwrap();
+        let http_addr = http_listener.local_addr().unwrap();
+
+        let handler = self.rpc.unwrap_or_else(|| {
+            Arc::new(|_: &proto::rpc_call::Input| Err("no rpc handler".to_string()))
+        });
+        runtime.spawn(serve_realtime(
+            realtime_listener,
+            handler,
+            Arc::new(self.pushed_updates),
+            self.connection_error,
+            rpc_calls.clone(),
+            tokens.clone(),
+        ));
+        runtime.spawn(serve_http(
+            http_listener,
+            Arc::new(self.routes),
+            http_requests.clone(),
+        ));
+
+        let root = std::env::temp_dir().join(format!(
+            "inline-e2e-{}-{}",
+            std::process::id(),
+            realtime_addr.port()
+        ));
+        MockBackend {
+            realtime_url: format!("ws://{realtime_addr}/realtime"),
+            http_base_url: format!("http://{http_addr}"),
+            root,
+            rpc_calls,
+            tokens,
+            http_requests,
+            _runtime: runtime,
+        }
+    }
+}
+
+pub struct MockBackend {
+    pub realtime_url: String,
+    pub http_base_url: String,
+    pub root: PathBuf,
+    rpc_calls: Arc<Mutex<Vec<proto::RpcCall>>>,
+    tokens: Arc<Mutex<Vec<String>>>,
+    http_requests: Arc<Mutex<Vec<RecordedHttpRequest>>>,
+    _runtime: tokio::runtime::Runtime,
+}
+
+impl MockBackend {
+    pub fn builder() -> MockBackendBuilder {
+        MockBackendBuilder::default()
+    }
+
+    /// Runs the `inline` binary against this backend, authenticated with
+    /// `INLINE_TOKEN` and isolated from the user's real data and config.
+    pub fn run_inline(&self, args: &[&str]) -> Output {
+        self.inline_command(args)
+            .output()
+            .expect("run inline binary")
+    }
+
+    pub fn run_inline_with_env(&self, args: &[&str], env: &[(&str, &OsStr)]) -> Output {
+        self.inline_command(args)
+            .envs(env.iter().copied())
+            .output()
+            .expect("run inline binary")
+    }
+
+    fn inline_command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_inline"));
+        command
+            .args(args)
+            .env("INLINE_TOKEN", TEST_TOKEN)
+            .env("INLINE_DATA_DIR", &self.root)
+            .env("INLINE_SECRETS_PATH", self.root.join("secrets.json"))
+            .env("INLINE_STATE_PATH", self.root.join("state.json"))
+            .env("INLINE_STATE_KEY", TEST_STATE_KEY)
+            .env("INLINE_CONFIG_PATH", self.root.join("config.toml"))
+            .env("INLINE_API_BASE_URL", format!("{}/v1", self.http_base_url))
+            .env("INLINE_REALTIME_URL", &self.realtime_url)
+            .env(
+                "INLINE_RELEASE_MANIFEST_URL",
+                format!("{}/cli/manifest.json", self.http_base_url),
+            )
+            .env_remove("INLINE_PROFILE")
+            .env_remove("INLINE_OUTPUT")
+            .env_remove("INLINE_READ_ONLY")
+            .env_remove("INLINE_PROXY")
+            .env_remove("INLINE_CA_BUNDLE")
+            .env_remove("INLINE_TLS_PINS")
+            .env_remove("INLINE_SEND_LIMIT")
+            .env_remove("INLINE_UPDATE_CHECK")
+            .env_remove("INLINE_RELEASE_PUBLIC_KEY")
+            .env_remove("HTTPS_PROXY")
+            .env_remove("https_proxy")
+            .env_remove("ALL_PROXY")
+            .env_remove("all_proxy");
+        command
+    }
+
+    pub fn rpc_calls(&self) -> Vec<proto::RpcCall> {
+        self.rpc_calls.lock().unwrap().clone()
+    }
+
+    pub fn connection_tokens(&self) -> Vec<String> {
+        self.tokens.lock().unwrap().clone()
+    }
+
+    pub fn http_requests(&self) -> Vec<RecordedHttpRequest> {
+        self.http_requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockBackend {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+async fn serve_realtime(
+    listener: TcpListener,
+    handler: Arc<RpcHandler>,
+    pushed_updates: Arc<Vec<proto::Update>>,
+    connection_error: Option<proto::connection_error::Reason>,
+    calls: Arc<Mutex<Vec<proto::RpcCall>>>,
+    tokens: Arc<Mutex<Vec<String>>>,
+) {
+    while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(handle_realtime_connection(
+            stream,
+            handler.clone(),
+            pushed_updates.clone(),
+            connection_error,
+            calls.clone(),
+            tokens.clone(),
+        ));
+    }
+}
+
+async fn handle_realtime_connection(
+    stream: TcpStream,
+    handler: Arc<RpcHandler>,
+    pushed_updates: Arc<Vec<proto::Update>>,
+    connection_error: Option<proto::connection_error::Reason>,
+    calls: Arc<Mutex<Vec<proto::RpcCall>>>,
+    tokens: Arc<Mutex<Vec<String>>>,
+) {
+    let Ok(mut ws) = tokio_tungstenite::accept_async(stream).await else {
+        return;
+    };
+    let mut next_id = 1u64;
+    let push_at = tokio::time::sleep(std::time::Duration::from_secs(3600));
+    tokio::pin!(push_at);
+    let mut push_pending = false;
+    loop {
+        let frame = tokio::select! {
+            frame = ws.next() => frame,
+            () = &mut push_at, if push_pending => {
+                push_pending = false;
+                let reply = proto::ServerProtocolMessage {
+                    id: next_id,
+                    body: Some(proto::server_protocol_message::Body::Message(proto::ServerMessage {
+                        payload: Some(proto::server_message::Payload::Update(proto::UpdatesPayload {
+                            updates: pushed_updates.to_vec(),
+                        })),
+                    })),
+                };
+                next_id += 1;
+                if ws
+                    .send(WsMessage::Binary(reply.encode_to_vec().into()))
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+                continue;
+            }
+        };
+        let Some(Ok(frame)) = frame else {
+            return;
+        };
+        let WsMessage::Binary(bytes) = frame else {
+            continue;
+        };
+        let Ok(message) = proto::ClientMessage::decode(&*bytes) else {
+            return;
+        };
+        let body = match message.body {
+            Some(proto::client_message::Body::ConnectionInit(init)) => {
+                tokens.lock().unwrap().push(init.token);
+                if let Some(reason) = connection_error {
+                    let reply = proto::ServerProtocolMessage {
+                        id: next_id,
+                        body: Some(proto::server_protocol_message::Body::ConnectionError(
+                            proto::ConnectionError {
+                                reason: reason as i32,
+                            },
+                        )),
+                    };
+                    let _ = ws
+                        .send(WsMessage::Binary(reply.encode_to_vec().into()))
+                        .await;
+                    return;
+                }
+                if !pushed_updates.is_empty() {
+                    push_at
+                        .as_mut()
+                        .reset(tokio::time::Instant::now() + std::time::Duration::from_millis(300));
+                    push_pending = true;
+                }
+                proto::server_protocol_message::Body::ConnectionOpen(proto::ConnectionOpen {})
+            }
+            Some(proto::client_message::Body::RpcCall(call)) => {
+                calls.lock().unwrap().push(call.clone());
+                let result = match call.input.as_ref() {
+                    Some(input) => handler(input),
+                    None => Err("missing rpc input".to_string()),
+                };
+                match result {
+                    Ok(result) => {
+                        proto::server_protocol_message::Body::RpcResult(proto::RpcResult {
+                            req_msg_id: message.id,
+                            result: Some(result),
+                        })
+                    }
+                    Err(error) if error == NOT_IMPLEMENTED => {
+                        proto::server_protocol_message::Body::RpcError(proto::RpcError {
+                            req_msg_id: message.id,
+                            error_code: proto::rpc_error::Code::Unknown as i32,
+                            message: error,
+                            code: 501,
+                        })
+                    }
+                    Err(error) => proto::server_protocol_message::Body::RpcError(proto::RpcError {
+                        req_msg_id: message.id,
+                        error_code: proto::rpc_error::Code::BadRequest as i32,
+                        message: error,
+                        code: 400,
+                    }),
+                }
+            }
+            Some(proto::client_message::Body::Ping(ping)) => {
+                proto::server_protocol_message::Body::Pong(proto::Pong { nonce: ping.nonce })
+            }
+            Some(proto::client_message::Body::Ack(_)) | None => continue,
+        };
+        let reply = proto::ServerProtocolMessage {
+            id: next_id,
+            body: Some(body),
+        };
+        next_id += 1;
+        if ws
+            .send(WsMessage::Binary(reply.encode_to_vec().into()))
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+}
+
+async fn serve_http(
+    listener: TcpListener,
+    routes: Arc<HashMap<String, HttpResponse>>,
+    requests: Arc<Mutex<Vec<RecordedHttpRequest>>>,
+) {
+    while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(handle_http_connection(
+            stream,
+            routes.clone(),
+            requests.clone(),
+        ));
+    }
+}
+
+async fn handle_http_connection(
+    mut stream: TcpStream,
+    routes: Arc<HashMap<String, HttpResponse>>,
+    requests: Arc<Mutex<Vec<RecordedHttpRequest>>>,
+) {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        let Ok(read) = stream.read(&mut chunk).await else {
+            return;
+        };
+        if read == 0 {
+            return;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(index) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break index + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let target = request_line.next().unwrap_or_default();
+    let path = target.split('?').next().unwrap_or_default().to_string();
+    let content_length = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = buffer[header_end..].to_vec();
+    while body.len() < content_length {
+        let Ok(read) = stream.read(&mut chunk).await else {
+            return;
+        };
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..read]);
+    }
+
+    requests.lock().unwrap().push(RecordedHttpRequest {
+        method,
+        path: path.clone(),
+        body,
+    });
+
+    let response = routes.get(&path).cloned().unwrap_or(HttpResponse {
+        status: 404,
+        content_type: "text/plain".to_string(),
+        body: b"not found".to_vec(),
+    });
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        if response.status < 400 { "OK" } else { "Error" },
+        response.content_type,
+        response.body.len()
+    );
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(&response.body).await;
+    let _ = stream.shutdown().await;
+}
@@ -16,9 +16,9 @@ use std::{
 
 use futures_util::future::BoxFuture;
 use inline_sdk::{
-    ApiClient, ApiError, AuthMetadata, ClientIdentity, RealtimeError, RealtimeEvent,
-    RealtimeEventReceiver, RealtimeSession, RpcRequest, UploadFileBytesInput, UploadFileResult,
-    UploadFileType, UploadVideoMetadata, proto,
+    ApiClient, ApiError, AuthMetadata, ClientIdentity, ProxyConfig, RealtimeClient, RealtimeError,
+    RealtimeEvent, RealtimeEventReceiver, RealtimeSession, RpcRequest, TlsConfig,
+    UploadFileBytesInput, UploadFileResult, UploadFileType, UploadVideoMetadata, proto,
 };
 use serde_json::Value;
 use tokio::sync::{Mutex, Notify};
@@ -67,6 +67,8 @@ pub struct SdkBackendBuilder {
     sync_config: SyncConfig,
     realtime_handshake: bool,
     realtime_connector: Option<Arc<dyn RealtimeConnector>>,
+    proxy: Option<ProxyConfig>,
+    tls: Option<TlsConfig>,
 }
 
 impl fmt::Debug for SdkBackendBuilder {
@@ -85,6 +87,8 @@ impl fmt::Debug for SdkBackendBuilder {
                     .as_ref()
                     .map(|_| "<realtime-connector>"),
             )
+            .field("proxy", &self.proxy)
+            .field("tls", &self.tls)
             .finish()
     }
 }
@@ -99,6 +103,8 @@ impl Default for SdkBackendBuilder {
             sync_config: SyncConfig::default(),
             realtime_handshake: false,
             realtime_connector: None,
+            proxy: None,
+            tls: None,
         }
     }
 }
@@ -161,6 +167,19 @@ impl SdkBackendBuilder {
         self
     }
 
+    /// Routes API and realtime traffic through a proxy.
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Trusts extra root CAs and checks certificate pins for API and realtime
+    /// connections.
+    pub fn tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
     /// Disables realtime handshake on connect.
     pub fn without_realtime_handshake(mut self) -> Self {
         self.realtime_handshake = false;
@@ -170,7 +189,14 @@ impl SdkBackendBuilder {
 
     /// Builds an SDK-backed backend.
     pub fn build(self) -> Result<SdkBackend, SdkBackendBuildError> {
-        let api = ApiClient::try_new_with_identity(self.api_base_url, self.identity.clone())?;
+        let mut api = ApiClient::builder(self.api_base_url).identity(self.identity.clone());
+        if let Some(proxy) = self.proxy.clone() {
+            api = api.proxy(proxy);
+        }
+        if let Some(tls) = self.tls.clone() {
+            api = api.tls(tls);
+        }
+        let api = api.build()?;
         let sync = Arc::new(SyncManager::new(self.store.clone(), self.sync_config));
         Ok(SdkBackend {
             api,
@@ -181,6 +207,8 @@ impl SdkBackendBuilder {
             sync_required: Arc::new(AtomicBool::new(true)),
             realtime_handshake: self.realtime_handshake,
             realtime_connector: self.realtime_connector,
+            proxy: self.proxy,
+            tls: self.tls,
             realtime: Arc::new(Mutex::new(None)),
             realtime_events: Arc::new(Mutex::new(None)),
             in_flight_deliveries: Arc::new(StdMutex::new(HashSet::new())),
@@ -200,6 +228,8 @@ pub struct SdkBackend {
     sync_required: Arc<AtomicBool>,
     realtime_handshake: bool,
     realtime_connector: Option<Arc<dyn RealtimeConnector>>,
+    proxy: Option<ProxyConfig>,
+    tls: Option<TlsConfig>,
     realtime: Arc<Mutex<Option<RealtimeSession>>>,
     realtime_events: Arc<Mutex<Option<RealtimeEventReceiver>>>,
     in_flight_deliveries: Arc<StdMutex<HashSet<u64>>>,
@@ -222,6 +252,8 @@ impl fmt::Debug for SdkBackend {
                     .as_ref()
                     .map(|_| "<realtime-connector>"),
             )
+            .field("proxy", &self.proxy)
+            .field("tls", &self.tls)
             .finish()
     }
 }
@@ -257,6 +289,20 @@ impl SdkBackend {
         self.realtime_handshake
     }
 
+    /// Runs update discovery from the stored sync cursor now and returns the
+    /// committed events without waiting on realtime pushes.
+    ///
+    /// Chat and space buckets the server announces in response still arrive
+    /// as hints and are applied by [`ClientBackend::receive_event_deliveries`],
+    /// so one-shot hosts should keep receiving until the connection goes idle.
+    pub async fn catch_up(&self) -> BackendResult<Vec<ClientEventDelivery>> {
+        self.require_session().await?;
+        let deliveries = self.sync.discover(self).await?;
+        self.sync_required.store(false, Ordering::Release);
+        self.mark_deliveries_in_flight(&deliveries);
+        Ok(deliveries)
+    }
+
     async fn require_session(&self) -> BackendResult<StoredSession> {
         self.store
             .load_session()
@@ -295,19 +341,31 @@ impl SdkBackend {
             return Ok(existing.clone());
         }
 
-        let connected = RealtimeSession::connect_with_identity(
-            &self.realtime_url,
-            session.auth.access_token().expose_secret(),
-            self.identity.clone(),
-        )
-        .await
-        .map_err(realtime_error_to_backend)?;
+        let connected = self
+            .connect_realtime_session(session.auth.access_token().expose_secret())
+            .await
+            .map_err(realtime_error_to_backend)?;
         let receiver = connected.subscribe();
         *self.realtime_events.lock().await = Some(receiver);
         *realtime = Some(connected.clone());
         Ok(connected)
     }
 
+    async fn connect_realtime_session(
+        &self,
+        token: &str,
+    ) -> Result<RealtimeSession, RealtimeError> {
+        let mut builder =
+            RealtimeClient::builder(&self.realtime_url, token).identity(self.identity.clone());
+        if let Some(proxy) = self.proxy.clone() {
+            builder = builder.proxy(proxy);
+        }
+        if let Some(tls) = self.tls.clone() {
+            builder = builder.tls(tls);
+        }
+        builder.connect_session().await
+    }
+
     async fn install_realtime(&self, realtime: RealtimeSession) {
         let receiver = realtime.subscribe();
         *self.realtime.lock().await = Some(realtime);
@@ -370,13 +428,9 @@ impl SdkBackend {
                 None
             } else {
                 Some(
-                    RealtimeSession::connect_with_identity(
-                        &self.realtime_url,
-                        request.auth.access_token().expose_secret(),
-                        self.identity.clone(),
-                    )
-                    .await
-                    .map_err(realtime_error_to_backend)?,
+                    self.connect_realtime_session(request.auth.access_token().expose_secret())
+                        .await
+                        .map_err(realtime_error_to_backend)?,
                 )
             }
         } else {
@@ -452,13 +506,10 @@ impl SdkBackend {
                     ))
                     .await?;
             } else {
-                let connected = RealtimeSession::connect_with_identity(
-                    &self.realtime_url,
-                    session.auth.access_token().expose_secret(),
-                    self.identity.clone(),
-                )
-                .await
-                .map_err(realtime_error_to_backend)?;
+                let connected = self
+                    .connect_realtime_session(session.auth.access_token().expose_secret())
+                    .await
+                    .map_err(realtime_error_to_backend)?;
                 self.install_realtime(connected).await;
             }
         }
@@ -5634,6 +5685,96 @@ mod tests {
         server.await.unwrap();
     }
 
+    #[tokio::test]
+    async fn sdk_backend_catch_up_returns_without_waiting_for_realtime_pushes() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            let _ = read_test_client_message(&mut ws).await;
+            send_test_server_message(
+                &mut ws,
+                proto::ServerProtocolMessage {
+                    id: 1,
+                    body: Some(proto::server_protocol_message::Body::ConnectionOpen(
+                        proto::ConnectionOpen {},
+                    )),
+                },
+            )
+            .await;
+
+            let state = read_test_client_message(&mut ws).await;
+            send_test_server_message(
+                &mut ws,
+                rpc_result_message(
+                    2,
+                    state.id,
+                    proto::rpc_result::Result::GetUpdatesState(proto::GetUpdatesStateResult {
+                        date: 100,
+                        updates_found: Some(false),
+                        core_sync_schema_revision: crate::CORE_SYNC_SCHEMA_REVISION,
+                    }),
+                ),
+            )
+            .await;
+
+            let user_bucket = read_test_client_message(&mut ws).await;
+            send_test_server_message(
+                &mut ws,
+                rpc_result_message(
+                    3,
+                    user_bucket.id,
+                    proto::rpc_result::Result::GetUpdates(proto::GetUpdatesResult {
+                        updates: Vec::new(),
+                        seq: 1,
+                        date: 100,
+                        r#final: Some(true),
+                        result_type: proto::get_updates_result::ResultType::Empty as i32,
+                        core_sync_schema_revision: crate::CORE_SYNC_SCHEMA_REVISION,
+                        ..Default::default()
+                    }),
+                ),
+            )
+            .await;
+            // Keep the socket open so only catch-up, not a disconnect, can return.
+            let _ = ws.next().await;
+        });
+
+        let store = InMemoryStore::new();
+        store.save_session(connect_session()).await.unwrap();
+        store
+            .save_sync_bucket_state(
+                crate::SyncBucketKey::User,
+                crate::SyncBucketState { seq: 1, date: 100 },
+            )
+            .await
+            .unwrap();
+        let backend = SdkBackend::builder()
+            .store(store.clone())
+            .realtime_url(format!("ws://{addr}/realtime"))
+            .without_realtime_handshake()
+            .build()
+            .unwrap();
+
+        let deliveries =
+            tokio::time::timeout(std::time::Duration::from_secs(3), backend.catch_up())
+                .await
+                .unwrap()
+                .unwrap();
+
+        assert!(deliveries.is_empty());
+        assert_eq!(
+            store
+                .sync_bucket_state(crate::SyncBucketKey::User)
+                .await
+                .unwrap(),
+            crate::SyncBucketState { seq: 1, date: 100 }
+        );
+        drop(backend);
+        server.abort();
+    }
+
     #[tokio::test]
     async fn sdk_backend_recovers_hint_only_updates_on_the_multiplexed_connection() {
         let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
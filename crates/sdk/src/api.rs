@@ -3,15 +3,19 @@
 use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
 use std::fmt;
 use std::fs;
 use std::path::PathBuf;
 use std::str::FromStr;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 use url::Url;
 
 use crate::client_info::{self, AuthMetadata, ClientIdentity};
+use crate::proxy::ProxyConfig;
+use crate::tls::TlsConfig;
 
 /// Default timeout for API HTTP requests made by SDK-created clients.
 pub const DEFAULT_API_TIMEOUT: Duration = Duration::from_secs(60);
@@ -116,6 +120,53 @@ pub struct ApiClient {
     base_url: String,
     http: Client,
     request_timeout: Option<Duration>,
+    response_cache: Option<ResponseCacheConfig>,
+}
+
+/// Storage for cached API responses. The client decides which calls are
+/// cached and when an entry is fresh; the caller decides where entries live.
+///
+/// Only read-style methods (`get*`, `search*`, `list*`) are cached. Keys are
+/// SHA-256 digests of the base URL, method, bearer token, and request body, so
+/// stores never see the token and different accounts never share entries.
+pub trait ResponseCache: Send + Sync {
+    /// Returns the entry stored under `key`, fresh or stale.
+    fn get(&self, key: &str) -> Option<CachedResponse>;
+    /// Stores `response` under `key`, replacing any previous entry.
+    fn put(&self, key: &str, response: CachedResponse);
+}
+
+/// A successful API response body with the validators the server sent.
+#[derive(Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedResponse {
+    /// Raw JSON response body.
+    pub body: String,
+    /// `ETag` header, sent back as `If-None-Match` once the entry is stale.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    /// `Last-Modified` header, sent back as `If-Modified-Since`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    /// Unix seconds when the body was fetched or last revalidated.
+    pub stored_at: i64,
+}
+
+impl fmt::Debug for CachedResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachedResponse")
+            .field("body", &format_args!("<{} bytes>", self.body.len()))
+            .field("etag", &self.etag)
+            .field("last_modified", &self.last_modified)
+            .field("stored_at", &self.stored_at)
+            .finish()
+    }
+}
+
+#[derive(Clone)]
+struct ResponseCacheConfig {
+    store: Arc<dyn ResponseCache>,
+    ttl: Duration,
 }
 
 impl fmt::Debug for ApiClient {
@@ -124,6 +175,10 @@ impl fmt::Debug for ApiClient {
             .field("base_url", &self.base_url)
             .field("http", &"<reqwest::Client>")
             .field("request_timeout", &self.request_timeout)
+            .field(
+                "response_cache_ttl",
+                &self.response_cache.as_ref().map(|cache| cache.ttl),
+            )
             .finish()
     }
 }
@@ -136,6 +191,9 @@ pub struct ApiClientBuilder {
     identity: ClientIdentity,
     http: Option<Client>,
     request_timeout: Option<Duration>,
+    proxy: Option<ProxyConfig>,
+    tls: Option<TlsConfig>,
+    response_cache: Option<ResponseCacheConfig>,
 }
 
 impl fmt::Debug for ApiClientBuilder {
@@ -148,6 +206,12 @@ impl fmt::Debug for ApiClientBuilder {
                 &self.http.as_ref().map(|_| "<custom reqwest::Client>"),
             )
             .field("request_timeout", &self.request_timeout)
+            .field("proxy", &self.proxy)
+            .field("tls", &self.tls)
+            .field(
+                "response_cache_ttl",
+                &self.response_cache.as_ref().map(|cache| cache.ttl),
+            )
             .finish()
     }
 }
@@ -197,6 +261,9 @@ impl ApiClientBuilder {
             identity: ClientIdentity::sdk(),
             http: None,
             request_timeout: Some(DEFAULT_API_TIMEOUT),
+            proxy: None,
+            tls: None,
+            response_cache: None,
         }
     }
 
@@ -228,6 +295,28 @@ impl ApiClientBuilder {
         self
     }
 
+    /// Sends API requests through a proxy instead of `reqwest`'s environment
+    /// detection. Ignored when a custom `reqwest` client is supplied.
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Trusts extra root CAs and checks certificate pins on every request.
+    /// Ignored when a custom `reqwest` client is supplied.
+    pub fn tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Caches read-style responses in `store`. Entries younger than `ttl` are
+    /// served without a request; older ones are revalidated with `ETag` or
+    /// `Last-Modified` when the server sent them, and refetched otherwise.
+    pub fn response_cache(mut self, store: Arc<dyn ResponseCache>, ttl: Duration) -> Self {
+        self.response_cache = Some(ResponseCacheConfig { store, ttl });
+        self
+    }
+
     /// Builds the API client.
     pub fn build(self) -> Result<ApiClient, ApiError> {
         let base_url = normalize_api_base_url(self.base_url)?;
@@ -245,6 +334,14 @@ impl ApiClientBuilder {
                 if let Some(timeout) = self.request_timeout {
                     builder = builder.timeout(timeout);
                 }
+                if let Some(proxy) = &self.proxy {
+                    builder = builder.proxy(proxy.reqwest_proxy()?);
+                }
+                if let Some(tls) = &self.tls {
+                    builder = builder.use_preconfigured_tls(rustls::ClientConfig::clone(
+                        &tls.rustls_client_config(),
+                    ));
+                }
                 (builder.build()?, self.request_timeout)
             }
         };
@@ -252,6 +349,7 @@ impl ApiClientBuilder {
             base_url,
             http,
             request_timeout,
+            response_cache: self.response_cache,
         })
     }
 }
@@ -266,6 +364,7 @@ impl ApiClient {
             base_url: normalize_api_base_url(base_url)?,
             http,
             request_timeout: None,
+            response_cache: None,
         })
     }
 
@@ -355,6 +454,8 @@ impl ApiClient {
             mime_type,
             file_type,
             video_metadata,
+            photo_metadata,
+            thumbnail,
         } = input;
         let bytes = fs::read(&path)?;
         self.upload_file_bytes(
@@ -365,6 +466,8 @@ impl ApiClient {
                 mime_type,
                 file_type,
                 video_metadata,
+                photo_metadata,
+                thumbnail,
             },
         )
         .await
@@ -381,11 +484,12 @@ impl ApiClient {
         let url = format!("{}/uploadFile", self.base_url);
         log::debug!(
             target: "inline_sdk::api",
-            "uploading file bytes type={} size_bytes={} has_mime_type={} has_video_metadata={}",
+            "uploading file bytes type={} size_bytes={} has_mime_type={} has_video_metadata={} has_thumbnail={}",
             input.file_type,
             input.bytes.len(),
             input.mime_type.is_some(),
-            input.video_metadata.is_some()
+            input.video_metadata.is_some(),
+            input.thumbnail.is_some()
         );
         let mut form = reqwest::multipart::Form::new().text("type", input.file_type.as_str());
         let mut file_part = reqwest::multipart::Part::bytes(input.bytes);
@@ -401,6 +505,17 @@ impl ApiClient {
                 .text("height", video.height.to_string())
                 .text("duration", video.duration.to_string());
         }
+        if let Some(photo) = input.photo_metadata {
+            form = form
+                .text("width", photo.width.to_string())
+                .text("height", photo.height.to_string());
+        }
+        if let Some(thumbnail) = input.thumbnail {
+            let part = reqwest::multipart::Part::bytes(thumbnail.bytes)
+                .file_name("thumbnail")
+                .mime_str(&thumbnail.mime_type)?;
+            form = form.part("thumbnail", part);
+        }
 
         let response = self
             .http
@@ -451,6 +566,31 @@ impl ApiClient {
         self.post_with_token(url, token, payload).await
     }
 
+    /// Searches Inline users by name, username, or email, including people you
+    /// don't share a chat with yet.
+    pub async fn search_contacts(
+        &self,
+        token: &str,
+        query: &str,
+        limit: Option<u32>,
+    ) -> Result<SearchContactsResult, ApiError> {
+        validate_bearer_token(token)?;
+        let query = query.trim();
+        if query.is_empty() {
+            return Err(ApiError::InvalidInput {
+                message: "search query cannot be empty".to_string(),
+            });
+        }
+        let url = format!("{}/searchContacts", self.base_url);
+        let mut payload = serde_json::Map::new();
+        payload.insert("q".to_string(), json!(query));
+        if let Some(limit) = limit {
+            payload.insert("limit".to_string(), json!(limit));
+        }
+        self.post_with_token_cached("searchContacts", url, token, payload)
+            .await
+    }
+
     /// Revokes the current authenticated API session.
     pub async fn logout(&self, token: &str) -> Result<(), ApiError> {
         validate_bearer_token(token)?;
@@ -504,6 +644,39 @@ impl ApiClient {
         self.post_with_token(url, token, payload).await
     }
 
+    /// Posts a JSON object to any API method (`readMessages`, `/getChats`) and
+    /// returns its `result`, for endpoints without a typed wrapper here.
+    /// Read-style methods go through the response cache when one is set.
+    pub async fn post_raw(
+        &self,
+        token: &str,
+        method: &str,
+        payload: serde_json::Map<String, Value>,
+    ) -> Result<Value, ApiError> {
+        validate_bearer_token(token)?;
+        let url = self.method_url(method)?;
+        let method = method.trim().trim_start_matches('/');
+        self.post_with_token_cached(method, url, token, payload)
+            .await
+    }
+
+    /// Full URL for an API method name, with or without a leading slash.
+    pub fn method_url(&self, method: &str) -> Result<String, ApiError> {
+        let method = method.trim().trim_start_matches('/');
+        if method.is_empty()
+            || !method
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+        {
+            return Err(ApiError::InvalidInput {
+                message: format!(
+                    "API method must be a name like readMessages or /readMessages, got {method:?}"
+                ),
+            });
+        }
+        Ok(format!("{}/{method}", self.base_url))
+    }
+
     async fn post<T: for<'de> Deserialize<'de>>(
         &self,
         url: String,
@@ -548,6 +721,133 @@ impl ApiClient {
         );
         decode_api_response(response).await
     }
+
+    /// [`post_with_token`](Self::post_with_token) through the response cache,
+    /// for read-style methods when a cache is configured.
+    async fn post_with_token_cached<T: for<'de> Deserialize<'de>>(
+        &self,
+        method: &str,
+        url: String,
+        token: &str,
+        payload: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<T, ApiError> {
+        let Some(cache) = self
+            .response_cache
+            .as_ref()
+            .filter(|_| is_cacheable_method(method))
+        else {
+            return self.post_with_token(url, token, payload).await;
+        };
+        let key = response_cache_key(&self.base_url, method, token, &payload);
+        let now = unix_now();
+        let cached = cache.store.get(&key);
+        if let Some(cached) = &cached
+            && (cached.stored_at..cached.stored_at + cache.ttl.as_secs() as i64).contains(&now)
+        {
+            log::trace!(target: "inline_sdk::api", "POST /{method} served from cache");
+            return decode_api_response_text(StatusCode::OK, &cached.body);
+        }
+
+        log::trace!(
+            target: "inline_sdk::api",
+            "POST {} with bearer auth, revalidating={}",
+            api_url_path_for_log(&url),
+            cached.is_some()
+        );
+        let mut request = self.http.post(url).bearer_auth(token).json(&payload);
+        if let Some(cached) = &cached {
+            if let Some(etag) = &cached.etag {
+                request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        log::trace!(
+            target: "inline_sdk::api",
+            "API response status={status}"
+        );
+        let etag = header_string(&response, reqwest::header::ETAG);
+        let last_modified = header_string(&response, reqwest::header::LAST_MODIFIED);
+
+        if status == StatusCode::NOT_MODIFIED
+            && let Some(cached) = cached
+        {
+            let result = decode_api_response_text(StatusCode::OK, &cached.body)?;
+            cache.store.put(
+                &key,
+                CachedResponse {
+                    etag: etag.or(cached.etag),
+                    last_modified: last_modified.or(cached.last_modified),
+                    stored_at: now,
+                    body: cached.body,
+                },
+            );
+            return Ok(result);
+        }
+
+        let body = response.text().await?;
+        let result = decode_api_response_text(status, &body)?;
+        cache.store.put(
+            &key,
+            CachedResponse {
+                body,
+                etag,
+                last_modified,
+                stored_at: now,
+            },
+        );
+        Ok(result)
+    }
+}
+
+/// Methods that only read, judged by name: `getMe`, `searchContacts`, `listX`.
+fn is_cacheable_method(method: &str) -> bool {
+    ["get", "search", "list"].iter().any(|prefix| {
+        method
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.chars().next())
+            .is_some_and(|ch| ch.is_ascii_uppercase())
+    })
+}
+
+fn response_cache_key(
+    base_url: &str,
+    method: &str,
+    token: &str,
+    payload: &serde_json::Map<String, Value>,
+) -> String {
+    let mut hasher = Sha256::new();
+    let body = serde_json::to_string(payload).unwrap_or_default();
+    for part in [base_url, method, token, &body] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn header_string(
+    response: &reqwest::Response,
+    name: reqwest::header::HeaderName,
+) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(ToString::to_string)
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
 }
 
 /// Response from sending an auth code.
@@ -670,9 +970,60 @@ impl UploadVideoMetadata {
     }
 }
 
+/// Photo dimensions sent with photo uploads, so clients can lay out the image
+/// before it has loaded.
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadPhotoMetadata {
+    /// Photo width in pixels.
+    pub width: i32,
+    /// Photo height in pixels.
+    pub height: i32,
+}
+
+impl UploadPhotoMetadata {
+    /// Creates photo metadata for a photo upload.
+    pub fn new(width: i32, height: i32) -> Self {
+        Self { width, height }
+    }
+}
+
+/// Small preview image (a poster frame for videos) sent as the `thumbnail`
+/// form part, so clients can render a preview before the file downloads.
+#[must_use]
+#[derive(Clone, PartialEq, Eq)]
+pub struct UploadThumbnail {
+    /// Encoded image bytes.
+    pub bytes: Vec<u8>,
+    /// MIME type of `bytes`.
+    pub mime_type: String,
+}
+
+impl fmt::Debug for UploadThumbnail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UploadThumbnail")
+            .field("bytes_len", &self.bytes.len())
+            .field("mime_type", &self.mime_type)
+            .finish()
+    }
+}
+
+impl UploadThumbnail {
+    /// Creates a JPEG thumbnail.
+    pub fn jpeg(bytes: impl Into<Vec<u8>>) -> Self {
+        Self {
+            bytes: bytes.into(),
+            mime_type: "image/jpeg".to_string(),
+        }
+    }
+}
+
 /// Local file upload input.
+///
+/// Build it with the constructors and `with_*` setters; fields may be added.
 #[must_use]
 #[derive(Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct UploadFileInput {
     /// Local path to read and upload.
     pub path: PathBuf,
@@ -684,6 +1035,10 @@ pub struct UploadFileInput {
     pub file_type: UploadFileType,
     /// Required video details when uploading a video.
     pub video_metadata: Option<UploadVideoMetadata>,
+    /// Optional dimensions when uploading a photo.
+    pub photo_metadata: Option<UploadPhotoMetadata>,
+    /// Optional preview image for photo and video uploads.
+    pub thumbnail: Option<UploadThumbnail>,
 }
 
 impl fmt::Debug for UploadFileInput {
@@ -694,6 +1049,8 @@ impl fmt::Debug for UploadFileInput {
             .field("mime_type", &self.mime_type)
             .field("file_type", &self.file_type)
             .field("video_metadata", &self.video_metadata)
+            .field("photo_metadata", &self.photo_metadata)
+            .field("thumbnail", &self.thumbnail)
             .finish()
     }
 }
@@ -711,6 +1068,8 @@ impl UploadFileInput {
             mime_type: None,
             file_type,
             video_metadata: None,
+            photo_metadata: None,
+            thumbnail: None,
         }
     }
 
@@ -747,11 +1106,26 @@ impl UploadFileInput {
         self.video_metadata = Some(metadata);
         self
     }
+
+    /// Sets photo dimensions for a photo upload.
+    pub fn with_photo_metadata(mut self, metadata: UploadPhotoMetadata) -> Self {
+        self.photo_metadata = Some(metadata);
+        self
+    }
+
+    /// Attaches a preview image to a photo or video upload.
+    pub fn with_thumbnail(mut self, thumbnail: UploadThumbnail) -> Self {
+        self.thumbnail = Some(thumbnail);
+        self
+    }
 }
 
 /// In-memory file upload input.
+///
+/// Build it with the constructors and `with_*` setters; fields may be added.
 #[must_use]
 #[derive(Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct UploadFileBytesInput {
     /// File bytes to upload.
     pub bytes: Vec<u8>,
@@ -763,6 +1137,10 @@ pub struct UploadFileBytesInput {
     pub file_type: UploadFileType,
     /// Required video details when uploading a video.
     pub video_metadata: Option<UploadVideoMetadata>,
+    /// Optional dimensions when uploading a photo.
+    pub photo_metadata: Option<UploadPhotoMetadata>,
+    /// Optional preview image for photo and video uploads.
+    pub thumbnail: Option<UploadThumbnail>,
 }
 
 impl fmt::Debug for UploadFileBytesInput {
@@ -773,6 +1151,8 @@ impl fmt::Debug for UploadFileBytesInput {
             .field("mime_type", &self.mime_type)
             .field("file_type", &self.file_type)
             .field("video_metadata", &self.video_metadata)
+            .field("photo_metadata", &self.photo_metadata)
+            .field("thumbnail", &self.thumbnail)
             .finish()
     }
 }
@@ -790,6 +1170,8 @@ impl UploadFileBytesInput {
             mime_type: None,
             file_type,
             video_metadata: None,
+            photo_metadata: None,
+            thumbnail: None,
         }
     }
 
@@ -826,6 +1208,18 @@ impl UploadFileBytesInput {
         self.video_metadata = Some(metadata);
         self
     }
+
+    /// Sets photo dimensions for a photo upload.
+    pub fn with_photo_metadata(mut self, metadata: UploadPhotoMetadata) -> Self {
+        self.photo_metadata = Some(metadata);
+        self
+    }
+
+    /// Attaches a preview image to a photo or video upload.
+    pub fn with_thumbnail(mut self, thumbnail: UploadThumbnail) -> Self {
+        self.thumbnail = Some(thumbnail);
+        self
+    }
 }
 
 /// File upload response.
@@ -920,6 +1314,15 @@ pub struct CreatePrivateChatResult {
     pub user: Value,
 }
 
+/// Response from searching users.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchContactsResult {
+    /// Raw user objects returned by the server, best match first.
+    #[serde(default)]
+    pub users: Vec<Value>,
+}
+
 /// Input for creating a Linear issue from a message.
 #[must_use]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -1237,11 +1640,21 @@ fn validate_auth_metadata(metadata: &AuthMetadata) -> Result<(), ApiError> {
 }
 
 fn validate_upload_file_input(input: &UploadFileInput) -> Result<(), ApiError> {
-    validate_upload_file_metadata(&input.file_name, input.file_type, input.video_metadata)
+    validate_upload_file_metadata(&input.file_name, input.file_type, input.video_metadata)?;
+    validate_upload_previews(
+        input.file_type,
+        input.photo_metadata,
+        input.thumbnail.as_ref(),
+    )
 }
 
 fn validate_upload_file_bytes_input(input: &UploadFileBytesInput) -> Result<(), ApiError> {
     validate_upload_file_metadata(&input.file_name, input.file_type, input.video_metadata)?;
+    validate_upload_previews(
+        input.file_type,
+        input.photo_metadata,
+        input.thumbnail.as_ref(),
+    )?;
     if input.bytes.is_empty() {
         return Err(ApiError::InvalidInput {
             message: "upload file bytes cannot be empty".to_string(),
@@ -1273,6 +1686,35 @@ fn validate_upload_file_metadata(
     }
 }
 
+fn validate_upload_previews(
+    file_type: UploadFileType,
+    photo_metadata: Option<UploadPhotoMetadata>,
+    thumbnail: Option<&UploadThumbnail>,
+) -> Result<(), ApiError> {
+    match (file_type, photo_metadata) {
+        (UploadFileType::Photo, Some(metadata)) if metadata.width <= 0 || metadata.height <= 0 => {
+            return Err(ApiError::InvalidInput {
+                message: "photo metadata width and height must be positive".to_string(),
+            });
+        }
+        (UploadFileType::Photo, _) | (_, None) => {}
+        (_, Some(_)) => {
+            return Err(ApiError::InvalidInput {
+                message: "photo metadata can only be used with photo uploads".to_string(),
+            });
+        }
+    }
+    match (file_type, thumbnail) {
+        (UploadFileType::Document, Some(_)) => Err(ApiError::InvalidInput {
+            message: "thumbnails can only be used with photo or video uploads".to_string(),
+        }),
+        (_, Some(thumbnail)) if thumbnail.bytes.is_empty() => Err(ApiError::InvalidInput {
+            message: "upload thumbnail bytes cannot be empty".to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
 fn validate_upload_video_metadata(metadata: UploadVideoMetadata) -> Result<(), ApiError> {
     if metadata.width <= 0 || metadata.height <= 0 || metadata.duration <= 0 {
         return Err(ApiError::InvalidInput {
@@ -1584,6 +2026,129 @@ mod tests {
         assert!(request.body.get("peerThreadId").is_none());
     }
 
+    #[tokio::test]
+    async fn post_raw_sends_payload_to_the_named_method() {
+        let request = capture_json_request(
+            r#"{"ok":true,"result":{"unread":0}}"#,
+            |client| async move {
+                let mut payload = serde_json::Map::new();
+                payload.insert("peerThreadId".to_string(), json!(123));
+                let result = client
+                    .post_raw("secret-token", "/readMessages", payload)
+                    .await?;
+                assert_eq!(result, json!({"unread": 0}));
+                Ok::<_, ApiError>(result)
+            },
+        )
+        .await;
+
+        assert_eq!(request.path, "/v1/readMessages");
+        assert_eq!(
+            request.headers.get("authorization").map(String::as_str),
+            Some("Bearer secret-token")
+        );
+        assert_eq!(request.body, json!({"peerThreadId": 123}));
+    }
+
+    #[test]
+    fn method_url_rejects_paths_and_queries() {
+        let client = ApiClient::try_new("https://api.inline.chat/v1").unwrap();
+        assert_eq!(
+            client.method_url("getChats").unwrap(),
+            "https://api.inline.chat/v1/getChats"
+        );
+        for method in ["", "/", "../admin", "getChats?limit=1", "a/b"] {
+            assert!(matches!(
+                client.method_url(method),
+                Err(ApiError::InvalidInput { .. })
+            ));
+        }
+    }
+
+    #[derive(Default)]
+    struct MemoryCache(std::sync::Mutex<std::collections::HashMap<String, CachedResponse>>);
+
+    impl ResponseCache for MemoryCache {
+        fn get(&self, key: &str) -> Option<CachedResponse> {
+            self.0.lock().unwrap().get(key).cloned()
+        }
+
+        fn put(&self, key: &str, response: CachedResponse) {
+            self.0.lock().unwrap().insert(key.to_string(), response);
+        }
+    }
+
+    #[tokio::test]
+    async fn response_cache_serves_fresh_entries_and_revalidates_stale_ones() {
+        let body = r#"{"ok":true,"result":{"users":[{"id":42}]}}"#;
+        let (addr, server) = serve_responses(vec![
+            json_response(body, "etag: \"v1\"\r\n"),
+            "HTTP/1.1 304 Not Modified\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                .to_string(),
+        ])
+        .await;
+        let cache = Arc::new(MemoryCache::default());
+        let client = ApiClient::builder(format!("http://{addr}/v1"))
+            .response_cache(cache.clone(), Duration::from_secs(60))
+            .build()
+            .unwrap();
+        let search = || client.search_contacts("secret-token", "sam", None);
+
+        let first = search().await.unwrap();
+        // Served from the cache: the server only answers two requests in all.
+        assert_eq!(search().await.unwrap().users, first.users);
+        for entry in cache.0.lock().unwrap().values_mut() {
+            assert_eq!(entry.etag.as_deref(), Some("\"v1\""));
+            entry.stored_at -= 120;
+        }
+        assert_eq!(search().await.unwrap().users, first.users);
+
+        let requests = server.await.unwrap();
+        assert!(!requests[0].headers.contains_key("if-none-match"));
+        assert_eq!(
+            requests[1].headers.get("if-none-match").map(String::as_str),
+            Some("\"v1\"")
+        );
+        let entries = cache.0.lock().unwrap();
+        let entry = entries.values().next().unwrap();
+        assert!(entry.stored_at > unix_now() - 5);
+        assert!(!entries.keys().any(|key| key.contains("secret-token")));
+    }
+
+    #[test]
+    fn only_read_style_methods_are_cached() {
+        for method in ["getMe", "searchContacts", "listSpaces"] {
+            assert!(is_cacheable_method(method), "{method}");
+        }
+        for method in [
+            "readMessages",
+            "getaway",
+            "logout",
+            "search",
+            "createPrivateChat",
+        ] {
+            assert!(!is_cacheable_method(method), "{method}");
+        }
+    }
+
+    #[tokio::test]
+    async fn search_contacts_posts_trimmed_query_and_limit() {
+        let request = capture_json_request(
+            r#"{"ok":true,"result":{"users":[{"id":42,"username":"sam"}]}}"#,
+            |client| async move {
+                let result = client
+                    .search_contacts("secret-token", "  sam ", Some(5))
+                    .await?;
+                assert_eq!(result.users[0]["id"], json!(42));
+                Ok::<_, ApiError>(result)
+            },
+        )
+        .await;
+
+        assert_eq!(request.path, "/v1/searchContacts");
+        assert_eq!(request.body, json!({"q": "sam", "limit": 5}));
+    }
+
     #[tokio::test]
     async fn logout_posts_empty_bearer_request() {
         let request = capture_json_request(r#"{"ok":true,"result":null}"#, |client| async move {
@@ -1724,6 +2289,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn upload_input_validation_checks_photo_metadata_and_thumbnails() {
+        let photo = UploadFileInput::photo("cat.jpg", "cat.jpg")
+            .with_photo_metadata(UploadPhotoMetadata::new(4032, 3024))
+            .with_thumbnail(UploadThumbnail::jpeg(vec![0xff, 0xd8]));
+        validate_upload_file_input(&photo).unwrap();
+        assert!(format!("{photo:?}").contains("bytes_len: 2"));
+
+        let cases = [
+            (
+                UploadFileInput::photo("cat.jpg", "cat.jpg")
+                    .with_photo_metadata(UploadPhotoMetadata::new(0, 10)),
+                "photo metadata width and height must be positive",
+            ),
+            (
+                UploadFileInput::document("notes.txt", "notes.txt")
+                    .with_photo_metadata(UploadPhotoMetadata::new(10, 10)),
+                "photo metadata can only be used with photo uploads",
+            ),
+            (
+                UploadFileInput::document("notes.txt", "notes.txt")
+                    .with_thumbnail(UploadThumbnail::jpeg(vec![1])),
+                "thumbnails can only be used with photo or video uploads",
+            ),
+            (
+                UploadFileInput::photo("cat.jpg", "cat.jpg")
+                    .with_thumbnail(UploadThumbnail::jpeg(Vec::new())),
+                "upload thumbnail bytes cannot be empty",
+            ),
+        ];
+        for (input, expected) in cases {
+            match validate_upload_file_input(&input).unwrap_err() {
+                ApiError::InvalidInput { message } => assert_eq!(message, expected),
+                other => panic!("expected invalid input, got {other:?}"),
+            }
+        }
+    }
+
     #[test]
     fn upload_input_validation_rejects_empty_file_name() {
         let input = UploadFileInput::document("notes.txt", " ");
@@ -1988,38 +2591,53 @@ mod tests {
         F: FnOnce(ApiClient) -> Fut,
         Fut: Future<Output = Result<T, ApiError>>,
     {
+        let (addr, server) = serve_responses(vec![json_response(response_body, "")]).await;
+        let client = ApiClient::try_new(format!("http://{addr}/v1")).unwrap();
+        exercise(client).await.unwrap();
+        server.await.unwrap().remove(0)
+    }
+
+    fn json_response(body: &str, extra_headers: &str) -> String {
+        format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n{extra_headers}connection: close\r\n\r\n{body}",
+            body.len()
+        )
+    }
+
+    /// Answers one connection per response, in order, and returns the requests.
+    async fn serve_responses(
+        responses: Vec<String>,
+    ) -> (
+        std::net::SocketAddr,
+        tokio::task::JoinHandle<Vec<CapturedRequest>>,
+    ) {
         let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
         let addr = listener.local_addr().unwrap();
         let server = tokio::spawn(async move {
-            let (mut stream, _) = listener.accept().await.unwrap();
-            let mut request = Vec::new();
-            loop {
-                let mut chunk = [0_u8; 1024];
-                let read = stream.read(&mut chunk).await.unwrap();
-                assert!(read != 0, "client closed before completing request");
-                request.extend_from_slice(&chunk[..read]);
-                if let Some(header_end) = http_header_end(&request) {
-                    let header_text = String::from_utf8_lossy(&request[..header_end]);
-                    let content_length = http_content_length(&header_text);
-                    if request.len() >= header_end + content_length {
-                        break;
+            let mut captured = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                loop {
+                    let mut chunk = [0_u8; 1024];
+                    let read = stream.read(&mut chunk).await.unwrap();
+                    assert!(read != 0, "client closed before completing request");
+                    request.extend_from_slice(&chunk[..read]);
+                    if let Some(header_end) = http_header_end(&request) {
+                        let header_text = String::from_utf8_lossy(&request[..header_end]);
+                        let content_length = http_content_length(&header_text);
+                        if request.len() >= header_end + content_length {
+                            break;
+                        }
                     }
                 }
-            }
 
-            let captured = parse_captured_request(&request);
-            let response = format!(
-                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
-                response_body.len(),
-                response_body
-            );
-            stream.write_all(response.as_bytes()).await.unwrap();
+                captured.push(parse_captured_request(&request));
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
             captured
         });
-
-        let client = ApiClient::try_new(format!("http://{addr}/v1")).unwrap();
-        exercise(client).await.unwrap();
-        server.await.unwrap()
+        (addr, server)
     }
 
     fn http_header_end(request: &[u8]) -> Option<usize> {
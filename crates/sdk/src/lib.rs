@@ -36,23 +36,28 @@
 
 pub mod api;
 pub mod client_info;
+pub mod proxy;
 pub mod realtime;
+pub mod tls;
 
 pub use api::{
-    ApiClient, ApiClientBuilder, ApiError, CreateLinearIssueInput, CreateLinearIssueResult,
-    CreateNotionTaskInput, CreateNotionTaskResult, CreatePrivateChatResult, DEFAULT_API_TIMEOUT,
-    PeerId, ReadMessagesInput, ReadMessagesResult, SendCodeResult, UploadFileBytesInput,
-    UploadFileInput, UploadFileResult, UploadFileType, UploadFileTypeParseError,
+    ApiClient, ApiClientBuilder, ApiError, CachedResponse, CreateLinearIssueInput,
+    CreateLinearIssueResult, CreateNotionTaskInput, CreateNotionTaskResult,
+    CreatePrivateChatResult, DEFAULT_API_TIMEOUT, PeerId, ReadMessagesInput, ReadMessagesResult,
+    ResponseCache, SendCodeResult, UploadFileBytesInput, UploadFileInput, UploadFileResult,
+    UploadFileType, UploadFileTypeParseError, UploadPhotoMetadata, UploadThumbnail,
     UploadVideoMetadata, VerifyCodeResult,
 };
 pub use client_info::{AuthMetadata, ClientIdentity, ClientIdentityError};
 pub use inline_protocol::proto;
+pub use proxy::{ProxyConfig, ProxyError};
 pub use realtime::{
     DEFAULT_CONNECT_TIMEOUT, DEFAULT_HEARTBEAT_INTERVAL, DEFAULT_HEARTBEAT_TIMEOUT,
     DEFAULT_RPC_TIMEOUT, DEFAULT_SESSION_COMMAND_CAPACITY, DEFAULT_SESSION_EVENT_CAPACITY,
     DEFAULT_SESSION_MAX_IN_FLIGHT_RPCS, RealtimeClient, RealtimeClientBuilder, RealtimeError,
     RealtimeEvent, RealtimeEventReceiver, RealtimeSession, RpcRequest,
 };
+pub use tls::{TlsConfig, TlsConfigBuilder, TlsError};
 
 /// Convenient imports for common SDK consumers.
 pub mod prelude {
@@ -62,10 +67,11 @@ pub mod prelude {
         CreateNotionTaskResult, CreatePrivateChatResult, DEFAULT_API_TIMEOUT,
         DEFAULT_CONNECT_TIMEOUT, DEFAULT_HEARTBEAT_INTERVAL, DEFAULT_HEARTBEAT_TIMEOUT,
         DEFAULT_RPC_TIMEOUT, DEFAULT_SESSION_COMMAND_CAPACITY, DEFAULT_SESSION_EVENT_CAPACITY,
-        DEFAULT_SESSION_MAX_IN_FLIGHT_RPCS, PeerId, ReadMessagesInput, ReadMessagesResult,
-        RealtimeClient, RealtimeClientBuilder, RealtimeError, RealtimeEvent, RealtimeEventReceiver,
-        RealtimeSession, RpcRequest, SendCodeResult, UploadFileBytesInput, UploadFileInput,
-        UploadFileResult, UploadFileType, UploadFileTypeParseError, UploadVideoMetadata,
+        DEFAULT_SESSION_MAX_IN_FLIGHT_RPCS, PeerId, ProxyConfig, ProxyError, ReadMessagesInput,
+        ReadMessagesResult, RealtimeClient, RealtimeClientBuilder, RealtimeError, RealtimeEvent,
+        RealtimeEventReceiver, RealtimeSession, RpcRequest, SendCodeResult, TlsConfig, TlsError,
+        UploadFileBytesInput, UploadFileInput, UploadFileResult, UploadFileType,
+        UploadFileTypeParseError, UploadPhotoMetadata, UploadThumbnail, UploadVideoMetadata,
         VerifyCodeResult, proto,
     };
 }
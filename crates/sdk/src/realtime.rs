@@ -2,19 +2,21 @@
 
 use futures_util::{SinkExt, StreamExt};
 use prost::Message;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt;
 use std::future::Future;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::sync::{Semaphore, broadcast, mpsc, oneshot, watch};
-use tokio_tungstenite::connect_async;
 use tokio_tungstenite::tungstenite::Message as WsMessage;
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
 use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::{Connector, client_async_tls_with_config, connect_async_tls_with_config};
 use url::Url;
 
 use crate::client_info::{self, ClientIdentity};
+use crate::proxy::ProxyConfig;
+use crate::tls::TlsConfig;
 use inline_protocol::proto;
 
 /// Default timeout for opening a realtime connection.
@@ -87,6 +89,9 @@ pub enum RealtimeError {
         /// Human-readable formatted error.
         friendly: String,
     },
+    /// The proxy could not open a tunnel to the realtime server.
+    #[error("proxy error: {0}")]
+    Proxy(std::io::Error),
     /// The realtime connection closed before the requested operation completed.
     #[error("realtime connection closed")]
     ConnectionClosed,
@@ -110,6 +115,14 @@ pub enum RealtimeError {
         /// Human-readable formatted error.
         friendly: String,
     },
+    /// The server doesn't implement the requested method, either as learned
+    /// earlier on this session or as seeded through
+    /// [`RealtimeClientBuilder::unsupported_methods`].
+    #[error("server does not support {method}")]
+    UnsupportedMethod {
+        /// Requested RPC method.
+        method: &'static str,
+    },
 }
 
 impl fmt::Debug for RealtimeError {
@@ -152,6 +165,7 @@ impl fmt::Debug for RealtimeError {
                 .field("reason_name", reason_name)
                 .field("friendly", friendly)
                 .finish(),
+            RealtimeError::Proxy(error) => f.debug_tuple("Proxy").field(error).finish(),
             RealtimeError::ConnectionClosed => f.debug_struct("ConnectionClosed").finish(),
             RealtimeError::EventLagged { skipped } => f
                 .debug_struct("EventLagged")
@@ -171,6 +185,10 @@ impl fmt::Debug for RealtimeError {
                 .field("message", message)
                 .field("friendly", friendly)
                 .finish(),
+            RealtimeError::UnsupportedMethod { method } => f
+                .debug_struct("UnsupportedMethod")
+                .field("method", method)
+                .finish(),
         }
     }
 }
@@ -226,6 +244,16 @@ pub struct RealtimeSession {
     heartbeat_interval: Option<Duration>,
     heartbeat_timeout: Duration,
     rpc_permits: Arc<Semaphore>,
+    unsupported_methods: Arc<Mutex<UnsupportedMethods>>,
+}
+
+/// What a session knows about methods the server lacks.
+#[derive(Default)]
+struct UnsupportedMethods {
+    /// Every method calls fail fast on, seeded or learned.
+    known: BTreeSet<proto::Method>,
+    /// Learned from an explicit "not implemented" reply rather than guessed.
+    confirmed: BTreeSet<proto::Method>,
 }
 
 impl fmt::Debug for RealtimeSession {
@@ -277,6 +305,9 @@ pub struct RealtimeClientBuilder {
     heartbeat_interval: Option<Duration>,
     heartbeat_timeout: Duration,
     max_in_flight_rpcs: usize,
+    proxy: Option<ProxyConfig>,
+    tls: Option<TlsConfig>,
+    unsupported_methods: BTreeSet<proto::Method>,
 }
 
 impl fmt::Debug for RealtimeClientBuilder {
@@ -290,6 +321,8 @@ impl fmt::Debug for RealtimeClientBuilder {
             .field("heartbeat_interval", &self.heartbeat_interval)
             .field("heartbeat_timeout", &self.heartbeat_timeout)
             .field("max_in_flight_rpcs", &self.max_in_flight_rpcs)
+            .field("proxy", &self.proxy)
+            .field("tls", &self.tls)
             .finish()
     }
 }
@@ -369,12 +402,62 @@ impl RealtimeSession {
 
     /// Invokes a typed Inline RPC while the same transport continues routing
     /// pushed events to subscribers.
+    ///
+    /// A result of the wrong type is what an older server sends for a method
+    /// it numbers differently, so it is reported as
+    /// [`RealtimeError::UnsupportedMethod`] and remembered for this session.
     pub async fn call<R>(&self, request: R) -> Result<R::Response, RealtimeError>
     where
         R: RpcRequest,
     {
         let result = self.invoke(R::METHOD, request.into_rpc_input()).await?;
-        R::response_from_rpc_result(result)
+        R::response_from_rpc_result(result).map_err(|error| match error {
+            RealtimeError::UnexpectedResult { method, .. } => {
+                self.mark_unsupported(R::METHOD, false);
+                RealtimeError::UnsupportedMethod { method }
+            }
+            other => other,
+        })
+    }
+
+    /// Methods this session has found the server doesn't implement, including
+    /// the ones it was seeded with.
+    pub fn unsupported_methods(&self) -> Vec<proto::Method> {
+        self.unsupported_methods
+            .lock()
+            .map(|methods| methods.known.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Methods the server explicitly answered as not implemented (RPC status
+    /// 501) on this session. Unlike the other [`unsupported_methods`]
+    /// (Self::unsupported_methods), which are inferred from error messages or
+    /// mismatched results, these are safe to remember across sessions.
+    pub fn confirmed_unsupported_methods(&self) -> Vec<proto::Method> {
+        self.unsupported_methods
+            .lock()
+            .map(|methods| methods.confirmed.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    fn is_unsupported(&self, method: proto::Method) -> bool {
+        self.unsupported_methods
+            .lock()
+            .is_ok_and(|methods| methods.known.contains(&method))
+    }
+
+    fn mark_unsupported(&self, method: proto::Method, confirmed: bool) {
+        log::debug!(
+            target: "inline_sdk::realtime",
+            "server does not support method={} confirmed={confirmed}",
+            method.as_str_name()
+        );
+        if let Ok(mut methods) = self.unsupported_methods.lock() {
+            methods.known.insert(method);
+            if confirmed {
+                methods.confirmed.insert(method);
+            }
+        }
     }
 
     /// Invokes an Inline RPC while the same transport continues routing pushed
@@ -384,6 +467,11 @@ impl RealtimeSession {
         method: proto::Method,
         input: proto::rpc_call::Input,
     ) -> Result<proto::rpc_result::Result, RealtimeError> {
+        if self.is_unsupported(method) {
+            return Err(RealtimeError::UnsupportedMethod {
+                method: method.as_str_name(),
+            });
+        }
         if self.is_closed() {
             return Err(RealtimeError::ConnectionClosed);
         }
@@ -407,10 +495,22 @@ impl RealtimeSession {
                 .await
                 .map_err(|_| RealtimeError::ConnectionClosed)?
         };
-        with_optional_timeout("rpc", self.rpc_timeout, response).await
+        with_optional_timeout("rpc", self.rpc_timeout, response)
+            .await
+            .map_err(|error| {
+                let confirmed = is_not_implemented_error(&error);
+                if confirmed || is_unknown_method_error(&error) {
+                    self.mark_unsupported(method, confirmed);
+                    RealtimeError::UnsupportedMethod {
+                        method: method.as_str_name(),
+                    }
+                } else {
+                    error
+                }
+            })
     }
 
-    fn from_client(client: RealtimeClient) -> Self {
+    fn from_client(client: RealtimeClient, unsupported_methods: BTreeSet<proto::Method>) -> Self {
         let rpc_timeout = client.rpc_timeout;
         let heartbeat_interval = client.heartbeat_interval;
         let heartbeat_timeout = client.heartbeat_timeout;
@@ -432,6 +532,10 @@ impl RealtimeSession {
             heartbeat_interval,
             heartbeat_timeout,
             rpc_permits: Arc::new(Semaphore::new(max_in_flight_rpcs)),
+            unsupported_methods: Arc::new(Mutex::new(UnsupportedMethods {
+                known: unsupported_methods,
+                confirmed: BTreeSet::new(),
+            })),
         }
     }
 }
@@ -477,6 +581,9 @@ impl RealtimeClientBuilder {
             heartbeat_interval: Some(DEFAULT_HEARTBEAT_INTERVAL),
             heartbeat_timeout: DEFAULT_HEARTBEAT_TIMEOUT,
             max_in_flight_rpcs: DEFAULT_SESSION_MAX_IN_FLIGHT_RPCS,
+            proxy: None,
+            tls: None,
+            unsupported_methods: BTreeSet::new(),
         }
     }
 
@@ -529,6 +636,27 @@ impl RealtimeClientBuilder {
         self
     }
 
+    /// Opens the WebSocket through a proxy. Hosts listed in `NO_PROXY` still
+    /// connect directly.
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Trusts extra root CAs and checks certificate pins for `wss://` URLs.
+    pub fn tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Methods the server is already known not to implement, such as ones a
+    /// caller remembered from an earlier session. Session calls to them fail
+    /// with [`RealtimeError::UnsupportedMethod`] without a round trip.
+    pub fn unsupported_methods(mut self, methods: impl IntoIterator<Item = proto::Method>) -> Self {
+        self.unsupported_methods.extend(methods);
+        self
+    }
+
     /// Opens the WebSocket connection and waits for `ConnectionOpen`.
     pub async fn connect(self) -> Result<RealtimeClient, RealtimeError> {
         let url = normalize_realtime_url(self.url)?;
@@ -554,8 +682,39 @@ impl RealtimeClientBuilder {
             realtime_header_value("user_agent", &client_info::user_agent_for(&self.identity))?,
         );
 
-        let (ws, _) =
-            with_optional_timeout("connect", self.connect_timeout, connect_async(request)).await?;
+        let connector = self
+            .tls
+            .as_ref()
+            .map(|tls| Connector::Rustls(tls.rustls_client_config()));
+        let host = url.host_str().unwrap_or_default().to_string();
+        let (ws, _) = match self.proxy.as_ref().filter(|proxy| !proxy.bypasses(&host)) {
+            Some(proxy) => {
+                let port = url.port_or_known_default().unwrap_or(443);
+                log::debug!(
+                    target: "inline_sdk::realtime",
+                    "opening realtime websocket through proxy {}",
+                    proxy.redacted_url()
+                );
+                with_optional_timeout("connect", self.connect_timeout, async {
+                    let stream = proxy
+                        .connect(&host, port)
+                        .await
+                        .map_err(RealtimeError::Proxy)?;
+                    Ok::<_, RealtimeError>(
+                        client_async_tls_with_config(request, stream, None, connector).await?,
+                    )
+                })
+                .await?
+            }
+            None => {
+                with_optional_timeout(
+                    "connect",
+                    self.connect_timeout,
+                    connect_async_tls_with_config(request, None, false, connector),
+                )
+                .await?
+            }
+        };
         log::debug!(target: "inline_sdk::realtime", "websocket connected");
         let mut client = RealtimeClient {
             ws,
@@ -586,8 +745,11 @@ impl RealtimeClientBuilder {
 
     /// Opens one WebSocket and starts a multiplexed session for concurrent RPC
     /// calls and pushed events.
-    pub async fn connect_session(self) -> Result<RealtimeSession, RealtimeError> {
-        self.connect().await.map(RealtimeSession::from_client)
+    pub async fn connect_session(mut self) -> Result<RealtimeSession, RealtimeError> {
+        let unsupported_methods = std::mem::take(&mut self.unsupported_methods);
+        self.connect()
+            .await
+            .map(|client| RealtimeSession::from_client(client, unsupported_methods))
     }
 }
 
@@ -650,6 +812,13 @@ impl RealtimeClient {
         self.rpc_timeout
     }
 
+    /// Sends a WebSocket close frame and ends the connection.
+    pub async fn close(mut self) -> Result<(), RealtimeError> {
+        log::debug!(target: "inline_sdk::realtime", "closing realtime websocket");
+        self.ws.close(None).await?;
+        Ok(())
+    }
+
     /// Waits for the next server-pushed realtime event.
     ///
     /// This reads the same Inline realtime protocol stream used for RPC calls.
@@ -1560,6 +1729,39 @@ fn rpc_error_code_name(error_code: i32) -> String {
         .to_string()
 }
 
+/// Whether an RPC error is the server stating that it doesn't implement the
+/// method: status 501, as HTTP uses for the same thing.
+fn is_not_implemented_error(error: &RealtimeError) -> bool {
+    matches!(error, RealtimeError::RpcError { code: 501, .. })
+}
+
+/// Whether an RPC error looks like a server rejecting the method itself rather
+/// than the request. Servers predating a method answer `BAD_REQUEST` or
+/// `UNKNOWN` with a message such as "Unknown method". This is a guess from the
+/// message, so it only holds for the current session.
+fn is_unknown_method_error(error: &RealtimeError) -> bool {
+    let RealtimeError::RpcError {
+        error_name,
+        message,
+        ..
+    } = error
+    else {
+        return false;
+    };
+    let message = message.to_ascii_lowercase();
+    matches!(error_name.as_str(), "BAD_REQUEST" | "UNKNOWN")
+        && message.contains("method")
+        && [
+            "unknown",
+            "unsupported",
+            "not found",
+            "not implemented",
+            "invalid",
+        ]
+        .iter()
+        .any(|phrase| message.contains(phrase))
+}
+
 fn rpc_error_from_proto(error: proto::RpcError) -> RealtimeError {
     let error_name = rpc_error_code_name(error.error_code);
     let friendly = format_rpc_error(error.error_code, &error_name, &error.message, error.code);
@@ -1946,6 +2148,61 @@ mod tests {
         server.await.unwrap();
     }
 
+    #[tokio::test]
+    async fn realtime_client_connects_through_an_http_connect_proxy() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            read_test_client_message(&mut ws).await;
+            send_test_server_message(
+                &mut ws,
+                proto::ServerProtocolMessage {
+                    id: 1,
+                    body: Some(proto::server_protocol_message::Body::ConnectionOpen(
+                        proto::ConnectionOpen {},
+                    )),
+                },
+            )
+            .await;
+        });
+        // The proxy resolves `realtime.internal` itself; the client never does.
+        let proxy_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_addr = proxy_listener.local_addr().unwrap();
+        let proxy = tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+            let (mut client, _) = proxy_listener.accept().await.unwrap();
+            let mut head = Vec::new();
+            while !head.ends_with(b"\r\n\r\n") {
+                head.push(client.read_u8().await.unwrap());
+            }
+            let mut upstream = TcpStream::connect(addr).await.unwrap();
+            client
+                .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+                .await
+                .unwrap();
+            let _ = tokio::io::copy_bidirectional(&mut client, &mut upstream).await;
+            String::from_utf8(head).unwrap()
+        });
+
+        let client = RealtimeClient::builder("ws://realtime.internal:9000/realtime", "token-1")
+            .proxy(ProxyConfig::parse(&format!("http://{proxy_addr}")).unwrap())
+            .without_connect_timeout()
+            .connect()
+            .await
+            .unwrap();
+
+        drop(client);
+        server.await.unwrap();
+        let head = proxy.await.unwrap();
+        assert!(
+            head.starts_with("CONNECT realtime.internal:9000 HTTP/1.1\r\n"),
+            "{head}"
+        );
+    }
+
     #[tokio::test]
     async fn realtime_client_receives_update_events_and_acks_them() {
         let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
@@ -2252,6 +2509,138 @@ mod tests {
         server.await.unwrap();
     }
 
+    #[tokio::test]
+    async fn realtime_session_remembers_methods_the_server_lacks() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            let _ = read_test_client_message(&mut ws).await;
+            send_test_server_message(
+                &mut ws,
+                proto::ServerProtocolMessage {
+                    id: 1,
+                    body: Some(proto::server_protocol_message::Body::ConnectionOpen(
+                        proto::ConnectionOpen {},
+                    )),
+                },
+            )
+            .await;
+
+            let request = read_test_client_message(&mut ws).await;
+            send_test_server_message(
+                &mut ws,
+                proto::ServerProtocolMessage {
+                    id: 2,
+                    body: Some(proto::server_protocol_message::Body::RpcError(
+                        proto::RpcError {
+                            req_msg_id: request.id,
+                            error_code: proto::rpc_error::Code::BadRequest as i32,
+                            message: "Unknown method".to_string(),
+                            code: 400,
+                        },
+                    )),
+                },
+            )
+            .await;
+            // getMe answered with the wrong result type, as an older server
+            // that numbers methods differently would.
+            let request = read_test_client_message(&mut ws).await;
+            send_test_server_message(
+                &mut ws,
+                proto::ServerProtocolMessage {
+                    id: 3,
+                    body: Some(proto::server_protocol_message::Body::RpcResult(
+                        proto::RpcResult {
+                            req_msg_id: request.id,
+                            result: Some(proto::rpc_result::Result::GetChats(
+                                proto::GetChatsResult::default(),
+                            )),
+                        },
+                    )),
+                },
+            )
+            .await;
+            // An explicit "not implemented".
+            let request = read_test_client_message(&mut ws).await;
+            send_test_server_message(
+                &mut ws,
+                proto::ServerProtocolMessage {
+                    id: 4,
+                    body: Some(proto::server_protocol_message::Body::RpcError(
+                        proto::RpcError {
+                            req_msg_id: request.id,
+                            error_code: proto::rpc_error::Code::Unknown as i32,
+                            message: "Not implemented".to_string(),
+                            code: 501,
+                        },
+                    )),
+                },
+            )
+            .await;
+        });
+
+        let session = RealtimeClient::builder(format!("ws://{addr}/realtime"), "token-1")
+            .without_connect_timeout()
+            .without_rpc_timeout()
+            .unsupported_methods([proto::Method::GetChatHistory])
+            .connect_session()
+            .await
+            .unwrap();
+
+        // Seeded methods fail without reaching the server.
+        let seeded = session
+            .call(proto::GetChatHistoryInput::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            seeded,
+            RealtimeError::UnsupportedMethod {
+                method: "GET_CHAT_HISTORY"
+            }
+        ));
+        assert!(matches!(
+            session.call(proto::GetChatsInput {}).await,
+            Err(RealtimeError::UnsupportedMethod {
+                method: "GET_CHATS"
+            })
+        ));
+        assert!(matches!(
+            session.call(proto::GetMeInput {}).await,
+            Err(RealtimeError::UnsupportedMethod { method: "GET_ME" })
+        ));
+        assert!(matches!(
+            session.call(proto::GetUserSettingsInput {}).await,
+            Err(RealtimeError::UnsupportedMethod {
+                method: "GET_USER_SETTINGS"
+            })
+        ));
+        server.await.unwrap();
+
+        let mut known = session.unsupported_methods();
+        known.sort_by_key(|method| method.as_str_name());
+        assert_eq!(
+            known,
+            vec![
+                proto::Method::GetChats,
+                proto::Method::GetChatHistory,
+                proto::Method::GetMe,
+                proto::Method::GetUserSettings
+            ]
+        );
+        // Only the 501 is certain enough to outlive the session.
+        assert_eq!(
+            session.confirmed_unsupported_methods(),
+            vec![proto::Method::GetUserSettings]
+        );
+        // Already known: no second round trip to a server that is gone.
+        assert!(matches!(
+            session.call(proto::GetMeInput {}).await,
+            Err(RealtimeError::UnsupportedMethod { method: "GET_ME" })
+        ));
+    }
+
     #[tokio::test]
     async fn realtime_session_drops_timed_out_rpc_and_remains_usable() {
         let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
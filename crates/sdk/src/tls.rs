@@ -0,0 +1,509 @@
+//! TLS trust settings for self-hosted servers.
+//!
+//! [`TlsConfig`] adds private root CAs to the platform's trusted roots and the
+//! bundled web PKI roots, and can pin certificates by SHA-256 fingerprint. The
+//! same settings apply to API requests and the realtime WebSocket. Pins are
+//! checked after normal chain and hostname validation, and only match
+//! certificates in the validated chain: extra certificates the server sends
+//! alongside it don't count.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use rustls::client::WebPkiServerVerifier;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName, TrustAnchor, UnixTime};
+use rustls::{CertificateError, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use sha2::{Digest, Sha256};
+
+/// Error returned when TLS settings can't be loaded.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum TlsError {
+    /// A CA bundle file could not be read.
+    #[error("failed to read CA bundle: {0}")]
+    ReadCaBundle(#[source] std::io::Error),
+    /// A CA bundle has no usable PEM certificates.
+    #[error("invalid CA bundle: {message}")]
+    InvalidCaBundle {
+        /// Human-readable validation failure.
+        message: String,
+    },
+    /// A certificate pin is not a SHA-256 fingerprint.
+    #[error("invalid certificate pin `{pin}`: expected 64 hex digits (colons allowed)")]
+    InvalidPin {
+        /// Pin value supplied by the caller.
+        pin: String,
+    },
+    /// The TLS client configuration could not be assembled.
+    #[error("tls configuration error: {message}")]
+    Config {
+        /// Human-readable failure.
+        message: String,
+    },
+}
+
+enum CaSource {
+    File(PathBuf),
+    Pem(Vec<u8>),
+}
+
+/// Builder for [`TlsConfig`].
+#[derive(Default)]
+pub struct TlsConfigBuilder {
+    ca_bundles: Vec<CaSource>,
+    pins: Vec<String>,
+}
+
+impl fmt::Debug for TlsConfigBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsConfigBuilder")
+            .field("ca_bundles", &self.ca_bundles.len())
+            .field("pins", &self.pins)
+            .finish()
+    }
+}
+
+impl TlsConfigBuilder {
+    /// Trusts every certificate in a PEM bundle file, in addition to the
+    /// platform and bundled roots.
+    pub fn ca_bundle_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ca_bundles.push(CaSource::File(path.into()));
+        self
+    }
+
+    /// Trusts every certificate in PEM-encoded bytes.
+    pub fn ca_bundle_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.ca_bundles.push(CaSource::Pem(pem.into()));
+        self
+    }
+
+    /// Requires the validated chain (leaf, intermediate, or root) to contain a
+    /// certificate with this SHA-256 fingerprint. Accepts hex with optional colons and an
+    /// optional `sha256:` prefix, as printed by
+    /// `openssl x509 -noout -fingerprint -sha256`. Repeat to allow several
+    /// certificates during rotation.
+    pub fn pin_sha256(mut self, fingerprint: impl Into<String>) -> Self {
+        self.pins.push(fingerprint.into());
+        self
+    }
+
+    /// Loads the CA bundles and builds the TLS configuration.
+    pub fn build(self) -> Result<TlsConfig, TlsError> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let loaded = self.server_verifier(provider.clone())?;
+        let client_config = rustls::ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(config_error)?
+            .dangerous()
+            .with_custom_certificate_verifier(loaded.verifier)
+            .with_no_client_auth();
+        Ok(TlsConfig {
+            client_config: Arc::new(client_config),
+            ca_certificates: loaded.ca_certificates,
+            pins: loaded.pins,
+        })
+    }
+
+    fn server_verifier(&self, provider: Arc<CryptoProvider>) -> Result<LoadedVerifier, TlsError> {
+        let pins = self
+            .pins
+            .iter()
+            .map(|pin| parse_pin(pin).ok_or_else(|| TlsError::InvalidPin { pin: pin.clone() }))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        // Without a TLS config the WebSocket trusts the platform roots, so a
+        // config only ever adds to them.
+        let native = rustls_native_certs::load_native_certs();
+        for error in &native.errors {
+            log::debug!("could not load platform root certificates: {error}");
+        }
+        let mut pinned_roots = Vec::new();
+        let mut add_root = |certificate: &CertificateDer<'_>| {
+            let anchor = webpki::anchor_from_trusted_cert(certificate)?.to_owned();
+            if pins.contains(&Sha256::digest(certificate).into()) {
+                pinned_roots.push(anchor.clone());
+            }
+            roots.roots.push(anchor);
+            Ok::<_, webpki::Error>(())
+        };
+        for certificate in &native.certs {
+            // Platform stores can hold certificates webpki can't parse.
+            let _ = add_root(certificate);
+        }
+        let mut ca_certificates = 0;
+        for source in &self.ca_bundles {
+            let file;
+            let pem = match source {
+                CaSource::File(path) => {
+                    file = std::fs::read(path).map_err(TlsError::ReadCaBundle)?;
+                    &file
+                }
+                CaSource::Pem(pem) => pem,
+            };
+            let certificates = CertificateDer::pem_slice_iter(pem)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|error| TlsError::InvalidCaBundle {
+                    message: error.to_string(),
+                })?;
+            if certificates.is_empty() {
+                return Err(TlsError::InvalidCaBundle {
+                    message: "no PEM certificates found".to_string(),
+                });
+            }
+            for certificate in certificates {
+                add_root(&certificate).map_err(|error| TlsError::InvalidCaBundle {
+                    message: error.to_string(),
+                })?;
+                ca_certificates += 1;
+            }
+        }
+
+        let roots = Arc::new(roots);
+        let webpki = WebPkiServerVerifier::builder_with_provider(roots.clone(), provider.clone())
+            .build()
+            .map_err(config_error)?;
+        let verifier: Arc<dyn ServerCertVerifier> = if pins.is_empty() {
+            webpki
+        } else {
+            Arc::new(PinnedServerVerifier {
+                inner: webpki,
+                roots,
+                pinned_roots,
+                provider,
+                pins: pins.clone(),
+            })
+        };
+        Ok(LoadedVerifier {
+            verifier,
+            ca_certificates,
+            pins,
+        })
+    }
+}
+
+struct LoadedVerifier {
+    verifier: Arc<dyn ServerCertVerifier>,
+    ca_certificates: usize,
+    pins: Vec<[u8; 32]>,
+}
+
+/// Trust settings shared by API requests and the realtime WebSocket.
+#[derive(Clone)]
+pub struct TlsConfig {
+    client_config: Arc<rustls::ClientConfig>,
+    ca_certificates: usize,
+    pins: Vec<[u8; 32]>,
+}
+
+impl fmt::Debug for TlsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsConfig")
+            .field("ca_certificates", &self.ca_certificates)
+            .field("pins", &self.pinned_sha256())
+            .finish()
+    }
+}
+
+impl TlsConfig {
+    /// Starts a TLS configuration builder.
+    pub fn builder() -> TlsConfigBuilder {
+        TlsConfigBuilder::default()
+    }
+
+    /// Number of extra root certificates loaded from CA bundles.
+    pub fn ca_certificate_count(&self) -> usize {
+        self.ca_certificates
+    }
+
+    /// Pinned fingerprints as colon-separated uppercase hex.
+    pub fn pinned_sha256(&self) -> Vec<String> {
+        self.pins.iter().map(format_fingerprint).collect()
+    }
+
+    /// The `rustls` client configuration, for callers that build their own
+    /// `reqwest` client with `use_preconfigured_tls`.
+    pub fn rustls_client_config(&self) -> Arc<rustls::ClientConfig> {
+        self.client_config.clone()
+    }
+}
+
+#[derive(Debug)]
+struct PinnedServerVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    roots: Arc<RootCertStore>,
+    /// Trusted roots whose certificate is pinned.
+    pinned_roots: Vec<TrustAnchor<'static>>,
+    provider: Arc<CryptoProvider>,
+    pins: Vec<[u8; 32]>,
+}
+
+impl PinnedServerVerifier {
+    fn is_pinned(&self, certificate: &[u8]) -> bool {
+        self.pins.contains(&Sha256::digest(certificate).into())
+    }
+
+    /// Whether some chain from `end_entity` to a trusted root contains a
+    /// pinned certificate. The presented intermediates are only candidates
+    /// for path building; one that isn't part of a valid chain never matches.
+    fn has_pinned_path(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        now: UnixTime,
+    ) -> Result<bool, rustls::Error> {
+        if self.is_pinned(end_entity) {
+            return Ok(true);
+        }
+        let certificate = webpki::EndEntityCert::try_from(end_entity)
+            .map_err(|_| rustls::Error::InvalidCertificate(CertificateError::BadEncoding))?;
+        let pinned_path = |path: &webpki::VerifiedPath<'_>| {
+            let pinned = path
+                .intermediate_certificates()
+                .any(|certificate| self.is_pinned(certificate.der().as_ref()))
+                || self.pinned_roots.contains(path.anchor());
+            // Any error makes webpki try the next candidate chain.
+            if pinned {
+                Ok(())
+            } else {
+                Err(webpki::Error::UnknownIssuer)
+            }
+        };
+        Ok(certificate
+            .verify_for_usage(
+                self.provider.signature_verification_algorithms.all,
+                &self.roots.roots,
+                intermediates,
+                now,
+                webpki::KeyUsage::server_auth(),
+                None,
+                Some(&pinned_path),
+            )
+            .is_ok())
+    }
+}
+
+impl ServerCertVerifier for PinnedServerVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+        if self.has_pinned_path(end_entity, intermediates, now)? {
+            Ok(verified)
+        } else {
+            let digest: [u8; 32] = Sha256::digest(end_entity.as_ref()).into();
+            Err(rustls::Error::General(format!(
+                "server certificate {} does not match any pinned certificate",
+                format_fingerprint(&digest)
+            )))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+fn parse_pin(value: &str) -> Option<[u8; 32]> {
+    let value = value.trim();
+    let value = value
+        .get(..7)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("sha256:"))
+        .map_or(value, |_| &value[7..]);
+    let hex = value.replace(':', "");
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut pin = [0u8; 32];
+    for (index, byte) in pin.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).ok()?;
+    }
+    Some(pin)
+}
+
+fn format_fingerprint(digest: &[u8; 32]) -> String {
+    digest
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn config_error(error: impl fmt::Display) -> TlsError {
+    TlsError::Config {
+        message: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_CA: &str = "-----BEGIN CERTIFICATE-----
+MIIBmDCCAT+gAwIBAgIUFEe5atYi9D8+u16Lz3Lm+o06H90wCgYIKoZIzj0EAwIw
+GTEXMBUGA1UEAwwOSW5saW5lIFRlc3QgQ0EwIBcNMjYxMDE1MDk1OTE2WhgPMjEy
+NjA5MjEwOTU5MTZaMBkxFzAVBgNVBAMMDklubGluZSBUZXN0IENBMFkwEwYHKoZI
+zj0CAQYIKoZIzj0DAQcDQgAE+lOjJc0TKhOgeCZKC+sCRgCbFpBkDu8colZoBhfU
+Se7lE+smEqHfLZzbDjwUvSp5/LtBx4Gn9W0Fh73Q+4Vj3KNjMGEwHQYDVR0OBBYE
+FHqQFq+OkFlc3wYz/nE1Ys98bcwWMB8GA1UdIwQYMBaAFHqQFq+OkFlc3wYz/nE1
+Ys98bcwWMA8GA1UdEwEB/wQFMAMBAf8wDgYDVR0PAQH/BAQDAgEGMAoGCCqGSM49
+BAMCA0cAMEQCICBtxjAfyYwLe6exstxy2wLkRuH8OdJiJ3rAu4nDscc4AiA0ADI7
+TrRmipZ8+M5ze6G9qFZ9anz5moGRJgPhAaFU5A==
+-----END CERTIFICATE-----
+";
+
+    /// `localhost` leaf issued by `TEST_CA`, valid until 2126.
+    const TEST_LEAF: &str = "-----BEGIN CERTIFICATE-----
+MIIBuDCCAV6gAwIBAgIUZrpOl34SZLin2Q6yyxcvMFIz9xEwCgYIKoZIzj0EAwIw
+GTEXMBUGA1UEAwwOSW5saW5lIFRlc3QgQ0EwIBcNMjYxMDE1MDk1OTE2WhgPMjEy
+NjA5MjEwOTU5MTZaMBQxEjAQBgNVBAMMCWxvY2FsaG9zdDBZMBMGByqGSM49AgEG
+CCqGSM49AwEHA0IABE+BwibO30juICMMpvoPGLO6xyFu+N0einQgbFSyHC1MjGR3
+royYF20f52fz+pzYLuFm5PArS7qQR6oI+vgGMfujgYYwgYMwFAYDVR0RBA0wC4IJ
+bG9jYWxob3N0MAkGA1UdEwQCMAAwEwYDVR0lBAwwCgYIKwYBBQUHAwEwCwYDVR0P
+BAQDAgeAMB0GA1UdDgQWBBQIn48WfORaTLc8EwR1UHq7Nhs3hjAfBgNVHSMEGDAW
+gBR6kBavjpBZXN8GM/5xNWLPfG3MFjAKBggqhkjOPQQDAgNIADBFAiEAg99ZyqVk
+VP7JAM9YEPrlotC/gDij2uNBuXKU284xI8ECIHwVNwpLSRkhLj7Eggq6FFh6iW+e
+A6IO+BlLMR2i5yqF
+-----END CERTIFICATE-----
+";
+
+    /// Intermediate CA issued by `TEST_CA`; it did not issue `TEST_LEAF`.
+    const TEST_INTERMEDIATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBpjCCAUygAwIBAgIUZrpOl34SZLin2Q6yyxcvMFIz9xIwCgYIKoZIzj0EAwIw
+GTEXMBUGA1UEAwwOSW5saW5lIFRlc3QgQ0EwIBcNMjYxMDE1MTQzNjAxWhgPMjEy
+NjA5MjExNDM2MDFaMCMxITAfBgNVBAMMGElubGluZSBUZXN0IEludGVybWVkaWF0
+ZTBZMBMGByqGSM49AgEGCCqGSM49AwEHA0IABE3znI2LtVy51hJPd5gjB5tGxrsj
+Wjb1emDA6WVI+i9ooE+35fM0rqSI0N7Mtgix3YYguQUliVMP5UWg1nRgOROjZjBk
+MBIGA1UdEwEB/wQIMAYBAf8CAQAwDgYDVR0PAQH/BAQDAgEGMB0GA1UdDgQWBBQk
+gjDkShi6nEgGsUo66wrnocZSfzAfBgNVHSMEGDAWgBR6kBavjpBZXN8GM/5xNWLP
+fG3MFjAKBggqhkjOPQQDAgNIADBFAiEAiWYxIRglWhM6bfCpfNiPhnO8gMy3Biqb
+nL8V/ywDZcQCIHqz3HORAvoaZwFolOflKQnllnuSEjooPBQPj52euN/3
+-----END CERTIFICATE-----
+";
+
+    const TEST_CA_SHA256: &str = "74:89:89:37:90:C3:8A:36:25:23:E3:71:01:94:D0:D4:02:FC:05:EE:0A:D7:F7:40:D2:C0:FC:6D:19:01:16:AB";
+
+    const TEST_INTERMEDIATE_SHA256: &str = "D8:4C:78:EA:85:96:F8:F9:03:9B:2A:A9:30:17:E1:28:ED:7C:59:3E:2B:1D:99:DE:F4:AE:73:6A:20:6F:A5:5E";
+
+    const TEST_LEAF_SHA256: &str = "D1:49:09:07:45:CA:BB:B3:E1:2D:55:F9:5A:8A:51:CA:54:F9:9E:BE:48:B3:EE:61:D3:5B:9D:13:AF:DE:C3:76";
+
+    fn verify(builder: &TlsConfigBuilder) -> Result<ServerCertVerified, rustls::Error> {
+        verify_with(builder, &[])
+    }
+
+    fn verify_with(
+        builder: &TlsConfigBuilder,
+        intermediates: &[&str],
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let loaded = builder.server_verifier(provider).unwrap();
+        let leaf = CertificateDer::from_pem_slice(TEST_LEAF.as_bytes()).unwrap();
+        let intermediates = intermediates
+            .iter()
+            .map(|pem| CertificateDer::from_pem_slice(pem.as_bytes()).unwrap())
+            .collect::<Vec<_>>();
+        loaded.verifier.verify_server_cert(
+            &leaf,
+            &intermediates,
+            &ServerName::try_from("localhost").unwrap(),
+            &[],
+            UnixTime::now(),
+        )
+    }
+
+    #[test]
+    fn custom_ca_bundle_trusts_private_certificates() {
+        assert!(verify(&TlsConfig::builder()).is_err());
+
+        let builder = TlsConfig::builder().ca_bundle_pem(TEST_CA);
+
+        assert!(verify(&builder).is_ok());
+        assert_eq!(builder.build().unwrap().ca_certificate_count(), 1);
+    }
+
+    #[test]
+    fn pins_must_match_a_presented_certificate() {
+        let pinned = TlsConfig::builder()
+            .ca_bundle_pem(TEST_CA)
+            .pin_sha256(format!("sha256:{}", TEST_LEAF_SHA256.to_lowercase()));
+        assert!(verify(&pinned).is_ok());
+        assert_eq!(
+            pinned.build().unwrap().pinned_sha256(),
+            vec![TEST_LEAF_SHA256.to_string()]
+        );
+
+        let mismatched = TlsConfig::builder()
+            .ca_bundle_pem(TEST_CA)
+            .pin_sha256("00".repeat(32));
+        let error = verify(&mismatched).unwrap_err().to_string();
+        assert!(error.contains(TEST_LEAF_SHA256), "{error}");
+    }
+
+    #[test]
+    fn pins_only_match_the_validated_chain() {
+        // A valid leaf that isn't pinned, sent with the pinned intermediate
+        // appended: the intermediate isn't on the leaf's chain.
+        let pinned_intermediate = TlsConfig::builder()
+            .ca_bundle_pem(TEST_CA)
+            .pin_sha256(TEST_INTERMEDIATE_SHA256);
+        assert!(verify_with(&pinned_intermediate, &[TEST_INTERMEDIATE]).is_err());
+
+        // A pinned root matches only when the leaf's chain ends at it.
+        let pinned_root = TlsConfig::builder()
+            .ca_bundle_pem(TEST_CA)
+            .pin_sha256(TEST_CA_SHA256);
+        assert!(verify(&pinned_root).is_ok());
+        let elsewhere = TlsConfig::builder()
+            .ca_bundle_pem(TEST_INTERMEDIATE)
+            .ca_bundle_pem(TEST_CA)
+            .pin_sha256(TEST_INTERMEDIATE_SHA256);
+        assert!(verify_with(&elsewhere, &[TEST_INTERMEDIATE]).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_pins_and_empty_bundles() {
+        assert!(matches!(
+            TlsConfig::builder().pin_sha256("abc").build(),
+            Err(TlsError::InvalidPin { .. })
+        ));
+        assert!(matches!(
+            TlsConfig::builder().ca_bundle_pem("not pem").build(),
+            Err(TlsError::InvalidCaBundle { .. })
+        ));
+    }
+}